@@ -0,0 +1,49 @@
+// Experiment Assignments
+// Markets routed to an experiment arm, recorded for A/B analysis
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+use crate::MarketId;
+
+/// A market routed to one arm of an experiment
+#[derive(Debug, Clone, Serialize)]
+pub struct ExperimentAssignment {
+    /// The experiment, which is also the A/B test id
+    pub experiment_id: Uuid,
+    pub market_id: MarketId,
+    /// Strategy version id of the arm, as in `AbTest::strategy_a`/`strategy_b`
+    pub strategy: String,
+    pub category: String,
+    pub assigned_at: DateTime<Utc>,
+}
+
+/// Sink for experiment assignments
+pub trait AssignmentSink: Send + Sync {
+    fn record(&self, assignment: ExperimentAssignment);
+}
+
+/// In-memory assignment log (for testing and single-process setups)
+#[derive(Debug, Default)]
+pub struct InMemoryAssignmentSink {
+    assignments: Mutex<Vec<ExperimentAssignment>>,
+}
+
+impl InMemoryAssignmentSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Recorded assignments, oldest first
+    pub fn assignments(&self) -> Vec<ExperimentAssignment> {
+        self.assignments.lock().unwrap().clone()
+    }
+}
+
+impl AssignmentSink for InMemoryAssignmentSink {
+    fn record(&self, assignment: ExperimentAssignment) {
+        self.assignments.lock().unwrap().push(assignment);
+    }
+}
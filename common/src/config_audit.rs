@@ -0,0 +1,50 @@
+// Config Audit
+// Record of configuration changes applied at runtime
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// A configuration change that was applied
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigChange {
+    /// Id of the preview the change was approved from
+    pub id: Uuid,
+    /// What was changed, e.g. "correlation_rules"
+    pub component: String,
+    /// Where the new configuration came from, e.g. its file path
+    pub source: String,
+    pub summary: String,
+    /// Who approved the change; None if it was applied automatically
+    pub approved_by: Option<String>,
+    pub applied_at: DateTime<Utc>,
+}
+
+/// Sink for applied configuration changes
+pub trait ConfigAudit: Send + Sync {
+    fn record(&self, change: ConfigChange);
+}
+
+/// In-memory audit log (for testing and single-process setups)
+#[derive(Debug, Default)]
+pub struct InMemoryConfigAudit {
+    changes: Mutex<Vec<ConfigChange>>,
+}
+
+impl InMemoryConfigAudit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Recorded changes, oldest first
+    pub fn changes(&self) -> Vec<ConfigChange> {
+        self.changes.lock().unwrap().clone()
+    }
+}
+
+impl ConfigAudit for InMemoryConfigAudit {
+    fn record(&self, change: ConfigChange) {
+        self.changes.lock().unwrap().push(change);
+    }
+}
@@ -0,0 +1,163 @@
+//! Transaction costs
+//!
+//! Taker fees plus the price impact of walking the book. Signal generation
+//! nets these out of edge and EV; paper trading fills through the same model
+//! so simulated fills are no better than a real order's would be.
+
+use crate::OrderSide;
+use serde::{Deserialize, Serialize};
+
+/// Result of filling an order against the book
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BookFill {
+    pub size: f64,
+    /// Size-weighted average fill price, before fees
+    pub avg_price: f64,
+    /// Taker fee on the fill's notional
+    pub fee: f64,
+    /// Book levels the fill touched; 0 when it used the fallback slippage
+    pub levels: usize,
+}
+
+impl BookFill {
+    /// Price per share with the fee folded in: higher for a buy, lower for a sell
+    pub fn effective_price(&self, side: OrderSide) -> f64 {
+        if self.size <= 0.0 {
+            return self.avg_price;
+        }
+        match side {
+            OrderSide::Buy => self.avg_price + self.fee / self.size,
+            OrderSide::Sell => self.avg_price - self.fee / self.size,
+        }
+    }
+}
+
+/// Taker fees and slippage
+///
+/// With a book, an order walks the levels on the side it takes, best first.
+/// Any size beyond the book, or the whole order when there is no book, fills
+/// `fallback_slippage_bps` worse than the last price available.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CostModel {
+    /// Taker fee per fill, in bps of notional
+    #[serde(default)]
+    pub taker_fee_bps: f64,
+    /// Slippage assumed past the visible book, in bps of price
+    #[serde(default)]
+    pub fallback_slippage_bps: f64,
+}
+
+impl CostModel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_taker_fee_bps(mut self, bps: f64) -> Self {
+        self.taker_fee_bps = bps;
+        self
+    }
+
+    pub fn with_fallback_slippage_bps(mut self, bps: f64) -> Self {
+        self.fallback_slippage_bps = bps;
+        self
+    }
+
+    /// Fill `size` against `levels` of (price, size): the asks for a buy, the
+    /// bids for a sell. `quote` prices the order when `levels` is empty.
+    pub fn fill(&self, side: OrderSide, levels: &[(f64, f64)], quote: f64, size: f64) -> BookFill {
+        let mut levels: Vec<(f64, f64)> = levels.iter().copied().filter(|&(_, s)| s > 0.0).collect();
+        match side {
+            OrderSide::Buy => levels.sort_by(|a, b| a.0.total_cmp(&b.0)),
+            OrderSide::Sell => levels.sort_by(|a, b| b.0.total_cmp(&a.0)),
+        }
+
+        let mut remaining = size;
+        let mut notional = 0.0;
+        let mut touched = 0;
+        let mut last_price = quote;
+        for &(price, available) in &levels {
+            if remaining <= 0.0 {
+                break;
+            }
+            let take = remaining.min(available);
+            notional += take * price;
+            remaining -= take;
+            touched += 1;
+            last_price = price;
+        }
+
+        if remaining > 0.0 {
+            let slippage = last_price * self.fallback_slippage_bps / 10_000.0;
+            let price = match side {
+                OrderSide::Buy => last_price + slippage,
+                OrderSide::Sell => last_price - slippage,
+            };
+            notional += remaining * price;
+        }
+
+        let avg_price = if size > 0.0 {
+            notional / size
+        } else {
+            levels.first().map_or(quote, |&(price, _)| price)
+        };
+        BookFill {
+            size,
+            avg_price,
+            fee: notional * self.taker_fee_bps / 10_000.0,
+            levels: touched,
+        }
+    }
+
+    /// Cost per share of buying `size` and selling it back, or the reverse:
+    /// the gap between the two average fills plus both fees
+    pub fn round_trip_cost(&self, asks: &[(f64, f64)], bids: &[(f64, f64)], quote: f64, size: f64) -> f64 {
+        let buy = self.fill(OrderSide::Buy, asks, quote, size);
+        let sell = self.fill(OrderSide::Sell, bids, quote, size);
+        buy.effective_price(OrderSide::Buy) - sell.effective_price(OrderSide::Sell)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASKS: [(f64, f64); 3] = [(0.53, 1000.0), (0.50, 400.0), (0.51, 400.0)];
+    const BIDS: [(f64, f64); 2] = [(0.49, 400.0), (0.47, 2000.0)];
+
+    #[test]
+    fn test_fill_walks_thin_book() {
+        let model = CostModel::new().with_taker_fee_bps(20.0);
+
+        // 400 @ 0.50, 400 @ 0.51, 200 @ 0.53: two levels through the best ask
+        let fill = model.fill(OrderSide::Buy, &ASKS, 0.50, 1000.0);
+        assert_eq!(fill.levels, 3);
+        assert!((fill.avg_price - 0.51).abs() < 1e-9);
+        assert!((fill.fee - 1.02).abs() < 1e-9);
+        assert!((fill.effective_price(OrderSide::Buy) - 0.51102).abs() < 1e-9);
+
+        // Top of book only
+        let small = model.fill(OrderSide::Buy, &ASKS, 0.50, 100.0);
+        assert_eq!(small.levels, 1);
+        assert!((small.avg_price - 0.50).abs() < 1e-9);
+
+        // 400 @ 0.49, 600 @ 0.47 = 0.478, less 20bps
+        let round_trip = model.round_trip_cost(&ASKS, &BIDS, 0.50, 1000.0);
+        assert!((round_trip - (0.51102 - 0.478 * 0.998)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fill_falls_back_past_the_book() {
+        let model = CostModel::new().with_fallback_slippage_bps(100.0);
+
+        // No book: the quote moved 1%
+        let fill = model.fill(OrderSide::Sell, &[], 0.60, 50.0);
+        assert_eq!(fill.levels, 0);
+        assert!((fill.avg_price - 0.594).abs() < 1e-9);
+
+        // 400 @ 0.49, the other 100 at 0.49 less 1%
+        let fill = model.fill(OrderSide::Sell, &BIDS[..1], 0.50, 500.0);
+        assert_eq!(fill.levels, 1);
+        assert!((fill.avg_price - (0.49 * 400.0 + 0.4851 * 100.0) / 500.0).abs() < 1e-9);
+        assert_eq!(fill.fee, 0.0);
+    }
+}
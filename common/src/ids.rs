@@ -0,0 +1,236 @@
+//! Typed identifiers
+//!
+//! Market, outcome, asset and condition ids are all UUIDs or bare strings
+//! on the wire. Wrapping them keeps one from being passed where another is
+//! expected; each serializes and binds to Postgres exactly like the value
+//! it wraps, so persisted data and queries are unaffected.
+//!
+//! `tests/ui` holds the mix-ups that no longer compile.
+//!
+//! Callers still holding a bare `Uuid` or `String` convert with `From`.
+//! These shims stay through the 0.1 releases. In 0.2, `From<Uuid>` for
+//! `MarketId` becomes deprecated, because a fresh UUID standing in for a
+//! market is the bug these types exist to stop; use `MarketId::for_condition`
+//! or parse a stored id instead. The string conversions stay.
+
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Internal id of a market
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type)]
+#[serde(transparent)]
+#[sqlx(transparent)]
+pub struct MarketId(Uuid);
+
+impl MarketId {
+    /// A new random id, for markets that don't have one yet
+    pub fn random() -> Self {
+        Self(Uuid::new_v4())
+    }
+
+    pub fn as_uuid(&self) -> &Uuid {
+        &self.0
+    }
+
+    pub fn into_uuid(self) -> Uuid {
+        self.0
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        self.0.as_bytes()
+    }
+
+    /// The same id for a condition every time, e.g. to key its events by;
+    /// not the id of the market created for it
+    pub fn for_condition(condition_id: &ConditionId) -> Self {
+        Self(Uuid::new_v5(&Uuid::NAMESPACE_OID, condition_id.as_str().as_bytes()))
+    }
+}
+
+impl From<Uuid> for MarketId {
+    fn from(id: Uuid) -> Self {
+        Self(id)
+    }
+}
+
+impl From<MarketId> for Uuid {
+    fn from(id: MarketId) -> Self {
+        id.0
+    }
+}
+
+impl PartialEq<Uuid> for MarketId {
+    fn eq(&self, other: &Uuid) -> bool {
+        self.0 == *other
+    }
+}
+
+impl fmt::Display for MarketId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for MarketId {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(s).map(Self)
+    }
+}
+
+macro_rules! string_id {
+    ($(#[$doc:meta])* $name:ident) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type)]
+        #[serde(transparent)]
+        #[sqlx(transparent)]
+        pub struct $name(String);
+
+        impl $name {
+            pub fn new(id: impl Into<String>) -> Self {
+                Self(id.into())
+            }
+
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            pub fn into_string(self) -> String {
+                self.0
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(id: String) -> Self {
+                Self(id)
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(id: &str) -> Self {
+                Self(id.to_string())
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl Borrow<str> for $name {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+    };
+}
+
+string_id!(
+    /// Outcome of a market, e.g. "YES" or a Polymarket token id
+    OutcomeId
+);
+
+string_id!(
+    /// Polymarket CLOB asset (token) id, as used by the market data feed
+    AssetId
+);
+
+string_id!(
+    /// Polymarket condition id of a market
+    ConditionId
+);
+
+impl AssetId {
+    /// The outcome this asset trades.
+    ///
+    /// On Polymarket every outcome is its own token, so the asset id doubles
+    /// as the outcome id; the conversion is explicit to keep it deliberate.
+    pub fn to_outcome_id(&self) -> OutcomeId {
+        OutcomeId(self.0.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MarketEvent, Trade};
+
+    #[test]
+    fn test_ids_serialize_as_their_inner_value() {
+        let uuid = Uuid::parse_str("6f1c1d5e-8f0b-4c1e-9d2a-3b4c5d6e7f80").unwrap();
+        let market_id = MarketId::from(uuid);
+
+        assert_eq!(serde_json::to_string(&market_id).unwrap(), "\"6f1c1d5e-8f0b-4c1e-9d2a-3b4c5d6e7f80\"");
+        assert_eq!(serde_json::to_string(&OutcomeId::from("YES")).unwrap(), "\"YES\"");
+        assert_eq!(market_id.to_string(), uuid.to_string());
+        assert_eq!("6f1c1d5e-8f0b-4c1e-9d2a-3b4c5d6e7f80".parse::<MarketId>().unwrap(), market_id);
+        assert_eq!(Uuid::from(market_id), uuid);
+        assert_eq!(market_id, uuid);
+    }
+
+    #[test]
+    fn test_previously_persisted_events_still_deserialize() {
+        // Written before market and outcome ids were typed
+        let json = r#"{
+            "type": "Trade",
+            "id": "0f8fad5b-d9cb-469f-a165-70867728950e",
+            "market_id": "7c9e6679-7425-40de-944b-e07fc1f90ae7",
+            "outcome_id": "YES",
+            "price": 0.42,
+            "size": 100.0,
+            "side": "Buy",
+            "timestamp": "2024-11-05T12:00:00Z"
+        }"#;
+
+        let event: MarketEvent = serde_json::from_str(json).unwrap();
+        let MarketEvent::Trade(Trade { market_id, outcome_id, .. }) = &event else {
+            panic!("Expected a trade, got {:?}", event);
+        };
+        assert_eq!(market_id.to_string(), "7c9e6679-7425-40de-944b-e07fc1f90ae7");
+        assert_eq!(outcome_id, "YES");
+
+        // And serialize back to the same shape
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["market_id"], "7c9e6679-7425-40de-944b-e07fc1f90ae7");
+        assert_eq!(value["outcome_id"], "YES");
+    }
+
+    #[test]
+    fn test_string_ids_borrow_as_str() {
+        let mut prices = std::collections::HashMap::new();
+        prices.insert(OutcomeId::from("YES"), 0.6);
+        assert_eq!(prices.get("YES"), Some(&0.6));
+
+        let asset = AssetId::from("2174263314346390629056905015582624");
+        assert_eq!(asset.to_outcome_id(), "2174263314346390629056905015582624");
+    }
+}
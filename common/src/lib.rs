@@ -3,22 +3,44 @@ use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, Type};
 pub use uuid::Uuid;
 
+mod assignments;
+mod config_audit;
+mod costs;
+mod ids;
+mod rewards;
+mod stats;
+
+pub use assignments::{AssignmentSink, ExperimentAssignment, InMemoryAssignmentSink};
+pub use config_audit::{ConfigAudit, ConfigChange, InMemoryConfigAudit};
+pub use costs::{BookFill, CostModel};
+pub use ids::{AssetId, ConditionId, MarketId, OutcomeId};
+pub use rewards::{RewardCampaign, RewardsModel};
+pub use stats::{P2Quantile, P2TailMean, RunningMoments};
+
 /// Core market data types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Market {
-    pub id: Uuid,
-    pub condition_id: String,
+    pub id: MarketId,
+    pub condition_id: ConditionId,
     pub question: String,
     pub description: String,
     pub category: String,
     pub outcomes: Vec<Outcome>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Polymarket negRisk market id shared by every member of a linked
+    /// group, of which exactly one resolves YES
+    #[serde(default)]
+    pub neg_risk_group: Option<String>,
+    /// Smallest price increment the exchange accepts; `None` when the feed
+    /// has not said, in which case consumers assume 0.01
+    #[serde(default)]
+    pub tick_size: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Outcome {
-    pub id: String,
+    pub id: OutcomeId,
     pub name: String,
     pub price: f64,
     pub liquidity: f64,
@@ -27,15 +49,19 @@ pub struct Outcome {
 /// Order book data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderBook {
-    pub market_id: Uuid,
+    pub market_id: MarketId,
     pub timestamp: DateTime<Utc>,
     pub bids: Vec<Order>,
     pub asks: Vec<Order>,
+    /// Increases with every change the publisher makes to the book; 0 when
+    /// the source does not sequence its books
+    #[serde(default)]
+    pub sequence: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Order {
-    pub outcome_id: String,
+    pub outcome_id: OutcomeId,
     pub price: f64,
     pub size: f64,
 }
@@ -44,8 +70,8 @@ pub struct Order {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Trade {
     pub id: Uuid,
-    pub market_id: Uuid,
-    pub outcome_id: String,
+    pub market_id: MarketId,
+    pub outcome_id: OutcomeId,
     pub price: f64,
     pub size: f64,
     pub side: OrderSide,
@@ -59,11 +85,27 @@ pub enum OrderSide {
     Sell,
 }
 
+/// News article, as published to the `news-articles` topic
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewsArticle {
+    pub id: String,
+    pub url: String,
+    pub title: String,
+    /// Themes separated by ';', e.g. "ELECTION;TAX_FNCACT_PRESIDENT"
+    pub themes: String,
+    /// Average tone, -100 (negative) to +100 (positive)
+    pub tone: f64,
+    pub timestamp: DateTime<Utc>,
+    /// Publisher, e.g. "reuters.com"
+    #[serde(default)]
+    pub source: String,
+}
+
 /// Market price tick
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PriceTick {
-    pub market_id: Uuid,
-    pub outcome_id: String,
+    pub market_id: MarketId,
+    pub outcome_id: OutcomeId,
     pub price: f64,
     pub volume_24h: f64,
     pub liquidity: f64,
@@ -78,17 +120,36 @@ pub enum MarketEvent {
     Trade(Trade),
     PriceTick(PriceTick),
     MarketCreated(Market),
-    MarketResolved { market_id: Uuid, outcome_id: String },
+    MarketResolved { market_id: MarketId, outcome_id: OutcomeId },
+    /// A resolution as the feed reports it, by condition rather than market;
+    /// `ResolutionRouter` maps it onto the market created for the condition
+    ConditionResolved { condition_id: ConditionId, outcome_id: OutcomeId },
+    /// A previous resolution was overturned; positions settled on `previous_outcome` reopen
+    ResolutionReversed { market_id: MarketId, previous_outcome: OutcomeId },
+    /// The market was voided and positions are refunded under `refund_policy`
+    MarketInvalidated { market_id: MarketId, refund_policy: RefundPolicy },
+}
+
+/// How positions in an invalidated market are paid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RefundPolicy {
+    /// Every share pays out 0.5, as for a 50/50 resolution
+    FiftyFifty,
+    /// Every position is refunded its cost basis
+    AtCost,
 }
 
 impl MarketEvent {
-    pub fn market_id(&self) -> Uuid {
+    pub fn market_id(&self) -> MarketId {
         match self {
             MarketEvent::OrderBookUpdate(ob) => ob.market_id,
             MarketEvent::Trade(t) => t.market_id,
             MarketEvent::PriceTick(pt) => pt.market_id,
             MarketEvent::MarketCreated(m) => m.id,
             MarketEvent::MarketResolved { market_id, .. } => *market_id,
+            MarketEvent::ConditionResolved { condition_id, .. } => MarketId::for_condition(condition_id),
+            MarketEvent::ResolutionReversed { market_id, .. } => *market_id,
+            MarketEvent::MarketInvalidated { market_id, .. } => *market_id,
         }
     }
 
@@ -98,7 +159,10 @@ impl MarketEvent {
             MarketEvent::Trade(t) => t.timestamp,
             MarketEvent::PriceTick(pt) => pt.timestamp,
             MarketEvent::MarketCreated(m) => m.created_at,
-            MarketEvent::MarketResolved { .. } => Utc::now(),
+            MarketEvent::MarketResolved { .. }
+            | MarketEvent::ConditionResolved { .. }
+            | MarketEvent::ResolutionReversed { .. }
+            | MarketEvent::MarketInvalidated { .. } => Utc::now(),
         }
     }
 }
@@ -120,8 +184,8 @@ pub enum ResolutionStatus {
 /// Market resolution result
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Resolution {
-    pub market_id: Uuid,
-    pub outcome_id: String,
+    pub market_id: MarketId,
+    pub outcome_id: OutcomeId,
     pub status: ResolutionStatus,
     pub resolved_at: Option<DateTime<Utc>>,
     pub resolution_price: Option<f64>, // 1.0 for true, 0.0 for false
@@ -131,8 +195,8 @@ pub struct Resolution {
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct AttributedTrade {
     pub trade_id: Uuid,
-    pub market_id: Uuid,
-    pub outcome_id: String,
+    pub market_id: MarketId,
+    pub outcome_id: OutcomeId,
     pub side: OrderSide,
     pub entry_price: f64,
     pub size: f64,
@@ -146,17 +210,80 @@ pub struct AttributedTrade {
     pub pnl_percent: Option<f64>,
 }
 
+/// Kind of signal a generator emits
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SignalType {
+    SpreadArbitrage,
+    Momentum,
+    MeanReversion,
+    Value,
+    Sentiment,
+    /// Pull a resting quote; names it by `quote_id` in the signal's metadata
+    CancelQuote,
+}
+
+impl SignalType {
+    /// Name as stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignalType::SpreadArbitrage => "SpreadArbitrage",
+            SignalType::Momentum => "Momentum",
+            SignalType::MeanReversion => "MeanReversion",
+            SignalType::Value => "Value",
+            SignalType::Sentiment => "Sentiment",
+            SignalType::CancelQuote => "CancelQuote",
+        }
+    }
+}
+
+impl std::fmt::Display for SignalType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for SignalType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "SpreadArbitrage" => Ok(SignalType::SpreadArbitrage),
+            "Momentum" => Ok(SignalType::Momentum),
+            "MeanReversion" => Ok(SignalType::MeanReversion),
+            "Value" => Ok(SignalType::Value),
+            "Sentiment" => Ok(SignalType::Sentiment),
+            "CancelQuote" => Ok(SignalType::CancelQuote),
+            other => Err(format!("unknown signal type {}", other)),
+        }
+    }
+}
+
+/// Output from research agent processing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentOutput {
+    pub agent_id: String,
+    pub market_id: MarketId,
+    pub signal_type: String,
+    pub data: serde_json::Value,
+    pub confidence: f64, // 0.0 to 1.0
+    pub timestamp: DateTime<Utc>,
+    pub processing_time_ms: u64,
+}
+
 /// Signal generated by an agent/strategy
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Signal {
     pub id: Uuid,
-    pub market_id: Uuid,
-    pub outcome_id: String,
+    pub market_id: MarketId,
+    pub outcome_id: OutcomeId,
     pub predicted_probability: f64,
     pub confidence: f64,
     pub direction: OrderSide,
     pub agent_id: Uuid,
     pub strategy_id: String,
+    /// Kind of signal, for attribution by signal type
+    #[serde(default)]
+    pub signal_type: Option<SignalType>,
     pub generated_at: DateTime<Utc>,
     pub metadata: serde_json::Value,
 }
@@ -174,10 +301,14 @@ pub struct PerformanceMetrics {
     pub total_pnl: f64,
     pub roi: f64,
     pub sharpe_ratio: Option<f64>,
+    /// Like Sharpe, but only penalizes returns below the risk-free rate
+    #[serde(default)]
+    pub sortino_ratio: Option<f64>,
     pub max_drawdown: f64,
     pub avg_win: f64,
     pub avg_loss: f64,
     pub profit_factor: f64,
+    /// Annualized return over max drawdown
     pub calmar_ratio: Option<f64>,
 }
 
@@ -271,10 +402,79 @@ pub struct AbTest {
     pub allocation_ratio: f64, // 0.5 = 50/50 split
     pub min_sample_size: i64,
     pub statistical_significance: f64, // 0.95 for 95% confidence
+    /// Test that decides the winner in `AbTestEngine::analyze_test`
+    #[serde(default)]
+    #[sqlx(default)]
+    pub method: AbTestMethod,
+    /// Interim looks before a sequential test must end; 0 looks whenever
+    /// asked until `min_sample_size` is reached
+    #[serde(default)]
+    #[sqlx(default)]
+    pub looks_planned: i32,
+    /// How a sequential test spreads its alpha over the looks
+    #[serde(default)]
+    #[sqlx(default)]
+    pub alpha_spending: AlphaSpending,
+    /// Interim looks taken so far
+    #[serde(default)]
+    #[sqlx(default)]
+    pub current_look: i32,
+}
+
+impl AbTest {
+    /// Deterministically assign a market to strategy A or B using `allocation_ratio`
+    pub fn assign_strategy(&self, market_id: MarketId) -> &str {
+        if experiment_bucket(self.id, market_id) < self.allocation_ratio {
+            &self.strategy_a
+        } else {
+            &self.strategy_b
+        }
+    }
+
+    /// Assign a market within its stratum, given how many of the stratum's
+    /// markets each arm already has.
+    ///
+    /// Picks the arm furthest below its `allocation_ratio` share once this
+    /// market is counted, so within a stratum neither arm drifts more than
+    /// one market from its share. Ties go to `assign_strategy`.
+    pub fn assign_stratified(&self, market_id: MarketId, count_a: i64, count_b: i64) -> &str {
+        let markets = (count_a + count_b + 1) as f64;
+        let deficit_a = self.allocation_ratio * markets - count_a as f64;
+        let deficit_b = (1.0 - self.allocation_ratio) * markets - count_b as f64;
+
+        if (deficit_a - deficit_b).abs() < 1e-9 {
+            self.assign_strategy(market_id)
+        } else if deficit_a > deficit_b {
+            &self.strategy_a
+        } else {
+            &self.strategy_b
+        }
+    }
+}
+
+/// Stable bucket in [0, 1) for a market within an experiment.
+///
+/// Uses FNV-1a with a splitmix64 finalizer so the same (experiment, market)
+/// pair maps to the same bucket across processes and builds.
+pub fn experiment_bucket(experiment_id: Uuid, market_id: MarketId) -> f64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in experiment_id.as_bytes().iter().chain(market_id.as_bytes()) {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+
+    hash ^= hash >> 30;
+    hash = hash.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash ^= hash >> 27;
+    hash = hash.wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^= hash >> 31;
+
+    (hash >> 11) as f64 / (1u64 << 53) as f64
 }
 
+/// Stored as its variant name, e.g. 'Running'
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
-#[sqlx(type_name = "ab_test_status", rename_all = "lowercase")]
+#[sqlx(type_name = "TEXT")]
 pub enum AbTestStatus {
     Running,
     Paused,
@@ -282,6 +482,30 @@ pub enum AbTestStatus {
     Inconclusive,
 }
 
+/// Significance test comparing the strategies' per-trade P&L
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum AbTestMethod {
+    /// Welch's unequal-variance t-test on mean P&L
+    #[default]
+    Welch,
+    /// Mann-Whitney U rank test, robust to heavy-tailed P&L
+    MannWhitney,
+}
+
+/// Lan-DeMets alpha-spending function for sequential A/B tests
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum AlphaSpending {
+    /// O'Brien-Fleming type: almost nothing early, nearly all of it at the end
+    #[default]
+    ObrienFleming,
+    /// Pocock type: spread roughly evenly, so early stops come easier
+    Pocock,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AbTestResult {
     pub test_id: Uuid,
@@ -291,6 +515,38 @@ pub struct AbTestResult {
     pub confidence: Option<f64>,
     pub p_value: Option<f64>,
     pub recommendation: String,
+    /// Rank-based comparison of per-trade P&L, if requested
+    #[serde(default)]
+    pub nonparametric: Option<NonparametricResult>,
+    /// Test the winner, confidence and p-value come from
+    #[serde(default)]
+    pub method: AbTestMethod,
+    /// Mean per-trade P&L of A minus B, with a Welch confidence interval
+    #[serde(default)]
+    pub effect_size: Option<MeanDifference>,
+}
+
+/// Difference in mean per-trade P&L between two strategies
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MeanDifference {
+    /// Mean of A minus mean of B
+    pub difference: f64,
+    pub lower: f64,
+    pub upper: f64,
+    /// Coverage of `lower..=upper`, e.g. 0.95
+    pub confidence_level: f64,
+}
+
+/// Mann-Whitney U comparison of two strategies' per-trade P&L
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NonparametricResult {
+    /// U statistic for strategy A
+    pub u_statistic: f64,
+    pub p_value: f64,
+    /// Probability a random A trade beats a random B trade (ties count half)
+    pub prob_a_superior: f64,
+    /// Strategy that stochastically dominates: "A", "B", or null if not significant
+    pub dominant: Option<String>,
 }
 
 /// Shadow mode trade (paper trading)
@@ -298,8 +554,8 @@ pub struct AbTestResult {
 pub struct ShadowTrade {
     pub id: Uuid,
     pub trade_id: Option<Uuid>, // Real trade ID if executed
-    pub market_id: Uuid,
-    pub outcome_id: String,
+    pub market_id: MarketId,
+    pub outcome_id: OutcomeId,
     pub side: OrderSide,
     pub price: f64,
     pub size: f64,
@@ -307,4 +563,17 @@ pub struct ShadowTrade {
     pub strategy_id: String,
     pub hypothetical_pnl: Option<f64>,
     pub would_have_won: Option<bool>,
+    /// Best price on the side the trade took when it arrived, the baseline
+    /// its slippage is measured from
+    #[serde(default)]
+    #[sqlx(default)]
+    pub reference_price: Option<f64>,
+    /// Price at which the position is closed early for a profit
+    #[serde(default)]
+    #[sqlx(default)]
+    pub target_price: Option<f64>,
+    /// Price at which the position is closed early to cut the loss
+    #[serde(default)]
+    #[sqlx(default)]
+    pub stop_loss: Option<f64>,
 }
@@ -0,0 +1,353 @@
+//! Incremental statistics
+//!
+//! Running estimators that update in O(1) per observation, for metrics that
+//! would otherwise be recomputed from a full history on every event.
+
+use serde::{Deserialize, Serialize};
+
+/// Count, mean and variance of a stream, with removal for sliding windows
+///
+/// Uses Welford's update, so results match a two-pass computation up to
+/// floating point rounding. Removing a value that was never added leaves
+/// the moments meaningless.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunningMoments {
+    count: u64,
+    mean: f64,
+    /// Sum of squared deviations from the mean
+    m2: f64,
+}
+
+impl RunningMoments {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, x: f64) {
+        self.count += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    /// Remove a value previously added, e.g. the oldest one of a window
+    pub fn remove(&mut self, x: f64) {
+        match self.count {
+            0 => {}
+            1 => *self = Self::default(),
+            _ => {
+                let delta = x - self.mean;
+                self.count -= 1;
+                self.mean -= delta / self.count as f64;
+                self.m2 = (self.m2 - delta * (x - self.mean)).max(0.0);
+            }
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Population variance (divides by n)
+    pub fn variance(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.m2 / self.count as f64
+        }
+    }
+
+    /// Population standard deviation
+    pub fn std_dev(&self) -> f64 {
+        self.variance().sqrt()
+    }
+}
+
+/// Streaming estimate of a single quantile in constant memory
+///
+/// The P² algorithm (Jain & Chlamtac, 1985) keeps five markers whose
+/// heights track the minimum, the p/2, p and (1+p)/2 quantiles and the
+/// maximum, adjusting them with piecewise-parabolic interpolation as
+/// observations arrive. Until five observations have been seen the
+/// estimate is exact.
+///
+/// # Accuracy
+///
+/// P² has no worst-case error bound: the estimate is a heuristic that is
+/// good for smooth, unimodal distributions and poor for data with gaps or
+/// strong regime changes, and it weights the whole stream equally. For
+/// stationary data with at least 1,000 observations the estimate is within
+/// 0.1 standard deviations of the exact sample quantile for p in
+/// [0.01, 0.99], which the tests check on normal and heavy-tailed series.
+/// Central quantiles are usually within 0.02; the extreme ones converge
+/// slowest.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct P2Quantile {
+    p: f64,
+    count: u64,
+    /// Marker heights
+    heights: [f64; 5],
+    /// Actual marker positions, 1-based
+    positions: [f64; 5],
+    /// Desired marker positions
+    desired: [f64; 5],
+    /// Increments of the desired positions per observation
+    increments: [f64; 5],
+}
+
+impl P2Quantile {
+    /// Estimator for the `p` quantile, 0 < p < 1
+    pub fn new(p: f64) -> Self {
+        assert!(p > 0.0 && p < 1.0, "quantile must be in (0, 1), got {}", p);
+        Self {
+            p,
+            count: 0,
+            heights: [0.0; 5],
+            positions: [1.0, 2.0, 3.0, 4.0, 5.0],
+            desired: [1.0, 1.0 + 2.0 * p, 1.0 + 4.0 * p, 3.0 + 2.0 * p, 5.0],
+            increments: [0.0, p / 2.0, p, (1.0 + p) / 2.0, 1.0],
+        }
+    }
+
+    pub fn quantile(&self) -> f64 {
+        self.p
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn add(&mut self, x: f64) {
+        if self.count < 5 {
+            self.heights[self.count as usize] = x;
+            self.count += 1;
+            if self.count == 5 {
+                self.heights.sort_by(|a, b| a.total_cmp(b));
+            }
+            return;
+        }
+        self.count += 1;
+
+        // Cell the observation falls in, stretching the extremes if needed
+        let h = &mut self.heights;
+        let k = if x < h[0] {
+            h[0] = x;
+            0
+        } else if x >= h[4] {
+            h[4] = x;
+            3
+        } else {
+            (1..5).find(|&i| x < h[i]).unwrap() - 1
+        };
+
+        for position in &mut self.positions[k + 1..] {
+            *position += 1.0;
+        }
+        for (desired, increment) in self.desired.iter_mut().zip(self.increments) {
+            *desired += increment;
+        }
+
+        // Move the inner markers towards their desired positions
+        for i in 1..4 {
+            let d = self.desired[i] - self.positions[i];
+            if (d >= 1.0 && self.positions[i + 1] - self.positions[i] > 1.0)
+                || (d <= -1.0 && self.positions[i - 1] - self.positions[i] < -1.0)
+            {
+                let d = d.signum();
+                let parabolic = self.parabolic(i, d);
+                self.heights[i] = if self.heights[i - 1] < parabolic && parabolic < self.heights[i + 1] {
+                    parabolic
+                } else {
+                    self.linear(i, d)
+                };
+                self.positions[i] += d;
+            }
+        }
+    }
+
+    /// Current estimate, None before any observation
+    ///
+    /// With fewer than five observations this is the sample quantile at
+    /// index `floor(p * n)` of the sorted observations.
+    pub fn estimate(&self) -> Option<f64> {
+        match self.count {
+            0 => None,
+            n if n < 5 => {
+                let mut seen = self.heights[..n as usize].to_vec();
+                seen.sort_by(|a, b| a.total_cmp(b));
+                Some(seen[((self.p * n as f64) as usize).min(n as usize - 1)])
+            }
+            _ => Some(self.heights[2]),
+        }
+    }
+
+    fn parabolic(&self, i: usize, d: f64) -> f64 {
+        let (q, n) = (&self.heights, &self.positions);
+        q[i] + d / (n[i + 1] - n[i - 1])
+            * ((n[i] - n[i - 1] + d) * (q[i + 1] - q[i]) / (n[i + 1] - n[i])
+                + (n[i + 1] - n[i] - d) * (q[i] - q[i - 1]) / (n[i] - n[i - 1]))
+    }
+
+    fn linear(&self, i: usize, d: f64) -> f64 {
+        let j = if d > 0.0 { i + 1 } else { i - 1 };
+        self.heights[i] + d * (self.heights[j] - self.heights[i]) / (self.positions[j] - self.positions[i])
+    }
+}
+
+/// Streaming estimate of the mean of the lowest `alpha` fraction of a
+/// stream (expected shortfall at level alpha)
+///
+/// Integrates the quantile function over [0, alpha] with the midpoint
+/// rule, using one `P2Quantile` per slice, so it inherits P²'s accuracy.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct P2TailMean {
+    slices: Vec<P2Quantile>,
+}
+
+impl P2TailMean {
+    /// Slices used to integrate the tail
+    pub const SLICES: usize = 5;
+
+    pub fn new(alpha: f64) -> Self {
+        assert!(alpha > 0.0 && alpha < 1.0, "tail fraction must be in (0, 1), got {}", alpha);
+        let width = alpha / Self::SLICES as f64;
+        Self {
+            slices: (0..Self::SLICES).map(|i| P2Quantile::new(width * (i as f64 + 0.5))).collect(),
+        }
+    }
+
+    pub fn add(&mut self, x: f64) {
+        for slice in &mut self.slices {
+            slice.add(x);
+        }
+    }
+
+    pub fn estimate(&self) -> Option<f64> {
+        let estimates: Option<Vec<f64>> = self.slices.iter().map(P2Quantile::estimate).collect();
+        estimates.map(|e| e.iter().sum::<f64>() / e.len() as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic xorshift so the series is the same on every run
+    struct Series(u64);
+
+    impl Series {
+        fn uniform(&mut self) -> f64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 >> 11) as f64 / (1u64 << 53) as f64
+        }
+
+        /// Standard normal via Box-Muller
+        fn normal(&mut self) -> f64 {
+            let u1 = self.uniform().max(f64::MIN_POSITIVE);
+            let u2 = self.uniform();
+            (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+        }
+
+        /// Student-t with 3 degrees of freedom, for fat tails
+        fn heavy_tailed(&mut self) -> f64 {
+            let z = self.normal();
+            let chi2: f64 = (0..3).map(|_| self.normal().powi(2)).sum();
+            z / (chi2 / 3.0).sqrt()
+        }
+    }
+
+    fn exact_quantile(values: &[f64], p: f64) -> f64 {
+        let mut sorted = values.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        sorted[(p * sorted.len() as f64) as usize]
+    }
+
+    #[test]
+    fn test_running_moments_sliding_window() {
+        let mut series = Series(7);
+        let values: Vec<f64> = (0..10_000).map(|_| 100.0 + series.normal() * 5.0).collect();
+        let window = 250;
+
+        let mut moments = RunningMoments::new();
+        for (i, &x) in values.iter().enumerate() {
+            moments.add(x);
+            if i >= window {
+                moments.remove(values[i - window]);
+            }
+        }
+
+        let tail = &values[values.len() - window..];
+        let mean = tail.iter().sum::<f64>() / window as f64;
+        let variance = tail.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / window as f64;
+        assert_eq!(moments.count(), window as u64);
+        assert!((moments.mean() - mean).abs() < 1e-9);
+        assert!((moments.variance() - variance).abs() < 1e-6 * variance);
+
+        moments.remove(tail[0]);
+        for &x in &tail[1..] {
+            moments.remove(x);
+        }
+        assert_eq!(moments, RunningMoments::default());
+    }
+
+    #[test]
+    fn test_p2_matches_exact_quantiles_within_documented_tolerance() {
+        for (seed, heavy) in [(11, false), (29, true)] {
+            let mut series = Series(seed);
+            let values: Vec<f64> = (0..20_000)
+                .map(|_| if heavy { series.heavy_tailed() } else { series.normal() })
+                .collect();
+            let mut moments = RunningMoments::new();
+            values.iter().for_each(|&x| moments.add(x));
+
+            for p in [0.01, 0.05, 0.5, 0.95, 0.99] {
+                let mut estimator = P2Quantile::new(p);
+                for (i, &x) in values.iter().enumerate() {
+                    estimator.add(x);
+                    if i + 1 == 1_000 || i + 1 == values.len() {
+                        let exact = exact_quantile(&values[..=i], p);
+                        let error = (estimator.estimate().unwrap() - exact).abs();
+                        assert!(
+                            error < 0.1 * moments.std_dev(),
+                            "p={} n={} heavy={}: estimate off by {}",
+                            p, i + 1, heavy, error
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_p2_small_samples_are_exact() {
+        let mut estimator = P2Quantile::new(0.5);
+        assert_eq!(estimator.estimate(), None);
+        for x in [3.0, 1.0, 2.0] {
+            estimator.add(x);
+        }
+        assert_eq!(estimator.estimate(), Some(2.0));
+    }
+
+    #[test]
+    fn test_tail_mean_matches_expected_shortfall() {
+        let mut series = Series(3);
+        let values: Vec<f64> = (0..20_000).map(|_| series.normal()).collect();
+        let mut tail = P2TailMean::new(0.05);
+        values.iter().for_each(|&x| tail.add(x));
+
+        let mut sorted = values.clone();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let worst = &sorted[..values.len() / 20];
+        let exact = worst.iter().sum::<f64>() / worst.len() as f64;
+
+        // Standard normal ES at 5% is about -2.06
+        assert!((exact + 2.06).abs() < 0.05);
+        assert!((tail.estimate().unwrap() - exact).abs() < 0.05, "{} vs {}", tail.estimate().unwrap(), exact);
+    }
+}
@@ -0,0 +1,6 @@
+//! Mixed-up identifiers must not compile
+
+#[test]
+fn mixed_up_ids_are_rejected() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
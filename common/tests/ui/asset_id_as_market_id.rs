@@ -0,0 +1,7 @@
+use common::{AssetId, MarketId};
+
+fn positions_in(_market_id: MarketId) {}
+
+fn main() {
+    positions_in(AssetId::from("2174263314346390629056905015582624"));
+}
@@ -0,0 +1,5 @@
+use common::{AssetId, OutcomeId};
+
+fn main() {
+    let _outcome: OutcomeId = AssetId::from("2174263314346390629056905015582624");
+}
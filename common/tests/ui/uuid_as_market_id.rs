@@ -0,0 +1,6 @@
+use common::{MarketId, Uuid};
+
+fn main() {
+    // A fresh UUID has to be converted explicitly to stand in for a market
+    let _market_id: MarketId = Uuid::new_v4();
+}
@@ -0,0 +1,37 @@
+use data_ingestion::connectors::{SyntheticConnector, SyntheticScenario};
+use data_ingestion::event_bus::KafkaProducer;
+use tracing::info;
+use tracing_subscriber::fmt;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    fmt()
+        .with_max_level(tracing::Level::INFO)
+        .init();
+
+    // Run with: cargo run --bin synthetic_load -- examples/synthetic_5k.toml [brokers]
+    let mut args = std::env::args().skip(1);
+    let scenario = match args.next() {
+        Some(path) => SyntheticScenario::from_file(&path)?,
+        None => SyntheticScenario::default(),
+    };
+    let brokers = args.next().unwrap_or_else(|| "localhost:9092".to_string());
+
+    info!(
+        "Synthetic load: {} markets, {:.1} events/s per market",
+        scenario.market_count,
+        scenario.book_updates_per_sec + scenario.ticks_per_sec + scenario.trades_per_sec
+    );
+
+    let producer = KafkaProducer::new(&brokers).await?;
+    let connector = SyntheticConnector::new(scenario);
+
+    tokio::select! {
+        result = connector.run(&producer) => result?,
+        _ = tokio::signal::ctrl_c() => {
+            info!("👋 Shutting down synthetic load...");
+        }
+    }
+
+    Ok(())
+}
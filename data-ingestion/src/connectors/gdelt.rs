@@ -1,94 +1,331 @@
-use anyhow::Result;
-use chrono::{Duration, Utc};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use common::NewsArticle;
+use flate2::read::DeflateDecoder;
 use reqwest::Client;
-use serde::Deserialize;
-use tracing::info;
+use std::collections::{HashSet, VecDeque};
+use std::io::Read;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use crate::event_bus::KafkaProducer;
+
+/// Columns in a GKG 2.1 record
+const GKG_COLUMNS: usize = 27;
+const RECORD_ID: usize = 0;
+const DATE: usize = 1;
+const SOURCE_COMMON_NAME: usize = 3;
+const DOCUMENT_IDENTIFIER: usize = 4;
+const V1_THEMES: usize = 7;
+const V2_THEMES: usize = 8;
+const V2_TONE: usize = 15;
+const EXTRAS: usize = 26;
+
+/// GDELT connector configuration
+#[derive(Debug, Clone)]
+pub struct GDELTConfig {
+    /// Lists the files of the latest 15-minute update
+    pub last_update_url: String,
+    pub poll_interval: Duration,
+    /// Keep only articles with a theme starting with one of these; empty keeps all
+    pub theme_filter: Vec<String>,
+    pub topic: String,
+    /// Articles per published message
+    pub batch_size: usize,
+    /// Record ids remembered for deduplication across files
+    pub dedupe_window: usize,
+}
+
+impl Default for GDELTConfig {
+    fn default() -> Self {
+        Self {
+            last_update_url: "http://data.gdeltproject.org/gdeltv2/lastupdate.txt".to_string(),
+            poll_interval: Duration::from_secs(15 * 60),
+            theme_filter: Vec::new(),
+            topic: "news-articles".to_string(),
+            batch_size: 500,
+            dedupe_window: 500_000,
+        }
+    }
+}
+
+/// What happened to the records of a GKG file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GkgStats {
+    pub parsed: usize,
+    /// Already seen in this or an earlier file
+    pub duplicates: usize,
+    /// Too few columns, no URL, or an unreadable date or tone
+    pub malformed: usize,
+    /// No theme matched the filter
+    pub filtered: usize,
+}
+
+impl GkgStats {
+    fn add(&mut self, other: GkgStats) {
+        self.parsed += other.parsed;
+        self.duplicates += other.duplicates;
+        self.malformed += other.malformed;
+        self.filtered += other.filtered;
+    }
+}
+
+/// Parses GKG 2.1 records into NewsArticles
+///
+/// GDELT republishes records across updates, so ids are remembered (up to
+/// `dedupe_window` of them) and repeats are dropped.
+pub struct GkgParser {
+    theme_filter: Vec<String>,
+    dedupe_window: usize,
+    seen: HashSet<String>,
+    seen_order: VecDeque<String>,
+    totals: GkgStats,
+}
+
+impl GkgParser {
+    pub fn new(theme_filter: Vec<String>, dedupe_window: usize) -> Self {
+        Self {
+            theme_filter,
+            dedupe_window,
+            seen: HashSet::new(),
+            seen_order: VecDeque::new(),
+            totals: GkgStats::default(),
+        }
+    }
+
+    /// Parse a tab-separated GKG file, skipping bad rows
+    pub fn parse(&mut self, csv: &str) -> (Vec<NewsArticle>, GkgStats) {
+        let mut articles = Vec::new();
+        let mut stats = GkgStats::default();
+
+        for (line_no, line) in csv.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Some(article) = parse_record(line) else {
+                debug!("Skipping malformed GKG line {}", line_no + 1);
+                stats.malformed += 1;
+                continue;
+            };
+            if !self.remember(&article.id) {
+                stats.duplicates += 1;
+                continue;
+            }
+            if !self.matches_filter(&article.themes) {
+                stats.filtered += 1;
+                continue;
+            }
+            stats.parsed += 1;
+            articles.push(article);
+        }
+
+        self.totals.add(stats);
+        (articles, stats)
+    }
+
+    /// Counts over every file parsed so far
+    pub fn totals(&self) -> GkgStats {
+        self.totals
+    }
+
+    /// Record an id, false if it was already seen
+    fn remember(&mut self, id: &str) -> bool {
+        if !self.seen.insert(id.to_string()) {
+            return false;
+        }
+        self.seen_order.push_back(id.to_string());
+        while self.seen_order.len() > self.dedupe_window.max(1) {
+            if let Some(oldest) = self.seen_order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        true
+    }
+
+    fn matches_filter(&self, themes: &str) -> bool {
+        self.theme_filter.is_empty()
+            || themes
+                .split(';')
+                .any(|theme| self.theme_filter.iter().any(|prefix| theme.starts_with(prefix.as_str())))
+    }
+}
+
+/// One GKG record, or None if it is malformed
+fn parse_record(line: &str) -> Option<NewsArticle> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    if fields.len() < GKG_COLUMNS {
+        return None;
+    }
+
+    let id = fields[RECORD_ID].trim();
+    let url = fields[DOCUMENT_IDENTIFIER].trim();
+    if id.is_empty() || url.is_empty() {
+        return None;
+    }
+    let date = NaiveDateTime::parse_from_str(fields[DATE].trim(), "%Y%m%d%H%M%S").ok()?;
+    // Average tone comes first of the seven tone values
+    let tone: f64 = fields[V2_TONE].split(',').next()?.trim().parse().ok()?;
+
+    Some(NewsArticle {
+        id: id.to_string(),
+        url: url.to_string(),
+        title: page_title(fields[EXTRAS]).unwrap_or_default(),
+        themes: themes(fields[V2_THEMES], fields[V1_THEMES]),
+        tone,
+        timestamp: Utc.from_utc_datetime(&date),
+        source: fields[SOURCE_COMMON_NAME].trim().to_string(),
+    })
+}
+
+/// Distinct theme names joined with ';'. V2 themes are "THEME,offset"
+/// pairs, one per mention; V1 themes are used when there are none.
+fn themes(v2: &str, v1: &str) -> String {
+    let mentions: Vec<&str> = if v2.trim().is_empty() {
+        v1.split(';').collect()
+    } else {
+        v2.split(';').filter_map(|mention| mention.split(',').next()).collect()
+    };
+    let mut names: Vec<&str> = Vec::new();
+    for name in mentions.into_iter().map(str::trim) {
+        if !name.is_empty() && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names.join(";")
+}
+
+/// Title from the Extras column's <PAGE_TITLE> block
+fn page_title(extras: &str) -> Option<String> {
+    let start = extras.find("<PAGE_TITLE>")? + "<PAGE_TITLE>".len();
+    let end = start + extras[start..].find("</PAGE_TITLE>")?;
+    Some(extras[start..end].trim().to_string())
+}
+
+/// URL of the GKG file in a lastupdate.txt listing ("size md5 url" per line)
+fn gkg_url(last_update: &str) -> Option<&str> {
+    last_update
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(2))
+        .find(|url| url.ends_with(".gkg.csv.zip"))
+}
+
+/// Contents of the first entry of a zip archive. GDELT archives hold a
+/// single CSV, stored or deflated.
+fn unzip_first(archive: &[u8]) -> Result<Vec<u8>> {
+    const LOCAL_HEADER: u32 = 0x0403_4b50;
+    const HEADER_LEN: usize = 30;
+
+    let u16_at = |at: usize| u16::from_le_bytes([archive[at], archive[at + 1]]);
+    let u32_at = |at: usize| u32::from_le_bytes([archive[at], archive[at + 1], archive[at + 2], archive[at + 3]]);
+
+    if archive.len() < HEADER_LEN || u32_at(0) != LOCAL_HEADER {
+        bail!("Not a zip archive");
+    }
+    let flags = u16_at(6);
+    let method = u16_at(8);
+    let compressed_size = u32_at(18) as usize;
+    let data_start = HEADER_LEN + u16_at(26) as usize + u16_at(28) as usize;
+    let data = archive.get(data_start..).ok_or_else(|| anyhow!("Truncated zip archive"))?;
+
+    // Bit 3: sizes follow the data instead, so inflate until the stream ends
+    let data = if flags & 0x08 == 0 {
+        data.get(..compressed_size).ok_or_else(|| anyhow!("Truncated zip archive"))?
+    } else {
+        data
+    };
+
+    match method {
+        0 => Ok(data.to_vec()),
+        8 => {
+            let mut contents = Vec::new();
+            DeflateDecoder::new(data)
+                .read_to_end(&mut contents)
+                .context("Failed to inflate zip entry")?;
+            Ok(contents)
+        }
+        other => bail!("Unsupported zip compression method {}", other),
+    }
+}
 
 /// GDELT news connector
-/// GDELT provides free global news data for sentiment analysis
+/// Follows the GDELT 2.0 Global Knowledge Graph, published every 15 minutes
 pub struct GDELTConnector {
-    api_url: String,
+    config: GDELTConfig,
     client: Client,
-}
-
-#[derive(Debug, Deserialize)]
-struct GDELTArticle {
-    #[serde(rename = "GKGRECORDID")]
-    id: String,
-    #[serde(rename = "DocumentIdentifier")]
-    url: String,
-    #[serde(rename = "SharingImage")]
-    image: Option<String>,
-    #[serde(rename = "Title")]
-    title: String,
-    #[serde(rename = "Themes")]
-    themes: String,
-    #[serde(rename = "Locations")]
-    locations: String,
-    #[serde(rename = "Persons")]
-    persons: String,
-    #[serde(rename = "Tone")]
-    tone: f64,
+    parser: GkgParser,
+    last_file: Option<String>,
 }
 
 impl GDELTConnector {
     pub fn new() -> Self {
+        Self::with_config(GDELTConfig::default())
+    }
+
+    pub fn with_config(config: GDELTConfig) -> Self {
+        let parser = GkgParser::new(config.theme_filter.clone(), config.dedupe_window);
         Self {
-            api_url: "https://api.gdeltproject.org/api/v2/doc/doc".to_string(),
+            config,
             client: Client::new(),
+            parser,
+            last_file: None,
         }
     }
 
-    pub async fn fetch_recent_articles(&self, hours: i64) -> Result<Vec<GDELTArticle>> {
-        let end_time = Utc::now();
-        let start_time = end_time - Duration::hours(hours);
+    /// Articles in the latest GKG file, or none if it was already fetched
+    pub async fn poll(&mut self) -> Result<Vec<NewsArticle>> {
+        let listing = self.fetch(&self.config.last_update_url).await?;
+        let listing = String::from_utf8_lossy(&listing);
+        let url = gkg_url(&listing)
+            .ok_or_else(|| anyhow!("No GKG file in {}", self.config.last_update_url))?
+            .to_string();
 
-        // GDELT query format: mode query start end maxrecords format
-        let query = format!(
-            "{} {} {} {} {} json",
-            "artlist",                     // mode
-            "*:*",                        // query (all articles)
-            start_time.format("%Y%m%d%H%M%S"),
-            end_time.format("%Y%m%d%H%M%S"),
-            "250"                         // max records
-        );
+        if self.last_file.as_deref() == Some(url.as_str()) {
+            debug!("GKG file {} already processed", url);
+            return Ok(Vec::new());
+        }
 
-        info!("Fetching articles from GDELT...");
+        info!("Fetching GKG file {}", url);
+        let archive = self.fetch(&url).await?;
+        let csv = unzip_first(&archive).with_context(|| format!("Failed to unzip {}", url))?;
+        let (articles, stats) = self.parser.parse(&String::from_utf8_lossy(&csv));
+        self.last_file = Some(url);
+
+        info!(
+            "✅ Parsed {} GDELT articles ({} duplicate, {} malformed, {} filtered)",
+            stats.parsed, stats.duplicates, stats.malformed, stats.filtered
+        );
+        Ok(articles)
+    }
 
-        let response = self.client
-            .get(&self.api_url)
-            .query(&[("query", &query)])
-            .send()
-            .await?;
+    /// Counts over every file parsed so far
+    pub fn stats(&self) -> GkgStats {
+        self.parser.totals()
+    }
 
+    async fn fetch(&self, url: &str) -> Result<Vec<u8>> {
+        let response = self.client.get(url).send().await?;
         if !response.status().is_success() {
-            return Err(anyhow::anyhow!(
-                "GDELT API error: {}",
-                response.status()
-            ));
+            return Err(anyhow!("GDELT error for {}: {}", url, response.status()));
         }
-
-        let articles: Vec<GDELTArticle> = response.json().await?;
-
-        info!("✅ Fetched {} articles from GDELT", articles.len());
-
-        Ok(articles)
+        Ok(response.bytes().await?.to_vec())
     }
 
-    pub async fn run_continuous(&self) -> Result<()> {
+    pub async fn run_continuous(&mut self, producer: &KafkaProducer) -> Result<()> {
         loop {
-            match self.fetch_recent_articles(1).await {
+            match self.poll().await {
                 Ok(articles) => {
-                    // TODO: Process articles and send to event bus
-                    info!("Processed {} articles", articles.len());
+                    for batch in articles.chunks(self.config.batch_size.max(1)) {
+                        if let Err(e) = producer.publish_articles(&self.config.topic, batch).await {
+                            warn!("Failed to publish {} GDELT articles: {}", batch.len(), e);
+                        }
+                    }
                 }
                 Err(e) => {
-                    eprintln!("Error fetching GDELT articles: {}", e);
+                    warn!("Error polling GDELT: {}", e);
                 }
             }
 
-            // Wait before next fetch
-            tokio::time::sleep(tokio::time::Duration::from_secs(15 * 60)).await;
+            tokio::time::sleep(self.config.poll_interval).await;
         }
     }
 }
@@ -96,12 +333,88 @@ impl GDELTConnector {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn fixture() -> String {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/gkg_sample.csv");
+        std::fs::read_to_string(path).unwrap()
+    }
 
-    #[tokio::test]
-    async fn test_gdelt_fetch() {
-        let connector = GDELTConnector::new();
-        let articles = connector.fetch_recent_articles(24).await.unwrap();
-        assert!(!articles.is_empty());
-        println!("First article: {:?}", articles.first());
+    #[test]
+    fn test_parse_skips_bad_rows() {
+        let mut parser = GkgParser::new(vec!["ELECTION".to_string(), "ECON_".to_string()], 100);
+        let (articles, stats) = parser.parse(&fixture());
+
+        assert_eq!(
+            stats,
+            GkgStats {
+                parsed: 2,
+                duplicates: 1,
+                malformed: 3,
+                filtered: 1,
+            }
+        );
+
+        let election = &articles[0];
+        assert_eq!(election.id, "20260105121500-0");
+        assert_eq!(election.url, "https://www.reuters.com/world/us/turnout-climbs");
+        assert_eq!(election.source, "reuters.com");
+        assert_eq!(election.title, "Turnout climbs in early voting");
+        assert_eq!(election.themes, "ELECTION;TAX_FNCACT_PRESIDENT");
+        assert_eq!(election.tone, -2.5);
+        assert_eq!(election.timestamp, Utc.with_ymd_and_hms(2026, 1, 5, 12, 15, 0).unwrap());
+
+        // No V2 themes: V1 themes are used, and there is no title
+        let fed = &articles[1];
+        assert_eq!(fed.themes, "ECON_INFLATION;ECON_INTEREST_RATES");
+        assert_eq!(fed.title, "");
+        assert_eq!(fed.tone, 3.1);
+    }
+
+    #[test]
+    fn test_records_are_deduped_across_files() {
+        let mut parser = GkgParser::new(Vec::new(), 100);
+        let (first, _) = parser.parse(&fixture());
+        assert_eq!(first.len(), 3);
+
+        let (second, stats) = parser.parse(&fixture());
+        assert!(second.is_empty());
+        assert_eq!(stats.duplicates, 4);
+        assert_eq!(parser.totals().parsed, 3);
+        assert_eq!(parser.totals().malformed, 6);
+    }
+
+    #[test]
+    fn test_unzip_gkg_archive() {
+        let csv = fixture();
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(csv.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let name = b"20260105121500.gkg.csv";
+        let mut archive = Vec::new();
+        archive.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        archive.extend_from_slice(&[20, 0, 0, 0, 8, 0]); // version, flags, deflate
+        archive.extend_from_slice(&[0; 8]); // time, date, crc
+        archive.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        archive.extend_from_slice(&(csv.len() as u32).to_le_bytes());
+        archive.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        archive.extend_from_slice(&0u16.to_le_bytes());
+        archive.extend_from_slice(name);
+        archive.extend_from_slice(&compressed);
+
+        assert_eq!(unzip_first(&archive).unwrap(), csv.as_bytes());
+        assert!(unzip_first(b"not a zip").is_err());
+
+        let listing = "\
+            150383 297a16b493de7cf6ca809a7cc31d0b93 http://data.gdeltproject.org/gdeltv2/20260105121500.export.CSV.zip\n\
+            318084 bb27f78ba45f69a17ea6ed7755e9f8ff http://data.gdeltproject.org/gdeltv2/20260105121500.mentions.CSV.zip\n\
+            10768507 ea8dde0beb0ba98810a92db068c0ce99 http://data.gdeltproject.org/gdeltv2/20260105121500.gkg.csv.zip\n";
+        assert_eq!(
+            gkg_url(listing),
+            Some("http://data.gdeltproject.org/gdeltv2/20260105121500.gkg.csv.zip")
+        );
     }
 }
@@ -1,5 +1,7 @@
 pub mod polymarket;
 pub mod gdelt;
+pub mod synthetic;
 
 pub use polymarket::PolymarketConnector;
 pub use gdelt::GDELTConnector;
+pub use synthetic::{SyntheticConnector, SyntheticScenario};
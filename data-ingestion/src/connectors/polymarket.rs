@@ -2,25 +2,35 @@ use anyhow::{anyhow, Result};
 use chrono::{DateTime, TimeZone, Utc};
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex, MutexGuard};
 use tokio::time::{interval, sleep};
 use tokio_tungstenite::tungstenite::Message;
 use tracing::{debug, error, info, warn};
 
+use crate::databases::TimeSeriesDB;
 use crate::event_bus::KafkaProducer;
-use common::{Market, MarketEvent, Order, OrderBook, OrderSide, Outcome, PriceTick, Trade};
+use crate::order_book::{DiffOutcome, LevelUpdate, OrderBookManager};
+use common::{
+    AssetId, ConditionId, Market, MarketEvent, MarketId, OrderSide, Outcome, OutcomeId,
+    PriceTick, Trade,
+};
 
 /// Polymarket CLOB WebSocket connector
 pub struct PolymarketConnector {
     ws_url: String,
-    asset_ids: Vec<String>,
+    asset_ids: Vec<AssetId>,
     reconnect_delay: u64,
     heartbeat_interval_secs: u64,
+    /// Live books built from snapshots and price changes
+    books: Arc<Mutex<OrderBookManager>>,
+    /// Ticks, trades and books are also persisted here when set
+    store: Option<Arc<TimeSeriesDB>>,
 }
 
 /// Subscription message for market channel
 #[derive(Debug, Serialize)]
 struct MarketSubscription {
-    assets_ids: Vec<String>,
+    assets_ids: Vec<AssetId>,
     #[serde(rename = "type")]
     msg_type: String,
     custom_feature_enabled: bool,
@@ -29,7 +39,7 @@ struct MarketSubscription {
 /// Dynamic subscription message
 #[derive(Debug, Serialize)]
 struct DynamicSubscription {
-    assets_ids: Vec<String>,
+    assets_ids: Vec<AssetId>,
     operation: String,
 }
 
@@ -55,7 +65,7 @@ enum WsMessageContent {
 /// Full orderbook snapshot
 #[derive(Debug, Deserialize)]
 struct BookMessage {
-    asset_id: String,
+    asset_id: AssetId,
     bids: Vec<OrderLevel>,
     asks: Vec<OrderLevel>,
     #[serde(default)]
@@ -65,23 +75,35 @@ struct BookMessage {
 /// Price level updates
 #[derive(Debug, Deserialize)]
 struct PriceChangeMessage {
-    asset_id: String,
+    asset_id: AssetId,
+    #[serde(default)]
     price: f64,
+    /// Levels that changed; size 0 removes a level
+    #[serde(default)]
+    changes: Vec<LevelChange>,
     #[serde(default)]
     timestamp: i64,
 }
 
+#[derive(Debug, Deserialize)]
+struct LevelChange {
+    price: f64,
+    size: f64,
+    /// "BUY" for a bid, "SELL" for an ask
+    side: String,
+}
+
 /// Tick size changes
 #[derive(Debug, Deserialize)]
 struct TickSizeChangeMessage {
-    asset_id: String,
+    asset_id: AssetId,
     tick_size: f64,
 }
 
 /// Trade executions
 #[derive(Debug, Deserialize)]
 struct LastTradePriceMessage {
-    asset_id: String,
+    asset_id: AssetId,
     price: f64,
     size: f64,
     side: String,
@@ -92,7 +114,7 @@ struct LastTradePriceMessage {
 /// Best prices update
 #[derive(Debug, Deserialize)]
 struct BestBidAskMessage {
-    asset_id: String,
+    asset_id: AssetId,
     best_bid: Option<PriceLevel>,
     best_ask: Option<PriceLevel>,
 }
@@ -106,17 +128,22 @@ struct PriceLevel {
 /// New market created
 #[derive(Debug, Deserialize)]
 struct NewMarketMessage {
-    condition_id: String,
+    condition_id: ConditionId,
     question: String,
     description: String,
     outcomes: Vec<OutcomeData>,
     #[serde(default)]
     created_at: Option<i64>,
+    /// Set on members of a negRisk group, shared by all of them
+    #[serde(default, alias = "negRiskMarketID")]
+    neg_risk_market_id: Option<String>,
+    #[serde(default, alias = "minimum_tick_size")]
+    tick_size: Option<f64>,
 }
 
 #[derive(Debug, Deserialize)]
 struct OutcomeData {
-    id: String,
+    id: OutcomeId,
     name: String,
     #[serde(default)]
     price: Option<f64>,
@@ -127,8 +154,8 @@ struct OutcomeData {
 /// Market resolution
 #[derive(Debug, Deserialize)]
 struct MarketResolvedMessage {
-    condition_id: String,
-    winning_outcome_id: String,
+    condition_id: ConditionId,
+    winning_outcome_id: OutcomeId,
 }
 
 /// Order level for orderbook
@@ -146,35 +173,62 @@ impl PolymarketConnector {
             asset_ids: Vec::new(),
             reconnect_delay: 5,
             heartbeat_interval_secs: 10,
+            books: Arc::new(Mutex::new(OrderBookManager::new())),
+            store: None,
         }
     }
 
+    /// Persist everything published to Kafka in TimescaleDB as well
+    pub fn with_store(mut self, store: Arc<TimeSeriesDB>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
     pub fn ws_url(&self) -> &str {
         &self.ws_url
     }
 
-    pub fn with_assets(asset_ids: Vec<String>) -> Self {
+    /// The live books, for in-process consumers (best bid/ask, depth)
+    pub fn order_books(&self) -> Arc<Mutex<OrderBookManager>> {
+        self.books.clone()
+    }
+
+    fn books(&self) -> MutexGuard<'_, OrderBookManager> {
+        self.books.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Subscribe to the given assets; plain token id strings are accepted too
+    pub fn with_assets<I>(asset_ids: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<AssetId>,
+    {
         Self {
             ws_url: "wss://ws-subscriptions-clob.polymarket.com/ws/market".to_string(),
-            asset_ids,
+            asset_ids: asset_ids.into_iter().map(Into::into).collect(),
             reconnect_delay: 5,
             heartbeat_interval_secs: 10,
+            books: Arc::new(Mutex::new(OrderBookManager::new())),
+            store: None,
         }
     }
 
     pub async fn run(producer: &KafkaProducer) -> Result<()> {
-        let connector = Self::new();
+        Self::new().run_forever(producer).await
+    }
 
+    /// Stay connected, reconnecting after failures
+    pub async fn run_forever(&self, producer: &KafkaProducer) -> Result<()> {
         // Main reconnection loop
         loop {
-            match connector.connect_and_run(producer).await {
+            match self.connect_and_run(producer).await {
                 Ok(_) => {
                     info!("Polymarket WebSocket connection closed normally");
                 }
                 Err(e) => {
                     error!("Polymarket WebSocket connection failed: {}", e);
-                    info!("Reconnecting in {} seconds...", connector.reconnect_delay);
-                    sleep(tokio::time::Duration::from_secs(connector.reconnect_delay)).await;
+                    info!("Reconnecting in {} seconds...", self.reconnect_delay);
+                    sleep(tokio::time::Duration::from_secs(self.reconnect_delay)).await;
                 }
             }
         }
@@ -190,6 +244,9 @@ impl PolymarketConnector {
 
         let (mut write, mut read) = ws_stream.split();
 
+        // Updates were missed while disconnected
+        self.books().invalidate_all();
+
         // Send subscription message immediately
         let subscribe_msg = MarketSubscription {
             assets_ids: self.asset_ids.clone(),
@@ -216,6 +273,16 @@ impl PolymarketConnector {
                                     if let Err(e) = self.handle_text_message(&text, producer).await {
                                         error!("Failed to handle text message: {}", e);
                                     }
+
+                                    // Subscribing again makes the feed send a fresh book
+                                    let stale = self.books().take_snapshot_requests();
+                                    if !stale.is_empty() {
+                                        let resubscribe = DynamicSubscription {
+                                            assets_ids: stale,
+                                            operation: "subscribe".to_string(),
+                                        };
+                                        write.send(Message::Text(serde_json::to_string(&resubscribe)?)).await?;
+                                    }
                                 }
                                 Message::Pong(_) => {
                                     debug!("Received PONG");
@@ -311,37 +378,17 @@ impl PolymarketConnector {
             Utc::now()
         };
 
-        // Convert asset_id to UUID (simplified - in production you'd map asset IDs to market IDs)
-        let market_id = uuid::Uuid::new_v4();
-
-        let bids: Vec<Order> = msg.bids
-            .into_iter()
-            .map(|level| Order {
-                outcome_id: msg.asset_id.clone(),
-                price: level.price,
-                size: level.size,
-            })
-            .collect();
-
-        let asks: Vec<Order> = msg.asks
-            .into_iter()
-            .map(|level| Order {
-                outcome_id: msg.asset_id.clone(),
-                price: level.price,
-                size: level.size,
-            })
-            .collect();
-
-        let orderbook = OrderBook {
-            market_id,
-            timestamp,
-            bids,
-            asks,
+        let levels = |levels: &[OrderLevel]| -> Vec<(f64, f64)> {
+            levels.iter().map(|level| (level.price, level.size)).collect()
         };
+        let orderbook = self.books().apply_snapshot(&msg.asset_id, &levels(&msg.bids), &levels(&msg.asks), timestamp);
 
-        debug!("Orderbook update for {}: {} bids, {} asks",
-            msg.asset_id, orderbook.bids.len(), orderbook.asks.len());
+        debug!("Orderbook snapshot for {}: {} bids, {} asks (seq {})",
+            msg.asset_id, orderbook.bids.len(), orderbook.asks.len(), orderbook.sequence);
 
+        if let Some(store) = &self.store {
+            store.write_orderbook_snapshots(std::slice::from_ref(&orderbook)).await?;
+        }
         producer.publish("order-book-updates", &MarketEvent::OrderBookUpdate(orderbook)).await?;
         Ok(())
     }
@@ -353,10 +400,38 @@ impl PolymarketConnector {
             Utc::now()
         };
 
-        let market_id = uuid::Uuid::new_v4();
+        if !msg.changes.is_empty() {
+            let updates: Vec<LevelUpdate> = msg.changes
+                .iter()
+                .map(|change| LevelUpdate {
+                    side: if change.side.eq_ignore_ascii_case("SELL") { OrderSide::Sell } else { OrderSide::Buy },
+                    price: change.price,
+                    size: change.size,
+                })
+                .collect();
+
+            let outcome = self.books().apply_diff(&msg.asset_id, &updates, timestamp);
+            match outcome {
+                DiffOutcome::Applied(orderbook) => {
+                    debug!("Orderbook diff for {}: {} levels (seq {})",
+                        msg.asset_id, updates.len(), orderbook.sequence);
+                    if let Some(store) = &self.store {
+                        store.write_orderbook_snapshots(std::slice::from_ref(&orderbook)).await?;
+                    }
+                    producer.publish("order-book-updates", &MarketEvent::OrderBookUpdate(orderbook)).await?;
+                }
+                DiffOutcome::Stale => debug!("Stale price change for {}", msg.asset_id),
+                DiffOutcome::NeedsSnapshot => debug!("Dropped price change for {} awaiting a snapshot", msg.asset_id),
+            }
+        }
+
+        if msg.price <= 0.0 {
+            return Ok(());
+        }
+        let market_id = MarketId::random();
         let price_tick = PriceTick {
             market_id,
-            outcome_id: msg.asset_id.clone(),
+            outcome_id: msg.asset_id.to_outcome_id(),
             price: msg.price,
             volume_24h: 0.0, // Not provided in price_change message
             liquidity: 0.0,   // Not provided in price_change message
@@ -364,6 +439,9 @@ impl PolymarketConnector {
         };
 
         debug!("Price change for {}: {}", msg.asset_id, msg.price);
+        if let Some(store) = &self.store {
+            store.write_price_ticks(std::slice::from_ref(&price_tick)).await?;
+        }
         producer.publish("price-ticks", &MarketEvent::PriceTick(price_tick)).await?;
         Ok(())
     }
@@ -375,7 +453,7 @@ impl PolymarketConnector {
             Utc::now()
         };
 
-        let market_id = uuid::Uuid::new_v4();
+        let market_id = MarketId::random();
         let side = match msg.side.to_uppercase().as_str() {
             "BUY" => OrderSide::Buy,
             "SELL" => OrderSide::Sell,
@@ -385,7 +463,7 @@ impl PolymarketConnector {
         let trade = Trade {
             id: uuid::Uuid::new_v4(),
             market_id,
-            outcome_id: msg.asset_id.clone(),
+            outcome_id: msg.asset_id.to_outcome_id(),
             price: msg.price,
             size: msg.size,
             side,
@@ -394,37 +472,46 @@ impl PolymarketConnector {
 
         debug!("Trade for {}: {} {} @ {}",
             msg.asset_id, msg.side, msg.size, msg.price);
+        if let Some(store) = &self.store {
+            store.write_trades(std::slice::from_ref(&trade)).await?;
+        }
         producer.publish("trades", &MarketEvent::Trade(trade)).await?;
         Ok(())
     }
 
     async fn handle_best_bid_ask(&self, msg: BestBidAskMessage, producer: &KafkaProducer) -> Result<()> {
         let timestamp = Utc::now();
-        let market_id = uuid::Uuid::new_v4();
-        let asset_id = msg.asset_id.clone();
+        let market_id = MarketId::random();
+        let outcome_id = msg.asset_id.to_outcome_id();
 
         // Publish as both bid and ask price ticks
         if let Some(bid) = msg.best_bid.clone() {
             let price_tick = PriceTick {
                 market_id,
-                outcome_id: asset_id.clone(),
+                outcome_id: outcome_id.clone(),
                 price: bid.price,
                 volume_24h: 0.0,
                 liquidity: bid.size,
                 timestamp,
             };
+            if let Some(store) = &self.store {
+                store.write_price_ticks(std::slice::from_ref(&price_tick)).await?;
+            }
             producer.publish("price-ticks", &MarketEvent::PriceTick(price_tick)).await?;
         }
 
         if let Some(ask) = msg.best_ask.clone() {
             let price_tick = PriceTick {
                 market_id,
-                outcome_id: asset_id,
+                outcome_id,
                 price: ask.price,
                 volume_24h: 0.0,
                 liquidity: ask.size,
                 timestamp,
             };
+            if let Some(store) = &self.store {
+                store.write_price_ticks(std::slice::from_ref(&price_tick)).await?;
+            }
             producer.publish("price-ticks", &MarketEvent::PriceTick(price_tick)).await?;
         }
 
@@ -449,7 +536,7 @@ impl PolymarketConnector {
             .collect();
 
         let market = Market {
-            id: uuid::Uuid::new_v4(),
+            id: MarketId::random(),
             condition_id: msg.condition_id.clone(),
             question: msg.question.clone(),
             description: msg.description,
@@ -457,6 +544,8 @@ impl PolymarketConnector {
             outcomes,
             created_at,
             updated_at: created_at,
+            neg_risk_group: msg.neg_risk_market_id.filter(|id| !id.is_empty()),
+            tick_size: msg.tick_size,
         };
 
         info!("New market created: {}", msg.question);
@@ -465,12 +554,10 @@ impl PolymarketConnector {
     }
 
     async fn handle_market_resolved(&self, msg: MarketResolvedMessage, producer: &KafkaProducer) -> Result<()> {
-        // Simplified - in production you'd map condition_id to UUID
-        let market_id = uuid::Uuid::new_v4();
-
+        // Keyed by condition; the resolution router maps it onto our market
         info!("Market resolved: {} -> {}", msg.condition_id, msg.winning_outcome_id);
-        producer.publish("market-events", &MarketEvent::MarketResolved {
-            market_id,
+        producer.publish("market-events", &MarketEvent::ConditionResolved {
+            condition_id: msg.condition_id,
             outcome_id: msg.winning_outcome_id,
         }).await?;
         Ok(())
@@ -538,6 +625,23 @@ mod tests {
         match ws_msg.content {
             WsMessageContent::PriceChange(msg) => {
                 assert_eq!(msg.price, 0.58);
+                assert!(msg.changes.is_empty());
+            }
+            _ => panic!("Expected PriceChange message"),
+        }
+
+        let json = r#"{
+            "type": "price_change",
+            "asset_id": "21742633143463906290569050155826241533067272736897614950488156847949938836455",
+            "changes": [{"price": 0.57, "size": 0, "side": "BUY"}, {"price": 0.60, "size": 250, "side": "SELL"}],
+            "timestamp": 1708627200000
+        }"#;
+        let ws_msg: WsMessage = serde_json::from_str(json).unwrap();
+        match ws_msg.content {
+            WsMessageContent::PriceChange(msg) => {
+                assert_eq!(msg.changes.len(), 2);
+                assert_eq!(msg.changes[1].side, "SELL");
+                assert_eq!(msg.changes[0].size, 0.0);
             }
             _ => panic!("Expected PriceChange message"),
         }
@@ -563,4 +667,42 @@ mod tests {
             _ => panic!("Expected BestBidAsk message"),
         }
     }
+
+    #[test]
+    fn test_parse_neg_risk_new_market() {
+        let json = r#"{
+            "type": "new_market",
+            "condition_id": "0x5f65177b394277fd294cd75650044e32ba009a95022d88a0c1d565897d72f8f1",
+            "question": "Will Gavin Newsom win the 2028 Democratic nomination?",
+            "description": "",
+            "outcomes": [{"id": "1", "name": "Yes"}, {"id": "2", "name": "No"}],
+            "negRiskMarketID": "0x2c1f5cbeb3c1e5cd1b2dbd6d5d5bde1f0e0ce3d0ef9b1bd435fdcfa0e9b2c800",
+            "minimum_tick_size": 0.001
+        }"#;
+
+        let ws_msg: WsMessage = serde_json::from_str(json).unwrap();
+        match ws_msg.content {
+            WsMessageContent::NewMarket(msg) => {
+                assert_eq!(
+                    msg.neg_risk_market_id.as_deref(),
+                    Some("0x2c1f5cbeb3c1e5cd1b2dbd6d5d5bde1f0e0ce3d0ef9b1bd435fdcfa0e9b2c800")
+                );
+                assert_eq!(msg.tick_size, Some(0.001));
+            }
+            _ => panic!("Expected NewMarket message"),
+        }
+    }
+
+    #[test]
+    fn test_subscription_sends_plain_asset_ids() {
+        let connector = PolymarketConnector::with_assets(vec!["2174263314346390629056905015582624".to_string()]);
+        let subscription = MarketSubscription {
+            assets_ids: connector.asset_ids.clone(),
+            msg_type: "market".to_string(),
+            custom_feature_enabled: false,
+        };
+
+        let value = serde_json::to_value(&subscription).unwrap();
+        assert_eq!(value["assets_ids"], serde_json::json!(["2174263314346390629056905015582624"]));
+    }
 }
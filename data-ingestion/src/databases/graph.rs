@@ -1,94 +1,731 @@
-use anyhow::Result;
-use neo4rs::{Graph, query};
-use tracing::info;
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Duration, Utc};
+use common::MarketId;
+use rust_decimal::Decimal;
+use signal_generation::{CorrelationEdge, CorrelationType};
+use sqlx::postgres::PgPool;
+use sqlx::Row;
+use std::collections::{HashMap, HashSet, VecDeque};
+use tracing::{debug, info};
 
-/// Graph database for people, organizations, and event dependencies
-/// Uses Neo4j for relationship mapping
+/// How two markets are related
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum EdgeType {
+    /// Two markets on the same question, which resolve the same way
+    SameEvent,
+    /// `from_market` resolving YES probably means `to_market` does too
+    ImpliesCandidate,
+    /// At most one of the two probably resolves YES
+    MutuallyExclusiveCandidate,
+}
+
+impl EdgeType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EdgeType::SameEvent => "same_event",
+            EdgeType::ImpliesCandidate => "implies_candidate",
+            EdgeType::MutuallyExclusiveCandidate => "mutually_exclusive_candidate",
+        }
+    }
+
+    fn parse(name: &str) -> Result<Self> {
+        match name {
+            "same_event" => Ok(EdgeType::SameEvent),
+            "implies_candidate" => Ok(EdgeType::ImpliesCandidate),
+            "mutually_exclusive_candidate" => Ok(EdgeType::MutuallyExclusiveCandidate),
+            other => Err(anyhow!("Unknown edge type {}", other)),
+        }
+    }
+
+    /// Whether swapping the ends changes the meaning
+    fn is_directed(&self) -> bool {
+        matches!(self, EdgeType::ImpliesCandidate)
+    }
+
+    fn correlation_type(&self) -> CorrelationType {
+        match self {
+            EdgeType::SameEvent => CorrelationType::SameOutcome,
+            EdgeType::ImpliesCandidate => CorrelationType::Implies,
+            EdgeType::MutuallyExclusiveCandidate => CorrelationType::MutuallyExclusive,
+        }
+    }
+}
+
+/// A market in the graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketNode {
+    pub market_id: MarketId,
+    pub question: String,
+    pub category: Option<String>,
+    /// Id of the question's vector in the vector store
+    pub embedding_id: Option<String>,
+    pub resolution_date: Option<DateTime<Utc>>,
+}
+
+impl MarketNode {
+    pub fn new(market_id: MarketId, question: impl Into<String>) -> Self {
+        Self {
+            market_id,
+            question: question.into(),
+            category: None,
+            embedding_id: None,
+            resolution_date: None,
+        }
+    }
+
+    pub fn with_category(mut self, category: impl Into<String>) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+
+    pub fn with_embedding_id(mut self, embedding_id: impl Into<String>) -> Self {
+        self.embedding_id = Some(embedding_id.into());
+        self
+    }
+
+    pub fn with_resolution_date(mut self, resolution_date: DateTime<Utc>) -> Self {
+        self.resolution_date = Some(resolution_date);
+        self
+    }
+}
+
+/// A typed relationship, with the evidence behind it
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketEdge {
+    pub from_market: MarketId,
+    pub to_market: MarketId,
+    pub edge_type: EdgeType,
+    /// 0-1
+    pub confidence: f64,
+    /// Similarity of the two questions, 0-1
+    pub text_similarity: f64,
+    /// Correlation of the two price series, when both are long enough
+    pub price_correlation: Option<f64>,
+}
+
+impl MarketEdge {
+    fn key(&self) -> EdgeKey {
+        (self.from_market, self.to_market, self.edge_type)
+    }
+}
+
+type EdgeKey = (MarketId, MarketId, EdgeType);
+
+/// Thresholds for discovering edges
+#[derive(Debug, Clone)]
+pub struct GraphConfig {
+    /// Question similarity below which two markets are never linked
+    pub min_text_similarity: f64,
+    /// Question similarity at which two markets are taken as the same event
+    pub same_event_similarity: f64,
+    /// Price correlation, either sign, that confirms or decides an edge
+    pub min_price_correlation: f64,
+    /// Prices both markets need before their correlation counts
+    pub min_observations: usize,
+    /// Prices kept per market
+    pub price_window: usize,
+    /// Spread put on exported `CorrelationEdge`s
+    pub min_spread: Decimal,
+}
+
+impl Default for GraphConfig {
+    fn default() -> Self {
+        Self {
+            min_text_similarity: 0.3,
+            same_event_similarity: 0.7,
+            min_price_correlation: 0.7,
+            min_observations: 10,
+            price_window: 100,
+            min_spread: Decimal::new(2, 2),
+        }
+    }
+}
+
+/// Words too common to say what a question is about
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "be", "by", "for", "in", "is", "of", "on", "or", "the", "to", "will",
+];
+
+fn question_terms(question: &str) -> HashSet<String> {
+    question
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty() && !STOPWORDS.contains(word))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Shared terms over all terms
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let denominator = norm(a) * norm(b);
+    if denominator == 0.0 {
+        0.0
+    } else {
+        (dot / denominator) as f64
+    }
+}
+
+/// Pearson correlation of the most recent prices both series have
+fn correlation(a: &VecDeque<f64>, b: &VecDeque<f64>) -> Option<f64> {
+    let n = a.len().min(b.len());
+    let a: Vec<f64> = a.iter().skip(a.len() - n).copied().collect();
+    let b: Vec<f64> = b.iter().skip(b.len() - n).copied().collect();
+    let mean_a = a.iter().sum::<f64>() / n as f64;
+    let mean_b = b.iter().sum::<f64>() / n as f64;
+
+    let mut covariance = 0.0;
+    let mut variance_a = 0.0;
+    let mut variance_b = 0.0;
+    for (x, y) in a.iter().zip(&b) {
+        covariance += (x - mean_a) * (y - mean_b);
+        variance_a += (x - mean_a) * (x - mean_a);
+        variance_b += (y - mean_b) * (y - mean_b);
+    }
+
+    let denominator = (variance_a * variance_b).sqrt();
+    if denominator == 0.0 {
+        return None;
+    }
+    Some(covariance / denominator)
+}
+
+/// Market relationship graph for discovering correlation edges
+/// Stored as adjacency tables in PostgreSQL
+///
+/// Markets are nodes; typed, scored edges between them come from
+/// `upsert_edge` or from `discover_edges`, which links markets whose
+/// questions overlap and uses their price histories to confirm the link or
+/// decide its type. `export_correlation_edges` hands the result to
+/// `CorrelationGenerator`. The graph is held in memory and written through
+/// to the database when there is one; `load` reads it back.
 pub struct GraphDB {
-    graph: Graph,
+    pool: Option<PgPool>,
+    config: GraphConfig,
+    nodes: HashMap<MarketId, MarketNode>,
+    terms: HashMap<MarketId, HashSet<String>>,
+    edges: HashMap<EdgeKey, MarketEdge>,
+    /// Edge keys touching each market
+    adjacency: HashMap<MarketId, HashSet<EdgeKey>>,
+    prices: HashMap<MarketId, VecDeque<f64>>,
+    /// Question embeddings, by market; not persisted
+    embeddings: HashMap<MarketId, Vec<f32>>,
 }
 
 impl GraphDB {
-    pub fn new(uri: &str, user: &str, password: &str) -> Result<Self> {
-        let graph = Graph::new(uri, user, password)?;
+    /// A graph that lives only in memory
+    pub fn new(config: GraphConfig) -> Self {
+        Self {
+            pool: None,
+            config,
+            nodes: HashMap::new(),
+            terms: HashMap::new(),
+            edges: HashMap::new(),
+            adjacency: HashMap::new(),
+            prices: HashMap::new(),
+            embeddings: HashMap::new(),
+        }
+    }
 
-        info!("✅ Connected to Neo4j graph database");
+    pub async fn connect(url: &str, config: GraphConfig) -> Result<Self> {
+        let pool = PgPool::connect(url).await?;
 
-        Ok(Self { graph })
-    }
+        info!("✅ Connected to graph database");
 
-    pub async fn init_schema(&self) -> Result<()> {
-        // Create indexes for better query performance
-        self.graph
-            .execute(query(
-                "CREATE INDEX person_name IF NOT EXISTS FOR (p:Person) ON (p.name)",
-            ))
-            .await?;
+        Ok(Self::from_pool(pool, config))
+    }
 
-        self.graph
-            .execute(query(
-                "CREATE INDEX org_name IF NOT EXISTS FOR (o:Organization) ON (o.name)",
-            ))
-            .await?;
+    /// Write through an existing pool
+    pub fn from_pool(pool: PgPool, config: GraphConfig) -> Self {
+        Self {
+            pool: Some(pool),
+            ..Self::new(config)
+        }
+    }
 
-        self.graph
-            .execute(query(
-                "CREATE INDEX market_id IF NOT EXISTS FOR (m:Market) ON (m.id)",
-            ))
-            .await?;
+    /// Create the node and edge tables
+    pub async fn initialize(&self) -> Result<()> {
+        let Some(pool) = &self.pool else {
+            return Ok(());
+        };
+        let statements = [
+            "CREATE TABLE IF NOT EXISTS market_nodes (
+                market_id UUID PRIMARY KEY,
+                question TEXT NOT NULL,
+                category TEXT,
+                embedding_id TEXT,
+                resolution_date TIMESTAMPTZ
+            )",
+            "CREATE TABLE IF NOT EXISTS market_edges (
+                from_market UUID NOT NULL REFERENCES market_nodes (market_id),
+                to_market UUID NOT NULL REFERENCES market_nodes (market_id),
+                edge_type TEXT NOT NULL,
+                confidence DOUBLE PRECISION NOT NULL,
+                text_similarity DOUBLE PRECISION NOT NULL,
+                price_correlation DOUBLE PRECISION,
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                PRIMARY KEY (from_market, to_market, edge_type)
+            )",
+            "CREATE INDEX IF NOT EXISTS market_edges_to_market ON market_edges (to_market)",
+        ];
+        for statement in statements {
+            sqlx::query(statement).execute(pool).await?;
+        }
 
         info!("✅ Initialized graph database schema");
 
         Ok(())
     }
 
-    pub async fn add_person(&self, name: &str, source: &str) -> Result<()> {
-        self.graph
-            .execute(
-                query("MERGE (p:Person {name: $name}) SET p.source = $source")
-                    .param("name", name)
-                    .param("source", source),
-            )
-            .await?;
+    /// Replace the in-memory graph with what is stored
+    pub async fn load(&mut self) -> Result<()> {
+        let Some(pool) = self.pool.clone() else {
+            return Ok(());
+        };
+        let nodes = sqlx::query(
+            "SELECT market_id, question, category, embedding_id, resolution_date FROM market_nodes",
+        )
+        .fetch_all(&pool)
+        .await?;
+        let edges = sqlx::query(
+            "SELECT from_market, to_market, edge_type, confidence, text_similarity, price_correlation FROM market_edges",
+        )
+        .fetch_all(&pool)
+        .await?;
+
+        self.nodes.clear();
+        self.terms.clear();
+        self.edges.clear();
+        self.adjacency.clear();
+        for row in nodes {
+            self.insert_node(MarketNode {
+                market_id: row.try_get("market_id")?,
+                question: row.try_get("question")?,
+                category: row.try_get("category")?,
+                embedding_id: row.try_get("embedding_id")?,
+                resolution_date: row.try_get("resolution_date")?,
+            });
+        }
+        for row in edges {
+            self.insert_edge(MarketEdge {
+                from_market: row.try_get("from_market")?,
+                to_market: row.try_get("to_market")?,
+                edge_type: EdgeType::parse(row.try_get("edge_type")?)?,
+                confidence: row.try_get("confidence")?,
+                text_similarity: row.try_get("text_similarity")?,
+                price_correlation: row.try_get("price_correlation")?,
+            });
+        }
+
+        info!("Loaded {} markets and {} edges", self.nodes.len(), self.edges.len());
 
         Ok(())
     }
 
-    pub async fn add_organization(&self, name: &str, source: &str) -> Result<()> {
-        self.graph
-            .execute(
-                query(
-                    "MERGE (o:Organization {name: $name}) SET o.source = $source",
-                )
-                .param("name", name)
-                .param("source", source),
+    /// Add a market, or update one already in the graph
+    pub async fn add_market(&mut self, node: MarketNode) -> Result<()> {
+        if let Some(pool) = &self.pool {
+            sqlx::query(
+                "INSERT INTO market_nodes (market_id, question, category, embedding_id, resolution_date)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (market_id) DO UPDATE SET
+                    question = EXCLUDED.question,
+                    category = EXCLUDED.category,
+                    embedding_id = EXCLUDED.embedding_id,
+                    resolution_date = EXCLUDED.resolution_date",
             )
+            .bind(node.market_id)
+            .bind(&node.question)
+            .bind(&node.category)
+            .bind(&node.embedding_id)
+            .bind(node.resolution_date)
+            .execute(pool)
             .await?;
-
+        }
+        self.insert_node(node);
         Ok(())
     }
 
-    pub async fn link_person_to_market(
-        &self,
-        person: &str,
-        market_id: &str,
-        relation: &str,
-    ) -> Result<()> {
-        self.graph
-            .execute(
-                query(
-                    "
-                MATCH (p:Person {name: $person})
-                MATCH (m:Market {id: $market_id})
-                MERGE (p)-[r:RELATED {type: $relation}]->(m)
-                ",
-                )
-                .param("person", person)
-                .param("market_id", market_id)
-                .param("relation", relation),
+    /// Add an edge between two markets in the graph, replacing one of the
+    /// same type between them; undirected edges match either way round
+    pub async fn upsert_edge(&mut self, edge: MarketEdge) -> Result<()> {
+        for market_id in [edge.from_market, edge.to_market] {
+            if !self.nodes.contains_key(&market_id) {
+                bail!("Market {} is not in the graph", market_id);
+            }
+        }
+        if edge.from_market == edge.to_market {
+            bail!("Edge from market {} to itself", edge.from_market);
+        }
+        let mut edge = edge;
+        if !edge.edge_type.is_directed() && edge.from_market > edge.to_market {
+            std::mem::swap(&mut edge.from_market, &mut edge.to_market);
+        }
+
+        if let Some(pool) = &self.pool {
+            sqlx::query(
+                "INSERT INTO market_edges
+                    (from_market, to_market, edge_type, confidence, text_similarity, price_correlation)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (from_market, to_market, edge_type) DO UPDATE SET
+                    confidence = EXCLUDED.confidence,
+                    text_similarity = EXCLUDED.text_similarity,
+                    price_correlation = EXCLUDED.price_correlation,
+                    updated_at = now()",
             )
+            .bind(edge.from_market)
+            .bind(edge.to_market)
+            .bind(edge.edge_type.as_str())
+            .bind(edge.confidence)
+            .bind(edge.text_similarity)
+            .bind(edge.price_correlation)
+            .execute(pool)
             .await?;
-
+        }
+        self.insert_edge(edge);
         Ok(())
     }
+
+    /// Record a market's latest YES price
+    pub fn record_price(&mut self, market_id: MarketId, price: f64) {
+        let history = self.prices.entry(market_id).or_default();
+        history.push_back(price);
+        while history.len() > self.config.price_window {
+            history.pop_front();
+        }
+    }
+
+    /// Compare questions by embedding as well as by shared terms
+    pub fn set_embedding(&mut self, market_id: MarketId, embedding: Vec<f32>) {
+        self.embeddings.insert(market_id, embedding);
+    }
+
+    pub fn market(&self, market_id: MarketId) -> Option<&MarketNode> {
+        self.nodes.get(&market_id)
+    }
+
+    /// Edges touching a market, most confident first
+    pub fn neighbors(&self, market_id: MarketId, edge_type: Option<EdgeType>, min_confidence: f64) -> Vec<&MarketEdge> {
+        let mut neighbors: Vec<&MarketEdge> = self
+            .adjacency
+            .get(&market_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|key| self.edges.get(key))
+            .filter(|edge| edge_type.is_none_or(|edge_type| edge.edge_type == edge_type))
+            .filter(|edge| edge.confidence >= min_confidence)
+            .collect();
+        neighbors.sort_by(|a, b| b.confidence.total_cmp(&a.confidence).then(a.key().cmp(&b.key())));
+        neighbors
+    }
+
+    /// Edges at or above `min_confidence`, as `CorrelationGenerator` takes them
+    pub fn export_correlation_edges(&self, min_confidence: f64) -> Vec<CorrelationEdge> {
+        let mut edges: Vec<&MarketEdge> =
+            self.edges.values().filter(|edge| edge.confidence >= min_confidence).collect();
+        edges.sort_by(|a, b| b.confidence.total_cmp(&a.confidence).then(a.key().cmp(&b.key())));
+        edges
+            .into_iter()
+            .map(|edge| CorrelationEdge {
+                from_market: edge.from_market,
+                to_market: edge.to_market,
+                correlation_type: edge.edge_type.correlation_type(),
+                min_spread: self.config.min_spread,
+            })
+            .collect()
+    }
+
+    /// Compare every pair of markets and upsert the edges found
+    ///
+    /// Edges found earlier that no longer qualify are kept; hand-added edges
+    /// of a different type are left alone.
+    pub async fn discover_edges(&mut self) -> Result<Vec<MarketEdge>> {
+        let mut ids: Vec<MarketId> = self.nodes.keys().copied().collect();
+        ids.sort();
+
+        let mut discovered = Vec::new();
+        for (i, a) in ids.iter().enumerate() {
+            for b in &ids[i + 1..] {
+                if let Some(edge) = self.classify(*a, *b) {
+                    discovered.push(edge);
+                }
+            }
+        }
+
+        for edge in &discovered {
+            self.upsert_edge(edge.clone()).await?;
+        }
+        debug!("Discovered {} edges between {} markets", discovered.len(), ids.len());
+        Ok(discovered)
+    }
+
+    /// The edge two markets' questions and prices point to, if any
+    ///
+    /// Questions naming the same thing but one different subject ("Will X
+    /// win ..." against "Will Y win ...") are exclusive candidates, unless
+    /// their prices move together, which makes the cheaper one imply the
+    /// other. Near-identical questions resolving together are the same event.
+    /// Otherwise only the price correlation's sign decides. Confidence is
+    /// the question similarity averaged with the correlation when prices
+    /// agree, halved when they have history but don't.
+    fn classify(&self, a: MarketId, b: MarketId) -> Option<MarketEdge> {
+        let (node_a, node_b) = (&self.nodes[&a], &self.nodes[&b]);
+        if let (Some(category_a), Some(category_b)) = (&node_a.category, &node_b.category) {
+            if category_a != category_b {
+                return None;
+            }
+        }
+
+        let (terms_a, terms_b) = (&self.terms[&a], &self.terms[&b]);
+        let mut text_similarity = jaccard(terms_a, terms_b);
+        if let (Some(embedding_a), Some(embedding_b)) = (self.embeddings.get(&a), self.embeddings.get(&b)) {
+            text_similarity = text_similarity.max(cosine_similarity(embedding_a, embedding_b));
+        }
+        if text_similarity < self.config.min_text_similarity {
+            return None;
+        }
+
+        let price_correlation = match (self.prices.get(&a), self.prices.get(&b)) {
+            (Some(prices_a), Some(prices_b))
+                if prices_a.len() >= self.config.min_observations && prices_b.len() >= self.config.min_observations =>
+            {
+                correlation(prices_a, prices_b)
+            }
+            _ => None,
+        };
+        let correlated = price_correlation.is_some_and(|c| c >= self.config.min_price_correlation);
+        let anticorrelated = price_correlation.is_some_and(|c| c <= -self.config.min_price_correlation);
+
+        let resolve_together = match (node_a.resolution_date, node_b.resolution_date) {
+            (Some(date_a), Some(date_b)) => (date_a - date_b).abs() <= Duration::days(1),
+            _ => true,
+        };
+        let one_subject_apart = terms_a.difference(terms_b).count() == 1
+            && terms_b.difference(terms_a).count() == 1
+            && terms_a.intersection(terms_b).count() >= 2;
+
+        let (edge_type, confirmed) = if one_subject_apart && resolve_together && !correlated {
+            (EdgeType::MutuallyExclusiveCandidate, anticorrelated)
+        } else if text_similarity >= self.config.same_event_similarity && resolve_together && !anticorrelated {
+            (EdgeType::SameEvent, correlated)
+        } else if anticorrelated {
+            (EdgeType::MutuallyExclusiveCandidate, true)
+        } else if correlated {
+            (EdgeType::ImpliesCandidate, true)
+        } else {
+            return None;
+        };
+
+        let confidence = match price_correlation {
+            Some(c) if confirmed => (text_similarity + c.abs()) / 2.0,
+            Some(_) => text_similarity / 2.0,
+            None => text_similarity,
+        };
+
+        // The more specific market trades lower and implies the broader one
+        let mean = |id: &MarketId| {
+            let prices = &self.prices[id];
+            prices.iter().sum::<f64>() / prices.len() as f64
+        };
+        let (from_market, to_market) = if edge_type == EdgeType::ImpliesCandidate && mean(&a) > mean(&b) {
+            (b, a)
+        } else {
+            (a, b)
+        };
+
+        Some(MarketEdge {
+            from_market,
+            to_market,
+            edge_type,
+            confidence,
+            text_similarity,
+            price_correlation,
+        })
+    }
+
+    fn insert_node(&mut self, node: MarketNode) {
+        self.terms.insert(node.market_id, question_terms(&node.question));
+        self.nodes.insert(node.market_id, node);
+    }
+
+    fn insert_edge(&mut self, edge: MarketEdge) {
+        let key = edge.key();
+        for market_id in [edge.from_market, edge.to_market] {
+            self.adjacency.entry(market_id).or_default().insert(key);
+        }
+        self.edges.insert(key, edge);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(from_market: MarketId, to_market: MarketId, edge_type: EdgeType, confidence: f64) -> MarketEdge {
+        MarketEdge {
+            from_market,
+            to_market,
+            edge_type,
+            confidence,
+            text_similarity: confidence,
+            price_correlation: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_discovers_candidate_edges_from_questions_and_prices() {
+        let mut graph = GraphDB::new(GraphConfig::default());
+        let election = Utc::now() + Duration::days(700);
+        let market = |question: &str, category: &str| {
+            let node = MarketNode::new(MarketId::random(), question)
+                .with_category(category)
+                .with_resolution_date(election);
+            (node.market_id, node)
+        };
+        let (vance, vance_node) = market("Will Vance win the 2028 presidential election?", "Politics");
+        let (newsom, newsom_node) = market("Will Newsom win the 2028 presidential election?", "Politics");
+        let (republican, republican_node) = market("Will a Republican win the 2028 presidential election?", "Politics");
+        let (candidate, candidate_node) =
+            market("Will the Republican candidate win the 2028 presidential election?", "Politics");
+        let (turnout, turnout_node) = market("Will turnout exceed 70% in the 2028 presidential election?", "Politics");
+        let (fed, fed_node) = market("Will the Fed cut rates at the 2028 election meeting?", "Economics");
+        for node in [vance_node, newsom_node, republican_node, candidate_node, turnout_node, fed_node] {
+            graph.add_market(node).await.unwrap();
+        }
+
+        for t in 0..20 {
+            let t = t as f64;
+            let wobble = if t as i64 % 2 == 0 { 0.01 } else { -0.01 };
+            graph.record_price(vance, 0.30 + 0.01 * t);
+            graph.record_price(newsom, 0.45 - 0.01 * t);
+            graph.record_price(republican, 0.50 + 0.01 * t);
+            graph.record_price(candidate, 0.50 + 0.01 * t + wobble / 10.0);
+            graph.record_price(turnout, 0.60 + wobble);
+            graph.record_price(fed, 0.30 + 0.01 * t);
+        }
+
+        let discovered = graph.discover_edges().await.unwrap();
+        let found: HashSet<(EdgeType, MarketId, MarketId)> = discovered
+            .iter()
+            .map(|edge| {
+                let (from, to) = if edge.edge_type.is_directed() || edge.from_market < edge.to_market {
+                    (edge.from_market, edge.to_market)
+                } else {
+                    (edge.to_market, edge.from_market)
+                };
+                (edge.edge_type, from, to)
+            })
+            .collect();
+        let undirected = |edge_type, a: MarketId, b: MarketId| (edge_type, a.min(b), a.max(b));
+        let expected = HashSet::from([
+            undirected(EdgeType::MutuallyExclusiveCandidate, vance, newsom),
+            undirected(EdgeType::MutuallyExclusiveCandidate, republican, newsom),
+            undirected(EdgeType::MutuallyExclusiveCandidate, candidate, newsom),
+            undirected(EdgeType::SameEvent, republican, candidate),
+            // The cheaper, more specific market implies the broader one
+            (EdgeType::ImpliesCandidate, vance, republican),
+            (EdgeType::ImpliesCandidate, vance, candidate),
+        ]);
+        assert_eq!(found, expected);
+
+        let exclusive = graph.neighbors(newsom, Some(EdgeType::MutuallyExclusiveCandidate), 0.0);
+        assert_eq!(exclusive.len(), 3);
+        assert!(exclusive.iter().all(|edge| edge.price_correlation.unwrap() < -0.9));
+        assert!(graph.neighbors(turnout, None, 0.0).is_empty());
+
+        assert_eq!(graph.export_correlation_edges(0.0).len(), 6);
+        let confident = graph.export_correlation_edges(0.9);
+        assert_eq!(confident.len(), 1);
+        assert_eq!(confident[0].correlation_type, CorrelationType::SameOutcome);
+        assert_eq!(
+            HashSet::from([confident[0].from_market, confident[0].to_market]),
+            HashSet::from([republican, candidate])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prices_that_disagree_halve_confidence() {
+        let mut graph = GraphDB::new(GraphConfig::default());
+        let (vance, newsom) = (MarketId::random(), MarketId::random());
+        graph.add_market(MarketNode::new(vance, "Will Vance win the 2028 presidential election?")).await.unwrap();
+        graph.add_market(MarketNode::new(newsom, "Will Newsom win the 2028 presidential election?")).await.unwrap();
+
+        // Questions alone: four of six terms shared
+        let discovered = graph.discover_edges().await.unwrap();
+        assert_eq!(discovered.len(), 1);
+        assert_eq!(discovered[0].edge_type, EdgeType::MutuallyExclusiveCandidate);
+        assert_eq!(discovered[0].price_correlation, None);
+        assert!((discovered[0].confidence - 4.0 / 6.0).abs() < 1e-9);
+
+        // Prices that don't move against each other
+        for t in 0..20 {
+            graph.record_price(vance, 0.30 + 0.01 * t as f64);
+            graph.record_price(newsom, if t % 2 == 0 { 0.41 } else { 0.39 });
+        }
+        graph.discover_edges().await.unwrap();
+        let neighbors = graph.neighbors(vance, None, 0.0);
+        assert_eq!(neighbors.len(), 1);
+        assert_eq!(neighbors[0].edge_type, EdgeType::MutuallyExclusiveCandidate);
+        assert!((neighbors[0].confidence - 2.0 / 6.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_upsert_replaces_undirected_edges_either_way_round() {
+        let mut graph = GraphDB::new(GraphConfig::default());
+        let (a, b) = (MarketId::random(), MarketId::random());
+        graph.add_market(MarketNode::new(a, "A")).await.unwrap();
+        graph.add_market(MarketNode::new(b, "B")).await.unwrap();
+
+        graph.upsert_edge(edge(a, b, EdgeType::SameEvent, 0.5)).await.unwrap();
+        graph.upsert_edge(edge(b, a, EdgeType::SameEvent, 0.8)).await.unwrap();
+        graph.upsert_edge(edge(b, a, EdgeType::ImpliesCandidate, 0.6)).await.unwrap();
+
+        let neighbors = graph.neighbors(a, None, 0.0);
+        assert_eq!(neighbors.len(), 2);
+        assert_eq!((neighbors[0].edge_type, neighbors[0].confidence), (EdgeType::SameEvent, 0.8));
+        assert_eq!((neighbors[1].from_market, neighbors[1].to_market), (b, a));
+        assert_eq!(graph.neighbors(b, None, 0.7).len(), 1);
+
+        assert!(graph.upsert_edge(edge(a, MarketId::random(), EdgeType::SameEvent, 0.9)).await.is_err());
+        assert!(graph.upsert_edge(edge(a, a, EdgeType::SameEvent, 0.9)).await.is_err());
+    }
+
+    /// Runs against a scratch PostgreSQL: `TEST_DATABASE_URL=... cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_graph_round_trip_postgres() {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL not set");
+        let graph = |config| GraphDB::connect(&url, config);
+        let mut writer = graph(GraphConfig::default()).await.unwrap();
+        writer.initialize().await.unwrap();
+
+        let (a, b) = (MarketId::random(), MarketId::random());
+        let resolution_date = Utc::now().date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc();
+        writer
+            .add_market(
+                MarketNode::new(a, "Will Vance win the 2028 presidential election?")
+                    .with_category("Politics")
+                    .with_embedding_id("question-a")
+                    .with_resolution_date(resolution_date),
+            )
+            .await
+            .unwrap();
+        writer.add_market(MarketNode::new(b, "Will a Republican win?")).await.unwrap();
+        writer.upsert_edge(edge(a, b, EdgeType::ImpliesCandidate, 0.4)).await.unwrap();
+        writer.upsert_edge(edge(a, b, EdgeType::ImpliesCandidate, 0.75)).await.unwrap();
+
+        let mut reader = graph(GraphConfig::default()).await.unwrap();
+        reader.load().await.unwrap();
+        assert_eq!(reader.market(a), writer.market(a));
+        assert_eq!(reader.neighbors(b, None, 0.0), [&edge(a, b, EdgeType::ImpliesCandidate, 0.75)]);
+    }
 }
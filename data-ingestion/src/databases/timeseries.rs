@@ -1,26 +1,168 @@
-use anyhow::Result;
-use common::{OrderBook, PriceTick, Trade};
-use sqlx::postgres::PgPool;
-use tracing::info;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use common::{MarketId, OrderBook, OrderSide, PriceTick, Trade};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgPool, PgRow};
+use sqlx::{Postgres, QueryBuilder, Row};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, Notify, Semaphore};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+/// Batching, backpressure and failure handling for `TimeSeriesDB` writes
+#[derive(Debug, Clone)]
+pub struct TimeSeriesConfig {
+    /// Rows per multi-row INSERT; a table reaching it is flushed right away
+    pub max_batch_size: usize,
+    /// Buffered rows are flushed at least this often
+    pub flush_interval: Duration,
+    /// Rows buffered or being written before writes wait for the database
+    pub buffer_capacity: usize,
+    /// Attempts at a failed batch before it is spilled
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled after each one
+    pub retry_backoff: Duration,
+    /// Batches that ran out of retries are appended here as JSON lines
+    pub spill_path: PathBuf,
+}
+
+impl Default for TimeSeriesConfig {
+    fn default() -> Self {
+        Self {
+            max_batch_size: 1_000,
+            flush_interval: Duration::from_secs(1),
+            buffer_capacity: 50_000,
+            max_retries: 5,
+            retry_backoff: Duration::from_millis(500),
+            spill_path: PathBuf::from("timeseries_spill.jsonl"),
+        }
+    }
+}
+
+/// Counters since the writer started
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriterStats {
+    pub rows_written: u64,
+    pub batches_written: u64,
+    pub retries: u64,
+    pub rows_spilled: u64,
+    /// Rows lost because the spill file could not be written either
+    pub rows_dropped: u64,
+}
+
+/// One INSERT's worth of rows, also the spill file's line format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "table", content = "rows", rename_all = "snake_case")]
+enum Batch {
+    PriceTicks(Vec<PriceTick>),
+    Trades(Vec<Trade>),
+    OrderBookSnapshots(Vec<OrderBook>),
+}
+
+impl Batch {
+    fn len(&self) -> usize {
+        match self {
+            Batch::PriceTicks(rows) => rows.len(),
+            Batch::Trades(rows) => rows.len(),
+            Batch::OrderBookSnapshots(rows) => rows.len(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Buffer {
+    ticks: Vec<PriceTick>,
+    trades: Vec<Trade>,
+    books: Vec<OrderBook>,
+}
+
+#[derive(Default)]
+struct Counters {
+    rows_written: AtomicU64,
+    batches_written: AtomicU64,
+    retries: AtomicU64,
+    rows_spilled: AtomicU64,
+    rows_dropped: AtomicU64,
+}
+
+struct Writer {
+    pool: PgPool,
+    config: TimeSeriesConfig,
+    buffer: std::sync::Mutex<Buffer>,
+    /// One permit per row that may still be buffered
+    space: Semaphore,
+    /// A table reached `max_batch_size`
+    full: Notify,
+    /// Keeps flushes, and so rows, in order
+    flushing: Mutex<()>,
+    counters: Counters,
+}
 
 /// Time-series database for prices, volumes, and order book data
 /// Uses TimescaleDB (PostgreSQL extension)
+///
+/// Writes are buffered and flushed in batches by a background task, so a
+/// slow or failing database never fails the connector writing to it: batches
+/// are retried with backoff, then spilled to a file for `replay_spill`. When
+/// `buffer_capacity` rows are pending, writes wait until a flush makes room.
+/// Call `flush` before dropping to write what is still buffered.
 pub struct TimeSeriesDB {
-    pool: PgPool,
+    writer: Arc<Writer>,
+    flusher: JoinHandle<()>,
 }
 
 impl TimeSeriesDB {
     pub async fn new(url: &str) -> Result<Self> {
+        Self::with_config(url, TimeSeriesConfig::default()).await
+    }
+
+    pub async fn with_config(url: &str, config: TimeSeriesConfig) -> Result<Self> {
         let pool = PgPool::connect(url).await?;
 
         info!("✅ Connected to TimescaleDB");
 
-        Ok(Self { pool })
+        Ok(Self::from_pool(pool, config))
     }
 
-    pub async fn init_tables(&self) -> Result<()> {
-        // Create hypertable for price ticks
-        sqlx::query(
+    /// Write through an existing pool; starts the background flusher
+    pub fn from_pool(pool: PgPool, config: TimeSeriesConfig) -> Self {
+        let writer = Arc::new(Writer {
+            pool,
+            space: Semaphore::new(config.buffer_capacity.max(1)),
+            config,
+            buffer: std::sync::Mutex::new(Buffer::default()),
+            full: Notify::new(),
+            flushing: Mutex::new(()),
+            counters: Counters::default(),
+        });
+
+        let flusher = tokio::spawn({
+            let writer = writer.clone();
+            async move {
+                let period = writer.config.flush_interval;
+                let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+                loop {
+                    tokio::select! {
+                        _ = ticker.tick() => {}
+                        _ = writer.full.notified() => {}
+                    }
+                    writer.flush().await;
+                }
+            }
+        });
+
+        Self { writer, flusher }
+    }
+
+    /// Create the hypertables and their per-market indexes
+    pub async fn initialize(&self) -> Result<()> {
+        let statements = [
+            "CREATE EXTENSION IF NOT EXISTS timescaledb",
             r#"
             CREATE TABLE IF NOT EXISTS price_ticks (
                 time TIMESTAMPTZ NOT NULL,
@@ -29,16 +171,10 @@ impl TimeSeriesDB {
                 price DOUBLE PRECISION NOT NULL,
                 volume_24h DOUBLE PRECISION NOT NULL,
                 liquidity DOUBLE PRECISION NOT NULL
-            );
-
-            SELECT create_hypertable('price_ticks', 'time', if_not_exists => TRUE);
+            )
             "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // Create hypertable for trades
-        sqlx::query(
+            "SELECT create_hypertable('price_ticks', 'time', if_not_exists => TRUE)",
+            "CREATE INDEX IF NOT EXISTS price_ticks_market_time ON price_ticks (market_id, time DESC)",
             r#"
             CREATE TABLE IF NOT EXISTS trades (
                 time TIMESTAMPTZ NOT NULL,
@@ -48,60 +184,507 @@ impl TimeSeriesDB {
                 price DOUBLE PRECISION NOT NULL,
                 size DOUBLE PRECISION NOT NULL,
                 side TEXT NOT NULL
-            );
-
-            SELECT create_hypertable('trades', 'time', if_not_exists => TRUE);
+            )
             "#,
-        )
-        .execute(&self.pool)
-        .await?;
+            "SELECT create_hypertable('trades', 'time', if_not_exists => TRUE)",
+            "CREATE INDEX IF NOT EXISTS trades_market_time ON trades (market_id, time DESC)",
+            r#"
+            CREATE TABLE IF NOT EXISTS order_book_snapshots (
+                time TIMESTAMPTZ NOT NULL,
+                market_id UUID NOT NULL,
+                sequence BIGINT NOT NULL,
+                bids JSONB NOT NULL,
+                asks JSONB NOT NULL
+            )
+            "#,
+            "SELECT create_hypertable('order_book_snapshots', 'time', if_not_exists => TRUE)",
+            "CREATE INDEX IF NOT EXISTS order_book_snapshots_market_time ON order_book_snapshots (market_id, time DESC)",
+        ];
+        for statement in statements {
+            sqlx::query(statement)
+                .execute(&self.writer.pool)
+                .await
+                .with_context(|| format!("Failed to run: {}", statement.trim()))?;
+        }
 
         info!("✅ Initialized time-series tables");
 
         Ok(())
     }
 
-    pub async fn insert_price_tick(&self, tick: &PriceTick) -> Result<()> {
-        sqlx::query(
+    pub async fn write_price_ticks(&self, ticks: &[PriceTick]) -> Result<()> {
+        self.reserve(ticks.len()).await?;
+        let full = {
+            let mut buffer = self.writer.buffer();
+            buffer.ticks.extend_from_slice(ticks);
+            buffer.ticks.len() >= self.writer.config.max_batch_size
+        };
+        self.notify_if(full);
+        Ok(())
+    }
+
+    pub async fn write_trades(&self, trades: &[Trade]) -> Result<()> {
+        self.reserve(trades.len()).await?;
+        let full = {
+            let mut buffer = self.writer.buffer();
+            buffer.trades.extend_from_slice(trades);
+            buffer.trades.len() >= self.writer.config.max_batch_size
+        };
+        self.notify_if(full);
+        Ok(())
+    }
+
+    pub async fn write_orderbook_snapshots(&self, books: &[OrderBook]) -> Result<()> {
+        self.reserve(books.len()).await?;
+        let full = {
+            let mut buffer = self.writer.buffer();
+            buffer.books.extend_from_slice(books);
+            buffer.books.len() >= self.writer.config.max_batch_size
+        };
+        self.notify_if(full);
+        Ok(())
+    }
+
+    /// Write everything buffered so far, retrying or spilling as needed
+    pub async fn flush(&self) {
+        self.writer.flush().await;
+    }
+
+    pub fn stats(&self) -> WriterStats {
+        let counters = &self.writer.counters;
+        WriterStats {
+            rows_written: counters.rows_written.load(Ordering::Relaxed),
+            batches_written: counters.batches_written.load(Ordering::Relaxed),
+            retries: counters.retries.load(Ordering::Relaxed),
+            rows_spilled: counters.rows_spilled.load(Ordering::Relaxed),
+            rows_dropped: counters.rows_dropped.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Write spilled batches back to the database, emptying the spill file
+    /// once they are all in; returns the rows written
+    pub async fn replay_spill(&self) -> Result<usize> {
+        let _flushing = self.writer.flushing.lock().await;
+        let path = &self.writer.config.spill_path;
+        let file = match std::fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut rows = 0;
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let batch: Batch = serde_json::from_str(&line).context("Malformed spill line")?;
+            self.writer.insert(&batch).await?;
+            rows += batch.len();
+        }
+        std::fs::File::create(path)?;
+
+        info!("Replayed {} spilled rows from {}", rows, path.display());
+        Ok(rows)
+    }
+
+    /// Price ticks for a market with `from <= time < to`, oldest first
+    pub async fn get_price_history(
+        &self,
+        market_id: MarketId,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<PriceTick>> {
+        let rows = sqlx::query(
             r#"
-            INSERT INTO price_ticks (time, market_id, outcome_id, price, volume_24h, liquidity)
-            VALUES ($1, $2, $3, $4, $5, $6)
+            SELECT time, market_id, outcome_id, price, volume_24h, liquidity
+            FROM price_ticks
+            WHERE market_id = $1 AND time >= $2 AND time < $3
+            ORDER BY time, outcome_id
             "#,
         )
-        .bind(tick.timestamp)
-        .bind(tick.market_id)
-        .bind(&tick.outcome_id)
-        .bind(tick.price)
-        .bind(tick.volume_24h)
-        .bind(tick.liquidity)
-        .execute(&self.pool)
+        .bind(market_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.writer.pool)
         .await?;
 
-        Ok(())
+        rows.iter()
+            .map(|row| {
+                Ok(PriceTick {
+                    timestamp: row.try_get("time")?,
+                    market_id: row.try_get("market_id")?,
+                    outcome_id: row.try_get::<String, _>("outcome_id")?.into(),
+                    price: row.try_get("price")?,
+                    volume_24h: row.try_get("volume_24h")?,
+                    liquidity: row.try_get("liquidity")?,
+                })
+            })
+            .collect()
     }
 
-    pub async fn insert_trade(&self, trade: &Trade) -> Result<()> {
-        let side = match trade.side {
-            common::OrderSide::Buy => "buy",
-            common::OrderSide::Sell => "sell",
-        };
-
-        sqlx::query(
+    /// Trades for a market with `from <= time < to`, oldest first
+    pub async fn get_trades(
+        &self,
+        market_id: MarketId,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<Trade>> {
+        let rows = sqlx::query(
             r#"
-            INSERT INTO trades (time, trade_id, market_id, outcome_id, price, size, side)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            SELECT time, trade_id, market_id, outcome_id, price, size, side
+            FROM trades
+            WHERE market_id = $1 AND time >= $2 AND time < $3
+            ORDER BY time, trade_id
             "#,
         )
-        .bind(trade.timestamp)
-        .bind(trade.id)
-        .bind(trade.market_id)
-        .bind(&trade.outcome_id)
-        .bind(trade.price)
-        .bind(trade.size)
-        .bind(side)
-        .execute(&self.pool)
+        .bind(market_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.writer.pool)
         .await?;
 
+        rows.iter().map(trade_from_row).collect()
+    }
+
+    /// Wait for room for `rows` more rows; the permits come back once the
+    /// rows are written or spilled
+    async fn reserve(&self, rows: usize) -> Result<()> {
+        let rows = rows.min(self.writer.config.buffer_capacity.max(1)) as u32;
+        if rows == 0 {
+            return Ok(());
+        }
+        if self.writer.space.available_permits() < rows as usize {
+            warn!("Time-series buffer full, waiting for the database to catch up");
+            self.writer.full.notify_one();
+        }
+        self.writer
+            .space
+            .acquire_many(rows)
+            .await
+            .map_err(|_| anyhow!("Time-series writer is shut down"))?
+            .forget();
+        Ok(())
+    }
+
+    fn notify_if(&self, full: bool) {
+        if full {
+            self.writer.full.notify_one();
+        }
+    }
+}
+
+impl Drop for TimeSeriesDB {
+    fn drop(&mut self) {
+        self.flusher.abort();
+        self.writer.space.close();
+    }
+}
+
+impl Writer {
+    fn buffer(&self) -> std::sync::MutexGuard<'_, Buffer> {
+        self.buffer.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    async fn flush(&self) {
+        let _flushing = self.flushing.lock().await;
+        let Buffer { ticks, trades, books } = std::mem::take(&mut *self.buffer());
+        let taken = ticks.len() + trades.len() + books.len();
+        if taken == 0 {
+            return;
+        }
+
+        let size = self.config.max_batch_size.max(1);
+        let batches = ticks
+            .chunks(size)
+            .map(|rows| Batch::PriceTicks(rows.to_vec()))
+            .chain(trades.chunks(size).map(|rows| Batch::Trades(rows.to_vec())))
+            .chain(books.chunks(size).map(|rows| Batch::OrderBookSnapshots(rows.to_vec())));
+        for batch in batches {
+            self.write_or_spill(batch).await;
+        }
+
+        self.space.add_permits(taken.min(self.config.buffer_capacity.max(1)));
+    }
+
+    async fn write_or_spill(&self, batch: Batch) {
+        let mut backoff = self.config.retry_backoff;
+        for attempt in 1..=self.config.max_retries.max(1) {
+            match self.insert(&batch).await {
+                Ok(()) => {
+                    self.counters.rows_written.fetch_add(batch.len() as u64, Ordering::Relaxed);
+                    self.counters.batches_written.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+                Err(e) if attempt < self.config.max_retries => {
+                    warn!("Time-series batch failed (attempt {}), retrying in {:?}: {}", attempt, backoff, e);
+                    self.counters.retries.fetch_add(1, Ordering::Relaxed);
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Err(e) => error!("Time-series batch failed after {} attempts: {}", attempt, e),
+            }
+        }
+
+        match self.spill(&batch) {
+            Ok(()) => {
+                warn!("Spilled {} rows to {}", batch.len(), self.config.spill_path.display());
+                self.counters.rows_spilled.fetch_add(batch.len() as u64, Ordering::Relaxed);
+            }
+            Err(e) => {
+                error!("Failed to spill {} rows, dropping them: {}", batch.len(), e);
+                self.counters.rows_dropped.fetch_add(batch.len() as u64, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn spill(&self, batch: &Batch) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.config.spill_path)?;
+        writeln!(file, "{}", serde_json::to_string(batch)?)?;
         Ok(())
     }
+
+    /// One multi-row INSERT for the batch
+    async fn insert(&self, batch: &Batch) -> Result<()> {
+        let mut query: QueryBuilder<Postgres> = match batch {
+            Batch::PriceTicks(ticks) => {
+                let mut query = QueryBuilder::new(
+                    "INSERT INTO price_ticks (time, market_id, outcome_id, price, volume_24h, liquidity) ",
+                );
+                query.push_values(ticks, |mut row, tick| {
+                    row.push_bind(tick.timestamp)
+                        .push_bind(tick.market_id)
+                        .push_bind(tick.outcome_id.to_string())
+                        .push_bind(tick.price)
+                        .push_bind(tick.volume_24h)
+                        .push_bind(tick.liquidity);
+                });
+                query
+            }
+            Batch::Trades(trades) => {
+                let mut query = QueryBuilder::new(
+                    "INSERT INTO trades (time, trade_id, market_id, outcome_id, price, size, side) ",
+                );
+                query.push_values(trades, |mut row, trade| {
+                    row.push_bind(trade.timestamp)
+                        .push_bind(trade.id)
+                        .push_bind(trade.market_id)
+                        .push_bind(trade.outcome_id.to_string())
+                        .push_bind(trade.price)
+                        .push_bind(trade.size)
+                        .push_bind(side_name(trade.side));
+                });
+                query
+            }
+            Batch::OrderBookSnapshots(books) => {
+                let mut levels = Vec::with_capacity(books.len());
+                for book in books {
+                    levels.push((serde_json::to_string(&book.bids)?, serde_json::to_string(&book.asks)?));
+                }
+                let mut query = QueryBuilder::new(
+                    "INSERT INTO order_book_snapshots (time, market_id, sequence, bids, asks) ",
+                );
+                query.push_values(books.iter().zip(levels), |mut row, (book, (bids, asks))| {
+                    row.push_bind(book.timestamp)
+                        .push_bind(book.market_id)
+                        .push_bind(book.sequence as i64)
+                        .push_bind(bids)
+                        .push_unseparated("::jsonb")
+                        .push_bind(asks)
+                        .push_unseparated("::jsonb");
+                });
+                query
+            }
+        };
+
+        query.build().execute(&self.pool).await?;
+        Ok(())
+    }
+}
+
+fn side_name(side: OrderSide) -> &'static str {
+    match side {
+        OrderSide::Buy => "buy",
+        OrderSide::Sell => "sell",
+    }
+}
+
+fn trade_from_row(row: &PgRow) -> Result<Trade> {
+    let side = match row.try_get::<String, _>("side")?.as_str() {
+        "buy" => OrderSide::Buy,
+        "sell" => OrderSide::Sell,
+        other => return Err(anyhow!("Unknown trade side: {}", other)),
+    };
+    Ok(Trade {
+        id: row.try_get("trade_id")?,
+        market_id: row.try_get("market_id")?,
+        outcome_id: row.try_get::<String, _>("outcome_id")?.into(),
+        price: row.try_get("price")?,
+        size: row.try_get("size")?,
+        side,
+        timestamp: row.try_get("time")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration as ChronoDuration;
+    use sqlx::postgres::PgPoolOptions;
+
+    fn tick(market_id: MarketId, price: f64, timestamp: DateTime<Utc>) -> PriceTick {
+        PriceTick {
+            market_id,
+            outcome_id: "yes".into(),
+            price,
+            volume_24h: 1_000.0,
+            liquidity: 500.0,
+            timestamp,
+        }
+    }
+
+    fn spill_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{}.jsonl", name, uuid::Uuid::new_v4()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_database_spills_and_frees_buffer() {
+        // Nothing listens on port 1, so every attempt fails fast
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(200))
+            .connect_lazy("postgres://polymarket@127.0.0.1:1/polymarket")
+            .unwrap();
+        let path = spill_path("timeseries-spill");
+        let config = TimeSeriesConfig {
+            max_batch_size: 2,
+            buffer_capacity: 4,
+            max_retries: 2,
+            retry_backoff: Duration::from_millis(1),
+            spill_path: path.clone(),
+            ..TimeSeriesConfig::default()
+        };
+        let db = TimeSeriesDB::from_pool(pool, config);
+
+        let market_id = MarketId::random();
+        let now = Utc::now();
+        let ticks: Vec<PriceTick> = (0..3).map(|i| tick(market_id, 0.5, now + ChronoDuration::seconds(i))).collect();
+        db.write_price_ticks(&ticks).await.unwrap();
+        db.flush().await;
+
+        let stats = db.stats();
+        assert_eq!(stats.rows_written, 0);
+        assert_eq!(stats.rows_spilled, 3);
+        // Two batches of at most 2 rows, each retried once
+        assert_eq!(stats.retries, 2);
+
+        let spilled: Vec<Batch> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(spilled.iter().map(Batch::len).collect::<Vec<_>>(), [2, 1]);
+        assert!(matches!(&spilled[0], Batch::PriceTicks(rows) if rows[0].market_id == market_id));
+
+        // The buffer has room again, so a write bigger than what was left
+        // before the flush goes through without waiting
+        tokio::time::timeout(Duration::from_secs(1), db.write_price_ticks(&ticks[..2]))
+            .await
+            .expect("write waited on a flushed buffer")
+            .unwrap();
+        let _ = std::fs::remove_file(&path);
+    }
+
+    async fn test_db(config: TimeSeriesConfig) -> TimeSeriesDB {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL not set");
+        let db = TimeSeriesDB::with_config(&url, config).await.unwrap();
+        db.initialize().await.unwrap();
+        db
+    }
+
+    /// Runs against a scratch TimescaleDB: `TEST_DATABASE_URL=... cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_batched_ticks_and_time_range_postgres() {
+        let config = TimeSeriesConfig {
+            max_batch_size: 3,
+            // Only full batches and explicit flushes write
+            flush_interval: Duration::from_secs(3600),
+            spill_path: spill_path("timeseries-ticks"),
+            ..TimeSeriesConfig::default()
+        };
+        let db = test_db(config).await;
+
+        let market_id = MarketId::random();
+        let start = Utc::now() - ChronoDuration::hours(1);
+        let at = |secs: i64| start + ChronoDuration::seconds(secs);
+        // Out of order, plus a tick for another market
+        let ticks: Vec<PriceTick> = [4, 1, 6, 0, 3, 5, 2]
+            .into_iter()
+            .map(|secs| tick(market_id, 0.4 + secs as f64 / 100.0, at(secs)))
+            .chain([tick(MarketId::random(), 0.9, at(3))])
+            .collect();
+        db.write_price_ticks(&ticks).await.unwrap();
+        db.flush().await;
+
+        let stats = db.stats();
+        assert_eq!(stats.rows_written, 8);
+        assert_eq!(stats.batches_written, 3);
+        assert_eq!(stats.rows_spilled, 0);
+
+        let history = db.get_price_history(market_id, at(0), at(7)).await.unwrap();
+        let times: Vec<_> = history.iter().map(|tick| tick.timestamp).collect();
+        assert_eq!(times, (0..7).map(at).collect::<Vec<_>>());
+        assert!((history[2].price - 0.42).abs() < 1e-12);
+
+        // `from` is inclusive, `to` exclusive
+        let window = db.get_price_history(market_id, at(2), at(5)).await.unwrap();
+        assert_eq!(window.iter().map(|tick| tick.timestamp).collect::<Vec<_>>(), [at(2), at(3), at(4)]);
+    }
+
+    /// Runs against a scratch TimescaleDB: `TEST_DATABASE_URL=... cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_trades_and_books_round_trip_postgres() {
+        let config = TimeSeriesConfig {
+            spill_path: spill_path("timeseries-trades"),
+            ..TimeSeriesConfig::default()
+        };
+        let db = test_db(config).await;
+
+        let market_id = MarketId::random();
+        let start = Utc::now() - ChronoDuration::hours(1);
+        let trade = |secs: i64, side: OrderSide| Trade {
+            id: uuid::Uuid::new_v4(),
+            market_id,
+            outcome_id: "yes".into(),
+            price: 0.55,
+            size: 10.0 + secs as f64,
+            side,
+            timestamp: start + ChronoDuration::seconds(secs),
+        };
+        db.write_trades(&[trade(2, OrderSide::Sell), trade(0, OrderSide::Buy), trade(1, OrderSide::Buy)])
+            .await
+            .unwrap();
+        db.write_orderbook_snapshots(&[OrderBook {
+            market_id,
+            timestamp: start,
+            bids: vec![common::Order { outcome_id: "yes".into(), price: 0.54, size: 100.0 }],
+            asks: vec![],
+            sequence: 7,
+        }])
+        .await
+        .unwrap();
+        db.flush().await;
+        assert_eq!(db.stats().rows_written, 4);
+
+        let trades = db.get_trades(market_id, start, start + ChronoDuration::seconds(3)).await.unwrap();
+        assert_eq!(trades.iter().map(|trade| trade.size).collect::<Vec<_>>(), [10.0, 11.0, 12.0]);
+        assert_eq!(trades[2].side, OrderSide::Sell);
+        assert!(db.get_trades(MarketId::random(), start, start + ChronoDuration::seconds(3)).await.unwrap().is_empty());
+    }
 }
@@ -4,7 +4,7 @@ use rdkafka::producer::{FutureProducer, FutureRecord};
 use rdkafka::util::Timeout;
 use tracing::{debug, error};
 
-use common::MarketEvent;
+use common::{MarketEvent, NewsArticle};
 use std::time::Duration;
 
 pub struct KafkaProducer {
@@ -40,5 +40,27 @@ impl KafkaProducer {
 
         Ok(())
     }
-}
 
+    /// Publish a batch of articles as one JSON array, keyed by the first id
+    pub async fn publish_articles(&self, topic: &str, articles: &[NewsArticle]) -> Result<()> {
+        let Some(first) = articles.first() else {
+            return Ok(());
+        };
+        let value = serde_json::to_string(articles)?;
+
+        debug!("Publishing {} articles to {}", articles.len(), topic);
+
+        self.producer
+            .send(
+                FutureRecord::to(topic).key(&first.id).payload(&value),
+                Timeout::After(Duration::from_secs(5)),
+            )
+            .await
+            .map_err(|(e, _)| {
+                error!("Failed to publish to {}: {}", topic, e);
+                anyhow::anyhow!("Failed to publish message: {}", e)
+            })?;
+
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
 pub mod connectors;
 pub mod databases;
 pub mod event_bus;
+pub mod order_book;
@@ -1,12 +1,11 @@
 use anyhow::Result;
-use tracing::{info, Level};
+use tracing::{error, info, Level};
 use tracing_subscriber;
 
-mod event_bus;
-mod connectors;
-mod databases;
-
-use event_bus::KafkaProducer;
+use data_ingestion::connectors;
+use data_ingestion::databases::TimeSeriesDB;
+use data_ingestion::event_bus::KafkaProducer;
+use std::sync::Arc;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -20,9 +19,29 @@ async fn main() -> Result<()> {
     // Initialize Kafka producer
     let kafka_producer = KafkaProducer::new("localhost:9092").await?;
 
+    // Persist ticks, trades and books for research and backtesting
+    let mut polymarket = connectors::polymarket::PolymarketConnector::new();
+    let store = match std::env::var("DATABASE_URL") {
+        Ok(url) => {
+            let store = Arc::new(TimeSeriesDB::new(&url).await?);
+            store.initialize().await?;
+            if let Err(e) = store.replay_spill().await {
+                error!("Failed to replay spilled rows: {}", e);
+            }
+            polymarket = polymarket.with_store(store.clone());
+            Some(store)
+        }
+        Err(_) => None,
+    };
+
+    let mut gdelt = connectors::gdelt::GDELTConnector::new();
+
     // Start connectors
     tokio::select! {
-        result = connectors::polymarket::PolymarketConnector::run(&kafka_producer) => {
+        result = polymarket.run_forever(&kafka_producer) => {
+            result?
+        }
+        result = gdelt.run_continuous(&kafka_producer) => {
             result?
         }
         _ = tokio::signal::ctrl_c() => {
@@ -30,5 +49,9 @@ async fn main() -> Result<()> {
         }
     }
 
+    if let Some(store) = store {
+        store.flush().await;
+    }
+
     Ok(())
 }
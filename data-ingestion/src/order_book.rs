@@ -0,0 +1,318 @@
+use chrono::{DateTime, Utc};
+use common::{AssetId, MarketId, Order, OrderBook, OrderSide};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tracing::{debug, warn};
+
+/// Prices are keyed in millionths, well below Polymarket's smallest tick
+const PRICE_SCALE: f64 = 1_000_000.0;
+
+fn price_key(price: f64) -> i64 {
+    (price * PRICE_SCALE).round() as i64
+}
+
+fn key_price(key: i64) -> f64 {
+    key as f64 / PRICE_SCALE
+}
+
+/// One changed level; size 0 removes it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LevelUpdate {
+    /// Buy for a bid, Sell for an ask
+    pub side: OrderSide,
+    pub price: f64,
+    pub size: f64,
+}
+
+/// What applying a diff did
+#[derive(Debug, Clone)]
+pub enum DiffOutcome {
+    /// The book changed; publish it
+    Applied(OrderBook),
+    /// Older than the book, which already reflects it
+    Stale,
+    /// No usable book; discarded until a fresh snapshot arrives
+    NeedsSnapshot,
+}
+
+#[derive(Debug)]
+struct LiveBook {
+    bids: BTreeMap<i64, f64>,
+    asks: BTreeMap<i64, f64>,
+    timestamp: DateTime<Utc>,
+}
+
+impl LiveBook {
+    fn levels(&mut self, side: OrderSide) -> &mut BTreeMap<i64, f64> {
+        match side {
+            OrderSide::Buy => &mut self.bids,
+            OrderSide::Sell => &mut self.asks,
+        }
+    }
+
+    fn best_bid(&self) -> Option<(f64, f64)> {
+        self.bids.iter().next_back().map(|(&key, &size)| (key_price(key), size))
+    }
+
+    fn best_ask(&self) -> Option<(f64, f64)> {
+        self.asks.iter().next().map(|(&key, &size)| (key_price(key), size))
+    }
+
+    fn crossed(&self) -> bool {
+        matches!((self.best_bid(), self.best_ask()), (Some((bid, _)), Some((ask, _))) if bid >= ask)
+    }
+}
+
+/// Live order books per asset, from the feed's snapshots and diffs
+///
+/// A `book` message replaces an asset's book; a `price_change` adds,
+/// resizes or removes levels in it. Every change bumps the asset's sequence
+/// number, which never goes backwards, even across snapshots. A diff for an
+/// asset without a book, or one that leaves the book crossed (a missed
+/// update), drops the book and marks the asset as needing a snapshot, so a
+/// corrupt book is never published.
+#[derive(Debug, Default)]
+pub struct OrderBookManager {
+    books: HashMap<AssetId, LiveBook>,
+    sequences: HashMap<AssetId, u64>,
+    /// Until assets are mapped to markets, each one gets a stable id of its own
+    market_ids: HashMap<AssetId, MarketId>,
+    awaiting_snapshot: HashSet<AssetId>,
+    /// Assets that started awaiting a snapshot since the last take
+    snapshot_requests: Vec<AssetId>,
+}
+
+impl OrderBookManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace an asset's book with a full snapshot of (price, size) levels
+    pub fn apply_snapshot(
+        &mut self,
+        asset_id: &AssetId,
+        bids: &[(f64, f64)],
+        asks: &[(f64, f64)],
+        timestamp: DateTime<Utc>,
+    ) -> OrderBook {
+        let levels = |levels: &[(f64, f64)]| {
+            levels
+                .iter()
+                .filter(|&&(_, size)| size > 0.0)
+                .map(|&(price, size)| (price_key(price), size))
+                .collect()
+        };
+        self.books.insert(
+            asset_id.clone(),
+            LiveBook {
+                bids: levels(bids),
+                asks: levels(asks),
+                timestamp,
+            },
+        );
+        if self.awaiting_snapshot.remove(asset_id) {
+            debug!("Fresh snapshot for {}", asset_id);
+        }
+        self.publish(asset_id)
+    }
+
+    /// Apply level changes to an asset's book
+    pub fn apply_diff(&mut self, asset_id: &AssetId, updates: &[LevelUpdate], timestamp: DateTime<Utc>) -> DiffOutcome {
+        let Some(book) = self.books.get_mut(asset_id) else {
+            if !self.awaiting_snapshot.contains(asset_id) {
+                warn!("Update for {} before any snapshot, awaiting one", asset_id);
+                self.invalidate(asset_id);
+            }
+            return DiffOutcome::NeedsSnapshot;
+        };
+        if timestamp < book.timestamp {
+            return DiffOutcome::Stale;
+        }
+
+        for update in updates {
+            let levels = book.levels(update.side);
+            if update.size > 0.0 {
+                levels.insert(price_key(update.price), update.size);
+            } else {
+                levels.remove(&price_key(update.price));
+            }
+        }
+        book.timestamp = timestamp;
+
+        if book.crossed() {
+            warn!("Book for {} crossed after an update, awaiting a snapshot", asset_id);
+            self.invalidate(asset_id);
+            return DiffOutcome::NeedsSnapshot;
+        }
+        DiffOutcome::Applied(self.publish(asset_id))
+    }
+
+    /// Drop an asset's book until the next snapshot
+    pub fn invalidate(&mut self, asset_id: &AssetId) {
+        self.books.remove(asset_id);
+        if self.awaiting_snapshot.insert(asset_id.clone()) {
+            self.snapshot_requests.push(asset_id.clone());
+        }
+    }
+
+    /// Drop every book, e.g. after a reconnect
+    pub fn invalidate_all(&mut self) {
+        let assets: Vec<AssetId> = self.books.keys().cloned().collect();
+        for asset_id in &assets {
+            self.invalidate(asset_id);
+        }
+    }
+
+    /// Assets that started waiting for a snapshot since the last call; the
+    /// caller resubscribes to them, which makes the feed send one
+    pub fn take_snapshot_requests(&mut self) -> Vec<AssetId> {
+        std::mem::take(&mut self.snapshot_requests)
+    }
+
+    /// Whether the asset has a book that can be trusted
+    pub fn is_live(&self, asset_id: &AssetId) -> bool {
+        self.books.contains_key(asset_id)
+    }
+
+    /// Highest bid as (price, size)
+    pub fn best_bid(&self, asset_id: &AssetId) -> Option<(f64, f64)> {
+        self.books.get(asset_id)?.best_bid()
+    }
+
+    /// Lowest ask as (price, size)
+    pub fn best_ask(&self, asset_id: &AssetId) -> Option<(f64, f64)> {
+        self.books.get(asset_id)?.best_ask()
+    }
+
+    /// Size resting at a price on one side, 0 if there is no level
+    pub fn depth_at(&self, asset_id: &AssetId, side: OrderSide, price: f64) -> f64 {
+        let Some(book) = self.books.get(asset_id) else {
+            return 0.0;
+        };
+        let levels = match side {
+            OrderSide::Buy => &book.bids,
+            OrderSide::Sell => &book.asks,
+        };
+        levels.get(&price_key(price)).copied().unwrap_or(0.0)
+    }
+
+    /// Sequence number of the asset's last published book
+    pub fn sequence(&self, asset_id: &AssetId) -> u64 {
+        self.sequences.get(asset_id).copied().unwrap_or(0)
+    }
+
+    /// The asset's current book, best levels first
+    pub fn book(&self, asset_id: &AssetId) -> Option<OrderBook> {
+        let book = self.books.get(asset_id)?;
+        let order = |(&key, &size): (&i64, &f64)| Order {
+            outcome_id: asset_id.to_outcome_id(),
+            price: key_price(key),
+            size,
+        };
+        Some(OrderBook {
+            market_id: self.market_ids.get(asset_id).copied().unwrap_or_else(MarketId::random),
+            timestamp: book.timestamp,
+            bids: book.bids.iter().rev().map(order).collect(),
+            asks: book.asks.iter().map(order).collect(),
+            sequence: self.sequence(asset_id),
+        })
+    }
+
+    /// Bump the asset's sequence and return its book
+    fn publish(&mut self, asset_id: &AssetId) -> OrderBook {
+        *self.sequences.entry(asset_id.clone()).or_insert(0) += 1;
+        self.market_ids.entry(asset_id.clone()).or_insert_with(MarketId::random);
+        self.book(asset_id).expect("published book exists")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn asset() -> AssetId {
+        AssetId::from("21742633143463906290569050155826241533067272736897614950488156847949938836455")
+    }
+
+    fn levels(book: &[Order]) -> Vec<(f64, f64)> {
+        book.iter().map(|order| (order.price, order.size)).collect()
+    }
+
+    fn bid(price: f64, size: f64) -> LevelUpdate {
+        LevelUpdate { side: OrderSide::Buy, price, size }
+    }
+
+    fn ask(price: f64, size: f64) -> LevelUpdate {
+        LevelUpdate { side: OrderSide::Sell, price, size }
+    }
+
+    #[test]
+    fn test_diffs_modify_snapshot() {
+        let mut manager = OrderBookManager::new();
+        let asset = asset();
+        let start = Utc::now();
+
+        let book = manager.apply_snapshot(&asset, &[(0.48, 100.0), (0.50, 200.0)], &[(0.53, 150.0), (0.55, 50.0)], start);
+        assert_eq!(book.sequence, 1);
+        assert_eq!(levels(&book.bids), [(0.50, 200.0), (0.48, 100.0)]);
+        let market_id = book.market_id;
+
+        // Resize the best bid, add a bid, remove the best ask
+        let DiffOutcome::Applied(book) = manager.apply_diff(
+            &asset,
+            &[bid(0.50, 120.0), bid(0.51, 30.0), ask(0.53, 0.0)],
+            start + Duration::seconds(1),
+        ) else {
+            panic!("diff not applied");
+        };
+        assert_eq!(book.sequence, 2);
+        assert_eq!(book.market_id, market_id);
+        assert_eq!(levels(&book.bids), [(0.51, 30.0), (0.50, 120.0), (0.48, 100.0)]);
+        assert_eq!(levels(&book.asks), [(0.55, 50.0)]);
+
+        assert_eq!(manager.best_bid(&asset), Some((0.51, 30.0)));
+        assert_eq!(manager.best_ask(&asset), Some((0.55, 50.0)));
+        assert_eq!(manager.depth_at(&asset, OrderSide::Buy, 0.50), 120.0);
+        assert_eq!(manager.depth_at(&asset, OrderSide::Sell, 0.53), 0.0);
+
+        // Already covered by the book
+        assert!(matches!(manager.apply_diff(&asset, &[bid(0.49, 10.0)], start), DiffOutcome::Stale));
+        assert_eq!(manager.sequence(&asset), 2);
+
+        // A new snapshot replaces every level and keeps counting
+        let book = manager.apply_snapshot(&asset, &[(0.40, 10.0)], &[(0.60, 10.0)], start + Duration::seconds(2));
+        assert_eq!(book.sequence, 3);
+        assert_eq!(levels(&book.bids), [(0.40, 10.0)]);
+    }
+
+    #[test]
+    fn test_gap_awaits_fresh_snapshot() {
+        let mut manager = OrderBookManager::new();
+        let asset = asset();
+        let start = Utc::now();
+
+        // Nothing to apply the update to
+        assert!(matches!(manager.apply_diff(&asset, &[bid(0.50, 10.0)], start), DiffOutcome::NeedsSnapshot));
+        assert_eq!(manager.take_snapshot_requests(), std::slice::from_ref(&asset));
+        assert!(manager.take_snapshot_requests().is_empty());
+        assert!(manager.book(&asset).is_none());
+
+        manager.apply_snapshot(&asset, &[(0.48, 100.0)], &[(0.52, 100.0)], start);
+        assert!(manager.is_live(&asset));
+
+        // A bid through the ask means an update was missed
+        let outcome = manager.apply_diff(&asset, &[bid(0.53, 10.0)], start + Duration::seconds(1));
+        assert!(matches!(outcome, DiffOutcome::NeedsSnapshot));
+        assert!(!manager.is_live(&asset));
+        assert_eq!(manager.best_bid(&asset), None);
+        assert_eq!(manager.take_snapshot_requests(), std::slice::from_ref(&asset));
+
+        // Later diffs are dropped until the snapshot arrives
+        let outcome = manager.apply_diff(&asset, &[ask(0.55, 10.0)], start + Duration::seconds(2));
+        assert!(matches!(outcome, DiffOutcome::NeedsSnapshot));
+
+        let book = manager.apply_snapshot(&asset, &[(0.50, 100.0)], &[(0.54, 100.0)], start + Duration::seconds(3));
+        assert_eq!(book.sequence, 2);
+        assert!(manager.take_snapshot_requests().is_empty());
+    }
+}
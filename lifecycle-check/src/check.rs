@@ -0,0 +1,419 @@
+//! Shutdown, snapshot and restart check against a synthetic market feed
+
+use crate::stack::{ComponentHealth, ComponentStatus, EmbeddedStack, StackConfig, StackSnapshot};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use common::MarketId;
+use data_ingestion::connectors::synthetic::{SyntheticConnector, SyntheticEvent, SyntheticScenario};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+use tracing::info;
+use uuid::Uuid;
+
+/// Tolerance for equity continuity, in USD
+const EQUITY_TOLERANCE: f64 = 1e-6;
+
+/// Script of one lifecycle check
+#[derive(Debug, Clone)]
+pub struct LifecycleScenario {
+    /// Market feed; `time_scale` is ignored, events are applied as fast as possible
+    pub feed: SyntheticScenario,
+    pub stack: StackConfig,
+    /// Simulated time the stack runs before it is shut down
+    pub run_for: Duration,
+    /// Simulated time the stack is down; the feed keeps producing events
+    pub restart_gap: Duration,
+    /// Simulated time the restarted stack runs after catching up
+    pub resume_for: Duration,
+    /// Events before the shutdown point the feed redelivers on restart,
+    /// as a consumer resuming from a lagging committed offset would see
+    pub redelivered_events: usize,
+    /// Directory to write the shutdown snapshot to; the restart reads it
+    /// back from there. Kept in memory when None.
+    pub artifact_dir: Option<PathBuf>,
+}
+
+impl Default for LifecycleScenario {
+    fn default() -> Self {
+        let mut stack = StackConfig::default();
+        // This checks state consistency across a restart, not risk limits
+        stack.risk.circuit_breakers.enabled = false;
+
+        Self {
+            feed: SyntheticScenario {
+                market_count: 25,
+                resolution_hazard_per_hour: 2.0,
+                seed: 1,
+                ..SyntheticScenario::default()
+            },
+            stack,
+            run_for: Duration::minutes(10),
+            restart_gap: Duration::seconds(30),
+            resume_for: Duration::minutes(5),
+            redelivered_events: 200,
+            artifact_dir: None,
+        }
+    }
+}
+
+/// An invariant that did not hold across the restart
+#[derive(Debug, Clone, PartialEq)]
+pub enum InvariantViolation {
+    /// Equity of the restarted stack differs from the equity at shutdown
+    EquityDiscontinuity { at_shutdown: f64, after_restart: f64 },
+    /// Generator state was not restored as captured
+    GeneratorStateMismatch { generator: usize },
+    /// A signal was executed more than once
+    DuplicateExecution { signal_id: Uuid, count: usize },
+    /// An event triggered more than one fill on the same market
+    DuplicateFill { market_id: MarketId, sequence: u64 },
+    /// A signal pending at shutdown neither filled, expired nor is still working
+    PendingSignalLost { signal_id: Uuid },
+    /// A pending signal is still working after its expiry
+    StalePendingSignal { signal_id: Uuid, expires_at: DateTime<Utc> },
+    /// The restarted stack didn't apply every event the feed produced
+    NotCaughtUp { expected: Option<u64>, applied: Option<u64> },
+    ComponentNotHealthy { component: String, health: ComponentHealth, detail: Option<String> },
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EquityDiscontinuity { at_shutdown, after_restart } => write!(
+                f,
+                "equity changed across restart: {:.6} at shutdown, {:.6} after restart",
+                at_shutdown, after_restart
+            ),
+            Self::GeneratorStateMismatch { generator } => {
+                write!(f, "generator {} state differs from its snapshot", generator)
+            }
+            Self::DuplicateExecution { signal_id, count } => {
+                write!(f, "signal {} executed {} times", signal_id, count)
+            }
+            Self::DuplicateFill { market_id, sequence } => {
+                write!(f, "event {} filled market {} more than once", sequence, market_id)
+            }
+            Self::PendingSignalLost { signal_id } => {
+                write!(f, "pending signal {} lost across restart", signal_id)
+            }
+            Self::StalePendingSignal { signal_id, expires_at } => {
+                write!(f, "signal {} still pending after expiry at {}", signal_id, expires_at)
+            }
+            Self::NotCaughtUp { expected, applied } => {
+                write!(f, "stack applied events up to {:?}, feed produced up to {:?}", applied, expected)
+            }
+            Self::ComponentNotHealthy { component, health, detail } => {
+                write!(f, "{} is {:?}", component, health)?;
+                if let Some(detail) = detail {
+                    write!(f, ": {}", detail)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Outcome of a lifecycle check
+#[derive(Debug, Clone)]
+pub struct LifecycleReport {
+    pub events_before_shutdown: usize,
+    /// Events applied during catch-up, i.e. produced while the stack was down
+    pub events_caught_up: usize,
+    /// Redelivered events the restarted stack recognised as already applied
+    pub duplicates_skipped: usize,
+    pub events_after_restart: usize,
+    pub executions: usize,
+    pub pending_at_shutdown: usize,
+    /// Pending at shutdown and either filled or still working afterwards
+    pub pending_resumed: usize,
+    /// Pending at shutdown and expired or cancelled afterwards
+    pub pending_expired: usize,
+    pub equity_at_shutdown: f64,
+    pub equity_after_restart: f64,
+    pub final_equity: f64,
+    pub health: Vec<ComponentStatus>,
+    pub violations: Vec<InvariantViolation>,
+}
+
+impl LifecycleReport {
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl fmt::Display for LifecycleReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "lifecycle-check: {}", if self.passed() { "PASS" } else { "FAIL" })?;
+        writeln!(
+            f,
+            "  events: {} before shutdown, {} caught up, {} redelivered and skipped, {} after restart",
+            self.events_before_shutdown, self.events_caught_up, self.duplicates_skipped, self.events_after_restart
+        )?;
+        writeln!(
+            f,
+            "  signals: {} executions, {} pending at shutdown ({} resumed, {} expired)",
+            self.executions, self.pending_at_shutdown, self.pending_resumed, self.pending_expired
+        )?;
+        writeln!(
+            f,
+            "  equity: {:.4} at shutdown, {:.4} after restart, {:.4} final",
+            self.equity_at_shutdown, self.equity_after_restart, self.final_equity
+        )?;
+        for status in &self.health {
+            writeln!(f, "  {}: {:?}", status.component, status.health)?;
+        }
+        for violation in &self.violations {
+            writeln!(f, "  VIOLATION: {}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+/// Run the stack, shut it down, restart it from its snapshot and check
+/// the invariants that must hold across the restart.
+///
+/// Returns an error only if the check itself could not run; invariant
+/// violations are reported in the `LifecycleReport`.
+pub async fn run_lifecycle_check(scenario: &LifecycleScenario) -> Result<LifecycleReport> {
+    let start = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+    let shutdown_at = scenario.run_for;
+    let restart_at = shutdown_at + scenario.restart_gap;
+
+    let mut stream = SyntheticConnector::new(scenario.feed.clone()).events(start);
+    let initial_markets: Vec<_> = stream.live_markets().cloned().collect();
+    let events: Vec<SyntheticEvent> = stream.take_until(restart_at + scenario.resume_for);
+    let at = |event: &SyntheticEvent| start + event.offset;
+
+    let mut stack = EmbeddedStack::new(scenario.stack.clone(), start)?;
+    for market in initial_markets {
+        stack.add_market(market);
+    }
+
+    let sequenced = || events.iter().enumerate().map(|(i, e)| (i as u64, e));
+
+    let mut events_before_shutdown: usize = 0;
+    for (sequence, event) in sequenced().take_while(|(_, e)| e.offset <= shutdown_at) {
+        stack.process(sequence, at(event), &event.event).await?;
+        events_before_shutdown += 1;
+    }
+
+    let snapshot = stack.shutdown();
+    let equity_at_shutdown = snapshot.equity();
+    drop(stack);
+    info!(
+        "Shut down after {} events with {} pending signals",
+        events_before_shutdown,
+        snapshot.pending_signals.len()
+    );
+
+    let restored = load_snapshot(&store_snapshot(&snapshot, scenario.artifact_dir.as_ref())?)?;
+    let mut stack = EmbeddedStack::restore(scenario.stack.clone(), restored)?;
+    let equity_after_restart = stack.equity();
+
+    let mut violations = Vec::new();
+    if (equity_after_restart - equity_at_shutdown).abs() > EQUITY_TOLERANCE {
+        violations.push(InvariantViolation::EquityDiscontinuity {
+            at_shutdown: equity_at_shutdown,
+            after_restart: equity_after_restart,
+        });
+    }
+    for (generator, (restored, captured)) in
+        stack.generator_states().iter().zip(&snapshot.generator_states).enumerate()
+    {
+        if restored != captured {
+            violations.push(InvariantViolation::GeneratorStateMismatch { generator });
+        }
+    }
+
+    // Catch up from a lagging offset, then keep running
+    let redeliver_from = events_before_shutdown.saturating_sub(scenario.redelivered_events);
+    let mut events_caught_up = 0;
+    let mut events_after_restart = 0;
+    for (sequence, event) in sequenced().skip(redeliver_from) {
+        if stack.process(sequence, at(event), &event.event).await? {
+            if event.offset <= restart_at {
+                events_caught_up += 1;
+            } else {
+                events_after_restart += 1;
+            }
+        }
+    }
+
+    let last_sequence = (events.len() as u64).checked_sub(1);
+    violations.extend(check_invariants(&snapshot, &stack, last_sequence));
+
+    let pending_at_shutdown: HashSet<Uuid> = snapshot.pending_signals.iter().map(|s| s.id).collect();
+    let expired: HashSet<Uuid> = stack.expired_signals().iter().copied().collect();
+    let resumed: HashSet<Uuid> = stack
+        .executions()
+        .iter()
+        .map(|e| e.signal_id)
+        .chain(stack.pending_signals().iter().map(|s| s.id))
+        .collect();
+
+    let health = stack.health();
+    Ok(LifecycleReport {
+        events_before_shutdown,
+        events_caught_up,
+        duplicates_skipped: stack.duplicates_skipped(),
+        events_after_restart,
+        executions: stack.executions().len(),
+        pending_at_shutdown: pending_at_shutdown.len(),
+        pending_resumed: pending_at_shutdown.intersection(&resumed).count(),
+        pending_expired: pending_at_shutdown.intersection(&expired).count(),
+        equity_at_shutdown,
+        equity_after_restart,
+        final_equity: stack.equity(),
+        health,
+        violations,
+    })
+}
+
+/// Invariants of a restarted stack after it has run past the restart.
+///
+/// `last_sequence` is the last sequence the feed produced.
+pub fn check_invariants(
+    snapshot: &StackSnapshot,
+    stack: &EmbeddedStack,
+    last_sequence: Option<u64>,
+) -> Vec<InvariantViolation> {
+    let mut violations = Vec::new();
+
+    let mut by_signal: HashMap<Uuid, usize> = HashMap::new();
+    let mut by_event: HashMap<(MarketId, u64), usize> = HashMap::new();
+    for execution in stack.executions() {
+        *by_signal.entry(execution.signal_id).or_default() += 1;
+        *by_event.entry((execution.market_id, execution.sequence)).or_default() += 1;
+    }
+    let mut duplicates: Vec<_> = by_signal.into_iter().filter(|(_, count)| *count > 1).collect();
+    duplicates.sort();
+    violations.extend(
+        duplicates
+            .into_iter()
+            .map(|(signal_id, count)| InvariantViolation::DuplicateExecution { signal_id, count }),
+    );
+    let mut double_fills: Vec<_> = by_event.into_iter().filter(|(_, count)| *count > 1).collect();
+    double_fills.sort_by_key(|((_, sequence), _)| *sequence);
+    violations.extend(
+        double_fills
+            .into_iter()
+            .map(|((market_id, sequence), _)| InvariantViolation::DuplicateFill { market_id, sequence }),
+    );
+
+    let executed: HashSet<Uuid> = stack.executions().iter().map(|e| e.signal_id).collect();
+    let expired: HashSet<Uuid> = stack.expired_signals().iter().copied().collect();
+    let working: HashSet<Uuid> = stack.pending_signals().iter().map(|s| s.id).collect();
+    for signal in &snapshot.pending_signals {
+        if !executed.contains(&signal.id) && !expired.contains(&signal.id) && !working.contains(&signal.id) {
+            violations.push(InvariantViolation::PendingSignalLost { signal_id: signal.id });
+        }
+    }
+    for signal in stack.pending_signals() {
+        if let Some(expires_at) = signal.expires_at.filter(|at| *at <= stack.sim_time()) {
+            violations.push(InvariantViolation::StalePendingSignal {
+                signal_id: signal.id,
+                expires_at,
+            });
+        }
+    }
+
+    if stack.last_sequence() != last_sequence {
+        violations.push(InvariantViolation::NotCaughtUp {
+            expected: last_sequence,
+            applied: stack.last_sequence(),
+        });
+    }
+
+    for status in stack.health() {
+        if status.health != ComponentHealth::Healthy {
+            violations.push(InvariantViolation::ComponentNotHealthy {
+                component: status.component,
+                health: status.health,
+                detail: status.detail,
+            });
+        }
+    }
+
+    violations
+}
+
+/// Serialize the snapshot, to `artifact_dir` if given
+fn store_snapshot(snapshot: &StackSnapshot, artifact_dir: Option<&PathBuf>) -> Result<String> {
+    let json = serde_json::to_string_pretty(snapshot).context("Failed to serialize snapshot")?;
+
+    match artifact_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)?;
+            let path = dir.join("snapshot.json");
+            std::fs::write(&path, &json).with_context(|| format!("Failed to write {}", path.display()))?;
+            info!("Wrote shutdown snapshot to {}", path.display());
+            std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))
+        }
+        None => Ok(json),
+    }
+}
+
+fn load_snapshot(json: &str) -> Result<StackSnapshot> {
+    serde_json::from_str(json).context("Failed to parse snapshot")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn short_scenario() -> LifecycleScenario {
+        LifecycleScenario {
+            feed: SyntheticScenario {
+                market_count: 10,
+                seed: 3,
+                ..LifecycleScenario::default().feed
+            },
+            run_for: Duration::minutes(3),
+            resume_for: Duration::minutes(2),
+            redelivered_events: 50,
+            ..LifecycleScenario::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_invariants_flags_double_execution() {
+        let scenario = short_scenario();
+        let start = Utc::now();
+        let mut stack = EmbeddedStack::new(scenario.stack.clone(), start).unwrap();
+        let mut stream = SyntheticConnector::new(scenario.feed.clone()).events(start);
+        for market in stream.live_markets().cloned().collect::<Vec<_>>() {
+            stack.add_market(market);
+        }
+        let events = stream.take_until(scenario.run_for);
+        for (i, event) in events.iter().enumerate() {
+            stack.process(i as u64, start + event.offset, &event.event).await.unwrap();
+        }
+        assert!(!stack.executions().is_empty());
+
+        let snapshot = stack.shutdown();
+        let last = events.len() as u64 - 1;
+
+        let restored = EmbeddedStack::restore(scenario.stack.clone(), snapshot.clone()).unwrap();
+        assert!(check_invariants(&snapshot, &restored, Some(last)).is_empty());
+
+        // A stack that refilled an executed signal, lost a pending one and
+        // never caught up is caught on every count
+        let mut corrupted = snapshot.clone();
+        corrupted.executions.push(corrupted.executions[0].clone());
+        let lost = corrupted.pending_signals.pop();
+        let restored = EmbeddedStack::restore(scenario.stack.clone(), corrupted).unwrap();
+        let violations = check_invariants(&snapshot, &restored, Some(last + 1));
+
+        let signal_id = snapshot.executions[0].signal_id;
+        assert!(violations.contains(&InvariantViolation::DuplicateExecution { signal_id, count: 2 }));
+        assert!(violations.iter().any(|v| matches!(v, InvariantViolation::DuplicateFill { .. })));
+        if let Some(lost) = lost {
+            assert!(violations.contains(&InvariantViolation::PendingSignalLost { signal_id: lost.id }));
+        }
+        assert!(violations.contains(&InvariantViolation::NotCaughtUp {
+            expected: Some(last + 1),
+            applied: Some(last),
+        }));
+    }
+}
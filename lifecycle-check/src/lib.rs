@@ -0,0 +1,11 @@
+//! Release gate: drain, snapshot, restart and resume a trading stack
+//!
+//! Runs an `EmbeddedStack` against the `SyntheticConnector`, shuts it down
+//! into a snapshot, restarts it from that snapshot while the feed replays
+//! what was missed, and checks that state carried over consistently.
+
+mod check;
+mod stack;
+
+pub use check::{check_invariants, run_lifecycle_check, InvariantViolation, LifecycleReport, LifecycleScenario};
+pub use stack::{ComponentHealth, ComponentStatus, EmbeddedStack, Execution, StackConfig, StackSnapshot};
@@ -0,0 +1,31 @@
+use chrono::Duration;
+use lifecycle_check::{run_lifecycle_check, LifecycleScenario};
+use std::path::PathBuf;
+use tracing_subscriber::fmt;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    fmt()
+        .with_max_level(tracing::Level::WARN)
+        .init();
+
+    // Run with: cargo run -p lifecycle-check -- [run_minutes] [seed] [artifact_dir]
+    let mut args = std::env::args().skip(1);
+    let mut scenario = LifecycleScenario::default();
+    if let Some(minutes) = args.next() {
+        scenario.run_for = Duration::minutes(minutes.parse()?);
+    }
+    if let Some(seed) = args.next() {
+        scenario.feed.seed = seed.parse()?;
+    }
+    scenario.artifact_dir = args.next().map(PathBuf::from);
+
+    let report = run_lifecycle_check(&scenario).await?;
+    print!("{}", report);
+
+    if !report.passed() {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,487 @@
+//! Embedded trading stack driven in-process by market events
+//!
+//! Wires the portfolio, the signal pipeline and a simulated executor
+//! together without Kafka or a database, so a whole stack can be run,
+//! shut down into a `StackSnapshot` and restarted from it.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use common::{Market, MarketEvent, MarketId, OrderBook, OrderSide, PriceTick, Trade};
+use portfolio_risk::{Portfolio, PortfolioRiskManager, RiskConfig};
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use signal_generation::{
+    Level, MarketMakingConfig, MarketMakingGenerator, OrderBookSnapshot, PipelineConfig, ResearchOutput,
+    SentimentScore, SignalDirection, SignalInput, PairSide, SignalPipeline, StateUpdate, TradeSignal,
+};
+use std::collections::HashMap;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Configuration of the embedded stack
+#[derive(Debug, Clone)]
+pub struct StackConfig {
+    pub risk: RiskConfig,
+    /// Simulated time a signal stays executable after it is generated
+    pub signal_ttl: Duration,
+    /// Shares quoted per market making signal
+    pub quote_size: Decimal,
+}
+
+impl Default for StackConfig {
+    fn default() -> Self {
+        Self {
+            risk: RiskConfig::default(),
+            signal_ttl: Duration::seconds(60),
+            quote_size: Decimal::from(20),
+        }
+    }
+}
+
+/// Health of one stack component
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ComponentHealth {
+    Healthy,
+    Degraded,
+    Unhealthy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentStatus {
+    pub component: String,
+    pub health: ComponentHealth,
+    pub detail: Option<String>,
+}
+
+impl ComponentStatus {
+    fn new(component: &str, health: ComponentHealth, detail: Option<String>) -> Self {
+        Self {
+            component: component.to_string(),
+            health,
+            detail,
+        }
+    }
+}
+
+/// A fill of one of the stack's own signals
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Execution {
+    pub signal_id: Uuid,
+    pub market_id: MarketId,
+    pub outcome_id: String,
+    pub price: f64,
+    pub size: f64,
+    /// Sequence number of the event that triggered the fill
+    pub sequence: u64,
+    pub executed_at: DateTime<Utc>,
+}
+
+/// Everything a stopped stack needs to resume where it left off
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StackSnapshot {
+    /// Last event sequence applied before shutdown
+    pub last_sequence: Option<u64>,
+    pub sim_time: DateTime<Utc>,
+    pub markets: Vec<Market>,
+    pub portfolio: Portfolio,
+    /// Per-generator state, in pipeline order
+    pub generator_states: Vec<Option<serde_json::Value>>,
+    pub pending_signals: Vec<TradeSignal>,
+    pub executions: Vec<Execution>,
+    /// Signals that expired or were cancelled before filling
+    pub expired_signals: Vec<Uuid>,
+}
+
+impl StackSnapshot {
+    /// Marked-to-market value of open positions plus realized P&L
+    pub fn equity(&self) -> f64 {
+        self.portfolio.total_value() + self.portfolio.total_pnl()
+    }
+}
+
+/// Portfolio, signal pipeline and executor running in one process
+pub struct EmbeddedStack {
+    config: StackConfig,
+    portfolio: PortfolioRiskManager,
+    pipeline: SignalPipeline,
+    markets: HashMap<MarketId, Market>,
+    pending: Vec<TradeSignal>,
+    executions: Vec<Execution>,
+    expired: Vec<Uuid>,
+    last_sequence: Option<u64>,
+    sim_time: DateTime<Utc>,
+    /// Events skipped because they were already applied
+    duplicates_skipped: usize,
+    /// Last error applying an event to the portfolio, e.g. a tripped breaker
+    portfolio_error: Option<String>,
+    draining: bool,
+}
+
+impl EmbeddedStack {
+    /// Start an empty stack at simulated time `start`
+    pub fn new(config: StackConfig, start: DateTime<Utc>) -> Result<Self> {
+        let portfolio = PortfolioRiskManager::with_config(config.risk.clone())?;
+        let pipeline = Self::build_pipeline(&config);
+
+        Ok(Self {
+            config,
+            portfolio,
+            pipeline,
+            markets: HashMap::new(),
+            pending: Vec::new(),
+            executions: Vec::new(),
+            expired: Vec::new(),
+            last_sequence: None,
+            sim_time: start,
+            duplicates_skipped: 0,
+            portfolio_error: None,
+            draining: false,
+        })
+    }
+
+    /// Restart a stack from the snapshot taken at shutdown
+    pub fn restore(config: StackConfig, snapshot: StackSnapshot) -> Result<Self> {
+        let portfolio = PortfolioRiskManager::restore(config.risk.clone(), snapshot.portfolio)?;
+        let mut pipeline = Self::build_pipeline(&config);
+        pipeline
+            .restore_generator_states(snapshot.generator_states)
+            .context("Failed to restore generator state")?;
+
+        info!(
+            "Restored stack at sequence {:?}: {} markets, {} pending signals",
+            snapshot.last_sequence,
+            snapshot.markets.len(),
+            snapshot.pending_signals.len()
+        );
+
+        Ok(Self {
+            config,
+            portfolio,
+            pipeline,
+            markets: snapshot.markets.into_iter().map(|m| (m.id, m)).collect(),
+            pending: snapshot.pending_signals,
+            executions: snapshot.executions,
+            expired: snapshot.expired_signals,
+            last_sequence: snapshot.last_sequence,
+            sim_time: snapshot.sim_time,
+            duplicates_skipped: 0,
+            portfolio_error: None,
+            draining: false,
+        })
+    }
+
+    fn build_pipeline(config: &StackConfig) -> SignalPipeline {
+        let pipeline_config = PipelineConfig {
+            min_edge: Decimal::from_str_exact("0.01").unwrap(),
+            ..PipelineConfig::default()
+        };
+        let market_making = MarketMakingConfig {
+            base_position_size: config.quote_size,
+            ..MarketMakingConfig::default()
+        };
+
+        SignalPipeline::new(pipeline_config).add_generator(Box::new(MarketMakingGenerator::new(market_making)))
+    }
+
+    /// Register a market that is already live, without an event
+    pub fn add_market(&mut self, market: Market) {
+        self.portfolio.set_market_category(market.id, &market.category);
+        self.markets.insert(market.id, market);
+    }
+
+    /// Apply one event at simulated time `at`.
+    ///
+    /// Events are applied at most once: a `sequence` at or below the last
+    /// one applied is skipped and `false` is returned, so a source can
+    /// redeliver from an older offset after a restart.
+    pub async fn process(&mut self, sequence: u64, at: DateTime<Utc>, event: &MarketEvent) -> Result<bool> {
+        if self.last_sequence.is_some_and(|last| sequence <= last) {
+            self.duplicates_skipped += 1;
+            return Ok(false);
+        }
+
+        self.sim_time = self.sim_time.max(at);
+        self.expire_pending();
+
+        match event {
+            MarketEvent::MarketCreated(market) => {
+                self.markets.insert(market.id, market.clone());
+                self.apply_to_portfolio(event);
+            }
+            MarketEvent::OrderBookUpdate(book) => {
+                if !self.draining {
+                    self.quote(book).await?;
+                }
+            }
+            MarketEvent::PriceTick(tick) => {
+                self.apply_to_portfolio(event);
+                self.fill_pending(sequence, tick);
+            }
+            MarketEvent::MarketResolved { market_id, .. }
+            | MarketEvent::MarketInvalidated { market_id, .. } => {
+                self.apply_to_portfolio(event);
+                self.cancel_pending(*market_id);
+                self.markets.remove(market_id);
+            }
+            MarketEvent::ResolutionReversed { .. } => self.apply_to_portfolio(event),
+            // The harness feeds resolutions by market; condition-keyed ones need a router
+            MarketEvent::ConditionResolved { .. } => {}
+            // Trades in the market feed are other participants' fills
+            MarketEvent::Trade(_) => {}
+        }
+
+        self.last_sequence = Some(sequence);
+        Ok(true)
+    }
+
+    /// Stop generating signals, expire what is overdue and capture state
+    pub fn shutdown(&mut self) -> StackSnapshot {
+        self.draining = true;
+        self.expire_pending();
+
+        info!(
+            "Stack drained at sequence {:?}: {} pending signals, {} executions",
+            self.last_sequence,
+            self.pending.len(),
+            self.executions.len()
+        );
+
+        StackSnapshot {
+            last_sequence: self.last_sequence,
+            sim_time: self.sim_time,
+            markets: self.markets.values().cloned().collect(),
+            portfolio: self.portfolio.portfolio().clone(),
+            generator_states: self.pipeline.snapshot_generator_states(),
+            pending_signals: self.pending.clone(),
+            executions: self.executions.clone(),
+            expired_signals: self.expired.clone(),
+        }
+    }
+
+    /// Health of every component
+    pub fn health(&self) -> Vec<ComponentStatus> {
+        let portfolio = match &self.portfolio_error {
+            Some(error) => ComponentStatus::new("portfolio", ComponentHealth::Unhealthy, Some(error.clone())),
+            None => ComponentStatus::new("portfolio", ComponentHealth::Healthy, None),
+        };
+
+        let pipeline = if self.draining {
+            ComponentStatus::new("signal-pipeline", ComponentHealth::Degraded, Some("draining".to_string()))
+        } else if self.pipeline.generator_count() == 0 {
+            ComponentStatus::new("signal-pipeline", ComponentHealth::Unhealthy, Some("no generators".to_string()))
+        } else {
+            ComponentStatus::new("signal-pipeline", ComponentHealth::Healthy, None)
+        };
+
+        let stale = self.pending.iter().filter(|s| self.is_expired(s)).count();
+        let executor = if stale > 0 {
+            ComponentStatus::new(
+                "executor",
+                ComponentHealth::Degraded,
+                Some(format!("{} pending signals past expiry", stale)),
+            )
+        } else {
+            ComponentStatus::new("executor", ComponentHealth::Healthy, None)
+        };
+
+        vec![portfolio, pipeline, executor]
+    }
+
+    /// Marked-to-market value of open positions plus realized P&L
+    pub fn equity(&self) -> f64 {
+        let portfolio = self.portfolio.portfolio();
+        portfolio.total_value() + portfolio.total_pnl()
+    }
+
+    pub fn last_sequence(&self) -> Option<u64> {
+        self.last_sequence
+    }
+
+    pub fn sim_time(&self) -> DateTime<Utc> {
+        self.sim_time
+    }
+
+    pub fn pending_signals(&self) -> &[TradeSignal] {
+        &self.pending
+    }
+
+    pub fn executions(&self) -> &[Execution] {
+        &self.executions
+    }
+
+    pub fn expired_signals(&self) -> &[Uuid] {
+        &self.expired
+    }
+
+    pub fn duplicates_skipped(&self) -> usize {
+        self.duplicates_skipped
+    }
+
+    pub fn generator_states(&self) -> Vec<Option<serde_json::Value>> {
+        self.pipeline.snapshot_generator_states()
+    }
+
+    fn apply_to_portfolio(&mut self, event: &MarketEvent) {
+        if let Err(e) = self.portfolio.process_event(event) {
+            warn!("Portfolio rejected event: {}", e);
+            self.portfolio_error = Some(e.to_string());
+        }
+    }
+
+    /// Run the pipeline on a book update and queue new signals
+    async fn quote(&mut self, book: &OrderBook) -> Result<()> {
+        let Some(market) = self.markets.get(&book.market_id) else {
+            return Ok(());
+        };
+
+        let input = SignalInput {
+            market: market.clone(),
+            research_output: ResearchOutput {
+                market_id: market.id,
+                analysis: String::new(),
+                sentiment: SentimentScore {
+                    overall: 0.0,
+                    sources: Vec::new(),
+                },
+                confidence: 0.0,
+                probability_estimate: None,
+                key_factors: Vec::new(),
+                timestamp: self.sim_time,
+            },
+            order_book: Some(Self::book_snapshot(book)),
+            price_history: Vec::new(),
+        };
+
+        for mut signal in self.pipeline.process(&input).await? {
+            if signal.is_cancel() {
+                let quote_id = signal.quote_id();
+                self.pending.retain(|p| p.quote_id().is_none() || p.quote_id() != quote_id);
+                debug!("Cancelled quote {:?} in market {}", quote_id, signal.market_id);
+                continue;
+            }
+
+            // One working order per market and side
+            let working = self
+                .pending
+                .iter()
+                .any(|p| p.market_id == signal.market_id && p.direction == signal.direction);
+            if working {
+                continue;
+            }
+
+            signal.created_at = self.sim_time;
+            signal.expires_at = Some(self.sim_time + self.config.signal_ttl);
+            debug!("Queued signal {} for market {}", signal.id, signal.market_id);
+            self.pending.push(signal);
+        }
+
+        Ok(())
+    }
+
+    fn book_snapshot(book: &OrderBook) -> OrderBookSnapshot {
+        let levels = |orders: &[common::Order]| {
+            orders
+                .iter()
+                .map(|o| Level {
+                    outcome_id: o.outcome_id.to_string(),
+                    price: Decimal::from_f64(o.price).unwrap_or_default(),
+                    size: Decimal::from_f64(o.size).unwrap_or_default(),
+                })
+                .collect()
+        };
+
+        OrderBookSnapshot {
+            market_id: book.market_id,
+            bids: levels(&book.bids),
+            asks: levels(&book.asks),
+            timestamp: book.timestamp,
+        }
+    }
+
+    /// Fill pending signals the tick's YES price has crossed
+    fn fill_pending(&mut self, sequence: u64, tick: &PriceTick) {
+        let Some(market) = self.markets.get(&tick.market_id) else {
+            return;
+        };
+        let (Some(yes), Some(no)) = (market.outcomes.first(), market.outcomes.get(1)) else {
+            return;
+        };
+        if tick.outcome_id != yes.id {
+            return;
+        }
+        let (yes_id, no_id) = (yes.id.clone(), no.id.clone());
+
+        let (filled, pending): (Vec<TradeSignal>, Vec<TradeSignal>) =
+            std::mem::take(&mut self.pending).into_iter().partition(|signal| {
+                let entry = signal.entry_price.to_f64().unwrap_or(0.0);
+                signal.market_id == tick.market_id
+                    && match signal.direction {
+                        SignalDirection::Long => tick.price <= entry,
+                        SignalDirection::Short => 1.0 - tick.price <= entry,
+                        SignalDirection::Neutral => false,
+                    }
+            });
+        self.pending = pending;
+
+        for signal in filled {
+            let (outcome_id, outcome_side) = match signal.direction {
+                SignalDirection::Short => (no_id.clone(), PairSide::No),
+                _ => (yes_id.clone(), PairSide::Yes),
+            };
+            let price = signal.entry_price.to_f64().unwrap_or(0.0);
+            let size = signal.position_size.to_f64().unwrap_or(0.0);
+
+            // Both sides are bought: a Short is a buy of NO
+            self.apply_to_portfolio(&MarketEvent::Trade(Trade {
+                id: Uuid::new_v4(),
+                market_id: signal.market_id,
+                outcome_id: outcome_id.clone(),
+                price,
+                size,
+                side: OrderSide::Buy,
+                timestamp: self.sim_time,
+            }));
+            self.pipeline.update_state(
+                signal.market_id,
+                &StateUpdate::TradeExecution {
+                    signal_id: Some(signal.id),
+                    outcome_id: Some(outcome_id.to_string()),
+                    outcome_side,
+                    side: OrderSide::Buy,
+                    size: signal.position_size,
+                    price: signal.entry_price,
+                },
+            );
+
+            debug!("Filled signal {} at {:.4}", signal.id, price);
+            self.executions.push(Execution {
+                signal_id: signal.id,
+                market_id: signal.market_id,
+                outcome_id: outcome_id.to_string(),
+                price,
+                size,
+                sequence,
+                executed_at: self.sim_time,
+            });
+        }
+    }
+
+    fn is_expired(&self, signal: &TradeSignal) -> bool {
+        signal.expires_at.is_some_and(|at| at <= self.sim_time)
+    }
+
+    fn expire_pending(&mut self) {
+        let (expired, pending): (Vec<TradeSignal>, Vec<TradeSignal>) =
+            std::mem::take(&mut self.pending).into_iter().partition(|s| self.is_expired(s));
+        self.pending = pending;
+        self.expired.extend(expired.iter().map(|s| s.id));
+    }
+
+    /// Drop the working orders of a market that can no longer trade
+    fn cancel_pending(&mut self, market_id: MarketId) {
+        let (cancelled, pending): (Vec<TradeSignal>, Vec<TradeSignal>) =
+            std::mem::take(&mut self.pending).into_iter().partition(|s| s.market_id == market_id);
+        self.pending = pending;
+        self.expired.extend(cancelled.iter().map(|s| s.id));
+    }
+}
@@ -0,0 +1,31 @@
+use chrono::Duration;
+use lifecycle_check::{run_lifecycle_check, ComponentHealth, LifecycleScenario};
+
+#[tokio::test]
+async fn test_short_scenario_survives_restart() {
+    let artifact_dir = std::env::temp_dir().join(format!("lifecycle-check-{}", std::process::id()));
+    let scenario = LifecycleScenario {
+        run_for: Duration::minutes(4),
+        restart_gap: Duration::seconds(20),
+        resume_for: Duration::minutes(2),
+        redelivered_events: 100,
+        artifact_dir: Some(artifact_dir.clone()),
+        ..LifecycleScenario::default()
+    };
+
+    let report = run_lifecycle_check(&scenario).await.unwrap();
+    assert!(report.passed(), "{}", report);
+
+    // The scenario exercised what it checks
+    assert!(report.executions > 0);
+    assert!(report.pending_at_shutdown > 0);
+    assert_eq!(report.pending_resumed + report.pending_expired, report.pending_at_shutdown);
+    assert_eq!(report.duplicates_skipped, scenario.redelivered_events);
+    assert!(report.events_caught_up > 0);
+    assert!(report.events_after_restart > 0);
+    assert!((report.equity_after_restart - report.equity_at_shutdown).abs() < 1e-6);
+    assert!(report.health.iter().all(|s| s.health == ComponentHealth::Healthy));
+    assert!(artifact_dir.join("snapshot.json").exists());
+
+    std::fs::remove_dir_all(artifact_dir).unwrap();
+}
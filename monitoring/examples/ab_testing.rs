@@ -1,6 +1,6 @@
 use anyhow::Result;
 use chrono::{Duration, Utc};
-use common::{AbTest, AbTestStatus, OrderSide, Signal, Trade};
+use common::{AbTest, AbTestMethod, AbTestStatus, AlphaSpending, MarketId, OrderSide, Signal, Trade};
 use monitoring::{AbTestEngine, AbTestManager};
 use sqlx::postgres::PgPoolOptions;
 use std::sync::Arc;
@@ -27,7 +27,7 @@ async fn main() -> Result<()> {
     info!("=== A/B Testing Framework Example ===\n");
 
     let manager = AbTestManager::new(pool.clone());
-    let engine = AbTestEngine::new(pool.clone());
+    let engine = AbTestEngine::new(pool.clone(), 0.02);
 
     manager.initialize().await?;
 
@@ -47,6 +47,10 @@ async fn main() -> Result<()> {
         allocation_ratio: 0.5,  // 50/50 split between strategies
         min_sample_size: 50,    // Need at least 50 trades per strategy
         statistical_significance: 0.95,  // 95% confidence level
+        method: AbTestMethod::Welch,
+        looks_planned: 4,  // Check for an early stop every 25% of the sample
+        alpha_spending: AlphaSpending::ObrienFleming,
+        current_look: 0,
     };
 
     manager.create_test(test).await?;
@@ -61,29 +65,17 @@ async fn main() -> Result<()> {
     info!("------------------------------------");
 
     // In a real scenario, you'd iterate through incoming markets
-    let market_1 = Uuid::new_v4();
-    let market_2 = Uuid::new_v4();
+    let market_1 = MarketId::random();
+    let market_2 = MarketId::random();
 
-    // Random assignment based on allocation ratio
-    let rng = fastrand::f32();
-    let strategy_a = if rng < 0.5 {
-        "sentiment-v1"
-    } else {
-        "sentiment-v2"
-    };
-
-    manager.assign_market(test_id, market_1, strategy_a).await?;
-    info!("✓ Assigned market {} to {}", market_1, strategy_a);
+    // Hashed with the test id against the allocation ratio, so calling
+    // again (after a restart, from another worker) gives the same answer
+    let strategy_1 = manager.assign_market_auto(test_id, market_1).await?;
+    info!("✓ Assigned market {} to {}", market_1, strategy_1);
 
-    let rng = fastrand::f32();
-    let strategy_b = if rng < 0.5 {
-        "sentiment-v1"
-    } else {
-        "sentiment-v2"
-    };
-
-    manager.assign_market(test_id, market_2, strategy_b).await?;
-    info!("✓ Assigned market {} to {}", market_2, strategy_b);
+    // Or keep each category split 50/50 on its own
+    let strategy_2 = manager.assign_market_stratified(test_id, market_2, "Politics").await?;
+    info!("✓ Assigned market {} to {}", market_2, strategy_2);
 
     // Example 3: Check assignment counts
     info!("Example 3: Monitoring assignment counts");
@@ -93,6 +85,17 @@ async fn main() -> Result<()> {
     info!("Strategy A assignments: {}", counts.count_a);
     info!("Strategy B assignments: {}", counts.count_b);
 
+    let report = manager.rebalance_report(test_id).await?;
+    for stratum in &report.strata {
+        info!(
+            "  {}: {} / {}{}",
+            stratum.category,
+            stratum.count_a,
+            stratum.count_b,
+            if stratum.imbalanced { " (out of balance)" } else { "" }
+        );
+    }
+
     // Check if we have enough samples
     let has_enough = engine.check_sample_size(test_id).await?;
     info!("Minimum sample size met: {}", has_enough);
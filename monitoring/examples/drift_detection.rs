@@ -33,6 +33,7 @@ async fn main() -> Result<()> {
         hit_rate_decline_threshold: 10.0,
         brier_score_increase_threshold: 0.05,  // Alert if Brier score increases by 0.05
         volume_decline_threshold: 30.0,
+        ..Default::default()
     };
 
     let drift_detector = DriftDetector::new(pool.clone(), config);
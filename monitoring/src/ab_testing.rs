@@ -1,25 +1,43 @@
 use anyhow::{Context, Result};
-use chrono::{DateTime, Duration, Utc};
-use common::{AbTest, AbTestResult, AbTestStatus, PerformanceMetrics};
+use chrono::{DateTime, Utc};
+use crate::metrics::MetricsCalculator;
+use common::{AbTest, AbTestMethod, AbTestResult, AbTestStatus, AlphaSpending, AssignmentSink, AttributedTrade, ExperimentAssignment, MarketId, MeanDifference, NonparametricResult, PerformanceMetrics, ShadowTrade};
 use sqlx::postgres::PgPool;
-use std::collections::HashMap;
+use statrs::distribution::{ContinuousCDF, Normal, StudentsT};
+use std::collections::BTreeMap;
 use std::sync::Arc;
-use tracing::{info, warn, debug};
+use tokio::sync::mpsc;
+use tracing::{info, debug, warn};
 use uuid::Uuid;
 
 /// A/B Test Manager - Manages strategy comparison tests
 pub struct AbTestManager {
     db_pool: Arc<PgPool>,
+    /// Largest gap between an arm's share of markets and its allocation
+    /// ratio before `rebalance_report` flags it
+    balance_tolerance: f64,
 }
 
+/// Default `balance_tolerance`: five percentage points
+const DEFAULT_BALANCE_TOLERANCE: f64 = 0.05;
+
 impl AbTestManager {
     pub fn new(db_pool: Arc<PgPool>) -> Self {
-        Self { db_pool }
+        Self {
+            db_pool,
+            balance_tolerance: DEFAULT_BALANCE_TOLERANCE,
+        }
+    }
+
+    /// Flag assignment shares further than `tolerance` from the allocation ratio
+    pub fn with_balance_tolerance(mut self, tolerance: f64) -> Self {
+        self.balance_tolerance = tolerance;
+        self
     }
 
     /// Initialize A/B test tables
     pub async fn initialize(&self) -> Result<()> {
-        sqlx::query(
+        sqlx::raw_sql(
             r#"
             CREATE TABLE IF NOT EXISTS ab_tests (
                 id UUID PRIMARY KEY,
@@ -29,9 +47,9 @@ impl AbTestManager {
                 start_time TIMESTAMPTZ NOT NULL,
                 end_time TIMESTAMPTZ,
                 status TEXT NOT NULL,
-                allocation_ratio NUMERIC(4, 2) NOT NULL,
+                allocation_ratio DOUBLE PRECISION NOT NULL,
                 min_sample_size BIGINT NOT NULL,
-                statistical_significance NUMERIC(4, 2) NOT NULL,
+                statistical_significance DOUBLE PRECISION NOT NULL,
                 created_at TIMESTAMPTZ DEFAULT NOW()
             );
 
@@ -53,12 +71,41 @@ impl AbTestManager {
                 confidence NUMERIC(4, 2),
                 p_value NUMERIC(10, 6),
                 recommendation TEXT NOT NULL,
+                nonparametric JSONB,
                 created_at TIMESTAMPTZ DEFAULT NOW(),
                 UNIQUE(test_id)
             );
 
+            ALTER TABLE ab_test_results ADD COLUMN IF NOT EXISTS nonparametric JSONB;
+            ALTER TABLE ab_test_results ADD COLUMN IF NOT EXISTS method TEXT;
+            ALTER TABLE ab_test_results ADD COLUMN IF NOT EXISTS effect_size JSONB;
+            ALTER TABLE ab_tests ADD COLUMN IF NOT EXISTS method TEXT NOT NULL DEFAULT 'welch';
+            -- NUMERIC(4, 2) rounded 1/3 allocations and cannot be read into an f64
+            ALTER TABLE ab_tests ALTER COLUMN allocation_ratio TYPE DOUBLE PRECISION;
+            ALTER TABLE ab_tests ALTER COLUMN statistical_significance TYPE DOUBLE PRECISION;
+            ALTER TABLE ab_tests ADD COLUMN IF NOT EXISTS looks_planned INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE ab_tests ADD COLUMN IF NOT EXISTS alpha_spending TEXT NOT NULL DEFAULT 'obrien_fleming';
+            ALTER TABLE ab_tests ADD COLUMN IF NOT EXISTS current_look INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE ab_test_assignments ADD COLUMN IF NOT EXISTS category TEXT;
+
+            CREATE TABLE IF NOT EXISTS ab_test_interim_looks (
+                id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+                test_id UUID NOT NULL REFERENCES ab_tests(id),
+                look_number INTEGER NOT NULL,
+                information_fraction DOUBLE PRECISION NOT NULL,
+                z_statistic DOUBLE PRECISION NOT NULL,
+                alpha_spent DOUBLE PRECISION NOT NULL,
+                boundary DOUBLE PRECISION NOT NULL,
+                conditional_power DOUBLE PRECISION,
+                decision TEXT NOT NULL,
+                winner TEXT,
+                created_at TIMESTAMPTZ DEFAULT NOW(),
+                UNIQUE(test_id, look_number)
+            );
+
             CREATE INDEX IF NOT EXISTS idx_abtest_status ON ab_tests(status);
             CREATE INDEX IF NOT EXISTS idx_abtest_assignment ON ab_test_assignments(test_id);
+            CREATE INDEX IF NOT EXISTS idx_abtest_interim_looks ON ab_test_interim_looks(test_id);
             "#,
         )
         .execute(self.db_pool.as_ref())
@@ -78,9 +125,10 @@ impl AbTestManager {
             r#"
             INSERT INTO ab_tests (
                 id, name, strategy_a, strategy_b, start_time,
-                end_time, status, allocation_ratio, min_sample_size, statistical_significance
+                end_time, status, allocation_ratio, min_sample_size, statistical_significance, method,
+                looks_planned, alpha_spending, current_look
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
             "#,
         )
         .bind(test.id)
@@ -98,6 +146,10 @@ impl AbTestManager {
         .bind(test.allocation_ratio)
         .bind(test.min_sample_size)
         .bind(test.statistical_significance)
+        .bind(test.method)
+        .bind(test.looks_planned)
+        .bind(test.alpha_spending)
+        .bind(test.current_look)
         .execute(self.db_pool.as_ref())
         .await
         .context("Failed to create A/B test")?;
@@ -110,7 +162,7 @@ impl AbTestManager {
     pub async fn assign_market(
         &self,
         test_id: Uuid,
-        market_id: Uuid,
+        market_id: MarketId,
         strategy: &str,
     ) -> Result<()> {
         sqlx::query(
@@ -131,11 +183,149 @@ impl AbTestManager {
         Ok(())
     }
 
+    /// Deterministically assign a market using the test's allocation ratio and record it
+    pub async fn assign_market_deterministic(
+        &self,
+        test: &AbTest,
+        market_id: MarketId,
+    ) -> Result<String> {
+        let strategy = test.assign_strategy(market_id);
+        self.record_assignment(test.id, market_id, strategy, None).await
+    }
+
+    /// Assign a market by hashing it with the test id against the allocation
+    /// ratio, and record it.
+    ///
+    /// The same market always gets the same strategy for a test, so this is
+    /// safe to call again after a restart or from several workers at once;
+    /// a market already assigned keeps whatever it was recorded with.
+    pub async fn assign_market_auto(&self, test_id: Uuid, market_id: MarketId) -> Result<String> {
+        let test = self.get_test(test_id).await?;
+        self.assign_market_deterministic(&test, market_id).await
+    }
+
+    /// Assign a market so each category's markets split by the allocation
+    /// ratio, and record it.
+    ///
+    /// Counts the category's assignments so far and picks the arm short of
+    /// its share (`AbTest::assign_stratified`), so both arms see a similar
+    /// category mix even when a few categories dominate. Which arm a market
+    /// gets depends on arrival order, but once recorded it never changes.
+    /// Markets assigned at the same moment may both see the same counts,
+    /// which `rebalance_report` would show.
+    pub async fn assign_market_stratified(&self, test_id: Uuid, market_id: MarketId, category: &str) -> Result<String> {
+        if let Some(existing) = self.get_assignment(test_id, market_id).await? {
+            return Ok(existing);
+        }
+
+        let test = self.get_test(test_id).await?;
+        let (count_a, count_b) = sqlx::query_as::<_, (i64, i64)>(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE assigned_strategy = $3),
+                COUNT(*) FILTER (WHERE assigned_strategy = $4)
+            FROM ab_test_assignments
+            WHERE test_id = $1 AND category = $2
+            "#,
+        )
+        .bind(test_id)
+        .bind(category)
+        .bind(&test.strategy_a)
+        .bind(&test.strategy_b)
+        .fetch_one(self.db_pool.as_ref())
+        .await
+        .context("Failed to count category assignments")?;
+
+        let strategy = test.assign_stratified(market_id, count_a, count_b);
+        self.record_assignment(test_id, market_id, strategy, Some(category)).await
+    }
+
+    /// Record an assignment unless the market has one, returning the one that stands
+    async fn record_assignment(
+        &self,
+        test_id: Uuid,
+        market_id: MarketId,
+        strategy: &str,
+        category: Option<&str>,
+    ) -> Result<String> {
+        let inserted: Option<String> = sqlx::query_scalar(
+            r#"
+            INSERT INTO ab_test_assignments (test_id, market_id, assigned_strategy, assigned_at, category)
+            VALUES ($1, $2, $3, NOW(), $4)
+            ON CONFLICT (test_id, market_id) DO NOTHING
+            RETURNING assigned_strategy
+            "#,
+        )
+        .bind(test_id)
+        .bind(market_id)
+        .bind(strategy)
+        .bind(category)
+        .fetch_optional(self.db_pool.as_ref())
+        .await
+        .context("Failed to assign market")?;
+
+        if let Some(strategy) = inserted {
+            debug!("Assigned market {} to strategy {} in test {}", market_id, strategy, test_id);
+            return Ok(strategy);
+        }
+
+        // Lost the race or assigned earlier; a fresh statement sees the winner
+        self.get_assignment(test_id, market_id)
+            .await?
+            .with_context(|| format!("Assignment of market {} in test {} vanished", market_id, test_id))
+    }
+
+    /// Compare each arm's share of assigned markets with the allocation
+    /// ratio, overall and per category
+    pub async fn rebalance_report(&self, test_id: Uuid) -> Result<RebalanceReport> {
+        let test = self.get_test(test_id).await?;
+        let strata = sqlx::query_as::<_, (String, i64, i64)>(
+            r#"
+            SELECT
+                COALESCE(category, $4),
+                COUNT(*) FILTER (WHERE assigned_strategy = $2),
+                COUNT(*) FILTER (WHERE assigned_strategy = $3)
+            FROM ab_test_assignments
+            WHERE test_id = $1
+            GROUP BY 1
+            ORDER BY 1
+            "#,
+        )
+        .bind(test_id)
+        .bind(&test.strategy_a)
+        .bind(&test.strategy_b)
+        .bind(UNCATEGORIZED)
+        .fetch_all(self.db_pool.as_ref())
+        .await
+        .context("Failed to count assignments by category")?;
+
+        let report = RebalanceReport::from_counts(&test, self.balance_tolerance, strata);
+        if report.needs_rebalance() {
+            warn!(
+                "A/B test {} assignments are out of balance: {:.1}% to {} against {:.1}% planned",
+                test_id,
+                report.share_a * 100.0,
+                test.strategy_a,
+                test.allocation_ratio * 100.0
+            );
+        }
+        Ok(report)
+    }
+
+    /// Load a test
+    pub async fn get_test(&self, test_id: Uuid) -> Result<AbTest> {
+        sqlx::query_as::<_, AbTest>("SELECT * FROM ab_tests WHERE id = $1")
+            .bind(test_id)
+            .fetch_one(self.db_pool.as_ref())
+            .await
+            .context("Test not found")
+    }
+
     /// Get which strategy was assigned to a market
     pub async fn get_assignment(
         &self,
         test_id: Uuid,
-        market_id: Uuid,
+        market_id: MarketId,
     ) -> Result<Option<String>> {
         let strategy = sqlx::query_scalar(
             "SELECT assigned_strategy FROM ab_test_assignments WHERE test_id = $1 AND market_id = $2"
@@ -243,151 +433,448 @@ impl AbTestManager {
         info!("Completed A/B test {}", test_id);
         Ok(())
     }
+
+    /// End a test without a winner
+    pub async fn mark_inconclusive(&self, test_id: Uuid) -> Result<()> {
+        sqlx::query(
+            "UPDATE ab_tests SET status = 'Inconclusive', end_time = NOW() WHERE id = $1"
+        )
+        .bind(test_id)
+        .execute(self.db_pool.as_ref())
+        .await
+        .context("Failed to mark test inconclusive")?;
+
+        info!("A/B test {} ended inconclusive", test_id);
+        Ok(())
+    }
+
+    /// Record the number of interim looks taken
+    async fn set_current_look(&self, test_id: Uuid, look_number: i32) -> Result<()> {
+        sqlx::query("UPDATE ab_tests SET current_look = $2 WHERE id = $1")
+            .bind(test_id)
+            .bind(look_number)
+            .execute(self.db_pool.as_ref())
+            .await
+            .context("Failed to update current look")?;
+
+        Ok(())
+    }
+}
+
+/// Writes the signal pipeline's experiment assignments to `ab_test_assignments`.
+///
+/// `GeneratorExperiment::generate` records synchronously, so assignments are
+/// queued and written by a background task. A market already assigned keeps
+/// its recorded strategy.
+pub struct AbTestAssignmentRecorder {
+    tx: mpsc::UnboundedSender<ExperimentAssignment>,
+}
+
+impl AbTestAssignmentRecorder {
+    /// Start the writer; must be called within a Tokio runtime. The task ends
+    /// once the recorder is dropped and the queue is written.
+    pub fn spawn(manager: Arc<AbTestManager>) -> (Self, tokio::task::JoinHandle<()>) {
+        let (tx, mut rx) = mpsc::unbounded_channel::<ExperimentAssignment>();
+        let writer = tokio::spawn(async move {
+            while let Some(assignment) = rx.recv().await {
+                let recorded = manager
+                    .record_assignment(
+                        assignment.experiment_id,
+                        assignment.market_id,
+                        &assignment.strategy,
+                        Some(&assignment.category),
+                    )
+                    .await;
+                match recorded {
+                    Ok(strategy) if strategy != assignment.strategy => warn!(
+                        "Market {} runs {} in experiment {} but was assigned {}",
+                        assignment.market_id, assignment.strategy, assignment.experiment_id, strategy
+                    ),
+                    Ok(_) => {}
+                    Err(e) => warn!(
+                        "Failed to record assignment of market {} in experiment {}: {:#}",
+                        assignment.market_id, assignment.experiment_id, e
+                    ),
+                }
+            }
+        });
+        (Self { tx }, writer)
+    }
+}
+
+impl AssignmentSink for AbTestAssignmentRecorder {
+    fn record(&self, assignment: ExperimentAssignment) {
+        if self.tx.send(assignment).is_err() {
+            warn!("A/B assignment writer has stopped, dropping assignment");
+        }
+    }
 }
 
 /// A/B Test Engine - Statistical analysis for comparing strategies
 pub struct AbTestEngine {
     db_pool: Arc<PgPool>,
     manager: AbTestManager,
+    risk_free_rate: f64,
 }
 
+/// Closed trade as `(timestamp, pnl, pnl_percent)`
+type TradeReturn = (DateTime<Utc>, f64, f64);
+
 impl AbTestEngine {
-    pub fn new(db_pool: Arc<PgPool>) -> Self {
+    pub fn new(db_pool: Arc<PgPool>, risk_free_rate: f64) -> Self {
         let manager = AbTestManager::new(db_pool.clone());
-        Self { db_pool, manager }
+        Self {
+            db_pool,
+            manager,
+            risk_free_rate,
+        }
     }
 
-    /// Analyze an A/B test and generate results
+    /// Analyze an A/B test and generate results, with the test's `method`
     pub async fn analyze_test(&self, test_id: Uuid) -> Result<AbTestResult> {
-        let test = sqlx::query_as::<_, AbTest>(
-            "SELECT * FROM ab_tests WHERE id = $1"
-        )
-        .bind(test_id)
-        .fetch_one(self.db_pool.as_ref())
-        .await
-        .context("Test not found")?;
+        let (test, trades_a, trades_b) = self.fetch_test_trades(test_id).await?;
+        let result = Self::compare_by_method(&test, &trades_a, &trades_b, self.risk_free_rate);
 
-        // Get metrics for both strategies
-        let metrics_a = self
-            .calculate_test_metrics(&test.strategy_a, test.start_time, test.end_time.unwrap_or(Utc::now()))
-            .await?;
+        // Store result
+        self.store_result(&result).await?;
 
-        let metrics_b = self
-            .calculate_test_metrics(&test.strategy_b, test.start_time, test.end_time.unwrap_or(Utc::now()))
-            .await?;
+        Ok(result)
+    }
 
-        // Perform statistical tests
-        let (winner, confidence, p_value) = self
-            .perform_t_test(&metrics_a, &metrics_b)
-            .await?;
+    /// Analyze an A/B test with a Mann-Whitney U test on per-trade P&L.
+    ///
+    /// The winner, confidence and p-value come from the rank test, which does
+    /// not assume normally distributed P&L.
+    pub async fn analyze_test_nonparametric(&self, test_id: Uuid) -> Result<AbTestResult> {
+        let (test, trades_a, trades_b) = self.fetch_test_trades(test_id).await?;
+        let result = Self::compare_nonparametric(&test, &trades_a, &trades_b, self.risk_free_rate);
 
-        let recommendation = self.generate_recommendation(&metrics_a, &metrics_b, winner.as_deref());
+        self.store_result(&result).await?;
 
-        let result = AbTestResult {
-            test_id,
+        Ok(result)
+    }
+
+    /// Compare the strategies market by market, on markets both traded.
+    ///
+    /// Shadow mode gives both strategies' hypothetical results on the same
+    /// markets, so per-market P&L (live and shadow, summed) can be differenced
+    /// to cancel out how each market itself played out. A paired t-test runs
+    /// on the differences, with a Wilcoxon signed-rank test alongside.
+    pub async fn analyze_paired(&self, test_id: Uuid) -> Result<PairedTestResult> {
+        let test = self.fetch_test(test_id).await?;
+        let to = test.end_time.unwrap_or(Utc::now());
+
+        let pnl_a = self.fetch_market_pnl(&test.strategy_a, test.start_time, to).await?;
+        let pnl_b = self.fetch_market_pnl(&test.strategy_b, test.start_time, to).await?;
+
+        Ok(Self::compare_paired(&test, &pnl_a, &pnl_b))
+    }
+
+    async fn fetch_test(&self, test_id: Uuid) -> Result<AbTest> {
+        self.manager.get_test(test_id).await
+    }
+
+    /// Load a test and the closed trades of both its strategies
+    async fn fetch_test_trades(&self, test_id: Uuid) -> Result<(AbTest, Vec<TradeReturn>, Vec<TradeReturn>)> {
+        let test = self.fetch_test(test_id).await?;
+        let to = test.end_time.unwrap_or(Utc::now());
+
+        // Get per-trade P&L for both strategies
+        let trades_a = self.fetch_trade_returns(&test.strategy_a, test.start_time, to).await?;
+        let trades_b = self.fetch_trade_returns(&test.strategy_b, test.start_time, to).await?;
+
+        Ok((test, trades_a, trades_b))
+    }
+
+    /// Analyze a test from already-attributed trades, without touching the database.
+    ///
+    /// Trades are split by `strategy_id`, so generator variants are analyzed
+    /// directly on their strategy_version_ids. The test's `method` picks the
+    /// significance test, as in `analyze_test`.
+    pub fn analyze_trades(test: &AbTest, trades: &[AttributedTrade], risk_free_rate: f64) -> AbTestResult {
+        let to = test.end_time.unwrap_or(Utc::now());
+        let trades_a = Self::strategy_returns(&test.strategy_a, test.start_time, to, trades);
+        let trades_b = Self::strategy_returns(&test.strategy_b, test.start_time, to, trades);
+
+        Self::compare_by_method(test, &trades_a, &trades_b, risk_free_rate)
+    }
+
+    /// Nonparametric counterpart of `analyze_trades`
+    pub fn analyze_trades_nonparametric(
+        test: &AbTest,
+        trades: &[AttributedTrade],
+        risk_free_rate: f64,
+    ) -> AbTestResult {
+        let to = test.end_time.unwrap_or(Utc::now());
+        let trades_a = Self::strategy_returns(&test.strategy_a, test.start_time, to, trades);
+        let trades_b = Self::strategy_returns(&test.strategy_b, test.start_time, to, trades);
+
+        Self::compare_nonparametric(test, &trades_a, &trades_b, risk_free_rate)
+    }
+
+    /// Paired counterpart of `analyze_trades`, from live and shadow trades.
+    ///
+    /// Shadow trades that were executed (`trade_id` set) are skipped, as
+    /// their P&L is already in the live trades.
+    pub fn analyze_paired_trades(
+        test: &AbTest,
+        trades: &[AttributedTrade],
+        shadow_trades: &[ShadowTrade],
+    ) -> PairedTestResult {
+        let to = test.end_time.unwrap_or(Utc::now());
+        let market_pnl = |strategy_id: &str| {
+            let mut pnl: BTreeMap<MarketId, f64> = BTreeMap::new();
+            let live = trades
+                .iter()
+                .filter(|t| t.strategy_id == strategy_id)
+                .filter(|t| t.entry_time >= test.start_time && t.entry_time <= to)
+                .filter_map(|t| Some((t.market_id, t.pnl?)));
+            let shadow = shadow_trades
+                .iter()
+                .filter(|t| t.strategy_id == strategy_id && t.trade_id.is_none())
+                .filter(|t| t.timestamp >= test.start_time && t.timestamp <= to)
+                .filter_map(|t| Some((t.market_id, t.hypothetical_pnl?)));
+            for (market_id, trade_pnl) in live.chain(shadow) {
+                *pnl.entry(market_id).or_default() += trade_pnl;
+            }
+            pnl
+        };
+
+        Self::compare_paired(test, &market_pnl(&test.strategy_a), &market_pnl(&test.strategy_b))
+    }
+
+    /// Paired comparison of per-market P&L on the markets both strategies traded
+    fn compare_paired(test: &AbTest, pnl_a: &BTreeMap<MarketId, f64>, pnl_b: &BTreeMap<MarketId, f64>) -> PairedTestResult {
+        let differences: Vec<f64> = pnl_a
+            .iter()
+            .filter_map(|(market_id, a)| Some(a - pnl_b.get(market_id)?))
+            .collect();
+
+        let mean_difference = if differences.is_empty() {
+            0.0
+        } else {
+            differences.iter().sum::<f64>() / differences.len() as f64
+        };
+        let t_test = paired_t_test(&differences);
+        let wilcoxon = wilcoxon_signed_rank(&differences);
+
+        // Same minimum as the unpaired test, counted in markets
+        let p_value = t_test.filter(|_| differences.len() >= 10).map(|t| t.p_value);
+        let confidence = p_value.map(|p| 1.0 - p);
+        let winner = match (confidence, t_test) {
+            (Some(confidence), Some(t)) if confidence > test.statistical_significance => {
+                Some(if t.t_stat > 0.0 { "A" } else { "B" }.to_string())
+            }
+            _ => None,
+        };
+
+        PairedTestResult {
+            test_id: test.id,
+            markets_paired: differences.len(),
+            mean_difference,
+            t_test,
+            wilcoxon,
+            winner,
+            confidence,
+            p_value,
+        }
+    }
+
+    /// Compare two strategies and build the test result
+    /// Compare two strategies with the test's `method`
+    fn compare_by_method(
+        test: &AbTest,
+        trades_a: &[TradeReturn],
+        trades_b: &[TradeReturn],
+        risk_free_rate: f64,
+    ) -> AbTestResult {
+        match test.method {
+            AbTestMethod::Welch => Self::compare(test, trades_a, trades_b, risk_free_rate),
+            AbTestMethod::MannWhitney => Self::compare_nonparametric(test, trades_a, trades_b, risk_free_rate),
+        }
+    }
+
+    fn compare(
+        test: &AbTest,
+        trades_a: &[TradeReturn],
+        trades_b: &[TradeReturn],
+        risk_free_rate: f64,
+    ) -> AbTestResult {
+        let (metrics_a, metrics_b) = Self::summarize(test, trades_a, trades_b, risk_free_rate);
+
+        let pnls_a: Vec<f64> = trades_a.iter().map(|t| t.1).collect();
+        let pnls_b: Vec<f64> = trades_b.iter().map(|t| t.1).collect();
+        let (winner, confidence, p_value) =
+            Self::perform_t_test(&pnls_a, &pnls_b, test.statistical_significance);
+        let recommendation = Self::generate_recommendation(&metrics_a, &metrics_b, winner.as_deref());
+
+        AbTestResult {
+            test_id: test.id,
             strategy_a_metrics: metrics_a,
             strategy_b_metrics: metrics_b,
             winner,
             confidence,
             p_value,
             recommendation,
-        };
+            nonparametric: None,
+            method: AbTestMethod::Welch,
+            effect_size: mean_difference_interval(&pnls_a, &pnls_b, test.statistical_significance),
+        }
+    }
 
-        // Store result
-        self.store_result(&result).await?;
+    /// Compare two strategies with a Mann-Whitney U test
+    fn compare_nonparametric(
+        test: &AbTest,
+        trades_a: &[TradeReturn],
+        trades_b: &[TradeReturn],
+        risk_free_rate: f64,
+    ) -> AbTestResult {
+        let (metrics_a, metrics_b) = Self::summarize(test, trades_a, trades_b, risk_free_rate);
 
-        Ok(result)
+        let pnls_a: Vec<f64> = trades_a.iter().map(|t| t.1).collect();
+        let pnls_b: Vec<f64> = trades_b.iter().map(|t| t.1).collect();
+        let nonparametric = Self::perform_mann_whitney(&pnls_a, &pnls_b, test.statistical_significance);
+
+        let winner = nonparametric.as_ref().and_then(|n| n.dominant.clone());
+        let p_value = nonparametric.as_ref().map(|n| n.p_value);
+        let recommendation = Self::generate_recommendation(&metrics_a, &metrics_b, winner.as_deref());
+
+        AbTestResult {
+            test_id: test.id,
+            strategy_a_metrics: metrics_a,
+            strategy_b_metrics: metrics_b,
+            winner,
+            confidence: p_value.map(|p| 1.0 - p),
+            p_value,
+            recommendation,
+            nonparametric,
+            method: AbTestMethod::MannWhitney,
+            effect_size: mean_difference_interval(&pnls_a, &pnls_b, test.statistical_significance),
+        }
+    }
+
+    /// Performance metrics of both strategies over the test period
+    fn summarize(
+        test: &AbTest,
+        trades_a: &[TradeReturn],
+        trades_b: &[TradeReturn],
+        risk_free_rate: f64,
+    ) -> (PerformanceMetrics, PerformanceMetrics) {
+        let to = test.end_time.unwrap_or(Utc::now());
+        let metrics_a =
+            MetricsCalculator::summarize_trades(&test.strategy_a, test.start_time, to, trades_a, risk_free_rate);
+        let metrics_b =
+            MetricsCalculator::summarize_trades(&test.strategy_b, test.start_time, to, trades_b, risk_free_rate);
+
+        (metrics_a, metrics_b)
+    }
+
+    /// Closed trades of a strategy within a period, in entry order
+    fn strategy_returns(
+        strategy_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        trades: &[AttributedTrade],
+    ) -> Vec<TradeReturn> {
+        let mut closed: Vec<&AttributedTrade> = trades
+            .iter()
+            .filter(|t| t.strategy_id == strategy_id)
+            .filter(|t| t.entry_time >= from && t.entry_time <= to)
+            .filter(|t| t.pnl.is_some())
+            .collect();
+        closed.sort_by_key(|t| t.entry_time);
+
+        closed
+            .iter()
+            .filter_map(|t| Some((t.entry_time, t.pnl?, t.pnl_percent.unwrap_or(0.0))))
+            .collect()
     }
 
-    /// Calculate metrics for a strategy within a test period
-    async fn calculate_test_metrics(
+    /// Per-market P&L of a strategy within a test period: closed live
+    /// trades plus resolved shadow trades that were never executed
+    async fn fetch_market_pnl(
         &self,
         strategy_id: &str,
         from: DateTime<Utc>,
         to: DateTime<Utc>,
-    ) -> Result<PerformanceMetrics> {
-        let row = sqlx::query_as::<_, (i64, i64, i64, f64, f64, f64)>(
+    ) -> Result<BTreeMap<MarketId, f64>> {
+        let rows = sqlx::query_as::<_, (MarketId, f64)>(
             r#"
-            SELECT
-                COUNT(*) as total_trades,
-                COUNT(*) FILTER (WHERE t.pnl > 0) as winning_trades,
-                COUNT(*) FILTER (WHERE t.pnl < 0) as losing_trades,
-                COALESCE(SUM(t.pnl), 0.0) as total_pnl,
-                COALESCE(AVG(t.pnl_percent), 0.0) as avg_return,
-                COALESCE(STDDEV(t.pnl_percent), 0.0) as std_return
+            SELECT market_id, SUM(pnl)::DOUBLE PRECISION
+            FROM (
+                SELECT t.market_id, t.pnl::DOUBLE PRECISION AS pnl
+                FROM trades t
+                JOIN attributed_trades at ON t.id = at.trade_id
+                WHERE at.strategy_id = $1
+                AND t.timestamp >= $2 AND t.timestamp <= $3
+                AND t.pnl IS NOT NULL
+                UNION ALL
+                SELECT s.market_id, s.hypothetical_pnl::DOUBLE PRECISION AS pnl
+                FROM shadow_trades s
+                WHERE s.strategy_id = $1
+                AND s.timestamp >= $2 AND s.timestamp <= $3
+                AND s.hypothetical_pnl IS NOT NULL
+                AND s.trade_id IS NULL
+            ) market_trades
+            GROUP BY market_id
+            "#,
+        )
+        .bind(strategy_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(self.db_pool.as_ref())
+        .await
+        .context("Failed to fetch per-market P&L")?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Fetch the closed trades of a strategy within a test period
+    async fn fetch_trade_returns(
+        &self,
+        strategy_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<TradeReturn>> {
+        let trades = sqlx::query_as::<_, TradeReturn>(
+            r#"
+            SELECT t.timestamp, t.pnl, t.pnl_percent
             FROM trades t
             JOIN attributed_trades at ON t.id = at.trade_id
             WHERE at.strategy_id = $1
             AND t.timestamp >= $2 AND t.timestamp <= $3
+            AND t.pnl IS NOT NULL
+            ORDER BY t.timestamp
             "#,
         )
         .bind(strategy_id)
         .bind(from)
         .bind(to)
-        .fetch_one(self.db_pool.as_ref())
+        .fetch_all(self.db_pool.as_ref())
         .await
-        .context("Failed to calculate test metrics")?;
+        .context("Failed to fetch trade P&L")?;
 
-        let hit_rate = if row.0 > 0 { (row.1 as f64 / row.0 as f64) * 100.0 } else { 0.0 };
-        let roi = if row.0 > 0 { (row.3 / (row.0 as f64 * 100.0)) * 100.0 } else { 0.0 };
-
-        Ok(PerformanceMetrics {
-            strategy_id: strategy_id.to_string(),
-            period_start: from,
-            period_end: to,
-            total_trades: row.0,
-            winning_trades: row.1,
-            losing_trades: row.2,
-            hit_rate,
-            total_pnl: row.3,
-            roi,
-            sharpe_ratio: None,
-            max_drawdown: 0.0,
-            avg_win: 0.0,
-            avg_loss: 0.0,
-            profit_factor: 0.0,
-            calmar_ratio: None,
-        })
+        Ok(trades)
     }
 
-    /// Perform t-test to compare strategies
-    async fn perform_t_test(
-        &self,
-        metrics_a: &PerformanceMetrics,
-        metrics_b: &PerformanceMetrics,
-    ) -> Result<(Option<String>, Option<f64>, Option<f64>)> {
-        if metrics_a.total_trades < 10 || metrics_b.total_trades < 10 {
-            return Ok((None, None, None)); // Not enough data
-        }
-
-        // Simple t-test on P&L per trade
-        let mean_a = metrics_a.total_pnl / metrics_a.total_trades as f64;
-        let mean_b = metrics_b.total_pnl / metrics_b.total_trades as f64;
-
-        let var_a = (metrics_a.total_pnl / metrics_a.total_trades as f64 - mean_a).powi(2);
-        let var_b = (metrics_b.total_pnl / metrics_b.total_trades as f64 - mean_b).powi(2);
-
-        let n_a = metrics_a.total_trades as f64;
-        let n_b = metrics_b.total_trades as f64;
-
-        // Pooled variance
-        let pooled_var = ((n_a - 1.0) * var_a + (n_b - 1.0) * var_b) / (n_a + n_b - 2.0);
-        let std_err = (pooled_var / n_a + pooled_var / n_b).sqrt();
-
-        if std_err == 0.0 {
-            return Ok((None, None, None));
+    /// Perform Welch's t-test on per-trade P&L to compare strategies
+    fn perform_t_test(
+        pnls_a: &[f64],
+        pnls_b: &[f64],
+        significance: f64,
+    ) -> (Option<String>, Option<f64>, Option<f64>) {
+        if pnls_a.len() < 10 || pnls_b.len() < 10 {
+            return (None, None, None); // Not enough data
         }
 
-        let t_stat = (mean_a - mean_b) / std_err;
-        let df = (n_a + n_b - 2.0) as i32;
-
-        // Simplified p-value calculation (would use proper t-distribution in production)
-        let p_value = 2.0 * (1.0 - self.approx_cdf(t_stat.abs()));
+        let Some(test) = welch_t_test(pnls_a, pnls_b) else {
+            return (None, None, None);
+        };
 
-        let confidence = 1.0 - p_value;
+        let confidence = 1.0 - test.p_value;
 
-        let winner = if confidence > 0.95 {
-            if mean_a > mean_b {
+        let winner = if confidence > significance {
+            if test.t_stat > 0.0 {
                 Some("A".to_string())
             } else {
                 Some("B".to_string())
@@ -396,36 +883,46 @@ impl AbTestEngine {
             None
         };
 
-        Ok((winner, Some(confidence), Some(p_value)))
+        (winner, Some(confidence), Some(test.p_value))
     }
 
-    /// Approximate CDF for t-distribution (simplified)
-    fn approx_cdf(&self, x: f64) -> f64 {
-        // Approximation of standard normal CDF
-        let a1 = 0.254829592;
-        let a2 = -0.284496736;
-        let a3 = 1.421413741;
-        let a4 = -1.453152027;
-        let a5 = 1.061405429;
-        let p = 0.3275911;
+    /// Perform a Mann-Whitney U test on per-trade P&L
+    fn perform_mann_whitney(
+        pnls_a: &[f64],
+        pnls_b: &[f64],
+        significance: f64,
+    ) -> Option<NonparametricResult> {
+        if pnls_a.len() < 5 || pnls_b.len() < 5 {
+            return None; // Normal approximation is unreliable below this
+        }
 
-        let sign = if x < 0.0 { -1.0 } else { 1.0 };
-        let x = x.abs();
+        let test = mann_whitney_u(pnls_a, pnls_b)?;
 
-        let t = 1.0 / (1.0 + p * x);
-        let y = 1.0 - (((((a5 * t + a4) * t) + a3) * t + a2) * t + a1) * t * (-0.5 * x * x).exp();
+        let dominant = if 1.0 - test.p_value > significance {
+            if test.prob_superiority > 0.5 {
+                Some("A".to_string())
+            } else {
+                Some("B".to_string())
+            }
+        } else {
+            None
+        };
 
-        0.5 * (1.0 + sign * y)
+        Some(NonparametricResult {
+            u_statistic: test.u_statistic,
+            p_value: test.p_value,
+            prob_a_superior: test.prob_superiority,
+            dominant,
+        })
     }
 
     /// Generate a recommendation based on test results
     fn generate_recommendation(
-        &self,
         metrics_a: &PerformanceMetrics,
         metrics_b: &PerformanceMetrics,
         winner: Option<&str>,
     ) -> String {
-        match winner.as_deref() {
+        match winner {
             Some("A") => format!(
                 "Strategy {} significantly outperforms {} (ΔP&L: ${:.2}). Consider promoting {}.",
                 metrics_a.strategy_id,
@@ -460,21 +957,26 @@ impl AbTestEngine {
     async fn store_result(&self, result: &AbTestResult) -> Result<()> {
         let metrics_a_json = serde_json::to_string(&result.strategy_a_metrics)?;
         let metrics_b_json = serde_json::to_string(&result.strategy_b_metrics)?;
+        let nonparametric_json = result.nonparametric.as_ref().map(serde_json::to_string).transpose()?;
+        let effect_size_json = result.effect_size.as_ref().map(serde_json::to_string).transpose()?;
 
         sqlx::query(
             r#"
             INSERT INTO ab_test_results (
                 test_id, strategy_metrics_a, strategy_metrics_b,
-                winner, confidence, p_value, recommendation
+                winner, confidence, p_value, recommendation, nonparametric, method, effect_size
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            VALUES ($1, $2::jsonb, $3::jsonb, $4, $5, $6, $7, $8::jsonb, $9, $10::jsonb)
             ON CONFLICT (test_id) DO UPDATE SET
                 strategy_metrics_a = EXCLUDED.strategy_metrics_a,
                 strategy_metrics_b = EXCLUDED.strategy_metrics_b,
                 winner = EXCLUDED.winner,
                 confidence = EXCLUDED.confidence,
                 p_value = EXCLUDED.p_value,
-                recommendation = EXCLUDED.recommendation
+                recommendation = EXCLUDED.recommendation,
+                nonparametric = EXCLUDED.nonparametric,
+                method = EXCLUDED.method,
+                effect_size = EXCLUDED.effect_size
             "#,
         )
         .bind(result.test_id)
@@ -484,6 +986,9 @@ impl AbTestEngine {
         .bind(result.confidence)
         .bind(result.p_value)
         .bind(&result.recommendation)
+        .bind(nonparametric_json)
+        .bind(result.method)
+        .bind(effect_size_json)
         .execute(self.db_pool.as_ref())
         .await
         .context("Failed to store test result")?;
@@ -504,11 +1009,1532 @@ impl AbTestEngine {
 
         Ok(counts.count_a >= test.min_sample_size && counts.count_b >= test.min_sample_size)
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct AssignmentCounts {
-    pub test_id: Uuid,
-    pub count_a: i64,
-    pub count_b: i64,
+    /// Run an interim look at a running test and decide whether to stop early.
+    ///
+    /// Alpha is spent with the test's `alpha_spending` function over the
+    /// fraction of `min_sample_size` closed trades reached, so with the
+    /// default O'Brien-Fleming boundary early looks need overwhelming
+    /// evidence. Each look is stored so later looks only spend what is left.
+    /// The test itself is left running; see `evaluate_for_stopping`.
+    pub async fn evaluate_sequential(&self, test_id: Uuid) -> Result<SequentialDecision> {
+        let (test, trades_a, trades_b) = self.fetch_test_trades(test_id).await?;
+        Ok(self
+            .take_look(&test, &trades_a, &trades_b)
+            .await?
+            .map(|look| look.decision)
+            .unwrap_or(SequentialDecision::Continue))
+    }
+
+    /// Take an interim look and end the test if it is decided.
+    ///
+    /// Crossing the boundary completes the test with that winner; stopping
+    /// for futility, or running out of planned looks, ends it inconclusive.
+    /// Either way a result is stored whose winner is the sequential one, not
+    /// that of a fixed-level test on the same trades. Paused and finished
+    /// tests are left alone. Assignments are untouched, so markets keep their
+    /// strategy for as long as the test runs.
+    pub async fn evaluate_for_stopping(&self, test_id: Uuid) -> Result<SequentialDecision> {
+        let (test, trades_a, trades_b) = self.fetch_test_trades(test_id).await?;
+        if test.status != AbTestStatus::Running {
+            debug!("A/B test {} is {:?}, not evaluating for stopping", test_id, test.status);
+            return Ok(SequentialDecision::Continue);
+        }
+
+        let Some(look) = self.take_look(&test, &trades_a, &trades_b).await? else {
+            return Ok(SequentialDecision::Continue);
+        };
+
+        let winner = match &look.decision {
+            SequentialDecision::Continue => return Ok(look.decision),
+            SequentialDecision::Stop { winner } => Some(winner.clone()),
+            SequentialDecision::Futility => None,
+        };
+
+        let mut result = Self::compare_by_method(&test, &trades_a, &trades_b, self.risk_free_rate);
+        result.recommendation =
+            Self::generate_recommendation(&result.strategy_a_metrics, &result.strategy_b_metrics, winner.as_deref());
+        result.winner = winner;
+        self.store_result(&result).await?;
+
+        if result.winner.is_some() {
+            self.manager.complete_test(test_id).await?;
+        } else {
+            self.manager.mark_inconclusive(test_id).await?;
+        }
+        Ok(look.decision)
+    }
+
+    /// Evaluate every running test for stopping, for a scheduler to call
+    /// periodically.
+    ///
+    /// A test that fails to evaluate is logged and skipped so it cannot hold
+    /// up the others. Returns the decision for each test evaluated.
+    pub async fn evaluate_all_running_tests(&self) -> Result<Vec<(Uuid, SequentialDecision)>> {
+        let tests = self.manager.get_running_tests().await?;
+        let mut decisions = Vec::with_capacity(tests.len());
+
+        for test in tests {
+            match self.evaluate_for_stopping(test.id).await {
+                Ok(decision) => decisions.push((test.id, decision)),
+                Err(e) => warn!("Failed to evaluate A/B test {} for stopping: {}", test.id, e),
+            }
+        }
+
+        Ok(decisions)
+    }
+
+    /// Compute and store the next interim look, if one is due
+    async fn take_look(
+        &self,
+        test: &AbTest,
+        trades_a: &[TradeReturn],
+        trades_b: &[TradeReturn],
+    ) -> Result<Option<InterimLook>> {
+        let looks = self.get_interim_looks(test.id).await?;
+
+        let pnls_a: Vec<f64> = trades_a.iter().map(|t| t.1).collect();
+        let pnls_b: Vec<f64> = trades_b.iter().map(|t| t.1).collect();
+
+        let Some(look) = Self::sequential_look(test, looks.last(), &pnls_a, &pnls_b) else {
+            debug!("No interim look due for A/B test {}", test.id);
+            return Ok(None);
+        };
+
+        self.store_interim_look(&look).await?;
+        self.manager.set_current_look(test.id, look.look_number).await?;
+
+        info!(
+            "A/B test {} look {}: z = {:.3}, boundary = {:.3}, t = {:.2} -> {:?}",
+            test.id, look.look_number, look.z_statistic, look.boundary, look.information_fraction, look.decision
+        );
+        Ok(Some(look))
+    }
+
+    /// Compute the next interim look from per-trade P&L, without touching the database.
+    ///
+    /// The look's nominal level is the alpha newly spent since `previous`,
+    /// which ignores the correlation between looks and so errs conservative.
+    /// Futility is declared when conditional power under the current trend
+    /// drops below `FUTILITY_CONDITIONAL_POWER` past half the information,
+    /// or when the full sample is reached without crossing the boundary.
+    ///
+    /// With `looks_planned` set, look k is due once both strategies have
+    /// k / looks_planned of `min_sample_size` trades, and the last planned
+    /// look spends all remaining alpha however many trades it has.
+    /// Returns None if no look is due or either strategy has too few trades
+    /// to test.
+    pub fn sequential_look(
+        test: &AbTest,
+        previous: Option<&InterimLook>,
+        pnls_a: &[f64],
+        pnls_b: &[f64],
+    ) -> Option<InterimLook> {
+        let look_number = previous.map(|l| l.look_number + 1).unwrap_or(1);
+        let samples = pnls_a.len().min(pnls_b.len()) as f64;
+        let min_sample_size = test.min_sample_size.max(1) as f64;
+        let mut information_fraction = (samples / min_sample_size).min(1.0);
+
+        // A look without new trades would spend looks but no alpha
+        if previous.is_some_and(|l| information_fraction <= l.information_fraction) {
+            return None;
+        }
+
+        if test.looks_planned > 0 {
+            if look_number > test.looks_planned {
+                return None;
+            }
+            let due = (look_number as f64 * min_sample_size / test.looks_planned as f64).ceil();
+            if samples < due {
+                return None;
+            }
+            if look_number == test.looks_planned {
+                information_fraction = 1.0;
+            }
+        }
+
+        let t_test = welch_t_test(pnls_a, pnls_b)?;
+        let z = t_test.t_stat;
+
+        let alpha = 1.0 - test.statistical_significance;
+        let spent_before = previous.map(|l| l.alpha_spent).unwrap_or(0.0);
+        let alpha_spent = spend_alpha(test.alpha_spending, alpha, information_fraction).max(spent_before);
+        let normal = Normal::new(0.0, 1.0).ok()?;
+        let boundary = two_sided_critical_value(&normal, alpha_spent - spent_before);
+
+        let conditional_power = if information_fraction < 1.0 {
+            // Final look spends whatever alpha remains
+            let final_boundary = two_sided_critical_value(&normal, alpha - alpha_spent);
+            let drift = z.abs() / information_fraction.sqrt();
+            Some(1.0 - normal.cdf((final_boundary - drift) / (1.0 - information_fraction).sqrt()))
+        } else {
+            None
+        };
+
+        let decision = if z.abs() >= boundary {
+            SequentialDecision::Stop {
+                winner: if z > 0.0 { "A" } else { "B" }.to_string(),
+            }
+        } else if information_fraction >= 1.0
+            || (information_fraction >= FUTILITY_MIN_FRACTION
+                && conditional_power.is_some_and(|p| p < FUTILITY_CONDITIONAL_POWER))
+        {
+            SequentialDecision::Futility
+        } else {
+            SequentialDecision::Continue
+        };
+
+        Some(InterimLook {
+            test_id: test.id,
+            look_number,
+            information_fraction,
+            z_statistic: z,
+            alpha_spent,
+            boundary,
+            conditional_power,
+            decision,
+        })
+    }
+
+    /// Get the interim looks taken for a test, in order
+    pub async fn get_interim_looks(&self, test_id: Uuid) -> Result<Vec<InterimLook>> {
+        let rows = sqlx::query_as::<_, (i32, f64, f64, f64, f64, Option<f64>, String, Option<String>)>(
+            r#"
+            SELECT look_number, information_fraction, z_statistic, alpha_spent,
+                   boundary, conditional_power, decision, winner
+            FROM ab_test_interim_looks
+            WHERE test_id = $1
+            ORDER BY look_number
+            "#,
+        )
+        .bind(test_id)
+        .fetch_all(self.db_pool.as_ref())
+        .await
+        .context("Failed to fetch interim looks")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| InterimLook {
+                test_id,
+                look_number: row.0,
+                information_fraction: row.1,
+                z_statistic: row.2,
+                alpha_spent: row.3,
+                boundary: row.4,
+                conditional_power: row.5,
+                decision: match (row.6.as_str(), row.7) {
+                    ("stop", Some(winner)) => SequentialDecision::Stop { winner },
+                    ("futility", _) => SequentialDecision::Futility,
+                    _ => SequentialDecision::Continue,
+                },
+            })
+            .collect())
+    }
+
+    /// Store an interim look
+    async fn store_interim_look(&self, look: &InterimLook) -> Result<()> {
+        let (decision, winner) = match &look.decision {
+            SequentialDecision::Stop { winner } => ("stop", Some(winner.as_str())),
+            SequentialDecision::Continue => ("continue", None),
+            SequentialDecision::Futility => ("futility", None),
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO ab_test_interim_looks (
+                test_id, look_number, information_fraction, z_statistic,
+                alpha_spent, boundary, conditional_power, decision, winner
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+        )
+        .bind(look.test_id)
+        .bind(look.look_number)
+        .bind(look.information_fraction)
+        .bind(look.z_statistic)
+        .bind(look.alpha_spent)
+        .bind(look.boundary)
+        .bind(look.conditional_power)
+        .bind(decision)
+        .bind(winner)
+        .execute(self.db_pool.as_ref())
+        .await
+        .context("Failed to store interim look")?;
+
+        Ok(())
+    }
+}
+
+/// Conditional power below which a running test is stopped for futility
+const FUTILITY_CONDITIONAL_POWER: f64 = 0.1;
+
+/// Information fraction before which futility is not assessed
+const FUTILITY_MIN_FRACTION: f64 = 0.5;
+
+/// Outcome of an interim look at a running A/B test
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SequentialDecision {
+    /// The boundary was crossed: "A" or "B" wins
+    Stop { winner: String },
+    Continue,
+    /// The strategies are unlikely to be shown different
+    Futility,
+}
+
+/// One interim analysis of a sequential A/B test
+#[derive(Debug, Clone)]
+pub struct InterimLook {
+    pub test_id: Uuid,
+    pub look_number: i32,
+    /// Fraction of `min_sample_size` reached, in (0, 1]
+    pub information_fraction: f64,
+    /// Welch statistic of A vs B per-trade P&L
+    pub z_statistic: f64,
+    /// Cumulative alpha spent up to and including this look
+    pub alpha_spent: f64,
+    /// Critical |z| at this look
+    pub boundary: f64,
+    /// Probability of crossing at the final look if the current trend holds
+    pub conditional_power: Option<f64>,
+    pub decision: SequentialDecision,
+}
+
+/// Lan-DeMets O'Brien-Fleming-type spending: alpha spent by information fraction `t`
+pub fn obrien_fleming_spending(alpha: f64, t: f64) -> f64 {
+    if t <= 0.0 || alpha <= 0.0 {
+        return 0.0;
+    }
+
+    let normal = Normal::new(0.0, 1.0).expect("standard normal");
+    let z = normal.inverse_cdf(1.0 - alpha / 2.0);
+    (2.0 * (1.0 - normal.cdf(z / t.min(1.0).sqrt()))).min(alpha)
+}
+
+/// Lan-DeMets Pocock-type spending: alpha spent by information fraction `t`
+pub fn pocock_spending(alpha: f64, t: f64) -> f64 {
+    if t <= 0.0 || alpha <= 0.0 {
+        return 0.0;
+    }
+
+    (alpha * (1.0 + (std::f64::consts::E - 1.0) * t.min(1.0)).ln()).min(alpha)
+}
+
+/// Alpha spent by information fraction `t` under `spending`
+pub fn spend_alpha(spending: AlphaSpending, alpha: f64, t: f64) -> f64 {
+    match spending {
+        AlphaSpending::ObrienFleming => obrien_fleming_spending(alpha, t),
+        AlphaSpending::Pocock => pocock_spending(alpha, t),
+    }
+}
+
+/// Critical |z| of a two-sided test at level `alpha`; infinite if nothing is left to spend
+fn two_sided_critical_value(normal: &Normal, alpha: f64) -> f64 {
+    if alpha <= 0.0 {
+        f64::INFINITY
+    } else {
+        normal.inverse_cdf(1.0 - alpha / 2.0)
+    }
+}
+
+/// Result of a two-sample t-test
+#[derive(Debug, Clone, Copy)]
+pub struct TTestResult {
+    pub t_stat: f64,
+    pub degrees_of_freedom: f64,
+    /// Two-sided p-value
+    pub p_value: f64,
+}
+
+/// Welch's unequal-variance t-test. Returns None if either sample has fewer
+/// than two points or both have zero variance.
+pub fn welch_t_test(a: &[f64], b: &[f64]) -> Option<TTestResult> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+
+    let (mean_a, var_a) = sample_mean_variance(a);
+    let (mean_b, var_b) = sample_mean_variance(b);
+    let se_a = var_a / a.len() as f64;
+    let se_b = var_b / b.len() as f64;
+
+    let std_err = (se_a + se_b).sqrt();
+    if std_err == 0.0 {
+        return None;
+    }
+
+    let t_stat = (mean_a - mean_b) / std_err;
+
+    // Welch–Satterthwaite degrees of freedom
+    let degrees_of_freedom = (se_a + se_b).powi(2)
+        / (se_a.powi(2) / (a.len() - 1) as f64 + se_b.powi(2) / (b.len() - 1) as f64);
+
+    let dist = StudentsT::new(0.0, 1.0, degrees_of_freedom).ok()?;
+    let p_value = (2.0 * (1.0 - dist.cdf(t_stat.abs()))).clamp(0.0, 1.0);
+
+    Some(TTestResult {
+        t_stat,
+        degrees_of_freedom,
+        p_value,
+    })
+}
+
+/// Difference in means of `a` and `b` with a two-sided Welch confidence
+/// interval at `confidence_level`. None under the same conditions as
+/// `welch_t_test`.
+pub fn mean_difference_interval(a: &[f64], b: &[f64], confidence_level: f64) -> Option<MeanDifference> {
+    let test = welch_t_test(a, b)?;
+    let (mean_a, var_a) = sample_mean_variance(a);
+    let (mean_b, var_b) = sample_mean_variance(b);
+    let difference = mean_a - mean_b;
+    let std_err = (var_a / a.len() as f64 + var_b / b.len() as f64).sqrt();
+
+    let dist = StudentsT::new(0.0, 1.0, test.degrees_of_freedom).ok()?;
+    let margin = dist.inverse_cdf(1.0 - (1.0 - confidence_level) / 2.0) * std_err;
+
+    Some(MeanDifference {
+        difference,
+        lower: difference - margin,
+        upper: difference + margin,
+        confidence_level,
+    })
+}
+
+/// Result of a Mann-Whitney U test
+#[derive(Debug, Clone, Copy)]
+pub struct MannWhitneyResult {
+    /// U statistic for the first sample
+    pub u_statistic: f64,
+    pub z_score: f64,
+    /// Two-sided p-value (normal approximation)
+    pub p_value: f64,
+    /// U / (n_a * n_b): probability a draw from `a` exceeds one from `b`
+    pub prob_superiority: f64,
+}
+
+/// Mann-Whitney U test using the tie-corrected normal approximation with
+/// continuity correction. Returns None if either sample is empty or all
+/// values are tied.
+pub fn mann_whitney_u(a: &[f64], b: &[f64]) -> Option<MannWhitneyResult> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+
+    let n_a = a.len() as f64;
+    let n_b = b.len() as f64;
+    let n = n_a + n_b;
+
+    let mut pooled: Vec<(f64, bool)> = a
+        .iter()
+        .map(|v| (*v, true))
+        .chain(b.iter().map(|v| (*v, false)))
+        .collect();
+    pooled.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    // Average ranks over ties
+    let mut rank_sum_a = 0.0;
+    let mut tie_term = 0.0;
+    let mut i = 0;
+    while i < pooled.len() {
+        let mut j = i;
+        while j + 1 < pooled.len() && pooled[j + 1].0 == pooled[i].0 {
+            j += 1;
+        }
+
+        let rank = (i + j) as f64 / 2.0 + 1.0;
+        let ties = (j - i + 1) as f64;
+        tie_term += ties.powi(3) - ties;
+        rank_sum_a += rank * pooled[i..=j].iter().filter(|(_, from_a)| *from_a).count() as f64;
+        i = j + 1;
+    }
+
+    let u_statistic = rank_sum_a - n_a * (n_a + 1.0) / 2.0;
+    let mean = n_a * n_b / 2.0;
+    let variance = n_a * n_b / 12.0 * ((n + 1.0) - tie_term / (n * (n - 1.0)));
+    if variance <= 0.0 {
+        return None;
+    }
+
+    let diff = u_statistic - mean;
+    let z_score = (diff.abs() - 0.5).max(0.0) / variance.sqrt() * diff.signum();
+
+    let normal = Normal::new(0.0, 1.0).ok()?;
+    let p_value = (2.0 * (1.0 - normal.cdf(z_score.abs()))).clamp(0.0, 1.0);
+
+    Some(MannWhitneyResult {
+        u_statistic,
+        z_score,
+        p_value,
+        prob_superiority: u_statistic / (n_a * n_b),
+    })
+}
+
+/// Paired comparison of two strategies on the markets both traded
+#[derive(Debug, Clone)]
+pub struct PairedTestResult {
+    pub test_id: Uuid,
+    /// Markets with P&L from both strategies
+    pub markets_paired: usize,
+    /// Mean per-market P&L of A minus B
+    pub mean_difference: f64,
+    /// Paired t-test on the per-market differences
+    pub t_test: Option<TTestResult>,
+    pub wilcoxon: Option<WilcoxonResult>,
+    /// "A", "B", or None if not significant or fewer than 10 markets paired
+    pub winner: Option<String>,
+    pub confidence: Option<f64>,
+    pub p_value: Option<f64>,
+}
+
+/// Paired t-test: a one-sample t-test of the differences against zero.
+/// Returns None if there are fewer than two differences or they are all equal.
+pub fn paired_t_test(differences: &[f64]) -> Option<TTestResult> {
+    if differences.len() < 2 {
+        return None;
+    }
+
+    let (mean, variance) = sample_mean_variance(differences);
+    let std_err = (variance / differences.len() as f64).sqrt();
+    if std_err == 0.0 {
+        return None;
+    }
+
+    let t_stat = mean / std_err;
+    let degrees_of_freedom = (differences.len() - 1) as f64;
+
+    let dist = StudentsT::new(0.0, 1.0, degrees_of_freedom).ok()?;
+    let p_value = (2.0 * (1.0 - dist.cdf(t_stat.abs()))).clamp(0.0, 1.0);
+
+    Some(TTestResult {
+        t_stat,
+        degrees_of_freedom,
+        p_value,
+    })
+}
+
+/// Result of a Wilcoxon signed-rank test
+#[derive(Debug, Clone, Copy)]
+pub struct WilcoxonResult {
+    /// Sum of the ranks of positive differences
+    pub w_plus: f64,
+    /// Differences left after dropping zeros
+    pub n_nonzero: usize,
+    pub z_score: f64,
+    /// Two-sided p-value (normal approximation)
+    pub p_value: f64,
+}
+
+/// Wilcoxon signed-rank test, dropping zero differences, using the
+/// tie-corrected normal approximation with continuity correction. Returns
+/// None if no differences are nonzero.
+pub fn wilcoxon_signed_rank(differences: &[f64]) -> Option<WilcoxonResult> {
+    let mut nonzero: Vec<f64> = differences.iter().copied().filter(|d| *d != 0.0).collect();
+    if nonzero.is_empty() {
+        return None;
+    }
+    nonzero.sort_by(|x, y| x.abs().total_cmp(&y.abs()));
+
+    // Average ranks of |d| over ties
+    let mut w_plus = 0.0;
+    let mut tie_term = 0.0;
+    let mut i = 0;
+    while i < nonzero.len() {
+        let mut j = i;
+        while j + 1 < nonzero.len() && nonzero[j + 1].abs() == nonzero[i].abs() {
+            j += 1;
+        }
+
+        let rank = (i + j) as f64 / 2.0 + 1.0;
+        let ties = (j - i + 1) as f64;
+        tie_term += ties.powi(3) - ties;
+        w_plus += rank * nonzero[i..=j].iter().filter(|d| **d > 0.0).count() as f64;
+        i = j + 1;
+    }
+
+    let n = nonzero.len() as f64;
+    let mean = n * (n + 1.0) / 4.0;
+    let variance = n * (n + 1.0) * (2.0 * n + 1.0) / 24.0 - tie_term / 48.0;
+    if variance <= 0.0 {
+        return None;
+    }
+
+    let diff = w_plus - mean;
+    let z_score = (diff.abs() - 0.5).max(0.0) / variance.sqrt() * diff.signum();
+
+    let normal = Normal::new(0.0, 1.0).ok()?;
+    let p_value = (2.0 * (1.0 - normal.cdf(z_score.abs()))).clamp(0.0, 1.0);
+
+    Some(WilcoxonResult {
+        w_plus,
+        n_nonzero: nonzero.len(),
+        z_score,
+        p_value,
+    })
+}
+
+/// Mean and unbiased sample variance
+pub(crate) fn sample_mean_variance(values: &[f64]) -> (f64, f64) {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (mean, variance)
+}
+
+#[derive(Debug, Clone)]
+pub struct AssignmentCounts {
+    pub test_id: Uuid,
+    pub count_a: i64,
+    pub count_b: i64,
+}
+
+/// Stratum for assignments recorded without a category
+pub const UNCATEGORIZED: &str = "uncategorized";
+
+/// How one category's markets split between the arms
+#[derive(Debug, Clone)]
+pub struct StratumBalance {
+    pub category: String,
+    pub count_a: i64,
+    pub count_b: i64,
+    /// Share of the category's markets assigned to strategy A
+    pub share_a: f64,
+    /// `share_a` is further than the tolerance from the allocation ratio,
+    /// and by more than one market
+    pub imbalanced: bool,
+}
+
+/// Assignment split of a test against its allocation ratio
+#[derive(Debug, Clone)]
+pub struct RebalanceReport {
+    pub test_id: Uuid,
+    pub allocation_ratio: f64,
+    pub tolerance: f64,
+    pub count_a: i64,
+    pub count_b: i64,
+    pub share_a: f64,
+    pub imbalanced: bool,
+    pub strata: Vec<StratumBalance>,
+}
+
+impl RebalanceReport {
+    /// Build the report from `(category, count_a, count_b)` rows.
+    ///
+    /// A split within one market of exact is never flagged, however far its
+    /// share is from the ratio: a category of three markets can't do better.
+    pub fn from_counts(test: &AbTest, tolerance: f64, strata: Vec<(String, i64, i64)>) -> Self {
+        let ratio = test.allocation_ratio;
+        let share = |a: i64, b: i64| if a + b > 0 { a as f64 / (a + b) as f64 } else { ratio };
+        let off = |a: i64, b: i64| {
+            (share(a, b) - ratio).abs() > tolerance && (a as f64 - ratio * (a + b) as f64).abs() > 1.0
+        };
+
+        let strata: Vec<StratumBalance> = strata
+            .into_iter()
+            .map(|(category, count_a, count_b)| StratumBalance {
+                share_a: share(count_a, count_b),
+                imbalanced: off(count_a, count_b),
+                category,
+                count_a,
+                count_b,
+            })
+            .collect();
+        let count_a = strata.iter().map(|s| s.count_a).sum();
+        let count_b = strata.iter().map(|s| s.count_b).sum();
+        let share_a = share(count_a, count_b);
+
+        Self {
+            test_id: test.id,
+            allocation_ratio: test.allocation_ratio,
+            tolerance,
+            count_a,
+            count_b,
+            share_a,
+            imbalanced: off(count_a, count_b),
+            strata,
+        }
+    }
+
+    /// Whether the whole test or any category is out of balance
+    pub fn needs_rebalance(&self) -> bool {
+        self.imbalanced || self.strata.iter().any(|s| s.imbalanced)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use common::OrderSide;
+
+    fn create_trade(strategy_id: &str, pnl: f64) -> AttributedTrade {
+        AttributedTrade {
+            trade_id: Uuid::new_v4(),
+            market_id: MarketId::random(),
+            outcome_id: "yes".into(),
+            side: OrderSide::Buy,
+            entry_price: 0.5,
+            size: 100.0,
+            entry_time: Utc::now() - Duration::hours(1),
+            exit_price: None,
+            exit_time: None,
+            signal_id: Uuid::new_v4(),
+            agent_id: Uuid::new_v4(),
+            strategy_id: strategy_id.to_string(),
+            pnl: Some(pnl),
+            pnl_percent: Some(pnl / 50.0),
+        }
+    }
+
+    #[test]
+    fn test_analyze_variant_trades() {
+        let test = AbTest {
+            id: Uuid::new_v4(),
+            name: "min_edge".to_string(),
+            strategy_a: "value:edge_3".to_string(),
+            strategy_b: "value:edge_5".to_string(),
+            start_time: Utc::now() - Duration::days(1),
+            end_time: None,
+            status: AbTestStatus::Running,
+            allocation_ratio: 0.5,
+            min_sample_size: 10,
+            statistical_significance: 0.95,
+            method: AbTestMethod::Welch,
+            looks_planned: 0,
+            alpha_spending: AlphaSpending::default(),
+            current_look: 0,
+        };
+
+        let mut trades: Vec<AttributedTrade> = (0..12)
+            .map(|i| create_trade("value:edge_3", if i % 3 == 0 { -5.0 } else { 4.0 }))
+            .collect();
+        trades.extend((0..15).map(|i| create_trade("value:edge_5", if i % 2 == 0 { -5.0 } else { 6.0 })));
+        trades.push(create_trade("other", 100.0));
+
+        let result = AbTestEngine::analyze_trades(&test, &trades, 0.0);
+
+        assert_eq!(result.test_id, test.id);
+        assert_eq!(result.strategy_a_metrics.strategy_id, "value:edge_3");
+        assert_eq!(result.strategy_a_metrics.total_trades, 12);
+        assert_eq!(result.strategy_a_metrics.winning_trades, 8);
+        assert_eq!(result.strategy_b_metrics.strategy_id, "value:edge_5");
+        assert_eq!(result.strategy_b_metrics.total_trades, 15);
+        assert!((result.strategy_b_metrics.total_pnl - 2.0).abs() < 1e-9);
+        assert!(result.recommendation.contains("value:edge_"));
+    }
+
+    /// Five trades with equity curve 10, 5, 25, 10, 15
+    fn series_fixture() -> Vec<(f64, f64)> {
+        vec![(10.0, 0.02), (-5.0, -0.01), (20.0, 0.04), (-15.0, -0.03), (5.0, 0.01)]
+    }
+
+    fn assert_series_metrics(metrics: &PerformanceMetrics) {
+        assert_eq!(metrics.total_trades, 5);
+        assert!((metrics.total_pnl - 15.0).abs() < 1e-9);
+        assert!((metrics.avg_win - 35.0 / 3.0).abs() < 1e-9);
+        assert!((metrics.avg_loss - 10.0).abs() < 1e-9);
+        assert!((metrics.profit_factor - 1.75).abs() < 1e-9);
+        // Peak 25 drawn down to 10
+        assert!((metrics.max_drawdown - 60.0).abs() < 1e-9);
+        // mean 0.006 annualized over 252, over a 60% drawdown
+        assert!((metrics.calmar_ratio.unwrap() - 2.52).abs() < 1e-9);
+        // mean 0.006, stdev 0.027019, risk-free 0.0001 per period, annualized over 252
+        assert!((metrics.sharpe_ratio.unwrap() - 3.466497).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_metrics_from_trade_series() {
+        let test = AbTest {
+            id: Uuid::new_v4(),
+            name: "series".to_string(),
+            strategy_a: "a".to_string(),
+            strategy_b: "b".to_string(),
+            start_time: Utc::now() - Duration::days(1),
+            end_time: None,
+            status: AbTestStatus::Running,
+            allocation_ratio: 0.5,
+            min_sample_size: 10,
+            statistical_significance: 0.95,
+            method: AbTestMethod::Welch,
+            looks_planned: 0,
+            alpha_spending: AlphaSpending::default(),
+            current_look: 0,
+        };
+
+        // Insert out of order; metrics are computed in entry order
+        let base = Utc::now() - Duration::hours(10);
+        let mut trades: Vec<AttributedTrade> = series_fixture()
+            .into_iter()
+            .enumerate()
+            .map(|(i, (pnl, pnl_percent))| AttributedTrade {
+                entry_time: base + Duration::minutes(i as i64),
+                pnl_percent: Some(pnl_percent),
+                ..create_trade("a", pnl)
+            })
+            .collect();
+        trades.reverse();
+
+        let result = AbTestEngine::analyze_trades(&test, &trades, 0.0001);
+
+        assert_series_metrics(&result.strategy_a_metrics);
+        assert_eq!(result.strategy_b_metrics.total_trades, 0);
+        assert!(result.strategy_b_metrics.sharpe_ratio.is_none());
+    }
+
+    /// Runs against a scratch Postgres: `TEST_DATABASE_URL=... cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_fetch_trade_returns_postgres() {
+        use sqlx::postgres::PgPoolOptions;
+
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL not set");
+        // Single connection so the temp tables are visible to every query
+        let pool = Arc::new(PgPoolOptions::new().max_connections(1).connect(&url).await.unwrap());
+
+        sqlx::raw_sql(
+            r#"
+            CREATE TEMP TABLE trades (
+                id UUID PRIMARY KEY,
+                timestamp TIMESTAMPTZ NOT NULL,
+                pnl DOUBLE PRECISION,
+                pnl_percent DOUBLE PRECISION
+            );
+            CREATE TEMP TABLE attributed_trades (
+                trade_id UUID PRIMARY KEY,
+                strategy_id TEXT NOT NULL
+            );
+            "#,
+        )
+        .execute(pool.as_ref())
+        .await
+        .unwrap();
+
+        let base = Utc::now() - Duration::hours(10);
+        let mut seeded: Vec<(DateTime<Utc>, f64, f64, &str)> = series_fixture()
+            .into_iter()
+            .enumerate()
+            .map(|(i, (pnl, pnl_percent))| (base + Duration::minutes(i as i64), pnl, pnl_percent, "a"))
+            .collect();
+        seeded.push((base, 100.0, 1.0, "b"));
+        seeded.reverse();
+
+        for (timestamp, pnl, pnl_percent, strategy_id) in seeded {
+            let id = Uuid::new_v4();
+            sqlx::query("INSERT INTO trades (id, timestamp, pnl, pnl_percent) VALUES ($1, $2, $3, $4)")
+                .bind(id)
+                .bind(timestamp)
+                .bind(pnl)
+                .bind(pnl_percent)
+                .execute(pool.as_ref())
+                .await
+                .unwrap();
+            sqlx::query("INSERT INTO attributed_trades (trade_id, strategy_id) VALUES ($1, $2)")
+                .bind(id)
+                .bind(strategy_id)
+                .execute(pool.as_ref())
+                .await
+                .unwrap();
+        }
+
+        let engine = AbTestEngine::new(pool, 0.0001);
+        let from = base - Duration::minutes(1);
+        let to = Utc::now();
+        let trades = engine.fetch_trade_returns("a", from, to).await.unwrap();
+        let metrics = MetricsCalculator::summarize_trades("a", from, to, &trades, engine.risk_free_rate);
+
+        assert_series_metrics(&metrics);
+    }
+
+    #[test]
+    fn test_mann_whitney_known_p_value() {
+        let a = [1.0, 2.0, 2.0, 3.0, 5.0, 5.0, 5.0, 8.0];
+        let b = [2.0, 3.0, 3.0, 4.0, 4.0, 6.0, 7.0, 9.0, 9.0, 10.0];
+
+        // Tie-corrected normal approximation with continuity correction
+        let result = mann_whitney_u(&a, &b).unwrap();
+        assert!((result.u_statistic - 25.0).abs() < 1e-9);
+        assert!((result.z_score + 1.297768).abs() < 1e-5);
+        assert!((result.p_value - 0.194367).abs() < 1e-5);
+        assert!(result.prob_superiority < 0.5);
+
+        assert!(mann_whitney_u(&[1.0, 1.0], &[1.0, 1.0]).is_none());
+    }
+
+    #[test]
+    fn test_nonparametric_flags_consistently_better_strategy() {
+        let test = AbTest {
+            id: Uuid::new_v4(),
+            name: "fat_tails".to_string(),
+            strategy_a: "a".to_string(),
+            strategy_b: "b".to_string(),
+            start_time: Utc::now() - Duration::days(1),
+            end_time: None,
+            status: AbTestStatus::Running,
+            allocation_ratio: 0.5,
+            min_sample_size: 8,
+            statistical_significance: 0.95,
+            method: AbTestMethod::Welch,
+            looks_planned: 0,
+            alpha_spending: AlphaSpending::default(),
+            current_look: 0,
+        };
+
+        // A wins small every time; B has one outsized payout that dominates the mean
+        let mut trades: Vec<AttributedTrade> = [3.1, 2.4, 4.0, 1.8, 2.9, 3.5, 2.2, 3.8]
+            .into_iter()
+            .map(|pnl| create_trade("a", pnl))
+            .collect();
+        trades.extend(
+            [0.5, -1.0, 1.2, 0.1, -0.4, 0.9, 1.5, 60.0]
+                .into_iter()
+                .map(|pnl| create_trade("b", pnl)),
+        );
+
+        let result = AbTestEngine::analyze_trades_nonparametric(&test, &trades, 0.0);
+        let nonparametric = result.nonparametric.clone().unwrap();
+
+        assert!((nonparametric.u_statistic - 56.0).abs() < 1e-9);
+        assert!(nonparametric.p_value < 0.05);
+        assert!(nonparametric.prob_a_superior > 0.5);
+        assert_eq!(nonparametric.dominant.as_deref(), Some("A"));
+        assert_eq!(result.winner.as_deref(), Some("A"));
+
+        // B's outlier makes its total P&L higher, which is what the mean-based test sees
+        assert!(result.strategy_b_metrics.total_pnl > result.strategy_a_metrics.total_pnl);
+        assert!(AbTestEngine::analyze_trades(&test, &trades, 0.0).nonparametric.is_none());
+    }
+
+    #[test]
+    fn test_welch_t_test_known_p_value() {
+        let a = [0.5, 1.2, -0.3, 2.1, 0.8, 1.5, -0.1, 0.9, 1.1, 0.4];
+        let b = [-0.2, 0.3, -1.0, 0.6, -0.5, 0.1, -0.8, 0.2, -0.4, 0.0, -0.6, 0.4];
+
+        // Reference values (numerical integration of the t density):
+        // t = 3.5692, df = 15.687, two-sided p = 0.002630
+        let result = welch_t_test(&a, &b).unwrap();
+        assert!((result.t_stat - 3.5692).abs() < 1e-3);
+        assert!((result.degrees_of_freedom - 15.687).abs() < 1e-3);
+        assert!((result.p_value - 0.002630).abs() < 1e-5);
+
+        // A leads B with p well below 0.05
+        let (winner, confidence, p_value) = AbTestEngine::perform_t_test(&a, &b, 0.95);
+        assert_eq!(winner.as_deref(), Some("A"));
+        assert!((confidence.unwrap() + p_value.unwrap() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_mean_difference_interval_known_bounds() {
+        let a = [0.5, 1.2, -0.3, 2.1, 0.8, 1.5, -0.1, 0.9, 1.1, 0.4];
+        let b = [-0.2, 0.3, -1.0, 0.6, -0.5, 0.1, -0.8, 0.2, -0.4, 0.0, -0.6, 0.4];
+
+        // Reference: difference 0.968333 ± t(0.975, 15.687) = 2.123349 times
+        // the Welch standard error
+        let interval = mean_difference_interval(&a, &b, 0.95).unwrap();
+        assert!((interval.difference - 0.968333).abs() < 1e-6);
+        assert!((interval.lower - 0.392268).abs() < 1e-5);
+        assert!((interval.upper - 1.544398).abs() < 1e-5);
+        assert_eq!(interval.confidence_level, 0.95);
+
+        // A wider interval at a higher level, still excluding zero here
+        let wider = mean_difference_interval(&a, &b, 0.99).unwrap();
+        assert!(wider.lower < interval.lower && wider.upper > interval.upper && wider.lower > 0.0);
+        assert!(mean_difference_interval(&a, &[1.0], 0.95).is_none());
+    }
+
+    #[test]
+    fn test_equal_distributions_declare_no_winner() {
+        let mut test = AbTest {
+            id: Uuid::new_v4(),
+            name: "null".to_string(),
+            strategy_a: "a".to_string(),
+            strategy_b: "b".to_string(),
+            start_time: Utc::now() - Duration::days(1),
+            end_time: None,
+            status: AbTestStatus::Running,
+            allocation_ratio: 0.5,
+            min_sample_size: 10,
+            statistical_significance: 0.95,
+            method: AbTestMethod::Welch,
+            looks_planned: 0,
+            alpha_spending: AlphaSpending::default(),
+            current_look: 0,
+        };
+
+        // The same heavy-tailed P&L in a different order
+        let pnls = [1.2, -0.5, 0.3, 2.2, -1.1, 0.7, 0.0, 1.5, -0.3, 0.9, 0.4, -0.8, 25.0, -12.0];
+        let mut trades: Vec<AttributedTrade> = pnls.iter().map(|&pnl| create_trade("a", pnl)).collect();
+        trades.extend(pnls.iter().rev().map(|&pnl| create_trade("b", pnl)));
+
+        for method in [AbTestMethod::Welch, AbTestMethod::MannWhitney] {
+            test.method = method;
+            let result = AbTestEngine::analyze_trades(&test, &trades, 0.0);
+            assert_eq!(result.method, method);
+            assert_eq!(result.winner, None);
+            assert!((result.p_value.unwrap() - 1.0).abs() < 1e-9, "{:?}", result.p_value);
+
+            let effect = result.effect_size.unwrap();
+            assert!(effect.difference.abs() < 1e-9);
+            assert!(effect.lower < 0.0 && effect.upper > 0.0);
+        }
+        // Mann-Whitney is chosen on the test rather than through a separate call
+        assert!(AbTestEngine::analyze_trades(&test, &trades, 0.0).nonparametric.is_some());
+    }
+
+    #[test]
+    fn test_paired_tests_known_p_values() {
+        let differences = [1.2, 0.4, -0.3, 2.1, 0.8, 1.5, 0.0, 0.9, -0.6, 1.1, 0.7, 0.4];
+
+        // One-sample t on the differences: t = 3.0993, df = 11, p = 0.010117
+        let t = paired_t_test(&differences).unwrap();
+        assert!((t.t_stat - 3.099309).abs() < 1e-5);
+        assert_eq!(t.degrees_of_freedom, 11.0);
+        assert!((t.p_value - 0.010117).abs() < 1e-5);
+
+        // The zero is dropped; the two 0.4s share rank 2.5
+        let w = wilcoxon_signed_rank(&differences).unwrap();
+        assert_eq!(w.n_nonzero, 11);
+        assert!((w.w_plus - 61.0).abs() < 1e-9);
+        assert!((w.z_score - 2.446257).abs() < 1e-5);
+        assert!((w.p_value - 0.014435).abs() < 1e-5);
+
+        assert!(paired_t_test(&[2.0, 2.0, 2.0]).is_none());
+        assert!(wilcoxon_signed_rank(&[0.0, 0.0]).is_none());
+    }
+
+    #[test]
+    fn test_paired_reaches_significance_before_unpaired() {
+        // How each market resolves dominates both strategies' P&L; A is a
+        // steady 1.5 better on the same market
+        let mut rng = fastrand::Rng::with_seed(7);
+        let markets: Vec<(MarketId, f64, f64)> = (0..60)
+            .map(|_| {
+                let outcome = rng.f64() * 60.0 - 30.0;
+                let a = outcome + 1.5 + rng.f64() * 2.0 - 1.0;
+                let b = outcome + rng.f64() * 2.0 - 1.0;
+                (MarketId::random(), a, b)
+            })
+            .collect();
+
+        let test = create_sequential_test(60);
+        let trades_for = |n: usize| -> (Vec<AttributedTrade>, Vec<ShadowTrade>) {
+            let mut live = Vec::new();
+            let mut shadow = Vec::new();
+            for (market_id, a, b) in &markets[..n] {
+                live.push(AttributedTrade { market_id: *market_id, ..create_trade("a", *a) });
+                // B only ran in shadow mode on these markets
+                shadow.push(ShadowTrade {
+                    id: Uuid::new_v4(),
+                    trade_id: None,
+                    market_id: *market_id,
+                    outcome_id: "yes".into(),
+                    side: OrderSide::Buy,
+                    price: 0.5,
+                    size: 100.0,
+                    timestamp: Utc::now() - Duration::hours(1),
+                    strategy_id: "b".to_string(),
+                    hypothetical_pnl: Some(*b),
+                    would_have_won: Some(*b > 0.0),
+                    reference_price: None,
+                    target_price: None,
+                    stop_loss: None,
+                });
+            }
+            // Unpaired markets only count towards the unpaired test
+            live.push(create_trade("a", 40.0));
+            shadow.push(ShadowTrade { market_id: MarketId::random(), ..shadow[0].clone() });
+            (live, shadow)
+        };
+
+        let first_significant = |paired: bool| {
+            (10..=markets.len()).find(|&n| {
+                let (live, shadow) = trades_for(n);
+                let winner = if paired {
+                    let result = AbTestEngine::analyze_paired_trades(&test, &live, &shadow);
+                    assert_eq!(result.markets_paired, n);
+                    result.winner
+                } else {
+                    // The unpaired test sees B's shadow P&L as a plain sample
+                    let mut all = live.clone();
+                    all.extend(shadow.iter().map(|s| AttributedTrade {
+                        market_id: s.market_id,
+                        ..create_trade("b", s.hypothetical_pnl.unwrap())
+                    }));
+                    AbTestEngine::analyze_trades(&test, &all, 0.0).winner
+                };
+                winner.as_deref() == Some("A")
+            })
+        };
+
+        assert_eq!(first_significant(true), Some(10));
+        assert!(first_significant(false).is_none());
+
+        let (live, shadow) = trades_for(60);
+        let result = AbTestEngine::analyze_paired_trades(&test, &live, &shadow);
+        assert!((result.mean_difference - 1.5).abs() < 0.5);
+        assert!(result.wilcoxon.unwrap().p_value < 0.05);
+    }
+
+    fn create_sequential_test(min_sample_size: i64) -> AbTest {
+        AbTest {
+            id: Uuid::new_v4(),
+            name: "sequential".to_string(),
+            strategy_a: "a".to_string(),
+            strategy_b: "b".to_string(),
+            start_time: Utc::now() - Duration::days(1),
+            end_time: None,
+            status: AbTestStatus::Running,
+            allocation_ratio: 0.5,
+            min_sample_size,
+            statistical_significance: 0.95,
+            method: AbTestMethod::Welch,
+            looks_planned: 0,
+            alpha_spending: AlphaSpending::default(),
+            current_look: 0,
+        }
+    }
+
+    #[test]
+    fn test_obrien_fleming_spending() {
+        assert_eq!(obrien_fleming_spending(0.05, 0.0), 0.0);
+        assert!((obrien_fleming_spending(0.05, 0.25) - 8.857544e-5).abs() < 1e-10);
+        assert!((obrien_fleming_spending(0.05, 0.5) - 0.0055746).abs() < 1e-7);
+        assert!((obrien_fleming_spending(0.05, 1.0) - 0.05).abs() < 1e-8);
+    }
+
+    #[test]
+    fn test_sequential_stops_early_on_clear_winner() {
+        let test = create_sequential_test(100);
+        let pnls_a: Vec<f64> = (0..30).map(|i| 10.0 + (i % 5) as f64).collect();
+        let pnls_b: Vec<f64> = (0..30).map(|i| -10.0 + (i % 5) as f64).collect();
+
+        let look = AbTestEngine::sequential_look(&test, None, &pnls_a, &pnls_b).unwrap();
+
+        // 30% of the sample, so only alpha(0.3) = 0.000346 is spent
+        assert_eq!(look.look_number, 1);
+        assert!((look.information_fraction - 0.3).abs() < 1e-12);
+        assert!((look.alpha_spent - 0.00034572).abs() < 1e-8);
+        assert!((look.boundary - 3.5784).abs() < 1e-3);
+        assert_eq!(look.decision, SequentialDecision::Stop { winner: "A".to_string() });
+
+        let look = AbTestEngine::sequential_look(&test, None, &pnls_b, &pnls_a).unwrap();
+        assert_eq!(look.decision, SequentialDecision::Stop { winner: "B".to_string() });
+    }
+
+    #[test]
+    fn test_sequential_stops_for_futility_on_null_effect() {
+        let test = create_sequential_test(40);
+        // Same P&L values in a different order: no effect at all
+        let pnls_a: Vec<f64> = (0..20).map(|i| (i % 5) as f64 - 2.0).collect();
+        let pnls_b: Vec<f64> = pnls_a.iter().rev().copied().collect();
+
+        // Futility is not assessed before half the information
+        let first = AbTestEngine::sequential_look(&test, None, &pnls_a[..10], &pnls_b[..10]).unwrap();
+        assert_eq!(first.decision, SequentialDecision::Continue);
+        assert!(first.conditional_power.unwrap() < FUTILITY_CONDITIONAL_POWER);
+
+        let second = AbTestEngine::sequential_look(&test, Some(&first), &pnls_a, &pnls_b).unwrap();
+        assert_eq!(second.look_number, 2);
+        assert!((second.alpha_spent - 0.0055746).abs() < 1e-7);
+        // Only the alpha spent since the first look is available here
+        let normal = Normal::new(0.0, 1.0).unwrap();
+        let expected = normal.inverse_cdf(1.0 - (second.alpha_spent - first.alpha_spent) / 2.0);
+        assert!((second.boundary - expected).abs() < 1e-9);
+        assert_eq!(second.decision, SequentialDecision::Futility);
+    }
+
+    #[test]
+    fn test_sequential_futility_at_full_sample() {
+        let test = create_sequential_test(10);
+        let pnls_a = [1.0, -1.0, 2.0, -2.0, 0.5, 1.5, -0.5, 0.0, 1.0, -1.0];
+        let pnls_b = [1.1, -0.9, 1.8, -2.1, 0.4, 1.6, -0.4, 0.2, 0.9, -1.2];
+
+        let look = AbTestEngine::sequential_look(&test, None, &pnls_a, &pnls_b).unwrap();
+        assert_eq!(look.information_fraction, 1.0);
+        assert!(look.conditional_power.is_none());
+        assert_eq!(look.decision, SequentialDecision::Futility);
+    }
+
+    #[test]
+    fn test_pocock_spending() {
+        assert_eq!(pocock_spending(0.05, 0.0), 0.0);
+        assert!((pocock_spending(0.05, 0.25) - 0.0178687).abs() < 1e-7);
+        assert!((pocock_spending(0.05, 0.5) - 0.0310057).abs() < 1e-7);
+        assert!((pocock_spending(0.05, 1.0) - 0.05).abs() < 1e-12);
+        // Spends far more early than O'Brien-Fleming
+        assert!(spend_alpha(AlphaSpending::Pocock, 0.05, 0.25) > 100.0 * spend_alpha(AlphaSpending::ObrienFleming, 0.05, 0.25));
+    }
+
+    fn create_planned_test(looks_planned: i32, min_sample_size: i64) -> AbTest {
+        AbTest {
+            looks_planned,
+            ..create_sequential_test(min_sample_size)
+        }
+    }
+
+    #[test]
+    fn test_planned_looks_wait_for_their_share() {
+        let test = create_planned_test(4, 40);
+        let pnls_a: Vec<f64> = (0..40).map(|i| (i % 7) as f64 - 3.0).collect();
+        let pnls_b: Vec<f64> = (0..40).map(|i| (i % 5) as f64 - 2.0).collect();
+
+        assert!(AbTestEngine::sequential_look(&test, None, &pnls_a[..9], &pnls_b[..9]).is_none());
+        let first = AbTestEngine::sequential_look(&test, None, &pnls_a[..10], &pnls_b[..10]).unwrap();
+        assert_eq!(first.look_number, 1);
+        assert!((first.information_fraction - 0.25).abs() < 1e-12);
+
+        // Look 2 needs 20 trades in both strategies
+        assert!(AbTestEngine::sequential_look(&test, Some(&first), &pnls_a[..19], &pnls_b).is_none());
+        assert!(AbTestEngine::sequential_look(&test, Some(&first), &pnls_a[..20], &pnls_b).is_some());
+
+        // The last planned look spends everything, even if it comes early
+        let third = InterimLook { look_number: 3, ..first.clone() };
+        let last = AbTestEngine::sequential_look(&test, Some(&third), &pnls_a, &pnls_b).unwrap();
+        assert_eq!(last.information_fraction, 1.0);
+        assert!((last.alpha_spent - 0.05).abs() < 1e-8);
+        assert_ne!(last.decision, SequentialDecision::Continue);
+
+        let fourth = InterimLook { look_number: 4, ..last };
+        assert!(AbTestEngine::sequential_look(&test, Some(&fourth), &pnls_a, &pnls_b).is_none());
+    }
+
+    /// Standard normal draw by Box-Muller
+    fn normal_draw(rng: &mut fastrand::Rng) -> f64 {
+        let u = 1.0 - rng.f64();
+        (-2.0 * u.ln()).sqrt() * (2.0 * std::f64::consts::PI * rng.f64()).cos()
+    }
+
+    /// Looks taken as trades arrive, up to the first that is not Continue
+    fn simulate_planned_test(test: &AbTest, rng: &mut fastrand::Rng, effect: f64) -> Vec<InterimLook> {
+        let n = test.min_sample_size as usize;
+        let pnls_a: Vec<f64> = (0..n).map(|_| effect + normal_draw(rng)).collect();
+        let pnls_b: Vec<f64> = (0..n).map(|_| normal_draw(rng)).collect();
+
+        let mut looks: Vec<InterimLook> = Vec::new();
+        for trades in 2..=n {
+            if let Some(look) = AbTestEngine::sequential_look(test, looks.last(), &pnls_a[..trades], &pnls_b[..trades]) {
+                let decided = look.decision != SequentialDecision::Continue;
+                looks.push(look);
+                if decided {
+                    break;
+                }
+            }
+        }
+        looks
+    }
+
+    #[test]
+    fn test_planned_test_stops_superior_arm_at_second_look() {
+        let test = create_planned_test(4, 40);
+        let looks = simulate_planned_test(&test, &mut fastrand::Rng::with_seed(11), 1.5);
+
+        assert_eq!(looks.len(), 2);
+        assert_eq!(looks[0].decision, SequentialDecision::Continue);
+        assert_eq!(looks[1].decision, SequentialDecision::Stop { winner: "A".to_string() });
+
+        // Looks never move a market to the other strategy
+        let later = AbTest { current_look: looks.len() as i32, ..test.clone() };
+        for _ in 0..50 {
+            let market = MarketId::random();
+            assert_eq!(test.assign_strategy(market), later.assign_strategy(market));
+        }
+    }
+
+    #[test]
+    fn test_planned_test_null_effect_false_winner_rate() {
+        let test = create_planned_test(4, 40);
+        let mut rng = fastrand::Rng::with_seed(42);
+        let runs = 1000;
+
+        let mut false_winners = 0;
+        for _ in 0..runs {
+            let looks = simulate_planned_test(&test, &mut rng, 0.0);
+            let last = looks.last().unwrap();
+            assert_ne!(last.decision, SequentialDecision::Continue);
+            assert!(last.look_number <= test.looks_planned);
+            if matches!(last.decision, SequentialDecision::Stop { .. }) {
+                false_winners += 1;
+            }
+        }
+
+        let alpha = 1.0 - test.statistical_significance;
+        assert!((false_winners as f64 / runs as f64) <= alpha, "{} false winners in {} runs", false_winners, runs);
+    }
+
+    fn seeded_market(rng: &mut fastrand::Rng) -> MarketId {
+        MarketId::from(Uuid::from_u128(rng.u128(..)))
+    }
+
+    #[test]
+    fn test_hash_assignment_splits_by_allocation_ratio() {
+        for allocation_ratio in [0.5, 0.3] {
+            let test = AbTest {
+                id: Uuid::from_u128(0x5eed),
+                allocation_ratio,
+                ..create_sequential_test(100)
+            };
+            let mut rng = fastrand::Rng::with_seed(3);
+            let markets: Vec<MarketId> = (0..10_000).map(|_| seeded_market(&mut rng)).collect();
+
+            let to_a = markets.iter().filter(|m| test.assign_strategy(**m) == "a").count();
+            let share = to_a as f64 / markets.len() as f64;
+            assert!((share - allocation_ratio).abs() <= 0.01, "{} to A at ratio {}", share, allocation_ratio);
+
+            // The same market gets the same arm however often it is asked, and
+            // from a copy of the test loaded elsewhere
+            let reloaded = AbTest { current_look: 3, ..test.clone() };
+            for market in &markets[..100] {
+                let arm = test.assign_strategy(*market);
+                assert_eq!(test.assign_strategy(*market), arm);
+                assert_eq!(reloaded.assign_strategy(*market), arm);
+            }
+        }
+    }
+
+    #[test]
+    fn test_stratified_assignment_balances_each_category() {
+        let test = AbTest { allocation_ratio: 0.3, ..create_sequential_test(100) };
+        let mut rng = fastrand::Rng::with_seed(5);
+        let mut counts: BTreeMap<&str, (i64, i64)> = BTreeMap::new();
+
+        for _ in 0..1_000 {
+            let category = match rng.u8(..100) {
+                0..=69 => "Politics",
+                70..=94 => "Crypto",
+                _ => "Sports",
+            };
+            let (count_a, count_b) = counts.entry(category).or_default();
+            if test.assign_stratified(seeded_market(&mut rng), *count_a, *count_b) == "a" {
+                *count_a += 1;
+            } else {
+                *count_b += 1;
+            }
+        }
+
+        for (category, (count_a, count_b)) in &counts {
+            let expected = 0.3 * (count_a + count_b) as f64;
+            assert!((*count_a as f64 - expected).abs() <= 1.0, "{}: {} of {}", category, count_a, count_a + count_b);
+        }
+        let rows = counts.iter().map(|(c, (a, b))| (c.to_string(), *a, *b)).collect();
+        assert!(!RebalanceReport::from_counts(&test, 0.02, rows).needs_rebalance());
+    }
+
+    #[test]
+    fn test_rebalance_report_flags_skewed_categories() {
+        let test = create_sequential_test(100);
+        let report = RebalanceReport::from_counts(
+            &test,
+            0.05,
+            vec![("Crypto".to_string(), 30, 10), ("Politics".to_string(), 95, 105), ("Sports".to_string(), 2, 1)],
+        );
+
+        assert_eq!((report.count_a, report.count_b), (127, 116));
+        assert!(!report.imbalanced);
+        let flagged: Vec<&str> = report.strata.iter().filter(|s| s.imbalanced).map(|s| s.category.as_str()).collect();
+        // Sports is as close to 50/50 as three markets allow
+        assert_eq!(flagged, ["Crypto"]);
+        assert!(report.needs_rebalance());
+    }
+
+    /// Runs against a scratch Postgres: `TEST_DATABASE_URL=... cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_assign_market_auto_postgres() {
+        use sqlx::postgres::PgPoolOptions;
+        use sqlx::Executor;
+
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL not set");
+        let schema = format!("ab_assign_{}", Uuid::new_v4().simple());
+        let setup = PgPoolOptions::new().max_connections(1).connect(&url).await.unwrap();
+        setup.execute(format!("CREATE SCHEMA {schema}").as_str()).await.unwrap();
+
+        // Several connections, to race assignments against each other
+        let search_path = format!("SET search_path TO {schema}");
+        let pool = Arc::new(
+            PgPoolOptions::new()
+                .max_connections(4)
+                .after_connect(move |conn, _| {
+                    let search_path = search_path.clone();
+                    Box::pin(async move { conn.execute(search_path.as_str()).await.map(|_| ()) })
+                })
+                .connect(&url)
+                .await
+                .unwrap(),
+        );
+
+        let manager = AbTestManager::new(pool.clone());
+        manager.initialize().await.unwrap();
+        let test = AbTest { allocation_ratio: 0.3, ..create_sequential_test(100) };
+        manager.create_test(test.clone()).await.unwrap();
+
+        let market = MarketId::random();
+        let racers = (0..8).map(|_| manager.assign_market_auto(test.id, market));
+        let arms = futures::future::try_join_all(racers).await.unwrap();
+        assert!(arms.iter().all(|arm| *arm == test.assign_strategy(market)));
+
+        // A recorded assignment stands even if the hash would now say otherwise
+        let other = if arms[0] == "a" { "b" } else { "a" };
+        let pinned = MarketId::random();
+        manager.assign_market(test.id, pinned, other).await.unwrap();
+        if test.assign_strategy(pinned) != other {
+            assert_eq!(manager.assign_market_auto(test.id, pinned).await.unwrap(), other);
+        }
+
+        for _ in 0..20 {
+            manager.assign_market_stratified(test.id, MarketId::random(), "Crypto").await.unwrap();
+        }
+        let report = manager.rebalance_report(test.id).await.unwrap();
+        let crypto = report.strata.iter().find(|s| s.category == "Crypto").unwrap();
+        assert_eq!((crypto.count_a, crypto.count_b), (6, 14));
+        let uncategorized = report.strata.iter().find(|s| s.category == UNCATEGORIZED).unwrap();
+        assert_eq!(uncategorized.count_a + uncategorized.count_b, 2);
+
+        pool.close().await;
+        setup.execute(format!("DROP SCHEMA {schema} CASCADE").as_str()).await.unwrap();
+    }
+
+    /// Runs against a scratch Postgres: `TEST_DATABASE_URL=... cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_assignment_recorder_postgres() {
+        use sqlx::postgres::PgPoolOptions;
+        use sqlx::Executor;
+
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL not set");
+        let schema = format!("ab_recorder_{}", Uuid::new_v4().simple());
+        let setup = PgPoolOptions::new().max_connections(1).connect(&url).await.unwrap();
+        setup.execute(format!("CREATE SCHEMA {schema}").as_str()).await.unwrap();
+        let search_path = format!("SET search_path TO {schema}");
+        let pool = Arc::new(
+            PgPoolOptions::new()
+                .max_connections(2)
+                .after_connect(move |conn, _| {
+                    let search_path = search_path.clone();
+                    Box::pin(async move { conn.execute(search_path.as_str()).await.map(|_| ()) })
+                })
+                .connect(&url)
+                .await
+                .unwrap(),
+        );
+
+        let manager = Arc::new(AbTestManager::new(pool.clone()));
+        manager.initialize().await.unwrap();
+        let test = create_sequential_test(100);
+        manager.create_test(test.clone()).await.unwrap();
+
+        let (recorder, writer) = AbTestAssignmentRecorder::spawn(manager.clone());
+        let markets: Vec<MarketId> = (0..10).map(|_| MarketId::random()).collect();
+        for market_id in markets.iter().chain(&markets) {
+            recorder.record(ExperimentAssignment {
+                experiment_id: test.id,
+                market_id: *market_id,
+                strategy: test.assign_strategy(*market_id).to_string(),
+                category: "Crypto".to_string(),
+                assigned_at: Utc::now(),
+            });
+        }
+        drop(recorder);
+        writer.await.unwrap();
+
+        for market_id in &markets {
+            let stored = manager.get_assignment(test.id, *market_id).await.unwrap();
+            assert_eq!(stored.as_deref(), Some(test.assign_strategy(*market_id)));
+        }
+        let counts = manager.get_assignment_counts(test.id).await.unwrap();
+        assert_eq!(counts.count_a + counts.count_b, 10);
+        let report = manager.rebalance_report(test.id).await.unwrap();
+        assert!(report.strata.iter().all(|s| s.category == "Crypto"));
+
+        pool.close().await;
+        setup.execute(format!("DROP SCHEMA {schema} CASCADE").as_str()).await.unwrap();
+    }
+
+    async fn insert_strategy_trades(pool: &PgPool, strategy_id: &str, base: DateTime<Utc>, pnls: &[f64]) {
+        for (i, pnl) in pnls.iter().enumerate() {
+            let id = Uuid::new_v4();
+            sqlx::query("INSERT INTO trades (id, timestamp, pnl, pnl_percent) VALUES ($1, $2, $3, 0.0)")
+                .bind(id)
+                .bind(base + Duration::seconds(i as i64))
+                .bind(pnl)
+                .execute(pool)
+                .await
+                .unwrap();
+            sqlx::query("INSERT INTO attributed_trades (trade_id, strategy_id) VALUES ($1, $2)")
+                .bind(id)
+                .bind(strategy_id)
+                .execute(pool)
+                .await
+                .unwrap();
+        }
+    }
+
+    /// Runs against a scratch Postgres: `TEST_DATABASE_URL=... cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_evaluate_for_stopping_postgres() {
+        use sqlx::postgres::PgPoolOptions;
+
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL not set");
+        // Single connection so the scratch schema stays on the search path
+        let pool = Arc::new(PgPoolOptions::new().max_connections(1).connect(&url).await.unwrap());
+        let schema = format!("ab_testing_{}", Uuid::new_v4().simple());
+        sqlx::raw_sql(&format!(
+            r#"
+            CREATE SCHEMA {schema};
+            SET search_path TO {schema};
+            CREATE TABLE trades (
+                id UUID PRIMARY KEY,
+                timestamp TIMESTAMPTZ NOT NULL,
+                pnl DOUBLE PRECISION,
+                pnl_percent DOUBLE PRECISION
+            );
+            CREATE TABLE attributed_trades (
+                trade_id UUID PRIMARY KEY,
+                strategy_id TEXT NOT NULL
+            );
+            "#
+        ))
+        .execute(pool.as_ref())
+        .await
+        .unwrap();
+
+        let engine = AbTestEngine::new(pool.clone(), 0.0001);
+        engine.manager.initialize().await.unwrap();
+        let test = create_planned_test(4, 40);
+        engine.manager.create_test(test.clone()).await.unwrap();
+
+        // The trades that stop the pure simulation at its second look
+        let mut rng = fastrand::Rng::with_seed(11);
+        let pnls_a: Vec<f64> = (0..40).map(|_| 1.5 + normal_draw(&mut rng)).collect();
+        let pnls_b: Vec<f64> = (0..40).map(|_| normal_draw(&mut rng)).collect();
+        let base = Utc::now() - Duration::hours(1);
+
+        insert_strategy_trades(&pool, "a", base, &pnls_a[..10]).await;
+        insert_strategy_trades(&pool, "b", base, &pnls_b[..10]).await;
+        let decisions = engine.evaluate_all_running_tests().await.unwrap();
+        assert_eq!(decisions, vec![(test.id, SequentialDecision::Continue)]);
+
+        // No new trades, no new look
+        assert_eq!(engine.evaluate_for_stopping(test.id).await.unwrap(), SequentialDecision::Continue);
+        let stored = engine.fetch_test(test.id).await.unwrap();
+        assert_eq!((stored.current_look, stored.looks_planned), (1, 4));
+        assert_eq!(stored.status, AbTestStatus::Running);
+
+        insert_strategy_trades(&pool, "a", base + Duration::minutes(1), &pnls_a[10..20]).await;
+        insert_strategy_trades(&pool, "b", base + Duration::minutes(1), &pnls_b[10..20]).await;
+        assert_eq!(
+            engine.evaluate_for_stopping(test.id).await.unwrap(),
+            SequentialDecision::Stop { winner: "A".to_string() }
+        );
+
+        let stored = engine.fetch_test(test.id).await.unwrap();
+        assert_eq!(stored.status, AbTestStatus::Completed);
+        assert_eq!(stored.current_look, 2);
+        let winner: Option<String> = sqlx::query_scalar("SELECT winner FROM ab_test_results WHERE test_id = $1")
+            .bind(test.id)
+            .fetch_one(pool.as_ref())
+            .await
+            .unwrap();
+        assert_eq!(winner.as_deref(), Some("A"));
+        assert!(engine.evaluate_all_running_tests().await.unwrap().is_empty());
+
+        sqlx::raw_sql(&format!("DROP SCHEMA {schema} CASCADE")).execute(pool.as_ref()).await.unwrap();
+    }
 }
@@ -1,11 +1,12 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use common::{AttributedTrade, OrderSide, PerformanceMetrics, Signal, StrategyPerformance, Trade};
+use common::{AttributedTrade, MarketId, OrderSide, PerformanceMetrics, Signal, SignalType, StrategyPerformance, Trade};
 use rust_decimal::prelude::*;
 use sqlx::postgres::PgPool;
+use sqlx::FromRow;
 use std::collections::HashMap;
 use std::sync::Arc;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
 /// Attribution Engine - Maps trades to signals/agents and calculates P&L attribution
@@ -20,7 +21,7 @@ impl AttributionEngine {
 
     /// Initialize attribution tables
     pub async fn initialize(&self) -> Result<()> {
-        sqlx::query(
+        sqlx::raw_sql(
             r#"
             CREATE TABLE IF NOT EXISTS signals (
                 id UUID PRIMARY KEY,
@@ -31,10 +32,12 @@ impl AttributionEngine {
                 direction TEXT NOT NULL,
                 agent_id UUID NOT NULL,
                 strategy_id TEXT NOT NULL,
+                signal_type TEXT,
                 generated_at TIMESTAMPTZ NOT NULL,
                 metadata JSONB,
                 created_at TIMESTAMPTZ DEFAULT NOW()
             );
+            ALTER TABLE signals ADD COLUMN IF NOT EXISTS signal_type TEXT;
 
             CREATE INDEX IF NOT EXISTS idx_signals_market ON signals(market_id);
             CREATE INDEX IF NOT EXISTS idx_signals_strategy ON signals(strategy_id);
@@ -46,13 +49,49 @@ impl AttributionEngine {
                 signal_id UUID REFERENCES signals(id),
                 agent_id UUID NOT NULL,
                 strategy_id TEXT NOT NULL,
+                signal_type TEXT,
                 pnl NUMERIC(15, 4),
                 pnl_percent NUMERIC(10, 4),
-                attributed_at TIMESTAMPTZ DEFAULT NOW()
+                attributed_at TIMESTAMPTZ DEFAULT NOW(),
+                resolved_at TIMESTAMPTZ
             );
+            ALTER TABLE attributed_trades ADD COLUMN IF NOT EXISTS signal_type TEXT;
+            ALTER TABLE attributed_trades ADD COLUMN IF NOT EXISTS resolved_at TIMESTAMPTZ;
+            ALTER TABLE attributed_trades ADD COLUMN IF NOT EXISTS rebate_pnl NUMERIC(15, 4);
 
             CREATE INDEX IF NOT EXISTS idx_attributed_signal ON attributed_trades(signal_id);
             CREATE INDEX IF NOT EXISTS idx_attributed_strategy ON attributed_trades(strategy_id);
+
+            -- Append-only adjustments to booked trade P&L (e.g. reversed resolutions)
+            CREATE TABLE IF NOT EXISTS pnl_corrections (
+                id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+                trade_id UUID NOT NULL,
+                market_id UUID NOT NULL,
+                pnl DOUBLE PRECISION NOT NULL,
+                pnl_percent DOUBLE PRECISION NOT NULL,
+                reason TEXT NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_pnl_corrections_trade ON pnl_corrections(trade_id);
+            CREATE INDEX IF NOT EXISTS idx_pnl_corrections_market ON pnl_corrections(market_id);
+
+            -- Booked trade P&L net of corrections
+            CREATE OR REPLACE VIEW trade_pnl AS
+            SELECT
+                t.id AS trade_id,
+                t.pnl + COALESCE(SUM(c.pnl), 0) AS pnl,
+                t.pnl_percent + COALESCE(SUM(c.pnl_percent), 0) AS pnl_percent
+            FROM trades t
+            LEFT JOIN pnl_corrections c ON c.trade_id = t.id
+            GROUP BY t.id, t.pnl, t.pnl_percent;
+
+            -- Category of each traded market, for per-category attribution
+            CREATE TABLE IF NOT EXISTS market_categories (
+                market_id UUID PRIMARY KEY,
+                category TEXT NOT NULL,
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            );
             "#,
         )
         .execute(self.db_pool.as_ref())
@@ -69,9 +108,9 @@ impl AttributionEngine {
             r#"
             INSERT INTO signals (
                 id, market_id, outcome_id, predicted_probability, confidence,
-                direction, agent_id, strategy_id, generated_at, metadata
+                direction, agent_id, strategy_id, signal_type, generated_at, metadata
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
             "#,
         )
         .bind(signal.id)
@@ -85,6 +124,7 @@ impl AttributionEngine {
         })
         .bind(signal.agent_id)
         .bind(&signal.strategy_id)
+        .bind(signal.signal_type.as_ref().map(SignalType::as_str))
         .bind(signal.generated_at)
         .bind(&signal.metadata)
         .execute(self.db_pool.as_ref())
@@ -95,7 +135,27 @@ impl AttributionEngine {
         Ok(())
     }
 
+    /// Record the category a market belongs to, replacing any earlier one
+    pub async fn set_market_category(&self, market_id: MarketId, category: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO market_categories (market_id, category)
+            VALUES ($1, $2)
+            ON CONFLICT (market_id) DO UPDATE SET category = EXCLUDED.category, updated_at = NOW()
+            "#,
+        )
+        .bind(market_id)
+        .bind(category)
+        .execute(self.db_pool.as_ref())
+        .await
+        .context("Failed to set market category")?;
+
+        Ok(())
+    }
+
     /// Attribute a trade to a signal
+    ///
+    /// The signal's type is copied onto the attributed trade.
     pub async fn attribute_trade(
         &self,
         trade_id: Uuid,
@@ -105,8 +165,8 @@ impl AttributionEngine {
     ) -> Result<()> {
         sqlx::query(
             r#"
-            INSERT INTO attributed_trades (trade_id, signal_id, agent_id, strategy_id)
-            VALUES ($1, $2, $3, $4)
+            INSERT INTO attributed_trades (trade_id, signal_id, agent_id, strategy_id, signal_type)
+            VALUES ($1, $2, $3, $4, (SELECT signal_type FROM signals WHERE id = $2))
             "#,
         )
         .bind(trade_id)
@@ -121,6 +181,116 @@ impl AttributionEngine {
         Ok(())
     }
 
+    /// Append a compensating entry to a trade's booked P&L
+    ///
+    /// Booked P&L is never rewritten; attribution queries read it net of
+    /// corrections through the `trade_pnl` view.
+    pub async fn record_correction(
+        &self,
+        trade_id: Uuid,
+        market_id: MarketId,
+        pnl: f64,
+        pnl_percent: f64,
+        reason: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO pnl_corrections (trade_id, market_id, pnl, pnl_percent, reason)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(trade_id)
+        .bind(market_id)
+        .bind(pnl)
+        .bind(pnl_percent)
+        .bind(reason)
+        .execute(self.db_pool.as_ref())
+        .await
+        .context("Failed to record P&L correction")?;
+
+        debug!("Recorded {} correction of {:.4} for trade {}", reason, pnl, trade_id);
+        Ok(())
+    }
+
+    /// Book a liquidity rebate earned by a maker fill
+    ///
+    /// Rebates are kept apart from the trade's P&L, so attribution reports
+    /// them as their own line.
+    pub async fn record_rebate(&self, trade_id: Uuid, rebate: f64) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE attributed_trades
+            SET rebate_pnl = COALESCE(rebate_pnl, 0) + $2
+            WHERE trade_id = $1
+            "#,
+        )
+        .bind(trade_id)
+        .bind(rebate)
+        .execute(self.db_pool.as_ref())
+        .await
+        .context("Failed to record rebate")?;
+
+        debug!("Recorded rebate of {:.4} for trade {}", rebate, trade_id);
+        Ok(())
+    }
+
+    /// Mark the attributed trades of a settled market as resolved
+    ///
+    /// Returns how many attributed trades were marked.
+    pub async fn mark_market_resolved(&self, market_id: MarketId) -> Result<usize> {
+        let result = sqlx::query(
+            r#"
+            UPDATE attributed_trades at
+            SET resolved_at = NOW()
+            FROM trades t
+            WHERE at.trade_id = t.id AND t.market_id = $1
+            "#,
+        )
+        .bind(market_id)
+        .execute(self.db_pool.as_ref())
+        .await
+        .context("Failed to mark attributed trades resolved")?;
+
+        debug!("Marked {} attributed trades resolved for market {}", result.rows_affected(), market_id);
+        Ok(result.rows_affected() as usize)
+    }
+
+    /// Unmark the attributed trades of a market whose resolution was reversed
+    pub async fn clear_market_resolved(&self, market_id: MarketId) -> Result<usize> {
+        let result = sqlx::query(
+            r#"
+            UPDATE attributed_trades at
+            SET resolved_at = NULL
+            FROM trades t
+            WHERE at.trade_id = t.id AND t.market_id = $1 AND at.resolved_at IS NOT NULL
+            "#,
+        )
+        .bind(market_id)
+        .execute(self.db_pool.as_ref())
+        .await
+        .context("Failed to clear attributed trade resolutions")?;
+
+        Ok(result.rows_affected() as usize)
+    }
+
+    /// Get the corrections booked for a market's trades, oldest first
+    pub async fn get_corrections(&self, market_id: MarketId) -> Result<Vec<PnlCorrection>> {
+        let corrections = sqlx::query_as::<_, PnlCorrection>(
+            r#"
+            SELECT trade_id, market_id, pnl, pnl_percent, reason, created_at
+            FROM pnl_corrections
+            WHERE market_id = $1
+            ORDER BY created_at, id
+            "#,
+        )
+        .bind(market_id)
+        .fetch_all(self.db_pool.as_ref())
+        .await
+        .context("Failed to fetch P&L corrections")?;
+
+        Ok(corrections)
+    }
+
     /// Get all trades attributed to a strategy
     pub async fn get_strategy_trades(
         &self,
@@ -142,9 +312,10 @@ impl AttributionEngine {
                 s.id as signal_id,
                 s.agent_id,
                 s.strategy_id,
-                t.pnl,
-                t.pnl_percent
+                p.pnl,
+                p.pnl_percent
             FROM trades t
+            JOIN trade_pnl p ON p.trade_id = t.id
             JOIN attributed_trades at ON t.id = at.trade_id
             JOIN signals s ON at.signal_id = s.id
             WHERE s.strategy_id = $1
@@ -184,9 +355,10 @@ impl AttributionEngine {
                 s.id as signal_id,
                 s.agent_id,
                 s.strategy_id,
-                t.pnl,
-                t.pnl_percent
+                p.pnl,
+                p.pnl_percent
             FROM trades t
+            JOIN trade_pnl p ON p.trade_id = t.id
             JOIN attributed_trades at ON t.id = at.trade_id
             JOIN signals s ON at.signal_id = s.id
             WHERE s.agent_id = $1
@@ -212,18 +384,20 @@ impl AttributionEngine {
         from: DateTime<Utc>,
         to: DateTime<Utc>,
     ) -> Result<PnlAttribution> {
-        let row = sqlx::query_as::<_, (f64, i64, i64, i64, f64, f64, f64, f64)>(
+        let row = sqlx::query_as::<_, PnlTotals>(
             r#"
             SELECT
-                COALESCE(SUM(t.pnl), 0.0) as total_pnl,
+                COALESCE(SUM(p.pnl), 0.0) as total_pnl,
                 COUNT(*) as total_trades,
-                COUNT(*) FILTER (WHERE t.pnl > 0) as winning_trades,
-                COUNT(*) FILTER (WHERE t.pnl < 0) as losing_trades,
-                COALESCE(SUM(t.pnl) FILTER (WHERE t.pnl > 0), 0.0) as total_wins,
-                COALESCE(SUM(ABS(t.pnl)) FILTER (WHERE t.pnl < 0), 0.0) as total_losses,
-                COALESCE(AVG(t.pnl) FILTER (WHERE t.pnl > 0), 0.0) as avg_win,
-                COALESCE(AVG(ABS(t.pnl)) FILTER (WHERE t.pnl < 0), 0.0) as avg_loss
+                COUNT(*) FILTER (WHERE p.pnl > 0) as winning_trades,
+                COUNT(*) FILTER (WHERE p.pnl < 0) as losing_trades,
+                COALESCE(SUM(p.pnl) FILTER (WHERE p.pnl > 0), 0.0) as total_wins,
+                COALESCE(SUM(ABS(p.pnl)) FILTER (WHERE p.pnl < 0), 0.0) as total_losses,
+                COALESCE(AVG(p.pnl) FILTER (WHERE p.pnl > 0), 0.0) as avg_win,
+                COALESCE(AVG(ABS(p.pnl)) FILTER (WHERE p.pnl < 0), 0.0) as avg_loss,
+                COALESCE(SUM(at.rebate_pnl), 0.0)::DOUBLE PRECISION as rebate_pnl
             FROM trades t
+            JOIN trade_pnl p ON p.trade_id = t.id
             JOIN attributed_trades at ON t.id = at.trade_id
             WHERE at.strategy_id = $1
             AND t.timestamp >= $2
@@ -237,34 +411,176 @@ impl AttributionEngine {
         .await
         .context("Failed to calculate strategy P&L")?;
 
-        let hit_rate = if row.1 > 0 {
-            (row.2 as f64 / row.1 as f64) * 100.0
-        } else {
-            0.0
-        };
+        Ok(PnlAttribution::from_totals(strategy_id, (from, to), row))
+    }
 
-        let profit_factor = if row.5 > 0.0 { row.4 / row.5 } else { 0.0 };
+    /// Calculate a strategy's P&L attribution for each signal type it traded
+    ///
+    /// Trades whose signal has no recorded type are left out.
+    pub async fn calculate_pnl_by_signal_type(
+        &self,
+        strategy_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<HashMap<SignalType, PnlAttribution>> {
+        let rows = sqlx::query_as::<_, (String, f64, i64, i64, i64, f64, f64, f64, f64, f64)>(
+            r#"
+            SELECT
+                COALESCE(at.signal_type, s.signal_type) as signal_type,
+                COALESCE(SUM(p.pnl), 0.0) as total_pnl,
+                COUNT(*) as total_trades,
+                COUNT(*) FILTER (WHERE p.pnl > 0) as winning_trades,
+                COUNT(*) FILTER (WHERE p.pnl < 0) as losing_trades,
+                COALESCE(SUM(p.pnl) FILTER (WHERE p.pnl > 0), 0.0) as total_wins,
+                COALESCE(SUM(ABS(p.pnl)) FILTER (WHERE p.pnl < 0), 0.0) as total_losses,
+                COALESCE(AVG(p.pnl) FILTER (WHERE p.pnl > 0), 0.0) as avg_win,
+                COALESCE(AVG(ABS(p.pnl)) FILTER (WHERE p.pnl < 0), 0.0) as avg_loss,
+                COALESCE(SUM(at.rebate_pnl), 0.0)::DOUBLE PRECISION as rebate_pnl
+            FROM trades t
+            JOIN trade_pnl p ON p.trade_id = t.id
+            JOIN attributed_trades at ON t.id = at.trade_id
+            LEFT JOIN signals s ON at.signal_id = s.id
+            WHERE at.strategy_id = $1
+            AND t.timestamp >= $2
+            AND t.timestamp <= $3
+            AND COALESCE(at.signal_type, s.signal_type) IS NOT NULL
+            GROUP BY 1
+            "#,
+        )
+        .bind(strategy_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(self.db_pool.as_ref())
+        .await
+        .context("Failed to calculate P&L by signal type")?;
 
-        let roi = if row.4 + row.5 > 0.0 {
-            (row.0 / (row.4 + row.5)) * 100.0
-        } else {
-            0.0
-        };
+        let mut attribution = HashMap::new();
+        for (signal_type, total_pnl, total_trades, winning, losing, wins, losses, avg_win, avg_loss, rebate_pnl) in rows {
+            let Ok(signal_type) = signal_type.parse::<SignalType>() else {
+                warn!("Skipping {} trades with unknown signal type {}", total_trades, signal_type);
+                continue;
+            };
+            let totals = (total_pnl, total_trades, winning, losing, wins, losses, avg_win, avg_loss, rebate_pnl);
+            attribution.insert(signal_type, PnlAttribution::from_totals(strategy_id, (from, to), totals));
+        }
 
-        Ok(PnlAttribution {
-            strategy_id: strategy_id.to_string(),
-            period: (from, to),
-            total_pnl: row.0,
-            total_trades: row.1,
-            winning_trades: row.2,
-            losing_trades: row.3,
-            hit_rate,
-            total_wins: row.4,
-            total_losses: row.5,
-            avg_win: row.6,
-            avg_loss: row.7,
-            profit_factor,
-            roi,
+        Ok(attribution)
+    }
+
+    /// Calculate a strategy's P&L attribution for each market it traded,
+    /// largest absolute P&L first
+    pub async fn calculate_market_attribution(
+        &self,
+        strategy_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<MarketPnlAttribution>> {
+        let rows = sqlx::query_as::<_, (MarketId, f64, i64, i64, Option<f64>)>(
+            r#"
+            SELECT
+                t.market_id,
+                COALESCE(SUM(p.pnl), 0.0) as total_pnl,
+                COUNT(*) as total_trades,
+                COUNT(*) FILTER (WHERE p.pnl > 0) as winning_trades,
+                (AVG(EXTRACT(EPOCH FROM (at.resolved_at - t.timestamp))) / 3600.0)::DOUBLE PRECISION
+                    as avg_hold_hours
+            FROM trades t
+            JOIN trade_pnl p ON p.trade_id = t.id
+            JOIN attributed_trades at ON t.id = at.trade_id
+            WHERE at.strategy_id = $1
+            AND t.timestamp >= $2
+            AND t.timestamp <= $3
+            GROUP BY t.market_id
+            ORDER BY ABS(COALESCE(SUM(p.pnl), 0.0)) DESC, t.market_id
+            "#,
+        )
+        .bind(strategy_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(self.db_pool.as_ref())
+        .await
+        .context("Failed to calculate market attribution")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(market_id, total_pnl, total_trades, winning_trades, avg_hold_hours)| MarketPnlAttribution {
+                market_id,
+                total_pnl,
+                total_trades,
+                winning_trades,
+                hit_rate: hit_rate(winning_trades, total_trades),
+                avg_hold_hours,
+            })
+            .collect())
+    }
+
+    /// Calculate a strategy's P&L attribution for each market category it
+    /// traded, largest absolute P&L first
+    ///
+    /// Categories come from `set_market_category`; markets without one are
+    /// reported under `UNCATEGORIZED`.
+    pub async fn calculate_category_attribution(
+        &self,
+        strategy_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<CategoryPnlAttribution>> {
+        let rows = sqlx::query_as::<_, (String, f64, i64, i64, i64, Option<f64>)>(
+            r#"
+            SELECT
+                COALESCE(c.category, $4) as category,
+                COALESCE(SUM(p.pnl), 0.0) as total_pnl,
+                COUNT(*) as total_trades,
+                COUNT(*) FILTER (WHERE p.pnl > 0) as winning_trades,
+                COUNT(DISTINCT t.market_id) as markets,
+                (AVG(EXTRACT(EPOCH FROM (at.resolved_at - t.timestamp))) / 3600.0)::DOUBLE PRECISION
+                    as avg_hold_hours
+            FROM trades t
+            JOIN trade_pnl p ON p.trade_id = t.id
+            JOIN attributed_trades at ON t.id = at.trade_id
+            LEFT JOIN market_categories c ON c.market_id = t.market_id
+            WHERE at.strategy_id = $1
+            AND t.timestamp >= $2
+            AND t.timestamp <= $3
+            GROUP BY 1
+            ORDER BY ABS(COALESCE(SUM(p.pnl), 0.0)) DESC, 1
+            "#,
+        )
+        .bind(strategy_id)
+        .bind(from)
+        .bind(to)
+        .bind(UNCATEGORIZED)
+        .fetch_all(self.db_pool.as_ref())
+        .await
+        .context("Failed to calculate category attribution")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(category, total_pnl, total_trades, winning_trades, markets, avg_hold_hours)| {
+                CategoryPnlAttribution {
+                    category,
+                    markets,
+                    total_pnl,
+                    total_trades,
+                    winning_trades,
+                    hit_rate: hit_rate(winning_trades, total_trades),
+                    avg_hold_hours,
+                }
+            })
+            .collect())
+    }
+
+    /// Strategy, market and category attribution over the same period
+    pub async fn attribution_report(
+        &self,
+        strategy_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<AttributionReport> {
+        Ok(AttributionReport {
+            strategy: self.calculate_strategy_pnl(strategy_id, from, to).await?,
+            by_market: self.calculate_market_attribution(strategy_id, from, to).await?,
+            by_category: self.calculate_category_attribution(strategy_id, from, to).await?,
         })
     }
 
@@ -280,11 +596,12 @@ impl AttributionEngine {
             SELECT
                 at.strategy_id,
                 COUNT(*) as total_trades,
-                COALESCE(SUM(t.pnl), 0.0) as total_pnl,
-                COALESCE(SUM(t.pnl) FILTER (WHERE t.pnl > 0), 0.0) as gross_profit,
-                COALESCE(SUM(ABS(t.pnl)) FILTER (WHERE t.pnl < 0), 0.0) as gross_loss,
-                COALESCE(AVG(t.pnl_percent), 0.0) as avg_return_pct
+                COALESCE(SUM(p.pnl), 0.0) as total_pnl,
+                COALESCE(SUM(p.pnl) FILTER (WHERE p.pnl > 0), 0.0) as gross_profit,
+                COALESCE(SUM(ABS(p.pnl)) FILTER (WHERE p.pnl < 0), 0.0) as gross_loss,
+                COALESCE(AVG(p.pnl_percent), 0.0) as avg_return_pct
             FROM trades t
+            JOIN trade_pnl p ON p.trade_id = t.id
             JOIN attributed_trades at ON t.id = at.trade_id
             WHERE t.timestamp >= $1 AND t.timestamp <= $2
             GROUP BY at.strategy_id
@@ -310,13 +627,14 @@ impl AttributionEngine {
         let row = sqlx::query_as::<_, (i64, f64, f64, i64, f64, f64)>(
             r#"
             SELECT
-                COUNT(*) FILTER (WHERE t.pnl > 0) as win_count,
-                COALESCE(AVG(s.predicted_probability) FILTER (WHERE t.pnl > 0), 0.0) as win_avg_confidence,
-                COALESCE(AVG(s.confidence) FILTER (WHERE t.pnl > 0), 0.0) as win_avg_model_conf,
-                COUNT(*) FILTER (WHERE t.pnl <= 0) as loss_count,
-                COALESCE(AVG(s.predicted_probability) FILTER (WHERE t.pnl <= 0), 0.0) as loss_avg_confidence,
-                COALESCE(AVG(s.confidence) FILTER (WHERE t.pnl <= 0), 0.0) as loss_avg_model_conf
+                COUNT(*) FILTER (WHERE p.pnl > 0) as win_count,
+                COALESCE(AVG(s.predicted_probability) FILTER (WHERE p.pnl > 0), 0.0) as win_avg_confidence,
+                COALESCE(AVG(s.confidence) FILTER (WHERE p.pnl > 0), 0.0) as win_avg_model_conf,
+                COUNT(*) FILTER (WHERE p.pnl <= 0) as loss_count,
+                COALESCE(AVG(s.predicted_probability) FILTER (WHERE p.pnl <= 0), 0.0) as loss_avg_confidence,
+                COALESCE(AVG(s.confidence) FILTER (WHERE p.pnl <= 0), 0.0) as loss_avg_model_conf
             FROM trades t
+            JOIN trade_pnl p ON p.trade_id = t.id
             JOIN attributed_trades at ON t.id = at.trade_id
             JOIN signals s ON at.signal_id = s.id
             WHERE at.strategy_id = $1
@@ -343,7 +661,10 @@ impl AttributionEngine {
 pub struct PnlAttribution {
     pub strategy_id: String,
     pub period: (DateTime<Utc>, DateTime<Utc>),
+    /// Trading P&L, excluding rebates
     pub total_pnl: f64,
+    /// Liquidity rebates on the strategy's maker fills
+    pub rebate_pnl: f64,
     pub total_trades: i64,
     pub winning_trades: i64,
     pub losing_trades: i64,
@@ -356,6 +677,118 @@ pub struct PnlAttribution {
     pub roi: f64,
 }
 
+/// Aggregates behind a `PnlAttribution`: total P&L, trades, winners,
+/// losers, total wins, total losses, average win, average loss, rebates
+type PnlTotals = (f64, i64, i64, i64, f64, f64, f64, f64, f64);
+
+impl PnlAttribution {
+    fn from_totals(strategy_id: &str, period: (DateTime<Utc>, DateTime<Utc>), row: PnlTotals) -> Self {
+        let hit_rate = hit_rate(row.2, row.1);
+
+        let profit_factor = if row.5 > 0.0 { row.4 / row.5 } else { 0.0 };
+
+        let roi = if row.4 + row.5 > 0.0 {
+            (row.0 / (row.4 + row.5)) * 100.0
+        } else {
+            0.0
+        };
+
+        PnlAttribution {
+            strategy_id: strategy_id.to_string(),
+            period,
+            total_pnl: row.0,
+            rebate_pnl: row.8,
+            total_trades: row.1,
+            winning_trades: row.2,
+            losing_trades: row.3,
+            hit_rate,
+            total_wins: row.4,
+            total_losses: row.5,
+            avg_win: row.6,
+            avg_loss: row.7,
+            profit_factor,
+            roi,
+        }
+    }
+}
+
+/// Winning trades as a percentage of all trades
+fn hit_rate(winning_trades: i64, total_trades: i64) -> f64 {
+    if total_trades > 0 {
+        (winning_trades as f64 / total_trades as f64) * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// Category reported for markets without a recorded one
+pub const UNCATEGORIZED: &str = "uncategorized";
+
+/// A strategy's P&L in one market
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketPnlAttribution {
+    pub market_id: MarketId,
+    pub total_pnl: f64,
+    pub total_trades: i64,
+    pub winning_trades: i64,
+    pub hit_rate: f64,
+    /// Mean hours from entry to resolution over the resolved trades; None
+    /// while none have resolved
+    pub avg_hold_hours: Option<f64>,
+}
+
+/// A strategy's P&L in one market category
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryPnlAttribution {
+    pub category: String,
+    /// Distinct markets traded in the category
+    pub markets: i64,
+    pub total_pnl: f64,
+    pub total_trades: i64,
+    pub winning_trades: i64,
+    pub hit_rate: f64,
+    pub avg_hold_hours: Option<f64>,
+}
+
+/// A strategy's P&L broken down by market and by category
+#[derive(Debug, Clone)]
+pub struct AttributionReport {
+    pub strategy: PnlAttribution,
+    /// Largest absolute P&L first
+    pub by_market: Vec<MarketPnlAttribution>,
+    /// Largest absolute P&L first
+    pub by_category: Vec<CategoryPnlAttribution>,
+}
+
+impl AttributionReport {
+    /// The `n` markets that made the most, best first
+    pub fn top_winners(&self, n: usize) -> Vec<&MarketPnlAttribution> {
+        let mut winners: Vec<_> = self.by_market.iter().filter(|m| m.total_pnl > 0.0).collect();
+        winners.sort_by(|a, b| b.total_pnl.total_cmp(&a.total_pnl));
+        winners.truncate(n);
+        winners
+    }
+
+    /// The `n` markets that lost the most, worst first
+    pub fn top_losers(&self, n: usize) -> Vec<&MarketPnlAttribution> {
+        let mut losers: Vec<_> = self.by_market.iter().filter(|m| m.total_pnl < 0.0).collect();
+        losers.sort_by(|a, b| a.total_pnl.total_cmp(&b.total_pnl));
+        losers.truncate(n);
+        losers
+    }
+}
+
+/// Compensating entry against a trade's booked P&L
+#[derive(Debug, Clone, FromRow)]
+pub struct PnlCorrection {
+    pub trade_id: Uuid,
+    pub market_id: MarketId,
+    pub pnl: f64,
+    pub pnl_percent: f64,
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+}
+
 /// Analysis of signal outcomes
 #[derive(Debug, Clone)]
 pub struct SignalOutcomeAnalysis {
@@ -366,3 +799,241 @@ pub struct SignalOutcomeAnalysis {
     pub loss_avg_probability: f64,
     pub loss_avg_confidence: f64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pool on a fresh schema of the Postgres at TEST_DATABASE_URL
+    async fn scratch_pool() -> (Arc<PgPool>, String) {
+        use sqlx::postgres::PgPoolOptions;
+
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL not set");
+        // Single connection so the scratch schema stays on the search path
+        let pool = Arc::new(PgPoolOptions::new().max_connections(1).connect(&url).await.unwrap());
+        let schema = format!("attribution_{}", Uuid::new_v4().simple());
+        sqlx::raw_sql(&format!("CREATE SCHEMA {schema}; SET search_path TO {schema}"))
+            .execute(pool.as_ref())
+            .await
+            .unwrap();
+        (pool, schema)
+    }
+
+    async fn drop_schema(pool: &PgPool, schema: &str) {
+        sqlx::raw_sql(&format!("DROP SCHEMA {schema} CASCADE")).execute(pool).await.unwrap();
+    }
+
+    /// Runs against a scratch Postgres: `TEST_DATABASE_URL=... cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_pnl_split_by_signal_type_postgres() {
+        let (pool, schema) = scratch_pool().await;
+
+        // Tables as they were before signal types were recorded
+        sqlx::raw_sql(
+            r#"
+            CREATE TABLE trades (
+                id UUID PRIMARY KEY,
+                market_id UUID NOT NULL,
+                pnl DOUBLE PRECISION,
+                pnl_percent DOUBLE PRECISION,
+                timestamp TIMESTAMPTZ NOT NULL
+            );
+            CREATE TABLE signals (
+                id UUID PRIMARY KEY,
+                market_id UUID NOT NULL,
+                outcome_id TEXT NOT NULL,
+                predicted_probability DOUBLE PRECISION NOT NULL,
+                confidence DOUBLE PRECISION NOT NULL,
+                direction TEXT NOT NULL,
+                agent_id UUID NOT NULL,
+                strategy_id TEXT NOT NULL,
+                generated_at TIMESTAMPTZ NOT NULL,
+                metadata JSONB
+            );
+            CREATE TABLE attributed_trades (
+                trade_id UUID PRIMARY KEY,
+                signal_id UUID,
+                agent_id UUID NOT NULL,
+                strategy_id TEXT NOT NULL
+            );
+            "#,
+        )
+        .execute(pool.as_ref())
+        .await
+        .unwrap();
+
+        let attribution = AttributionEngine::new(pool.clone());
+        attribution.initialize().await.unwrap();
+
+        let agent_id = Uuid::new_v4();
+        let now = Utc::now();
+        let trades = [
+            (Some(SignalType::SpreadArbitrage), 12.0),
+            (Some(SignalType::SpreadArbitrage), -2.0),
+            (Some(SignalType::MeanReversion), -5.0),
+            (Some(SignalType::MeanReversion), -1.0),
+            (None, 100.0),
+        ];
+        for (signal_type, pnl) in trades {
+            let signal = Signal {
+                id: Uuid::new_v4(),
+                market_id: MarketId::random(),
+                outcome_id: "YES".into(),
+                predicted_probability: 0.6,
+                confidence: 0.8,
+                direction: OrderSide::Buy,
+                agent_id,
+                strategy_id: "mixed".to_string(),
+                signal_type,
+                generated_at: now,
+                metadata: serde_json::json!({}),
+            };
+            attribution.store_signal(&signal).await.unwrap();
+
+            let trade_id = Uuid::new_v4();
+            sqlx::query("INSERT INTO trades (id, market_id, pnl, pnl_percent, timestamp) VALUES ($1, $2, $3, 0, $4)")
+                .bind(trade_id)
+                .bind(signal.market_id)
+                .bind(pnl)
+                .bind(now)
+                .execute(pool.as_ref())
+                .await
+                .unwrap();
+            attribution.attribute_trade(trade_id, signal.id, agent_id, "mixed").await.unwrap();
+        }
+
+        let from = now - chrono::Duration::hours(1);
+        let to = now + chrono::Duration::hours(1);
+        let by_type = attribution.calculate_pnl_by_signal_type("mixed", from, to).await.unwrap();
+        assert_eq!(by_type.len(), 2);
+
+        let spread = &by_type[&SignalType::SpreadArbitrage];
+        assert_eq!(spread.total_trades, 2);
+        assert!((spread.total_pnl - 10.0).abs() < 1e-9);
+        assert!((spread.hit_rate - 50.0).abs() < 1e-9);
+
+        let reversion = &by_type[&SignalType::MeanReversion];
+        assert_eq!(reversion.total_trades, 2);
+        assert_eq!(reversion.losing_trades, 2);
+        assert!((reversion.total_pnl + 6.0).abs() < 1e-9);
+
+        // The untyped trade still counts towards the strategy as a whole
+        let total = attribution.calculate_strategy_pnl("mixed", from, to).await.unwrap();
+        assert_eq!(total.total_trades, 5);
+        assert!((total.total_pnl - 104.0).abs() < 1e-9);
+
+        drop_schema(&pool, &schema).await;
+    }
+
+    fn market(total_pnl: f64) -> MarketPnlAttribution {
+        MarketPnlAttribution {
+            market_id: MarketId::random(),
+            total_pnl,
+            total_trades: 1,
+            winning_trades: i64::from(total_pnl > 0.0),
+            hit_rate: 0.0,
+            avg_hold_hours: None,
+        }
+    }
+
+    #[test]
+    fn test_top_winners_and_losers() {
+        let now = Utc::now();
+        let report = AttributionReport {
+            strategy: PnlAttribution::from_totals("s", (now, now), (0.0, 0, 0, 0, 0.0, 0.0, 0.0, 0.0, 0.0)),
+            by_market: [-30.0, 25.0, 12.0, -4.0, 0.0, 40.0].into_iter().map(market).collect(),
+            by_category: vec![],
+        };
+
+        let winners: Vec<_> = report.top_winners(2).iter().map(|m| m.total_pnl).collect();
+        assert_eq!(winners, vec![40.0, 25.0]);
+        let losers: Vec<_> = report.top_losers(5).iter().map(|m| m.total_pnl).collect();
+        assert_eq!(losers, vec![-30.0, -4.0]);
+        assert!(report.top_winners(0).is_empty());
+    }
+
+    /// Same scratch Postgres as above
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_market_and_category_attribution_postgres() {
+        let (pool, schema) = scratch_pool().await;
+        sqlx::query(
+            r#"
+            CREATE TABLE trades (
+                id UUID PRIMARY KEY,
+                market_id UUID NOT NULL,
+                pnl DOUBLE PRECISION,
+                pnl_percent DOUBLE PRECISION,
+                timestamp TIMESTAMPTZ NOT NULL
+            )
+            "#,
+        )
+        .execute(pool.as_ref())
+        .await
+        .unwrap();
+
+        let attribution = AttributionEngine::new(pool.clone());
+        attribution.initialize().await.unwrap();
+
+        let (election, senate, rates) = (MarketId::random(), MarketId::random(), MarketId::random());
+        attribution.set_market_category(election, "sports").await.unwrap();
+        // A later category replaces the first
+        attribution.set_market_category(election, "politics").await.unwrap();
+        attribution.set_market_category(senate, "politics").await.unwrap();
+
+        let agent_id = Uuid::new_v4();
+        let now = Utc::now();
+        let trades = [(election, 20.0), (election, -5.0), (senate, 3.0), (rates, -40.0), (rates, 2.0)];
+        for (market_id, pnl) in trades {
+            let signal = Signal {
+                id: Uuid::new_v4(),
+                market_id,
+                outcome_id: "YES".into(),
+                predicted_probability: 0.6,
+                confidence: 0.8,
+                direction: OrderSide::Buy,
+                agent_id,
+                strategy_id: "bleeding".to_string(),
+                signal_type: None,
+                generated_at: now,
+                metadata: serde_json::json!({}),
+            };
+            attribution.store_signal(&signal).await.unwrap();
+
+            let trade_id = Uuid::new_v4();
+            sqlx::query("INSERT INTO trades (id, market_id, pnl, pnl_percent, timestamp) VALUES ($1, $2, $3, 0, $4)")
+                .bind(trade_id)
+                .bind(market_id)
+                .bind(pnl)
+                .bind(now - chrono::Duration::hours(6))
+                .execute(pool.as_ref())
+                .await
+                .unwrap();
+            attribution.attribute_trade(trade_id, signal.id, agent_id, "bleeding").await.unwrap();
+        }
+        attribution.mark_market_resolved(election).await.unwrap();
+
+        let from = now - chrono::Duration::days(1);
+        let report = attribution.attribution_report("bleeding", from, now).await.unwrap();
+        assert!((report.strategy.total_pnl + 20.0).abs() < 1e-9);
+
+        let markets: Vec<_> = report.by_market.iter().map(|m| (m.market_id, m.total_trades)).collect();
+        assert_eq!(markets, vec![(rates, 2), (election, 2), (senate, 1)]);
+        assert!((report.by_market[0].total_pnl + 38.0).abs() < 1e-9);
+        assert!((report.by_market[1].total_pnl - 15.0).abs() < 1e-9);
+        assert!((report.by_market[1].hit_rate - 50.0).abs() < 1e-9);
+        let hold = report.by_market[1].avg_hold_hours.unwrap();
+        assert!((hold - 6.0).abs() < 0.1);
+        assert_eq!(report.by_market[0].avg_hold_hours, None);
+
+        let categories: Vec<_> = report.by_category.iter().map(|c| (c.category.as_str(), c.markets)).collect();
+        assert_eq!(categories, vec![(UNCATEGORIZED, 1), ("politics", 2)]);
+        assert!((report.by_category[1].total_pnl - 18.0).abs() < 1e-9);
+
+        assert_eq!(report.top_losers(1)[0].market_id, rates);
+        assert_eq!(report.top_winners(1)[0].market_id, election);
+
+        drop_schema(&pool, &schema).await;
+    }
+}
@@ -0,0 +1,272 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use common::Market;
+use monitoring::{BundleExporter, BundleScope, JsonlSource, PgTableSource};
+use serde_json::Value;
+use signal_generation::{CorrelationConfig, CorrelationGenerator, DEFAULT_MAX_AUTO_APPLY_SIGNALS};
+use sqlx::postgres::PgPoolOptions;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{info, warn, Level};
+use tracing_subscriber::fmt;
+use uuid::Uuid;
+
+const USAGE: &str = "usage: polyctl bundle --market <uuid> [--from <rfc3339>] [--to <rfc3339>] [--out <dir>]
+                      [--events <jsonl>] [--agent-outputs <jsonl>] [--research-cache <jsonl>]
+                      [--validations <jsonl>] [--positions <jsonl>]
+       polyctl why <market-uuid> --diagnostics <jsonl> [--cycles <n>]
+       polyctl rules reload <rules.toml> [--current <toml>] [--markets <jsonl>]
+                            [--max-signals <n>] [--pid <pid>]
+
+Tables are read from DATABASE_URL when it is set. `why` reads a dump written
+by the signal pipeline's DiagnosticsLog::write_jsonl; a running pipeline serves
+the same history from its admin API (signal-generation/README.md).
+
+`rules reload` validates a correlation rule file and previews it against the
+markets in --markets (one serialized Market per line, priced by their first
+outcome), compared with the rules in --current. With --pid it then sends
+SIGHUP so the running pipeline reloads. Changes that would generate more than
+--max-signals signals at once are staged by the pipeline until approved
+through its admin API (signal-generation/README.md).";
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    fmt().with_max_level(Level::INFO).init();
+
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        Some("bundle") => bundle(args.collect()).await,
+        Some("why") => why(args.collect()),
+        Some("rules") => match args.next().as_deref() {
+            Some("reload") => rules_reload(args.collect()),
+            _ => bail!("{}", USAGE),
+        },
+        _ => bail!("{}", USAGE),
+    }
+}
+
+async fn bundle(args: Vec<String>) -> Result<()> {
+    let mut market_id = None;
+    let mut from = DateTime::<Utc>::MIN_UTC;
+    let mut to = Utc::now();
+    let mut out = None;
+    let mut exporter = BundleExporter::new();
+
+    let mut args = args.into_iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().with_context(|| format!("{} needs a value\n{}", flag, USAGE))?;
+        match flag.as_str() {
+            "--market" => market_id = Some(Uuid::parse_str(&value).context("Invalid --market")?),
+            "--from" => from = value.parse().context("Invalid --from")?,
+            "--to" => to = value.parse().context("Invalid --to")?,
+            "--out" => out = Some(PathBuf::from(value)),
+            "--events" => exporter = exporter.with_source(JsonlSource::market_events("events", value)),
+            "--agent-outputs" => exporter = exporter.with_source(JsonlSource::new("agent_outputs", value)),
+            "--research-cache" => exporter = exporter.with_source(JsonlSource::new("research_cache", value)),
+            "--validations" => {
+                exporter = exporter
+                    .with_source(JsonlSource::new("signal_validations", value).with_time_field("created_at"))
+            }
+            "--positions" => exporter = exporter.with_source(JsonlSource::new("positions", value)),
+            _ => bail!("Unknown flag {}\n{}", flag, USAGE),
+        }
+    }
+
+    let market_id = market_id.with_context(|| format!("--market is required\n{}", USAGE))?;
+    let out = out.unwrap_or_else(|| PathBuf::from(format!("bundle-{}", market_id)));
+
+    if let Ok(database_url) = std::env::var("DATABASE_URL") {
+        let pool = Arc::new(
+            PgPoolOptions::new()
+                .max_connections(1)
+                .connect(&database_url)
+                .await
+                .context("Failed to connect to DATABASE_URL")?,
+        );
+        exporter = exporter
+            .with_source(PgTableSource::signals(pool.clone()))
+            .with_source(PgTableSource::trades(pool.clone()))
+            .with_source(PgTableSource::pnl_corrections(pool.clone()))
+            .with_source(PgTableSource::alerts(pool));
+    }
+
+    let manifest = exporter
+        .export(&BundleScope::new(market_id, from, to), &out)
+        .await?;
+
+    for artifact in &manifest.artifacts {
+        info!("  {:<20} {:>8} rows  ({})", artifact.name, artifact.rows, artifact.source);
+    }
+    Ok(())
+}
+
+fn why(args: Vec<String>) -> Result<()> {
+    let mut args = args.into_iter();
+    let market = args.next().with_context(|| format!("why needs a market\n{}", USAGE))?;
+    let market_id = Uuid::parse_str(&market).context("Invalid market")?;
+    let mut diagnostics = None;
+    let mut cycles = 20;
+
+    while let Some(flag) = args.next() {
+        let value = args.next().with_context(|| format!("{} needs a value\n{}", flag, USAGE))?;
+        match flag.as_str() {
+            "--diagnostics" => diagnostics = Some(PathBuf::from(value)),
+            "--cycles" => cycles = value.parse().context("Invalid --cycles")?,
+            _ => bail!("Unknown flag {}\n{}", flag, USAGE),
+        }
+    }
+
+    let path = diagnostics.with_context(|| format!("--diagnostics is required\n{}", USAGE))?;
+    let contents = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let market_key = market_id.to_string();
+    let mut entries = Vec::new();
+    for (number, line) in contents.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        let entry: Value = serde_json::from_str(line)
+            .with_context(|| format!("{}:{} is not valid JSON", path.display(), number + 1))?;
+        if entry["market_id"].as_str() == Some(market_key.as_str()) {
+            entries.push(entry);
+        }
+    }
+    entries.sort_by_key(|e| e["cycle"].as_u64().unwrap_or(0));
+    let entries = &entries[entries.len().saturating_sub(cycles)..];
+
+    if entries.is_empty() {
+        info!("No diagnostics recorded for market {}", market_id);
+        return Ok(());
+    }
+
+    for entry in entries {
+        let outcomes = entry["outcomes"].as_array().map(Vec::as_slice).unwrap_or_default();
+        let emitted = outcomes
+            .iter()
+            .any(|o| matches!(o["stage"].as_str(), Some("emitted" | "executed")));
+        info!(
+            "cycle {} at {}: {}",
+            entry["cycle"],
+            entry["recorded_at"].as_str().unwrap_or("?"),
+            if emitted { "signal emitted" } else { "nothing emitted" }
+        );
+        for outcome in outcomes {
+            info!("  {}", describe_outcome(outcome));
+        }
+    }
+    Ok(())
+}
+
+fn rules_reload(args: Vec<String>) -> Result<()> {
+    let mut args = args.into_iter();
+    let rules = PathBuf::from(args.next().with_context(|| format!("rules reload needs a rules file\n{}", USAGE))?);
+    let mut current = None;
+    let mut markets = None;
+    let mut max_signals = DEFAULT_MAX_AUTO_APPLY_SIGNALS;
+    let mut pid = None;
+
+    while let Some(flag) = args.next() {
+        let value = args.next().with_context(|| format!("{} needs a value\n{}", flag, USAGE))?;
+        match flag.as_str() {
+            "--current" => current = Some(PathBuf::from(value)),
+            "--markets" => markets = Some(PathBuf::from(value)),
+            "--max-signals" => max_signals = value.parse().context("Invalid --max-signals")?,
+            "--pid" => pid = Some(value.parse::<u32>().context("Invalid --pid")?),
+            _ => bail!("Unknown flag {}\n{}", flag, USAGE),
+        }
+    }
+
+    let mut generator = match &current {
+        Some(path) => CorrelationGenerator::from_config_file(path)?,
+        None => CorrelationGenerator::new(),
+    };
+    let known = match &markets {
+        Some(path) => {
+            let markets = read_markets(path)?;
+            for market in &markets {
+                generator.observe_market(market);
+            }
+            markets.len()
+        }
+        None => 0,
+    };
+
+    // Unknown markets are only an error when there are markets to check against
+    let content = std::fs::read_to_string(&rules).with_context(|| format!("Failed to read {}", rules.display()))?;
+    let registry = (known > 0).then(|| &*generator.registry_mut());
+    let config = CorrelationConfig::validate(&content, registry)
+        .with_context(|| format!("Invalid correlation config {}", rules.display()))?;
+
+    let preview = generator.preview_config(&config)?;
+    info!("{}: {}", rules.display(), preview.summary());
+    for edge in &preview.added_edges {
+        info!("  + {:?} {} -> {}", edge.correlation_type, edge.from_market, edge.to_market);
+    }
+    for edge in &preview.removed_edges {
+        info!("  - {:?} {} -> {}", edge.correlation_type, edge.from_market, edge.to_market);
+    }
+    for violation in &preview.new_violations {
+        info!("  new violation: {} ({} legs)", violation.description, violation.trades.len());
+    }
+    for violation in &preview.cleared_violations {
+        info!("  cleared violation: {}", violation.description);
+    }
+    if preview.pending_rules > 0 {
+        info!("  {} rules wait for markets that are not known yet", preview.pending_rules);
+    }
+
+    if preview.signal_count() > max_signals {
+        warn!(
+            "This change would generate {} signals at once (gate {}), the pipeline will stage it until it is approved",
+            preview.signal_count(),
+            max_signals
+        );
+    }
+
+    if let Some(pid) = pid {
+        let status = std::process::Command::new("kill")
+            .args(["-HUP", &pid.to_string()])
+            .status()
+            .context("Failed to run kill")?;
+        if !status.success() {
+            bail!("Failed to send SIGHUP to {}", pid);
+        }
+        info!("Sent SIGHUP to {}, the rules reload on its next batch", pid);
+    }
+    Ok(())
+}
+
+fn read_markets(path: &Path) -> Result<Vec<Market>> {
+    let contents = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(number, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("{}:{} is not a serialized market", path.display(), number + 1))
+        })
+        .collect()
+}
+
+/// `stage key=value ...`, with nested reasons flattened
+fn describe_outcome(outcome: &Value) -> String {
+    let mut line = outcome["stage"].as_str().unwrap_or("unknown").to_string();
+    if let Some(fields) = outcome.as_object() {
+        for (key, value) in fields.iter().filter(|(k, _)| k.as_str() != "stage") {
+            match value {
+                Value::Object(nested) => {
+                    for (nested_key, nested_value) in nested {
+                        line.push_str(&format!(" {}.{}={}", key, nested_key, plain(nested_value)));
+                    }
+                }
+                _ => line.push_str(&format!(" {}={}", key, plain(value))),
+            }
+        }
+    }
+    line
+}
+
+fn plain(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,658 @@
+// Market Bundles
+// Exports everything recorded about one market into a directory for offline research
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use common::MarketEvent;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::postgres::PgPool;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::fs::File;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter};
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// File name of the manifest inside a bundle directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Replacement for redacted values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Field names whose values never leave the system
+const SECRET_KEYS: &[&str] = &[
+    "secret",
+    "password",
+    "passphrase",
+    "api_key",
+    "apikey",
+    "private_key",
+    "token",
+    "authorization",
+    "credential",
+];
+
+/// The market and time window a bundle covers
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BundleScope {
+    pub market_id: Uuid,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+impl BundleScope {
+    pub fn new(market_id: Uuid, from: DateTime<Utc>, to: DateTime<Utc>) -> Self {
+        Self { market_id, from, to }
+    }
+
+    pub fn contains(&self, timestamp: DateTime<Utc>) -> bool {
+        timestamp >= self.from && timestamp <= self.to
+    }
+}
+
+/// Index of a bundle, written last so a bundle without one is incomplete
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub market_id: Uuid,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub artifacts: Vec<ArtifactEntry>,
+}
+
+impl BundleManifest {
+    pub fn artifact(&self, name: &str) -> Option<&ArtifactEntry> {
+        self.artifacts.iter().find(|a| a.name == name)
+    }
+}
+
+/// One JSONL file of a bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactEntry {
+    pub name: String,
+    /// File name relative to the bundle directory
+    pub file: String,
+    /// Where the records were read from
+    pub source: String,
+    pub rows: u64,
+    pub first_timestamp: Option<DateTime<Utc>>,
+    pub last_timestamp: Option<DateTime<Utc>>,
+    /// Number of values replaced by `REDACTED`
+    pub redacted_fields: u64,
+}
+
+/// A store that can export its records for one market
+#[async_trait]
+pub trait ArtifactSource: Send + Sync {
+    /// Artifact name, also used as the file name
+    fn name(&self) -> &str;
+
+    /// Human-readable origin, recorded in the manifest
+    fn source(&self) -> String;
+
+    /// Write every record of the scope to the sink, one at a time
+    async fn export(&self, scope: &BundleScope, sink: &mut ArtifactSink) -> Result<()>;
+}
+
+/// Streams records of one artifact to its file
+pub struct ArtifactSink {
+    writer: BufWriter<File>,
+    rows: u64,
+    first_timestamp: Option<DateTime<Utc>>,
+    last_timestamp: Option<DateTime<Utc>>,
+    redacted_fields: u64,
+}
+
+impl ArtifactSink {
+    async fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .await
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+
+        Ok(Self {
+            writer: BufWriter::new(file),
+            rows: 0,
+            first_timestamp: None,
+            last_timestamp: None,
+            redacted_fields: 0,
+        })
+    }
+
+    /// Redact and append a record
+    pub async fn write(&mut self, mut record: Value, timestamp: Option<DateTime<Utc>>) -> Result<()> {
+        self.redacted_fields += redact(&mut record);
+
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+        self.writer.write_all(&line).await.context("Failed to write bundle record")?;
+
+        if let Some(timestamp) = timestamp {
+            self.first_timestamp = Some(self.first_timestamp.map_or(timestamp, |t| t.min(timestamp)));
+            self.last_timestamp = Some(self.last_timestamp.map_or(timestamp, |t| t.max(timestamp)));
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    async fn finish(mut self, name: &str, file: String, source: String) -> Result<ArtifactEntry> {
+        self.writer.flush().await.context("Failed to flush bundle file")?;
+
+        Ok(ArtifactEntry {
+            name: name.to_string(),
+            file,
+            source,
+            rows: self.rows,
+            first_timestamp: self.first_timestamp,
+            last_timestamp: self.last_timestamp,
+            redacted_fields: self.redacted_fields,
+        })
+    }
+}
+
+/// Replace the values of secret-looking fields, at any depth.
+///
+/// Returns the number of values replaced.
+pub fn redact(value: &mut Value) -> u64 {
+    match value {
+        Value::Object(fields) => fields
+            .iter_mut()
+            .map(|(key, value)| {
+                let key = key.to_lowercase();
+                if SECRET_KEYS.iter().any(|secret| key.contains(secret)) && !value.is_null() {
+                    *value = Value::String(REDACTED.to_string());
+                    1
+                } else {
+                    redact(value)
+                }
+            })
+            .sum(),
+        Value::Array(items) => items.iter_mut().map(redact).sum(),
+        _ => 0,
+    }
+}
+
+/// How a JSONL file identifies the market and time of a record
+#[derive(Debug, Clone)]
+enum RecordLayout {
+    /// Serialized `MarketEvent`s, such as a raw capture or Kafka archive.
+    /// Resolution events carry no timestamp and are kept regardless of the window.
+    MarketEvents,
+    Fields { market_field: String, time_field: String },
+}
+
+/// Records read line by line from a JSONL file
+pub struct JsonlSource {
+    name: String,
+    path: PathBuf,
+    layout: RecordLayout,
+}
+
+impl JsonlSource {
+    /// Records keyed by their `market_id` and `timestamp` fields
+    pub fn new(name: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            path: path.into(),
+            layout: RecordLayout::Fields {
+                market_field: "market_id".to_string(),
+                time_field: "timestamp".to_string(),
+            },
+        }
+    }
+
+    /// A file of serialized market events
+    pub fn market_events(name: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            path: path.into(),
+            layout: RecordLayout::MarketEvents,
+        }
+    }
+
+    /// Read the record time from another field
+    pub fn with_time_field(mut self, field: impl Into<String>) -> Self {
+        if let RecordLayout::Fields { time_field, .. } = &mut self.layout {
+            *time_field = field.into();
+        }
+        self
+    }
+
+    /// Market and time of a record, None if it has no market
+    fn key(&self, record: &Value) -> Result<Option<(Uuid, Option<DateTime<Utc>>)>> {
+        match &self.layout {
+            RecordLayout::MarketEvents => {
+                let event: MarketEvent = serde_json::from_value(record.clone())?;
+                let timestamp = match event {
+                    MarketEvent::MarketResolved { .. }
+                    | MarketEvent::ResolutionReversed { .. }
+                    | MarketEvent::MarketInvalidated { .. }
+                    | MarketEvent::ConditionResolved { .. } => None,
+                    _ => Some(event.timestamp()),
+                };
+                Ok(Some((event.market_id().into_uuid(), timestamp)))
+            }
+            RecordLayout::Fields { market_field, time_field } => {
+                let Some(market_id) = record.get(market_field).and_then(Value::as_str) else {
+                    return Ok(None);
+                };
+                let market_id = Uuid::parse_str(market_id)
+                    .with_context(|| format!("Invalid {} {}", market_field, market_id))?;
+                let timestamp = record
+                    .get(time_field)
+                    .and_then(Value::as_str)
+                    .map(|t| t.parse::<DateTime<Utc>>())
+                    .transpose()
+                    .with_context(|| format!("Invalid {}", time_field))?;
+                Ok(Some((market_id, timestamp)))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ArtifactSource for JsonlSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn source(&self) -> String {
+        format!("jsonl:{}", self.path.display())
+    }
+
+    async fn export(&self, scope: &BundleScope, sink: &mut ArtifactSink) -> Result<()> {
+        let file = File::open(&self.path)
+            .await
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        let mut lines = BufReader::new(file).lines();
+        let mut line_number = 0;
+
+        while let Some(line) = lines.next_line().await? {
+            line_number += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let record: Value = serde_json::from_str(&line)
+                .with_context(|| format!("{}:{}: invalid JSON", self.path.display(), line_number))?;
+            let key = self
+                .key(&record)
+                .with_context(|| format!("{}:{}: invalid record", self.path.display(), line_number))?;
+
+            match key {
+                Some((market_id, timestamp))
+                    if market_id == scope.market_id && timestamp.is_none_or(|t| scope.contains(t)) =>
+                {
+                    sink.write(record, timestamp).await?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Rows of a monitoring table with `market_id` and a time column
+pub struct PgTableSource {
+    name: String,
+    db_pool: Arc<PgPool>,
+    table: &'static str,
+    time_column: &'static str,
+}
+
+impl PgTableSource {
+    /// Table and column names are interpolated into the query, so they must be trusted
+    pub fn new(
+        name: impl Into<String>,
+        db_pool: Arc<PgPool>,
+        table: &'static str,
+        time_column: &'static str,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            db_pool,
+            table,
+            time_column,
+        }
+    }
+
+    /// Generated signals
+    pub fn signals(db_pool: Arc<PgPool>) -> Self {
+        Self::new("signals", db_pool, "signals", "generated_at")
+    }
+
+    /// Executions and exits
+    pub fn trades(db_pool: Arc<PgPool>) -> Self {
+        Self::new("trades", db_pool, "trades", "timestamp")
+    }
+
+    /// Adjustments to booked trade P&L
+    pub fn pnl_corrections(db_pool: Arc<PgPool>) -> Self {
+        Self::new("pnl_corrections", db_pool, "pnl_corrections", "created_at")
+    }
+
+    /// Resolution alerts
+    pub fn alerts(db_pool: Arc<PgPool>) -> Self {
+        Self::new("alerts", db_pool, "resolution_alerts", "created_at")
+    }
+}
+
+#[async_trait]
+impl ArtifactSource for PgTableSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn source(&self) -> String {
+        format!("postgres:{}", self.table)
+    }
+
+    async fn export(&self, scope: &BundleScope, sink: &mut ArtifactSink) -> Result<()> {
+        let query = format!(
+            "SELECT to_jsonb(r)::text, r.{time} FROM {table} r \
+             WHERE r.market_id = $1 AND r.{time} BETWEEN $2 AND $3 \
+             ORDER BY r.{time}",
+            table = self.table,
+            time = self.time_column,
+        );
+
+        let mut rows = sqlx::query_as::<_, (String, DateTime<Utc>)>(&query)
+            .bind(scope.market_id)
+            .bind(scope.from)
+            .bind(scope.to)
+            .fetch(self.db_pool.as_ref());
+
+        while let Some((record, timestamp)) = rows
+            .try_next()
+            .await
+            .with_context(|| format!("Failed to read {}", self.table))?
+        {
+            sink.write(serde_json::from_str(&record)?, Some(timestamp)).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes a market's records from every source into one directory
+#[derive(Default)]
+pub struct BundleExporter {
+    sources: Vec<Box<dyn ArtifactSource>>,
+}
+
+impl BundleExporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a source
+    pub fn with_source(mut self, source: impl ArtifactSource + 'static) -> Self {
+        self.sources.push(Box::new(source));
+        self
+    }
+
+    /// Export every source into `dir`, one `<name>.jsonl` per source plus the manifest
+    pub async fn export(&self, scope: &BundleScope, dir: impl AsRef<Path>) -> Result<BundleManifest> {
+        let dir = dir.as_ref();
+        if scope.from > scope.to {
+            bail!("Bundle window starts after it ends: {} > {}", scope.from, scope.to);
+        }
+
+        let mut names = HashSet::new();
+        for source in &self.sources {
+            if !names.insert(source.name()) {
+                bail!("Duplicate bundle artifact {}", source.name());
+            }
+        }
+
+        if tokio::fs::try_exists(dir.join(MANIFEST_FILE)).await? {
+            bail!("{} already contains a bundle", dir.display());
+        }
+        tokio::fs::create_dir_all(dir)
+            .await
+            .with_context(|| format!("Failed to create bundle directory {}", dir.display()))?;
+
+        let mut artifacts = Vec::with_capacity(self.sources.len());
+        for source in &self.sources {
+            let file = format!("{}.jsonl", source.name());
+            let mut sink = ArtifactSink::create(&dir.join(&file)).await?;
+
+            source
+                .export(scope, &mut sink)
+                .await
+                .with_context(|| format!("Failed to export {}", source.name()))?;
+
+            let entry = sink.finish(source.name(), file, source.source()).await?;
+            debug!("Bundled {} {} rows", entry.rows, entry.name);
+            artifacts.push(entry);
+        }
+
+        let manifest = BundleManifest {
+            market_id: scope.market_id,
+            from: scope.from,
+            to: scope.to,
+            created_at: Utc::now(),
+            artifacts,
+        };
+        tokio::fs::write(dir.join(MANIFEST_FILE), serde_json::to_vec_pretty(&manifest)?)
+            .await
+            .context("Failed to write bundle manifest")?;
+
+        info!(
+            "Bundled market {} into {} ({} artifacts)",
+            scope.market_id,
+            dir.display(),
+            manifest.artifacts.len()
+        );
+        Ok(manifest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use common::PriceTick;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("bundle-test-{}", Uuid::new_v4()))
+    }
+
+    fn write_jsonl(path: &Path, records: &[Value]) {
+        let lines: Vec<String> = records.iter().map(|r| r.to_string()).collect();
+        std::fs::write(path, lines.join("\n")).unwrap();
+    }
+
+    fn read_jsonl(path: &Path) -> Vec<Value> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect()
+    }
+
+    fn tick(market_id: Uuid, timestamp: DateTime<Utc>) -> Value {
+        serde_json::to_value(MarketEvent::PriceTick(PriceTick {
+            market_id: market_id.into(),
+            outcome_id: "yes".into(),
+            price: 0.55,
+            volume_24h: 1000.0,
+            liquidity: 500.0,
+            timestamp,
+        }))
+        .unwrap()
+    }
+
+    /// Fixture stores holding records of `market` and `other`
+    fn write_fixtures(dir: &Path, market: Uuid, other: Uuid, now: DateTime<Utc>) -> BundleExporter {
+        std::fs::create_dir_all(dir).unwrap();
+        let signal = Uuid::new_v4();
+        let other_signal = Uuid::new_v4();
+
+        write_jsonl(
+            &dir.join("capture.jsonl"),
+            &[
+                tick(market, now - Duration::days(30)),
+                tick(market, now - Duration::hours(2)),
+                tick(other, now - Duration::hours(2)),
+                serde_json::to_value(MarketEvent::MarketResolved {
+                    market_id: market.into(),
+                    outcome_id: "yes".into(),
+                })
+                .unwrap(),
+            ],
+        );
+        write_jsonl(
+            &dir.join("agent_outputs.jsonl"),
+            &[
+                json!({ "market_id": market, "agent": "sentiment", "score": 0.4,
+                        "config": { "api_key": "sk-live-123" }, "timestamp": now - Duration::hours(3) }),
+                json!({ "market_id": other, "agent": "sentiment", "score": -0.2, "timestamp": now }),
+            ],
+        );
+        write_jsonl(
+            &dir.join("signals.jsonl"),
+            &[
+                json!({ "id": signal, "market_id": market, "generated_at": now - Duration::hours(1),
+                        "validation": [{ "validator": "edge", "passed": true }] }),
+                json!({ "id": other_signal, "market_id": other, "generated_at": now - Duration::hours(1) }),
+            ],
+        );
+        write_jsonl(
+            &dir.join("trades.jsonl"),
+            &[
+                json!({ "id": Uuid::new_v4(), "signal_id": signal, "market_id": market,
+                        "side": "buy", "timestamp": now - Duration::minutes(50) }),
+                json!({ "id": Uuid::new_v4(), "signal_id": signal, "market_id": market,
+                        "side": "sell", "timestamp": now - Duration::minutes(10) }),
+                json!({ "id": Uuid::new_v4(), "signal_id": other_signal, "market_id": other,
+                        "side": "buy", "timestamp": now - Duration::minutes(50) }),
+            ],
+        );
+
+        BundleExporter::new()
+            .with_source(JsonlSource::market_events("events", dir.join("capture.jsonl")))
+            .with_source(JsonlSource::new("agent_outputs", dir.join("agent_outputs.jsonl")))
+            .with_source(JsonlSource::new("signals", dir.join("signals.jsonl")).with_time_field("generated_at"))
+            .with_source(JsonlSource::new("trades", dir.join("trades.jsonl")))
+    }
+
+    #[tokio::test]
+    async fn test_bundle_contains_only_the_market() {
+        let dir = temp_dir();
+        let now = Utc::now();
+        let market = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        let exporter = write_fixtures(&dir.join("stores"), market, other, now);
+        let scope = BundleScope::new(market, now - Duration::days(1), now);
+
+        let bundle = dir.join("bundle");
+        let manifest = exporter.export(&scope, &bundle).await.unwrap();
+
+        // The manifest lists every artifact and matches what is on disk
+        let on_disk: BundleManifest =
+            serde_json::from_slice(&std::fs::read(bundle.join(MANIFEST_FILE)).unwrap()).unwrap();
+        let names: Vec<&str> = on_disk.artifacts.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["events", "agent_outputs", "signals", "trades"]);
+        assert_eq!(on_disk.market_id, market);
+
+        let mut records: HashMap<String, Vec<Value>> = HashMap::new();
+        for artifact in &manifest.artifacts {
+            let rows = read_jsonl(&bundle.join(&artifact.file));
+            assert_eq!(rows.len() as u64, artifact.rows, "{}", artifact.name);
+            records.insert(artifact.name.clone(), rows);
+        }
+
+        // The tick outside the window is dropped, the untimed resolution kept
+        assert_eq!(manifest.artifact("events").unwrap().rows, 2);
+        assert_eq!(manifest.artifact("trades").unwrap().rows, 2);
+
+        // Every record belongs to the market and trades point at bundled signals
+        for rows in records.values() {
+            for row in rows {
+                assert_eq!(row["market_id"], json!(market));
+            }
+        }
+        let signal_ids: HashSet<&Value> = records["signals"].iter().map(|s| &s["id"]).collect();
+        assert!(records["trades"].iter().all(|t| signal_ids.contains(&t["signal_id"])));
+
+        // Nothing of the other market leaks in, and secrets are redacted
+        for entry in std::fs::read_dir(&bundle).unwrap() {
+            let content = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+            assert!(!content.contains(&other.to_string()));
+            assert!(!content.contains("sk-live-123"));
+        }
+        assert_eq!(records["agent_outputs"][0]["config"]["api_key"], json!(REDACTED));
+        assert_eq!(manifest.artifact("agent_outputs").unwrap().redacted_fields, 1);
+
+        // An existing bundle is never overwritten
+        assert!(exporter.export(&scope, &bundle).await.is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_redact_nested_values() {
+        let mut record = json!({
+            "agent": "news",
+            "headers": [{ "Authorization": "Bearer abc" }, { "accept": "json" }],
+            "db": { "password": "hunter2", "host": "localhost" },
+            "access_token": null,
+        });
+
+        assert_eq!(redact(&mut record), 2);
+        assert_eq!(record["headers"][0]["Authorization"], json!(REDACTED));
+        assert_eq!(record["headers"][1]["accept"], json!("json"));
+        assert_eq!(record["db"]["password"], json!(REDACTED));
+        assert_eq!(record["db"]["host"], json!("localhost"));
+        assert_eq!(record["agent"], json!("news"));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_postgres_alerts_exported() {
+        use sqlx::postgres::PgPoolOptions;
+
+        let url = std::env::var("TEST_DATABASE_URL").unwrap();
+        let pool = Arc::new(PgPoolOptions::new().max_connections(1).connect(&url).await.unwrap());
+        sqlx::query(
+            "CREATE TEMP TABLE resolution_alerts (
+                id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+                market_id UUID NOT NULL,
+                alert_type TEXT NOT NULL,
+                description TEXT NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )",
+        )
+        .execute(pool.as_ref())
+        .await
+        .unwrap();
+
+        let market = Uuid::new_v4();
+        for market_id in [market, Uuid::new_v4()] {
+            sqlx::query("INSERT INTO resolution_alerts (market_id, alert_type, description) VALUES ($1, 'reversal', 'x')")
+                .bind(market_id)
+                .execute(pool.as_ref())
+                .await
+                .unwrap();
+        }
+
+        let dir = temp_dir();
+        let scope = BundleScope::new(market, Utc::now() - Duration::hours(1), Utc::now() + Duration::hours(1));
+        let manifest = BundleExporter::new()
+            .with_source(PgTableSource::alerts(pool))
+            .export(&scope, &dir)
+            .await
+            .unwrap();
+
+        assert_eq!(manifest.artifact("alerts").unwrap().rows, 1);
+        assert_eq!(read_jsonl(&dir.join("alerts.jsonl"))[0]["market_id"], json!(market));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,6 +1,6 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use common::{CalibrationMetrics, ConfidenceBucket, Resolution};
+use common::{CalibrationMetrics, ConfidenceBucket, MarketEvent, MarketId, Resolution};
 use sqlx::postgres::PgPool;
 use std::sync::Arc;
 use tracing::{debug, info, warn};
@@ -18,7 +18,7 @@ impl CalibrationEngine {
 
     /// Initialize calibration tables
     pub async fn initialize(&self) -> Result<()> {
-        sqlx::query(
+        sqlx::raw_sql(
             r#"
             CREATE TABLE IF NOT EXISTS predictions (
                 id UUID PRIMARY KEY,
@@ -68,7 +68,7 @@ impl CalibrationEngine {
         prediction_id: Uuid,
         signal_id: Uuid,
         strategy_id: &str,
-        market_id: Uuid,
+        market_id: MarketId,
         outcome_id: &str,
         predicted_probability: f64,
     ) -> Result<()> {
@@ -95,7 +95,7 @@ impl CalibrationEngine {
     /// Update prediction with actual outcome after resolution
     pub async fn update_prediction_outcome(
         &self,
-        market_id: Uuid,
+        market_id: MarketId,
         winning_outcome: &str,
     ) -> Result<usize> {
         let result = sqlx::query(
@@ -115,6 +115,45 @@ impl CalibrationEngine {
         Ok(result.rows_affected() as usize)
     }
 
+    /// Clear prediction outcomes of a market whose resolution no longer stands
+    ///
+    /// Cleared predictions drop out of calibration until the market is
+    /// resolved again via `update_prediction_outcome`.
+    pub async fn clear_prediction_outcomes(&self, market_id: MarketId) -> Result<usize> {
+        let result = sqlx::query(
+            r#"
+            UPDATE predictions
+            SET actual_outcome = NULL
+            WHERE market_id = $1 AND actual_outcome IS NOT NULL
+            "#,
+        )
+        .bind(market_id)
+        .execute(self.db_pool.as_ref())
+        .await
+        .context("Failed to clear prediction outcomes")?;
+
+        warn!("Cleared outcomes of {} predictions for market {}", result.rows_affected(), market_id);
+        Ok(result.rows_affected() as usize)
+    }
+
+    /// Apply a resolution lifecycle event to recorded predictions
+    ///
+    /// Invalidated markets have no outcome to score against, so their
+    /// predictions stay unresolved.
+    pub async fn process_event(&self, event: &MarketEvent) -> Result<()> {
+        match event {
+            MarketEvent::MarketResolved { market_id, outcome_id } => {
+                self.update_prediction_outcome(*market_id, outcome_id.as_str()).await?;
+            }
+            MarketEvent::ResolutionReversed { market_id, .. }
+            | MarketEvent::MarketInvalidated { market_id, .. } => {
+                self.clear_prediction_outcomes(*market_id).await?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
     /// Calculate calibration metrics for a strategy
     pub async fn calculate_calibration(
         &self,
@@ -122,9 +161,62 @@ impl CalibrationEngine {
         period_start: DateTime<Utc>,
         period_end: DateTime<Utc>,
     ) -> Result<CalibrationMetrics> {
-        let predictions = sqlx::query_as::<_, (f64, i32)>(
+        let predictions = self.fetch_predictions(strategy_id, period_start, period_end).await?;
+        let metrics = self.build_metrics(strategy_id, period_start, period_end, &predictions)?;
+
+        if metrics.total_predictions > 0 {
+            self.store_calibration_metrics(&metrics).await?;
+        }
+
+        Ok(metrics)
+    }
+
+    /// Calculate calibration metrics together with the Brier decomposition
+    /// and reliability curve of the same predictions
+    pub async fn calculate_calibration_decomposed(
+        &self,
+        strategy_id: &str,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+    ) -> Result<DecomposedCalibration> {
+        let predictions = self.fetch_predictions(strategy_id, period_start, period_end).await?;
+        let metrics = self.build_metrics(strategy_id, period_start, period_end, &predictions)?;
+
+        if metrics.total_predictions > 0 {
+            self.store_calibration_metrics(&metrics).await?;
+        }
+
+        Ok(DecomposedCalibration {
+            decomposition: BrierScoreCalculator::decompose(&predictions)?,
+            reliability_curve: reliability_points(&metrics.confidence_buckets),
+            metrics,
+        })
+    }
+
+    /// Reliability diagram of a strategy: (mean predicted probability,
+    /// observed frequency, count) for each non-empty tenth of [0, 1].
+    ///
+    /// A calibrated strategy's points lie on the diagonal.
+    pub async fn reliability_curve(
+        &self,
+        strategy_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<(f64, f64, i64)>> {
+        let predictions = self.fetch_predictions(strategy_id, from, to).await?;
+        Ok(reliability_points(&self.create_confidence_buckets(&predictions)?))
+    }
+
+    /// Resolved predictions of a strategy as (predicted probability, outcome)
+    async fn fetch_predictions(
+        &self,
+        strategy_id: &str,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+    ) -> Result<Vec<(f64, i32)>> {
+        sqlx::query_as::<_, (f64, i32)>(
             r#"
-            SELECT predicted_probability, actual_outcome
+            SELECT predicted_probability::DOUBLE PRECISION, actual_outcome::INT
             FROM predictions
             WHERE strategy_id = $1
             AND timestamp >= $2
@@ -137,8 +229,16 @@ impl CalibrationEngine {
         .bind(period_end)
         .fetch_all(self.db_pool.as_ref())
         .await
-        .context("Failed to fetch predictions")?;
+        .context("Failed to fetch predictions")
+    }
 
+    fn build_metrics(
+        &self,
+        strategy_id: &str,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+        predictions: &[(f64, i32)],
+    ) -> Result<CalibrationMetrics> {
         if predictions.is_empty() {
             return Ok(CalibrationMetrics {
                 strategy_id: strategy_id.to_string(),
@@ -152,12 +252,12 @@ impl CalibrationEngine {
             });
         }
 
-        let brier_score = BrierScoreCalculator::calculate(&predictions)?;
-        let log_loss = self.calculate_log_loss(&predictions)?;
-        let calibration_error = self.calculate_calibration_error(&predictions)?;
-        let confidence_buckets = self.create_confidence_buckets(&predictions)?;
+        let brier_score = BrierScoreCalculator::calculate(predictions)?;
+        let log_loss = self.calculate_log_loss(predictions)?;
+        let calibration_error = self.calculate_calibration_error(predictions)?;
+        let confidence_buckets = self.create_confidence_buckets(predictions)?;
 
-        let metrics = CalibrationMetrics {
+        Ok(CalibrationMetrics {
             strategy_id: strategy_id.to_string(),
             period_start,
             period_end,
@@ -166,11 +266,7 @@ impl CalibrationEngine {
             calibration_error,
             total_predictions: predictions.len() as i64,
             confidence_buckets,
-        };
-
-        self.store_calibration_metrics(&metrics).await?;
-
-        Ok(metrics)
+        })
     }
 
     /// Calculate log loss
@@ -215,7 +311,7 @@ impl CalibrationEngine {
 
             let bucket_preds: Vec<&(f64, i32)> = predictions
                 .iter()
-                .filter(|(p, _)| *p >= min_conf && *p < max_conf)
+                .filter(|(p, _)| bucket_index(*p) == i)
                 .collect();
 
             if bucket_preds.is_empty() {
@@ -251,7 +347,7 @@ impl CalibrationEngine {
                 brier_score, log_loss, calibration_error,
                 total_predictions, confidence_buckets
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8::JSONB)
             ON CONFLICT (strategy_id, period_start, period_end)
             DO UPDATE SET
                 brier_score = EXCLUDED.brier_score,
@@ -283,6 +379,30 @@ impl CalibrationEngine {
         recent_days: i64,
         threshold: f64,
     ) -> Result<bool> {
+        let Some((historical, recent)) = self.brier_score_shift(strategy_id, recent_days).await? else {
+            return Ok(false);
+        };
+
+        let drift = recent - historical;
+        let is_drifted = drift > threshold;
+
+        if is_drifted {
+            warn!(
+                "Calibration drift detected for {}: {:.4} -> {:.4}",
+                strategy_id,
+                historical,
+                recent
+            );
+        }
+
+        Ok(is_drifted)
+    }
+
+    /// Historical and recent Brier scores of a strategy.
+    ///
+    /// The recent window is the last `recent_days`, the historical one the
+    /// two windows before it. None without enough historical predictions.
+    pub async fn brier_score_shift(&self, strategy_id: &str, recent_days: i64) -> Result<Option<(f64, f64)>> {
         let recent = Utc::now() - chrono::Duration::days(recent_days);
         let historical = Utc::now() - chrono::Duration::days(recent_days * 3);
 
@@ -296,23 +416,42 @@ impl CalibrationEngine {
 
         if historical_metrics.total_predictions < 10 {
             warn!("Not enough historical data for calibration drift detection");
-            return Ok(false);
+            return Ok(None);
         }
 
-        let drift = recent_metrics.brier_score - historical_metrics.brier_score;
-        let is_drifted = drift > threshold;
+        Ok(Some((historical_metrics.brier_score, recent_metrics.brier_score)))
+    }
+}
 
-        if is_drifted {
-            warn!(
-                "Calibration drift detected for {}: {:.4} -> {:.4}",
-                strategy_id,
-                historical_metrics.brier_score,
-                recent_metrics.brier_score
-            );
-        }
+/// Tenth of [0, 1] a probability falls in; 1.0 belongs to the last one
+fn bucket_index(prob: f64) -> usize {
+    ((prob * 10.0) as usize).min(9)
+}
 
-        Ok(is_drifted)
+/// Reliability diagram points of a set of confidence buckets
+fn reliability_points(buckets: &[ConfidenceBucket]) -> Vec<(f64, f64, i64)> {
+    buckets
+        .iter()
+        .map(|b| (b.avg_predicted_prob, b.actual_outcome_rate, b.count))
+        .collect()
+}
+
+/// Render a reliability curve as CSV for plotting tools
+pub fn to_csv(curve: &[(f64, f64, i64)]) -> String {
+    let mut csv = String::from("mean_predicted,observed_frequency,count\n");
+    for (predicted, observed, count) in curve {
+        csv.push_str(&format!("{:.6},{:.6},{}\n", predicted, observed, count));
     }
+    csv
+}
+
+/// Calibration metrics with the Brier decomposition and reliability curve
+#[derive(Debug, Clone)]
+pub struct DecomposedCalibration {
+    pub metrics: CalibrationMetrics,
+    pub decomposition: BrierDecomposition,
+    /// (mean predicted probability, observed frequency, count) per bucket
+    pub reliability_curve: Vec<(f64, f64, i64)>,
 }
 
 /// Brier Score Calculator
@@ -336,7 +475,11 @@ impl BrierScoreCalculator {
         Ok(sum / predictions.len() as f64)
     }
 
-    /// Decompose Brier score into reliability, resolution, and uncertainty
+    /// Decompose Brier score into reliability, resolution, and uncertainty.
+    ///
+    /// Predictions are binned by tenths as for the reliability curve, so
+    /// `reliability - resolution + uncertainty` matches the Brier score up
+    /// to the spread of forecasts within each bin.
     pub fn decompose(predictions: &[(f64, i32)]) -> Result<BrierDecomposition> {
         if predictions.is_empty() {
             return Ok(BrierDecomposition {
@@ -353,31 +496,26 @@ impl BrierScoreCalculator {
         let base_rate: f64 = predictions.iter().map(|(_, a)| *a as f64).sum::<f64>() / predictions.len() as f64;
         let uncertainty = base_rate * (1.0 - base_rate);
 
-        // Group by predicted probability
-        let mut groups: std::collections::HashMap<i32, Vec<f64>> = std::collections::HashMap::new();
+        // Group by bucket: (count, sum of predictions, sum of outcomes)
+        let mut groups = [(0.0, 0.0, 0.0); 10];
         for (prob, actual) in predictions {
-            let rounded_prob = (prob * 10.0) as i32; // Round to nearest 0.1
-            groups
-                .entry(rounded_prob)
-                .or_insert_with(Vec::new)
-                .push(*actual as f64);
+            let group = &mut groups[bucket_index(*prob)];
+            group.0 += 1.0;
+            group.1 += prob;
+            group.2 += *actual as f64;
         }
 
-        // Calculate reliability
         let mut reliability = 0.0;
-        let mut total_count = 0.0;
-
-        for (rounded_prob, outcomes) in &groups {
-            let count = outcomes.len() as f64;
-            let observed_freq = outcomes.iter().sum::<f64>() / count;
-            let predicted_prob = *rounded_prob as f64 / 10.0;
+        let mut resolution = 0.0;
+        for (count, prob_sum, outcome_sum) in groups.iter().filter(|g| g.0 > 0.0) {
+            let predicted_prob = prob_sum / count;
+            let observed_freq = outcome_sum / count;
             reliability += count * (observed_freq - predicted_prob).powi(2);
-            total_count += count;
+            resolution += count * (observed_freq - base_rate).powi(2);
         }
+        let total_count = predictions.len() as f64;
         reliability /= total_count;
-
-        // Resolution
-        let resolution = uncertainty - reliability;
+        resolution /= total_count;
 
         Ok(BrierDecomposition {
             brier_score,
@@ -395,3 +533,67 @@ pub struct BrierDecomposition {
     pub resolution: f64,
     pub uncertainty: f64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::postgres::PgPoolOptions;
+
+    fn engine() -> CalibrationEngine {
+        let pool = PgPoolOptions::new()
+            .connect_lazy("postgresql://localhost/unused")
+            .unwrap();
+        CalibrationEngine::new(Arc::new(pool))
+    }
+
+    /// 20 predictions at the middle of each tenth, of which exactly that
+    /// share resolve YES
+    fn perfectly_calibrated() -> Vec<(f64, i32)> {
+        (0..10)
+            .flat_map(|bucket| {
+                let prob = (bucket as f64 + 0.5) / 10.0;
+                let hits = (prob * 20.0).round() as usize;
+                (0..20).map(move |i| (prob, (i < hits) as i32))
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_calibrated_predictions_lie_on_diagonal() {
+        let engine = engine();
+        let predictions = perfectly_calibrated();
+
+        let curve = reliability_points(&engine.create_confidence_buckets(&predictions).unwrap());
+        assert_eq!(curve.len(), 10);
+        for (predicted, observed, count) in &curve {
+            assert!((predicted - observed).abs() < 1e-9, "({}, {}) is off the diagonal", predicted, observed);
+            assert_eq!(*count, 20);
+        }
+
+        let decomposition = BrierScoreCalculator::decompose(&predictions).unwrap();
+        assert!(decomposition.reliability.abs() < 1e-12);
+        assert!((decomposition.uncertainty - 0.25).abs() < 1e-12);
+        // With one forecast per bin the decomposition is exact
+        assert!(
+            (decomposition.reliability - decomposition.resolution + decomposition.uncertainty
+                - decomposition.brier_score)
+                .abs()
+                < 1e-12
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reliability_curve_csv() {
+        let engine = engine();
+        // A certain prediction falls in the top bucket
+        let predictions = vec![(1.0, 1), (0.9, 0), (0.25, 0), (0.25, 1)];
+        let curve = reliability_points(&engine.create_confidence_buckets(&predictions).unwrap());
+
+        assert_eq!(
+            to_csv(&curve),
+            "mean_predicted,observed_frequency,count\n\
+             0.250000,0.500000,2\n\
+             0.950000,0.500000,2\n"
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Duration, Utc};
-use common::{DriftDetection, DriftSeverity, DriftType, PerformanceMetrics};
+use common::{DriftDetection, DriftSeverity, DriftType, OrderBook, PerformanceMetrics};
+use crate::calibration::CalibrationEngine;
 use sqlx::postgres::PgPool;
 use std::sync::Arc;
 use tracing::{warn, info};
@@ -19,6 +20,14 @@ pub struct DriftDetectionConfig {
     pub brier_score_increase_threshold: f64,
     /// Threshold for volume decline (%)
     pub volume_decline_threshold: f64,
+    /// Threshold for average bid-ask spread widening in a traded market (%)
+    pub spread_widening_threshold: f64,
+    /// Threshold for average order book liquidity decline in a traded market (%)
+    pub liquidity_decline_threshold: f64,
+    /// Recent window for calibration drift (days)
+    pub calibration_window_days: i64,
+    /// Threshold for calibration Brier score increase
+    pub calibration_drift_threshold: f64,
 }
 
 impl Default for DriftDetectionConfig {
@@ -29,6 +38,10 @@ impl Default for DriftDetectionConfig {
             hit_rate_decline_threshold: 10.0,
             brier_score_increase_threshold: 0.05,
             volume_decline_threshold: 30.0,
+            spread_widening_threshold: 50.0,
+            liquidity_decline_threshold: 40.0,
+            calibration_window_days: 7,
+            calibration_drift_threshold: 0.05,
         }
     }
 }
@@ -37,11 +50,26 @@ impl Default for DriftDetectionConfig {
 pub struct DriftDetector {
     db_pool: Arc<PgPool>,
     config: DriftDetectionConfig,
+    calibration: CalibrationEngine,
+}
+
+/// Average order book structure of a market in the recent and historical windows
+#[derive(Debug, Clone, PartialEq)]
+struct MarketStructureStats {
+    market_id: Uuid,
+    historical_spread: f64,
+    recent_spread: f64,
+    historical_liquidity: f64,
+    recent_liquidity: f64,
 }
 
 impl DriftDetector {
     pub fn new(db_pool: Arc<PgPool>, config: DriftDetectionConfig) -> Self {
-        Self { db_pool, config }
+        Self {
+            calibration: CalibrationEngine::new(db_pool.clone()),
+            db_pool,
+            config,
+        }
     }
 
     pub fn new_with_defaults(db_pool: Arc<PgPool>) -> Self {
@@ -50,7 +78,7 @@ impl DriftDetector {
 
     /// Initialize drift detection tables
     pub async fn initialize(&self) -> Result<()> {
-        sqlx::query(
+        sqlx::raw_sql(
             r#"
             CREATE TABLE IF NOT EXISTS drift_alerts (
                 id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
@@ -69,6 +97,15 @@ impl DriftDetector {
             CREATE INDEX IF NOT EXISTS idx_drift_time ON drift_alerts(detected_at);
             CREATE INDEX IF NOT EXISTS idx_drift_severity ON drift_alerts(severity);
             CREATE INDEX IF NOT EXISTS idx_drift_acknowledged ON drift_alerts(acknowledged);
+
+            CREATE TABLE IF NOT EXISTS order_book_snapshots (
+                market_id UUID NOT NULL,
+                timestamp TIMESTAMPTZ NOT NULL,
+                spread DOUBLE PRECISION NOT NULL,
+                liquidity DOUBLE PRECISION NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_order_book_snapshots_market ON order_book_snapshots(market_id, timestamp);
             "#,
         )
         .execute(self.db_pool.as_ref())
@@ -125,6 +162,22 @@ impl DriftDetector {
             drifts.push(drift);
         }
 
+        // Check market structure drift (spread and liquidity)
+        if let Some(drift) = self
+            .check_market_structure_drift(strategy_id)
+            .await?
+        {
+            drifts.push(drift);
+        }
+
+        // Check calibration drift
+        if let Some(drift) = self
+            .check_calibration_drift(strategy_id)
+            .await?
+        {
+            drifts.push(drift);
+        }
+
         // Store alerts
         for drift in &drifts {
             self.store_drift_alert(drift).await?;
@@ -224,7 +277,7 @@ impl DriftDetector {
 
         let recent_brier = sqlx::query_scalar::<_, f64>(
             r#"
-            SELECT AVG((predicted_probability - actual_outcome)^2)
+            SELECT COALESCE(AVG((predicted_probability - actual_outcome)^2), 0.0)::DOUBLE PRECISION
             FROM predictions
             WHERE strategy_id = $1
             AND timestamp >= $2 AND timestamp <= $3
@@ -240,7 +293,7 @@ impl DriftDetector {
 
         let historical_brier = sqlx::query_scalar::<_, f64>(
             r#"
-            SELECT AVG((predicted_probability - actual_outcome)^2)
+            SELECT COALESCE(AVG((predicted_probability - actual_outcome)^2), 0.0)::DOUBLE PRECISION
             FROM predictions
             WHERE strategy_id = $1
             AND timestamp >= $2 AND timestamp < $3
@@ -350,6 +403,201 @@ impl DriftDetector {
         Ok(None)
     }
 
+    /// Record the spread and liquidity of an order book snapshot
+    pub async fn record_order_book(&self, book: &OrderBook) -> Result<()> {
+        let Some((spread, liquidity)) = order_book_structure(book) else {
+            return Ok(()); // One-sided book
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO order_book_snapshots (market_id, timestamp, spread, liquidity)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(book.market_id)
+        .bind(book.timestamp)
+        .bind(spread)
+        .bind(liquidity)
+        .execute(self.db_pool.as_ref())
+        .await
+        .context("Failed to store order book snapshot")?;
+
+        Ok(())
+    }
+
+    /// Check for market structure drift (spreads widening or liquidity drying up
+    /// in the markets a strategy trades)
+    async fn check_market_structure_drift(&self, strategy_id: &str) -> Result<Option<DriftDetection>> {
+        let now = Utc::now();
+        let recent_start = now - Duration::hours(self.config.window_hours);
+        let historical_start = now - Duration::hours(self.config.window_hours * 3);
+
+        let rows = sqlx::query_as::<_, (Uuid, Option<f64>, Option<f64>, Option<f64>, Option<f64>)>(
+            r#"
+            SELECT s.market_id,
+                   AVG(s.spread) FILTER (WHERE s.timestamp < $2),
+                   AVG(s.spread) FILTER (WHERE s.timestamp >= $2),
+                   AVG(s.liquidity) FILTER (WHERE s.timestamp < $2),
+                   AVG(s.liquidity) FILTER (WHERE s.timestamp >= $2)
+            FROM order_book_snapshots s
+            WHERE s.timestamp >= $3 AND s.timestamp <= $4
+            AND s.market_id IN (
+                SELECT DISTINCT t.market_id
+                FROM trades t
+                JOIN attributed_trades at ON t.id = at.trade_id
+                WHERE at.strategy_id = $1
+            )
+            GROUP BY s.market_id
+            "#,
+        )
+        .bind(strategy_id)
+        .bind(recent_start)
+        .bind(historical_start)
+        .bind(now)
+        .fetch_all(self.db_pool.as_ref())
+        .await
+        .context("Failed to fetch order book snapshots")?;
+
+        let stats: Vec<MarketStructureStats> = rows
+            .into_iter()
+            .filter_map(|(market_id, historical_spread, recent_spread, historical_liquidity, recent_liquidity)| {
+                Some(MarketStructureStats {
+                    market_id,
+                    historical_spread: historical_spread?,
+                    recent_spread: recent_spread?,
+                    historical_liquidity: historical_liquidity?,
+                    recent_liquidity: recent_liquidity?,
+                })
+            })
+            .collect();
+
+        Ok(self.evaluate_market_structure(strategy_id, &stats, now))
+    }
+
+    /// Report the market whose spread or liquidity moved furthest past its threshold
+    fn evaluate_market_structure(
+        &self,
+        strategy_id: &str,
+        stats: &[MarketStructureStats],
+        now: DateTime<Utc>,
+    ) -> Option<DriftDetection> {
+        let mut worst: Option<(f64, DriftDetection)> = None;
+
+        for market in stats {
+            let mut candidates = Vec::new();
+
+            if market.historical_spread > 0.0 {
+                let widening_pct =
+                    ((market.recent_spread - market.historical_spread) / market.historical_spread) * 100.0;
+                candidates.push((
+                    widening_pct,
+                    self.config.spread_widening_threshold,
+                    format!(
+                        "Spread in market {} widened {:.1}% (historical: {:.4} -> recent: {:.4})",
+                        market.market_id, widening_pct, market.historical_spread, market.recent_spread
+                    ),
+                ));
+            }
+
+            if market.historical_liquidity > 0.0 {
+                let decline_pct = ((market.historical_liquidity - market.recent_liquidity)
+                    / market.historical_liquidity)
+                    * 100.0;
+                candidates.push((
+                    decline_pct,
+                    self.config.liquidity_decline_threshold,
+                    format!(
+                        "Liquidity in market {} declined {:.1}% (historical: ${:.2} -> recent: ${:.2})",
+                        market.market_id, decline_pct, market.historical_liquidity, market.recent_liquidity
+                    ),
+                ));
+            }
+
+            for (metric_value, threshold, description) in candidates {
+                if metric_value <= threshold {
+                    continue;
+                }
+
+                // How far past its threshold the metric is, comparable across metrics
+                let excess = metric_value / threshold;
+                if worst.as_ref().is_some_and(|(w, _)| *w >= excess) {
+                    continue;
+                }
+
+                let severity = if excess > 2.0 {
+                    DriftSeverity::High
+                } else {
+                    DriftSeverity::Medium
+                };
+
+                worst = Some((
+                    excess,
+                    DriftDetection {
+                        strategy_id: strategy_id.to_string(),
+                        detected_at: now,
+                        drift_type: DriftType::MarketStructureDrift,
+                        severity,
+                        metric_value,
+                        threshold,
+                        description,
+                    },
+                ));
+            }
+        }
+
+        worst.map(|(_, drift)| drift)
+    }
+
+    /// Check for calibration drift, as `CalibrationEngine::detect_calibration_drift` does
+    async fn check_calibration_drift(&self, strategy_id: &str) -> Result<Option<DriftDetection>> {
+        let shift = self
+            .calibration
+            .brier_score_shift(strategy_id, self.config.calibration_window_days)
+            .await?;
+
+        Ok(shift.and_then(|(historical, recent)| {
+            self.evaluate_calibration(strategy_id, historical, recent, Utc::now())
+        }))
+    }
+
+    /// Build a calibration drift from historical and recent Brier scores
+    fn evaluate_calibration(
+        &self,
+        strategy_id: &str,
+        historical_brier: f64,
+        recent_brier: f64,
+        now: DateTime<Utc>,
+    ) -> Option<DriftDetection> {
+        let increase = recent_brier - historical_brier;
+        let threshold = self.config.calibration_drift_threshold;
+
+        if increase <= threshold {
+            return None;
+        }
+
+        let severity = if increase > 0.2 {
+            DriftSeverity::Critical
+        } else if increase > 0.1 {
+            DriftSeverity::High
+        } else {
+            DriftSeverity::Medium
+        };
+
+        Some(DriftDetection {
+            strategy_id: strategy_id.to_string(),
+            detected_at: now,
+            drift_type: DriftType::CalibrationDrift,
+            severity,
+            metric_value: increase,
+            threshold,
+            description: format!(
+                "Calibration degraded over {} days: Brier score {:.4} -> {:.4}",
+                self.config.calibration_window_days, historical_brier, recent_brier
+            ),
+        })
+    }
+
     /// Store drift alert
     async fn store_drift_alert(&self, drift: &DriftDetection) -> Result<()> {
         sqlx::query(
@@ -425,3 +673,197 @@ impl DriftDetector {
         Ok(())
     }
 }
+
+/// Best-price spread and total notional depth of a book, None if either side is empty
+fn order_book_structure(book: &OrderBook) -> Option<(f64, f64)> {
+    let best_bid = book.bids.iter().map(|o| o.price).reduce(f64::max)?;
+    let best_ask = book.asks.iter().map(|o| o.price).reduce(f64::min)?;
+    let liquidity: f64 = book.bids.iter().chain(&book.asks).map(|o| o.price * o.size).sum();
+
+    Some(((best_ask - best_bid).max(0.0), liquidity))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{MarketId, Order};
+    use sqlx::postgres::PgPoolOptions;
+
+    fn detector() -> DriftDetector {
+        let pool = PgPoolOptions::new()
+            .connect_lazy("postgresql://localhost/unused")
+            .unwrap();
+        DriftDetector::new_with_defaults(Arc::new(pool))
+    }
+
+    fn stats(historical: (f64, f64), recent: (f64, f64)) -> MarketStructureStats {
+        MarketStructureStats {
+            market_id: Uuid::new_v4(),
+            historical_spread: historical.0,
+            recent_spread: recent.0,
+            historical_liquidity: historical.1,
+            recent_liquidity: recent.1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_market_structure_drift() {
+        let detector = detector();
+        let now = Utc::now();
+
+        // Small moves stay under the 50% spread / 40% liquidity thresholds
+        let stable = stats((0.02, 10_000.0), (0.025, 8_000.0));
+        assert!(detector.evaluate_market_structure("s", std::slice::from_ref(&stable), now).is_none());
+
+        // Spread tripled in one market, liquidity halved in another
+        let wide = stats((0.02, 10_000.0), (0.06, 10_000.0));
+        let thin = stats((0.02, 10_000.0), (0.02, 5_000.0));
+        let drift = detector
+            .evaluate_market_structure("s", &[stable, thin.clone(), wide.clone()], now)
+            .unwrap();
+
+        assert_eq!(drift.drift_type, DriftType::MarketStructureDrift);
+        assert!((drift.metric_value - 200.0).abs() < 1e-9);
+        assert_eq!(drift.threshold, 50.0);
+        assert_eq!(drift.severity, DriftSeverity::High);
+        assert!(drift.description.contains(&wide.market_id.to_string()));
+
+        let drift = detector.evaluate_market_structure("s", std::slice::from_ref(&thin), now).unwrap();
+        assert!((drift.metric_value - 50.0).abs() < 1e-9);
+        assert_eq!(drift.threshold, 40.0);
+        assert_eq!(drift.severity, DriftSeverity::Medium);
+        assert!(drift.description.contains(&thin.market_id.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_calibration_drift() {
+        let detector = detector();
+        let now = Utc::now();
+
+        assert!(detector.evaluate_calibration("s", 0.18, 0.20, now).is_none());
+
+        let drift = detector.evaluate_calibration("s", 0.18, 0.30, now).unwrap();
+        assert_eq!(drift.drift_type, DriftType::CalibrationDrift);
+        assert!((drift.metric_value - 0.12).abs() < 1e-9);
+        assert_eq!(drift.threshold, 0.05);
+        assert_eq!(drift.severity, DriftSeverity::High);
+    }
+
+    #[test]
+    fn test_order_book_structure() {
+        let order = |price: f64, size: f64| Order { outcome_id: "yes".into(), price, size };
+        let mut book = OrderBook {
+            market_id: MarketId::random(),
+            timestamp: Utc::now(),
+            bids: vec![order(0.48, 100.0), order(0.50, 200.0)],
+            asks: vec![order(0.53, 100.0), order(0.55, 50.0)],
+            sequence: 0,
+        };
+
+        let (spread, liquidity) = order_book_structure(&book).unwrap();
+        assert!((spread - 0.03).abs() < 1e-9);
+        assert!((liquidity - (48.0 + 100.0 + 53.0 + 27.5)).abs() < 1e-9);
+
+        book.asks.clear();
+        assert!(order_book_structure(&book).is_none());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_seeded_structure_and_calibration_drift() {
+        let url = std::env::var("TEST_DATABASE_URL").unwrap();
+        // Single connection so the scratch schema stays on the search path
+        let pool = Arc::new(PgPoolOptions::new().max_connections(1).connect(&url).await.unwrap());
+        let schema = format!("drift_{}", Uuid::new_v4().simple());
+        sqlx::raw_sql(&format!(
+            r#"
+            CREATE SCHEMA {schema};
+            SET search_path TO {schema};
+            CREATE TABLE trades (
+                id UUID PRIMARY KEY,
+                market_id UUID NOT NULL,
+                timestamp TIMESTAMPTZ NOT NULL,
+                pnl DOUBLE PRECISION
+            );
+            CREATE TABLE attributed_trades (
+                trade_id UUID PRIMARY KEY,
+                strategy_id TEXT NOT NULL
+            );
+            "#
+        ))
+        .execute(pool.as_ref())
+        .await
+        .unwrap();
+        CalibrationEngine::new(pool.clone()).initialize().await.unwrap();
+
+        let detector = DriftDetector::new_with_defaults(pool.clone());
+        detector.initialize().await.unwrap();
+
+        let market_id = Uuid::new_v4();
+        let now = Utc::now();
+        let trade_id = Uuid::new_v4();
+        sqlx::query("INSERT INTO trades (id, market_id, timestamp, pnl) VALUES ($1, $2, $3, 1.0)")
+            .bind(trade_id)
+            .bind(market_id)
+            .bind(now - Duration::hours(1))
+            .execute(pool.as_ref())
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO attributed_trades (trade_id, strategy_id) VALUES ($1, 'drifting')")
+            .bind(trade_id)
+            .execute(pool.as_ref())
+            .await
+            .unwrap();
+
+        // Spread goes from 2 to 8 cents between the historical and recent windows
+        for (hours_ago, ask) in [(40, 0.52), (30, 0.52), (10, 0.58), (2, 0.58)] {
+            detector
+                .record_order_book(&OrderBook {
+                    market_id: market_id.into(),
+                    timestamp: now - Duration::hours(hours_ago),
+                    bids: vec![Order { outcome_id: "yes".into(), price: 0.50, size: 100.0 }],
+                    asks: vec![Order { outcome_id: "yes".into(), price: ask, size: 100.0 }],
+                    sequence: 0,
+                })
+                .await
+                .unwrap();
+        }
+
+        // Well calibrated historically, confidently wrong recently
+        for (days_ago, probability, outcome) in (0..12)
+            .map(|i| (10 + i, 0.9, 1))
+            .chain((0..12).map(|i| (i % 6, 0.9, 0)))
+        {
+            sqlx::query(
+                "INSERT INTO predictions
+                     (id, signal_id, strategy_id, market_id, outcome_id, predicted_probability, actual_outcome, timestamp)
+                 VALUES ($1, $2, 'drifting', $3, 'yes', $4, $5, $6)",
+            )
+            .bind(Uuid::new_v4())
+            .bind(Uuid::new_v4())
+            .bind(market_id)
+            .bind(probability)
+            .bind(outcome)
+            .bind(now - Duration::days(days_ago) - Duration::minutes(1))
+            .execute(pool.as_ref())
+            .await
+            .unwrap();
+        }
+
+        let drifts = detector.check_strategy_drift("drifting").await.unwrap();
+        let structure = drifts
+            .iter()
+            .find(|d| d.drift_type == DriftType::MarketStructureDrift)
+            .unwrap();
+        assert!((structure.metric_value - 300.0).abs() < 1e-6);
+
+        let calibration = drifts
+            .iter()
+            .find(|d| d.drift_type == DriftType::CalibrationDrift)
+            .unwrap();
+        assert!((calibration.metric_value - 0.8).abs() < 1e-6);
+        assert_eq!(calibration.severity, DriftSeverity::Critical);
+
+        sqlx::raw_sql(&format!("DROP SCHEMA {schema} CASCADE")).execute(pool.as_ref()).await.unwrap();
+    }
+}
@@ -1,18 +1,22 @@
 pub mod attribution;
+pub mod bundle;
 pub mod calibration;
 pub mod drift_detection;
 pub mod metrics;
 pub mod resolution;
+pub mod resolution_router;
 pub mod ab_testing;
 pub mod shadow_mode;
 
-pub use attribution::{AttributionEngine, PnlAttribution, SignalOutcomeAnalysis};
-pub use calibration::{CalibrationEngine, BrierScoreCalculator, BrierDecomposition};
+pub use attribution::{AttributionEngine, AttributionReport, CategoryPnlAttribution, MarketPnlAttribution, PnlAttribution, PnlCorrection, SignalOutcomeAnalysis, UNCATEGORIZED};
+pub use bundle::{ArtifactEntry, ArtifactSource, BundleExporter, BundleManifest, BundleScope, JsonlSource, PgTableSource};
+pub use calibration::{CalibrationEngine, BrierScoreCalculator, BrierDecomposition, DecomposedCalibration};
 pub use drift_detection::{DriftDetector, DriftDetectionConfig};
-pub use metrics::{MetricsCalculator, StrategyComparison};
-pub use resolution::{ResolutionMonitor, ResolutionTracker, ResolutionStats};
-pub use ab_testing::{AbTestManager, AbTestEngine, AssignmentCounts};
-pub use shadow_mode::{ShadowMode, PaperTrader, ShadowPerformance, ShadowRealComparison};
+pub use metrics::{BootstrapMetrics, EquityPoint, MetricsCalculator, PercentileBand, StrategyComparison};
+pub use resolution::{MarketResolutionStats, ResolutionAlert, ResolutionMonitor, ResolutionTracker, ResolutionStats};
+pub use resolution_router::{ResolutionRouter, ResolutionRouterConfig, ResolutionSink, ResolutionSummary};
+pub use ab_testing::{AbTestAssignmentRecorder, AbTestManager, AbTestEngine, AssignmentCounts, InterimLook, MannWhitneyResult, PairedTestResult, RebalanceReport, SequentialDecision, StratumBalance, TTestResult, WilcoxonResult, mann_whitney_u, obrien_fleming_spending, paired_t_test, pocock_spending, spend_alpha, welch_t_test, wilcoxon_signed_rank};
+pub use shadow_mode::{DivergenceAlert, DivergenceConfig, DivergenceTest, ExitRules, LatestOrderBooks, MarketDataSource, PaperExecution, PaperTrader, RestingPaperOrder, ShadowMode, ShadowPerformance, ShadowRealComparison, UnfilledPolicy};
 
 // Re-export from common
-pub use common::{PerformanceMetrics, StrategyPerformance};
+pub use common::{NonparametricResult, PerformanceMetrics, StrategyPerformance};
@@ -1,6 +1,6 @@
 use anyhow::Result;
 use chrono::{Duration, Utc};
-use common::{AbTest, AbTestStatus, OrderSide, ResolutionStatus};
+use common::{AbTest, AbTestMethod, AbTestStatus, AlphaSpending, MarketId, OrderSide, OutcomeId, ResolutionStatus};
 use monitoring::{
     AbTestEngine, AbTestManager, AttributionEngine, CalibrationEngine, DriftDetector,
     DriftDetectionConfig, MetricsCalculator, PaperTrader, ResolutionMonitor, ShadowMode,
@@ -36,9 +36,15 @@ async fn main() -> Result<()> {
     resolution_monitor.initialize().await?;
 
     // Simulate a market resolution
-    let market_id = Uuid::new_v4();
+    let market_id = MarketId::random();
     info!("Simulating resolution for market {}", market_id);
 
+    if let Ok(stats) = resolution_monitor.record_resolution(market_id, &OutcomeId::from("YES")).await {
+        info!("  Trades settled: {} won, {} lost", stats.winning_trades, stats.losing_trades);
+        info!("  Predictions updated: {}", stats.predictions_updated);
+        info!("  Attributed trades resolved: {}", stats.attributed_trades_resolved);
+    }
+
     // Example 2: Attribution
     info!("\n=== Example 2: Attribution Engine ===");
     let attribution = AttributionEngine::new(pool.clone());
@@ -57,6 +63,22 @@ async fn main() -> Result<()> {
         info!("  Profit Factor: {:.2}", attribution_result.profit_factor);
     }
 
+    // Which markets and categories the P&L came from
+    if let Ok(report) = attribution.attribution_report(strategy_id, from, to).await {
+        for category in &report.by_category {
+            info!(
+                "  [{}] {} markets, {} trades, P&L ${:.2}, hit rate {:.2}%",
+                category.category, category.markets, category.total_trades, category.total_pnl, category.hit_rate
+            );
+        }
+        for market in report.top_winners(3) {
+            info!("  Winner {}: ${:.2} over {} trades", market.market_id, market.total_pnl, market.total_trades);
+        }
+        for market in report.top_losers(3) {
+            info!("  Loser {}: ${:.2} over {} trades", market.market_id, market.total_pnl, market.total_trades);
+        }
+    }
+
     // Example 3: Metrics Calculation
     info!("\n=== Example 3: Performance Metrics ===");
     let metrics_calc = MetricsCalculator::new(pool.clone(), 0.02);
@@ -106,6 +128,7 @@ async fn main() -> Result<()> {
         hit_rate_decline_threshold: 10.0,
         brier_score_increase_threshold: 0.05,
         volume_decline_threshold: 25.0,
+        ..Default::default()
     };
 
     let drift_detector = DriftDetector::new(pool.clone(), drift_config);
@@ -130,7 +153,7 @@ async fn main() -> Result<()> {
     let ab_test_manager = AbTestManager::new(pool.clone());
     ab_test_manager.initialize().await?;
 
-    let ab_engine = AbTestEngine::new(pool.clone());
+    let ab_engine = AbTestEngine::new(pool.clone(), 0.02);
 
     // Create a test
     let test_id = Uuid::new_v4();
@@ -145,6 +168,10 @@ async fn main() -> Result<()> {
         allocation_ratio: 0.5,
         min_sample_size: 50,
         statistical_significance: 0.95,
+        method: AbTestMethod::Welch,
+        looks_planned: 4,
+        alpha_spending: AlphaSpending::ObrienFleming,
+        current_look: 0,
     };
 
     ab_test_manager.create_test(test).await?;
@@ -168,11 +195,11 @@ async fn main() -> Result<()> {
     let paper_trader = PaperTrader::new(pool.clone());
 
     // Execute a paper trade
-    let shadow_market_id = Uuid::new_v4();
+    let shadow_market_id = MarketId::random();
     let shadow_trade = paper_trader
         .execute_paper_trade(
             shadow_market_id,
-            "YES",
+            &OutcomeId::from("YES"),
             OrderSide::Buy,
             0.65,
             100.0,
@@ -180,7 +207,9 @@ async fn main() -> Result<()> {
         )
         .await?;
 
-    info!("Executed paper trade {}", shadow_trade.id);
+    if let Some(fill) = &shadow_trade.fill {
+        info!("Executed paper trade {}", fill.id);
+    }
 
     // Calculate shadow performance
     if let Ok(perf) = shadow_mode.calculate_shadow_performance("sentiment-v2").await {
@@ -188,6 +217,7 @@ async fn main() -> Result<()> {
         info!("  Total Trades: {}", perf.total_trades);
         info!("  Hit Rate: {:.2}%", perf.hit_rate);
         info!("  Hypothetical P&L: ${:.2}", perf.total_pnl);
+        info!("  Unrealized P&L: ${:.2} over {} open positions", perf.unrealized_pnl, perf.open_positions);
     }
 
     // Compare shadow vs real
@@ -199,6 +229,9 @@ async fn main() -> Result<()> {
         info!("  Shadow P&L: ${:.2}", comparison.shadow_performance.total_pnl);
         info!("  Real P&L: ${:.2}", comparison.real_pnl);
         info!("  P&L Diff: ${:.2}", comparison.pnl_diff);
+        if let Some(diff) = comparison.slippage_diff_bps {
+            info!("  Slippage Diff: {:.1} bps", diff);
+        }
     }
 
     info!("\n=== Monitoring System Examples Complete ===");
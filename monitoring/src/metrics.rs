@@ -5,6 +5,9 @@ use sqlx::postgres::PgPool;
 use std::sync::Arc;
 use tracing::info;
 
+/// Resamples drawn by `bootstrap_metrics` when no count is given
+pub const DEFAULT_BOOTSTRAP_ITERATIONS: usize = 1000;
+
 /// Metrics Calculator - Computes performance metrics for strategies/agents
 pub struct MetricsCalculator {
     db_pool: Arc<PgPool>,
@@ -21,7 +24,7 @@ impl MetricsCalculator {
 
     /// Initialize metrics tables
     pub async fn initialize(&self) -> Result<()> {
-        sqlx::query(
+        sqlx::raw_sql(
             r#"
             CREATE TABLE IF NOT EXISTS performance_metrics (
                 id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
@@ -48,6 +51,8 @@ impl MetricsCalculator {
             CREATE INDEX IF NOT EXISTS idx_metrics_strategy ON performance_metrics(strategy_id);
             CREATE INDEX IF NOT EXISTS idx_metrics_agent ON performance_metrics(agent_id);
             CREATE INDEX IF NOT EXISTS idx_metrics_time ON performance_metrics(period_start, period_end);
+
+            ALTER TABLE performance_metrics ADD COLUMN IF NOT EXISTS sortino_ratio NUMERIC(10, 4);
             "#,
         )
         .execute(self.db_pool.as_ref())
@@ -65,20 +70,269 @@ impl MetricsCalculator {
         period_start: DateTime<Utc>,
         period_end: DateTime<Utc>,
     ) -> Result<PerformanceMetrics> {
-        // Get trade-level data
-        let trades = sqlx::query_as::<_, (DateTime<Utc>, f64, f64)>(
+        let trades = self.fetch_trades(strategy_id, period_start, period_end).await?;
+
+        let metrics = Self::summarize_trades(
+            strategy_id,
+            period_start,
+            period_end,
+            &trades,
+            self.risk_free_rate,
+        );
+
+        if trades.is_empty() {
+            return Ok(metrics);
+        }
+
+        // Store metrics
+        self.store_metrics(&metrics).await?;
+
+        Ok(metrics)
+    }
+
+    /// Performance metrics over a window sliding across `from`..`to`
+    ///
+    /// Windows are `[start, start + window)` for start = from, from + step,
+    /// ... while the window ends by `to`; each row is keyed by its window's
+    /// end. Windows without trades still produce a row, with zero trades.
+    /// Rolling rows are not stored.
+    pub async fn calculate_rolling_metrics(
+        &self,
+        strategy_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        window: Duration,
+        step: Duration,
+    ) -> Result<Vec<(DateTime<Utc>, PerformanceMetrics)>> {
+        if window <= Duration::zero() || step <= Duration::zero() {
+            anyhow::bail!("Rolling window and step must be positive");
+        }
+
+        let trades = self.fetch_trades(strategy_id, from, to).await?;
+        Ok(Self::rolling_trades(strategy_id, from, to, window, step, &trades, self.risk_free_rate))
+    }
+
+    /// Rolling metrics over a time-ordered series of `(timestamp, pnl,
+    /// pnl_percent)` closed trades, windowed as in `calculate_rolling_metrics`
+    pub fn rolling_trades(
+        strategy_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        window: Duration,
+        step: Duration,
+        trades: &[(DateTime<Utc>, f64, f64)],
+        risk_free_rate: f64,
+    ) -> Vec<(DateTime<Utc>, PerformanceMetrics)> {
+        let mut rows = Vec::new();
+        if window <= Duration::zero() || step <= Duration::zero() {
+            return rows;
+        }
+
+        let mut start = from;
+        while start + window <= to {
+            let end = start + window;
+            let first = trades.partition_point(|t| t.0 < start);
+            let last = trades.partition_point(|t| t.0 < end);
+            let metrics = Self::summarize_trades(strategy_id, start, end, &trades[first..last], risk_free_rate);
+            rows.push((end, metrics));
+            start += step;
+        }
+
+        rows
+    }
+
+    /// A strategy's equity curve over `from`..`to` in buckets of `bucket`
+    ///
+    /// Buckets start at `from`, `from + bucket`, ... up to `to`, and every
+    /// one is returned, empty or not, from a single query.
+    pub async fn calculate_equity_curve(
+        &self,
+        strategy_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        bucket: Duration,
+    ) -> Result<Vec<EquityPoint>> {
+        if bucket <= Duration::zero() {
+            anyhow::bail!("Equity curve bucket must be positive");
+        }
+
+        let rows = sqlx::query_as::<_, (DateTime<Utc>, f64, f64, i64, i64, i64)>(
             r#"
+            WITH buckets AS (
+                SELECT generate_series($2::TIMESTAMPTZ, $3::TIMESTAMPTZ, $4 * INTERVAL '1 second') as bucket
+            ),
+            binned AS (
+                SELECT
+                    date_bin($4 * INTERVAL '1 second', t.timestamp, $2::TIMESTAMPTZ) as bucket,
+                    SUM(t.pnl) as pnl,
+                    COUNT(*) as trades,
+                    COUNT(*) FILTER (WHERE t.pnl > 0) as winning_trades
+                FROM trades t
+                JOIN attributed_trades at ON t.id = at.trade_id
+                WHERE at.strategy_id = $1
+                AND t.timestamp >= $2
+                AND t.timestamp <= $3
+                AND t.pnl IS NOT NULL
+                GROUP BY 1
+            )
             SELECT
+                b.bucket,
+                COALESCE(x.pnl, 0.0)::DOUBLE PRECISION as pnl,
+                (SUM(COALESCE(x.pnl, 0.0)) OVER w)::DOUBLE PRECISION as cumulative_pnl,
+                COALESCE(x.trades, 0) as trades,
+                (SUM(COALESCE(x.trades, 0)) OVER w)::BIGINT as cumulative_trades,
+                (SUM(COALESCE(x.winning_trades, 0)) OVER w)::BIGINT as cumulative_wins
+            FROM buckets b
+            LEFT JOIN binned x ON x.bucket = b.bucket
+            WINDOW w AS (ORDER BY b.bucket)
+            ORDER BY b.bucket
+            "#,
+        )
+        .bind(strategy_id)
+        .bind(from)
+        .bind(to)
+        .bind(bucket.num_milliseconds() as f64 / 1000.0)
+        .fetch_all(self.db_pool.as_ref())
+        .await
+        .context("Failed to calculate equity curve")?;
+
+        let mut points: Vec<EquityPoint> = rows
+            .into_iter()
+            .map(|(timestamp, pnl, cumulative_pnl, trades, cumulative_trades, cumulative_wins)| EquityPoint {
                 timestamp,
                 pnl,
-                pnl_percent
+                cumulative_pnl,
+                trades,
+                cumulative_trades,
+                hit_rate: if cumulative_trades > 0 {
+                    cumulative_wins as f64 / cumulative_trades as f64 * 100.0
+                } else {
+                    0.0
+                },
+                drawdown: 0.0,
+            })
+            .collect();
+        EquityPoint::fill_drawdowns(&mut points);
+
+        Ok(points)
+    }
+
+    /// Equity curve over a time-ordered series of `(timestamp, pnl,
+    /// pnl_percent)` closed trades, bucketed as in `calculate_equity_curve`
+    pub fn equity_curve(
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        bucket: Duration,
+        trades: &[(DateTime<Utc>, f64, f64)],
+    ) -> Vec<EquityPoint> {
+        let mut points = Vec::new();
+        if bucket <= Duration::zero() {
+            return points;
+        }
+
+        let (mut cumulative_pnl, mut cumulative_trades, mut cumulative_wins) = (0.0, 0, 0);
+        let mut start = from;
+        while start <= to {
+            let first = trades.partition_point(|t| t.0 < start);
+            let last = trades.partition_point(|t| t.0 < start + bucket && t.0 <= to);
+            let bucket_trades = &trades[first..last];
+
+            let pnl: f64 = bucket_trades.iter().map(|t| t.1).sum();
+            cumulative_pnl += pnl;
+            cumulative_trades += bucket_trades.len() as i64;
+            cumulative_wins += bucket_trades.iter().filter(|t| t.1 > 0.0).count() as i64;
+            points.push(EquityPoint {
+                timestamp: start,
+                pnl,
+                cumulative_pnl,
+                trades: bucket_trades.len() as i64,
+                cumulative_trades,
+                hit_rate: if cumulative_trades > 0 {
+                    cumulative_wins as f64 / cumulative_trades as f64 * 100.0
+                } else {
+                    0.0
+                },
+                drawdown: 0.0,
+            });
+            start += bucket;
+        }
+        EquityPoint::fill_drawdowns(&mut points);
+
+        points
+    }
+
+    /// Bootstrap confidence bands for a strategy's total P&L, ROI and hit rate
+    ///
+    /// Resamples the strategy's closed trades with replacement `iterations`
+    /// times (`DEFAULT_BOOTSTRAP_ITERATIONS` if None).
+    pub async fn bootstrap_metrics(
+        &self,
+        strategy_id: &str,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+        iterations: Option<usize>,
+    ) -> Result<BootstrapMetrics> {
+        let trades = self.fetch_trades(strategy_id, period_start, period_end).await?;
+        let iterations = iterations.unwrap_or(DEFAULT_BOOTSTRAP_ITERATIONS);
+
+        Ok(Self::bootstrap_trades(strategy_id, &trades, iterations, &mut fastrand::Rng::new()))
+    }
+
+    /// Bootstrap bands from a series of `(timestamp, pnl, pnl_percent)` closed
+    /// trades, drawing resamples from `rng`
+    pub fn bootstrap_trades(
+        strategy_id: &str,
+        trades: &[(DateTime<Utc>, f64, f64)],
+        iterations: usize,
+        rng: &mut fastrand::Rng,
+    ) -> BootstrapMetrics {
+        let mut total_pnl = Vec::with_capacity(iterations);
+        let mut roi = Vec::with_capacity(iterations);
+        let mut hit_rate = Vec::with_capacity(iterations);
+
+        if !trades.is_empty() {
+            let mut sample = Vec::with_capacity(trades.len());
+            for _ in 0..iterations {
+                sample.clear();
+                sample.extend((0..trades.len()).map(|_| trades[rng.usize(..trades.len())]));
+
+                let wins = sample.iter().filter(|t| t.1 > 0.0).count();
+                total_pnl.push(sample.iter().map(|t| t.1).sum::<f64>());
+                roi.push(Self::calculate_roi(&sample));
+                hit_rate.push(wins as f64 / sample.len() as f64 * 100.0);
+            }
+        }
+
+        BootstrapMetrics {
+            strategy_id: strategy_id.to_string(),
+            trades: trades.len(),
+            iterations: total_pnl.len(),
+            total_pnl: PercentileBand::from_samples(total_pnl),
+            roi: PercentileBand::from_samples(roi),
+            hit_rate: PercentileBand::from_samples(hit_rate),
+        }
+    }
+
+    /// Closed trades of a strategy as `(timestamp, pnl, pnl_percent)`, oldest first
+    async fn fetch_trades(
+        &self,
+        strategy_id: &str,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+    ) -> Result<Vec<(DateTime<Utc>, f64, f64)>> {
+        let trades = sqlx::query_as::<_, (DateTime<Utc>, f64, f64)>(
+            r#"
+            SELECT
+                t.timestamp,
+                t.pnl,
+                t.pnl_percent
             FROM trades t
             JOIN attributed_trades at ON t.id = at.trade_id
             WHERE at.strategy_id = $1
             AND t.timestamp >= $2
             AND t.timestamp <= $3
             AND t.pnl IS NOT NULL
-            ORDER BY timestamp
+            ORDER BY t.timestamp
             "#,
         )
         .bind(strategy_id)
@@ -88,26 +342,18 @@ impl MetricsCalculator {
         .await
         .context("Failed to fetch trade data")?;
 
-        if trades.is_empty() {
-            return Ok(PerformanceMetrics {
-                strategy_id: strategy_id.to_string(),
-                period_start,
-                period_end,
-                total_trades: 0,
-                winning_trades: 0,
-                losing_trades: 0,
-                hit_rate: 0.0,
-                total_pnl: 0.0,
-                roi: 0.0,
-                sharpe_ratio: None,
-                max_drawdown: 0.0,
-                avg_win: 0.0,
-                avg_loss: 0.0,
-                profit_factor: 0.0,
-                calmar_ratio: None,
-            });
-        }
+        Ok(trades)
+    }
 
+    /// Build performance metrics from a time-ordered series of
+    /// `(timestamp, pnl, pnl_percent)` closed trades
+    pub fn summarize_trades(
+        strategy_id: &str,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+        trades: &[(DateTime<Utc>, f64, f64)],
+        risk_free_rate: f64,
+    ) -> PerformanceMetrics {
         let total_trades = trades.len() as i64;
         let winning_trades = trades.iter().filter(|t| t.1 > 0.0).count() as i64;
         let losing_trades = trades.iter().filter(|t| t.1 < 0.0).count() as i64;
@@ -118,34 +364,22 @@ impl MetricsCalculator {
         };
 
         let total_pnl: f64 = trades.iter().map(|t| t.1).sum();
-        let roi = self.calculate_roi(&trades)?;
-
-        let sharpe_ratio = self.calculate_sharpe_ratio(&trades)?;
-        let max_drawdown = self.calculate_max_drawdown(&trades)?;
+        let roi = Self::calculate_roi(trades);
 
-        let avg_win = if winning_trades > 0 {
-            trades.iter().filter(|t| t.1 > 0.0).map(|t| t.1).sum::<f64>() / winning_trades as f64
-        } else {
-            0.0
-        };
-
-        let avg_loss = if losing_trades > 0 {
-            trades.iter().filter(|t| t.1 < 0.0).map(|t| t.1.abs()).sum::<f64>() / losing_trades as f64
-        } else {
-            0.0
-        };
+        let sharpe_ratio = Self::calculate_sharpe_ratio(trades, risk_free_rate);
+        let sortino_ratio = Self::calculate_sortino_ratio(trades, risk_free_rate);
+        let max_drawdown = Self::calculate_max_drawdown(trades);
 
         let total_wins = trades.iter().filter(|t| t.1 > 0.0).map(|t| t.1).sum::<f64>();
         let total_losses = trades.iter().filter(|t| t.1 < 0.0).map(|t| t.1.abs()).sum::<f64>();
+
+        let avg_win = if winning_trades > 0 { total_wins / winning_trades as f64 } else { 0.0 };
+        let avg_loss = if losing_trades > 0 { total_losses / losing_trades as f64 } else { 0.0 };
         let profit_factor = if total_losses > 0.0 { total_wins / total_losses } else { 0.0 };
 
-        let calmar_ratio = if max_drawdown != 0.0 {
-            Some(total_pnl.abs() / max_drawdown)
-        } else {
-            None
-        };
+        let calmar_ratio = Self::calculate_calmar_ratio(trades, max_drawdown);
 
-        let metrics = PerformanceMetrics {
+        PerformanceMetrics {
             strategy_id: strategy_id.to_string(),
             period_start,
             period_end,
@@ -156,21 +390,21 @@ impl MetricsCalculator {
             total_pnl,
             roi,
             sharpe_ratio,
+            sortino_ratio,
             max_drawdown,
             avg_win,
             avg_loss,
             profit_factor,
             calmar_ratio,
-        };
-
-        // Store metrics
-        self.store_metrics(&metrics).await?;
+        }
+    }
 
-        Ok(metrics)
+    pub fn risk_free_rate(&self) -> f64 {
+        self.risk_free_rate
     }
 
     /// Calculate ROI (Return on Investment)
-    fn calculate_roi(&self, trades: &[(DateTime<Utc>, f64, f64)]) -> Result<f64> {
+    fn calculate_roi(trades: &[(DateTime<Utc>, f64, f64)]) -> f64 {
         let total_invested: f64 = trades
             .iter()
             .map(|t| {
@@ -183,16 +417,16 @@ impl MetricsCalculator {
         let total_return: f64 = trades.iter().map(|t| t.1).sum();
 
         if total_invested > 0.0 {
-            Ok((total_return / total_invested) * 100.0)
+            (total_return / total_invested) * 100.0
         } else {
-            Ok(0.0)
+            0.0
         }
     }
 
     /// Calculate Sharpe Ratio
-    fn calculate_sharpe_ratio(&self, trades: &[(DateTime<Utc>, f64, f64)]) -> Result<Option<f64>> {
+    fn calculate_sharpe_ratio(trades: &[(DateTime<Utc>, f64, f64)], risk_free_rate: f64) -> Option<f64> {
         if trades.len() < 2 {
-            return Ok(None);
+            return None;
         }
 
         let returns: Vec<f64> = trades.iter().map(|t| t.2).collect();
@@ -208,15 +442,54 @@ impl MetricsCalculator {
         if std_dev > 0.0 {
             // Annualize: assume daily returns, multiply by sqrt(252)
             let annualized_std = std_dev * (252.0_f64).sqrt();
-            let excess_return = (mean_return - self.risk_free_rate) * 252.0;
-            Ok(Some(excess_return / annualized_std))
+            let excess_return = (mean_return - risk_free_rate) * 252.0;
+            Some(excess_return / annualized_std)
+        } else {
+            None
+        }
+    }
+
+    /// Calculate Sortino Ratio (excess return over downside deviation)
+    fn calculate_sortino_ratio(trades: &[(DateTime<Utc>, f64, f64)], risk_free_rate: f64) -> Option<f64> {
+        if trades.len() < 2 {
+            return None;
+        }
+
+        let returns: Vec<f64> = trades.iter().map(|t| t.2).collect();
+        let mean_return = returns.iter().sum::<f64>() / returns.len() as f64;
+
+        // Only returns below the risk-free rate count as risk
+        let downside_variance = returns
+            .iter()
+            .map(|r| (r - risk_free_rate).min(0.0).powi(2))
+            .sum::<f64>() / returns.len() as f64;
+
+        let downside_deviation = downside_variance.sqrt();
+
+        if downside_deviation > 0.0 {
+            // Annualized like the Sharpe ratio
+            let annualized_downside = downside_deviation * (252.0_f64).sqrt();
+            let excess_return = (mean_return - risk_free_rate) * 252.0;
+            Some(excess_return / annualized_downside)
         } else {
-            Ok(None)
+            None
         }
     }
 
+    /// Calculate Calmar Ratio (annualized return over max drawdown)
+    fn calculate_calmar_ratio(trades: &[(DateTime<Utc>, f64, f64)], max_drawdown: f64) -> Option<f64> {
+        if trades.is_empty() || max_drawdown <= 0.0 {
+            return None;
+        }
+
+        let mean_return = trades.iter().map(|t| t.2).sum::<f64>() / trades.len() as f64;
+        let annualized_return = mean_return * 252.0;
+
+        Some(annualized_return / (max_drawdown / 100.0))
+    }
+
     /// Calculate Maximum Drawdown
-    fn calculate_max_drawdown(&self, trades: &[(DateTime<Utc>, f64, f64)]) -> Result<f64> {
+    fn calculate_max_drawdown(trades: &[(DateTime<Utc>, f64, f64)]) -> f64 {
         let mut cumulative_pnl = 0.0;
         let mut peak = 0.0;
         let mut max_drawdown = 0.0;
@@ -232,7 +505,7 @@ impl MetricsCalculator {
             }
         }
 
-        Ok(max_drawdown * 100.0) // Return as percentage
+        max_drawdown * 100.0 // Return as percentage
     }
 
     /// Store calculated metrics
@@ -242,10 +515,10 @@ impl MetricsCalculator {
             INSERT INTO performance_metrics (
                 strategy_id, period_start, period_end,
                 total_trades, winning_trades, losing_trades,
-                hit_rate, total_pnl, roi, sharpe_ratio,
+                hit_rate, total_pnl, roi, sharpe_ratio, sortino_ratio,
                 max_drawdown, avg_win, avg_loss, profit_factor, calmar_ratio
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
             ON CONFLICT (strategy_id, period_start, period_end)
             DO UPDATE SET
                 total_trades = EXCLUDED.total_trades,
@@ -255,6 +528,7 @@ impl MetricsCalculator {
                 total_pnl = EXCLUDED.total_pnl,
                 roi = EXCLUDED.roi,
                 sharpe_ratio = EXCLUDED.sharpe_ratio,
+                sortino_ratio = EXCLUDED.sortino_ratio,
                 max_drawdown = EXCLUDED.max_drawdown,
                 avg_win = EXCLUDED.avg_win,
                 avg_loss = EXCLUDED.avg_loss,
@@ -272,6 +546,7 @@ impl MetricsCalculator {
         .bind(metrics.total_pnl)
         .bind(metrics.roi)
         .bind(metrics.sharpe_ratio)
+        .bind(metrics.sortino_ratio)
         .bind(metrics.max_drawdown)
         .bind(metrics.avg_win)
         .bind(metrics.avg_loss)
@@ -354,3 +629,300 @@ pub struct StrategyComparison {
     pub period_days: i64,
     pub winner: Option<String>,
 }
+
+/// One bucket of a strategy's equity curve
+#[derive(Debug, Clone, PartialEq)]
+pub struct EquityPoint {
+    /// Start of the bucket
+    pub timestamp: DateTime<Utc>,
+    /// P&L of the trades in the bucket
+    pub pnl: f64,
+    /// P&L of every trade up to the end of the bucket
+    pub cumulative_pnl: f64,
+    pub trades: i64,
+    pub cumulative_trades: i64,
+    /// Hit rate (%) of every trade up to the end of the bucket
+    pub hit_rate: f64,
+    /// Percent below the highest cumulative P&L so far, measured as in
+    /// `PerformanceMetrics::max_drawdown`
+    pub drawdown: f64,
+}
+
+impl EquityPoint {
+    /// Largest drawdown along a curve; it sees bucket closes only, so can be
+    /// smaller than the per-trade figure in `PerformanceMetrics`
+    pub fn max_drawdown(curve: &[EquityPoint]) -> f64 {
+        curve.iter().map(|p| p.drawdown).fold(0.0, f64::max)
+    }
+
+    fn fill_drawdowns(curve: &mut [EquityPoint]) {
+        let mut peak: f64 = 0.0;
+        for point in curve {
+            peak = peak.max(point.cumulative_pnl);
+            point.drawdown = (peak - point.cumulative_pnl) / peak.abs().max(1.0) * 100.0;
+        }
+    }
+}
+
+/// Bootstrap percentile bands of a strategy's headline metrics
+#[derive(Debug, Clone, PartialEq)]
+pub struct BootstrapMetrics {
+    pub strategy_id: String,
+    /// Closed trades resampled
+    pub trades: usize,
+    /// Resamples drawn, 0 without trades
+    pub iterations: usize,
+    pub total_pnl: PercentileBand,
+    pub roi: PercentileBand,
+    pub hit_rate: PercentileBand,
+}
+
+/// 2.5th, 50th and 97.5th percentiles of a bootstrapped metric
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PercentileBand {
+    pub lower: f64,
+    pub median: f64,
+    pub upper: f64,
+}
+
+impl PercentileBand {
+    /// Band of a set of samples, all zero if there are none
+    fn from_samples(mut samples: Vec<f64>) -> Self {
+        samples.sort_by(|a, b| a.total_cmp(b));
+        Self {
+            lower: percentile(&samples, 0.025),
+            median: percentile(&samples, 0.5),
+            upper: percentile(&samples, 0.975),
+        }
+    }
+
+    pub fn contains(&self, value: f64) -> bool {
+        self.lower <= value && value <= self.upper
+    }
+}
+
+/// Linearly interpolated percentile `q` (0.0 - 1.0) of sorted samples
+fn percentile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = q * (sorted.len() - 1) as f64;
+    let below = rank.floor() as usize;
+    let above = rank.ceil() as usize;
+    sorted[below] + (sorted[above] - sorted[below]) * (rank - below as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(returns: &[f64]) -> Vec<(DateTime<Utc>, f64, f64)> {
+        let start = Utc::now() - Duration::days(returns.len() as i64);
+        returns
+            .iter()
+            .enumerate()
+            .map(|(i, r)| (start + Duration::days(i as i64), r * 1000.0, *r))
+            .collect()
+    }
+
+    #[test]
+    fn test_sortino_and_calmar_from_known_series() {
+        // Frequent modest gains, rare small losses
+        let trades = series(&[0.03, -0.01, 0.02, 0.04, -0.005, 0.01, 0.02, -0.01]);
+        let metrics = MetricsCalculator::summarize_trades("s", Utc::now(), Utc::now(), &trades, 0.0);
+
+        // mean 0.011875, downside deviation sqrt((0.01^2 + 0.005^2 + 0.01^2) / 8)
+        let downside = ((0.0001 + 0.000025 + 0.0001) / 8.0_f64).sqrt();
+        let expected_sortino = (0.011875 * 252.0) / (downside * 252.0_f64.sqrt());
+        let sortino = metrics.sortino_ratio.unwrap();
+        assert!((sortino - expected_sortino).abs() < 1e-9);
+
+        // The downside is a fraction of total volatility
+        assert!(sortino > metrics.sharpe_ratio.unwrap());
+
+        // Equity 30, 20, 40, 80, 75, ...: the worst drawdown is 30 -> 20
+        assert!((metrics.max_drawdown - 100.0 / 3.0).abs() < 1e-9);
+        let calmar = metrics.calmar_ratio.unwrap();
+        assert!((calmar - 0.011875 * 252.0 / (1.0 / 3.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ratios_undefined_without_downside() {
+        // Never below the risk-free rate, never off its peak
+        let trades = series(&[0.01, 0.02, 0.015, 0.03]);
+        let metrics = MetricsCalculator::summarize_trades("s", Utc::now(), Utc::now(), &trades, 0.0);
+
+        assert!(metrics.sharpe_ratio.is_some());
+        assert_eq!(metrics.sortino_ratio, None);
+        assert_eq!(metrics.max_drawdown, 0.0);
+        assert_eq!(metrics.calmar_ratio, None);
+
+        let single = series(&[-0.02]);
+        let metrics = MetricsCalculator::summarize_trades("s", Utc::now(), Utc::now(), &single, 0.0);
+        assert_eq!(metrics.sortino_ratio, None);
+    }
+
+    #[test]
+    fn test_bootstrap_bands_bracket_point_estimates() {
+        let trades = series(&[0.03, -0.01, 0.02, 0.04, -0.005, 0.01, 0.02, -0.01, 0.015, -0.02, 0.025, 0.005]);
+        let point = MetricsCalculator::summarize_trades("s", Utc::now(), Utc::now(), &trades, 0.0);
+
+        let mut rng = fastrand::Rng::with_seed(7);
+        let bands = MetricsCalculator::bootstrap_trades("s", &trades, DEFAULT_BOOTSTRAP_ITERATIONS, &mut rng);
+        assert_eq!(bands.trades, 12);
+        assert_eq!(bands.iterations, 1000);
+
+        for (band, estimate) in [
+            (bands.total_pnl, point.total_pnl),
+            (bands.roi, point.roi),
+            (bands.hit_rate, point.hit_rate),
+        ] {
+            assert!(band.lower < band.median && band.median < band.upper, "{:?}", band);
+            assert!(band.contains(estimate), "{:?} should contain {}", band, estimate);
+            assert!((band.median - estimate).abs() < (band.upper - band.lower) / 4.0);
+        }
+
+        // Same seed, same bands
+        let again = MetricsCalculator::bootstrap_trades(
+            "s",
+            &trades,
+            DEFAULT_BOOTSTRAP_ITERATIONS,
+            &mut fastrand::Rng::with_seed(7),
+        );
+        assert_eq!(again, bands);
+    }
+
+    #[test]
+    fn test_bootstrap_without_trades() {
+        let bands = MetricsCalculator::bootstrap_trades("s", &[], 100, &mut fastrand::Rng::with_seed(7));
+        assert_eq!(bands.iterations, 0);
+        assert_eq!(bands.total_pnl, PercentileBand { lower: 0.0, median: 0.0, upper: 0.0 });
+    }
+
+    #[test]
+    fn test_equity_curve_buckets_and_drawdown() {
+        let from = Utc::now() - Duration::days(10);
+        let at = |hours: i64, pnl: f64| (from + Duration::hours(hours), pnl, pnl / 1000.0);
+        // A trade on a boundary opens the next bucket; nothing in 2h - 4h
+        let trades = vec![
+            at(0, 10.0),
+            at(0, -4.0),
+            at(1, 20.0),
+            at(4, -30.0),
+            at(5, 3.0),
+            at(6, 8.0),
+        ];
+
+        let curve = MetricsCalculator::equity_curve(from, from + Duration::hours(6), Duration::hours(2), &trades);
+        let timestamps: Vec<_> = curve.iter().map(|p| p.timestamp).collect();
+        assert_eq!(timestamps, [0, 2, 4, 6].map(|h| from + Duration::hours(h)).to_vec());
+        assert_eq!(curve.iter().map(|p| p.trades).collect::<Vec<_>>(), vec![3, 0, 2, 1]);
+        assert_eq!(curve.iter().map(|p| p.cumulative_pnl).collect::<Vec<_>>(), vec![26.0, 26.0, -1.0, 7.0]);
+        assert_eq!(curve[3].cumulative_trades, 6);
+        assert!((curve[2].hit_rate - 60.0).abs() < 1e-9);
+        assert!((curve[3].hit_rate - 100.0 * 4.0 / 6.0).abs() < 1e-9);
+
+        // Peak 26 down to -1
+        assert!((curve[2].drawdown - 27.0 / 26.0 * 100.0).abs() < 1e-9);
+        assert!((EquityPoint::max_drawdown(&curve) - 27.0 / 26.0 * 100.0).abs() < 1e-9);
+        // Trade by trade the low is -4, inside the 4h bucket
+        let metrics = MetricsCalculator::summarize_trades("s", from, from, &trades, 0.0);
+        assert!((metrics.max_drawdown - 30.0 / 26.0 * 100.0).abs() < 1e-9);
+
+        assert!(MetricsCalculator::equity_curve(from, from, Duration::zero(), &trades).is_empty());
+    }
+
+    /// Runs against a scratch Postgres: `TEST_DATABASE_URL=... cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_equity_curve_query_matches_trade_series_postgres() {
+        use sqlx::postgres::PgPoolOptions;
+
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL not set");
+        // Single connection so the temp tables are visible to every query
+        let pool = Arc::new(PgPoolOptions::new().max_connections(1).connect(&url).await.unwrap());
+        sqlx::raw_sql(
+            r#"
+            CREATE TEMP TABLE trades (
+                id UUID PRIMARY KEY,
+                pnl DOUBLE PRECISION,
+                pnl_percent DOUBLE PRECISION,
+                timestamp TIMESTAMPTZ NOT NULL
+            );
+            CREATE TEMP TABLE attributed_trades (
+                trade_id UUID PRIMARY KEY,
+                strategy_id TEXT NOT NULL
+            );
+            "#,
+        )
+        .execute(pool.as_ref())
+        .await
+        .unwrap();
+
+        // Whole seconds, so the fixture round-trips through Postgres exactly
+        let from = DateTime::from_timestamp(Utc::now().timestamp() - 86_400, 0).unwrap();
+        let trades: Vec<(DateTime<Utc>, f64, f64)> = [(0, 10.0), (0, -4.0), (60, 20.0), (240, -30.0), (300, 3.0), (360, 8.0)]
+            .into_iter()
+            .map(|(minutes, pnl)| (from + Duration::minutes(minutes), pnl, 0.0))
+            .collect();
+        for (strategy_id, (timestamp, pnl, _)) in trades.iter().map(|t| ("curve", t)).chain([("other", &trades[0])]) {
+            let trade_id = uuid::Uuid::new_v4();
+            sqlx::query("INSERT INTO trades (id, pnl, pnl_percent, timestamp) VALUES ($1, $2, 0, $3)")
+                .bind(trade_id)
+                .bind(pnl)
+                .bind(timestamp)
+                .execute(pool.as_ref())
+                .await
+                .unwrap();
+            sqlx::query("INSERT INTO attributed_trades (trade_id, strategy_id) VALUES ($1, $2)")
+                .bind(trade_id)
+                .bind(strategy_id)
+                .execute(pool.as_ref())
+                .await
+                .unwrap();
+        }
+
+        let calc = MetricsCalculator::new(pool, 0.0);
+        let to = from + Duration::hours(6);
+        let curve = calc.calculate_equity_curve("curve", from, to, Duration::hours(2)).await.unwrap();
+        assert_eq!(curve, MetricsCalculator::equity_curve(from, to, Duration::hours(2), &trades));
+        assert_eq!(curve.iter().map(|p| p.cumulative_pnl).collect::<Vec<_>>(), vec![26.0, 26.0, -1.0, 7.0]);
+    }
+
+    #[test]
+    fn test_rolling_windows_match_manual_slices() {
+        // Two weeks of trades every 6 hours, with nothing on days 5 to 9
+        let from = Utc::now() - Duration::days(30);
+        let to = from + Duration::days(14);
+        let trades: Vec<(DateTime<Utc>, f64, f64)> = (0..14 * 4)
+            .map(|i| {
+                let r = [0.02, -0.01, 0.015, -0.005, 0.01][i % 5];
+                (from + Duration::hours(6 * i as i64), r * 1000.0, r)
+            })
+            .filter(|t| t.0 < from + Duration::days(5) || t.0 >= from + Duration::days(9))
+            .collect();
+
+        let rows = MetricsCalculator::rolling_trades("s", from, to, Duration::days(3), Duration::days(1), &trades, 0.0);
+
+        // Windows end on days 3 through 14
+        assert_eq!(rows.len(), 12);
+        assert_eq!(rows[0].0, from + Duration::days(3));
+        assert_eq!(rows[11].0, to);
+
+        for (end, metrics) in &rows {
+            let start = *end - Duration::days(3);
+            let slice: Vec<_> = trades.iter().copied().filter(|t| t.0 >= start && t.0 < *end).collect();
+            let expected = MetricsCalculator::summarize_trades("s", start, *end, &slice, 0.0);
+            assert_eq!(metrics.period_start, start);
+            assert_eq!(metrics.total_trades, expected.total_trades);
+            assert_eq!(metrics.winning_trades, expected.winning_trades);
+            assert!((metrics.total_pnl - expected.total_pnl).abs() < 1e-9);
+            assert!((metrics.hit_rate - expected.hit_rate).abs() < 1e-9);
+        }
+
+        // The gap shows up as empty windows rather than missing ones
+        assert_eq!(rows[0].1.total_trades, 12);
+        let empty: Vec<_> = rows.iter().filter(|(_, m)| m.total_trades == 0).map(|(end, _)| *end).collect();
+        assert_eq!(empty, vec![from + Duration::days(8), from + Duration::days(9)]);
+    }
+}
@@ -1,34 +1,43 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use common::{Market, MarketEvent, Resolution, ResolutionStatus, Trade};
+use crate::attribution::AttributionEngine;
+use crate::calibration::CalibrationEngine;
+use common::{Market, MarketEvent, MarketId, OutcomeId, RefundPolicy, Resolution, ResolutionStatus, Trade};
 use sqlx::postgres::PgPool;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::{debug, info, warn};
+use sqlx::FromRow;
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 /// Resolution Monitor - Tracks market resolutions and updates trade outcomes
 pub struct ResolutionMonitor {
     db_pool: Arc<PgPool>,
     // In-memory cache of active trades awaiting resolution
-    pending_trades: Arc<RwLock<HashMap<Uuid, Vec<Uuid>>>>, // market_id -> trade_ids
+    pending_trades: Arc<RwLock<HashMap<MarketId, Vec<Uuid>>>>, // market_id -> trade_ids
     // Cache of resolutions
-    resolutions: Arc<RwLock<HashMap<Uuid, Resolution>>>, // market_id -> Resolution
+    resolutions: Arc<RwLock<HashMap<MarketId, Resolution>>>, // market_id -> Resolution
+    // Books compensating entries when a settlement changes
+    attribution: AttributionEngine,
+    // Scores predictions against the winning outcome
+    calibration: CalibrationEngine,
 }
 
 impl ResolutionMonitor {
     pub async fn new(db_pool: Arc<PgPool>) -> Result<Self> {
         Ok(Self {
-            db_pool,
             pending_trades: Arc::new(RwLock::new(HashMap::new())),
             resolutions: Arc::new(RwLock::new(HashMap::new())),
+            attribution: AttributionEngine::new(db_pool.clone()),
+            calibration: CalibrationEngine::new(db_pool.clone()),
+            db_pool,
         })
     }
 
     /// Initialize the resolution tracking tables
     pub async fn initialize(&self) -> Result<()> {
-        sqlx::query(
+        sqlx::raw_sql(
             r#"
             CREATE TABLE IF NOT EXISTS resolutions (
                 market_id UUID PRIMARY KEY,
@@ -42,6 +51,16 @@ impl ResolutionMonitor {
 
             CREATE INDEX IF NOT EXISTS idx_resolutions_status ON resolutions(status);
             CREATE INDEX IF NOT EXISTS idx_resolutions_resolved_at ON resolutions(resolved_at);
+
+            CREATE TABLE IF NOT EXISTS resolution_alerts (
+                id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+                market_id UUID NOT NULL,
+                alert_type TEXT NOT NULL,
+                description TEXT NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_resolution_alerts_market ON resolution_alerts(market_id);
             "#,
         )
         .execute(self.db_pool.as_ref())
@@ -56,27 +75,137 @@ impl ResolutionMonitor {
     pub async fn process_event(&self, event: &MarketEvent) -> Result<()> {
         match event {
             MarketEvent::MarketResolved { market_id, outcome_id } => {
-                self.handle_market_resolution(*market_id, outcome_id.clone()).await?;
+                self.record_resolution(*market_id, outcome_id).await?;
+            }
+            MarketEvent::ResolutionReversed { market_id, previous_outcome } => {
+                self.handle_resolution_reversal(*market_id, previous_outcome).await?;
+            }
+            MarketEvent::MarketInvalidated { market_id, refund_policy } => {
+                self.handle_market_invalidation(*market_id, *refund_policy).await?;
             }
             _ => {}
         }
         Ok(())
     }
 
-    /// Handle a market resolution event
-    async fn handle_market_resolution(&self, market_id: Uuid, outcome_id: String) -> Result<()> {
-        debug!("Market resolved: {} -> {}", market_id, outcome_id);
+    /// Resolve a market: settle its trades, score its predictions against
+    /// the winning outcome and mark its attributed trades resolved
+    ///
+    /// Replaying a resolution books nothing new and updates no predictions.
+    pub async fn record_resolution(&self, market_id: MarketId, winning_outcome_id: &OutcomeId) -> Result<MarketResolutionStats> {
+        debug!("Market resolved: {} -> {}", market_id, winning_outcome_id);
 
         // Create resolution record
         let resolution = Resolution {
             market_id,
-            outcome_id: outcome_id.clone(),
+            outcome_id: winning_outcome_id.clone(),
             status: ResolutionStatus::Resolved,
             resolved_at: Some(Utc::now()),
             resolution_price: None, // Will be set based on outcome
         };
 
-        // Store in database
+        self.store_resolution(&resolution).await?;
+
+        // Update all trades for this market
+        let mut stats = self
+            .update_trades_for_resolution(market_id, winning_outcome_id)
+            .await?;
+        stats.predictions_updated = self
+            .calibration
+            .update_prediction_outcome(market_id, winning_outcome_id.as_str())
+            .await?;
+        stats.attributed_trades_resolved = self.attribution.mark_market_resolved(market_id).await?;
+
+        info!(
+            "Successfully processed resolution for market {}: {} predictions updated",
+            market_id, stats.predictions_updated
+        );
+        Ok(stats)
+    }
+
+    /// Handle an overturned resolution
+    ///
+    /// Every trade booked under the previous outcome gets a compensating
+    /// correction back to zero; a later `MarketResolved` books the new
+    /// outcome on top.
+    async fn handle_resolution_reversal(&self, market_id: MarketId, previous_outcome: &OutcomeId) -> Result<()> {
+        debug!("Resolution reversed: {} was {}", market_id, previous_outcome);
+
+        let resolution = Resolution {
+            market_id,
+            outcome_id: OutcomeId::new(""),
+            status: ResolutionStatus::Disputed,
+            resolved_at: None,
+            resolution_price: None,
+        };
+        self.store_resolution(&resolution).await?;
+        self.calibration.clear_prediction_outcomes(market_id).await?;
+        self.attribution.clear_market_resolved(market_id).await?;
+
+        let mut reversed_pnl = 0.0;
+        let mut corrections = 0;
+        for trade in self.fetch_settled_trades(market_id).await? {
+            let booked = match trade.booked() {
+                Some(booked) => booked,
+                None => continue,
+            };
+
+            if let Booking::Correction { pnl, pnl_percent } = Booking::settle(Some(booked), (0.0, 0.0)) {
+                self.attribution
+                    .record_correction(trade.id, market_id, pnl, pnl_percent, "resolution_reversed")
+                    .await?;
+                reversed_pnl += booked.0;
+                corrections += 1;
+            }
+        }
+
+        self.raise_alert(
+            market_id,
+            "resolution_reversed",
+            &format!(
+                "Resolution to {} reversed: {} trades corrected, ${:.2} P&L reversed",
+                previous_outcome, corrections, reversed_pnl
+            ),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Handle a voided market by settling its trades under the refund policy
+    async fn handle_market_invalidation(&self, market_id: MarketId, refund_policy: RefundPolicy) -> Result<()> {
+        debug!("Market invalidated: {} ({:?})", market_id, refund_policy);
+
+        let resolution = Resolution {
+            market_id,
+            outcome_id: OutcomeId::new(""),
+            status: ResolutionStatus::Cancelled,
+            resolved_at: Some(Utc::now()),
+            resolution_price: None,
+        };
+        self.store_resolution(&resolution).await?;
+
+        let total_pnl = self
+            .settle_trades(market_id, "invalidated", |trade| {
+                Ok(Self::refund_pnl(refund_policy, trade.side, trade.price, 1.0))
+            })
+            .await?;
+        // Invalidated markets have no outcome to score predictions against
+        self.calibration.clear_prediction_outcomes(market_id).await?;
+        self.attribution.mark_market_resolved(market_id).await?;
+
+        self.raise_alert(
+            market_id,
+            "market_invalidated",
+            &format!("Market invalidated with {:?} refund, total P&L: ${:.2}", refund_policy, total_pnl),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Upsert a resolution record and cache it
+    async fn store_resolution(&self, resolution: &Resolution) -> Result<()> {
         sqlx::query(
             r#"
             INSERT INTO resolutions (market_id, outcome_id, status, resolved_at)
@@ -90,79 +219,152 @@ impl ResolutionMonitor {
         )
         .bind(resolution.market_id)
         .bind(&resolution.outcome_id)
-        .bind(&resolution.status)
+        .bind(status_text(resolution.status))
         .bind(resolution.resolved_at)
         .execute(self.db_pool.as_ref())
         .await
         .context("Failed to store resolution")?;
 
         // Update in-memory cache
-        self.resolutions.write().await.insert(market_id, resolution.clone());
+        self.resolutions.write().await.insert(resolution.market_id, resolution.clone());
+        Ok(())
+    }
 
-        // Update all pending trades for this market
-        self.update_trades_for_resolution(market_id, &outcome_id).await?;
+    /// Store and log an alert for a settlement that changed after the fact
+    async fn raise_alert(&self, market_id: MarketId, alert_type: &str, description: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO resolution_alerts (market_id, alert_type, description)
+            VALUES ($1, $2, $3)
+            "#,
+        )
+        .bind(market_id)
+        .bind(alert_type)
+        .bind(description)
+        .execute(self.db_pool.as_ref())
+        .await
+        .context("Failed to store resolution alert")?;
 
-        info!("Successfully processed resolution for market {}", market_id);
+        error!("🚨 RESOLUTION ALERT [{}] market {}: {}", alert_type, market_id, description);
         Ok(())
     }
 
-    /// Update all trades for a resolved market
-    async fn update_trades_for_resolution(&self, market_id: Uuid, winning_outcome: &str) -> Result<()> {
-        // Fetch all trades for this market that haven't been resolved yet
-        let trades = sqlx::query_as::<_, (Uuid, Uuid, String, bool, f64, DateTime<Utc>)>(
+    /// Get alerts raised for a market, oldest first
+    pub async fn get_alerts(&self, market_id: MarketId) -> Result<Vec<ResolutionAlert>> {
+        let alerts = sqlx::query_as::<_, ResolutionAlert>(
             r#"
-            SELECT id, market_id, outcome_id, side, price, timestamp
-            FROM trades
-            WHERE market_id = $1 AND pnl IS NULL
+            SELECT market_id, alert_type, description, created_at
+            FROM resolution_alerts
+            WHERE market_id = $1
+            ORDER BY created_at, id
             "#,
         )
         .bind(market_id)
         .fetch_all(self.db_pool.as_ref())
         .await
-        .context("Failed to fetch trades for resolution")?;
+        .context("Failed to fetch resolution alerts")?;
 
-        let trades_count = trades.len();
-        let mut total_pnl = 0.0;
+        Ok(alerts)
+    }
+
+    /// Update all trades for a resolved market
+    async fn update_trades_for_resolution(&self, market_id: MarketId, winning_outcome: &OutcomeId) -> Result<MarketResolutionStats> {
         let mut winning_count = 0;
         let mut losing_count = 0;
 
-        for (trade_id, _, outcome_id, side, price, timestamp) in trades {
-            let did_win = outcome_id == winning_outcome;
-            let (pnl, pnl_percent) = self.calculate_pnl(did_win, side, price, 1.0)?;
-
-            // Update the trade
-            sqlx::query(
-                r#"
-                UPDATE trades
-                SET pnl = $1, pnl_percent = $2, updated_at = NOW()
-                WHERE id = $3
-                "#,
-            )
-            .bind(pnl)
-            .bind(pnl_percent)
-            .bind(trade_id)
-            .execute(self.db_pool.as_ref())
-            .await
-            .context("Failed to update trade P&L")?;
-
-            total_pnl += pnl;
-            if did_win {
-                winning_count += 1;
-            } else {
-                losing_count += 1;
-            }
-        }
+        let total_pnl = self
+            .settle_trades(market_id, "resolved", |trade| {
+                let did_win = trade.outcome_id == *winning_outcome;
+                if did_win {
+                    winning_count += 1;
+                } else {
+                    losing_count += 1;
+                }
+                self.calculate_pnl(did_win, trade.side, trade.price, 1.0)
+            })
+            .await?;
 
         info!(
             "Resolved {} trades for market {}: {} wins, {} losses, total P&L: ${:.2}",
-            trades_count,
+            winning_count + losing_count,
             market_id,
             winning_count,
             losing_count,
             total_pnl
         );
 
-        Ok(())
+        Ok(MarketResolutionStats {
+            market_id,
+            winning_outcome_id: winning_outcome.clone(),
+            winning_trades: winning_count,
+            losing_trades: losing_count,
+            total_pnl,
+            predictions_updated: 0,
+            attributed_trades_resolved: 0,
+        })
+    }
+
+    /// Book the settlement P&L of every trade in a market
+    ///
+    /// Unsettled trades get their P&L set directly. Trades already booked
+    /// under an earlier settlement get a correction for the difference, so
+    /// booked P&L is never rewritten. Returns the total settled P&L.
+    async fn settle_trades(
+        &self,
+        market_id: MarketId,
+        reason: &str,
+        mut settle: impl FnMut(&SettledTrade) -> Result<(f64, f64)>,
+    ) -> Result<f64> {
+        let mut total_pnl = 0.0;
+
+        for trade in self.fetch_settled_trades(market_id).await? {
+            let target = settle(&trade)?;
+            total_pnl += target.0;
+
+            match Booking::settle(trade.booked(), target) {
+                Booking::Initial { pnl, pnl_percent } => {
+                    sqlx::query(
+                        r#"
+                        UPDATE trades
+                        SET pnl = $1, pnl_percent = $2, updated_at = NOW()
+                        WHERE id = $3
+                        "#,
+                    )
+                    .bind(pnl)
+                    .bind(pnl_percent)
+                    .bind(trade.id)
+                    .execute(self.db_pool.as_ref())
+                    .await
+                    .context("Failed to update trade P&L")?;
+                }
+                Booking::Correction { pnl, pnl_percent } => {
+                    self.attribution
+                        .record_correction(trade.id, market_id, pnl, pnl_percent, reason)
+                        .await?;
+                }
+                Booking::Unchanged => {}
+            }
+        }
+
+        Ok(total_pnl)
+    }
+
+    /// Fetch a market's trades with their P&L net of corrections
+    async fn fetch_settled_trades(&self, market_id: MarketId) -> Result<Vec<SettledTrade>> {
+        let trades = sqlx::query_as::<_, SettledTrade>(
+            r#"
+            SELECT t.id, t.outcome_id, t.side, t.price, p.pnl, p.pnl_percent
+            FROM trades t
+            JOIN trade_pnl p ON p.trade_id = t.id
+            WHERE t.market_id = $1
+            "#,
+        )
+        .bind(market_id)
+        .fetch_all(self.db_pool.as_ref())
+        .await
+        .context("Failed to fetch trades for resolution")?;
+
+        Ok(trades)
     }
 
     /// Calculate P&L for a trade
@@ -201,8 +403,20 @@ impl ResolutionMonitor {
         Ok((pnl, pnl_percent))
     }
 
+    /// Calculate P&L for a trade in an invalidated market
+    fn refund_pnl(policy: RefundPolicy, side: bool, price: f64, size: f64) -> (f64, f64) {
+        let cost = if side { price * size } else { (1.0 - price) * size };
+        let pnl = match policy {
+            RefundPolicy::AtCost => 0.0,
+            RefundPolicy::FiftyFifty => 0.5 * size - cost,
+        };
+        let pnl_percent = if cost > 0.0 { (pnl / cost) * 100.0 } else { 0.0 };
+
+        (pnl, pnl_percent)
+    }
+
     /// Get resolution for a market
-    pub async fn get_resolution(&self, market_id: Uuid) -> Option<Resolution> {
+    pub async fn get_resolution(&self, market_id: MarketId) -> Option<Resolution> {
         // Check cache first
         if let Some(resolution) = self.resolutions.read().await.get(&market_id) {
             return Some(resolution.clone());
@@ -237,10 +451,10 @@ impl ResolutionMonitor {
     }
 
     /// Check for markets that should be resolved but aren't
-    pub async fn check_stale_resolutions(&self, stale_threshold_hours: i64) -> Result<Vec<Uuid>> {
+    pub async fn check_stale_resolutions(&self, stale_threshold_hours: i64) -> Result<Vec<MarketId>> {
         let threshold = Utc::now() - chrono::Duration::hours(stale_threshold_hours);
 
-        let stale_markets = sqlx::query_scalar::<_, Uuid>(
+        let stale_markets = sqlx::query_scalar::<_, MarketId>(
             r#"
             SELECT DISTINCT m.id
             FROM markets m
@@ -262,6 +476,17 @@ impl ResolutionMonitor {
     }
 }
 
+/// How `resolutions.status` spells a status, matching the literals the
+/// queries compare against
+fn status_text(status: ResolutionStatus) -> &'static str {
+    match status {
+        ResolutionStatus::Pending => "Pending",
+        ResolutionStatus::Resolved => "Resolved",
+        ResolutionStatus::Disputed => "Disputed",
+        ResolutionStatus::Cancelled => "Cancelled",
+    }
+}
+
 /// Resolution Tracker - Maintains the lifecycle of market resolutions
 pub struct ResolutionTracker {
     db_pool: Arc<PgPool>,
@@ -273,7 +498,7 @@ impl ResolutionTracker {
     }
 
     /// Track a market as pending resolution
-    pub async fn track_market(&self, market_id: Uuid) -> Result<()> {
+    pub async fn track_market(&self, market_id: MarketId) -> Result<()> {
         sqlx::query(
             r#"
             INSERT INTO resolutions (market_id, outcome_id, status, resolved_at)
@@ -289,6 +514,31 @@ impl ResolutionTracker {
         Ok(())
     }
 
+    /// Close a tracked market's lifecycle with its winning outcome
+    ///
+    /// Returns false if the market was already resolved.
+    pub async fn mark_resolved(&self, market_id: MarketId, outcome_id: &OutcomeId) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO resolutions (market_id, outcome_id, status, resolved_at)
+            VALUES ($1, $2, 'Resolved', NOW())
+            ON CONFLICT (market_id) DO UPDATE
+            SET outcome_id = EXCLUDED.outcome_id,
+                status = EXCLUDED.status,
+                resolved_at = EXCLUDED.resolved_at,
+                updated_at = NOW()
+            WHERE resolutions.status <> 'Resolved'
+            "#,
+        )
+        .bind(market_id)
+        .bind(outcome_id)
+        .execute(self.db_pool.as_ref())
+        .await
+        .context("Failed to mark market resolved")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
     /// Get resolution statistics
     pub async fn get_resolution_stats(&self) -> Result<ResolutionStats> {
         let row = sqlx::query_as::<_, (i64, i64, i64, f64)>(
@@ -314,6 +564,60 @@ impl ResolutionTracker {
     }
 }
 
+/// Alert raised when a settled market's outcome changes
+#[derive(Debug, Clone, FromRow)]
+pub struct ResolutionAlert {
+    pub market_id: MarketId,
+    pub alert_type: String,
+    pub description: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A market's trade with the P&L booked so far
+#[derive(Debug, Clone, FromRow)]
+struct SettledTrade {
+    id: Uuid,
+    outcome_id: OutcomeId,
+    side: bool, // true = Buy, false = Sell
+    price: f64,
+    pnl: Option<f64>,
+    pnl_percent: Option<f64>,
+}
+
+impl SettledTrade {
+    fn booked(&self) -> Option<(f64, f64)> {
+        self.pnl.map(|pnl| (pnl, self.pnl_percent.unwrap_or(0.0)))
+    }
+}
+
+/// How a trade's settlement P&L is written
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Booking {
+    /// First settlement: set the trade's P&L
+    Initial { pnl: f64, pnl_percent: f64 },
+    /// Already booked: append the difference as a correction
+    Correction { pnl: f64, pnl_percent: f64 },
+    Unchanged,
+}
+
+impl Booking {
+    fn settle(booked: Option<(f64, f64)>, target: (f64, f64)) -> Self {
+        match booked {
+            None => Booking::Initial {
+                pnl: target.0,
+                pnl_percent: target.1,
+            },
+            Some((pnl, pnl_percent)) if (target.0 - pnl).abs() < 1e-9 && (target.1 - pnl_percent).abs() < 1e-9 => {
+                Booking::Unchanged
+            }
+            Some((pnl, pnl_percent)) => Booking::Correction {
+                pnl: target.0 - pnl,
+                pnl_percent: target.1 - pnl_percent,
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ResolutionStats {
     pub total_markets: i64,
@@ -321,3 +625,303 @@ pub struct ResolutionStats {
     pub pending_markets: i64,
     pub avg_hours_to_resolve: f64,
 }
+
+/// What resolving a single market changed
+#[derive(Debug, Clone)]
+pub struct MarketResolutionStats {
+    pub market_id: MarketId,
+    pub winning_outcome_id: OutcomeId,
+    /// Trades on the winning outcome
+    pub winning_trades: usize,
+    /// Trades on the other outcomes
+    pub losing_trades: usize,
+    /// Settlement P&L of the market's trades
+    pub total_pnl: f64,
+    /// Predictions given an outcome by this resolution
+    pub predictions_updated: usize,
+    /// Attributed trades marked resolved
+    pub attributed_trades_resolved: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Net booked P&L after applying a booking
+    fn apply(booked: Option<(f64, f64)>, booking: Booking) -> Option<(f64, f64)> {
+        match (booked, booking) {
+            (_, Booking::Initial { pnl, pnl_percent }) => Some((pnl, pnl_percent)),
+            (Some(b), Booking::Correction { pnl, pnl_percent }) => Some((b.0 + pnl, b.1 + pnl_percent)),
+            (booked, _) => booked,
+        }
+    }
+
+    #[test]
+    fn test_booking_resolve_reverse_re_resolve() {
+        // YES bought at 0.4: wins 0.6 (150%), loses 0.4 (-100%)
+        let won = (0.6, 150.0);
+        let lost = (-0.4, -100.0);
+
+        let initial = Booking::settle(None, won);
+        assert_eq!(initial, Booking::Initial { pnl: 0.6, pnl_percent: 150.0 });
+        let booked = apply(None, initial);
+
+        // Replaying the same resolution books nothing
+        assert_eq!(Booking::settle(booked, won), Booking::Unchanged);
+
+        let reversal = Booking::settle(booked, (0.0, 0.0));
+        assert_eq!(reversal, Booking::Correction { pnl: -0.6, pnl_percent: -150.0 });
+        let booked = apply(booked, reversal);
+
+        let re_resolved = Booking::settle(booked, lost);
+        match re_resolved {
+            Booking::Correction { pnl, pnl_percent } => {
+                assert!((pnl + 0.4).abs() < 1e-12);
+                assert!((pnl_percent + 100.0).abs() < 1e-12);
+            }
+            other => panic!("expected a correction, got {:?}", other),
+        }
+        let (pnl, _) = apply(booked, re_resolved).unwrap();
+        assert!((pnl + 0.4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_refund_pnl() {
+        assert_eq!(ResolutionMonitor::refund_pnl(RefundPolicy::AtCost, true, 0.4, 1.0), (0.0, 0.0));
+
+        let (pnl, pnl_percent) = ResolutionMonitor::refund_pnl(RefundPolicy::FiftyFifty, true, 0.4, 1.0);
+        assert!((pnl - 0.1).abs() < 1e-12);
+        assert!((pnl_percent - 25.0).abs() < 1e-9);
+
+        // A sell at 0.4 risked 0.6 and gets 0.5 back
+        let (pnl, _) = ResolutionMonitor::refund_pnl(RefundPolicy::FiftyFifty, false, 0.4, 1.0);
+        assert!((pnl + 0.1).abs() < 1e-12);
+    }
+
+    /// A pool on a fresh schema holding the trade, signal and attribution
+    /// tables the monitors expect to find
+    async fn scratch_pool() -> (Arc<PgPool>, String) {
+        use sqlx::postgres::PgPoolOptions;
+
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL not set");
+        // Single connection so the scratch schema stays on the search path
+        let pool = Arc::new(PgPoolOptions::new().max_connections(1).connect(&url).await.unwrap());
+        let schema = format!("resolution_{}", Uuid::new_v4().simple());
+        sqlx::raw_sql(&format!(
+            r#"
+            CREATE SCHEMA {schema};
+            SET search_path TO {schema};
+            CREATE TABLE trades (
+                id UUID PRIMARY KEY,
+                market_id UUID NOT NULL,
+                outcome_id TEXT NOT NULL,
+                side BOOLEAN NOT NULL,
+                price DOUBLE PRECISION NOT NULL,
+                pnl DOUBLE PRECISION,
+                pnl_percent DOUBLE PRECISION,
+                updated_at TIMESTAMPTZ
+            );
+            CREATE TABLE signals (
+                id UUID PRIMARY KEY,
+                market_id UUID,
+                strategy_id TEXT,
+                agent_id UUID,
+                generated_at TIMESTAMPTZ
+            );
+            CREATE TABLE attributed_trades (
+                trade_id UUID PRIMARY KEY,
+                signal_id UUID,
+                strategy_id TEXT
+            );
+            "#
+        ))
+        .execute(pool.as_ref())
+        .await
+        .unwrap();
+        (pool, schema)
+    }
+
+    async fn drop_schema(pool: &PgPool, schema: &str) {
+        sqlx::raw_sql(&format!("DROP SCHEMA {schema} CASCADE")).execute(pool).await.unwrap();
+    }
+
+    /// Runs against a scratch Postgres: `TEST_DATABASE_URL=... cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_reversal_and_invalidation_postgres() {
+        use crate::calibration::CalibrationEngine;
+
+        let (pool, schema) = scratch_pool().await;
+
+        let attribution = AttributionEngine::new(pool.clone());
+        attribution.initialize().await.unwrap();
+        let monitor = ResolutionMonitor::new(pool.clone()).await.unwrap();
+        monitor.initialize().await.unwrap();
+        let calibration = CalibrationEngine::new(pool.clone());
+        calibration.initialize().await.unwrap();
+
+        let market_id = MarketId::random();
+        let yes_trade = Uuid::new_v4();
+        let no_trade = Uuid::new_v4();
+        for (trade_id, outcome_id, price) in [(yes_trade, "YES", 0.4), (no_trade, "NO", 0.6)] {
+            sqlx::query("INSERT INTO trades (id, market_id, outcome_id, side, price) VALUES ($1, $2, $3, TRUE, $4)")
+                .bind(trade_id)
+                .bind(market_id)
+                .bind(outcome_id)
+                .bind(price)
+                .execute(pool.as_ref())
+                .await
+                .unwrap();
+            calibration
+                .record_prediction(Uuid::new_v4(), Uuid::new_v4(), "test", market_id, outcome_id, 0.5)
+                .await
+                .unwrap();
+        }
+
+        let apply = |event: MarketEvent| {
+            let monitor = &monitor;
+            let calibration = &calibration;
+            async move {
+                monitor.process_event(&event).await.unwrap();
+                calibration.process_event(&event).await.unwrap();
+            }
+        };
+        let net_pnl = |trade_id: Uuid| {
+            let pool = pool.clone();
+            async move {
+                sqlx::query_scalar::<_, Option<f64>>("SELECT pnl FROM trade_pnl WHERE trade_id = $1")
+                    .bind(trade_id)
+                    .fetch_one(pool.as_ref())
+                    .await
+                    .unwrap()
+            }
+        };
+        let outcomes = |market_id: MarketId| {
+            let pool = pool.clone();
+            async move {
+                sqlx::query_as::<_, (String, Option<i32>)>(
+                    "SELECT outcome_id, actual_outcome::INT FROM predictions WHERE market_id = $1 ORDER BY outcome_id",
+                )
+                .bind(market_id)
+                .fetch_all(pool.as_ref())
+                .await
+                .unwrap()
+            }
+        };
+
+        apply(MarketEvent::MarketResolved { market_id, outcome_id: "YES".into() }).await;
+        assert!((net_pnl(yes_trade).await.unwrap() - 0.6).abs() < 1e-9);
+        assert!((net_pnl(no_trade).await.unwrap() + 0.6).abs() < 1e-9);
+        assert!(attribution.get_corrections(market_id).await.unwrap().is_empty());
+        assert_eq!(outcomes(market_id).await, vec![("NO".to_string(), Some(0)), ("YES".to_string(), Some(1))]);
+
+        apply(MarketEvent::ResolutionReversed { market_id, previous_outcome: "YES".into() }).await;
+        let corrections = attribution.get_corrections(market_id).await.unwrap();
+        assert_eq!(corrections.len(), 2);
+        assert!(corrections.iter().all(|c| c.reason == "resolution_reversed"));
+        assert!(net_pnl(yes_trade).await.unwrap().abs() < 1e-9);
+        assert_eq!(outcomes(market_id).await, vec![("NO".to_string(), None), ("YES".to_string(), None)]);
+        assert_eq!(monitor.get_alerts(market_id).await.unwrap()[0].alert_type, "resolution_reversed");
+        assert_eq!(monitor.get_resolution(market_id).await.unwrap().status, ResolutionStatus::Disputed);
+
+        apply(MarketEvent::MarketResolved { market_id, outcome_id: "NO".into() }).await;
+        assert_eq!(attribution.get_corrections(market_id).await.unwrap().len(), 4);
+        assert!((net_pnl(yes_trade).await.unwrap() + 0.4).abs() < 1e-9);
+        assert!((net_pnl(no_trade).await.unwrap() - 0.4).abs() < 1e-9);
+        assert_eq!(outcomes(market_id).await, vec![("NO".to_string(), Some(1)), ("YES".to_string(), Some(0))]);
+
+        // The original booking is never rewritten
+        let booked: f64 = sqlx::query_scalar("SELECT pnl FROM trades WHERE id = $1")
+            .bind(yes_trade)
+            .fetch_one(pool.as_ref())
+            .await
+            .unwrap();
+        assert!((booked - 0.6).abs() < 1e-9);
+
+        // A resolved market voided with a 50/50 refund
+        apply(MarketEvent::MarketInvalidated { market_id, refund_policy: RefundPolicy::FiftyFifty }).await;
+        let corrections = attribution.get_corrections(market_id).await.unwrap();
+        assert_eq!(corrections.len(), 6);
+        assert!(corrections[4..].iter().all(|c| c.reason == "invalidated"));
+        assert!((net_pnl(yes_trade).await.unwrap() - 0.1).abs() < 1e-9);
+        assert!((net_pnl(no_trade).await.unwrap() + 0.1).abs() < 1e-9);
+        assert_eq!(outcomes(market_id).await, vec![("NO".to_string(), None), ("YES".to_string(), None)]);
+        assert_eq!(monitor.get_alerts(market_id).await.unwrap().len(), 2);
+        assert_eq!(monitor.get_resolution(market_id).await.unwrap().status, ResolutionStatus::Cancelled);
+
+        drop_schema(pool.as_ref(), &schema).await;
+    }
+
+    /// Runs against a scratch Postgres: `TEST_DATABASE_URL=... cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_record_resolution_scores_predictions_postgres() {
+        let (pool, schema) = scratch_pool().await;
+
+        let attribution = AttributionEngine::new(pool.clone());
+        attribution.initialize().await.unwrap();
+        let monitor = ResolutionMonitor::new(pool.clone()).await.unwrap();
+        monitor.initialize().await.unwrap();
+        let calibration = CalibrationEngine::new(pool.clone());
+        calibration.initialize().await.unwrap();
+
+        let market_id = MarketId::random();
+        let other_market = MarketId::random();
+        let trade_id = Uuid::new_v4();
+        sqlx::query("INSERT INTO trades (id, market_id, outcome_id, side, price) VALUES ($1, $2, 'YES', TRUE, 0.4)")
+            .bind(trade_id)
+            .bind(market_id)
+            .execute(pool.as_ref())
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO attributed_trades (trade_id, strategy_id) VALUES ($1, 'test')")
+            .bind(trade_id)
+            .execute(pool.as_ref())
+            .await
+            .unwrap();
+        for (market, outcome_id) in [(market_id, "YES"), (market_id, "YES"), (market_id, "NO"), (other_market, "YES")] {
+            calibration
+                .record_prediction(Uuid::new_v4(), Uuid::new_v4(), "test", market, outcome_id, 0.5)
+                .await
+                .unwrap();
+        }
+
+        let stats = monitor.record_resolution(market_id, &"NO".into()).await.unwrap();
+        assert_eq!(stats.predictions_updated, 3);
+        assert_eq!((stats.winning_trades, stats.losing_trades), (0, 1));
+        assert!((stats.total_pnl + 0.4).abs() < 1e-9);
+        assert_eq!(stats.attributed_trades_resolved, 1);
+
+        let outcomes = sqlx::query_as::<_, (MarketId, String, Option<i32>)>(
+            "SELECT market_id, outcome_id, actual_outcome::INT FROM predictions ORDER BY market_id, outcome_id",
+        )
+        .fetch_all(pool.as_ref())
+        .await
+        .unwrap();
+        for (market, outcome_id, actual) in outcomes {
+            let expected = if market != market_id {
+                None
+            } else if outcome_id == "NO" {
+                Some(1)
+            } else {
+                Some(0)
+            };
+            assert_eq!(actual, expected, "{} {}", market, outcome_id);
+        }
+
+        let resolved: Option<DateTime<Utc>> =
+            sqlx::query_scalar("SELECT resolved_at FROM attributed_trades WHERE trade_id = $1")
+                .bind(trade_id)
+                .fetch_one(pool.as_ref())
+                .await
+                .unwrap();
+        assert!(resolved.is_some());
+
+        // Replaying the resolution scores nothing twice
+        let replayed = monitor.record_resolution(market_id, &"NO".into()).await.unwrap();
+        assert_eq!(replayed.predictions_updated, 0);
+        assert!(attribution.get_corrections(market_id).await.unwrap().is_empty());
+
+        drop_schema(pool.as_ref(), &schema).await;
+    }
+}
@@ -0,0 +1,542 @@
+// Resolution Router
+// Maps condition-keyed resolutions onto tracked markets and fans them out, once each
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use crate::calibration::CalibrationEngine;
+use crate::resolution::{ResolutionMonitor, ResolutionTracker};
+use crate::shadow_mode::ShadowMode;
+use common::{ConditionId, Market, MarketEvent, MarketId, OutcomeId};
+use portfolio_risk::{PortfolioHandle, PortfolioRiskManager, ResolutionPnl};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+/// A store that has to hear about every resolution
+#[async_trait]
+pub trait ResolutionSink: Send + Sync {
+    /// Used in logs and summaries
+    fn name(&self) -> &'static str;
+
+    /// Called for every created market, before any resolution of it
+    async fn market_created(&self, _market: &Market) -> Result<()> {
+        Ok(())
+    }
+
+    /// Apply a resolution; Some with the PnL it realized if the sink keeps positions
+    async fn resolve(&self, market_id: MarketId, outcome_id: &OutcomeId) -> Result<Option<ResolutionPnl>>;
+}
+
+#[async_trait]
+impl ResolutionSink for PortfolioHandle {
+    fn name(&self) -> &'static str {
+        "portfolio"
+    }
+
+    async fn resolve(&self, market_id: MarketId, outcome_id: &OutcomeId) -> Result<Option<ResolutionPnl>> {
+        Ok(Some(self.resolve_market(market_id, outcome_id.as_str()).await?))
+    }
+}
+
+/// A manager not running as a `PortfolioService`, e.g. in a backtest
+#[async_trait]
+impl ResolutionSink for Mutex<PortfolioRiskManager> {
+    fn name(&self) -> &'static str {
+        "portfolio"
+    }
+
+    async fn resolve(&self, market_id: MarketId, outcome_id: &OutcomeId) -> Result<Option<ResolutionPnl>> {
+        Ok(Some(self.lock().await.resolve_market(market_id, outcome_id.as_str())?))
+    }
+}
+
+/// Scores predictions only; `ResolutionMonitor` already does this, so
+/// register one or the other
+#[async_trait]
+impl ResolutionSink for CalibrationEngine {
+    fn name(&self) -> &'static str {
+        "calibration"
+    }
+
+    async fn resolve(&self, market_id: MarketId, outcome_id: &OutcomeId) -> Result<Option<ResolutionPnl>> {
+        self.update_prediction_outcome(market_id, outcome_id.as_str()).await?;
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl ResolutionSink for ResolutionMonitor {
+    fn name(&self) -> &'static str {
+        "resolution_monitor"
+    }
+
+    async fn resolve(&self, market_id: MarketId, outcome_id: &OutcomeId) -> Result<Option<ResolutionPnl>> {
+        self.record_resolution(market_id, outcome_id).await?;
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl ResolutionSink for ResolutionTracker {
+    fn name(&self) -> &'static str {
+        "resolution_tracker"
+    }
+
+    async fn market_created(&self, market: &Market) -> Result<()> {
+        self.track_market(market.id).await
+    }
+
+    async fn resolve(&self, market_id: MarketId, outcome_id: &OutcomeId) -> Result<Option<ResolutionPnl>> {
+        self.mark_resolved(market_id, outcome_id).await?;
+        Ok(None)
+    }
+}
+
+/// Closes paper positions; their P&L is hypothetical, so none is reported
+#[async_trait]
+impl ResolutionSink for ShadowMode {
+    fn name(&self) -> &'static str {
+        "shadow_mode"
+    }
+
+    async fn resolve(&self, market_id: MarketId, outcome_id: &OutcomeId) -> Result<Option<ResolutionPnl>> {
+        self.update_shadow_outcomes(market_id, outcome_id).await?;
+        Ok(None)
+    }
+}
+
+/// Configuration for `ResolutionRouter`
+#[derive(Debug, Clone)]
+pub struct ResolutionRouterConfig {
+    /// How long a resolution for a condition with no market yet is kept
+    /// waiting for the market to be created
+    pub retry_window: Duration,
+}
+
+impl Default for ResolutionRouterConfig {
+    fn default() -> Self {
+        Self {
+            retry_window: Duration::hours(1),
+        }
+    }
+}
+
+/// Published once every sink has applied a resolution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolutionSummary {
+    pub condition_id: ConditionId,
+    pub market_id: MarketId,
+    pub outcome_id: OutcomeId,
+    /// Summed over sinks that keep positions
+    pub realized_pnl: f64,
+    pub pnl_by_strategy: HashMap<String, f64>,
+    pub sinks: Vec<String>,
+    pub resolved_at: DateTime<Utc>,
+}
+
+/// A resolution routed to a market, with the sinks that have applied it
+#[derive(Debug, Clone)]
+struct RoutedResolution {
+    condition_id: ConditionId,
+    outcome_id: OutcomeId,
+    applied: Vec<bool>,
+    pnl: ResolutionPnl,
+    summarized: bool,
+}
+
+/// A resolution that arrived before its market
+#[derive(Debug, Clone)]
+struct ParkedResolution {
+    outcome_id: OutcomeId,
+    parked_at: DateTime<Utc>,
+}
+
+/// Routes `ConditionResolved` events from market-events to the stores
+/// tracking the market
+///
+/// The feed reports resolutions by condition id while every store is keyed
+/// by our market id, so the router learns the mapping from `MarketCreated`.
+/// Each sink applies a resolution once: replays are skipped, and a sink
+/// that fails is retried by `retry_pending` without reapplying it to the
+/// others. Resolutions for conditions not yet created are parked for
+/// `retry_window`.
+pub struct ResolutionRouter {
+    config: ResolutionRouterConfig,
+    sinks: Vec<Arc<dyn ResolutionSink>>,
+    markets: HashMap<ConditionId, MarketId>,
+    resolutions: HashMap<MarketId, RoutedResolution>,
+    parked: HashMap<ConditionId, ParkedResolution>,
+}
+
+impl ResolutionRouter {
+    pub fn new(config: ResolutionRouterConfig) -> Self {
+        Self {
+            config,
+            sinks: Vec::new(),
+            markets: HashMap::new(),
+            resolutions: HashMap::new(),
+            parked: HashMap::new(),
+        }
+    }
+
+    /// Fan resolutions out to another store
+    pub fn with_sink(mut self, sink: Arc<dyn ResolutionSink>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// Market created for a condition, if seen
+    pub fn market_for(&self, condition_id: &ConditionId) -> Option<MarketId> {
+        self.markets.get(condition_id).copied()
+    }
+
+    /// Conditions resolved before their market was created
+    pub fn parked(&self) -> Vec<ConditionId> {
+        self.parked.keys().cloned().collect()
+    }
+
+    /// Consume a market event, returning a summary if it completed a resolution
+    pub async fn handle_event(&mut self, event: &MarketEvent) -> Option<ResolutionSummary> {
+        match event {
+            MarketEvent::MarketCreated(market) => {
+                self.markets.insert(market.condition_id.clone(), market.id);
+                for sink in &self.sinks {
+                    if let Err(e) = sink.market_created(market).await {
+                        warn!("{} failed to track market {}: {}", sink.name(), market.id, e);
+                    }
+                }
+
+                let parked = self.parked.remove(&market.condition_id)?;
+                debug!("Routing parked resolution of {} to market {}", market.condition_id, market.id);
+                self.route(market.condition_id.clone(), market.id, parked.outcome_id).await
+            }
+            MarketEvent::ConditionResolved { condition_id, outcome_id } => match self.market_for(condition_id) {
+                Some(market_id) => self.route(condition_id.clone(), market_id, outcome_id.clone()).await,
+                None => {
+                    debug!("Parking resolution of unknown condition {}", condition_id);
+                    // A re-sent resolution keeps its original deadline
+                    let parked = self.parked.entry(condition_id.clone()).or_insert_with(|| ParkedResolution {
+                        outcome_id: outcome_id.clone(),
+                        parked_at: Utc::now(),
+                    });
+                    parked.outcome_id = outcome_id.clone();
+                    None
+                }
+            },
+            // A later resolution is a new one to apply
+            MarketEvent::ResolutionReversed { market_id, .. } => {
+                self.resolutions.remove(market_id);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Retry sinks that failed to apply a resolution
+    pub async fn retry_pending(&mut self) -> Vec<ResolutionSummary> {
+        let pending: Vec<MarketId> = self
+            .resolutions
+            .iter()
+            .filter(|(_, routed)| !routed.summarized)
+            .map(|(market_id, _)| *market_id)
+            .collect();
+
+        let mut summaries = Vec::new();
+        for market_id in pending {
+            summaries.extend(self.apply(market_id).await);
+        }
+        summaries
+    }
+
+    /// Drop parked resolutions whose market never showed up
+    pub fn expire_parked(&mut self, now: DateTime<Utc>) -> Vec<ConditionId> {
+        let window = self.config.retry_window;
+        let expired: Vec<ConditionId> = self
+            .parked
+            .iter()
+            .filter(|(_, parked)| now - parked.parked_at > window)
+            .map(|(condition_id, _)| condition_id.clone())
+            .collect();
+
+        for condition_id in &expired {
+            let parked = self.parked.remove(condition_id).unwrap();
+            warn!(
+                "Dropping resolution of {} to {}: no market created within {}s",
+                condition_id,
+                parked.outcome_id,
+                window.num_seconds()
+            );
+        }
+        expired
+    }
+
+    async fn route(
+        &mut self,
+        condition_id: ConditionId,
+        market_id: MarketId,
+        outcome_id: OutcomeId,
+    ) -> Option<ResolutionSummary> {
+        if let Some(routed) = self.resolutions.get(&market_id) {
+            if routed.outcome_id != outcome_id {
+                warn!(
+                    "Ignoring resolution of market {} to {}: already resolved to {}",
+                    market_id, outcome_id, routed.outcome_id
+                );
+            } else {
+                debug!("Skipping replayed resolution of market {}", market_id);
+            }
+            return None;
+        }
+
+        self.resolutions.insert(
+            market_id,
+            RoutedResolution {
+                condition_id,
+                outcome_id,
+                applied: vec![false; self.sinks.len()],
+                pnl: ResolutionPnl::default(),
+                summarized: false,
+            },
+        );
+        self.apply(market_id).await
+    }
+
+    /// Apply a routed resolution to the sinks that have not yet
+    async fn apply(&mut self, market_id: MarketId) -> Option<ResolutionSummary> {
+        let routed = self.resolutions.get_mut(&market_id)?;
+        for (sink, applied) in self.sinks.iter().zip(routed.applied.iter_mut()) {
+            if *applied {
+                continue;
+            }
+            match sink.resolve(market_id, &routed.outcome_id).await {
+                Ok(pnl) => {
+                    *applied = true;
+                    if let Some(pnl) = pnl {
+                        routed.pnl.total += pnl.total;
+                        for (strategy_id, strategy_pnl) in pnl.by_strategy {
+                            *routed.pnl.by_strategy.entry(strategy_id).or_insert(0.0) += strategy_pnl;
+                        }
+                    }
+                }
+                Err(e) => warn!("{} failed to resolve market {}: {}", sink.name(), market_id, e),
+            }
+        }
+
+        if routed.summarized || !routed.applied.iter().all(|applied| *applied) {
+            return None;
+        }
+        routed.summarized = true;
+
+        info!(
+            market_id = %market_id,
+            condition_id = %routed.condition_id,
+            outcome_id = %routed.outcome_id,
+            realized_pnl = routed.pnl.total,
+            "Resolution applied"
+        );
+        Some(ResolutionSummary {
+            condition_id: routed.condition_id.clone(),
+            market_id,
+            outcome_id: routed.outcome_id.clone(),
+            realized_pnl: routed.pnl.total,
+            pnl_by_strategy: routed.pnl.by_strategy.clone(),
+            sinks: self.sinks.iter().map(|sink| sink.name().to_string()).collect(),
+            resolved_at: Utc::now(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{OrderSide, Outcome, Trade, Uuid};
+    use portfolio_risk::{CapitalConfig, RiskConfig};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Stands in for a Postgres-backed store, counting what reaches it
+    #[derive(Default)]
+    struct RecordingSink {
+        created: AtomicUsize,
+        resolved: std::sync::Mutex<Vec<(MarketId, OutcomeId)>>,
+        failures: AtomicUsize,
+    }
+
+    impl RecordingSink {
+        fn failing(times: usize) -> Self {
+            Self {
+                failures: AtomicUsize::new(times),
+                ..Self::default()
+            }
+        }
+
+        fn resolved(&self) -> Vec<(MarketId, OutcomeId)> {
+            self.resolved.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl ResolutionSink for RecordingSink {
+        fn name(&self) -> &'static str {
+            "recording"
+        }
+
+        async fn market_created(&self, _market: &Market) -> Result<()> {
+            self.created.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        async fn resolve(&self, market_id: MarketId, outcome_id: &OutcomeId) -> Result<Option<ResolutionPnl>> {
+            if self.failures.load(Ordering::SeqCst) > 0 {
+                self.failures.fetch_sub(1, Ordering::SeqCst);
+                anyhow::bail!("store unavailable");
+            }
+            self.resolved.lock().unwrap().push((market_id, outcome_id.clone()));
+            Ok(None)
+        }
+    }
+
+    fn market(condition_id: &str) -> Market {
+        let outcome = |name: &str| Outcome {
+            id: name.into(),
+            name: name.to_string(),
+            price: 0.5,
+            liquidity: 0.0,
+        };
+        Market {
+            id: MarketId::random(),
+            condition_id: condition_id.into(),
+            question: "Will it happen?".to_string(),
+            description: String::new(),
+            category: "politics".to_string(),
+            outcomes: vec![outcome("YES"), outcome("NO")],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            neg_risk_group: None,
+            tick_size: None,
+        }
+    }
+
+    fn buy(market_id: MarketId, outcome_id: &str, price: f64, size: f64) -> Trade {
+        Trade {
+            id: Uuid::new_v4(),
+            market_id,
+            outcome_id: outcome_id.into(),
+            price,
+            size,
+            side: OrderSide::Buy,
+            timestamp: Utc::now(),
+        }
+    }
+
+    fn resolved(condition_id: &str, outcome_id: &str) -> MarketEvent {
+        MarketEvent::ConditionResolved {
+            condition_id: condition_id.into(),
+            outcome_id: outcome_id.into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_trade_resolve_applies_once_per_store() {
+        let config = RiskConfig {
+            capital: CapitalConfig {
+                total_capital: 1_000.0,
+                budgets: HashMap::from([("momentum".to_string(), 200.0), ("contrarian".to_string(), 200.0)]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let portfolio = Arc::new(Mutex::new(PortfolioRiskManager::with_config(config).unwrap()));
+        let calibration = Arc::new(RecordingSink::default());
+        let monitor = Arc::new(RecordingSink::default());
+        let mut router = ResolutionRouter::new(ResolutionRouterConfig::default())
+            .with_sink(portfolio.clone())
+            .with_sink(calibration.clone())
+            .with_sink(monitor.clone());
+
+        let market = market("0xabc");
+        assert!(router.handle_event(&MarketEvent::MarketCreated(market.clone())).await.is_none());
+        {
+            let mut manager = portfolio.lock().await;
+            manager.process_strategy_trade("momentum", &buy(market.id, "YES", 0.4, 100.0)).unwrap();
+            manager.process_strategy_trade("contrarian", &buy(market.id, "NO", 0.6, 50.0)).unwrap();
+        }
+
+        let summary = router.handle_event(&resolved("0xabc", "YES")).await.unwrap();
+        assert_eq!(summary.market_id, market.id);
+        assert_eq!(summary.outcome_id, OutcomeId::from("YES"));
+        assert!((summary.realized_pnl - 30.0).abs() < 1e-9);
+        assert!((summary.pnl_by_strategy["momentum"] - 60.0).abs() < 1e-9);
+        assert!((summary.pnl_by_strategy["contrarian"] + 30.0).abs() < 1e-9);
+        assert_eq!(summary.sinks, ["portfolio", "recording", "recording"]);
+
+        // A replay, and a conflicting outcome, change nothing
+        assert!(router.handle_event(&resolved("0xabc", "YES")).await.is_none());
+        assert!(router.handle_event(&resolved("0xabc", "NO")).await.is_none());
+        assert!(router.retry_pending().await.is_empty());
+
+        for sink in [&calibration, &monitor] {
+            assert_eq!(sink.created.load(Ordering::SeqCst), 1);
+            assert_eq!(sink.resolved(), [(market.id, OutcomeId::from("YES"))]);
+        }
+        let manager = portfolio.lock().await;
+        assert!((manager.portfolio().total_pnl() - 30.0).abs() < 1e-9);
+        assert_eq!(manager.portfolio().positions().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_sink_retried_without_reapplying_others() {
+        let healthy = Arc::new(RecordingSink::default());
+        let flaky = Arc::new(RecordingSink::failing(1));
+        let mut router = ResolutionRouter::new(ResolutionRouterConfig::default())
+            .with_sink(healthy.clone())
+            .with_sink(flaky.clone());
+
+        let market = market("0xdef");
+        router.handle_event(&MarketEvent::MarketCreated(market.clone())).await;
+        assert!(router.handle_event(&resolved("0xdef", "NO")).await.is_none());
+        assert_eq!(healthy.resolved().len(), 1);
+        assert!(flaky.resolved().is_empty());
+
+        let summaries = router.retry_pending().await;
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(healthy.resolved().len(), 1);
+        assert_eq!(flaky.resolved(), [(market.id, OutcomeId::from("NO"))]);
+        assert!(router.retry_pending().await.is_empty());
+
+        // After a reversal the next resolution applies again
+        router
+            .handle_event(&MarketEvent::ResolutionReversed { market_id: market.id, previous_outcome: "NO".into() })
+            .await;
+        assert!(router.handle_event(&resolved("0xdef", "YES")).await.is_some());
+        assert_eq!(healthy.resolved().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_condition_parked_until_created_or_expired() {
+        let sink = Arc::new(RecordingSink::default());
+        let mut router = ResolutionRouter::new(ResolutionRouterConfig::default()).with_sink(sink.clone());
+
+        assert!(router.handle_event(&resolved("0x111", "YES")).await.is_none());
+        assert!(router.handle_event(&resolved("0x222", "NO")).await.is_none());
+        assert!(sink.resolved().is_empty());
+        assert_eq!(router.parked().len(), 2);
+
+        // Still within the window when its market arrives
+        assert!(router.expire_parked(Utc::now()).is_empty());
+        let market = market("0x111");
+        let summary = router.handle_event(&MarketEvent::MarketCreated(market.clone())).await.unwrap();
+        assert_eq!(summary.market_id, market.id);
+        assert_eq!(sink.resolved(), [(market.id, OutcomeId::from("YES"))]);
+
+        let expired = router.expire_parked(Utc::now() + Duration::hours(2));
+        assert_eq!(expired, [ConditionId::from("0x222")]);
+        assert!(router.parked().is_empty());
+
+        // Created after expiry: nothing left to apply
+        assert!(router.handle_event(&MarketEvent::MarketCreated(self::market("0x222"))).await.is_none());
+        assert_eq!(sink.resolved().len(), 1);
+    }
+}
@@ -1,24 +1,59 @@
 use anyhow::{Context, Result};
-use chrono::{DateTime, Utc};
-use common::{OrderSide, ShadowTrade};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use crate::ab_testing::{sample_mean_variance, welch_t_test};
+use common::{BookFill, CostModel, DriftSeverity, MarketId, OrderBook, OutcomeId, OrderSide, ShadowTrade, Signal};
 use sqlx::postgres::PgPool;
+use statrs::distribution::{ContinuousCDF, Normal};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::{info, debug};
 use uuid::Uuid;
 
+/// Thresholds for `ShadowMode::divergence_alert`
+#[derive(Debug, Clone)]
+pub struct DivergenceConfig {
+    /// p-value below which a difference counts as significant
+    pub significance: f64,
+    /// Minimum |Cohen's h| between shadow and real hit rates
+    pub min_hit_rate_effect: f64,
+    /// Minimum |Cohen's d| between shadow and real P&L per trade
+    pub min_pnl_effect: f64,
+}
+
+impl Default for DivergenceConfig {
+    fn default() -> Self {
+        Self {
+            significance: 0.05,
+            min_hit_rate_effect: 0.2,
+            min_pnl_effect: 0.2,
+        }
+    }
+}
+
 /// Shadow Mode - Paper trading for testing new strategies without real money
 pub struct ShadowMode {
     db_pool: Arc<PgPool>,
+    divergence_config: DivergenceConfig,
 }
 
 impl ShadowMode {
     pub fn new(db_pool: Arc<PgPool>) -> Self {
-        Self { db_pool }
+        Self {
+            db_pool,
+            divergence_config: DivergenceConfig::default(),
+        }
+    }
+
+    pub fn with_divergence_config(mut self, config: DivergenceConfig) -> Self {
+        self.divergence_config = config;
+        self
     }
 
     /// Initialize shadow mode tables
     pub async fn initialize(&self) -> Result<()> {
-        sqlx::query(
+        // Several statements, so unprepared
+        sqlx::raw_sql(
             r#"
             CREATE TABLE IF NOT EXISTS shadow_trades (
                 id UUID PRIMARY KEY,
@@ -32,8 +67,26 @@ impl ShadowMode {
                 strategy_id TEXT NOT NULL,
                 hypothetical_pnl NUMERIC(15, 4),
                 would_have_won BOOLEAN,
+                reference_price DOUBLE PRECISION,
+                target_price DOUBLE PRECISION,
+                stop_loss DOUBLE PRECISION,
+                mark_price DOUBLE PRECISION,
+                unrealized_pnl DOUBLE PRECISION,
+                marked_at TIMESTAMPTZ,
+                exit_price DOUBLE PRECISION,
+                exit_reason TEXT,
+                closed_at TIMESTAMPTZ,
                 created_at TIMESTAMPTZ DEFAULT NOW()
             );
+            ALTER TABLE shadow_trades ADD COLUMN IF NOT EXISTS reference_price DOUBLE PRECISION;
+            ALTER TABLE shadow_trades ADD COLUMN IF NOT EXISTS target_price DOUBLE PRECISION;
+            ALTER TABLE shadow_trades ADD COLUMN IF NOT EXISTS stop_loss DOUBLE PRECISION;
+            ALTER TABLE shadow_trades ADD COLUMN IF NOT EXISTS mark_price DOUBLE PRECISION;
+            ALTER TABLE shadow_trades ADD COLUMN IF NOT EXISTS unrealized_pnl DOUBLE PRECISION;
+            ALTER TABLE shadow_trades ADD COLUMN IF NOT EXISTS marked_at TIMESTAMPTZ;
+            ALTER TABLE shadow_trades ADD COLUMN IF NOT EXISTS exit_price DOUBLE PRECISION;
+            ALTER TABLE shadow_trades ADD COLUMN IF NOT EXISTS exit_reason TEXT;
+            ALTER TABLE shadow_trades ADD COLUMN IF NOT EXISTS closed_at TIMESTAMPTZ;
 
             CREATE INDEX IF NOT EXISTS idx_shadow_strategy ON shadow_trades(strategy_id);
             CREATE INDEX IF NOT EXISTS idx_shadow_market ON shadow_trades(market_id);
@@ -54,9 +107,10 @@ impl ShadowMode {
             r#"
             INSERT INTO shadow_trades (
                 id, trade_id, market_id, outcome_id, side, price, size,
-                timestamp, strategy_id, hypothetical_pnl, would_have_won
+                timestamp, strategy_id, hypothetical_pnl, would_have_won, reference_price,
+                target_price, stop_loss
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
             "#,
         )
         .bind(trade.id)
@@ -73,6 +127,9 @@ impl ShadowMode {
         .bind(&trade.strategy_id)
         .bind(trade.hypothetical_pnl)
         .bind(trade.would_have_won)
+        .bind(trade.reference_price)
+        .bind(trade.target_price)
+        .bind(trade.stop_loss)
         .execute(self.db_pool.as_ref())
         .await
         .context("Failed to record shadow trade")?;
@@ -81,6 +138,18 @@ impl ShadowMode {
         Ok(())
     }
 
+    /// Record that a shadow trade was also executed for real as `trade_id`,
+    /// so the comparison can set their fills side by side
+    pub async fn link_real_trade(&self, shadow_trade_id: Uuid, trade_id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE shadow_trades SET trade_id = $2 WHERE id = $1")
+            .bind(shadow_trade_id)
+            .bind(trade_id)
+            .execute(self.db_pool.as_ref())
+            .await
+            .context("Failed to link shadow trade to real trade")?;
+        Ok(())
+    }
+
     /// Get all shadow trades for a strategy
     pub async fn get_strategy_shadow_trades(
         &self,
@@ -92,7 +161,8 @@ impl ShadowMode {
             SELECT
                 id, trade_id, market_id, outcome_id, side,
                 price, size, timestamp, strategy_id,
-                hypothetical_pnl, would_have_won
+                hypothetical_pnl, would_have_won, reference_price,
+                target_price, stop_loss
             FROM shadow_trades
             WHERE strategy_id = $1
             ORDER BY timestamp DESC
@@ -108,29 +178,38 @@ impl ShadowMode {
         Ok(trades)
     }
 
-    /// Update shadow trade outcomes after market resolution
+    /// Close the open shadow positions in a resolved market, paying 1.0 per
+    /// share of the winning outcome and 0.0 for the others
+    ///
+    /// A position counts as won if it made money, so a sell of the losing
+    /// outcome wins.
     pub async fn update_shadow_outcomes(
         &self,
-        market_id: Uuid,
-        winning_outcome: &str,
+        market_id: MarketId,
+        winning_outcome: &OutcomeId,
     ) -> Result<usize> {
         let rows = sqlx::query(
             r#"
-            UPDATE shadow_trades
-            SET would_have_won = CASE WHEN outcome_id = $2 THEN TRUE ELSE FALSE END,
-                hypothetical_pnl = CASE
-                    WHEN outcome_id = $2 THEN
-                        CASE
-                            WHEN side = 'Buy' THEN (1.0 - price) * size
-                            ELSE price * size
-                        END
-                    ELSE
-                        CASE
-                            WHEN side = 'Buy' THEN -price * size
-                            ELSE (1.0 - price) * size
-                        END
-                END
-            WHERE market_id = $1 AND hypothetical_pnl IS NULL
+            UPDATE shadow_trades t
+            SET hypothetical_pnl = s.pnl,
+                would_have_won = s.pnl > 0,
+                mark_price = s.payout,
+                unrealized_pnl = NULL,
+                marked_at = NOW(),
+                exit_price = s.payout,
+                exit_reason = 'resolved',
+                closed_at = NOW()
+            FROM (
+                SELECT id, payout,
+                    (CASE WHEN side = 'Buy' THEN payout - price ELSE price - payout END) * size AS pnl
+                FROM (
+                    SELECT id, side, price::DOUBLE PRECISION AS price, size::DOUBLE PRECISION AS size,
+                        CASE WHEN outcome_id = $2 THEN 1.0 ELSE 0.0 END::DOUBLE PRECISION AS payout
+                    FROM shadow_trades
+                    WHERE market_id = $1 AND hypothetical_pnl IS NULL
+                ) open
+            ) s
+            WHERE t.id = s.id
             "#,
         )
         .bind(market_id)
@@ -143,6 +222,95 @@ impl ShadowMode {
         Ok(rows.rows_affected() as usize)
     }
 
+    /// Mark the open shadow positions in an outcome to `price`, then close
+    /// those whose target or stop it reaches; call it with every price update
+    ///
+    /// Returns how many positions were closed.
+    pub async fn mark_to_market(&self, market_id: MarketId, outcome_id: &OutcomeId, price: f64) -> Result<usize> {
+        let marked = sqlx::query_as::<_, (Uuid, String, f64, f64, Option<f64>, Option<f64>)>(
+            r#"
+            UPDATE shadow_trades
+            SET mark_price = $3,
+                unrealized_pnl = CASE WHEN side = 'Buy' THEN $3 - price::DOUBLE PRECISION
+                    ELSE price::DOUBLE PRECISION - $3 END * size::DOUBLE PRECISION,
+                marked_at = NOW()
+            WHERE market_id = $1 AND outcome_id = $2 AND hypothetical_pnl IS NULL
+            RETURNING id, side, price::DOUBLE PRECISION, size::DOUBLE PRECISION, target_price, stop_loss
+            "#,
+        )
+        .bind(market_id)
+        .bind(outcome_id)
+        .bind(price)
+        .fetch_all(self.db_pool.as_ref())
+        .await
+        .context("Failed to mark shadow positions")?;
+
+        let mut closed = 0;
+        for (id, side, entry, size, target_price, stop_loss) in marked {
+            let side = if side == "Buy" { OrderSide::Buy } else { OrderSide::Sell };
+            let exits = ExitRules { target_price, stop_loss };
+            if let Some(reason) = exits.triggered(side, price) {
+                self.close_position(id, price, paper_pnl(side, entry, price, size), reason).await?;
+                closed += 1;
+            }
+        }
+
+        if closed > 0 {
+            info!("Closed {} shadow positions in market {} at {:.4}", closed, market_id, price);
+        }
+        Ok(closed)
+    }
+
+    /// Close shadow positions opened more than `max_age` ago at their last
+    /// mark, or at their entry price if they were never marked
+    pub async fn expire_stale_positions(&self, max_age: Duration) -> Result<usize> {
+        let rows = sqlx::query(
+            r#"
+            UPDATE shadow_trades
+            SET hypothetical_pnl = COALESCE(unrealized_pnl, 0.0),
+                would_have_won = COALESCE(unrealized_pnl, 0.0) > 0,
+                exit_price = COALESCE(mark_price, price::DOUBLE PRECISION),
+                exit_reason = $2,
+                closed_at = NOW()
+            WHERE hypothetical_pnl IS NULL AND timestamp < $1
+            "#,
+        )
+        .bind(Utc::now() - max_age)
+        .bind(PaperExit::Expired.as_str())
+        .execute(self.db_pool.as_ref())
+        .await
+        .context("Failed to expire shadow positions")?;
+
+        if rows.rows_affected() > 0 {
+            info!("Expired {} stale shadow positions", rows.rows_affected());
+        }
+        Ok(rows.rows_affected() as usize)
+    }
+
+    async fn close_position(&self, id: Uuid, exit_price: f64, pnl: f64, reason: PaperExit) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE shadow_trades
+            SET hypothetical_pnl = $3,
+                would_have_won = $3 > 0,
+                exit_price = $2,
+                exit_reason = $4,
+                closed_at = NOW()
+            WHERE id = $1 AND hypothetical_pnl IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(exit_price)
+        .bind(pnl)
+        .bind(reason.as_str())
+        .execute(self.db_pool.as_ref())
+        .await
+        .context("Failed to close shadow position")?;
+
+        debug!("Closed shadow position {} at {:.4} ({}), pnl {:.4}", id, exit_price, reason.as_str(), pnl);
+        Ok(())
+    }
+
     /// Calculate shadow mode performance
     pub async fn calculate_shadow_performance(
         &self,
@@ -154,8 +322,8 @@ impl ShadowMode {
                 COUNT(*) as total_trades,
                 COUNT(*) FILTER (WHERE would_have_won = TRUE) as winning_trades,
                 COUNT(*) FILTER (WHERE would_have_won = FALSE) as losing_trades,
-                COALESCE(SUM(hypothetical_pnl), 0.0) as total_pnl,
-                COALESCE(AVG(hypothetical_pnl) FILTER (WHERE hypothetical_pnl > 0), 0.0) as avg_win
+                COALESCE(SUM(hypothetical_pnl), 0.0)::DOUBLE PRECISION as total_pnl,
+                COALESCE(AVG(hypothetical_pnl) FILTER (WHERE hypothetical_pnl > 0), 0.0)::DOUBLE PRECISION as avg_win
             FROM shadow_trades
             WHERE strategy_id = $1
             AND hypothetical_pnl IS NOT NULL
@@ -174,7 +342,7 @@ impl ShadowMode {
 
         let avg_loss = sqlx::query_scalar(
             r#"
-            SELECT COALESCE(AVG(ABS(hypothetical_pnl)), 0.0)
+            SELECT COALESCE(AVG(ABS(hypothetical_pnl)), 0.0)::DOUBLE PRECISION
             FROM shadow_trades
             WHERE strategy_id = $1 AND hypothetical_pnl < 0
             "#,
@@ -183,6 +351,21 @@ impl ShadowMode {
         .fetch_one(self.db_pool.as_ref())
         .await?;
 
+        let open = sqlx::query_as::<_, (i64, f64, f64)>(
+            r#"
+            SELECT
+                COUNT(*),
+                COALESCE(SUM(unrealized_pnl), 0.0)::DOUBLE PRECISION,
+                COALESCE(AVG(EXTRACT(EPOCH FROM NOW() - timestamp)), 0.0)::DOUBLE PRECISION / 3600.0
+            FROM shadow_trades
+            WHERE strategy_id = $1 AND hypothetical_pnl IS NULL
+            "#,
+        )
+        .bind(strategy_id)
+        .fetch_one(self.db_pool.as_ref())
+        .await
+        .context("Failed to summarise open shadow positions")?;
+
         Ok(ShadowPerformance {
             strategy_id: strategy_id.to_string(),
             total_trades: row.0,
@@ -192,56 +375,731 @@ impl ShadowMode {
             total_pnl: row.3,
             avg_win: row.4,
             avg_loss,
+            unrealized_pnl: open.1,
+            open_positions: open.0,
+            avg_open_age_hours: open.2,
+        })
+    }
+
+    /// Alert if a strategy's resolved shadow trades no longer behave like
+    /// its real ones, None while the difference is within noise
+    pub async fn divergence_alert(&self, strategy_id: &str) -> Result<Option<DivergenceAlert>> {
+        let shadow = sqlx::query_as::<_, (bool, f64)>(
+            r#"
+            SELECT COALESCE(would_have_won, hypothetical_pnl > 0), hypothetical_pnl::DOUBLE PRECISION
+            FROM shadow_trades
+            WHERE strategy_id = $1 AND hypothetical_pnl IS NOT NULL
+            "#,
+        )
+        .bind(strategy_id)
+        .fetch_all(self.db_pool.as_ref())
+        .await
+        .context("Failed to fetch resolved shadow trades")?;
+
+        let real = sqlx::query_as::<_, (bool, f64)>(
+            r#"
+            SELECT t.pnl > 0, t.pnl::DOUBLE PRECISION
+            FROM trades t
+            JOIN attributed_trades at ON t.id = at.trade_id
+            WHERE at.strategy_id = $1 AND t.pnl IS NOT NULL
+            "#,
+        )
+        .bind(strategy_id)
+        .fetch_all(self.db_pool.as_ref())
+        .await
+        .context("Failed to fetch resolved real trades")?;
+
+        Ok(Self::detect_divergence(strategy_id, &shadow, &real, &self.divergence_config))
+    }
+
+    /// Compare shadow and real trades, each given as (won, pnl)
+    ///
+    /// The hit rates are compared with a two-proportion z-test and the P&L
+    /// per trade with Welch's t-test. A test diverges when its p-value is
+    /// below `significance` and its effect size reaches the configured
+    /// minimum; an alert is returned if either does.
+    pub fn detect_divergence(
+        strategy_id: &str,
+        shadow: &[(bool, f64)],
+        real: &[(bool, f64)],
+        config: &DivergenceConfig,
+    ) -> Option<DivergenceAlert> {
+        let hit_rate = two_proportion_test(shadow, real).map(|test| test.judged(config.min_hit_rate_effect, config));
+
+        let shadow_pnl: Vec<f64> = shadow.iter().map(|(_, pnl)| *pnl).collect();
+        let real_pnl: Vec<f64> = real.iter().map(|(_, pnl)| *pnl).collect();
+        let pnl_per_trade = welch_t_test(&shadow_pnl, &real_pnl).map(|t| {
+            let (shadow_mean, shadow_var) = sample_mean_variance(&shadow_pnl);
+            let (real_mean, real_var) = sample_mean_variance(&real_pnl);
+            let (n_shadow, n_real) = (shadow_pnl.len() as f64, real_pnl.len() as f64);
+            let pooled_sd =
+                (((n_shadow - 1.0) * shadow_var + (n_real - 1.0) * real_var) / (n_shadow + n_real - 2.0)).sqrt();
+            DivergenceTest {
+                shadow: shadow_mean,
+                real: real_mean,
+                statistic: t.t_stat,
+                p_value: t.p_value,
+                effect_size: (shadow_mean - real_mean) / pooled_sd,
+                diverged: false,
+            }
+            .judged(config.min_pnl_effect, config)
+        });
+
+        let diverged: Vec<&DivergenceTest> = [&hit_rate, &pnl_per_trade]
+            .into_iter()
+            .flatten()
+            .filter(|test| test.diverged)
+            .collect();
+        let largest_effect = diverged.iter().map(|test| test.effect_size.abs()).reduce(f64::max)?;
+
+        // Cohen's conventions: 0.5 is a medium effect, 0.8 a large one
+        let severity = match (largest_effect, diverged.len()) {
+            (e, 2) if e >= 0.8 => DriftSeverity::Critical,
+            (e, _) if e >= 0.8 => DriftSeverity::High,
+            (e, 2) if e >= 0.5 => DriftSeverity::High,
+            (e, _) if e >= 0.5 => DriftSeverity::Medium,
+            (_, 2) => DriftSeverity::Medium,
+            _ => DriftSeverity::Low,
+        };
+
+        Some(DivergenceAlert {
+            strategy_id: strategy_id.to_string(),
+            severity,
+            shadow_trades: shadow.len(),
+            real_trades: real.len(),
+            hit_rate,
+            pnl_per_trade,
+            detected_at: Utc::now(),
         })
     }
 }
 
+/// Two-proportion z-test on the win rates of two samples, with Cohen's h
+/// as effect size. None if either sample is empty or the pooled rate is 0 or 1.
+fn two_proportion_test(shadow: &[(bool, f64)], real: &[(bool, f64)]) -> Option<DivergenceTest> {
+    if shadow.is_empty() || real.is_empty() {
+        return None;
+    }
+
+    let wins = |trades: &[(bool, f64)]| trades.iter().filter(|(won, _)| *won).count() as f64;
+    let (n_shadow, n_real) = (shadow.len() as f64, real.len() as f64);
+    let p_shadow = wins(shadow) / n_shadow;
+    let p_real = wins(real) / n_real;
+
+    let pooled = (wins(shadow) + wins(real)) / (n_shadow + n_real);
+    let std_err = (pooled * (1.0 - pooled) * (1.0 / n_shadow + 1.0 / n_real)).sqrt();
+    if std_err == 0.0 {
+        return None;
+    }
+
+    let z = (p_shadow - p_real) / std_err;
+    let normal = Normal::new(0.0, 1.0).ok()?;
+    let p_value = (2.0 * (1.0 - normal.cdf(z.abs()))).clamp(0.0, 1.0);
+
+    Some(DivergenceTest {
+        shadow: p_shadow * 100.0,
+        real: p_real * 100.0,
+        statistic: z,
+        p_value,
+        effect_size: 2.0 * p_shadow.sqrt().asin() - 2.0 * p_real.sqrt().asin(),
+        diverged: false,
+    })
+}
+
+/// One shadow-vs-real comparison within a `DivergenceAlert`
+#[derive(Debug, Clone)]
+pub struct DivergenceTest {
+    /// Shadow value (hit rate in percent, or mean P&L per trade)
+    pub shadow: f64,
+    pub real: f64,
+    /// z for the hit rate, t for the P&L
+    pub statistic: f64,
+    /// Two-sided p-value
+    pub p_value: f64,
+    /// Cohen's h for the hit rate, Cohen's d for the P&L
+    pub effect_size: f64,
+    /// Significant and at least the configured effect size
+    pub diverged: bool,
+}
+
+impl DivergenceTest {
+    fn judged(mut self, min_effect: f64, config: &DivergenceConfig) -> Self {
+        self.diverged = self.p_value < config.significance && self.effect_size.abs() >= min_effect;
+        self
+    }
+}
+
+/// Shadow performance that has stopped predicting real performance
+#[derive(Debug, Clone)]
+pub struct DivergenceAlert {
+    pub strategy_id: String,
+    pub severity: DriftSeverity,
+    pub shadow_trades: usize,
+    pub real_trades: usize,
+    /// None if either side has no trades or all trades share one outcome
+    pub hit_rate: Option<DivergenceTest>,
+    /// None if either side has fewer than two trades or no variance
+    pub pnl_per_trade: Option<DivergenceTest>,
+    pub detected_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ShadowPerformance {
     pub strategy_id: String,
+    /// Closed positions; the hit rate and P&L figures count only these
     pub total_trades: i64,
     pub winning_trades: i64,
     pub losing_trades: i64,
     pub hit_rate: f64,
+    /// Realized P&L of closed positions
     pub total_pnl: f64,
     pub avg_win: f64,
     pub avg_loss: f64,
+    /// P&L of open positions at their last mark
+    pub unrealized_pnl: f64,
+    pub open_positions: i64,
+    /// Mean time since the open positions were opened
+    pub avg_open_age_hours: f64,
+}
+
+/// Early exits for a paper position, copied from the signal that opened it
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ExitRules {
+    /// Close for a profit once the price reaches this
+    pub target_price: Option<f64>,
+    /// Close to cut the loss once the price reaches this
+    pub stop_loss: Option<f64>,
+}
+
+impl ExitRules {
+    /// The `target_price` and `stop_loss` numbers in a signal's metadata,
+    /// as signal generation stores them; either may be missing
+    pub fn from_signal(signal: &Signal) -> Self {
+        Self {
+            target_price: signal.metadata.get("target_price").and_then(|v| v.as_f64()),
+            stop_loss: signal.metadata.get("stop_loss").and_then(|v| v.as_f64()),
+        }
+    }
+
+    /// Which rule, if any, a price of `price` triggers for a position on `side`;
+    /// targets sit above a buy's entry and below a sell's
+    fn triggered(&self, side: OrderSide, price: f64) -> Option<PaperExit> {
+        let (reached_target, reached_stop) = match side {
+            OrderSide::Buy => (self.target_price.is_some_and(|t| price >= t), self.stop_loss.is_some_and(|s| price <= s)),
+            OrderSide::Sell => (self.target_price.is_some_and(|t| price <= t), self.stop_loss.is_some_and(|s| price >= s)),
+        };
+        if reached_target {
+            Some(PaperExit::TargetHit)
+        } else if reached_stop {
+            Some(PaperExit::StopLoss)
+        } else {
+            None
+        }
+    }
+}
+
+/// Why a paper position was closed, as stored in `exit_reason`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PaperExit {
+    TargetHit,
+    StopLoss,
+    Expired,
+}
+
+impl PaperExit {
+    fn as_str(self) -> &'static str {
+        match self {
+            PaperExit::TargetHit => "target_hit",
+            PaperExit::StopLoss => "stop_loss",
+            PaperExit::Expired => "expired",
+        }
+    }
+}
+
+/// P&L of `size` shares bought (or sold) at `entry` and closed at `exit`
+fn paper_pnl(side: OrderSide, entry: f64, exit: f64, size: f64) -> f64 {
+    match side {
+        OrderSide::Buy => (exit - entry) * size,
+        OrderSide::Sell => (entry - exit) * size,
+    }
+}
+
+/// Price open positions in an outcome are marked at: the midpoint when the
+/// book has both sides, otherwise whichever side it has
+fn book_mark_price(book: &OrderBook, outcome_id: &OutcomeId) -> Option<f64> {
+    match (best_quote(book, outcome_id, OrderSide::Sell), best_quote(book, outcome_id, OrderSide::Buy)) {
+        (Some(bid), Some(ask)) => Some((bid + ask) / 2.0),
+        (bid, ask) => bid.or(ask),
+    }
+}
+
+/// Price a paper fill of `size` pays per share, fees included: walked through
+/// the outcome's levels when there is a book, `price` moved by the fallback
+/// slippage when there is not
+fn paper_fill_price(
+    cost_model: &CostModel,
+    outcome_id: &OutcomeId,
+    side: OrderSide,
+    price: f64,
+    size: f64,
+    order_book: Option<&OrderBook>,
+) -> f64 {
+    let levels: Vec<(f64, f64)> = order_book
+        .map(|book| book_side(book, outcome_id, side))
+        .unwrap_or_default();
+    cost_model.fill(side, &levels, price, size).effective_price(side)
+}
+
+/// The outcome's levels a trade on `side` takes from: asks for a buy, bids for a sell
+fn book_side(book: &OrderBook, outcome_id: &OutcomeId, side: OrderSide) -> Vec<(f64, f64)> {
+    let orders = match side {
+        OrderSide::Buy => &book.asks,
+        OrderSide::Sell => &book.bids,
+    };
+    orders
+        .iter()
+        .filter(|o| o.outcome_id == *outcome_id && o.size > 0.0)
+        .map(|o| (o.price, o.size))
+        .collect()
+}
+
+/// Best price on the side a trade takes from, None if that side is empty
+fn best_quote(book: &OrderBook, outcome_id: &OutcomeId, side: OrderSide) -> Option<f64> {
+    let prices = book_side(book, outcome_id, side).into_iter().map(|(price, _)| price);
+    match side {
+        OrderSide::Buy => prices.reduce(f64::min),
+        OrderSide::Sell => prices.reduce(f64::max),
+    }
+}
+
+/// Whether `price` is at `limit` or better for a trade on `side`
+fn within_limit(side: OrderSide, price: f64, limit: f64) -> bool {
+    match side {
+        OrderSide::Buy => price <= limit,
+        OrderSide::Sell => price >= limit,
+    }
+}
+
+/// Latest market data for paper fills, e.g. the timeseries store's newest
+/// order book snapshot
+#[async_trait]
+pub trait MarketDataSource: Send + Sync {
+    /// Most recent order book for the market, None if there is none
+    async fn latest_order_book(&self, market_id: MarketId) -> Result<Option<OrderBook>>;
+}
+
+/// The newest order book seen for each market, fed from the market data feed
+#[derive(Debug, Default)]
+pub struct LatestOrderBooks {
+    books: std::sync::RwLock<HashMap<MarketId, OrderBook>>,
+}
+
+impl LatestOrderBooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep `book` unless a newer one for its market is already held
+    pub fn update(&self, book: OrderBook) {
+        let mut books = self.books.write().unwrap_or_else(|e| e.into_inner());
+        match books.get(&book.market_id) {
+            Some(held) if held.timestamp > book.timestamp => {}
+            _ => {
+                books.insert(book.market_id, book);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl MarketDataSource for LatestOrderBooks {
+    async fn latest_order_book(&self, market_id: MarketId) -> Result<Option<OrderBook>> {
+        Ok(self.books.read().unwrap_or_else(|e| e.into_inner()).get(&market_id).cloned())
+    }
+}
+
+/// What a paper trade does with size the book can't fill when it arrives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnfilledPolicy {
+    /// Rest it as a limit order at the trade's price until later books cross it
+    #[default]
+    Rest,
+    /// Cancel it, like an immediate-or-cancel order
+    Cancel,
+}
+
+/// Unfilled remainder of a paper trade, waiting at its limit price
+#[derive(Debug, Clone)]
+pub struct RestingPaperOrder {
+    pub id: Uuid,
+    pub market_id: MarketId,
+    pub outcome_id: OutcomeId,
+    pub side: OrderSide,
+    pub limit_price: f64,
+    pub remaining: f64,
+    pub strategy_id: String,
+    /// Best quote on the taking side when the trade arrived
+    pub reference_price: f64,
+    pub placed_at: DateTime<Utc>,
+    /// Time of the newest book already matched against; books this old or
+    /// older can't fill the order again
+    pub last_book: DateTime<Utc>,
+    /// Passed on to the positions its fills open
+    pub exits: ExitRules,
+}
+
+/// Fill as much of `size` as `book` offers at `limit` or better
+///
+/// None when nothing crosses. The fill walks the crossing levels best first,
+/// so it never needs the cost model's fallback slippage.
+fn fill_on_arrival(
+    cost_model: &CostModel,
+    book: &OrderBook,
+    outcome_id: &OutcomeId,
+    side: OrderSide,
+    limit: f64,
+    size: f64,
+) -> Option<BookFill> {
+    let levels: Vec<(f64, f64)> = book_side(book, outcome_id, side)
+        .into_iter()
+        .filter(|&(price, _)| within_limit(side, price, limit))
+        .collect();
+    let take = size.min(levels.iter().map(|(_, size)| size).sum());
+    if take <= 0.0 {
+        return None;
+    }
+    Some(cost_model.fill(side, &levels, limit, take))
+}
+
+/// Fill resting orders that `book` crosses, oldest first, dropping those
+/// filled in full
+///
+/// A crossed order fills at its limit price, as a resting order would. Size
+/// taken by one order isn't available to later ones on the same outcome and
+/// side, even past their limit, so fills err on the small side.
+fn fill_resting_orders(
+    cost_model: &CostModel,
+    resting: &mut Vec<RestingPaperOrder>,
+    book: &OrderBook,
+) -> Vec<(RestingPaperOrder, BookFill)> {
+    let mut taken: HashMap<(OutcomeId, bool), f64> = HashMap::new();
+    let mut fills = Vec::new();
+
+    for order in resting.iter_mut().filter(|o| o.market_id == book.market_id) {
+        if book.timestamp <= order.last_book {
+            continue;
+        }
+        order.last_book = book.timestamp;
+
+        let crossing: f64 = book_side(book, &order.outcome_id, order.side)
+            .into_iter()
+            .filter(|&(price, _)| within_limit(order.side, price, order.limit_price))
+            .map(|(_, size)| size)
+            .sum();
+        let already_taken = taken.entry((order.outcome_id.clone(), order.side == OrderSide::Buy)).or_default();
+        let take = order.remaining.min(crossing - *already_taken);
+        if take <= 0.0 {
+            continue;
+        }
+
+        *already_taken += take;
+        order.remaining -= take;
+        let fill = cost_model.fill(order.side, &[(order.limit_price, take)], order.limit_price, take);
+        fills.push((order.clone(), fill));
+    }
+
+    resting.retain(|o| o.remaining > 1e-9);
+    fills
+}
+
+/// Slippage of a fill against the quote at arrival, in bps; positive is worse
+fn slippage_bps(side: OrderSide, reference_price: f64, price: f64) -> f64 {
+    let difference = match side {
+        OrderSide::Buy => price - reference_price,
+        OrderSide::Sell => reference_price - price,
+    };
+    difference / reference_price * 10_000.0
+}
+
+/// Mean slippage in bps over (side, reference price, fill price) rows, None
+/// when there are none
+fn average_slippage_bps(fills: &[(String, f64, f64)]) -> Option<f64> {
+    let slippages: Vec<f64> = fills
+        .iter()
+        .filter(|(_, reference, _)| *reference > 0.0)
+        .map(|(side, reference, price)| {
+            let side = if side == "Sell" { OrderSide::Sell } else { OrderSide::Buy };
+            slippage_bps(side, *reference, *price)
+        })
+        .collect();
+    if slippages.is_empty() {
+        return None;
+    }
+    Some(slippages.iter().sum::<f64>() / slippages.len() as f64)
+}
+
+/// Price (fee included), size and arrival quote of one paper fill
+#[derive(Debug, Clone, Copy)]
+struct PaperFill {
+    price: f64,
+    size: f64,
+    reference_price: f64,
+}
+
+impl PaperFill {
+    fn new(price: f64, size: f64, reference_price: f64) -> Self {
+        Self { price, size, reference_price }
+    }
+}
+
+/// A paper trade on its way to the book
+struct PaperOrder<'a> {
+    market_id: MarketId,
+    outcome_id: &'a OutcomeId,
+    side: OrderSide,
+    price: f64,
+    size: f64,
+    strategy_id: &'a str,
+    exits: ExitRules,
+}
+
+/// What became of a paper trade when it arrived
+#[derive(Debug, Clone)]
+pub struct PaperExecution {
+    /// The part filled straight away, None if nothing crossed
+    pub fill: Option<ShadowTrade>,
+    /// Size left once the arrival fill is done
+    pub unfilled: f64,
+    /// Resting order holding `unfilled`; None if it was cancelled or nothing is left
+    pub resting_order_id: Option<Uuid>,
 }
 
 /// Paper Trader - Executes trades in shadow mode
 pub struct PaperTrader {
     db_pool: Arc<PgPool>,
     shadow_mode: ShadowMode,
+    cost_model: CostModel,
+    market_data: Option<Arc<dyn MarketDataSource>>,
+    unfilled_policy: UnfilledPolicy,
+    resting: std::sync::Mutex<Vec<RestingPaperOrder>>,
 }
 
 impl PaperTrader {
     pub fn new(db_pool: Arc<PgPool>) -> Self {
         let shadow_mode = ShadowMode::new(db_pool.clone());
-        Self { db_pool, shadow_mode }
+        Self {
+            db_pool,
+            shadow_mode,
+            cost_model: CostModel::default(),
+            market_data: None,
+            unfilled_policy: UnfilledPolicy::default(),
+            resting: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Charge fees and slippage on paper fills
+    pub fn with_cost_model(mut self, cost_model: CostModel) -> Self {
+        self.cost_model = cost_model;
+        self
+    }
+
+    /// Fill paper trades against the latest order book from `source`
+    pub fn with_market_data(mut self, source: Arc<dyn MarketDataSource>) -> Self {
+        self.market_data = Some(source);
+        self
+    }
+
+    /// Rest or cancel the part of a paper trade the book can't fill
+    pub fn with_unfilled_policy(mut self, policy: UnfilledPolicy) -> Self {
+        self.unfilled_policy = policy;
+        self
     }
 
     /// Execute a paper trade
+    ///
+    /// With market data and a book for the market, `price` is a limit: the
+    /// trade walks the outcome's levels at that price or better, and whatever
+    /// they can't fill rests or is cancelled per the unfilled policy. Without
+    /// a book the whole size fills at `price` moved by the fallback slippage.
+    /// Recorded prices have the taker fee folded in.
     pub async fn execute_paper_trade(
         &self,
-        market_id: Uuid,
-        outcome_id: &str,
+        market_id: MarketId,
+        outcome_id: &OutcomeId,
+        side: OrderSide,
+        price: f64,
+        size: f64,
+        strategy_id: &str,
+    ) -> Result<PaperExecution> {
+        let order = PaperOrder { market_id, outcome_id, side, price, size, strategy_id, exits: ExitRules::default() };
+        self.execute(order).await
+    }
+
+    /// Execute a paper trade of `size` at `price` in the signal's outcome and
+    /// direction, closing early on the target and stop in its metadata
+    pub async fn execute_signal(&self, signal: &Signal, price: f64, size: f64) -> Result<PaperExecution> {
+        let order = PaperOrder {
+            market_id: signal.market_id,
+            outcome_id: &signal.outcome_id,
+            side: signal.direction,
+            price,
+            size,
+            strategy_id: &signal.strategy_id,
+            exits: ExitRules::from_signal(signal),
+        };
+        self.execute(order).await
+    }
+
+    async fn execute(&self, order: PaperOrder<'_>) -> Result<PaperExecution> {
+        let PaperOrder { market_id, outcome_id, side, price, size, strategy_id, exits } = order;
+        let book = match &self.market_data {
+            Some(source) => source.latest_order_book(market_id).await?,
+            None => None,
+        };
+
+        let Some(book) = book else {
+            let fill_price = paper_fill_price(&self.cost_model, outcome_id, side, price, size, None);
+            let trade =
+                self.record_paper_trade(market_id, outcome_id, side, strategy_id, PaperFill::new(fill_price, size, price), exits)
+                    .await?;
+            return Ok(PaperExecution { fill: Some(trade), unfilled: 0.0, resting_order_id: None });
+        };
+
+        let reference_price = best_quote(&book, outcome_id, side).unwrap_or(price);
+        let arrival = fill_on_arrival(&self.cost_model, &book, outcome_id, side, price, size);
+        let fill = match arrival {
+            Some(fill) => Some(
+                self.record_paper_trade(
+                    market_id,
+                    outcome_id,
+                    side,
+                    strategy_id,
+                    PaperFill::new(fill.effective_price(side), fill.size, reference_price),
+                    exits,
+                )
+                .await?,
+            ),
+            None => None,
+        };
+
+        let unfilled = size - arrival.map_or(0.0, |fill| fill.size);
+        if unfilled <= 1e-9 {
+            return Ok(PaperExecution { fill, unfilled: 0.0, resting_order_id: None });
+        }
+
+        let resting_order_id = match self.unfilled_policy {
+            UnfilledPolicy::Rest => {
+                let order = RestingPaperOrder {
+                    id: Uuid::new_v4(),
+                    market_id: book.market_id,
+                    outcome_id: outcome_id.clone(),
+                    side,
+                    limit_price: price,
+                    remaining: unfilled,
+                    strategy_id: strategy_id.to_string(),
+                    reference_price,
+                    placed_at: Utc::now(),
+                    last_book: book.timestamp,
+                    exits,
+                };
+                debug!("Resting {:.2} of paper trade for strategy {} at {:.4}", unfilled, strategy_id, price);
+                let id = order.id;
+                self.resting.lock().unwrap_or_else(|e| e.into_inner()).push(order);
+                Some(id)
+            }
+            UnfilledPolicy::Cancel => {
+                debug!("Cancelled {:.2} of paper trade for strategy {} the book couldn't fill", unfilled, strategy_id);
+                None
+            }
+        };
+
+        Ok(PaperExecution { fill, unfilled, resting_order_id })
+    }
+
+    /// Execute a paper trade that walks the outcome's levels in `order_book`
+    /// on the side it takes, rather than filling the whole size at `price`
+    ///
+    /// `price` is only used for size the book cannot fill.
+    pub async fn execute_paper_trade_on_book(
+        &self,
+        order_book: &OrderBook,
+        outcome_id: &OutcomeId,
         side: OrderSide,
         price: f64,
         size: f64,
         strategy_id: &str,
+    ) -> Result<ShadowTrade> {
+        let fill_price = paper_fill_price(&self.cost_model, outcome_id, side, price, size, Some(order_book));
+        let market_id = order_book.market_id;
+        let reference_price = best_quote(order_book, outcome_id, side).unwrap_or(price);
+        let fill = PaperFill::new(fill_price, size, reference_price);
+        self.record_paper_trade(market_id, outcome_id, side, strategy_id, fill, ExitRules::default()).await
+    }
+
+    /// Fill resting paper orders that a new book for their market crosses,
+    /// then mark the market's open positions to it; call it with every book
+    /// the market data feed delivers
+    pub async fn process_tick(&self, book: &OrderBook) -> Result<Vec<ShadowTrade>> {
+        let fills = {
+            let mut resting = self.resting.lock().unwrap_or_else(|e| e.into_inner());
+            fill_resting_orders(&self.cost_model, &mut resting, book)
+        };
+
+        let mut trades = Vec::with_capacity(fills.len());
+        for (order, fill) in fills {
+            trades.push(
+                self.record_paper_trade(
+                    order.market_id,
+                    &order.outcome_id,
+                    order.side,
+                    &order.strategy_id,
+                    PaperFill::new(fill.effective_price(order.side), fill.size, order.reference_price),
+                    order.exits,
+                )
+                .await?,
+            );
+        }
+
+        let mut outcomes: Vec<&OutcomeId> = book.bids.iter().chain(&book.asks).map(|o| &o.outcome_id).collect();
+        outcomes.sort_unstable();
+        outcomes.dedup();
+        for outcome_id in outcomes {
+            if let Some(price) = book_mark_price(book, outcome_id) {
+                self.shadow_mode.mark_to_market(book.market_id, outcome_id, price).await?;
+            }
+        }
+        Ok(trades)
+    }
+
+    /// Paper orders still waiting for the book to cross them, oldest first
+    pub fn resting_orders(&self) -> Vec<RestingPaperOrder> {
+        self.resting.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    async fn record_paper_trade(
+        &self,
+        market_id: MarketId,
+        outcome_id: &OutcomeId,
+        side: OrderSide,
+        strategy_id: &str,
+        fill: PaperFill,
+        exits: ExitRules,
     ) -> Result<ShadowTrade> {
         let shadow_trade = ShadowTrade {
             id: Uuid::new_v4(),
             trade_id: None, // No real trade executed
             market_id,
-            outcome_id: outcome_id.to_string(),
+            outcome_id: outcome_id.clone(),
             side,
-            price,
-            size,
+            price: fill.price,
+            size: fill.size,
             timestamp: Utc::now(),
             strategy_id: strategy_id.to_string(),
             hypothetical_pnl: None, // Will be calculated on resolution
             would_have_won: None,
+            reference_price: Some(fill.reference_price),
+            target_price: exits.target_price,
+            stop_loss: exits.stop_loss,
         };
 
         self.shadow_mode.record_shadow_trade(&shadow_trade).await?;
@@ -259,9 +1117,9 @@ impl PaperTrader {
             r#"
             SELECT
                 COUNT(*) as total_trades,
-                COUNT(*) FILTER (WHERE pnl > 0) as winning_trades,
-                COUNT(*) FILTER (WHERE pnl < 0) as losing_trades,
-                COALESCE(SUM(pnl), 0.0) as total_pnl
+                COUNT(*) FILTER (WHERE t.pnl > 0) as winning_trades,
+                COUNT(*) FILTER (WHERE t.pnl < 0) as losing_trades,
+                COALESCE(SUM(t.pnl), 0.0)::DOUBLE PRECISION as total_pnl
             FROM trades t
             JOIN attributed_trades at ON t.id = at.trade_id
             WHERE at.strategy_id = $1
@@ -278,6 +1136,36 @@ impl PaperTrader {
             0.0
         };
 
+        // Slippage from the quote at arrival: every shadow fill, and the real
+        // trades that shadow fills were linked to
+        let shadow_fills = sqlx::query_as::<_, (String, f64, f64)>(
+            r#"
+            SELECT side, reference_price, price::DOUBLE PRECISION
+            FROM shadow_trades
+            WHERE strategy_id = $1 AND reference_price IS NOT NULL
+            "#,
+        )
+        .bind(strategy_id)
+        .fetch_all(self.db_pool.as_ref())
+        .await
+        .context("Failed to fetch shadow fills")?;
+
+        let real_fills = sqlx::query_as::<_, (String, f64, f64)>(
+            r#"
+            SELECT s.side, s.reference_price, t.price::DOUBLE PRECISION
+            FROM shadow_trades s
+            JOIN trades t ON t.id = s.trade_id
+            WHERE s.strategy_id = $1 AND s.reference_price IS NOT NULL
+            "#,
+        )
+        .bind(strategy_id)
+        .fetch_all(self.db_pool.as_ref())
+        .await
+        .context("Failed to fetch linked real fills")?;
+
+        let shadow_avg_slippage_bps = average_slippage_bps(&shadow_fills);
+        let real_avg_slippage_bps = average_slippage_bps(&real_fills);
+
         // Calculate differences before moving shadow_perf
         let hit_rate_diff = shadow_perf.hit_rate - real_hit_rate;
         let pnl_diff = shadow_perf.total_pnl - real_row.3;
@@ -290,6 +1178,9 @@ impl PaperTrader {
             real_pnl: real_row.3,
             hit_rate_diff,
             pnl_diff,
+            shadow_avg_slippage_bps,
+            real_avg_slippage_bps,
+            slippage_diff_bps: shadow_avg_slippage_bps.zip(real_avg_slippage_bps).map(|(s, r)| s - r),
         })
     }
 }
@@ -303,4 +1194,451 @@ pub struct ShadowRealComparison {
     pub real_pnl: f64,
     pub hit_rate_diff: f64,
     pub pnl_diff: f64,
+    /// Mean slippage of shadow fills from the quote at arrival, in bps
+    /// (positive is worse); None without any recorded
+    pub shadow_avg_slippage_bps: Option<f64>,
+    /// The same for real trades linked to shadow fills with `link_real_trade`
+    pub real_avg_slippage_bps: Option<f64>,
+    /// Shadow minus real; negative when paper fills look better than live ones
+    pub slippage_diff_bps: Option<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::Order;
+
+    /// `n` trades winning `win` with the given share, losing `loss` otherwise
+    fn trades(n: usize, win_share: f64, win: f64, loss: f64) -> Vec<(bool, f64)> {
+        let wins = (n as f64 * win_share).round() as usize;
+        (0..n)
+            .map(|i| {
+                // Spread the P&L a little so neither side has zero variance
+                let jitter = (i % 5) as f64 * 0.5;
+                if i < wins {
+                    (true, win + jitter)
+                } else {
+                    (false, -loss - jitter)
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_matching_shadow_and_real_raise_no_alert() {
+        let shadow = trades(80, 0.55, 10.0, 9.0);
+        let real = trades(70, 0.53, 9.5, 9.0);
+
+        let alert = ShadowMode::detect_divergence("s", &shadow, &real, &DivergenceConfig::default());
+        assert!(alert.is_none());
+    }
+
+    #[test]
+    fn test_diverging_shadow_and_real_raise_alert() {
+        // Paper wins far more often than live fills do
+        let shadow = trades(80, 0.65, 10.0, 9.0);
+        let real = trades(80, 0.35, 10.0, 9.0);
+
+        let alert = ShadowMode::detect_divergence("s", &shadow, &real, &DivergenceConfig::default()).unwrap();
+        assert_eq!(alert.strategy_id, "s");
+        assert_eq!((alert.shadow_trades, alert.real_trades), (80, 80));
+
+        let hit_rate = alert.hit_rate.as_ref().unwrap();
+        assert!(hit_rate.diverged);
+        assert!((hit_rate.shadow - 65.0).abs() < 1e-9);
+        assert!((hit_rate.real - 35.0).abs() < 1e-9);
+        // z = 0.3 / sqrt(0.25 * 2 / 80), h = 2 asin(sqrt .65) - 2 asin(sqrt .35)
+        assert!((hit_rate.statistic - 3.794733).abs() < 1e-5);
+        assert!((hit_rate.effect_size - 0.609385).abs() < 1e-5);
+        assert!(hit_rate.p_value < 0.001);
+
+        let pnl = alert.pnl_per_trade.as_ref().unwrap();
+        assert!(pnl.diverged);
+        assert!(pnl.shadow > pnl.real);
+        assert_eq!(alert.severity, DriftSeverity::High);
+
+        // A high enough effect threshold silences the same data
+        let strict = DivergenceConfig {
+            min_hit_rate_effect: 2.0,
+            min_pnl_effect: 2.0,
+            ..DivergenceConfig::default()
+        };
+        assert!(ShadowMode::detect_divergence("s", &shadow, &real, &strict).is_none());
+    }
+
+    fn order(outcome_id: &str, price: f64, size: f64) -> Order {
+        Order { outcome_id: outcome_id.into(), price, size }
+    }
+
+    /// YES asks 400 @ 0.50, 400 @ 0.51, 1000 @ 0.53
+    fn fixture_book(market_id: MarketId, at: DateTime<Utc>) -> OrderBook {
+        OrderBook {
+            market_id,
+            timestamp: at,
+            bids: vec![order("YES", 0.49, 400.0)],
+            asks: vec![order("YES", 0.50, 400.0), order("YES", 0.51, 400.0), order("YES", 0.53, 1000.0)],
+            sequence: 1,
+        }
+    }
+
+    fn resting_buy(market_id: MarketId, limit_price: f64, remaining: f64, last_book: DateTime<Utc>) -> RestingPaperOrder {
+        RestingPaperOrder {
+            id: Uuid::new_v4(),
+            market_id,
+            outcome_id: "YES".into(),
+            side: OrderSide::Buy,
+            limit_price,
+            remaining,
+            strategy_id: "s".to_string(),
+            reference_price: 0.50,
+            placed_at: last_book,
+            last_book,
+            exits: ExitRules::default(),
+        }
+    }
+
+    #[test]
+    fn test_paper_fill_walks_the_book() {
+        let book = OrderBook {
+            market_id: MarketId::random(),
+            timestamp: Utc::now(),
+            bids: vec![order("YES", 0.49, 400.0)],
+            // The NO level is another outcome's and is skipped
+            asks: vec![
+                order("YES", 0.50, 400.0),
+                order("NO", 0.45, 5000.0),
+                order("YES", 0.51, 400.0),
+                order("YES", 0.53, 1000.0),
+            ],
+            sequence: 0,
+        };
+        let model = CostModel::new().with_taker_fee_bps(20.0).with_fallback_slippage_bps(50.0);
+
+        // 400 @ 0.50, 400 @ 0.51, 200 @ 0.53 = 0.51, plus 20bps
+        let price = paper_fill_price(&model, &"YES".into(), OrderSide::Buy, 0.50, 1000.0, Some(&book));
+        assert!((price - 0.51102).abs() < 1e-9);
+
+        // No book: the quote less 50bps, less the fee
+        let price = paper_fill_price(&model, &"YES".into(), OrderSide::Sell, 0.60, 100.0, None);
+        assert!((price - 0.597 * 0.998).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_partial_fill_rests_and_fills_on_later_tick() {
+        let market_id = MarketId::random();
+        let now = Utc::now();
+        let book = fixture_book(market_id, now);
+        let model = CostModel::new().with_taker_fee_bps(20.0);
+
+        // 1000 at a 0.51 limit: 400 @ 0.50 and 400 @ 0.51, the 0.53 level is past it
+        let fill = fill_on_arrival(&model, &book, &"YES".into(), OrderSide::Buy, 0.51, 1000.0).unwrap();
+        assert_eq!(fill.size, 800.0);
+        assert_eq!(fill.levels, 2);
+        assert!((fill.avg_price - 0.505).abs() < 1e-9);
+        assert!((fill.effective_price(OrderSide::Buy) - 0.505 * 1.002).abs() < 1e-9);
+        assert_eq!(best_quote(&book, &"YES".into(), OrderSide::Buy), Some(0.50));
+        // Under the best ask nothing crosses
+        assert!(fill_on_arrival(&model, &book, &"YES".into(), OrderSide::Buy, 0.49, 10.0).is_none());
+
+        // The other 200 rest at 0.51; the snapshot they failed to fill on can't fill them
+        let mut resting = vec![resting_buy(market_id, 0.51, 200.0, now)];
+        assert!(fill_resting_orders(&model, &mut resting, &book).is_empty());
+
+        // A later book offers 150 at or under the limit, which fill at the limit
+        let later = OrderBook {
+            timestamp: now + Duration::seconds(5),
+            asks: vec![order("YES", 0.505, 150.0), order("YES", 0.52, 500.0)],
+            ..book.clone()
+        };
+        let fills = fill_resting_orders(&model, &mut resting, &later);
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].1.size, 150.0);
+        assert!((fills[0].1.avg_price - 0.51).abs() < 1e-9);
+        assert!((resting[0].remaining - 50.0).abs() < 1e-9);
+
+        // Another market's book leaves the order alone
+        let elsewhere = fixture_book(MarketId::random(), now + Duration::seconds(8));
+        assert!(fill_resting_orders(&model, &mut resting, &elsewhere).is_empty());
+
+        let last = OrderBook {
+            timestamp: now + Duration::seconds(10),
+            asks: vec![order("YES", 0.51, 400.0)],
+            ..book.clone()
+        };
+        let fills = fill_resting_orders(&model, &mut resting, &last);
+        assert!((fills[0].1.size - 50.0).abs() < 1e-9);
+        assert!(resting.is_empty());
+    }
+
+    #[test]
+    fn test_resting_orders_share_the_book() {
+        let market_id = MarketId::random();
+        let now = Utc::now();
+        let model = CostModel::new();
+        let mut resting = vec![
+            resting_buy(market_id, 0.52, 300.0, now),
+            resting_buy(market_id, 0.51, 300.0, now),
+        ];
+
+        // 400 cross both limits; the older order takes its 300 first
+        let book = OrderBook {
+            timestamp: now + Duration::seconds(1),
+            asks: vec![order("YES", 0.50, 400.0)],
+            ..fixture_book(market_id, now)
+        };
+        let fills = fill_resting_orders(&model, &mut resting, &book);
+        let sizes: Vec<f64> = fills.iter().map(|(_, fill)| fill.size).collect();
+        assert_eq!(sizes, vec![300.0, 100.0]);
+        assert_eq!(fills[0].1.avg_price, 0.52);
+        assert_eq!(resting.len(), 1);
+        assert!((resting[0].remaining - 200.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_slippage_against_arrival_quote() {
+        assert!((slippage_bps(OrderSide::Buy, 0.50, 0.505) - 100.0).abs() < 1e-9);
+        assert!((slippage_bps(OrderSide::Sell, 0.50, 0.49) - 200.0).abs() < 1e-9);
+        // Filling better than the quote is negative slippage
+        assert!((slippage_bps(OrderSide::Sell, 0.50, 0.51) + 200.0).abs() < 1e-9);
+
+        let fills = vec![
+            ("Buy".to_string(), 0.50, 0.505),
+            ("Sell".to_string(), 0.50, 0.49),
+            ("Buy".to_string(), 0.0, 0.49),
+        ];
+        assert!((average_slippage_bps(&fills).unwrap() - 150.0).abs() < 1e-9);
+        assert_eq!(average_slippage_bps(&[]), None);
+    }
+
+    #[test]
+    fn test_exit_rules_from_signal() {
+        let signal = Signal {
+            id: Uuid::new_v4(),
+            market_id: MarketId::random(),
+            outcome_id: "YES".into(),
+            predicted_probability: 0.6,
+            confidence: 0.8,
+            direction: OrderSide::Buy,
+            agent_id: Uuid::new_v4(),
+            strategy_id: "s".to_string(),
+            signal_type: None,
+            generated_at: Utc::now(),
+            metadata: serde_json::json!({ "target_price": 0.7, "stop_loss": 0.4 }),
+        };
+        let exits = ExitRules::from_signal(&signal);
+        assert_eq!(exits, ExitRules { target_price: Some(0.7), stop_loss: Some(0.4) });
+        assert_eq!(exits.triggered(OrderSide::Buy, 0.72), Some(PaperExit::TargetHit));
+        assert_eq!(exits.triggered(OrderSide::Buy, 0.40), Some(PaperExit::StopLoss));
+        assert_eq!(exits.triggered(OrderSide::Buy, 0.55), None);
+
+        // A sell profits as the price falls
+        let short = ExitRules { target_price: Some(0.5), stop_loss: Some(0.85) };
+        assert_eq!(short.triggered(OrderSide::Sell, 0.48), Some(PaperExit::TargetHit));
+        assert_eq!(short.triggered(OrderSide::Sell, 0.90), Some(PaperExit::StopLoss));
+        assert_eq!(short.triggered(OrderSide::Sell, 0.60), None);
+
+        let none = ExitRules::from_signal(&Signal { metadata: serde_json::json!({}), ..signal });
+        assert_eq!(none, ExitRules::default());
+        assert_eq!(none.triggered(OrderSide::Buy, 0.99), None);
+    }
+
+    #[test]
+    fn test_paper_pnl_and_mark_price() {
+        assert!((paper_pnl(OrderSide::Buy, 0.40, 1.0, 100.0) - 60.0).abs() < 1e-9);
+        assert!((paper_pnl(OrderSide::Sell, 0.70, 0.60, 50.0) - 5.0).abs() < 1e-9);
+        assert!((paper_pnl(OrderSide::Sell, 0.70, 1.0, 50.0) + 15.0).abs() < 1e-9);
+
+        let book = fixture_book(MarketId::random(), Utc::now());
+        let bid = best_quote(&book, &"YES".into(), OrderSide::Sell).unwrap();
+        let ask = best_quote(&book, &"YES".into(), OrderSide::Buy).unwrap();
+        assert!((book_mark_price(&book, &"YES".into()).unwrap() - (bid + ask) / 2.0).abs() < 1e-12);
+        let one_sided = OrderBook { bids: vec![], ..book };
+        assert_eq!(book_mark_price(&one_sided, &"YES".into()), Some(ask));
+        assert_eq!(book_mark_price(&one_sided, &"NO".into()), None);
+    }
+
+    /// Runs against a scratch Postgres: `TEST_DATABASE_URL=... cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_paper_trades_fill_against_latest_book_postgres() {
+        use sqlx::postgres::PgPoolOptions;
+
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL not set");
+        // Single connection so the scratch schema stays on the search path
+        let pool = Arc::new(PgPoolOptions::new().max_connections(1).connect(&url).await.unwrap());
+        let schema = format!("shadow_{}", Uuid::new_v4().simple());
+        sqlx::raw_sql(&format!(
+            r#"
+            CREATE SCHEMA {schema};
+            SET search_path TO {schema};
+            CREATE TABLE trades (
+                id UUID PRIMARY KEY,
+                market_id UUID NOT NULL,
+                price DOUBLE PRECISION NOT NULL,
+                pnl DOUBLE PRECISION,
+                timestamp TIMESTAMPTZ NOT NULL
+            );
+            CREATE TABLE attributed_trades (
+                trade_id UUID PRIMARY KEY,
+                strategy_id TEXT NOT NULL,
+                pnl NUMERIC(15, 4)
+            );
+            "#
+        ))
+        .execute(pool.as_ref())
+        .await
+        .unwrap();
+
+        let shadow_mode = ShadowMode::new(pool.clone());
+        shadow_mode.initialize().await.unwrap();
+
+        let market_id = MarketId::random();
+        let now = Utc::now();
+        let books = Arc::new(LatestOrderBooks::new());
+        books.update(fixture_book(market_id, now));
+        let model = CostModel::new().with_taker_fee_bps(20.0);
+        let trader = PaperTrader::new(pool.clone()).with_cost_model(model.clone()).with_market_data(books.clone());
+
+        let execution = trader
+            .execute_paper_trade(market_id, &"YES".into(), OrderSide::Buy, 0.51, 1000.0, "s")
+            .await
+            .unwrap();
+        let fill = execution.fill.unwrap();
+        assert_eq!(fill.size, 800.0);
+        assert!((fill.price - 0.505 * 1.002).abs() < 1e-9);
+        assert_eq!(fill.reference_price, Some(0.50));
+        assert!((execution.unfilled - 200.0).abs() < 1e-9);
+        assert_eq!(trader.resting_orders()[0].id, execution.resting_order_id.unwrap());
+
+        let later = OrderBook {
+            timestamp: now + Duration::seconds(5),
+            asks: vec![order("YES", 0.51, 500.0)],
+            ..fixture_book(market_id, now)
+        };
+        let fills = trader.process_tick(&later).await.unwrap();
+        assert_eq!(fills.len(), 1);
+        assert!((fills[0].size - 200.0).abs() < 1e-9);
+        assert!((fills[0].price - 0.51 * 1.002).abs() < 1e-9);
+        assert!(trader.resting_orders().is_empty());
+
+        // Cancelling instead leaves nothing behind
+        let ioc = PaperTrader::new(pool.clone())
+            .with_market_data(books.clone())
+            .with_unfilled_policy(UnfilledPolicy::Cancel);
+        let execution =
+            ioc.execute_paper_trade(market_id, &"YES".into(), OrderSide::Buy, 0.51, 1000.0, "ioc").await.unwrap();
+        assert!(execution.resting_order_id.is_none());
+        assert!(ioc.resting_orders().is_empty());
+
+        // Live, the arrival fill paid 0.52
+        let real_id = Uuid::new_v4();
+        sqlx::query("INSERT INTO trades (id, market_id, price, timestamp) VALUES ($1, $2, 0.52, NOW())")
+            .bind(real_id)
+            .bind(market_id)
+            .execute(pool.as_ref())
+            .await
+            .unwrap();
+        shadow_mode.link_real_trade(fill.id, real_id).await.unwrap();
+
+        let comparison = trader.compare_shadow_real("s").await.unwrap();
+        // Prices are stored to 4 places: 0.5060 is 120 bps on arrival and
+        // 0.5110 220 bps resting, against 400 bps live
+        let shadow = comparison.shadow_avg_slippage_bps.unwrap();
+        assert!((shadow - 170.0).abs() < 1e-6, "shadow slippage {}", shadow);
+        assert!((comparison.real_avg_slippage_bps.unwrap() - 400.0).abs() < 1e-6);
+        assert!((comparison.slippage_diff_bps.unwrap() + 230.0).abs() < 1e-6);
+
+        sqlx::raw_sql(&format!("DROP SCHEMA {schema} CASCADE")).execute(pool.as_ref()).await.unwrap();
+    }
+
+    /// Runs against a scratch Postgres: `TEST_DATABASE_URL=... cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_paper_position_lifecycle_postgres() {
+        use crate::resolution_router::ResolutionSink;
+        use common::OutcomeId;
+        use sqlx::postgres::PgPoolOptions;
+
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL not set");
+        // Single connection so the scratch schema stays on the search path
+        let pool = Arc::new(PgPoolOptions::new().max_connections(1).connect(&url).await.unwrap());
+        let schema = format!("shadow_{}", Uuid::new_v4().simple());
+        sqlx::raw_sql(&format!(
+            r#"
+            CREATE SCHEMA {schema};
+            SET search_path TO {schema};
+            CREATE TABLE trades (id UUID PRIMARY KEY);
+            "#
+        ))
+        .execute(pool.as_ref())
+        .await
+        .unwrap();
+
+        let shadow_mode = ShadowMode::new(pool.clone());
+        shadow_mode.initialize().await.unwrap();
+
+        let position = |market_id: MarketId, side: OrderSide, price: f64, size: f64, exits: ExitRules| ShadowTrade {
+            id: Uuid::new_v4(),
+            trade_id: None,
+            market_id,
+            outcome_id: "YES".into(),
+            side,
+            price,
+            size,
+            timestamp: Utc::now(),
+            strategy_id: "s".to_string(),
+            hypothetical_pnl: None,
+            would_have_won: None,
+            reference_price: None,
+            target_price: exits.target_price,
+            stop_loss: exits.stop_loss,
+        };
+        let (won, lost, ticked) = (MarketId::random(), MarketId::random(), MarketId::random());
+        let short_exits = ExitRules { target_price: Some(0.50), stop_loss: Some(0.85) };
+        for trade in [
+            position(won, OrderSide::Buy, 0.40, 100.0, ExitRules::default()),
+            position(lost, OrderSide::Buy, 0.30, 100.0, ExitRules::default()),
+            position(ticked, OrderSide::Sell, 0.70, 50.0, short_exits),
+        ] {
+            shadow_mode.record_shadow_trade(&trade).await.unwrap();
+        }
+
+        let perf = shadow_mode.calculate_shadow_performance("s").await.unwrap();
+        assert_eq!((perf.total_trades, perf.open_positions), (0, 3));
+
+        // One market resolves each way through the router's sink, the other ticks
+        shadow_mode.resolve(won, &OutcomeId::from("YES")).await.unwrap();
+        shadow_mode.resolve(lost, &OutcomeId::from("NO")).await.unwrap();
+        assert_eq!(shadow_mode.mark_to_market(ticked, &"YES".into(), 0.60).await.unwrap(), 0);
+
+        let perf = shadow_mode.calculate_shadow_performance("s").await.unwrap();
+        assert_eq!((perf.total_trades, perf.winning_trades, perf.losing_trades), (2, 1, 1));
+        assert!((perf.total_pnl - 30.0).abs() < 1e-9, "realized {}", perf.total_pnl);
+        assert!((perf.unrealized_pnl - 5.0).abs() < 1e-9, "unrealized {}", perf.unrealized_pnl);
+        assert_eq!(perf.open_positions, 1);
+        assert!(perf.avg_open_age_hours >= 0.0 && perf.avg_open_age_hours < 0.1);
+
+        // Resolution only closes a market once
+        assert_eq!(shadow_mode.update_shadow_outcomes(won, &"NO".into()).await.unwrap(), 0);
+
+        // The short's target closes it at the tick price
+        assert_eq!(shadow_mode.mark_to_market(ticked, &"YES".into(), 0.48).await.unwrap(), 1);
+        let perf = shadow_mode.calculate_shadow_performance("s").await.unwrap();
+        assert!((perf.total_pnl - 41.0).abs() < 1e-9, "realized {}", perf.total_pnl);
+        assert_eq!((perf.open_positions, perf.unrealized_pnl), (0, 0.0));
+
+        // A stale position closes at its last mark
+        let stale = ShadowTrade {
+            timestamp: Utc::now() - Duration::days(3),
+            ..position(MarketId::random(), OrderSide::Buy, 0.50, 10.0, ExitRules::default())
+        };
+        shadow_mode.record_shadow_trade(&stale).await.unwrap();
+        shadow_mode.mark_to_market(stale.market_id, &"YES".into(), 0.55).await.unwrap();
+        assert_eq!(shadow_mode.expire_stale_positions(Duration::days(1)).await.unwrap(), 1);
+        let perf = shadow_mode.calculate_shadow_performance("s").await.unwrap();
+        assert!((perf.total_pnl - 41.5).abs() < 1e-9, "realized {}", perf.total_pnl);
+        assert_eq!(perf.open_positions, 0);
+
+        sqlx::raw_sql(&format!("DROP SCHEMA {schema} CASCADE")).execute(pool.as_ref()).await.unwrap();
+    }
 }
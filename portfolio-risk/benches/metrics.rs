@@ -0,0 +1,35 @@
+//! Per-event cost of keeping risk metrics current, exact vs streaming
+//!
+//! Run with `cargo bench -p portfolio-risk`.
+
+use common::MarketId;
+use portfolio_risk::Portfolio;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Record `events` sales, recomputing the metrics after each one
+fn run(streaming: bool, events: usize) -> Duration {
+    let mut portfolio = Portfolio::new();
+    if streaming {
+        portfolio.enable_streaming_metrics();
+    }
+    let market_id = MarketId::random();
+    portfolio.add_position(market_id, "YES", 1_000_000.0, 0.5).unwrap();
+
+    let start = Instant::now();
+    for i in 0..events {
+        let price = 0.4 + (i * 7919 % 200) as f64 / 1000.0;
+        portfolio.remove_position(market_id, "YES", 10.0, price).unwrap();
+        black_box(portfolio.calculate_metrics());
+    }
+    start.elapsed()
+}
+
+fn main() {
+    let events = 20_000;
+    println!("{:>10} {:>14}", "mode", "ns/event");
+    for (name, streaming) in [("exact", false), ("streaming", true)] {
+        let elapsed = run(streaming, events);
+        println!("{:>10} {:>14.0}", name, elapsed.as_nanos() as f64 / events as f64);
+    }
+}
@@ -3,7 +3,7 @@
 use portfolio_risk::{
     PortfolioRiskManager, RiskConfig, RiskLimits, Portfolio
 };
-use common::{OrderSide, Uuid};
+use common::{MarketId, OrderSide};
 
 fn main() -> anyhow::Result<()> {
     println!("=== Portfolio & Risk Management Example ===\n");
@@ -40,7 +40,7 @@ fn main() -> anyhow::Result<()> {
 
     // Example 4: Evaluate a trade
     println!("Example 4: Evaluating a Potential Trade");
-    let market_id = Uuid::new_v4();
+    let market_id = MarketId::random();
     let evaluation = manager.evaluate_trade(
         market_id,
         "YES",
@@ -63,7 +63,7 @@ fn main() -> anyhow::Result<()> {
     // Example 5: Risk limit violation
     println!("Example 5: Testing Risk Limit Violation");
     let large_trade = manager.evaluate_trade(
-        Uuid::new_v4(),
+        MarketId::random(),
         "YES",
         OrderSide::Buy,
         0.5,
@@ -100,7 +100,7 @@ fn main() -> anyhow::Result<()> {
     // Example 7: Portfolio position tracking
     println!("Example 7: Position Tracking");
     let mut portfolio = Portfolio::new();
-    let market_id_1 = Uuid::new_v4();
+    let market_id_1 = MarketId::random();
 
     portfolio.add_position(market_id_1, "YES", 50.0, 0.5)?;
     portfolio.update_price(market_id_1, "YES", 0.55);
@@ -129,7 +129,7 @@ fn main() -> anyhow::Result<()> {
 
     // Example 9: Simulating market resolution
     println!("Example 9: Simulating Market Resolution");
-    let market_id_2 = Uuid::new_v4();
+    let market_id_2 = MarketId::random();
 
     portfolio.add_position(market_id_2, "YES", 30.0, 0.6)?;
     portfolio.add_position(market_id_2, "NO", 20.0, 0.4)?;
@@ -0,0 +1,466 @@
+//! Virtual capital accounts per strategy
+//!
+//! Each strategy trades against its own budget instead of the shared pool:
+//! entries debit the strategy's account, exits and settlements credit it,
+//! and returns are reported against the capital the strategy was given.
+
+use crate::config::CapitalConfig;
+use crate::risk::RiskViolation;
+use anyhow::bail;
+use chrono::Utc;
+use common::{ConfigAudit, ConfigChange, MarketId, OutcomeId, RefundPolicy};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Component name recorded with budget changes
+const AUDIT_COMPONENT: &str = "capital_budgets";
+
+/// Shares held in one outcome, and what they cost
+#[derive(Debug, Clone, Default)]
+struct Holding {
+    shares: f64,
+    cost: f64,
+}
+
+/// Ledger of one strategy's virtual capital
+#[derive(Debug, Clone)]
+pub struct CapitalAccount {
+    pub strategy_id: String,
+    pub budget: f64,
+    /// Cost basis of the account's open holdings
+    pub deployed: f64,
+    pub realized_pnl: f64,
+    holdings: HashMap<(MarketId, OutcomeId), Holding>,
+}
+
+impl CapitalAccount {
+    fn new(strategy_id: &str, budget: f64) -> Self {
+        Self {
+            strategy_id: strategy_id.to_string(),
+            budget,
+            deployed: 0.0,
+            realized_pnl: 0.0,
+            holdings: HashMap::new(),
+        }
+    }
+
+    /// Capital the strategy can still put to work; realized PnL counts
+    pub fn available(&self) -> f64 {
+        self.budget + self.realized_pnl - self.deployed
+    }
+
+    fn unrealized_pnl(&self, marks: &HashMap<(MarketId, OutcomeId), f64>) -> f64 {
+        self.holdings
+            .iter()
+            .map(|(key, holding)| match marks.get(key) {
+                Some(price) => holding.shares * price - holding.cost,
+                None => 0.0,
+            })
+            .sum()
+    }
+}
+
+/// Per-account figures for reporting and the strategy leaderboard
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountReport {
+    pub strategy_id: String,
+    pub budget: f64,
+    pub deployed: f64,
+    pub available: f64,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    /// Deployed capital as a fraction of budget
+    pub utilization: f64,
+    /// Total PnL over the allocated budget, not over portfolio equity
+    pub roi: f64,
+}
+
+/// Virtual sub-accounts carved out of the portfolio's real capital
+#[derive(Clone)]
+pub struct CapitalAccountManager {
+    config: CapitalConfig,
+    accounts: HashMap<String, CapitalAccount>,
+    /// Latest price per outcome, for unrealized PnL
+    marks: HashMap<(MarketId, OutcomeId), f64>,
+    audit: Option<Arc<dyn ConfigAudit>>,
+}
+
+impl std::fmt::Debug for CapitalAccountManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CapitalAccountManager")
+            .field("config", &self.config)
+            .field("accounts", &self.accounts)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CapitalAccountManager {
+    /// Open an account for every budget in the config
+    ///
+    /// Fails if the budgets sum to more than `total_capital` and
+    /// over-allocation is not allowed.
+    pub fn from_config(config: CapitalConfig) -> anyhow::Result<Self> {
+        let accounts = config
+            .budgets
+            .iter()
+            .map(|(strategy_id, budget)| (strategy_id.clone(), CapitalAccount::new(strategy_id, *budget)))
+            .collect();
+        let manager = Self {
+            config,
+            accounts,
+            marks: HashMap::new(),
+            audit: None,
+        };
+
+        manager.check_allocation(manager.total_budget())?;
+        Ok(manager)
+    }
+
+    /// Record budget changes and transfers to an audit log
+    pub fn with_audit(mut self, audit: Arc<dyn ConfigAudit>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Whether any account is open; without one, strategy checks pass
+    pub fn is_enabled(&self) -> bool {
+        !self.accounts.is_empty()
+    }
+
+    pub fn account(&self, strategy_id: &str) -> Option<&CapitalAccount> {
+        self.accounts.get(strategy_id)
+    }
+
+    /// Sum of all budgets
+    pub fn total_budget(&self) -> f64 {
+        self.accounts.values().map(|a| a.budget).sum()
+    }
+
+    /// Budgets sum to more than the real capital behind them
+    pub fn is_over_allocated(&self) -> bool {
+        self.total_budget() > self.config.total_capital + 1e-9
+    }
+
+    /// Open an account or change its budget at runtime
+    pub fn set_budget(&mut self, strategy_id: &str, budget: f64, approved_by: Option<&str>) -> anyhow::Result<()> {
+        if !budget.is_finite() || budget < 0.0 {
+            bail!("Invalid budget {} for strategy '{}'", budget, strategy_id);
+        }
+
+        let previous = self.accounts.get(strategy_id).map(|a| a.budget);
+        self.check_allocation(self.total_budget() - previous.unwrap_or(0.0) + budget)?;
+
+        self.accounts
+            .entry(strategy_id.to_string())
+            .or_insert_with(|| CapitalAccount::new(strategy_id, 0.0))
+            .budget = budget;
+
+        let summary = match previous {
+            Some(previous) => format!("{} budget {:.2} -> {:.2}", strategy_id, previous, budget),
+            None => format!("{} opened with budget {:.2}", strategy_id, budget),
+        };
+        info!(strategy_id, budget, "Strategy budget set");
+        self.record_change(summary, approved_by);
+        Ok(())
+    }
+
+    /// Move budget from one account to another
+    ///
+    /// Only capital the source is not using can be moved.
+    pub fn transfer(&mut self, from: &str, to: &str, amount: f64, approved_by: Option<&str>) -> anyhow::Result<()> {
+        if !amount.is_finite() || amount <= 0.0 {
+            bail!("Invalid transfer amount {}", amount);
+        }
+        if from == to {
+            bail!("Cannot transfer from '{}' to itself", from);
+        }
+        let Some(source) = self.accounts.get(from) else {
+            bail!("No capital account for strategy '{}'", from);
+        };
+        if !self.accounts.contains_key(to) {
+            bail!("No capital account for strategy '{}'", to);
+        }
+        if amount > source.available() + 1e-9 {
+            bail!(
+                "Strategy '{}' has {:.2} available, cannot transfer {:.2}",
+                from,
+                source.available(),
+                amount
+            );
+        }
+
+        self.accounts.get_mut(from).unwrap().budget -= amount;
+        self.accounts.get_mut(to).unwrap().budget += amount;
+
+        info!(from, to, amount, "Capital transferred between strategies");
+        self.record_change(format!("transfer {:.2} from {} to {}", amount, from, to), approved_by);
+        Ok(())
+    }
+
+    /// Check a strategy can pay for a new position of `value`
+    pub fn check_buying_power(&self, strategy_id: &str, value: f64) -> Result<(), RiskViolation> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+
+        let available = self.accounts.get(strategy_id).map(|a| a.available()).unwrap_or(0.0);
+        if value > available + 1e-9 {
+            return Err(RiskViolation::StrategyBudgetExceeded {
+                strategy_id: strategy_id.to_string(),
+                available,
+                proposed: value,
+            });
+        }
+        Ok(())
+    }
+
+    /// Debit an entry of `value` at `price` to a strategy
+    pub fn record_entry(
+        &mut self,
+        strategy_id: &str,
+        market_id: MarketId,
+        outcome_id: &str,
+        value: f64,
+        price: f64,
+    ) -> anyhow::Result<()> {
+        if price <= 0.0 {
+            bail!("Invalid entry price {}", price);
+        }
+        let Some(account) = self.accounts.get_mut(strategy_id) else {
+            bail!("No capital account for strategy '{}'", strategy_id);
+        };
+
+        let key = (market_id, OutcomeId::from(outcome_id));
+        let holding = account.holdings.entry(key.clone()).or_default();
+        holding.shares += value / price;
+        holding.cost += value;
+        account.deployed += value;
+        self.marks.insert(key, price);
+        Ok(())
+    }
+
+    /// Credit an exit of `value` at `price`; returns the PnL realized
+    pub fn record_exit(
+        &mut self,
+        strategy_id: &str,
+        market_id: MarketId,
+        outcome_id: &str,
+        value: f64,
+        price: f64,
+    ) -> anyhow::Result<f64> {
+        if price <= 0.0 {
+            bail!("Invalid exit price {}", price);
+        }
+        let Some(account) = self.accounts.get_mut(strategy_id) else {
+            bail!("No capital account for strategy '{}'", strategy_id);
+        };
+
+        let key = (market_id, OutcomeId::from(outcome_id));
+        let Some(holding) = account.holdings.get_mut(&key) else {
+            bail!("Strategy '{}' holds no {} in market {}", strategy_id, outcome_id, market_id);
+        };
+
+        let fraction = ((value / price) / holding.shares).min(1.0);
+        let cost_released = holding.cost * fraction;
+        holding.shares -= holding.shares * fraction;
+        holding.cost -= cost_released;
+        if holding.shares <= 1e-9 {
+            account.holdings.remove(&key);
+        }
+
+        let pnl = value - cost_released;
+        account.deployed -= cost_released;
+        account.realized_pnl += pnl;
+        self.marks.insert(key, price);
+        Ok(pnl)
+    }
+
+    /// Mark an outcome to market for unrealized PnL
+    pub fn update_price(&mut self, market_id: MarketId, outcome_id: &str, price: f64) {
+        self.marks.insert((market_id, OutcomeId::from(outcome_id)), price);
+    }
+
+    /// Settle every account's holdings in a resolved market
+    pub fn resolve_market(&mut self, market_id: MarketId, winning_outcome_id: &str) -> HashMap<String, f64> {
+        self.settle(market_id, |outcome_id, holding| {
+            if outcome_id.as_str() == winning_outcome_id {
+                holding.shares
+            } else {
+                0.0
+            }
+        })
+    }
+
+    /// Refund every account's holdings in an invalidated market
+    pub fn invalidate_market(&mut self, market_id: MarketId, refund_policy: RefundPolicy) -> HashMap<String, f64> {
+        self.settle(market_id, |_, holding| match refund_policy {
+            RefundPolicy::FiftyFifty => holding.shares * 0.5,
+            RefundPolicy::AtCost => holding.cost,
+        })
+    }
+
+    /// Close all holdings in a market at the payout given; PnL by strategy
+    fn settle(&mut self, market_id: MarketId, payout: impl Fn(&OutcomeId, &Holding) -> f64) -> HashMap<String, f64> {
+        let mut pnl_by_strategy = HashMap::new();
+        for account in self.accounts.values_mut() {
+            let settled: Vec<_> = account.holdings.keys().filter(|(id, _)| *id == market_id).cloned().collect();
+            for key in settled {
+                let holding = account.holdings.remove(&key).unwrap();
+                let pnl = payout(&key.1, &holding) - holding.cost;
+                account.deployed -= holding.cost;
+                account.realized_pnl += pnl;
+                *pnl_by_strategy.entry(account.strategy_id.clone()).or_insert(0.0) += pnl;
+            }
+        }
+        self.marks.retain(|(id, _), _| *id != market_id);
+        pnl_by_strategy
+    }
+
+    pub fn report(&self, strategy_id: &str) -> Option<AccountReport> {
+        self.accounts.get(strategy_id).map(|account| {
+            let unrealized_pnl = account.unrealized_pnl(&self.marks);
+            let (utilization, roi) = if account.budget > 0.0 {
+                (
+                    account.deployed / account.budget,
+                    (account.realized_pnl + unrealized_pnl) / account.budget,
+                )
+            } else {
+                (0.0, 0.0)
+            };
+
+            AccountReport {
+                strategy_id: account.strategy_id.clone(),
+                budget: account.budget,
+                deployed: account.deployed,
+                available: account.available(),
+                realized_pnl: account.realized_pnl,
+                unrealized_pnl,
+                utilization,
+                roi,
+            }
+        })
+    }
+
+    /// Reports for every account, best ROI first
+    pub fn leaderboard(&self) -> Vec<AccountReport> {
+        let mut reports: Vec<_> = self.accounts.keys().filter_map(|id| self.report(id)).collect();
+        reports.sort_by(|a, b| b.roi.total_cmp(&a.roi).then_with(|| a.strategy_id.cmp(&b.strategy_id)));
+        reports
+    }
+
+    fn check_allocation(&self, total_budget: f64) -> anyhow::Result<()> {
+        if total_budget <= self.config.total_capital + 1e-9 {
+            return Ok(());
+        }
+        if !self.config.allow_over_allocation {
+            bail!(
+                "Strategy budgets of {:.2} exceed total capital of {:.2}",
+                total_budget,
+                self.config.total_capital
+            );
+        }
+
+        warn!(
+            total_budget,
+            total_capital = self.config.total_capital,
+            "Strategy budgets over-allocated"
+        );
+        Ok(())
+    }
+
+    fn record_change(&self, summary: String, approved_by: Option<&str>) {
+        if let Some(audit) = &self.audit {
+            audit.record(ConfigChange {
+                id: Uuid::new_v4(),
+                component: AUDIT_COMPONENT.to_string(),
+                source: "runtime".to_string(),
+                summary,
+                approved_by: approved_by.map(str::to_string),
+                applied_at: Utc::now(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::InMemoryConfigAudit;
+
+    fn manager(total_capital: f64, budgets: &[(&str, f64)]) -> CapitalAccountManager {
+        CapitalAccountManager::from_config(CapitalConfig {
+            total_capital,
+            allow_over_allocation: false,
+            budgets: budgets.iter().map(|(id, b)| (id.to_string(), *b)).collect(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_roi_against_allocated_capital() {
+        let mut accounts = manager(10_000.0, &[("momentum", 1_000.0), ("value", 4_000.0)]);
+        let market = MarketId::random();
+
+        // Both make 100 on the same market; momentum did it with a quarter of the capital
+        accounts.record_entry("momentum", market, "YES", 200.0, 0.4).unwrap();
+        accounts.record_entry("value", market, "YES", 200.0, 0.4).unwrap();
+        assert_eq!(accounts.report("momentum").unwrap().utilization, 0.2);
+
+        let pnl = accounts.record_exit("momentum", market, "YES", 300.0, 0.6).unwrap();
+        assert!((pnl - 100.0).abs() < 1e-9);
+        accounts.update_price(market, "YES", 0.6);
+
+        let momentum = accounts.report("momentum").unwrap();
+        let value = accounts.report("value").unwrap();
+        assert!((momentum.roi - 0.10).abs() < 1e-9);
+        assert!((value.unrealized_pnl - 100.0).abs() < 1e-9);
+        assert!((value.roi - 0.025).abs() < 1e-9);
+        assert_eq!(momentum.deployed, 0.0);
+        assert!((momentum.available - 1_100.0).abs() < 1e-9);
+
+        let leaderboard = accounts.leaderboard();
+        assert_eq!(leaderboard[0].strategy_id, "momentum");
+
+        // Resolution credits the holder
+        let settled = accounts.resolve_market(market, "YES");
+        assert!((settled["value"] - 300.0).abs() < 1e-9);
+        assert!((accounts.report("value").unwrap().roi - 0.075).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_over_allocation_rejected_unless_allowed() {
+        let mut accounts = manager(1_000.0, &[("a", 600.0)]);
+        assert!(accounts.set_budget("b", 500.0, None).is_err());
+        assert!(accounts.account("b").is_none());
+
+        let mut allowed = CapitalAccountManager::from_config(CapitalConfig {
+            total_capital: 1_000.0,
+            allow_over_allocation: true,
+            budgets: HashMap::from([("a".to_string(), 600.0)]),
+        })
+        .unwrap();
+        allowed.set_budget("b", 500.0, None).unwrap();
+        assert!(allowed.is_over_allocated());
+    }
+
+    #[test]
+    fn test_transfer_limited_to_available_capital() {
+        let audit = Arc::new(InMemoryConfigAudit::new());
+        let mut accounts = manager(1_000.0, &[("a", 500.0), ("b", 500.0)]).with_audit(audit.clone());
+        accounts.record_entry("a", MarketId::random(), "YES", 400.0, 0.5).unwrap();
+
+        assert!(accounts.transfer("a", "b", 200.0, Some("ops")).is_err());
+        accounts.transfer("a", "b", 100.0, Some("ops")).unwrap();
+        assert_eq!(accounts.account("a").unwrap().budget, 400.0);
+        assert_eq!(accounts.account("b").unwrap().budget, 600.0);
+        assert!(accounts.check_buying_power("a", 1.0).is_err());
+
+        let changes = audit.changes();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].component, "capital_budgets");
+        assert_eq!(changes[0].approved_by.as_deref(), Some("ops"));
+    }
+}
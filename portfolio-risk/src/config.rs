@@ -16,13 +16,18 @@ pub struct RiskConfig {
     #[serde(default = "default_kelly_multiplier")]
     pub kelly_multiplier: f64,
 
-    /// Correlation monitoring settings
-    #[serde(default)]
+    /// Correlation at or above which markets count as one bet towards
+    /// `max_correlated_exposure`
+    #[serde(default = "default_correlation_threshold")]
     pub correlation_threshold: f64,
 
     /// Risk metric calculation settings
     #[serde(default)]
     pub metrics: MetricsConfig,
+
+    /// Per-strategy capital budgets
+    #[serde(default)]
+    pub capital: CapitalConfig,
 }
 
 impl Default for RiskConfig {
@@ -31,8 +36,9 @@ impl Default for RiskConfig {
             risk_limits: RiskLimits::default(),
             circuit_breakers: CircuitBreakerConfig::default(),
             kelly_multiplier: 0.25, // Conservative quarter-Kelly
-            correlation_threshold: 0.7,
+            correlation_threshold: default_correlation_threshold(),
             metrics: MetricsConfig::default(),
+            capital: CapitalConfig::default(),
         }
     }
 }
@@ -41,6 +47,10 @@ fn default_kelly_multiplier() -> f64 {
     0.25
 }
 
+fn default_correlation_threshold() -> f64 {
+    0.7
+}
+
 /// Risk limits at different levels
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RiskLimits {
@@ -65,6 +75,14 @@ pub struct RiskLimits {
     /// Stop loss percentage per position
     pub stop_loss_percentage: f64,
 
+    /// Maximum netted exposure to a negRisk group of linked markets (in USD)
+    #[serde(default = "default_max_group_exposure")]
+    pub max_group_exposure: f64,
+
+    /// Maximum exposure to a cluster of markets whose prices move together (in USD)
+    #[serde(default = "default_max_correlated_exposure")]
+    pub max_correlated_exposure: f64,
+
     /// Theme/category-specific limits
     #[serde(default)]
     pub theme_limits: HashMap<String, ThemeLimit>,
@@ -97,11 +115,32 @@ impl Default for RiskLimits {
             max_theme_percentage: 0.30,
             daily_loss_limit: 100.0,
             stop_loss_percentage: 0.20,
+            max_group_exposure: default_max_group_exposure(),
+            max_correlated_exposure: default_max_correlated_exposure(),
             theme_limits,
         }
     }
 }
 
+fn default_max_group_exposure() -> f64 {
+    250.0
+}
+
+fn default_max_correlated_exposure() -> f64 {
+    250.0
+}
+
+impl RiskLimits {
+    /// Exposure limit for a category, falling back to `max_theme_exposure`
+    pub fn theme_exposure_limit(&self, category: &str) -> f64 {
+        self.theme_limits
+            .get(category)
+            .or_else(|| self.theme_limits.get(&category.to_lowercase()))
+            .map(|limit| limit.max_exposure)
+            .unwrap_or(self.max_theme_exposure)
+    }
+}
+
 /// Theme-specific risk limits
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThemeLimit {
@@ -130,9 +169,10 @@ pub struct CircuitBreakerConfig {
     #[serde(default = "default_max_drawdown")]
     pub max_drawdown_percentage: f64,
 
-    /// Stop trading if VaR (95%) exceeds this (in USD)
+    /// Stop trading if VaR (95%) exceeds this, in USD or as a fraction
+    /// of portfolio value
     #[serde(default = "default_var_limit")]
-    pub var_95_limit: f64,
+    pub var_95_limit: VaRLimit,
 
     /// Cooldown period after circuit breaker trigger (in minutes)
     #[serde(default = "default_cooldown_minutes")]
@@ -149,7 +189,7 @@ impl Default for CircuitBreakerConfig {
             enabled: true,
             daily_loss_limit: 100.0,
             max_drawdown_percentage: 0.15,
-            var_95_limit: 200.0,
+            var_95_limit: default_var_limit(),
             cooldown_minutes: 30,
             max_violations_per_day: 3,
         }
@@ -168,8 +208,17 @@ fn default_max_drawdown() -> f64 {
     0.15
 }
 
-fn default_var_limit() -> f64 {
-    200.0
+fn default_var_limit() -> VaRLimit {
+    VaRLimit::Usd(200.0)
+}
+
+/// Limit on VaR (95%): a bare number is USD, `{ fraction = 0.05 }` is a
+/// fraction of portfolio value
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum VaRLimit {
+    Usd(f64),
+    Fraction { fraction: f64 },
 }
 
 fn default_cooldown_minutes() -> u64 {
@@ -195,9 +244,23 @@ pub struct MetricsConfig {
     #[serde(default = "default_sharpe_lookback")]
     pub sharpe_lookback_days: u32,
 
-    /// Risk-free rate for Sharpe ratio calculation (annualized)
+    /// Risk-free rate for Sharpe and Sortino ratios (annualized)
     #[serde(default = "default_risk_free_rate")]
     pub risk_free_rate: f64,
+
+    /// Interval the portfolio value is resampled to before computing the
+    /// returns behind Sharpe and Sortino
+    #[serde(default)]
+    pub return_bucket: ReturnBucket,
+
+    /// How VaR and expected shortfall are estimated from the returns
+    #[serde(default)]
+    pub var_method: VaRMethod,
+
+    /// Compute metrics incrementally rather than from the full PnL history
+    /// on every call (see `Portfolio::enable_streaming_metrics`)
+    #[serde(default)]
+    pub streaming: bool,
 }
 
 impl Default for MetricsConfig {
@@ -207,10 +270,49 @@ impl Default for MetricsConfig {
             var_confidence: 0.95,
             sharpe_lookback_days: 30,
             risk_free_rate: 0.05,
+            return_bucket: ReturnBucket::Daily,
+            var_method: VaRMethod::Historical,
+            streaming: false,
         }
     }
 }
 
+/// Sampling interval of portfolio returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReturnBucket {
+    Hourly,
+    #[default]
+    Daily,
+}
+
+impl ReturnBucket {
+    /// Length of one bucket
+    pub fn seconds(&self) -> i64 {
+        match self {
+            ReturnBucket::Hourly => 3_600,
+            ReturnBucket::Daily => 86_400,
+        }
+    }
+
+    /// Buckets per year; markets trade every day, so a year is 365 days
+    pub fn periods_per_year(&self) -> f64 {
+        365.0 * 86_400.0 / self.seconds() as f64
+    }
+}
+
+/// VaR estimation method
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VaRMethod {
+    /// Quantiles of the last `var_samples` returns; needs at least 10
+    #[default]
+    Historical,
+    /// Normal quantiles from the mean and standard deviation of the
+    /// returns; needs only two, so it suits a short history
+    Parametric,
+}
+
 fn default_var_samples() -> usize {
     100
 }
@@ -227,6 +329,24 @@ fn default_risk_free_rate() -> f64 {
     0.05
 }
 
+/// Virtual capital accounts, one per strategy
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CapitalConfig {
+    /// Real cash the budgets are carved out of
+    #[serde(default)]
+    pub total_capital: f64,
+
+    /// Accept budgets summing to more than `total_capital`; the manager
+    /// then reports itself over-allocated instead of rejecting the change
+    #[serde(default)]
+    pub allow_over_allocation: bool,
+
+    /// Budget per strategy id; strategies not listed here cannot trade
+    /// through `evaluate_strategy_trade` once any budget is set
+    #[serde(default)]
+    pub budgets: HashMap<String, f64>,
+}
+
 /// Load configuration from TOML file
 pub fn load_config(path: &str) -> anyhow::Result<RiskConfig> {
     let content = std::fs::read_to_string(path)?;
@@ -268,6 +388,13 @@ daily_loss_limit = 100.0
 # Stop loss percentage per position
 stop_loss_percentage = 0.20
 
+# Maximum netted exposure to a negRisk group of linked markets (USD)
+max_group_exposure = 250.0
+
+# Maximum exposure to markets whose prices correlate at or above
+# correlation_threshold (USD)
+max_correlated_exposure = 250.0
+
 # Theme-specific limits
 [risk_limits.theme_limits.politics]
 max_exposure = 500.0
@@ -294,7 +421,8 @@ daily_loss_limit = 100.0
 # Stop trading if drawdown exceeds this percentage
 max_drawdown_percentage = 0.15
 
-# Stop trading if VaR (95%) exceeds this (USD)
+# Stop trading if VaR (95%) exceeds this (USD), or write
+# var_95_limit = { fraction = 0.05 } for a fraction of portfolio value
 var_95_limit = 200.0
 
 # Cooldown period after circuit breaker trigger (minutes)
@@ -306,6 +434,7 @@ max_violations_per_day = 3
 # Kelly criterion multiplier (0.25 = quarter-Kelly, conservative)
 kelly_multiplier = 0.25
 
+# Price correlation at which markets count as the same bet
 correlation_threshold = 0.7
 
 [metrics]
@@ -318,8 +447,19 @@ var_confidence = 0.95
 # Lookback period for Sharpe ratio (days)
 sharpe_lookback_days = 30
 
-# Risk-free rate for Sharpe (annualized)
+# Risk-free rate for Sharpe and Sortino (annualized)
 risk_free_rate = 0.05
+
+# Resample portfolio value to \"hourly\" or \"daily\" buckets for returns
+return_bucket = \"daily\"
+
+# Estimate VaR from \"historical\" return quantiles or \"parametric\"
+# normal quantiles (for a short history)
+var_method = \"historical\"
+
+# Compute metrics incrementally (O(1) per trade) instead of from the
+# full PnL history; VaR becomes a streaming estimate
+streaming = false
 ";
 
     std::fs::write(path, template)?;
@@ -345,4 +485,18 @@ mod tests {
 
         assert_eq!(config.kelly_multiplier, deserialized.kelly_multiplier);
     }
+
+    #[test]
+    fn test_var_limit_in_usd_or_fraction() {
+        let usd: CircuitBreakerConfig = toml::from_str("var_95_limit = 150.0").unwrap();
+        assert_eq!(usd.var_95_limit, VaRLimit::Usd(150.0));
+
+        let fraction: CircuitBreakerConfig = toml::from_str("var_95_limit = { fraction = 0.05 }").unwrap();
+        assert_eq!(fraction.var_95_limit, VaRLimit::Fraction { fraction: 0.05 });
+
+        let mut config = RiskConfig::default();
+        config.circuit_breakers.var_95_limit = VaRLimit::Fraction { fraction: 0.05 };
+        let deserialized: RiskConfig = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(deserialized.circuit_breakers.var_95_limit, VaRLimit::Fraction { fraction: 0.05 });
+    }
 }
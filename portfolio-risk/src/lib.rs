@@ -3,25 +3,38 @@
 //! This crate provides comprehensive portfolio tracking, risk monitoring,
 //! and position management for Polymarket trading operations.
 
+mod capital;
 mod config;
 mod portfolio;
 mod risk;
 mod metrics;
+mod service;
+mod stress;
 
-pub use config::{RiskConfig, RiskLimits, CircuitBreakerConfig};
-pub use portfolio::{Portfolio, Position, PositionState, Exposure};
+pub use capital::{CapitalAccount, CapitalAccountManager, AccountReport};
+pub use config::{RiskConfig, RiskLimits, ThemeLimit, CircuitBreakerConfig, CapitalConfig, ReturnBucket, VaRLimit, VaRMethod, load_config, save_config, create_config_template};
+pub use portfolio::{Portfolio, Position, PositionState, Exposure, GroupExposure, Settlement};
 pub use risk::{RiskChecker, RiskViolation, CircuitBreaker, KellyCriterion, KellyCriterion as Kelly, RiskLevel};
 pub use metrics::{RiskMetrics, VaRResult};
+pub use service::{ExposureRecorder, ExposureSample, PortfolioService, PortfolioHandle, PortfolioSnapshot, SystemStateView};
+pub use stress::{Scenario, ScenarioEngine, Shock, StressResult, PositionImpact};
 
-use common::{MarketEvent, Uuid};
-use tracing::{info, error};
+use common::{MarketEvent, MarketId, RefundPolicy, RewardsModel};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::{debug, info, warn, error};
 
 /// Main entry point for portfolio and risk management
+///
+/// Mutations take `&mut self`; to share a portfolio between a writer and
+/// concurrent readers, run it inside a `PortfolioService` and read snapshots.
 #[derive(Debug, Clone)]
 pub struct PortfolioRiskManager {
     portfolio: Portfolio,
     risk_checker: RiskChecker,
     config: RiskConfig,
+    rewards: RewardsModel,
+    capital: CapitalAccountManager,
 }
 
 impl PortfolioRiskManager {
@@ -32,13 +45,54 @@ impl PortfolioRiskManager {
 
     /// Create a new portfolio risk manager with custom configuration
     pub fn with_config(config: RiskConfig) -> anyhow::Result<Self> {
+        let mut portfolio = Portfolio::new();
+        portfolio.set_return_sampling(config.metrics.return_bucket, config.metrics.risk_free_rate);
+        portfolio.set_var_method(config.metrics.var_method);
+        if config.metrics.streaming {
+            portfolio.enable_streaming_metrics();
+        }
+
         Ok(Self {
-            portfolio: Portfolio::new(),
-            risk_checker: RiskChecker::new(config.risk_limits.clone()),
+            portfolio,
+            risk_checker: RiskChecker::with_circuit_breakers(
+                config.risk_limits.clone(),
+                config.circuit_breakers.clone(),
+            )
+            .with_correlation_threshold(config.correlation_threshold),
+            capital: CapitalAccountManager::from_config(config.capital.clone())?,
             config,
+            rewards: RewardsModel::default(),
         })
     }
 
+    /// Rebate rates applied by `record_maker_fill`
+    pub fn with_rewards(mut self, rewards: RewardsModel) -> Self {
+        self.rewards = rewards;
+        self
+    }
+
+    /// Strategy capital accounts, e.g. with an audit log attached
+    pub fn with_capital_accounts(mut self, capital: CapitalAccountManager) -> Self {
+        self.capital = capital;
+        self
+    }
+
+    /// Rebuild a manager around a portfolio saved at shutdown
+    ///
+    /// Circuit breaker state is not part of the portfolio and starts fresh,
+    /// as do strategy capital accounts.
+    pub fn restore(config: RiskConfig, mut portfolio: Portfolio) -> anyhow::Result<Self> {
+        portfolio.set_return_sampling(config.metrics.return_bucket, config.metrics.risk_free_rate);
+        portfolio.set_var_method(config.metrics.var_method);
+        if config.metrics.streaming {
+            portfolio.enable_streaming_metrics();
+        }
+
+        let mut manager = Self::with_config(config)?;
+        manager.portfolio = portfolio;
+        Ok(manager)
+    }
+
     /// Process a market event (e.g., trade, price update)
     pub fn process_event(&mut self, event: &MarketEvent) -> anyhow::Result<()> {
         match event {
@@ -50,26 +104,130 @@ impl PortfolioRiskManager {
                 self.update_position_prices(tick)?;
             }
             MarketEvent::MarketResolved { market_id, outcome_id } => {
-                self.resolve_market(*market_id, outcome_id)?;
+                self.resolve_market(*market_id, outcome_id.as_str())?;
+            }
+            MarketEvent::ResolutionReversed { market_id, previous_outcome } => {
+                self.reverse_resolution(*market_id, previous_outcome.as_str())?;
+            }
+            MarketEvent::MarketInvalidated { market_id, refund_policy } => {
+                self.invalidate_market(*market_id, *refund_policy)?;
+            }
+            MarketEvent::MarketCreated(market) => {
+                self.set_market_category(market.id, &market.category);
+                if let Some(group_id) = &market.neg_risk_group {
+                    let no_outcome = market
+                        .outcomes
+                        .iter()
+                        .find(|o| o.name.eq_ignore_ascii_case("no"))
+                        .map(|o| o.id.clone());
+                    self.portfolio.set_neg_risk_group(market.id, group_id.clone(), no_outcome);
+                }
             }
             _ => {}
         }
         Ok(())
     }
 
+    /// Process an executed trade made on behalf of a strategy
+    ///
+    /// Books the trade like `process_event` and debits or credits the
+    /// strategy's capital account.
+    pub fn process_strategy_trade(&mut self, strategy_id: &str, trade: &common::Trade) -> anyhow::Result<()> {
+        self.update_position_from_trade(trade)?;
+
+        if self.capital.is_enabled() {
+            let value = trade.price * trade.size;
+            let outcome_id = trade.outcome_id.as_str();
+            match trade.side {
+                common::OrderSide::Buy => {
+                    self.capital.record_entry(strategy_id, trade.market_id, outcome_id, value, trade.price)?;
+                }
+                common::OrderSide::Sell => {
+                    self.capital.record_exit(strategy_id, trade.market_id, outcome_id, value, trade.price)?;
+                }
+            }
+        }
+
+        self.check_circuit_breakers(trade)
+    }
+
+    /// Process a fill of one of our resting orders
+    ///
+    /// The trade is booked like any other, and the liquidity rebate due at
+    /// its timestamp is accrued as a separate PnL line. Returns the rebate.
+    pub fn record_maker_fill(&mut self, trade: &common::Trade) -> anyhow::Result<f64> {
+        self.process_event(&MarketEvent::Trade(trade.clone()))?;
+
+        let rebate = self.rewards.rebate(trade.market_id, trade.timestamp, trade.price * trade.size);
+        if rebate != 0.0 {
+            self.portfolio.accrue_rebate(rebate);
+            debug!(market_id = %trade.market_id, rebate, "Maker rebate accrued");
+        }
+        Ok(rebate)
+    }
+
+    /// Register a market's category for theme exposure limits
+    pub fn set_market_category(&mut self, market_id: MarketId, category: &str) {
+        self.portfolio.set_category(market_id, category.to_string());
+    }
+
     /// Evaluate a potential trade before execution
+    ///
+    /// Theme limits apply to the category registered via `set_market_category`,
+    /// group limits to the negRisk group the market was created in, and the
+    /// correlated exposure limit to the outcomes whose price ticks move with
+    /// this one.
     pub fn evaluate_trade(
         &self,
-        market_id: Uuid,
+        market_id: MarketId,
         outcome_id: &str,
         side: common::OrderSide,
         price: f64,
         size: f64,
+    ) -> Result<TradeEvaluation, RiskViolation> {
+        self.evaluate_trade_with_edge(market_id, outcome_id, side, price, size, None)
+    }
+
+    /// `evaluate_trade` with the Kelly limit sized for `edge`, the caller's
+    /// estimate of the win probability over `price`, against a bankroll of
+    /// `max_total_exposure`. Without an edge Kelly sees no advantage and
+    /// allows nothing.
+    pub fn evaluate_trade_with_edge(
+        &self,
+        market_id: MarketId,
+        outcome_id: &str,
+        side: common::OrderSide,
+        price: f64,
+        size: f64,
+        edge: Option<f64>,
     ) -> Result<TradeEvaluation, RiskViolation> {
         let position_value = price * size;
+        let category = self.portfolio.category(market_id);
+
+        debug!(
+            market_id = %market_id,
+            outcome_id = %outcome_id,
+            category = ?category,
+            value = position_value,
+            "Evaluating trade"
+        );
 
         // Check all risk limits
         self.risk_checker.check_trade(
+            side,
+            position_value,
+            category,
+            &self.portfolio,
+        )?;
+        self.risk_checker.check_group_trade(
+            market_id,
+            outcome_id,
+            side,
+            position_value,
+            price,
+            &self.portfolio,
+        )?;
+        self.risk_checker.check_correlated_trade(
             market_id,
             outcome_id,
             side,
@@ -78,10 +236,11 @@ impl PortfolioRiskManager {
         )?;
 
         // Calculate Kelly-optimal position size
-        let kelly_limit = self.risk_checker.kelly_criterion.calculate_position(
-            price,
-            self.config.kelly_multiplier,
-        );
+        let kelly_limit = match edge {
+            Some(edge) => KellyCriterion::new(self.config.kelly_multiplier, Some(edge)),
+            None => self.risk_checker.kelly_criterion.clone(),
+        }
+        .calculate_position(price, self.config.risk_limits.max_total_exposure);
 
         // Check if position exceeds Kelly criterion
         if position_value > kelly_limit {
@@ -98,6 +257,25 @@ impl PortfolioRiskManager {
         })
     }
 
+    /// Evaluate a potential trade on behalf of a strategy
+    ///
+    /// Buys must fit the strategy's available capital as well as pass
+    /// every portfolio-wide check in `evaluate_trade`.
+    pub fn evaluate_strategy_trade(
+        &self,
+        strategy_id: &str,
+        market_id: MarketId,
+        outcome_id: &str,
+        side: common::OrderSide,
+        price: f64,
+        size: f64,
+    ) -> Result<TradeEvaluation, RiskViolation> {
+        if side == common::OrderSide::Buy {
+            self.capital.check_buying_power(strategy_id, price * size)?;
+        }
+        self.evaluate_trade(market_id, outcome_id, side, price, size)
+    }
+
     /// Update position after a trade is executed
     fn update_position_from_trade(&mut self, trade: &common::Trade) -> anyhow::Result<()> {
         let position_value = trade.price * trade.size;
@@ -106,7 +284,7 @@ impl PortfolioRiskManager {
             common::OrderSide::Buy => {
                 self.portfolio.add_position(
                     trade.market_id,
-                    &trade.outcome_id,
+                    trade.outcome_id.as_str(),
                     position_value,
                     trade.price,
                 )?;
@@ -114,7 +292,7 @@ impl PortfolioRiskManager {
             common::OrderSide::Sell => {
                 self.portfolio.remove_position(
                     trade.market_id,
-                    &trade.outcome_id,
+                    trade.outcome_id.as_str(),
                     position_value,
                     trade.price,
                 )?;
@@ -134,12 +312,14 @@ impl PortfolioRiskManager {
 
     /// Update position prices from market data
     fn update_position_prices(&mut self, tick: &common::PriceTick) -> anyhow::Result<()> {
-        self.portfolio.update_price(tick.market_id, &tick.outcome_id, tick.price);
+        self.portfolio.update_price(tick.market_id, tick.outcome_id.as_str(), tick.price);
+        self.capital.update_price(tick.market_id, tick.outcome_id.as_str(), tick.price);
+        self.risk_checker.update_price(tick.market_id, tick.outcome_id.as_str(), tick.price);
         Ok(())
     }
 
     /// Check if any circuit breakers are triggered
-    fn check_circuit_breakers(&self, trade: &common::Trade) -> anyhow::Result<()> {
+    fn check_circuit_breakers(&mut self, trade: &common::Trade) -> anyhow::Result<()> {
         let violations = self.risk_checker.check_circuit_breakers(&self.portfolio);
 
         if !violations.is_empty() {
@@ -163,24 +343,99 @@ impl PortfolioRiskManager {
         Ok(())
     }
 
+    /// Operator override to resume trading before the cooldown expires
+    pub fn force_reset_circuit_breaker(&mut self) {
+        self.risk_checker.force_reset_circuit_breaker();
+    }
+
+    /// Whether the circuit breaker is halting trading
+    pub fn circuit_breaker_active(&self) -> bool {
+        self.risk_checker.circuit_breaker().is_active()
+    }
+
     /// Resolve a market and update portfolio accordingly
-    fn resolve_market(
+    ///
+    /// Returns the PnL realized by the portfolio and by each strategy
+    /// account. Resolving an already settled market books nothing.
+    pub fn resolve_market(
         &mut self,
-        market_id: Uuid,
+        market_id: MarketId,
         winning_outcome_id: &str,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<ResolutionPnl> {
         let pnl = self.portfolio.resolve_market(market_id, winning_outcome_id)?;
+        let strategy_pnl = self.capital.resolve_market(market_id, winning_outcome_id);
 
         info!(
             market_id = %market_id,
             winning_outcome = %winning_outcome_id,
             pnl = pnl,
+            strategy_pnl = ?strategy_pnl,
             "Market resolved"
         );
 
+        Ok(ResolutionPnl { total: pnl, by_strategy: strategy_pnl })
+    }
+
+    /// Reopen the positions of a market whose resolution was overturned
+    fn reverse_resolution(
+        &mut self,
+        market_id: MarketId,
+        previous_outcome_id: &str,
+    ) -> anyhow::Result<()> {
+        let reversed_pnl = self.portfolio.reverse_resolution(market_id, previous_outcome_id)?;
+        // Strategy accounts keep the settlement; they are not reopened here
+
+        error!(
+            market_id = %market_id,
+            previous_outcome = %previous_outcome_id,
+            reversed_pnl = reversed_pnl,
+            reopened = self.portfolio.positions().keys().filter(|(id, _)| *id == market_id).count(),
+            "Market resolution reversed - positions reopened"
+        );
+
         Ok(())
     }
 
+    /// Refund the positions of an invalidated market
+    fn invalidate_market(
+        &mut self,
+        market_id: MarketId,
+        refund_policy: RefundPolicy,
+    ) -> anyhow::Result<()> {
+        let pnl = self.portfolio.invalidate_market(market_id, refund_policy)?;
+        let strategy_pnl = self.capital.invalidate_market(market_id, refund_policy);
+
+        warn!(
+            market_id = %market_id,
+            refund_policy = ?refund_policy,
+            pnl = pnl,
+            strategy_pnl = ?strategy_pnl,
+            "Market invalidated - positions refunded"
+        );
+
+        Ok(())
+    }
+
+    /// Get the underlying portfolio
+    pub fn portfolio(&self) -> &Portfolio {
+        &self.portfolio
+    }
+
+    /// Per-strategy capital accounts
+    pub fn capital_accounts(&self) -> &CapitalAccountManager {
+        &self.capital
+    }
+
+    /// Per-strategy capital accounts, for budget changes and transfers
+    pub fn capital_accounts_mut(&mut self) -> &mut CapitalAccountManager {
+        &mut self.capital
+    }
+
+    /// Project the portfolio under `scenario` against the configured limits
+    pub fn stress_test(&self, scenario: &Scenario) -> StressResult {
+        ScenarioEngine::from_config(&self.config).run(&self.portfolio, scenario)
+    }
+
     /// Get current portfolio metrics
     pub fn get_metrics(&self) -> RiskMetrics {
         self.portfolio.calculate_metrics()
@@ -192,6 +447,7 @@ impl PortfolioRiskManager {
             total_value: self.portfolio.total_value(),
             num_positions: self.portfolio.num_positions(),
             total_pnl: self.portfolio.total_pnl(),
+            rebate_pnl: self.portfolio.rebate_pnl(),
             exposure_by_category: self.portfolio.exposure_by_category(),
             risk_level: self.risk_checker.calculate_risk_level(&self.portfolio),
         }
@@ -212,12 +468,23 @@ pub struct TradeEvaluation {
     pub risk_level: RiskLevel,
 }
 
+/// PnL realized by resolving a market
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResolutionPnl {
+    pub total: f64,
+    /// Only strategies that held the market appear
+    pub by_strategy: HashMap<String, f64>,
+}
+
 /// Portfolio summary for reporting
 #[derive(Debug, Clone)]
 pub struct PortfolioSummary {
     pub total_value: f64,
     pub num_positions: usize,
+    /// Realized trading PnL, excluding rebates
     pub total_pnl: f64,
+    /// Liquidity rebates on maker fills
+    pub rebate_pnl: f64,
     pub exposure_by_category: Vec<(String, f64)>,
     pub risk_level: RiskLevel,
 }
@@ -225,7 +492,8 @@ pub struct PortfolioSummary {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use common::{Market, Outcome, OrderBook, Order, PriceTick};
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+    use common::Uuid;
 
     #[test]
     fn test_portfolio_creation() {
@@ -236,7 +504,7 @@ mod tests {
     #[test]
     fn test_position_tracking() {
         let mut portfolio = Portfolio::new();
-        let market_id = Uuid::new_v4();
+        let market_id = MarketId::random();
 
         portfolio.add_position(market_id, "YES", 100.0, 0.5).unwrap();
         assert_eq!(portfolio.total_value(), 100.0);
@@ -255,7 +523,7 @@ mod tests {
         };
 
         let manager = PortfolioRiskManager::with_config(config).unwrap();
-        let market_id = Uuid::new_v4();
+        let market_id = MarketId::random();
 
         // This should fail due to max position size
         let result = manager.evaluate_trade(
@@ -283,7 +551,7 @@ mod tests {
     #[test]
     fn test_var_calculation() {
         let mut portfolio = Portfolio::new();
-        let market_id = Uuid::new_v4();
+        let market_id = MarketId::random();
 
         // Add some positions
         portfolio.add_position(market_id, "YES", 100.0, 0.5).unwrap();
@@ -304,4 +572,471 @@ mod tests {
         // The test just checks it doesn't crash
         println!("VaR (95%): {:?}", metrics.var_95);
     }
+
+    fn buy(market_id: MarketId, outcome_id: &str, price: f64, size: f64) -> MarketEvent {
+        MarketEvent::Trade(common::Trade {
+            id: Uuid::new_v4(),
+            market_id,
+            outcome_id: outcome_id.into(),
+            price,
+            size,
+            side: common::OrderSide::Buy,
+            timestamp: chrono::Utc::now(),
+        })
+    }
+
+    /// 250 YES shares for 100 and 100 NO shares for 60
+    fn manager_with_both_sides(market_id: MarketId) -> PortfolioRiskManager {
+        let mut manager = PortfolioRiskManager::new().unwrap();
+        manager.process_event(&buy(market_id, "YES", 0.4, 250.0)).unwrap();
+        manager.process_event(&buy(market_id, "NO", 0.6, 100.0)).unwrap();
+        manager
+    }
+
+    fn resolved(market_id: MarketId, outcome_id: &str) -> MarketEvent {
+        MarketEvent::MarketResolved {
+            market_id,
+            outcome_id: outcome_id.into(),
+        }
+    }
+
+    #[test]
+    fn test_restore_from_serialized_portfolio() {
+        let market_id = MarketId::random();
+        let mut manager = manager_with_both_sides(market_id);
+        let other = MarketId::random();
+        manager.set_market_category(other, "crypto");
+        manager.process_event(&buy(other, "YES", 0.5, 40.0)).unwrap();
+        manager.process_event(&resolved(market_id, "YES")).unwrap();
+
+        let json = serde_json::to_string(manager.portfolio()).unwrap();
+        let portfolio: Portfolio = serde_json::from_str(&json).unwrap();
+        let restored = PortfolioRiskManager::restore(RiskConfig::default(), portfolio).unwrap();
+
+        assert_eq!(restored.get_summary().num_positions, 1);
+        assert_eq!(restored.get_summary().total_value, manager.get_summary().total_value);
+        assert_eq!(restored.get_summary().total_pnl, manager.get_summary().total_pnl);
+        assert_eq!(restored.portfolio().category(other), Some("crypto"));
+    }
+
+    #[test]
+    fn test_restore_re_enables_streaming_metrics() {
+        let mut config = RiskConfig::default();
+        config.metrics.streaming = true;
+        let mut manager = PortfolioRiskManager::with_config(config.clone()).unwrap();
+        let market_id = MarketId::random();
+        manager.process_event(&buy(market_id, "YES", 0.5, 100.0)).unwrap();
+        assert!(manager.portfolio().streaming_metrics_enabled());
+
+        let json = serde_json::to_string(manager.portfolio()).unwrap();
+        let portfolio: Portfolio = serde_json::from_str(&json).unwrap();
+        assert!(!portfolio.streaming_metrics_enabled());
+
+        let restored = PortfolioRiskManager::restore(config, portfolio.clone()).unwrap();
+        assert!(restored.portfolio().streaming_metrics_enabled());
+        let restored = PortfolioRiskManager::restore(RiskConfig::default(), portfolio).unwrap();
+        assert!(!restored.portfolio().streaming_metrics_enabled());
+    }
+
+    #[test]
+    fn test_maker_fill_accrues_rebate_inside_campaign() {
+        let market_id = MarketId::random();
+        let start = chrono::Utc::now();
+        let rewards = common::RewardsModel::new().with_campaign(common::RewardCampaign {
+            market_id: Some(market_id),
+            rate: 0.01,
+            starts_at: start,
+            ends_at: start + chrono::Duration::days(1),
+        });
+        let mut manager = PortfolioRiskManager::new().unwrap().with_rewards(rewards);
+
+        let fill_at = |at| common::Trade {
+            id: Uuid::new_v4(),
+            market_id,
+            outcome_id: "YES".into(),
+            price: 0.4,
+            size: 250.0,
+            side: common::OrderSide::Buy,
+            timestamp: at,
+        };
+
+        let rebate = manager.record_maker_fill(&fill_at(start + chrono::Duration::hours(1))).unwrap();
+        assert!((rebate - 1.0).abs() < 1e-9);
+
+        // The same fill after the campaign has ended earns nothing
+        let rebate = manager.record_maker_fill(&fill_at(start + chrono::Duration::days(2))).unwrap();
+        assert_eq!(rebate, 0.0);
+
+        // Booked as its own line; trading PnL and positions are unaffected
+        let summary = manager.get_summary();
+        assert!((summary.rebate_pnl - 1.0).abs() < 1e-9);
+        assert_eq!(summary.total_pnl, 0.0);
+        assert!((summary.total_value - 200.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_resolution_reversed_and_re_resolved() {
+        let market_id = MarketId::random();
+        let mut manager = manager_with_both_sides(market_id);
+
+        manager.process_event(&resolved(market_id, "YES")).unwrap();
+        let portfolio = manager.portfolio();
+        assert!((portfolio.total_pnl() - 90.0).abs() < 1e-9);
+        assert_eq!(portfolio.num_positions(), 0);
+        assert_eq!(portfolio.settlements(market_id).len(), 2);
+        let yes = portfolio
+            .settlements(market_id)
+            .iter()
+            .find(|s| s.position.outcome_id == "YES")
+            .unwrap();
+        assert!((yes.payout - 250.0).abs() < 1e-9);
+        assert_eq!(yes.winning_outcome.as_deref(), Some("YES"));
+
+        // Reversing a resolution that never happened is rejected
+        let wrong = MarketEvent::ResolutionReversed {
+            market_id,
+            previous_outcome: "NO".into(),
+        };
+        assert!(manager.process_event(&wrong).is_err());
+        let never_resolved = MarketEvent::ResolutionReversed {
+            market_id: MarketId::random(),
+            previous_outcome: "YES".into(),
+        };
+        let error = manager.process_event(&never_resolved).unwrap_err();
+        assert!(error.to_string().contains("never resolved"));
+
+        let reversed = MarketEvent::ResolutionReversed {
+            market_id,
+            previous_outcome: "YES".into(),
+        };
+        manager.process_event(&reversed).unwrap();
+        let portfolio = manager.portfolio();
+        assert!(portfolio.total_pnl().abs() < 1e-9);
+        assert_eq!(portfolio.num_positions(), 2);
+        // The original settlements stay on record, marked reversed
+        assert_eq!(portfolio.settlements(market_id).len(), 2);
+        assert!(portfolio.settlements(market_id).iter().all(|s| s.reversed_at.is_some()));
+        assert_eq!(portfolio.active_settlements(market_id).count(), 0);
+        // Reversing twice is rejected like any other unresolved market
+        assert!(manager.process_event(&reversed).is_err());
+        let portfolio = manager.portfolio();
+        assert!(portfolio
+            .positions()
+            .values()
+            .all(|p| p.state == PositionState::Open));
+
+        manager.process_event(&resolved(market_id, "NO")).unwrap();
+        let portfolio = manager.portfolio();
+        assert!((portfolio.total_pnl() + 60.0).abs() < 1e-9);
+        assert_eq!(portfolio.num_positions(), 0);
+        assert_eq!(portfolio.settlements(market_id).len(), 4);
+        assert_eq!(portfolio.active_settlements(market_id).count(), 2);
+        assert!(portfolio
+            .active_settlements(market_id)
+            .all(|s| s.winning_outcome.as_deref() == Some("NO")));
+    }
+
+    #[test]
+    fn test_resolved_market_invalidated_with_refund() {
+        let market_id = MarketId::random();
+        let mut manager = manager_with_both_sides(market_id);
+        manager.process_event(&resolved(market_id, "YES")).unwrap();
+
+        let invalidated = MarketEvent::MarketInvalidated {
+            market_id,
+            refund_policy: RefundPolicy::FiftyFifty,
+        };
+        manager.process_event(&invalidated).unwrap();
+
+        // YES pays 125 on 100 invested, NO pays 50 on 60 invested
+        let portfolio = manager.portfolio();
+        assert!((portfolio.total_pnl() - 15.0).abs() < 1e-9);
+        assert_eq!(portfolio.num_positions(), 0);
+        assert_eq!(portfolio.settlements(market_id).len(), 4);
+        let settlements: Vec<_> = portfolio.active_settlements(market_id).collect();
+        assert_eq!(settlements.len(), 2);
+        assert!(settlements.iter().all(|s| s.winning_outcome.is_none()));
+
+        let other_market = MarketId::random();
+        let mut manager = manager_with_both_sides(other_market);
+        let invalidated = MarketEvent::MarketInvalidated {
+            market_id: other_market,
+            refund_policy: RefundPolicy::AtCost,
+        };
+        manager.process_event(&invalidated).unwrap();
+        assert!(manager.portfolio().total_pnl().abs() < 1e-9);
+        assert_eq!(manager.portfolio().num_positions(), 0);
+    }
+
+    /// Record `n` PnLs of a noisy equity curve from `value` at irregular
+    /// times, up to eight hours apart, starting at `at`; returns the hourly
+    /// returns between the records' buckets, zeros for the hours without any
+    fn pnl_series(
+        portfolio: &mut Portfolio,
+        n: usize,
+        seed: u64,
+        at: &mut DateTime<Utc>,
+        value: &mut f64,
+    ) -> Vec<f64> {
+        let mut state = seed;
+        let mut uniform = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        };
+
+        let mut closes: Vec<(i64, f64)> = Vec::new();
+        for _ in 0..n {
+            *at += Duration::seconds((uniform() * 8.0 * 3600.0) as i64);
+            let pnl = (uniform() - 0.5) * 20.0;
+            *value += pnl;
+            portfolio.record_pnl_at(*at, pnl, *value);
+
+            let hour = at.timestamp().div_euclid(3600);
+            match closes.last_mut() {
+                Some(close) if close.0 == hour => close.1 = *value,
+                _ => closes.push((hour, *value)),
+            }
+        }
+        closes
+            .windows(2)
+            .flat_map(|pair| {
+                let empty = (pair[1].0 - pair[0].0 - 1) as usize;
+                std::iter::repeat_n(0.0, empty).chain(std::iter::once(pair[1].1 / pair[0].1 - 1.0))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_streaming_metrics_match_exact() {
+        let mut streaming = Portfolio::new();
+        streaming.set_return_sampling(ReturnBucket::Hourly, 0.05);
+        streaming.enable_streaming_metrics();
+        let (mut at, mut value) = (DateTime::from_timestamp(1_600_000_000, 0).unwrap(), 1000.0);
+        let returns = pnl_series(&mut streaming, 800, 17, &mut at, &mut value);
+
+        let fast = streaming.calculate_metrics();
+        let exact = streaming.calculate_exact_metrics();
+        let (fast_sharpe, exact_sharpe) = (fast.sharpe_ratio.unwrap(), exact.sharpe_ratio.unwrap());
+        assert!((fast_sharpe - exact_sharpe).abs() < 1e-9 * exact_sharpe.abs().max(1.0));
+        let (fast_sortino, exact_sortino) = (fast.sortino_ratio.unwrap(), exact.sortino_ratio.unwrap());
+        assert!((fast_sortino - exact_sortino).abs() < 1e-9 * exact_sortino.abs().max(1.0));
+        assert_eq!(fast.max_drawdown, exact.max_drawdown);
+
+        // Seeding from existing history gives the same estimators
+        let mut seeded = streaming.clone();
+        seeded.enable_streaming_metrics();
+        assert_eq!(seeded.calculate_metrics().var_95, fast.var_95);
+
+        // Past the 1000 record cap Sharpe stays windowed like the exact metric,
+        // while VaR estimates the quantiles of every hourly return
+        let more = pnl_series(&mut streaming, 2200, 23, &mut at, &mut value);
+        let returns: Vec<f64> = returns.into_iter().chain(more).collect();
+        let fast = streaming.calculate_metrics();
+        let exact = streaming.calculate_exact_metrics();
+        let (fast_sharpe, exact_sharpe) = (fast.sharpe_ratio.unwrap(), exact.sharpe_ratio.unwrap());
+        assert!((fast_sharpe - exact_sharpe).abs() < 1e-6 * exact_sharpe.abs().max(1.0));
+        let (fast_sortino, exact_sortino) = (fast.sortino_ratio.unwrap(), exact.sortino_ratio.unwrap());
+        assert!((fast_sortino - exact_sortino).abs() < 1e-6 * exact_sortino.abs().max(1.0));
+
+        let mut sorted = returns.clone();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+        let std_dev = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / returns.len() as f64).sqrt();
+        let quantile = |p: f64| sorted[(p * sorted.len() as f64) as usize];
+        let tail = &sorted[..sorted.len() / 20];
+        let expected_shortfall = tail.iter().sum::<f64>() / tail.len() as f64;
+
+        assert!((fast.var_99_pct.unwrap() - quantile(0.01)).abs() < 0.1 * std_dev);
+        assert!((fast.expected_shortfall_pct.unwrap() - expected_shortfall).abs() < 0.1 * std_dev);
+
+        // Priced at the latest portfolio value, the same as the exact metric
+        let value = fast.var_95.unwrap() / fast.var_95_pct.unwrap();
+        assert!((value * exact.var_95_pct.unwrap() - exact.var_95.unwrap()).abs() < 1e-9 * value);
+    }
+
+    #[test]
+    fn test_irregular_samples_resampled_to_daily_returns() {
+        // Daily returns alternate +2% and -1%, except day 10 which has no
+        // records and so carries day 9's close forward
+        let returns: Vec<f64> = (1..=20)
+            .map(|day| match day {
+                10 => 0.0,
+                day if day % 2 == 1 => 0.02,
+                _ => -0.01,
+            })
+            .collect();
+
+        let mut portfolio = Portfolio::new();
+        portfolio.set_return_sampling(ReturnBucket::Daily, 0.0365);
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        let mut close = 1000.0;
+        for day in 0..=20usize {
+            if day > 0 {
+                close *= 1.0 + returns[day - 1];
+            }
+            if day == 10 {
+                continue;
+            }
+            // One to three records a day at uneven hours; only the last is the close
+            let hours = [2, 9, 23];
+            let count = 1 + day % 3;
+            for (i, hour) in hours[3 - count..].iter().enumerate() {
+                let value = if i + 1 == count { close } else { close * (1.0 + 0.05 * (i + 1) as f64) };
+                portfolio.record_pnl_at(start + Duration::days(day as i64) + Duration::hours(*hour), 0.0, value);
+            }
+        }
+
+        let n = returns.len() as f64;
+        let mean = returns.iter().sum::<f64>() / n;
+        let std_dev = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n).sqrt();
+        let risk_free = 0.0365 / 365.0;
+        let downside = (returns.iter().map(|r| (r - risk_free).min(0.0).powi(2)).sum::<f64>() / n).sqrt();
+        let sharpe = (mean - risk_free) / std_dev * 365f64.sqrt();
+        let sortino = (mean - risk_free) / downside * 365f64.sqrt();
+
+        let exact = portfolio.calculate_exact_metrics();
+        assert!((exact.sharpe_ratio.unwrap() - sharpe).abs() < 1e-9, "{:?} vs {}", exact.sharpe_ratio, sharpe);
+        assert!((exact.sortino_ratio.unwrap() - sortino).abs() < 1e-9, "{:?} vs {}", exact.sortino_ratio, sortino);
+
+        portfolio.enable_streaming_metrics();
+        let fast = portfolio.calculate_metrics();
+        assert!((fast.sharpe_ratio.unwrap() - sharpe).abs() < 1e-9);
+        assert!((fast.sortino_ratio.unwrap() - sortino).abs() < 1e-9);
+
+        // The same records bucketed hourly are a different series
+        portfolio.set_return_sampling(ReturnBucket::Hourly, 0.0365);
+        assert!((portfolio.calculate_metrics().sharpe_ratio.unwrap() - sharpe).abs() > 0.1);
+        assert!((portfolio.calculate_exact_metrics().sharpe_ratio.unwrap() - sharpe).abs() > 0.1);
+    }
+
+    #[test]
+    fn test_parametric_var_on_a_short_history() {
+        let config = RiskConfig {
+            metrics: config::MetricsConfig {
+                var_method: VaRMethod::Parametric,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut manager = PortfolioRiskManager::with_config(config).unwrap();
+
+        // Five daily returns, +2% and -2% in turn
+        let start = Utc::now() - Duration::days(10);
+        let mut value = 1000.0;
+        for day in 0..=5 {
+            if day > 0 {
+                value *= if day % 2 == 1 { 1.02 } else { 0.98 };
+            }
+            manager.portfolio.record_pnl_at(start + Duration::days(day), 0.0, value);
+        }
+
+        let (mean, std_dev) = (0.004, (0.0004f64 - 0.004 * 0.004).sqrt());
+        let metrics = manager.get_metrics();
+        let var_95_pct = metrics.var_95_pct.unwrap();
+        assert!((var_95_pct - (mean - 1.644_853_6 * std_dev)).abs() < 1e-6);
+        assert!((metrics.var_95.unwrap() - var_95_pct * value).abs() < 1e-9);
+        assert!(metrics.expected_shortfall_pct.unwrap() < var_95_pct);
+
+        // The same returns in streaming mode
+        manager.portfolio.enable_streaming_metrics();
+        assert!((manager.get_metrics().var_95_pct.unwrap() - var_95_pct).abs() < 1e-12);
+
+        // Too few for historical simulation
+        manager.portfolio.set_var_method(VaRMethod::Historical);
+        assert!(manager.get_metrics().var_95.is_none());
+        assert!(manager.portfolio.calculate_exact_metrics().var_95.is_none());
+    }
+
+    #[test]
+    fn test_correlated_cluster_exposure_limit() {
+        let mut manager = PortfolioRiskManager::new().unwrap();
+        let (a, b, c, d) = (MarketId::random(), MarketId::random(), MarketId::random(), MarketId::random());
+
+        // A, B and C trend up together; D swings around 50%
+        let tick = |market_id, price| {
+            MarketEvent::PriceTick(common::PriceTick {
+                market_id,
+                outcome_id: "YES".into(),
+                price,
+                volume_24h: 0.0,
+                liquidity: 1000.0,
+                timestamp: Utc::now(),
+            })
+        };
+        for t in 0..20 {
+            let t = t as f64;
+            let noise = if t as i64 % 3 == 0 { 0.001 } else { -0.0005 };
+            manager.process_event(&tick(a, 0.30 + 0.01 * t)).unwrap();
+            manager.process_event(&tick(b, 0.20 + 0.012 * t + noise)).unwrap();
+            manager.process_event(&tick(c, 0.40 + 0.009 * t - noise)).unwrap();
+            manager.process_event(&tick(d, if t as i64 % 2 == 0 { 0.45 } else { 0.55 })).unwrap();
+        }
+
+        // $100 in each of A and B
+        manager.process_event(&buy(a, "YES", 0.5, 200.0)).unwrap();
+        manager.process_event(&buy(b, "YES", 0.5, 200.0)).unwrap();
+
+        // $80 more in C would put $280 on one bet, over the $250 limit
+        match manager.evaluate_trade(c, "YES", common::OrderSide::Buy, 0.5, 160.0) {
+            Err(RiskViolation::CorrelatedExposureExceeded { market, correlated, current, proposed, limit }) => {
+                assert_eq!(market, format!("{}/YES", c));
+                let members: std::collections::HashSet<String> = correlated.iter().map(|(m, _)| m.clone()).collect();
+                assert_eq!(members, [format!("{}/YES", a), format!("{}/YES", b)].into());
+                assert!(correlated.iter().all(|(_, correlation)| *correlation > 0.99));
+                assert_eq!((current, proposed, limit), (200.0, 80.0, 250.0));
+            }
+            other => panic!("Expected correlated exposure violation, got {:?}", other),
+        }
+
+        let checker = &manager.risk_checker;
+        let buy_side = common::OrderSide::Buy;
+        assert!(checker.check_correlated_trade(c, "YES", buy_side, 40.0, &manager.portfolio).is_ok());
+        assert!(checker.check_correlated_trade(c, "YES", common::OrderSide::Sell, 80.0, &manager.portfolio).is_ok());
+        assert!(checker.check_correlated_trade(d, "YES", buy_side, 80.0, &manager.portfolio).is_ok());
+    }
+
+    #[test]
+    fn test_strategy_budget_layered_on_global_limits() {
+        let config = RiskConfig {
+            capital: CapitalConfig {
+                total_capital: 1_000.0,
+                budgets: HashMap::from([("momentum".to_string(), 100.0)]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut manager = PortfolioRiskManager::with_config(config).unwrap();
+        let market_id = MarketId::random();
+
+        let MarketEvent::Trade(trade) = buy(market_id, "YES", 0.5, 120.0) else { unreachable!() };
+        manager.process_strategy_trade("momentum", &trade).unwrap();
+        assert_eq!(manager.portfolio().total_value(), 60.0);
+
+        // 40 left in the account while the portfolio limits have room
+        let result = manager.evaluate_strategy_trade("momentum", market_id, "NO", common::OrderSide::Buy, 0.5, 100.0);
+        assert!(matches!(
+            result,
+            Err(RiskViolation::StrategyBudgetExceeded { available, .. }) if available == 40.0
+        ));
+        assert!(manager
+            .risk_checker
+            .check_trade(common::OrderSide::Buy, 50.0, manager.portfolio.category(market_id), &manager.portfolio)
+            .is_ok());
+
+        // Raising the budget at runtime unblocks it
+        manager.capital_accounts_mut().set_budget("momentum", 200.0, Some("ops")).unwrap();
+        assert!(manager.capital_accounts().check_buying_power("momentum", 50.0).is_ok());
+        let result = manager.evaluate_strategy_trade("momentum", market_id, "NO", common::OrderSide::Buy, 0.5, 100.0);
+        assert!(!matches!(result, Err(RiskViolation::StrategyBudgetExceeded { .. })));
+
+        // Resolution credits the account and shows up in snapshots
+        manager.process_event(&resolved(market_id, "YES")).unwrap();
+        let report = manager.capital_accounts().report("momentum").unwrap();
+        assert_eq!(report.realized_pnl, 60.0);
+        assert_eq!(report.roi, 0.3);
+        let snapshot = PortfolioSnapshot::capture(&manager, 1);
+        assert_eq!(snapshot.capital_accounts[0].strategy_id, "momentum");
+    }
 }
@@ -20,17 +20,35 @@ pub struct RiskMetrics {
     /// Maximum drawdown (as a percentage, 0.0 to 1.0)
     pub max_drawdown: f64,
 
-    /// Sharpe ratio (annualized)
+    /// Sharpe ratio (annualized) of bucketed portfolio returns
     pub sharpe_ratio: Option<f64>,
 
-    /// Value at Risk at 95% confidence level
+    /// Sortino ratio (annualized), penalizing only returns below the
+    /// risk-free rate; None until a bucket falls short of it
+    #[serde(default)]
+    pub sortino_ratio: Option<f64>,
+
+    /// Value at Risk at 95% confidence level, in USD at the latest
+    /// portfolio value
     pub var_95: Option<f64>,
 
-    /// Value at Risk at 99% confidence level
+    /// Value at Risk at 99% confidence level, in USD
     pub var_99: Option<f64>,
 
-    /// Expected Shortfall (average of worst 5%)
+    /// Expected Shortfall (average of worst 5%), in USD
     pub expected_shortfall: Option<f64>,
+
+    /// VaR (95%) as a fraction of portfolio value
+    #[serde(default)]
+    pub var_95_pct: Option<f64>,
+
+    /// VaR (99%) as a fraction of portfolio value
+    #[serde(default)]
+    pub var_99_pct: Option<f64>,
+
+    /// Expected Shortfall as a fraction of portfolio value
+    #[serde(default)]
+    pub expected_shortfall_pct: Option<f64>,
 }
 
 impl RiskMetrics {
@@ -43,9 +61,13 @@ impl RiskMetrics {
             realized_pnl: 0.0,
             max_drawdown: 0.0,
             sharpe_ratio: None,
+            sortino_ratio: None,
             var_95: None,
             var_99: None,
             expected_shortfall: None,
+            var_95_pct: None,
+            var_99_pct: None,
+            expected_shortfall_pct: None,
         }
     }
 
@@ -75,17 +97,41 @@ impl Default for RiskMetrics {
     }
 }
 
+/// Standard normal quantile at 5%
+const Z_95: f64 = -1.644_853_626_951_472;
+
+/// Standard normal quantile at 1%
+const Z_99: f64 = -2.326_347_874_040_841;
+
+/// Mean of the standard normal below its 5% quantile, `-φ(z) / 0.05`
+const NORMAL_TAIL_MEAN_95: f64 = -2.062_712_807_507_426;
+
 /// Value at Risk calculation result
+///
+/// Losses are negative. The fractions are quantiles of portfolio returns;
+/// the USD figures apply them to the portfolio value they were taken at.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VaRResult {
-    /// VaR at 95% confidence level
+    /// VaR at 95% confidence level, in USD
     pub var_95: Option<f64>,
 
-    /// VaR at 99% confidence level
+    /// VaR at 99% confidence level, in USD
     pub var_99: Option<f64>,
 
-    /// Expected Shortfall (Conditional VaR)
+    /// Expected Shortfall (Conditional VaR), in USD
     pub expected_shortfall: Option<f64>,
+
+    /// VaR at 95% as a fraction of portfolio value
+    #[serde(default)]
+    pub var_95_pct: Option<f64>,
+
+    /// VaR at 99% as a fraction of portfolio value
+    #[serde(default)]
+    pub var_99_pct: Option<f64>,
+
+    /// Expected Shortfall as a fraction of portfolio value
+    #[serde(default)]
+    pub expected_shortfall_pct: Option<f64>,
 }
 
 impl VaRResult {
@@ -95,8 +141,62 @@ impl VaRResult {
             var_95: None,
             var_99: None,
             expected_shortfall: None,
+            var_95_pct: None,
+            var_99_pct: None,
+            expected_shortfall_pct: None,
         }
     }
+
+    /// Result from return quantiles, priced at `portfolio_value`
+    pub fn from_fractions(
+        var_95_pct: Option<f64>,
+        var_99_pct: Option<f64>,
+        expected_shortfall_pct: Option<f64>,
+        portfolio_value: f64,
+    ) -> Self {
+        let usd = |pct: Option<f64>| pct.map(|pct| pct * portfolio_value);
+        Self {
+            var_95: usd(var_95_pct),
+            var_99: usd(var_99_pct),
+            expected_shortfall: usd(expected_shortfall_pct),
+            var_95_pct,
+            var_99_pct,
+            expected_shortfall_pct,
+        }
+    }
+
+    /// Historical simulation over `returns`: the 5% and 1% order statistics
+    /// and the mean of the worst 5%
+    pub fn historical(returns: &[f64], portfolio_value: f64) -> Self {
+        if returns.is_empty() {
+            return Self::empty();
+        }
+
+        let mut sorted_returns = returns.to_vec();
+        sorted_returns.sort_by(|a, b| a.total_cmp(b));
+
+        let var_index = (0.05 * sorted_returns.len() as f64) as usize;
+        let var_95 = sorted_returns.get(var_index).copied();
+
+        let var_99_index = (0.01 * sorted_returns.len() as f64) as usize;
+        let var_99 = sorted_returns.get(var_99_index).copied();
+
+        let tail_size = (sorted_returns.len() as f64 * 0.05).ceil() as usize;
+        let expected_shortfall = Some(sorted_returns.iter().take(tail_size).sum::<f64>() / tail_size as f64);
+
+        Self::from_fractions(var_95, var_99, expected_shortfall, portfolio_value)
+    }
+
+    /// Variance-covariance estimate, assuming normally distributed returns
+    /// with the given mean and standard deviation
+    pub fn parametric(mean: f64, std_dev: f64, portfolio_value: f64) -> Self {
+        Self::from_fractions(
+            Some(mean + Z_95 * std_dev),
+            Some(mean + Z_99 * std_dev),
+            Some(mean + NORMAL_TAIL_MEAN_95 * std_dev),
+            portfolio_value,
+        )
+    }
 }
 
 impl Default for VaRResult {
@@ -467,4 +567,33 @@ mod tests {
         let score = RiskScore::from_metrics(&metrics);
         assert!(score >= RiskScore::High);
     }
+
+    #[test]
+    fn test_historical_vs_parametric_var_on_uniform_returns() {
+        // 100 returns evenly spread over (-2%, 2%), shuffled
+        let a = 0.02;
+        let returns: Vec<f64> = (0..100)
+            .map(|i| (i * 37) % 100)
+            .map(|k| a * ((2 * k + 1) as f64 / 100.0 - 1.0))
+            .collect();
+        let mean = returns.iter().sum::<f64>() / 100.0;
+        let std_dev = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / 100.0).sqrt();
+
+        // Historical VaR is the sixth (95%) and second (99%) worst return,
+        // and ES the mean of the five worst
+        let historical = VaRResult::historical(&returns, 1000.0);
+        assert!((historical.var_95_pct.unwrap() + 0.89 * a).abs() < 1e-12);
+        assert!((historical.var_99_pct.unwrap() + 0.97 * a).abs() < 1e-12);
+        assert!((historical.expected_shortfall_pct.unwrap() + 0.95 * a).abs() < 1e-12);
+        assert!((historical.var_95.unwrap() - 1000.0 * historical.var_95_pct.unwrap()).abs() < 1e-9);
+
+        // A uniform's tails are thinner than the normal the parametric VaR
+        // assumes: sd = a/sqrt(3), so VaR 95% = -0.950a against -0.89a and ES = -1.191a
+        let parametric = VaRResult::parametric(mean, std_dev, 1000.0);
+        assert!((parametric.var_95_pct.unwrap() + 0.9496 * a).abs() < 1e-4 * a);
+        assert!((parametric.var_99_pct.unwrap() + 1.3431 * a).abs() < 1e-4 * a);
+        assert!((parametric.expected_shortfall_pct.unwrap() + 1.1908 * a).abs() < 1e-4 * a);
+        assert!(parametric.var_95_pct.unwrap() < historical.var_95_pct.unwrap());
+        assert!((parametric.expected_shortfall.unwrap() - 1000.0 * parametric.expected_shortfall_pct.unwrap()).abs() < 1e-9);
+    }
 }
@@ -1,28 +1,58 @@
 //! Portfolio management and position tracking
 
+use crate::config::{ReturnBucket, VaRMethod};
 use crate::metrics::{RiskMetrics, VaRResult};
 use chrono::{DateTime, Utc};
+use common::{MarketId, OutcomeId, P2Quantile, P2TailMean, RefundPolicy, RunningMoments};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use uuid::Uuid;
+use std::collections::{HashMap, VecDeque};
 
 /// Current portfolio with all positions
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Portfolio {
     /// All open positions by (market_id, outcome_id)
-    positions: HashMap<(Uuid, String), Position>,
+    #[serde(with = "position_list")]
+    positions: HashMap<(MarketId, OutcomeId), Position>,
 
     /// Historical PnL data for metrics calculation
-    pnl_history: Vec<PnLRecord>,
+    pnl_history: VecDeque<PnLRecord>,
 
     /// Total realized PnL
     total_realized_pnl: f64,
 
+    /// Liquidity rebates accrued on maker fills, kept apart from trading PnL
+    #[serde(default)]
+    rebate_pnl: f64,
+
     /// Portfolio creation timestamp
     created_at: DateTime<Utc>,
 
     /// Category mapping for positions
-    categories: HashMap<Uuid, String>,
+    categories: HashMap<MarketId, String>,
+
+    /// Settlement records of resolved positions by market, kept so a
+    /// resolution can be reversed or re-settled. Reversed records stay, marked
+    /// with `reversed_at`
+    #[serde(default)]
+    settlements: HashMap<MarketId, Vec<Settlement>>,
+
+    /// How each settled market was settled, positions or not: the winning
+    /// outcome, or None if invalidated
+    #[serde(default)]
+    settled_markets: HashMap<MarketId, Option<String>>,
+
+    /// negRisk group membership by market
+    #[serde(default)]
+    neg_risk_groups: HashMap<MarketId, NegRiskMember>,
+
+    /// How returns are sampled for Sharpe, Sortino and VaR
+    #[serde(skip)]
+    sampling: ReturnSampling,
+
+    /// Incremental metric estimators, when enabled; not serialized, and
+    /// `PortfolioRiskManager::restore` re-enables them from `RiskConfig`
+    #[serde(skip)]
+    streaming: Option<StreamingMetrics>,
 }
 
 impl Portfolio {
@@ -30,22 +60,76 @@ impl Portfolio {
     pub fn new() -> Self {
         Self {
             positions: HashMap::new(),
-            pnl_history: Vec::new(),
+            pnl_history: VecDeque::new(),
             total_realized_pnl: 0.0,
+            rebate_pnl: 0.0,
             created_at: Utc::now(),
             categories: HashMap::new(),
+            settlements: HashMap::new(),
+            settled_markets: HashMap::new(),
+            neg_risk_groups: HashMap::new(),
+            sampling: ReturnSampling::default(),
+            streaming: None,
         }
     }
 
+    /// Resample portfolio value to `bucket` before taking returns, and
+    /// measure Sharpe and Sortino against `risk_free_rate` (annualized)
+    ///
+    /// Defaults to daily buckets and 5%. Not serialized, so a restored
+    /// portfolio needs it set again.
+    pub fn set_return_sampling(&mut self, bucket: ReturnBucket, risk_free_rate: f64) {
+        self.sampling = ReturnSampling {
+            bucket,
+            risk_free_rate,
+            ..self.sampling
+        };
+        if self.streaming.is_some() {
+            self.enable_streaming_metrics();
+        }
+    }
+
+    /// Estimate VaR and expected shortfall of the bucket returns by
+    /// `method`; historical by default, and not serialized either
+    pub fn set_var_method(&mut self, method: VaRMethod) {
+        self.sampling.var_method = method;
+        if self.streaming.is_some() {
+            self.enable_streaming_metrics();
+        }
+    }
+
+    /// Compute metrics incrementally instead of from the full PnL history
+    ///
+    /// Each recorded PnL then costs O(1) and `calculate_metrics` no longer
+    /// sorts or scans the history. The Sharpe ratio is unchanged up to
+    /// rounding. Max drawdown covers the whole life of the portfolio rather
+    /// than the last 1000 records. Historical VaR and expected shortfall are
+    /// P² estimates over every completed bucket return rather than exact
+    /// over the last 100 (see `common::P2Quantile` for the error bounds);
+    /// parametric VaR is unchanged. The estimators are seeded from the
+    /// current history and are not serialized.
+    pub fn enable_streaming_metrics(&mut self) {
+        let mut streaming = StreamingMetrics::new(self.sampling);
+        for record in &self.pnl_history {
+            streaming.record(record);
+        }
+        self.streaming = Some(streaming);
+    }
+
+    /// Whether metrics are computed incrementally
+    pub fn streaming_metrics_enabled(&self) -> bool {
+        self.streaming.is_some()
+    }
+
     /// Add or update a position (buy)
     pub fn add_position(
         &mut self,
-        market_id: Uuid,
+        market_id: MarketId,
         outcome_id: &str,
         value: f64,
         price: f64,
     ) -> anyhow::Result<()> {
-        let key = (market_id, outcome_id.to_string());
+        let key = (market_id, OutcomeId::from(outcome_id));
 
         match self.positions.get_mut(&key) {
             Some(position) => {
@@ -65,12 +149,12 @@ impl Portfolio {
     /// Remove from position (sell)
     pub fn remove_position(
         &mut self,
-        market_id: Uuid,
+        market_id: MarketId,
         outcome_id: &str,
         value: f64,
         price: f64,
     ) -> anyhow::Result<()> {
-        let key = (market_id, outcome_id.to_string());
+        let key = (market_id, OutcomeId::from(outcome_id));
 
         let pnl = match self.positions.get_mut(&key) {
             Some(position) => {
@@ -99,8 +183,8 @@ impl Portfolio {
     }
 
     /// Update current price for a position
-    pub fn update_price(&mut self, market_id: Uuid, outcome_id: &str, price: f64) {
-        let key = (market_id, outcome_id.to_string());
+    pub fn update_price(&mut self, market_id: MarketId, outcome_id: &str, price: f64) {
+        let key = (market_id, OutcomeId::from(outcome_id));
 
         if let Some(position) = self.positions.get_mut(&key) {
             position.current_price = price;
@@ -109,54 +193,303 @@ impl Portfolio {
     }
 
     /// Resolve a market and calculate final PnL
+    ///
+    /// Each winning share pays out 1.0. A settlement record is kept per
+    /// position so the resolution can later be reversed.
     pub fn resolve_market(
         &mut self,
-        market_id: Uuid,
+        market_id: MarketId,
         winning_outcome_id: &str,
     ) -> anyhow::Result<f64> {
+        let pnl = self.settle_market(market_id, Some(winning_outcome_id), |position| {
+            if position.outcome_id == winning_outcome_id {
+                position.shares()
+            } else {
+                0.0
+            }
+        });
+        Ok(pnl)
+    }
+
+    /// Reverse a previous resolution of a market
+    ///
+    /// Settled positions are reopened and a compensating PnL entry is booked
+    /// for each, so the history is appended to rather than rewritten; the
+    /// settlement records stay, marked as reversed. Returns the PnL taken
+    /// back out of the realized total.
+    pub fn reverse_resolution(
+        &mut self,
+        market_id: MarketId,
+        previous_outcome_id: &str,
+    ) -> anyhow::Result<f64> {
+        // Portfolios saved before settled markets were tracked only have their settlements
+        let settled = match self.settled_markets.get(&market_id) {
+            Some(outcome) => Some(outcome.clone()),
+            None => self
+                .active_settlements(market_id)
+                .next()
+                .map(|s| s.winning_outcome.clone()),
+        };
+
+        match settled {
+            None => Err(anyhow::anyhow!(
+                "Market {} was never resolved, there is nothing to reverse",
+                market_id
+            )),
+            Some(outcome) if outcome.as_deref() != Some(previous_outcome_id) => Err(anyhow::anyhow!(
+                "Market {} was settled on {:?}, not {}",
+                market_id,
+                outcome,
+                previous_outcome_id
+            )),
+            Some(_) => self.unsettle_market(market_id),
+        }
+    }
+
+    /// Settle an invalidated market under its refund policy
+    ///
+    /// A market that was already resolved is reopened first, so the refund
+    /// replaces the earlier settlement. Returns the net change in realized PnL.
+    pub fn invalidate_market(
+        &mut self,
+        market_id: MarketId,
+        refund_policy: RefundPolicy,
+    ) -> anyhow::Result<f64> {
+        let reversed = self.unsettle_market(market_id)?;
+
+        let pnl = self.settle_market(market_id, None, |position| match refund_policy {
+            RefundPolicy::FiftyFifty => position.shares() * 0.5,
+            RefundPolicy::AtCost => position.investment,
+        });
+
+        Ok(pnl - reversed)
+    }
+
+    /// Close every position of a market at the payout given by `payout`
+    fn settle_market(
+        &mut self,
+        market_id: MarketId,
+        winning_outcome_id: Option<&str>,
+        payout: impl Fn(&Position) -> f64,
+    ) -> f64 {
         let mut total_pnl = 0.0;
 
         // Collect all positions for this market
         let market_positions: Vec<_> = self
             .positions
-            .iter()
-            .filter(|((id, _), _)| *id == market_id)
-            .map(|(key, _)| key.clone())
+            .keys()
+            .filter(|(id, _)| *id == market_id)
+            .cloned()
             .collect();
 
         for key in market_positions {
-            let outcome_id = key.1.clone();
-
-            if let Some(position) = self.positions.remove(&key) {
-                let pnl = if outcome_id == winning_outcome_id {
-                    // Winning position
-                    position.unrealized_pnl + position.investment
-                } else {
-                    // Losing position
-                    -position.investment
-                };
+            if let Some(mut position) = self.positions.remove(&key) {
+                let payout = payout(&position);
+                let pnl = payout - position.investment;
 
                 total_pnl += pnl;
                 self.record_pnl(pnl);
 
                 tracing::info!(
                     market_id = %market_id,
-                    outcome_id = %outcome_id,
+                    outcome_id = %position.outcome_id,
                     pnl = pnl,
                     "Position resolved"
                 );
+
+                position.state = PositionState::Closed;
+                position.updated_at = Utc::now();
+                self.settlements.entry(market_id).or_default().push(Settlement {
+                    position,
+                    winning_outcome: winning_outcome_id.map(|o| o.to_string()),
+                    payout,
+                    pnl,
+                    settled_at: Utc::now(),
+                    reversed_at: None,
+                });
             }
         }
 
+        self.settled_markets
+            .insert(market_id, winning_outcome_id.map(|o| o.to_string()));
         self.total_realized_pnl += total_pnl;
-        Ok(total_pnl)
+        total_pnl
+    }
+
+    /// Reopen the settled positions of a market, booking compensating entries
+    /// and marking the settlements reversed
+    fn unsettle_market(&mut self, market_id: MarketId) -> anyhow::Result<f64> {
+        let mut reversed_pnl = 0.0;
+
+        // Each settlement is marked as soon as it is reopened, so a failure
+        // part way leaves the rest to a retry
+        let count = self.settlements.get(&market_id).map_or(0, Vec::len);
+        for i in 0..count {
+            let settlement = &self.settlements[&market_id][i];
+            if settlement.reversed_at.is_some() {
+                continue;
+            }
+            let pnl = settlement.pnl;
+            let mut position = settlement.position.clone();
+            position.state = PositionState::Open;
+            position.updated_at = Utc::now();
+
+            let key = (market_id, position.outcome_id.clone());
+            match self.positions.get_mut(&key) {
+                // Bought back in after the resolution
+                Some(open) => open.update_on_buy(position.investment, position.avg_entry_price)?,
+                None => {
+                    self.positions.insert(key, position);
+                }
+            }
+
+            if let Some(settlements) = self.settlements.get_mut(&market_id) {
+                settlements[i].reversed_at = Some(Utc::now());
+            }
+            reversed_pnl += pnl;
+            self.total_realized_pnl -= pnl;
+            self.record_pnl(-pnl);
+        }
+
+        self.settled_markets.remove(&market_id);
+        Ok(reversed_pnl)
+    }
+
+    /// Every settlement record of a market, reversed ones included, oldest first
+    pub fn settlements(&self, market_id: MarketId) -> &[Settlement] {
+        self.settlements
+            .get(&market_id)
+            .map(|s| s.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Settlement records of a market's currently settled positions
+    pub fn active_settlements(&self, market_id: MarketId) -> impl Iterator<Item = &Settlement> {
+        self.settlements(market_id)
+            .iter()
+            .filter(|s| s.reversed_at.is_none())
     }
 
     /// Set category for a market
-    pub fn set_category(&mut self, market_id: Uuid, category: String) {
+    pub fn set_category(&mut self, market_id: MarketId, category: String) {
         self.categories.insert(market_id, category);
     }
 
+    /// Get the category of a market, if known
+    pub fn category(&self, market_id: MarketId) -> Option<&str> {
+        self.categories.get(&market_id).map(|c| c.as_str())
+    }
+
+    /// Register a market as a member of a negRisk group.
+    ///
+    /// `no_outcome` is the member's NO outcome id; outcomes named "NO" are
+    /// recognised without it.
+    pub fn set_neg_risk_group(&mut self, market_id: MarketId, group_id: String, no_outcome: Option<OutcomeId>) {
+        self.neg_risk_groups.insert(market_id, NegRiskMember { group_id, no_outcome });
+    }
+
+    /// Get the negRisk group of a market, if any
+    pub fn neg_risk_group(&self, market_id: MarketId) -> Option<&str> {
+        self.neg_risk_groups.get(&market_id).map(|m| m.group_id.as_str())
+    }
+
+    /// Exposure to a negRisk group, netted across its members
+    pub fn group_exposure(&self, group_id: &str) -> GroupExposure {
+        self.projected_group_exposure(group_id, None)
+    }
+
+    /// Exposure to a negRisk group if `value` were bought (or sold, if
+    /// negative) of `outcome_id` in `market_id` at `price`
+    pub fn group_exposure_after(
+        &self,
+        group_id: &str,
+        market_id: MarketId,
+        outcome_id: &str,
+        value: f64,
+        price: f64,
+    ) -> GroupExposure {
+        self.projected_group_exposure(group_id, Some((market_id, outcome_id, value, price)))
+    }
+
+    /// Exactly one member of a negRisk group resolves YES, so a NO share of
+    /// one member pays out whenever any other member wins. The group's
+    /// exposure is its marked value less the payout it is guaranteed in
+    /// every outcome. The registered members are taken to be the whole group.
+    fn projected_group_exposure(
+        &self,
+        group_id: &str,
+        trade: Option<(MarketId, &str, f64, f64)>,
+    ) -> GroupExposure {
+        // Member -> (YES shares, NO shares)
+        let mut shares: HashMap<MarketId, (f64, f64)> = self
+            .neg_risk_groups
+            .iter()
+            .filter(|(_, member)| member.group_id == group_id)
+            .map(|(market_id, _)| (*market_id, (0.0, 0.0)))
+            .collect();
+        let mut gross_value = 0.0;
+
+        let positions = self
+            .positions
+            .values()
+            .map(|p| (p.market_id, p.outcome_id.as_str(), p.current_value(), p.shares()));
+        let trade = trade
+            .filter(|(_, _, _, price)| *price > 0.0)
+            .map(|(market_id, outcome_id, value, price)| (market_id, outcome_id, value, value / price));
+
+        for (market_id, outcome_id, value, held) in positions.chain(trade) {
+            let Some((yes, no)) = shares.get_mut(&market_id) else {
+                continue;
+            };
+            if self.is_no_outcome(market_id, outcome_id) {
+                *no += held;
+            } else {
+                *yes += held;
+            }
+            gross_value += value;
+        }
+
+        let total_no: f64 = shares.values().map(|(_, no)| no).sum();
+        let payouts: Vec<f64> = shares.values().map(|(yes, no)| yes + total_no - no).collect();
+        let min_payout = payouts.iter().copied().fold(f64::INFINITY, f64::min);
+        let max_payout = payouts.iter().copied().fold(0.0, f64::max);
+        let min_payout = if min_payout.is_finite() { min_payout } else { 0.0 };
+
+        GroupExposure {
+            group_id: group_id.to_string(),
+            members: shares.len(),
+            gross_value,
+            min_payout,
+            max_payout,
+            net_exposure: (gross_value - min_payout).max(0.0),
+        }
+    }
+
+    fn is_no_outcome(&self, market_id: MarketId, outcome_id: &str) -> bool {
+        let registered = self
+            .neg_risk_groups
+            .get(&market_id)
+            .and_then(|m| m.no_outcome.as_ref().map(OutcomeId::as_str));
+        registered == Some(outcome_id) || outcome_id.eq_ignore_ascii_case("NO")
+    }
+
+    /// Total exposure, counting each negRisk group at its netted exposure
+    /// rather than the sum of its members
+    pub fn net_exposure(&self) -> f64 {
+        let mut groups: Vec<&str> = self.neg_risk_groups.values().map(|m| m.group_id.as_str()).collect();
+        groups.sort_unstable();
+        groups.dedup();
+
+        let ungrouped: f64 = self
+            .positions
+            .values()
+            .filter(|p| !self.neg_risk_groups.contains_key(&p.market_id))
+            .map(|p| p.current_value())
+            .sum();
+
+        ungrouped + groups.iter().map(|g| self.group_exposure(g).net_exposure).sum::<f64>()
+    }
+
     /// Get total portfolio value
     pub fn total_value(&self) -> f64 {
         self.positions.values().map(|p| p.current_value()).sum()
@@ -172,6 +505,16 @@ impl Portfolio {
         self.total_realized_pnl
     }
 
+    /// Book a liquidity rebate earned on a maker fill
+    pub fn accrue_rebate(&mut self, amount: f64) {
+        self.rebate_pnl += amount;
+    }
+
+    /// Liquidity rebates accrued so far, not included in `total_pnl`
+    pub fn rebate_pnl(&self) -> f64 {
+        self.rebate_pnl
+    }
+
     /// Get current unrealized PnL
     pub fn unrealized_pnl(&self) -> f64 {
         self.positions.values().map(|p| p.unrealized_pnl()).sum()
@@ -197,45 +540,56 @@ impl Portfolio {
     }
 
     /// Get all positions
-    pub fn positions(&self) -> &HashMap<(Uuid, String), Position> {
+    pub fn positions(&self) -> &HashMap<(MarketId, OutcomeId), Position> {
         &self.positions
     }
 
     /// Record PnL for metrics calculation
     fn record_pnl(&mut self, pnl: f64) {
-        self.pnl_history.push(PnLRecord {
-            timestamp: Utc::now(),
+        self.record_pnl_at(Utc::now(), pnl, self.total_value());
+    }
+
+    /// Record PnL and the portfolio value it left, as of `timestamp`
+    pub(crate) fn record_pnl_at(&mut self, timestamp: DateTime<Utc>, pnl: f64, portfolio_value: f64) {
+        let record = PnLRecord {
+            timestamp,
             pnl,
-            portfolio_value: self.total_value(),
-        });
+            portfolio_value,
+        };
+        if let Some(streaming) = &mut self.streaming {
+            streaming.record(&record);
+        }
+        self.pnl_history.push_back(record);
 
         // Keep last 1000 records
         if self.pnl_history.len() > 1000 {
-            self.pnl_history.remove(0);
+            self.pnl_history.pop_front();
+            if let Some(streaming) = &mut self.streaming {
+                streaming.drop_oldest();
+            }
         }
     }
 
-    /// Calculate risk metrics
+    /// Calculate risk metrics, incrementally if streaming metrics are enabled
     pub fn calculate_metrics(&self) -> RiskMetrics {
+        match &self.streaming {
+            Some(streaming) => self.streaming_metrics(streaming),
+            None => self.calculate_exact_metrics(),
+        }
+    }
+
+    /// Calculate risk metrics from the PnL history
+    pub fn calculate_exact_metrics(&self) -> RiskMetrics {
         let total_value = self.total_value();
         let positions_count = self.positions.len();
 
         // Calculate VaR
-        let var_result = if self.pnl_history.len() >= 10 {
-            self.calculate_var(0.95, 100)
-        } else {
-            VaRResult {
-                var_95: None,
-                var_99: None,
-                expected_shortfall: None,
-            }
-        };
+        let var_result = self.calculate_var();
 
         // Calculate max drawdown
         let max_drawdown = self.calculate_max_drawdown();
 
-        // Calculate Sharpe ratio
-        let sharpe_ratio = self.calculate_sharpe_ratio();
+        let (sharpe_ratio, sortino_ratio) = self.calculate_return_ratios();
 
         RiskMetrics {
             total_value,
@@ -244,53 +598,74 @@ impl Portfolio {
             realized_pnl: self.total_realized_pnl,
             max_drawdown,
             sharpe_ratio,
+            sortino_ratio,
             var_95: var_result.var_95,
             var_99: var_result.var_99,
             expected_shortfall: var_result.expected_shortfall,
+            var_95_pct: var_result.var_95_pct,
+            var_99_pct: var_result.var_99_pct,
+            expected_shortfall_pct: var_result.expected_shortfall_pct,
         }
     }
 
-    /// Calculate VaR using historical method
-    fn calculate_var(&self, confidence: f64, samples: usize) -> VaRResult {
-        if self.pnl_history.is_empty() {
-            return VaRResult {
-                var_95: None,
-                var_99: None,
-                expected_shortfall: None,
-            };
-        }
-
-        let len = self.pnl_history.len().min(samples);
-        let returns: Vec<f64> = self.pnl_history
-            .iter()
-            .rev()
-            .take(len)
-            .map(|r| r.pnl)
-            .collect();
-
-        let mut sorted_returns = returns.clone();
-        sorted_returns.sort_by(|a, b| a.partial_cmp(b).unwrap());
-
-        let var_index = ((1.0 - confidence) * sorted_returns.len() as f64) as usize;
-        let var_95 = sorted_returns.get(var_index).copied();
+    fn streaming_metrics(&self, streaming: &StreamingMetrics) -> RiskMetrics {
+        let value = streaming.closes.back().map_or(0.0, |close| close.value);
+        let var_result = match self.sampling.var_method {
+            VaRMethod::Historical if streaming.var_95.count() >= MIN_HISTORICAL_VAR_RETURNS as u64 => {
+                VaRResult::from_fractions(
+                    streaming.var_95.estimate(),
+                    streaming.var_99.estimate(),
+                    streaming.tail.estimate(),
+                    value,
+                )
+            }
+            VaRMethod::Parametric if streaming.returns.count() >= 2 => {
+                VaRResult::parametric(streaming.returns.mean(), streaming.returns.std_dev(), value)
+            }
+            _ => VaRResult::empty(),
+        };
 
-        // VaR 99%
-        let var_99_index = ((1.0 - 0.99) * sorted_returns.len() as f64) as usize;
-        let var_99 = sorted_returns.get(var_99_index).copied();
+        let (sharpe_ratio, sortino_ratio) =
+            self.sampling
+                .ratios(streaming.returns.count(), streaming.returns.mean(), streaming.returns.std_dev(), streaming.shortfall);
 
-        // Expected Shortfall (average of worst 5%)
-        let tail_size = (sorted_returns.len() as f64 * 0.05).ceil() as usize;
-        let expected_shortfall = if tail_size > 0 {
-            let sum: f64 = sorted_returns.iter().take(tail_size).sum();
-            Some(sum / tail_size as f64)
-        } else {
-            None
-        };
+        RiskMetrics {
+            total_value: self.total_value(),
+            positions_count: self.positions.len(),
+            unrealized_pnl: self.unrealized_pnl(),
+            realized_pnl: self.total_realized_pnl,
+            max_drawdown: streaming.max_drawdown,
+            sharpe_ratio,
+            sortino_ratio,
+            var_95: var_result.var_95,
+            var_99: var_result.var_99,
+            expected_shortfall: var_result.expected_shortfall,
+            var_95_pct: var_result.var_95_pct,
+            var_99_pct: var_result.var_99_pct,
+            expected_shortfall_pct: var_result.expected_shortfall_pct,
+        }
+    }
 
-        VaRResult {
-            var_95,
-            var_99,
-            expected_shortfall,
+    /// VaR and expected shortfall of the bucket returns, priced at the
+    /// latest portfolio value
+    ///
+    /// Historical VaR takes the last 100 returns and needs at least 10;
+    /// parametric VaR takes every return, like Sharpe, and needs two.
+    fn calculate_var(&self) -> VaRResult {
+        let returns = self.bucket_returns();
+        let value = self.pnl_history.back().map_or(0.0, |record| record.portfolio_value);
+
+        match self.sampling.var_method {
+            VaRMethod::Historical if returns.len() >= MIN_HISTORICAL_VAR_RETURNS => {
+                VaRResult::historical(&returns[returns.len().saturating_sub(100)..], value)
+            }
+            VaRMethod::Parametric if returns.len() >= 2 => {
+                let n = returns.len() as f64;
+                let mean = returns.iter().sum::<f64>() / n;
+                let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n;
+                VaRResult::parametric(mean, variance.sqrt(), value)
+            }
+            _ => VaRResult::empty(),
         }
     }
 
@@ -317,49 +692,234 @@ impl Portfolio {
         max_drawdown
     }
 
-    /// Calculate Sharpe ratio
-    fn calculate_sharpe_ratio(&self) -> Option<f64> {
-        if self.pnl_history.len() < 2 {
-            return None;
+    /// Portfolio value at the close of every bucket from the first record's
+    /// to the last's, carried forward through buckets with no records
+    fn bucket_closes(&self) -> Vec<f64> {
+        let seconds = self.sampling.bucket.seconds();
+        let mut closes = Vec::new();
+        let mut current = i64::MIN;
+        for record in &self.pnl_history {
+            let bucket = record.timestamp.timestamp().div_euclid(seconds);
+            match closes.last_mut() {
+                Some(close) if bucket <= current => *close = record.portfolio_value,
+                Some(close) => {
+                    let carried = *close;
+                    closes.extend((current + 1..bucket).map(|_| carried));
+                    closes.push(record.portfolio_value);
+                    current = bucket;
+                }
+                None => {
+                    closes.push(record.portfolio_value);
+                    current = bucket;
+                }
+            }
         }
+        closes
+    }
 
-        let returns: Vec<f64> = self
-            .pnl_history
+    /// Returns from each bucket close to the next
+    fn bucket_returns(&self) -> Vec<f64> {
+        self.bucket_closes()
             .windows(2)
-            .map(|w| {
-                if w[0].portfolio_value > 0.0 {
-                    (w[1].portfolio_value - w[0].portfolio_value) / w[0].portfolio_value
-                } else {
-                    0.0
-                }
-            })
-            .collect();
+            .map(|pair| simple_return(pair[0], pair[1]))
+            .collect()
+    }
 
+    /// Annualized Sharpe and Sortino ratios of the bucket returns
+    fn calculate_return_ratios(&self) -> (Option<f64>, Option<f64>) {
+        let returns = self.bucket_returns();
         if returns.is_empty() {
-            return None;
+            return (None, None);
         }
 
-        let mean: f64 = returns.iter().sum::<f64>() / returns.len() as f64;
+        let n = returns.len() as f64;
+        let mean = returns.iter().sum::<f64>() / n;
+        let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n;
+        let shortfall = returns.iter().map(|r| self.sampling.shortfall(*r)).sum();
 
-        let variance: f64 = returns
-            .iter()
-            .map(|r| (r - mean).powi(2))
-            .sum::<f64>() / returns.len() as f64;
+        self.sampling.ratios(returns.len() as u64, mean, variance.sqrt(), shortfall)
+    }
+}
 
-        let std_dev = variance.sqrt();
+/// Fewest bucket returns historical VaR is taken over
+const MIN_HISTORICAL_VAR_RETURNS: usize = 10;
+
+/// Return from one portfolio value to the next
+fn simple_return(previous: f64, value: f64) -> f64 {
+    if previous > 0.0 {
+        (value - previous) / previous
+    } else {
+        0.0
+    }
+}
 
-        if std_dev == 0.0 {
-            return Some(0.0);
+/// Bucket and risk-free rate behind Sharpe and Sortino, and how VaR is
+/// estimated from the same returns
+#[derive(Debug, Clone, Copy)]
+struct ReturnSampling {
+    bucket: ReturnBucket,
+    risk_free_rate: f64,
+    var_method: VaRMethod,
+}
+
+impl Default for ReturnSampling {
+    fn default() -> Self {
+        Self {
+            bucket: ReturnBucket::Daily,
+            risk_free_rate: 0.05,
+            var_method: VaRMethod::Historical,
         }
+    }
+}
 
-        // Annualize (assuming daily returns)
-        let annualized_mean = mean * 365.0;
-        let annualized_std = std_dev * (365.0_f64).sqrt();
+impl ReturnSampling {
+    /// Risk-free return over one bucket
+    fn risk_free_per_period(&self) -> f64 {
+        self.risk_free_rate / self.bucket.periods_per_year()
+    }
+
+    /// Squared shortfall of a bucket return below the risk-free return
+    fn shortfall(&self, r: f64) -> f64 {
+        (r - self.risk_free_per_period()).min(0.0).powi(2)
+    }
 
-        // Use 5% as risk-free rate
-        let risk_free = 0.05;
+    /// Annualized Sharpe and Sortino from `count` bucket returns with the
+    /// given mean, standard deviation and summed squared shortfall
+    ///
+    /// Sharpe is 0 for returns that never vary; Sortino is None without a
+    /// bucket below the risk-free return.
+    fn ratios(&self, count: u64, mean: f64, std_dev: f64, shortfall: f64) -> (Option<f64>, Option<f64>) {
+        if count == 0 {
+            return (None, None);
+        }
 
-        Some((annualized_mean - risk_free) / annualized_std)
+        let excess = mean - self.risk_free_per_period();
+        let scale = self.bucket.periods_per_year().sqrt();
+        let sharpe = if std_dev > 0.0 { excess / std_dev * scale } else { 0.0 };
+        let downside_deviation = (shortfall.max(0.0) / count as f64).sqrt();
+        let sortino = (downside_deviation > 0.0).then(|| excess / downside_deviation * scale);
+        (Some(sharpe), sortino)
+    }
+}
+
+/// O(1) per-record estimators behind `Portfolio::enable_streaming_metrics`
+#[derive(Debug, Clone)]
+struct StreamingMetrics {
+    sampling: ReturnSampling,
+    /// Buckets holding the records still in the history
+    closes: VecDeque<BucketClose>,
+    /// Bucket returns over `closes`, empty buckets included as zeros
+    returns: RunningMoments,
+    /// Summed squared shortfall of those returns
+    shortfall: f64,
+    peak: f64,
+    max_drawdown: f64,
+    /// Quantiles of completed bucket returns, which can't change any more
+    var_95: P2Quantile,
+    var_99: P2Quantile,
+    /// Mean of the worst 5% of those returns
+    tail: P2TailMean,
+}
+
+/// Last value in a bucket with records
+#[derive(Debug, Clone, Copy)]
+struct BucketClose {
+    bucket: i64,
+    value: f64,
+    records: usize,
+}
+
+impl StreamingMetrics {
+    fn new(sampling: ReturnSampling) -> Self {
+        Self {
+            sampling,
+            closes: VecDeque::new(),
+            returns: RunningMoments::new(),
+            shortfall: 0.0,
+            peak: f64::MIN,
+            max_drawdown: 0.0,
+            var_95: P2Quantile::new(0.05),
+            var_99: P2Quantile::new(0.01),
+            tail: P2TailMean::new(0.05),
+        }
+    }
+
+    fn record(&mut self, record: &PnLRecord) {
+        let bucket = record.timestamp.timestamp().div_euclid(self.sampling.bucket.seconds());
+        match self.closes.back().copied() {
+            Some(last) if bucket <= last.bucket => {
+                let previous = self.closes.len().checked_sub(2).map(|i| self.closes[i]);
+                let updated = BucketClose {
+                    value: record.portfolio_value,
+                    records: last.records + 1,
+                    ..last
+                };
+                if let Some(previous) = previous {
+                    self.remove_returns(&previous, &last);
+                    self.add_returns(&previous, &updated);
+                }
+                *self.closes.back_mut().unwrap() = updated;
+            }
+            last => {
+                let close = BucketClose {
+                    bucket,
+                    value: record.portfolio_value,
+                    records: 1,
+                };
+                if let Some(last) = last {
+                    // The last bucket is closed, so the returns into it are final
+                    if let Some(previous) = self.closes.len().checked_sub(2).map(|i| self.closes[i]) {
+                        for r in Self::bucket_returns(&previous, &last) {
+                            self.var_95.add(r);
+                            self.var_99.add(r);
+                            self.tail.add(r);
+                        }
+                    }
+                    self.add_returns(&last, &close);
+                }
+                self.closes.push_back(close);
+            }
+        }
+
+        self.peak = self.peak.max(record.portfolio_value);
+        let drawdown = (self.peak - record.portfolio_value) / self.peak.max(1.0);
+        self.max_drawdown = self.max_drawdown.max(drawdown);
+    }
+
+    /// Forget the oldest record once it leaves the history
+    ///
+    /// Its bucket's close only changes if it was the bucket's last record,
+    /// in which case the bucket and the returns out of it go.
+    fn drop_oldest(&mut self) {
+        let Some(front) = self.closes.front_mut() else { return };
+        front.records -= 1;
+        if front.records == 0 {
+            let dropped = self.closes.pop_front().unwrap();
+            if let Some(next) = self.closes.front().copied() {
+                self.remove_returns(&dropped, &next);
+            }
+        }
+    }
+
+    /// Returns from one bucket close to the next: zeros through the empty
+    /// buckets between them, then the change in value
+    fn bucket_returns(from: &BucketClose, to: &BucketClose) -> impl Iterator<Item = f64> {
+        let empty = (to.bucket - from.bucket - 1).max(0) as usize;
+        std::iter::repeat_n(0.0, empty).chain(std::iter::once(simple_return(from.value, to.value)))
+    }
+
+    fn add_returns(&mut self, from: &BucketClose, to: &BucketClose) {
+        for r in Self::bucket_returns(from, to) {
+            self.returns.add(r);
+            self.shortfall += self.sampling.shortfall(r);
+        }
+    }
+
+    fn remove_returns(&mut self, from: &BucketClose, to: &BucketClose) {
+        for r in Self::bucket_returns(from, to) {
+            self.returns.remove(r);
+            self.shortfall -= self.sampling.shortfall(r);
+        }
     }
 }
 
@@ -372,8 +932,8 @@ impl Default for Portfolio {
 /// Single position in a market
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Position {
-    pub market_id: Uuid,
-    pub outcome_id: String,
+    pub market_id: MarketId,
+    pub outcome_id: OutcomeId,
     pub investment: f64,
     pub avg_entry_price: f64,
     pub current_price: f64,
@@ -384,10 +944,10 @@ pub struct Position {
 }
 
 impl Position {
-    pub fn new(market_id: Uuid, outcome_id: &str, value: f64, price: f64) -> Self {
+    pub fn new(market_id: MarketId, outcome_id: &str, value: f64, price: f64) -> Self {
         Self {
             market_id,
-            outcome_id: outcome_id.to_string(),
+            outcome_id: outcome_id.into(),
             investment: value,
             avg_entry_price: price,
             current_price: price,
@@ -455,6 +1015,30 @@ impl Position {
     pub fn unrealized_pnl(&self) -> f64 {
         self.current_value() - self.investment
     }
+
+    /// Number of outcome shares held
+    pub fn shares(&self) -> f64 {
+        if self.avg_entry_price > 0.0 {
+            self.investment / self.avg_entry_price
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Final settlement of a position in a resolved or invalidated market
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Settlement {
+    /// The position as it stood when settled
+    pub position: Position,
+    /// Winning outcome, or None if the market was invalidated
+    pub winning_outcome: Option<String>,
+    pub payout: f64,
+    pub pnl: f64,
+    pub settled_at: DateTime<Utc>,
+    /// When a reversal or invalidation reopened the position
+    #[serde(default)]
+    pub reversed_at: Option<DateTime<Utc>>,
 }
 
 /// Position state
@@ -474,6 +1058,29 @@ pub struct Exposure {
     pub percentage_of_portfolio: f64,
 }
 
+/// Netted exposure to a negRisk group
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupExposure {
+    pub group_id: String,
+    /// Registered members of the group
+    pub members: usize,
+    /// Marked value of all positions in the group
+    pub gross_value: f64,
+    /// Payout if the least favourable member wins
+    pub min_payout: f64,
+    /// Payout if the most favourable member wins
+    pub max_payout: f64,
+    /// Value that can be lost, `gross_value - min_payout`
+    pub net_exposure: f64,
+}
+
+/// A market's membership in a negRisk group
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct NegRiskMember {
+    group_id: String,
+    no_outcome: Option<OutcomeId>,
+}
+
 /// PnL record for metrics calculation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct PnLRecord {
@@ -481,3 +1088,29 @@ struct PnLRecord {
     pnl: f64,
     portfolio_value: f64,
 }
+
+/// Serializes positions as a list, since JSON map keys can't be tuples;
+/// the key is rebuilt from each position's own ids
+mod position_list {
+    use super::Position;
+    use common::{MarketId, OutcomeId};
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::HashMap;
+
+    pub fn serialize<S: Serializer>(
+        positions: &HashMap<(MarketId, OutcomeId), Position>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(positions.values())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<(MarketId, OutcomeId), Position>, D::Error> {
+        let positions = Vec::<Position>::deserialize(deserializer)?;
+        Ok(positions
+            .into_iter()
+            .map(|p| ((p.market_id, p.outcome_id.clone()), p))
+            .collect())
+    }
+}
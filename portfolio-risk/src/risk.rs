@@ -1,6 +1,6 @@
 //! Risk checking and circuit breaker implementation
 
-use crate::config::{RiskLimits, CircuitBreakerConfig};
+use crate::config::{RiskLimits, CircuitBreakerConfig, VaRLimit};
 use crate::portfolio::Portfolio;
 
 /// Current risk level assessment
@@ -24,8 +24,9 @@ impl RiskLevel {
 }
 use chrono::{DateTime, Utc, Duration};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use uuid::Uuid;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::OnceLock;
+use common::{MarketId, RunningMoments};
 
 /// Risk checker for evaluating trades and monitoring portfolio risk
 #[derive(Debug, Clone)]
@@ -34,32 +35,99 @@ pub struct RiskChecker {
     circuit_breaker_config: CircuitBreakerConfig,
     circuit_breaker: CircuitBreaker,
     pub kelly_criterion: KellyCriterion,
+    correlation_monitor: CorrelationMonitor,
     violation_count: usize,
     last_violation_time: Option<DateTime<Utc>>,
 }
 
+/// Default for `with_correlation_threshold`
+const DEFAULT_CORRELATION_THRESHOLD: f64 = 0.7;
+
+/// Correlation monitor series for an outcome of a market
+fn series_key(market_id: MarketId, outcome_id: &str) -> String {
+    format!("{}/{}", market_id, outcome_id)
+}
+
 impl RiskChecker {
     pub fn new(risk_limits: RiskLimits) -> Self {
+        Self::with_circuit_breakers(risk_limits, CircuitBreakerConfig::default())
+    }
+
+    /// Create a risk checker with custom circuit breaker configuration
+    pub fn with_circuit_breakers(
+        risk_limits: RiskLimits,
+        circuit_breaker_config: CircuitBreakerConfig,
+    ) -> Self {
         Self {
             risk_limits,
-            circuit_breaker_config: CircuitBreakerConfig::default(),
-            circuit_breaker: CircuitBreaker::new(),
+            circuit_breaker: CircuitBreaker::with_config(&circuit_breaker_config),
+            circuit_breaker_config,
             kelly_criterion: KellyCriterion::new(0.25, None),
+            correlation_monitor: CorrelationMonitor::new(DEFAULT_CORRELATION_THRESHOLD),
             violation_count: 0,
             last_violation_time: None,
         }
     }
 
+    /// Count outcomes whose prices correlate at or above `threshold` as one
+    /// bet in `check_correlated_trade` (default 0.7)
+    pub fn with_correlation_threshold(mut self, threshold: f64) -> Self {
+        self.correlation_monitor = CorrelationMonitor::new(threshold);
+        self
+    }
+
+    /// Record a price of an outcome for the correlation clusters
+    pub fn update_price(&mut self, market_id: MarketId, outcome_id: &str, price: f64) {
+        self.correlation_monitor.update_price(&series_key(market_id, outcome_id), price);
+    }
+
+    /// Price histories behind the correlation clusters, keyed `market_id/outcome_id`
+    pub fn correlation_monitor(&self) -> &CorrelationMonitor {
+        &self.correlation_monitor
+    }
+
+    /// Reject trading while the circuit breaker is active
+    pub fn check_circuit_breaker(&self) -> Result<(), RiskViolation> {
+        let now = Utc::now();
+        if !self.circuit_breaker.is_active_at(now) {
+            return Ok(());
+        }
+
+        Err(RiskViolation::CircuitBreakerActive {
+            triggered_at: self.circuit_breaker.trigger_time.unwrap_or(now),
+            cooldown_remaining: self
+                .circuit_breaker
+                .cooldown_remaining_at(now)
+                .to_std()
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Manually reset the circuit breaker before its cooldown expires
+    pub fn force_reset_circuit_breaker(&mut self) {
+        tracing::warn!("Circuit breaker force reset by operator");
+        self.circuit_breaker.reset();
+    }
+
+    /// Get the circuit breaker state
+    pub fn circuit_breaker(&self) -> &CircuitBreaker {
+        &self.circuit_breaker
+    }
+
     /// Check if a trade violates any risk limits
+    ///
+    /// Theme exposure is only checked when the market's category is known.
+    /// Total exposure counts negRisk groups at their netted exposure.
     pub fn check_trade(
         &self,
-        market_id: Uuid,
-        outcome_id: &str,
         side: common::OrderSide,
         value: f64,
+        category: Option<&str>,
         portfolio: &Portfolio,
     ) -> Result<(), RiskViolation> {
-        let total_value = portfolio.total_value();
+        self.check_circuit_breaker()?;
+
+        let total_value = portfolio.net_exposure();
         let new_total_value = total_value + value;
 
         // Check maximum total exposure
@@ -88,25 +156,17 @@ impl RiskChecker {
         }
 
         // Check theme exposure (if category is known)
-        let category = portfolio
-            .positions()
-            .iter()
-            .find(|((id, _), _)| *id == market_id)
-            .and_then(|_| {
-                // This would need category info - for now just check general theme limit
-                Some("default")
-            });
-
-        if let Some(_cat) = category {
-            let theme_exposure = self.calculate_theme_exposure(portfolio, "default");
+        if let Some(category) = category {
+            let theme_exposure = self.calculate_theme_exposure(portfolio, category);
             let new_theme_exposure = theme_exposure + value;
+            let limit = self.risk_limits.theme_exposure_limit(category);
 
-            if new_theme_exposure > self.risk_limits.max_theme_exposure {
+            if new_theme_exposure > limit {
                 return Err(RiskViolation::MaxThemeExposureExceeded {
-                    theme: "default".to_string(),
+                    theme: category.to_string(),
                     current: theme_exposure,
                     proposed: value,
-                    limit: self.risk_limits.max_theme_exposure,
+                    limit,
                 });
             }
         }
@@ -114,8 +174,105 @@ impl RiskChecker {
         Ok(())
     }
 
-    /// Check all circuit breakers
-    pub fn check_circuit_breakers(&self, portfolio: &Portfolio) -> Vec<RiskViolation> {
+    /// Check a trade in a member of a negRisk group against the group limit.
+    ///
+    /// The group is checked at its netted exposure after the trade, so
+    /// trades that hedge the group are allowed even when it is at the limit.
+    pub fn check_group_trade(
+        &self,
+        market_id: MarketId,
+        outcome_id: &str,
+        side: common::OrderSide,
+        value: f64,
+        price: f64,
+        portfolio: &Portfolio,
+    ) -> Result<(), RiskViolation> {
+        let Some(group_id) = portfolio.neg_risk_group(market_id) else {
+            return Ok(());
+        };
+
+        let signed_value = match side {
+            common::OrderSide::Buy => value,
+            common::OrderSide::Sell => -value,
+        };
+        let current = portfolio.group_exposure(group_id).net_exposure;
+        let projected = portfolio
+            .group_exposure_after(group_id, market_id, outcome_id, signed_value, price)
+            .net_exposure;
+        let limit = self.risk_limits.max_group_exposure;
+
+        if projected > limit && projected > current {
+            return Err(RiskViolation::MaxGroupExposureExceeded {
+                group_id: group_id.to_string(),
+                current,
+                projected,
+                limit,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Check a buy against the limit on exposure to outcomes whose prices
+    /// move with the one being bought.
+    ///
+    /// The cluster holds every outcome linked to this one by correlations at
+    /// or above the threshold, directly or through other members, and its
+    /// exposure is the marked value of the positions in it. Sells only
+    /// reduce exposure and always pass.
+    pub fn check_correlated_trade(
+        &self,
+        market_id: MarketId,
+        outcome_id: &str,
+        side: common::OrderSide,
+        value: f64,
+        portfolio: &Portfolio,
+    ) -> Result<(), RiskViolation> {
+        if side != common::OrderSide::Buy {
+            return Ok(());
+        }
+
+        let key = series_key(market_id, outcome_id);
+        let correlated = self.correlation_monitor.cluster(&key);
+        if correlated.is_empty() {
+            return Ok(());
+        }
+
+        let members: HashSet<&str> = correlated
+            .iter()
+            .map(|(member, _)| member.as_str())
+            .chain([key.as_str()])
+            .collect();
+        let current: f64 = portfolio
+            .positions()
+            .values()
+            .filter(|p| members.contains(series_key(p.market_id, p.outcome_id.as_str()).as_str()))
+            .map(|p| p.current_value())
+            .sum();
+        let limit = self.risk_limits.max_correlated_exposure;
+
+        if current + value > limit {
+            return Err(RiskViolation::CorrelatedExposureExceeded {
+                market: key,
+                correlated,
+                current,
+                proposed: value,
+                limit,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Check all circuit breakers, triggering the breaker on any violation
+    pub fn check_circuit_breakers(&mut self, portfolio: &Portfolio) -> Vec<RiskViolation> {
+        let now = Utc::now();
+
+        // Auto-reset once the cooldown has passed
+        if self.circuit_breaker.is_triggered && !self.circuit_breaker.is_active_at(now) {
+            self.circuit_breaker.reset();
+        }
+
         let mut violations = Vec::new();
 
         // Check daily loss limit
@@ -138,12 +295,28 @@ impl RiskChecker {
         }
 
         // Check VaR limit
-        if let Some(var) = metrics.var_95 {
-            if var.abs() > self.circuit_breaker_config.var_95_limit {
-                violations.push(RiskViolation::VaRLimitExceeded {
-                    var_95: var.abs(),
-                    limit: self.circuit_breaker_config.var_95_limit,
-                });
+        match self.circuit_breaker_config.var_95_limit {
+            VaRLimit::Usd(limit) => {
+                if let Some(var) = metrics.var_95.filter(|var| var.abs() > limit) {
+                    violations.push(RiskViolation::VaRLimitExceeded { var_95: var.abs(), limit });
+                }
+            }
+            VaRLimit::Fraction { fraction } => {
+                if let Some(var) = metrics.var_95_pct.filter(|var| var.abs() > fraction) {
+                    violations.push(RiskViolation::VaRFractionExceeded {
+                        var_95_pct: var.abs(),
+                        limit: fraction,
+                    });
+                }
+            }
+        }
+
+        if !violations.is_empty() {
+            self.violation_count += violations.len();
+            self.last_violation_time = Some(now);
+
+            if self.circuit_breaker_config.enabled && !self.circuit_breaker.is_active_at(now) {
+                self.circuit_breaker.trigger_at(now);
             }
         }
 
@@ -270,45 +443,75 @@ impl KellyCriterion {
 }
 
 /// Circuit breaker for automatic risk limits
+///
+/// The cooldown doubles for each additional trip within 24 hours.
 #[derive(Debug, Clone)]
 pub struct CircuitBreaker {
     is_triggered: bool,
     trigger_time: Option<DateTime<Utc>>,
     violations_today: Vec<DateTime<Utc>>,
     cooldown_duration: Duration,
+    current_cooldown: Duration,
 }
 
 impl CircuitBreaker {
     pub fn new() -> Self {
+        Self::with_cooldown(Duration::minutes(30))
+    }
+
+    /// Create a circuit breaker using the configured cooldown
+    pub fn with_config(config: &CircuitBreakerConfig) -> Self {
+        Self::with_cooldown(Duration::minutes(config.cooldown_minutes as i64))
+    }
+
+    fn with_cooldown(cooldown_duration: Duration) -> Self {
         Self {
             is_triggered: false,
             trigger_time: None,
             violations_today: Vec::new(),
-            cooldown_duration: Duration::minutes(30),
+            cooldown_duration,
+            current_cooldown: cooldown_duration,
         }
     }
 
     /// Trigger the circuit breaker
     pub fn trigger(&mut self) {
+        self.trigger_at(Utc::now());
+    }
+
+    fn trigger_at(&mut self, now: DateTime<Utc>) {
+        let cutoff = now - Duration::hours(24);
+        self.violations_today.retain(|t| *t > cutoff);
+        self.violations_today.push(now);
+
+        // Escalate: base cooldown doubled for every prior trip in the last 24h
+        let prior_trips = (self.violations_today.len() - 1).min(6) as i32;
+        self.current_cooldown = self.cooldown_duration * 2i32.pow(prior_trips as u32);
+
         self.is_triggered = true;
-        self.trigger_time = Some(Utc::now());
-        self.violations_today.push(Utc::now());
+        self.trigger_time = Some(now);
 
         tracing::error!(
-            time = ?Utc::now(),
+            time = ?now,
+            trips_24h = self.violations_today.len(),
+            cooldown_minutes = self.current_cooldown.num_minutes(),
             "Circuit breaker TRIGGERED - Trading halted"
         );
     }
 
     /// Check if circuit breaker is active
     pub fn is_active(&self) -> bool {
+        self.is_active_at(Utc::now())
+    }
+
+    fn is_active_at(&self, now: DateTime<Utc>) -> bool {
         if !self.is_triggered {
             return false;
         }
 
         // Check if cooldown period has passed
         if let Some(trigger_time) = self.trigger_time {
-            if Utc::now() - trigger_time > self.cooldown_duration {
+            if now - trigger_time > self.current_cooldown {
                 return false;
             }
         }
@@ -316,6 +519,25 @@ impl CircuitBreaker {
         true
     }
 
+    /// Time left until the breaker auto-resets
+    pub fn cooldown_remaining(&self) -> Duration {
+        self.cooldown_remaining_at(Utc::now())
+    }
+
+    fn cooldown_remaining_at(&self, now: DateTime<Utc>) -> Duration {
+        match self.trigger_time {
+            Some(trigger_time) if self.is_triggered => {
+                (trigger_time + self.current_cooldown - now).max(Duration::zero())
+            }
+            _ => Duration::zero(),
+        }
+    }
+
+    /// Current (possibly escalated) cooldown
+    pub fn current_cooldown(&self) -> Duration {
+        self.current_cooldown
+    }
+
     /// Reset the circuit breaker after cooldown
     pub fn reset(&mut self) {
         self.is_triggered = false;
@@ -363,6 +585,21 @@ pub enum RiskViolation {
         proposed: f64,
         limit: f64,
     },
+    MaxGroupExposureExceeded {
+        group_id: String,
+        current: f64,
+        projected: f64,
+        limit: f64,
+    },
+    CorrelatedExposureExceeded {
+        /// Series of the outcome being bought, `market_id/outcome_id`
+        market: String,
+        /// The rest of its cluster, each with its correlation to `market`
+        correlated: Vec<(String, f64)>,
+        current: f64,
+        proposed: f64,
+        limit: f64,
+    },
     DailyLossLimitExceeded {
         daily_pnl: f64,
         limit: f64,
@@ -375,6 +612,10 @@ pub enum RiskViolation {
         var_95: f64,
         limit: f64,
     },
+    VaRFractionExceeded {
+        var_95_pct: f64,
+        limit: f64,
+    },
     KellyLimitExceeded {
         proposed: f64,
         kelly_limit: f64,
@@ -384,6 +625,15 @@ pub enum RiskViolation {
         market_2: String,
         correlation: f64,
     },
+    CircuitBreakerActive {
+        triggered_at: DateTime<Utc>,
+        cooldown_remaining: std::time::Duration,
+    },
+    StrategyBudgetExceeded {
+        strategy_id: String,
+        available: f64,
+        proposed: f64,
+    },
 }
 
 impl std::fmt::Display for RiskViolation {
@@ -409,6 +659,28 @@ impl std::fmt::Display for RiskViolation {
                     theme, current, proposed, limit
                 )
             }
+            RiskViolation::MaxGroupExposureExceeded { group_id, current, projected, limit } => {
+                write!(
+                    f,
+                    "Group '{}' exposure ${:.2} would grow to ${:.2}, exceeding limit ${:.2}",
+                    group_id, current, projected, limit
+                )
+            }
+            RiskViolation::CorrelatedExposureExceeded { market, correlated, current, proposed, limit } => {
+                let members: Vec<String> = correlated
+                    .iter()
+                    .map(|(member, correlation)| format!("{} ({:.2})", member, correlation))
+                    .collect();
+                write!(
+                    f,
+                    "Correlated exposure ${:.2} + ${:.2} in {} exceeds limit ${:.2}, correlated with {}",
+                    current,
+                    proposed,
+                    market,
+                    limit,
+                    members.join(", ")
+                )
+            }
             RiskViolation::DailyLossLimitExceeded { daily_pnl, limit } => {
                 write!(
                     f,
@@ -428,6 +700,14 @@ impl std::fmt::Display for RiskViolation {
             RiskViolation::VaRLimitExceeded { var_95, limit } => {
                 write!(f, "VaR (95%) ${:.2} exceeds limit ${:.2}", var_95, limit)
             }
+            RiskViolation::VaRFractionExceeded { var_95_pct, limit } => {
+                write!(
+                    f,
+                    "VaR (95%) {:.2}% of portfolio value exceeds limit {:.2}%",
+                    var_95_pct * 100.0,
+                    limit * 100.0
+                )
+            }
             RiskViolation::KellyLimitExceeded { proposed, kelly_limit } => {
                 write!(
                     f,
@@ -442,19 +722,85 @@ impl std::fmt::Display for RiskViolation {
                     correlation, market_1, market_2
                 )
             }
+            RiskViolation::CircuitBreakerActive { triggered_at, cooldown_remaining } => {
+                write!(
+                    f,
+                    "Circuit breaker active since {}, {}s of cooldown remaining",
+                    triggered_at,
+                    cooldown_remaining.as_secs()
+                )
+            }
+            RiskViolation::StrategyBudgetExceeded { strategy_id, available, proposed } => {
+                write!(
+                    f,
+                    "Strategy '{}' has ${:.2} of buying power, ${:.2} proposed",
+                    strategy_id, available, proposed
+                )
+            }
         }
     }
 }
 
 impl std::error::Error for RiskViolation {}
 
+/// Fewest prices a market needs before it joins a cluster
+const MIN_CLUSTER_SAMPLES: usize = 10;
+
+/// Prices kept per market for correlation
+const PRICE_WINDOW: usize = 100;
+
+/// Variances below this count as flat; removals from the running moments
+/// can leave a constant series with a rounding residue instead of zero
+const MIN_PRICE_VARIANCE: f64 = 1e-12;
+
+/// Recent prices of one market, with their moments kept as the window slides
+#[derive(Debug, Clone, Default)]
+struct PriceWindow {
+    prices: VecDeque<f64>,
+    moments: RunningMoments,
+}
+
+impl PriceWindow {
+    fn push(&mut self, price: f64) {
+        self.prices.push_back(price);
+        self.moments.add(price);
+        if self.prices.len() > PRICE_WINDOW {
+            if let Some(oldest) = self.prices.pop_front() {
+                self.moments.remove(oldest);
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.prices.len()
+    }
+
+    /// The last `n` prices, oldest first
+    fn tail(&self, n: usize) -> impl Iterator<Item = f64> + '_ {
+        self.prices.iter().skip(self.prices.len() - n).copied()
+    }
+
+    /// Moments of the last `n` prices, removing the older ones from the
+    /// window's moments rather than summing the tail again
+    fn tail_moments(&self, n: usize) -> RunningMoments {
+        let mut moments = self.moments;
+        for &price in self.prices.iter().take(self.prices.len() - n) {
+            moments.remove(price);
+        }
+        moments
+    }
+}
+
 /// Correlation monitoring for detecting correlated positions
 #[derive(Debug, Clone)]
 pub struct CorrelationMonitor {
     /// Correlation threshold for flagging
     threshold: f64,
     /// Price history for correlation calculation
-    price_history: HashMap<String, Vec<f64>>,
+    price_history: HashMap<String, PriceWindow>,
+    /// Cluster root of every market, rebuilt on the first lookup after a
+    /// price update
+    clusters: OnceLock<HashMap<String, String>>,
 }
 
 impl CorrelationMonitor {
@@ -462,18 +808,70 @@ impl CorrelationMonitor {
         Self {
             threshold,
             price_history: HashMap::new(),
+            clusters: OnceLock::new(),
         }
     }
 
     /// Update price history for a market
     pub fn update_price(&mut self, market_id: &str, price: f64) {
-        let history = self.price_history.entry(market_id.to_string()).or_insert_with(Vec::new);
-        history.push(price);
+        self.price_history.entry(market_id.to_string()).or_default().push(price);
+        self.clusters = OnceLock::new();
+    }
 
-        // Keep last 100 prices
-        if history.len() > 100 {
-            history.remove(0);
+    /// Markets linked to `market_id` by correlations at or above the
+    /// threshold, directly or through other markets, each with its own
+    /// correlation to `market_id`, most correlated first
+    ///
+    /// Only positive correlations link markets, since prices moving against
+    /// each other hedge rather than stack. Markets need 10 prices to link.
+    pub fn cluster(&self, market_id: &str) -> Vec<(String, f64)> {
+        let roots = self.clusters.get_or_init(|| self.build_clusters());
+        let Some(root) = roots.get(market_id) else {
+            return Vec::new();
+        };
+
+        let mut members: Vec<(String, f64)> = roots
+            .iter()
+            .filter(|(member, member_root)| *member_root == root && member.as_str() != market_id)
+            .map(|(member, _)| (member.clone(), self.calculate_correlation(market_id, member).unwrap_or(0.0)))
+            .collect();
+        members.sort_by(|a, b| b.1.total_cmp(&a.1));
+        members
+    }
+
+    /// Union-find over the pairs correlated at or above the threshold
+    fn build_clusters(&self) -> HashMap<String, String> {
+        fn find(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+
+        let market_ids: Vec<&String> = self
+            .price_history
+            .iter()
+            .filter(|(_, history)| history.len() >= MIN_CLUSTER_SAMPLES)
+            .map(|(market_id, _)| market_id)
+            .collect();
+        let mut parent: Vec<usize> = (0..market_ids.len()).collect();
+
+        for i in 0..market_ids.len() {
+            for j in (i + 1)..market_ids.len() {
+                let correlated = self
+                    .calculate_correlation(market_ids[i], market_ids[j])
+                    .is_some_and(|correlation| correlation >= self.threshold);
+                if correlated {
+                    let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+                    parent[a] = b;
+                }
+            }
         }
+
+        (0..market_ids.len())
+            .map(|i| (market_ids[i].clone(), market_ids[find(&mut parent, i)].clone()))
+            .collect()
     }
 
     /// Check for correlated positions
@@ -502,53 +900,43 @@ impl CorrelationMonitor {
     }
 
     /// Calculate Pearson correlation between two markets
+    ///
+    /// Over the most recent prices both markets have. Means and variances
+    /// come from the windows' running moments, so only the covariance takes
+    /// a pass over the prices.
     fn calculate_correlation(&self, market_1: &str, market_2: &str) -> Option<f64> {
-        let prices_1 = self.price_history.get(market_1)?;
-        let prices_2 = self.price_history.get(market_2)?;
+        let window_1 = self.price_history.get(market_1)?;
+        let window_2 = self.price_history.get(market_2)?;
 
         // Need at least 2 data points
-        if prices_1.len() < 2 || prices_2.len() < 2 {
+        if window_1.len() < 2 || window_2.len() < 2 {
             return None;
         }
 
-        // Use minimum length
-        let n = prices_1.len().min(prices_2.len());
-
-        // Calculate means
-        let mean_1: f64 = prices_1.iter().take(n).sum::<f64>() / n as f64;
-        let mean_2: f64 = prices_2.iter().take(n).sum::<f64>() / n as f64;
-
-        // Calculate covariance and variances
-        let mut covariance = 0.0;
-        let mut variance_1 = 0.0;
-        let mut variance_2 = 0.0;
-
-        for i in 0..n {
-            let diff_1 = prices_1[i] - mean_1;
-            let diff_2 = prices_2[i] - mean_2;
-
-            covariance += diff_1 * diff_2;
-            variance_1 += diff_1 * diff_1;
-            variance_2 += diff_2 * diff_2;
-        }
-
-        covariance /= n as f64;
-        variance_1 /= n as f64;
-        variance_2 /= n as f64;
-
-        // Calculate correlation
-        let denominator = (variance_1 * variance_2).sqrt();
-        if denominator == 0.0 {
+        let n = window_1.len().min(window_2.len());
+        let (moments_1, moments_2) = (window_1.tail_moments(n), window_2.tail_moments(n));
+        if moments_1.variance() < MIN_PRICE_VARIANCE || moments_2.variance() < MIN_PRICE_VARIANCE {
             return None;
         }
 
-        Some(covariance / denominator)
+        let (mean_1, mean_2) = (moments_1.mean(), moments_2.mean());
+        let covariance = window_1
+            .tail(n)
+            .zip(window_2.tail(n))
+            .map(|(price_1, price_2)| (price_1 - mean_1) * (price_2 - mean_2))
+            .sum::<f64>()
+            / n as f64;
+
+        let correlation = covariance / (moments_1.variance() * moments_2.variance()).sqrt();
+        Some(correlation.clamp(-1.0, 1.0))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::ThemeLimit;
+    use common::MarketId;
 
     #[test]
     fn test_kelly_criterion() {
@@ -584,4 +972,243 @@ mod tests {
         let violations = monitor.check_correlations();
         assert!(!violations.is_empty());
     }
+
+    /// Two-pass Pearson correlation of the last prices both series share
+    fn exact_correlation(prices_1: &[f64], prices_2: &[f64]) -> f64 {
+        let n = prices_1.len().min(prices_2.len());
+        let (prices_1, prices_2) = (&prices_1[prices_1.len() - n..], &prices_2[prices_2.len() - n..]);
+        let mean_1 = prices_1.iter().sum::<f64>() / n as f64;
+        let mean_2 = prices_2.iter().sum::<f64>() / n as f64;
+        let (mut covariance, mut variance_1, mut variance_2) = (0.0, 0.0, 0.0);
+        for (p1, p2) in prices_1.iter().zip(prices_2) {
+            covariance += (p1 - mean_1) * (p2 - mean_2);
+            variance_1 += (p1 - mean_1).powi(2);
+            variance_2 += (p2 - mean_2).powi(2);
+        }
+        covariance / (variance_1 * variance_2).sqrt()
+    }
+
+    #[test]
+    fn test_sliding_correlation_matches_exact() {
+        let mut monitor = CorrelationMonitor::new(0.7);
+        let (mut prices_1, mut prices_2) = (Vec::new(), Vec::new());
+
+        // Long enough for both windows to slide; the second market trades
+        // less often, so the windows differ in length for a while
+        for i in 0..400 {
+            let x = i as f64;
+            let price_1 = 0.5 + 0.2 * (x / 7.0).sin() + 0.01 * (x * 1.3).cos();
+            monitor.update_price("market1", price_1);
+            prices_1.push(price_1);
+
+            if i % 3 == 0 || i > 250 {
+                let price_2 = 0.4 + 0.15 * (x / 7.0).sin() + 0.05 * (x / 2.0).cos();
+                monitor.update_price("market2", price_2);
+                prices_2.push(price_2);
+            }
+
+            if prices_2.len() >= 2 {
+                let window_1 = &prices_1[prices_1.len().saturating_sub(PRICE_WINDOW)..];
+                let window_2 = &prices_2[prices_2.len().saturating_sub(PRICE_WINDOW)..];
+                let correlation = monitor.calculate_correlation("market1", "market2").unwrap();
+                assert!((correlation - exact_correlation(window_1, window_2)).abs() < 1e-9, "at {}", i);
+            }
+        }
+
+        // A flat series has no correlation with anything
+        for _ in 0..PRICE_WINDOW {
+            monitor.update_price("market1", 0.5);
+        }
+        assert_eq!(monitor.calculate_correlation("market1", "market2"), None);
+    }
+
+    #[test]
+    fn test_theme_exposure_per_category() {
+        let mut limits = RiskLimits::default();
+        limits.theme_limits.insert("Politics".to_string(), ThemeLimit {
+            max_exposure: 150.0,
+            max_positions: 5,
+            max_percentage: 0.30,
+        });
+        let checker = RiskChecker::new(limits);
+        let mut portfolio = Portfolio::new();
+
+        for _ in 0..2 {
+            assert!(checker
+                .check_trade(common::OrderSide::Buy, 60.0, Some("Politics"), &portfolio)
+                .is_ok());
+
+            let market_id = MarketId::random();
+            portfolio.set_category(market_id, "Politics".to_string());
+            portfolio.add_position(market_id, "YES", 60.0, 0.5).unwrap();
+        }
+
+        match checker.check_trade(common::OrderSide::Buy, 60.0, Some("Politics"), &portfolio) {
+            Err(RiskViolation::MaxThemeExposureExceeded { theme, current, limit, .. }) => {
+                assert_eq!(theme, "Politics");
+                assert_eq!(current, 120.0);
+                assert_eq!(limit, 150.0);
+            }
+            other => panic!("Expected theme violation, got {:?}", other),
+        }
+
+        // Economics falls back to max_theme_exposure
+        assert!(checker
+            .check_trade(common::OrderSide::Buy, 60.0, Some("Economics"), &portfolio)
+            .is_ok());
+    }
+
+    /// A 6-member negRisk group with 100 YES shares held in each of the
+    /// first 3 members, marked at 0.3, 0.2 and 0.1
+    fn neg_risk_portfolio() -> (Portfolio, Vec<MarketId>) {
+        let mut portfolio = Portfolio::new();
+        let members: Vec<MarketId> = (0..6).map(|_| MarketId::random()).collect();
+        for member in &members {
+            portfolio.set_neg_risk_group(*member, "dem-nominee-2028".to_string(), None);
+        }
+        for (member, price) in members.iter().zip([0.3, 0.2, 0.1]) {
+            portfolio.add_position(*member, "YES", 100.0 * price, price).unwrap();
+        }
+        (portfolio, members)
+    }
+
+    #[test]
+    fn test_neg_risk_group_exposure() {
+        let (mut portfolio, members) = neg_risk_portfolio();
+
+        // Only one member pays out, and none is guaranteed to
+        let exposure = portfolio.group_exposure("dem-nominee-2028");
+        assert_eq!(exposure.members, 6);
+        assert!((exposure.gross_value - 60.0).abs() < 1e-9);
+        assert!((exposure.max_payout - 100.0).abs() < 1e-9);
+        assert_eq!(exposure.min_payout, 0.0);
+        assert!((exposure.net_exposure - 60.0).abs() < 1e-9);
+
+        // 100 NO shares of the first member pay out whenever it loses,
+        // which covers every outcome: 130 marked, 100 guaranteed
+        portfolio.add_position(members[0], "NO", 70.0, 0.7).unwrap();
+        let exposure = portfolio.group_exposure("dem-nominee-2028");
+        assert!((exposure.gross_value - 130.0).abs() < 1e-9);
+        assert!((exposure.min_payout - 100.0).abs() < 1e-9);
+        assert!((exposure.net_exposure - 30.0).abs() < 1e-9);
+
+        // Ungrouped markets count in full
+        portfolio.add_position(MarketId::random(), "YES", 25.0, 0.5).unwrap();
+        assert!((portfolio.total_value() - 155.0).abs() < 1e-9);
+        assert!((portfolio.net_exposure() - 55.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_neg_risk_group_limit() {
+        let limits = RiskLimits {
+            max_group_exposure: 80.0,
+            ..Default::default()
+        };
+        let checker = RiskChecker::new(limits);
+        let (portfolio, members) = neg_risk_portfolio();
+        let buy = common::OrderSide::Buy;
+
+        assert!(checker
+            .check_group_trade(members[3], "YES", buy, 20.0, 0.2, &portfolio)
+            .is_ok());
+
+        match checker.check_group_trade(members[3], "YES", buy, 30.0, 0.2, &portfolio) {
+            Err(RiskViolation::MaxGroupExposureExceeded { group_id, current, projected, limit }) => {
+                assert_eq!(group_id, "dem-nominee-2028");
+                assert!((current - 60.0).abs() < 1e-9);
+                assert!((projected - 90.0).abs() < 1e-9);
+                assert_eq!(limit, 80.0);
+            }
+            other => panic!("Expected group violation, got {:?}", other),
+        }
+
+        // A hedge is allowed even though it adds more value than the limit
+        assert!(checker
+            .check_group_trade(members[0], "NO", buy, 70.0, 0.7, &portfolio)
+            .is_ok());
+
+        // Markets outside any group are not affected
+        assert!(checker
+            .check_group_trade(MarketId::random(), "YES", buy, 90.0, 0.5, &portfolio)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_circuit_breaker_halts_until_cooldown() {
+        let mut checker = RiskChecker::new(RiskLimits::default());
+        let portfolio = Portfolio::new();
+
+        assert!(checker.check_trade(common::OrderSide::Buy, 10.0, None, &portfolio).is_ok());
+
+        checker.circuit_breaker.trigger();
+        match checker.check_trade(common::OrderSide::Buy, 10.0, None, &portfolio) {
+            Err(RiskViolation::CircuitBreakerActive { cooldown_remaining, .. }) => {
+                assert!(cooldown_remaining.as_secs() > 29 * 60);
+            }
+            other => panic!("Expected circuit breaker rejection, got {:?}", other),
+        }
+
+        // Cooldown expires
+        checker.circuit_breaker.trigger_time = Some(Utc::now() - Duration::minutes(31));
+        assert!(checker.check_trade(common::OrderSide::Buy, 10.0, None, &portfolio).is_ok());
+
+        // Next check auto-resets the expired breaker
+        assert!(checker.check_circuit_breakers(&portfolio).is_empty());
+        assert!(!checker.circuit_breaker().is_triggered);
+    }
+
+    #[test]
+    fn test_circuit_breaker_escalation_and_force_reset() {
+        let mut checker = RiskChecker::new(RiskLimits::default());
+        let portfolio = Portfolio::new();
+
+        checker.circuit_breaker.trigger();
+        assert_eq!(checker.circuit_breaker().current_cooldown(), Duration::minutes(30));
+
+        checker.circuit_breaker.reset();
+        checker.circuit_breaker.trigger();
+        assert_eq!(checker.circuit_breaker().violations_today(), 2);
+        assert_eq!(checker.circuit_breaker().current_cooldown(), Duration::minutes(60));
+
+        // 31 minutes is no longer enough after the second trip
+        checker.circuit_breaker.trigger_time = Some(Utc::now() - Duration::minutes(31));
+        assert!(checker.check_trade(common::OrderSide::Buy, 10.0, None, &portfolio).is_err());
+
+        checker.force_reset_circuit_breaker();
+        assert!(checker.check_trade(common::OrderSide::Buy, 10.0, None, &portfolio).is_ok());
+    }
+
+    #[test]
+    fn test_circuit_breaker_on_var_fraction_of_value() {
+        // A $1,000 portfolio swinging 3% a day, up then down: 95% VaR is a
+        // $30 loss, within the default $200 limit but 3% of value
+        let mut portfolio = Portfolio::new();
+        let start = Utc::now() - Duration::days(40);
+        let mut value = 1000.0;
+        for day in 0..=30 {
+            let r = if day % 2 == 1 { 0.03 } else { -0.03 };
+            if day > 0 {
+                value *= 1.0 + r;
+            }
+            portfolio.record_pnl_at(start + Duration::days(day), 0.0, value);
+        }
+        let var_95_pct = portfolio.calculate_metrics().var_95_pct.unwrap();
+        assert!((var_95_pct + 0.03).abs() < 1e-12);
+
+        let mut checker = RiskChecker::new(RiskLimits::default());
+        assert!(checker.check_circuit_breakers(&portfolio).is_empty());
+
+        let config = CircuitBreakerConfig {
+            var_95_limit: VaRLimit::Fraction { fraction: 0.02 },
+            ..CircuitBreakerConfig::default()
+        };
+        let mut checker = RiskChecker::with_circuit_breakers(RiskLimits::default(), config);
+        let violations = checker.check_circuit_breakers(&portfolio);
+        assert!(matches!(
+            violations.as_slice(),
+            [RiskViolation::VaRFractionExceeded { var_95_pct, limit }]
+                if (var_95_pct - 0.03).abs() < 1e-12 && *limit == 0.02
+        ));
+        assert!(checker.circuit_breaker().is_active());
+    }
 }
@@ -0,0 +1,576 @@
+//! Single-writer portfolio service with snapshot publishing
+//!
+//! One task owns the `PortfolioRiskManager` and applies every mutation in
+//! order. After each mutation it publishes an immutable `PortfolioSnapshot`
+//! on a watch channel, so readers (status API, agents) always see a fully
+//! applied state and never block the writer. `SystemStateView` and
+//! `ExposureRecorder` are built from those snapshots too.
+
+use crate::capital::AccountReport;
+use crate::metrics::RiskMetrics;
+use crate::portfolio::Position;
+use crate::risk::{RiskLevel, RiskViolation};
+use crate::{PortfolioRiskManager, PortfolioSummary, ResolutionPnl, TradeEvaluation};
+use chrono::{DateTime, Utc};
+use common::{MarketEvent, MarketId, OrderSide};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot, watch};
+use tracing::{debug, info, warn};
+
+/// Immutable, consistent view of the portfolio at a point in time
+#[derive(Debug, Clone)]
+pub struct PortfolioSnapshot {
+    /// Incremented on every published mutation
+    pub sequence: u64,
+    pub taken_at: DateTime<Utc>,
+    pub positions: Vec<Position>,
+    /// Sum of `positions` current values
+    pub total_value: f64,
+    pub total_pnl: f64,
+    pub rebate_pnl: f64,
+    pub exposure_by_category: Vec<(String, f64)>,
+    pub risk_level: RiskLevel,
+    /// Whether the circuit breaker was halting trading when captured
+    pub trading_halted: bool,
+    pub metrics: RiskMetrics,
+    /// Strategy capital accounts, best ROI first
+    pub capital_accounts: Vec<AccountReport>,
+}
+
+impl PortfolioSnapshot {
+    /// Capture a snapshot of the manager's current state
+    pub fn capture(manager: &PortfolioRiskManager, sequence: u64) -> Self {
+        let summary = manager.get_summary();
+        let positions: Vec<Position> = manager.portfolio().positions().values().cloned().collect();
+        let total_value = positions.iter().map(|p| p.current_value()).sum();
+
+        Self {
+            sequence,
+            taken_at: Utc::now(),
+            positions,
+            total_value,
+            total_pnl: summary.total_pnl,
+            rebate_pnl: summary.rebate_pnl,
+            exposure_by_category: summary.exposure_by_category,
+            risk_level: summary.risk_level,
+            trading_halted: manager.circuit_breaker_active(),
+            metrics: manager.get_metrics(),
+            capital_accounts: manager.capital_accounts().leaderboard(),
+        }
+    }
+
+    pub fn num_positions(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Portfolio summary built from this snapshot
+    pub fn summary(&self) -> PortfolioSummary {
+        PortfolioSummary {
+            total_value: self.total_value,
+            num_positions: self.positions.len(),
+            total_pnl: self.total_pnl,
+            rebate_pnl: self.rebate_pnl,
+            exposure_by_category: self.exposure_by_category.clone(),
+            risk_level: self.risk_level,
+        }
+    }
+
+    /// System state built from this snapshot
+    pub fn system_state(&self) -> SystemStateView {
+        SystemStateView {
+            sequence: self.sequence,
+            as_of: self.taken_at,
+            trading_halted: self.trading_halted,
+            risk_level: self.risk_level,
+            num_positions: self.positions.len(),
+            total_value: self.total_value,
+            total_pnl: self.total_pnl,
+            exposure_by_category: self.exposure_by_category.clone(),
+            var_95: self.metrics.var_95,
+            max_drawdown: self.metrics.max_drawdown,
+        }
+    }
+}
+
+/// What the status API and agents read about the trading system
+///
+/// Every field comes from the same snapshot, so they agree with each other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SystemStateView {
+    pub sequence: u64,
+    pub as_of: DateTime<Utc>,
+    pub trading_halted: bool,
+    pub risk_level: RiskLevel,
+    pub num_positions: usize,
+    pub total_value: f64,
+    pub total_pnl: f64,
+    pub exposure_by_category: Vec<(String, f64)>,
+    pub var_95: Option<f64>,
+    pub max_drawdown: f64,
+}
+
+/// Portfolio exposure at one published snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExposureSample {
+    pub sequence: u64,
+    pub taken_at: DateTime<Utc>,
+    pub total_value: f64,
+    pub by_category: Vec<(String, f64)>,
+}
+
+/// Records portfolio exposure over time from published snapshots
+///
+/// Keeps the latest `capacity` samples. The watch channel only holds the
+/// newest snapshot, so a recorder that falls behind skips the ones it
+/// missed rather than delaying the writer.
+#[derive(Debug, Clone)]
+pub struct ExposureRecorder {
+    samples: VecDeque<ExposureSample>,
+    capacity: usize,
+}
+
+impl ExposureRecorder {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Record the snapshot's exposure; false if it is not newer than the
+    /// last one recorded
+    pub fn record(&mut self, snapshot: &PortfolioSnapshot) -> bool {
+        if self.samples.back().is_some_and(|last| last.sequence >= snapshot.sequence) {
+            return false;
+        }
+
+        self.samples.push_back(ExposureSample {
+            sequence: snapshot.sequence,
+            taken_at: snapshot.taken_at,
+            total_value: snapshot.total_value,
+            by_category: snapshot.exposure_by_category.clone(),
+        });
+        while self.samples.len() > self.capacity {
+            self.samples.pop_front();
+        }
+        true
+    }
+
+    /// Record every snapshot seen until the service stops, then return
+    pub async fn run(mut self, mut snapshots: watch::Receiver<Arc<PortfolioSnapshot>>) -> Self {
+        loop {
+            let snapshot = snapshots.borrow_and_update().clone();
+            self.record(&snapshot);
+            if snapshots.changed().await.is_err() {
+                break;
+            }
+        }
+        self
+    }
+
+    /// Samples, oldest first
+    pub fn samples(&self) -> impl Iterator<Item = &ExposureSample> {
+        self.samples.iter()
+    }
+
+    pub fn latest(&self) -> Option<&ExposureSample> {
+        self.samples.back()
+    }
+}
+
+/// Commands handled by the portfolio writer task
+enum PortfolioCommand {
+    ProcessEvent {
+        event: MarketEvent,
+        respond_to: oneshot::Sender<anyhow::Result<()>>,
+    },
+    EvaluateTrade {
+        market_id: MarketId,
+        outcome_id: String,
+        side: OrderSide,
+        price: f64,
+        size: f64,
+        respond_to: oneshot::Sender<Result<TradeEvaluation, RiskViolation>>,
+    },
+    ResolveMarket {
+        market_id: MarketId,
+        outcome_id: String,
+        respond_to: oneshot::Sender<anyhow::Result<ResolutionPnl>>,
+    },
+    SetCategory {
+        market_id: MarketId,
+        category: String,
+    },
+    ForceResetCircuitBreaker,
+}
+
+/// Owns the portfolio and applies all mutations
+pub struct PortfolioService {
+    manager: PortfolioRiskManager,
+    sequence: u64,
+    commands: mpsc::Receiver<PortfolioCommand>,
+    snapshots: watch::Sender<Arc<PortfolioSnapshot>>,
+}
+
+impl PortfolioService {
+    /// Spawn the writer task and return a handle to it
+    pub fn spawn(manager: PortfolioRiskManager) -> PortfolioHandle {
+        let (tx, rx) = mpsc::channel(1024);
+        let (snapshot_tx, snapshot_rx) = watch::channel(Arc::new(PortfolioSnapshot::capture(&manager, 0)));
+
+        let service = Self {
+            manager,
+            sequence: 0,
+            commands: rx,
+            snapshots: snapshot_tx,
+        };
+        tokio::spawn(service.run());
+
+        PortfolioHandle {
+            tx,
+            snapshots: snapshot_rx,
+        }
+    }
+
+    async fn run(mut self) {
+        info!("Portfolio service started");
+
+        while let Some(cmd) = self.commands.recv().await {
+            match cmd {
+                PortfolioCommand::ProcessEvent { event, respond_to } => {
+                    let result = self.manager.process_event(&event);
+                    if let Err(e) = &result {
+                        warn!("Failed to process event for market {}: {}", event.market_id(), e);
+                    }
+                    self.publish();
+                    let _ = respond_to.send(result);
+                }
+                PortfolioCommand::EvaluateTrade {
+                    market_id,
+                    outcome_id,
+                    side,
+                    price,
+                    size,
+                    respond_to,
+                } => {
+                    let result = self.manager.evaluate_trade(market_id, &outcome_id, side, price, size);
+                    let _ = respond_to.send(result);
+                }
+                PortfolioCommand::ResolveMarket { market_id, outcome_id, respond_to } => {
+                    let result = self.manager.resolve_market(market_id, &outcome_id);
+                    if let Err(e) = &result {
+                        warn!("Failed to resolve market {}: {}", market_id, e);
+                    }
+                    self.publish();
+                    let _ = respond_to.send(result);
+                }
+                PortfolioCommand::SetCategory { market_id, category } => {
+                    self.manager.set_market_category(market_id, &category);
+                    self.publish();
+                }
+                PortfolioCommand::ForceResetCircuitBreaker => {
+                    self.manager.force_reset_circuit_breaker();
+                    self.publish();
+                }
+            }
+        }
+
+        info!("Portfolio service stopped");
+    }
+
+    /// Publish a snapshot of the fully applied state
+    fn publish(&mut self) {
+        self.sequence += 1;
+        let snapshot = PortfolioSnapshot::capture(&self.manager, self.sequence);
+        debug!("Publishing portfolio snapshot {}", self.sequence);
+        self.snapshots.send_replace(Arc::new(snapshot));
+    }
+}
+
+/// Handle for submitting mutations and reading snapshots
+#[derive(Clone)]
+pub struct PortfolioHandle {
+    tx: mpsc::Sender<PortfolioCommand>,
+    snapshots: watch::Receiver<Arc<PortfolioSnapshot>>,
+}
+
+impl PortfolioHandle {
+    /// Apply a market event; resolves once the new snapshot is published
+    pub async fn process_event(&self, event: MarketEvent) -> anyhow::Result<()> {
+        let (respond_to, rx) = oneshot::channel();
+        self.tx
+            .send(PortfolioCommand::ProcessEvent { event, respond_to })
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to send event: {}", e))?;
+
+        rx.await
+            .map_err(|e| anyhow::anyhow!("Portfolio service dropped event: {}", e))?
+    }
+
+    /// Evaluate a potential trade against the writer's current state
+    pub async fn evaluate_trade(
+        &self,
+        market_id: MarketId,
+        outcome_id: &str,
+        side: OrderSide,
+        price: f64,
+        size: f64,
+    ) -> anyhow::Result<Result<TradeEvaluation, RiskViolation>> {
+        let (respond_to, rx) = oneshot::channel();
+        self.tx
+            .send(PortfolioCommand::EvaluateTrade {
+                market_id,
+                outcome_id: outcome_id.to_string(),
+                side,
+                price,
+                size,
+                respond_to,
+            })
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to send trade evaluation: {}", e))?;
+
+        rx.await
+            .map_err(|e| anyhow::anyhow!("Portfolio service dropped trade evaluation: {}", e))
+    }
+
+    /// Resolve a market, returning the PnL it realized
+    pub async fn resolve_market(&self, market_id: MarketId, outcome_id: &str) -> anyhow::Result<ResolutionPnl> {
+        let (respond_to, rx) = oneshot::channel();
+        self.tx
+            .send(PortfolioCommand::ResolveMarket {
+                market_id,
+                outcome_id: outcome_id.to_string(),
+                respond_to,
+            })
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to send resolution: {}", e))?;
+
+        rx.await
+            .map_err(|e| anyhow::anyhow!("Portfolio service dropped resolution: {}", e))?
+    }
+
+    /// Register a market's category
+    pub async fn set_market_category(&self, market_id: MarketId, category: &str) -> anyhow::Result<()> {
+        self.tx
+            .send(PortfolioCommand::SetCategory {
+                market_id,
+                category: category.to_string(),
+            })
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to send category: {}", e))
+    }
+
+    /// Operator override to resume trading before the cooldown expires
+    pub async fn force_reset_circuit_breaker(&self) -> anyhow::Result<()> {
+        self.tx
+            .send(PortfolioCommand::ForceResetCircuitBreaker)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to send circuit breaker reset: {}", e))
+    }
+
+    /// Latest published snapshot
+    pub fn snapshot(&self) -> Arc<PortfolioSnapshot> {
+        self.snapshots.borrow().clone()
+    }
+
+    /// Subscribe to snapshot updates
+    pub fn subscribe(&self) -> watch::Receiver<Arc<PortfolioSnapshot>> {
+        self.snapshots.clone()
+    }
+
+    pub fn get_summary(&self) -> PortfolioSummary {
+        self.snapshot().summary()
+    }
+
+    pub fn get_metrics(&self) -> RiskMetrics {
+        self.snapshot().metrics.clone()
+    }
+
+    pub fn system_state(&self) -> SystemStateView {
+        self.snapshot().system_state()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{PriceTick, Trade, Uuid};
+
+    fn buy(market_id: MarketId, price: f64, size: f64) -> MarketEvent {
+        MarketEvent::Trade(Trade {
+            id: Uuid::new_v4(),
+            market_id,
+            outcome_id: "YES".into(),
+            price,
+            size,
+            side: OrderSide::Buy,
+            timestamp: Utc::now(),
+        })
+    }
+
+    fn tick(market_id: MarketId, price: f64) -> MarketEvent {
+        MarketEvent::PriceTick(PriceTick {
+            market_id,
+            outcome_id: "YES".into(),
+            price,
+            volume_24h: 0.0,
+            liquidity: 0.0,
+            timestamp: Utc::now(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_process_event_publishes_snapshot() {
+        let handle = PortfolioService::spawn(PortfolioRiskManager::new().unwrap());
+        assert_eq!(handle.snapshot().sequence, 0);
+
+        handle.process_event(buy(MarketId::random(), 0.5, 100.0)).await.unwrap();
+
+        let snapshot = handle.snapshot();
+        assert_eq!(snapshot.sequence, 1);
+        assert_eq!(snapshot.num_positions(), 1);
+        assert_eq!(handle.get_summary().total_value, 50.0);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_market_returns_realized_pnl() {
+        let handle = PortfolioService::spawn(PortfolioRiskManager::new().unwrap());
+        let market_id = MarketId::random();
+        handle.process_event(buy(market_id, 0.4, 100.0)).await.unwrap();
+
+        let pnl = handle.resolve_market(market_id, "YES").await.unwrap();
+        assert!((pnl.total - 60.0).abs() < 1e-9);
+        assert!(pnl.by_strategy.is_empty());
+        assert_eq!(handle.snapshot().num_positions(), 0);
+
+        // Already settled, so nothing more to realize
+        assert_eq!(handle.resolve_market(market_id, "YES").await.unwrap().total, 0.0);
+    }
+
+    /// What every reader should see at one sequence number
+    #[derive(Debug)]
+    struct Expected {
+        num_positions: usize,
+        total_value: f64,
+        total_pnl: f64,
+        by_category: Vec<(String, f64)>,
+    }
+
+    impl Expected {
+        fn of(manager: &PortfolioRiskManager) -> Self {
+            let summary = manager.get_summary();
+            Self {
+                num_positions: summary.num_positions,
+                total_value: summary.total_value,
+                total_pnl: summary.total_pnl,
+                by_category: sorted(summary.exposure_by_category),
+            }
+        }
+
+        fn assert_matches(&self, total_value: f64, by_category: &[(String, f64)], sequence: u64) {
+            assert!((total_value - self.total_value).abs() < 1e-9, "total value at {}", sequence);
+            let by_category = sorted(by_category.to_vec());
+            assert_eq!(by_category.len(), self.by_category.len(), "categories at {}", sequence);
+            for ((category, value), (expected_category, expected)) in by_category.iter().zip(&self.by_category) {
+                assert_eq!(category, expected_category);
+                assert!((value - expected).abs() < 1e-9, "{} exposure at {}", category, sequence);
+            }
+        }
+    }
+
+    fn sorted(mut exposure: Vec<(String, f64)>) -> Vec<(String, f64)> {
+        exposure.sort_by(|a, b| a.0.cmp(&b.0));
+        exposure
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_readers_never_see_torn_state() {
+        let markets: Vec<MarketId> = (0..8).map(|_| MarketId::random()).collect();
+        let mut events = Vec::new();
+        for i in 0..100 {
+            let market_id = markets[i % markets.len()];
+            events.push(buy(market_id, 0.4, 1.0 + (i % 7) as f64));
+            events.push(tick(market_id, 0.3 + (i % 5) as f64 * 0.05));
+            if i % 10 == 9 {
+                events.push(MarketEvent::MarketResolved { market_id, outcome_id: "YES".into() });
+            }
+        }
+
+        // Replay the same commands on a manager of our own to know the state
+        // after each one
+        let mut replay = PortfolioRiskManager::new().unwrap();
+        let mut expected = vec![Expected::of(&replay)];
+        for (m, market_id) in markets.iter().enumerate() {
+            replay.set_market_category(*market_id, ["politics", "crypto", "sports"][m % 3]);
+            expected.push(Expected::of(&replay));
+        }
+        for event in &events {
+            let _ = replay.process_event(event);
+            expected.push(Expected::of(&replay));
+        }
+        let expected = Arc::new(expected);
+
+        let handle = PortfolioService::spawn(PortfolioRiskManager::new().unwrap());
+        let recorder = tokio::spawn(ExposureRecorder::new(10_000).run(handle.subscribe()));
+        let last_sequence = (expected.len() - 1) as u64;
+
+        let mut readers = Vec::new();
+        for _ in 0..4 {
+            let handle = handle.clone();
+            let expected = expected.clone();
+            readers.push(tokio::spawn(async move {
+                let mut seen = 0;
+                loop {
+                    let view = handle.system_state();
+                    assert!(view.sequence >= seen);
+                    seen = view.sequence;
+
+                    let state = &expected[view.sequence as usize];
+                    assert_eq!(view.num_positions, state.num_positions, "positions at {}", view.sequence);
+                    assert!((view.total_pnl - state.total_pnl).abs() < 1e-9, "pnl at {}", view.sequence);
+                    state.assert_matches(view.total_value, &view.exposure_by_category, view.sequence);
+
+                    if view.sequence == last_sequence {
+                        break;
+                    }
+                    tokio::task::yield_now().await;
+                }
+            }));
+        }
+
+        // A single writer, so snapshot sequence n is the state after command n
+        for (m, market_id) in markets.iter().enumerate() {
+            handle.set_market_category(*market_id, ["politics", "crypto", "sports"][m % 3]).await.unwrap();
+        }
+        for event in events {
+            let _ = handle.process_event(event).await;
+        }
+
+        for reader in readers {
+            reader.await.unwrap();
+        }
+        assert_eq!(handle.snapshot().sequence, last_sequence);
+
+        // The recorder returns once the service stops
+        drop(handle);
+        let recorder = recorder.await.unwrap();
+        assert_eq!(recorder.latest().unwrap().sequence, last_sequence);
+        for sample in recorder.samples() {
+            expected[sample.sequence as usize].assert_matches(sample.total_value, &sample.by_category, sample.sequence);
+        }
+    }
+
+    #[test]
+    fn test_exposure_recorder_keeps_latest_samples() {
+        let manager = PortfolioRiskManager::new().unwrap();
+        let mut recorder = ExposureRecorder::new(2);
+        for sequence in [1, 2, 2, 3] {
+            recorder.record(&PortfolioSnapshot::capture(&manager, sequence));
+        }
+        assert!(!recorder.record(&PortfolioSnapshot::capture(&manager, 1)));
+
+        let sequences: Vec<u64> = recorder.samples().map(|sample| sample.sequence).collect();
+        assert_eq!(sequences, [2, 3]);
+    }
+}
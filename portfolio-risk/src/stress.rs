@@ -0,0 +1,486 @@
+//! Scenario stress testing
+//!
+//! Projects the portfolio under hypothetical moves — price shocks by
+//! category or across the book, price overrides, forced resolutions and
+//! thinner exit liquidity — and reports what it would be worth and which
+//! limits and circuit breakers would trip. The live portfolio is never
+//! touched; every run works on a copy.
+
+use crate::config::{CircuitBreakerConfig, RiskConfig, RiskLimits};
+use crate::portfolio::{Portfolio, Position};
+use crate::risk::{RiskChecker, RiskViolation};
+use chrono::Utc;
+use common::{CostModel, MarketId, OrderSide, OutcomeId};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// Positions listed in `StressResult::worst_positions`
+const WORST_POSITIONS: usize = 5;
+
+/// One hypothetical move; price moves apply in the order given
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Shock {
+    /// Move every price by `fraction` of itself, e.g. -0.10 for 10% lower
+    Uniform { fraction: f64 },
+    /// Move every price in a category by `change` in price terms, e.g. -0.20
+    /// for 20 points lower; the category matches case-insensitively
+    Category { category: String, change: f64 },
+    /// Mark an outcome at `price`
+    SetPrice {
+        market_id: MarketId,
+        outcome_id: OutcomeId,
+        price: f64,
+    },
+    /// Settle a market on `winning_outcome`, whatever the price shocks say
+    Resolve {
+        market_id: MarketId,
+        winning_outcome: OutcomeId,
+    },
+    /// Scale the size of every bid by `depth_factor` and value positions at
+    /// what selling them into that book would fetch through the cost model
+    Liquidity { depth_factor: f64 },
+}
+
+/// A named set of shocks applied together
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    pub shocks: Vec<Shock>,
+}
+
+impl Scenario {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            shocks: Vec::new(),
+        }
+    }
+
+    pub fn with_shock(mut self, shock: Shock) -> Self {
+        self.shocks.push(shock);
+        self
+    }
+
+    /// This scenario followed by `other`'s shocks
+    pub fn and(mut self, other: Scenario) -> Self {
+        self.name = format!("{} + {}", self.name, other.name);
+        self.shocks.extend(other.shocks);
+        self
+    }
+
+    /// Every price `fraction` lower or higher
+    pub fn uniform(fraction: f64) -> Self {
+        Self::new(format!("uniform {:+}%", fraction * 100.0)).with_shock(Shock::Uniform { fraction })
+    }
+
+    /// Every price in `category` moved by `change` in price terms
+    pub fn category_crash(category: &str, change: f64) -> Self {
+        Self::new(format!("{} {:+} points", category, change * 100.0)).with_shock(Shock::Category {
+            category: category.to_string(),
+            change,
+        })
+    }
+
+    /// Bids half as deep as in the registered books
+    pub fn liquidity_halving() -> Self {
+        Self::new("liquidity halving").with_shock(Shock::Liquidity { depth_factor: 0.5 })
+    }
+
+    /// `market_id` settles on `winning_outcome`
+    pub fn forced_resolution(market_id: MarketId, winning_outcome: &str) -> Self {
+        Self::new(format!("{} resolves {}", market_id, winning_outcome)).with_shock(Shock::Resolve {
+            market_id,
+            winning_outcome: winning_outcome.into(),
+        })
+    }
+
+    /// The built-in scenarios: 10% lower across the board, politics 20
+    /// points lower, and exit liquidity halved
+    pub fn library() -> Vec<Scenario> {
+        vec![
+            Self::uniform(-0.10),
+            Self::category_crash("politics", -0.20),
+            Self::liquidity_halving(),
+        ]
+    }
+}
+
+/// How one position fares under a scenario
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionImpact {
+    pub market_id: MarketId,
+    pub outcome_id: OutcomeId,
+    pub current_value: f64,
+    pub projected_value: f64,
+    pub pnl_delta: f64,
+}
+
+/// Outcome of running a scenario against a portfolio
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StressResult {
+    pub scenario: String,
+    pub current_value: f64,
+    pub projected_value: f64,
+    pub pnl_delta: f64,
+    /// Limits the stressed portfolio would breach, then the circuit
+    /// breakers the loss would trip
+    pub violations: Vec<RiskViolation>,
+    /// Largest losses first, at most five
+    pub worst_positions: Vec<PositionImpact>,
+}
+
+impl StressResult {
+    /// Whether any limit or circuit breaker would trip
+    pub fn breached(&self) -> bool {
+        !self.violations.is_empty()
+    }
+}
+
+/// Runs scenarios against a portfolio under a set of risk limits
+#[derive(Debug, Clone)]
+pub struct ScenarioEngine {
+    risk_limits: RiskLimits,
+    circuit_breakers: CircuitBreakerConfig,
+    costs: CostModel,
+    /// Bids of (price, size) to exit each outcome into
+    bids: HashMap<(MarketId, OutcomeId), Vec<(f64, f64)>>,
+}
+
+impl ScenarioEngine {
+    pub fn new(risk_limits: RiskLimits, circuit_breakers: CircuitBreakerConfig) -> Self {
+        Self {
+            risk_limits,
+            circuit_breakers,
+            costs: CostModel::default(),
+            bids: HashMap::new(),
+        }
+    }
+
+    pub fn from_config(config: &RiskConfig) -> Self {
+        Self::new(config.risk_limits.clone(), config.circuit_breakers.clone())
+    }
+
+    /// Fees and fallback slippage for exits under a `Liquidity` shock
+    pub fn with_cost_model(mut self, costs: CostModel) -> Self {
+        self.costs = costs;
+        self
+    }
+
+    /// Bids an outcome's position would be sold into under a `Liquidity`
+    /// shock; an outcome without a book exits at the fallback slippage
+    pub fn set_bids(&mut self, market_id: MarketId, outcome_id: &str, bids: Vec<(f64, f64)>) {
+        self.bids.insert((market_id, outcome_id.into()), bids);
+    }
+
+    /// Project `portfolio` under `scenario`
+    pub fn run(&self, portfolio: &Portfolio, scenario: &Scenario) -> StressResult {
+        let resolutions: HashMap<MarketId, &OutcomeId> = scenario
+            .shocks
+            .iter()
+            .filter_map(|shock| match shock {
+                Shock::Resolve { market_id, winning_outcome } => Some((*market_id, winning_outcome)),
+                _ => None,
+            })
+            .collect();
+        let depth_factor: Option<f64> = scenario
+            .shocks
+            .iter()
+            .filter_map(|shock| match shock {
+                Shock::Liquidity { depth_factor } => Some(*depth_factor),
+                _ => None,
+            })
+            .reduce(|a, b| a * b);
+
+        // Resolved outcomes are marked at their payout of 1 or 0, so the
+        // breakers see the whole move as one step down from the current value
+        let mut marked = portfolio.clone();
+        let mut impacts = Vec::with_capacity(portfolio.num_positions());
+        for position in portfolio.positions().values() {
+            let current_value = position.current_value();
+            let price = match resolutions.get(&position.market_id) {
+                Some(winner) if **winner == position.outcome_id => 1.0,
+                Some(_) => 0.0,
+                None => {
+                    let price = self.shocked_price(portfolio, position, scenario);
+                    match depth_factor {
+                        Some(factor) => self.exit_price(position, price, factor),
+                        None => price,
+                    }
+                }
+            };
+            marked.update_price(position.market_id, position.outcome_id.as_str(), price);
+            let projected_value = position.shares() * price;
+            impacts.push(PositionImpact {
+                market_id: position.market_id,
+                outcome_id: position.outcome_id.clone(),
+                current_value,
+                projected_value,
+                pnl_delta: projected_value - current_value,
+            });
+        }
+
+        let current_value = portfolio.total_value();
+        let projected_value: f64 = impacts.iter().map(|i| i.projected_value).sum();
+        let pnl_delta = projected_value - current_value;
+        let now = Utc::now();
+        marked.record_pnl_at(now, 0.0, current_value);
+        marked.record_pnl_at(now, pnl_delta, projected_value);
+
+        // Limits apply to what stays open once the resolved markets settle
+        let mut settled = marked.clone();
+        for (market_id, winner) in &resolutions {
+            let _ = settled.resolve_market(*market_id, winner.as_str());
+        }
+
+        let mut violations = self.limit_violations(&settled);
+        if pnl_delta < -self.risk_limits.daily_loss_limit {
+            violations.push(RiskViolation::DailyLossLimitExceeded {
+                daily_pnl: pnl_delta,
+                limit: self.risk_limits.daily_loss_limit,
+            });
+        }
+        let mut checker = RiskChecker::with_circuit_breakers(self.risk_limits.clone(), self.circuit_breakers.clone());
+        violations.extend(checker.check_circuit_breakers(&marked));
+
+        impacts.sort_by(|a, b| a.pnl_delta.total_cmp(&b.pnl_delta));
+        impacts.truncate(WORST_POSITIONS);
+
+        StressResult {
+            scenario: scenario.name.clone(),
+            current_value,
+            projected_value,
+            pnl_delta,
+            violations,
+            worst_positions: impacts,
+        }
+    }
+
+    /// Run each scenario in turn
+    pub fn run_all(&self, portfolio: &Portfolio, scenarios: &[Scenario]) -> Vec<StressResult> {
+        scenarios.iter().map(|scenario| self.run(portfolio, scenario)).collect()
+    }
+
+    /// An outcome's mark after every price shock, clamped to [0, 1]
+    fn shocked_price(&self, portfolio: &Portfolio, position: &Position, scenario: &Scenario) -> f64 {
+        let category = portfolio.category(position.market_id);
+        scenario
+            .shocks
+            .iter()
+            .fold(position.current_price, |price, shock| match shock {
+                Shock::Uniform { fraction } => price * (1.0 + fraction),
+                Shock::Category { category: shocked, change }
+                    if category.is_some_and(|c| c.eq_ignore_ascii_case(shocked)) =>
+                {
+                    price + change
+                }
+                Shock::SetPrice { market_id, outcome_id, price: set }
+                    if *market_id == position.market_id && *outcome_id == position.outcome_id =>
+                {
+                    *set
+                }
+                _ => price,
+            })
+            .clamp(0.0, 1.0)
+    }
+
+    /// Price per share selling the position into its outcome's bids, moved
+    /// from the current price to `price` and thinned by `depth_factor`, net
+    /// of fees
+    fn exit_price(&self, position: &Position, price: f64, depth_factor: f64) -> f64 {
+        let shift = price - position.current_price;
+        let bids: Vec<(f64, f64)> = self
+            .bids
+            .get(&(position.market_id, position.outcome_id.clone()))
+            .map(|bids| bids.iter().map(|&(p, s)| ((p + shift).max(0.0), s * depth_factor)).collect())
+            .unwrap_or_default();
+        self.costs
+            .fill(OrderSide::Sell, &bids, price, position.shares())
+            .effective_price(OrderSide::Sell)
+            .max(0.0)
+    }
+
+    /// Standing limits the portfolio breaches as it is
+    fn limit_violations(&self, portfolio: &Portfolio) -> Vec<RiskViolation> {
+        let limits = &self.risk_limits;
+        let mut violations = Vec::new();
+
+        let exposure = portfolio.net_exposure();
+        if exposure > limits.max_total_exposure {
+            violations.push(RiskViolation::MaxTotalExposureExceeded {
+                current: exposure,
+                proposed: 0.0,
+                limit: limits.max_total_exposure,
+            });
+        }
+
+        let mut positions: Vec<_> = portfolio.positions().values().collect();
+        positions.sort_by(|a, b| b.current_value().total_cmp(&a.current_value()));
+        for position in positions {
+            if position.current_value() > limits.max_position_size {
+                violations.push(RiskViolation::MaxPositionSizeExceeded {
+                    proposed: position.current_value(),
+                    limit: limits.max_position_size,
+                });
+            }
+        }
+
+        for (category, exposure) in portfolio.exposure_by_category() {
+            let limit = limits.theme_exposure_limit(&category);
+            if exposure > limit {
+                violations.push(RiskViolation::MaxThemeExposureExceeded {
+                    theme: category,
+                    current: exposure,
+                    proposed: 0.0,
+                    limit,
+                });
+            }
+        }
+
+        let groups: BTreeSet<&str> = portfolio
+            .positions()
+            .keys()
+            .filter_map(|(market_id, _)| portfolio.neg_risk_group(*market_id))
+            .collect();
+        for group_id in groups {
+            let exposure = portfolio.group_exposure(group_id).net_exposure;
+            if exposure > limits.max_group_exposure {
+                violations.push(RiskViolation::MaxGroupExposureExceeded {
+                    group_id: group_id.to_string(),
+                    current: exposure,
+                    projected: exposure,
+                    limit: limits.max_group_exposure,
+                });
+            }
+        }
+
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 400 Politics YES shares marked at 0.50 and 200 at 0.60, plus 300
+    /// Sports YES shares at 0.40: 200 + 120 + 120 = 440
+    fn known_portfolio() -> (Portfolio, [MarketId; 3]) {
+        let markets = [MarketId::random(), MarketId::random(), MarketId::random()];
+        let mut portfolio = Portfolio::new();
+        portfolio.add_position(markets[0], "YES", 200.0, 0.50).unwrap();
+        portfolio.add_position(markets[1], "YES", 100.0, 0.50).unwrap();
+        portfolio.update_price(markets[1], "YES", 0.60);
+        portfolio.add_position(markets[2], "YES", 120.0, 0.40).unwrap();
+        portfolio.set_category(markets[0], "Politics".to_string());
+        portfolio.set_category(markets[1], "Politics".to_string());
+        portfolio.set_category(markets[2], "Sports".to_string());
+        (portfolio, markets)
+    }
+
+    /// Default limits, but with room for the 200 position
+    fn engine() -> ScenarioEngine {
+        let limits = RiskLimits {
+            max_position_size: 250.0,
+            ..RiskLimits::default()
+        };
+        ScenarioEngine::new(limits, CircuitBreakerConfig::default())
+    }
+
+    #[test]
+    fn test_politics_crash_projects_exact_values() {
+        let (portfolio, markets) = known_portfolio();
+        let result = engine().run(&portfolio, &Scenario::category_crash("politics", -0.20));
+
+        // 400 * 0.30 + 200 * 0.40 + 120 untouched
+        assert!((result.current_value - 440.0).abs() < 1e-9);
+        assert!((result.projected_value - 320.0).abs() < 1e-9);
+        assert!((result.pnl_delta + 120.0).abs() < 1e-9);
+
+        // 80 lost on the first market, 40 on the second, nothing on Sports
+        assert_eq!(result.worst_positions.len(), 3);
+        assert_eq!(result.worst_positions[0].market_id, markets[0]);
+        assert!((result.worst_positions[0].pnl_delta + 80.0).abs() < 1e-9);
+        assert!((result.worst_positions[1].pnl_delta + 40.0).abs() < 1e-9);
+        assert_eq!(result.worst_positions[2].pnl_delta, 0.0);
+
+        // 120 is past the 100 daily loss limit, and 27% down past the 15%
+        // drawdown breaker
+        assert!(result.violations.iter().any(|v| matches!(v, RiskViolation::DailyLossLimitExceeded { .. })));
+        assert!(result
+            .violations
+            .iter()
+            .any(|v| matches!(v, RiskViolation::MaxDrawdownExceeded { current, .. } if (*current - 120.0 / 440.0).abs() < 1e-9)));
+
+        // The portfolio itself is untouched
+        assert!((portfolio.total_value() - 440.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_forced_resolution_settles_at_payout() {
+        let (portfolio, markets) = known_portfolio();
+
+        // Sports resolves NO: its 120 goes to zero, past the 100 daily loss limit
+        let engine = engine();
+        let result = engine.run(&portfolio, &Scenario::forced_resolution(markets[2], "NO"));
+        assert!((result.projected_value - 320.0).abs() < 1e-9);
+        assert!((result.worst_positions[0].pnl_delta + 120.0).abs() < 1e-9);
+        assert!(result
+            .violations
+            .iter()
+            .any(|v| matches!(v, RiskViolation::DailyLossLimitExceeded { daily_pnl, .. } if (*daily_pnl + 120.0).abs() < 1e-9)));
+
+        // Resolving YES pays a dollar a share: 300 against 120 marked
+        let result = engine.run(&portfolio, &Scenario::forced_resolution(markets[2], "YES"));
+        assert!((result.projected_value - 620.0).abs() < 1e-9);
+        assert!((result.pnl_delta - 180.0).abs() < 1e-9);
+        assert!(!result.breached(), "{:?}", result.violations);
+    }
+
+    #[test]
+    fn test_composed_scenarios_and_limits() {
+        let (portfolio, markets) = known_portfolio();
+
+        // A price override applies after the uniform move: 400 * 0.45 +
+        // 200 * 0.54 + 300 * 0.90
+        let scenario = Scenario::uniform(-0.10).with_shock(Shock::SetPrice {
+            market_id: markets[2],
+            outcome_id: "YES".into(),
+            price: 0.90,
+        });
+        let result = engine().run(&portfolio, &scenario);
+        assert!((result.projected_value - (180.0 + 108.0 + 270.0)).abs() < 1e-9);
+        // 270 in one market is over the 250 position limit
+        assert!(result
+            .violations
+            .iter()
+            .any(|v| matches!(v, RiskViolation::MaxPositionSizeExceeded { proposed, .. } if (*proposed - 270.0).abs() < 1e-9)));
+
+        let combined = Scenario::category_crash("politics", -0.20).and(Scenario::forced_resolution(markets[2], "NO"));
+        assert_eq!(combined.shocks.len(), 2);
+        assert!((engine().run(&portfolio, &combined).projected_value - 200.0).abs() < 1e-9);
+
+        // Scenarios round-trip so they can be stored and re-run
+        let json = serde_json::to_string(&combined).unwrap();
+        assert!(json.contains("\"type\":\"resolve\""));
+        let restored: Scenario = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, combined);
+    }
+
+    #[test]
+    fn test_liquidity_halving_exits_through_the_book() {
+        let (portfolio, markets) = known_portfolio();
+        let mut engine = engine().with_cost_model(CostModel::new().with_fallback_slippage_bps(1000.0));
+        // 300 Sports shares against 200 at 0.40 and 400 at 0.38
+        engine.set_bids(markets[2], "YES", vec![(0.40, 200.0), (0.38, 400.0)]);
+
+        // Halved: 100 at 0.40, 200 at 0.38 = 116; the Politics positions
+        // have no book and exit 10% under their marks, 180 + 108
+        let result = engine.run(&portfolio, &Scenario::liquidity_halving());
+        assert!((result.projected_value - (116.0 + 180.0 + 108.0)).abs() < 1e-9);
+
+        // Library scenarios all run
+        let results = engine.run_all(&portfolio, &Scenario::library());
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|r| r.pnl_delta < 0.0));
+    }
+}
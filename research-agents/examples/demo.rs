@@ -2,23 +2,22 @@
 //!
 //! This example demonstrates:
 //! 1. Setting up the orchestrator and agent bus
-//! 2. Creating a sentiment agent
+//! 2. Creating the sentiment and news volume agents
 //! 3. Adding markets and news articles
 //! 4. Running the agents and collecting signals
 //! 5. Example output format
 
 use anyhow::Result;
 use chrono::Utc;
-use common::Market;
+use common::{Market, MarketId};
 use research_agents::{
-    Agent, AgentBus, AgentBusConfig, AgentBusHandle, AgentInput, Orchestrator, OrchestratorConfig,
-    SentimentAgent, SentimentAgentConfig, SentimentSignal,
+    Agent, AgentBus, AgentBusConfig, AgentBusHandle, AgentInput, NewsVolumeAgent, NewsVolumeAgentConfig,
+    Orchestrator, OrchestratorConfig, SentimentAgent, SentimentAgentConfig, SentimentSignal,
 };
 use std::sync::Arc;
 use tokio::time::{sleep, Duration};
 use tracing::{info, Level};
 use tracing_subscriber;
-use uuid::Uuid;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -57,6 +56,11 @@ async fn main() -> Result<()> {
     handle.register_agent(sentiment_agent_boxed).await?;
     info!("✅ Sentiment agent registered");
 
+    // Flags markets whose news coverage suddenly jumps
+    let news_volume_agent = NewsVolumeAgent::new(NewsVolumeAgentConfig::default());
+    handle.register_agent(Box::new(news_volume_agent)).await?;
+    info!("✅ News volume agent registered");
+
     // Also create a separate agent for manual processing in the demo
     let sentiment_agent = SentimentAgent::new(sentiment_config);
 
@@ -109,20 +113,20 @@ async fn main() -> Result<()> {
 fn create_example_markets() -> Vec<Market> {
     vec![
         Market {
-            id: Uuid::new_v4(),
-            condition_id: "cond1".to_string(),
+            id: MarketId::random(),
+            condition_id: "cond1".into(),
             question: "Will Donald Trump win the 2024 US Presidential Election?".to_string(),
             description: "This market resolves to YES if Donald Trump wins the 2024 US Presidential Election.".to_string(),
             category: "Politics".to_string(),
             outcomes: vec![
                 common::Outcome {
-                    id: "yes".to_string(),
+                    id: "yes".into(),
                     name: "Yes".to_string(),
                     price: 0.52,
                     liquidity: 1000000.0,
                 },
                 common::Outcome {
-                    id: "no".to_string(),
+                    id: "no".into(),
                     name: "No".to_string(),
                     price: 0.48,
                     liquidity: 1000000.0,
@@ -130,22 +134,24 @@ fn create_example_markets() -> Vec<Market> {
             ],
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            neg_risk_group: None,
+            tick_size: None,
         },
         Market {
-            id: Uuid::new_v4(),
-            condition_id: "cond2".to_string(),
+            id: MarketId::random(),
+            condition_id: "cond2".into(),
             question: "Will Bitcoin exceed $100,000 by end of 2024?".to_string(),
             description: "This market resolves to YES if Bitcoin (BTC) exceeds $100,000 USD on any major exchange by December 31, 2024.".to_string(),
             category: "Economics".to_string(),
             outcomes: vec![
                 common::Outcome {
-                    id: "yes".to_string(),
+                    id: "yes".into(),
                     name: "Yes".to_string(),
                     price: 0.35,
                     liquidity: 500000.0,
                 },
                 common::Outcome {
-                    id: "no".to_string(),
+                    id: "no".into(),
                     name: "No".to_string(),
                     price: 0.65,
                     liquidity: 500000.0,
@@ -153,22 +159,24 @@ fn create_example_markets() -> Vec<Market> {
             ],
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            neg_risk_group: None,
+            tick_size: None,
         },
         Market {
-            id: Uuid::new_v4(),
-            condition_id: "cond3".to_string(),
+            id: MarketId::random(),
+            condition_id: "cond3".into(),
             question: "Will Russia invade another country in 2024?".to_string(),
             description: "This market resolves to YES if Russian military forces invade any country not currently in conflict by December 31, 2024.".to_string(),
             category: "Geopolitics".to_string(),
             outcomes: vec![
                 common::Outcome {
-                    id: "yes".to_string(),
+                    id: "yes".into(),
                     name: "Yes".to_string(),
                     price: 0.28,
                     liquidity: 750000.0,
                 },
                 common::Outcome {
-                    id: "no".to_string(),
+                    id: "no".into(),
                     name: "No".to_string(),
                     price: 0.72,
                     liquidity: 750000.0,
@@ -176,6 +184,8 @@ fn create_example_markets() -> Vec<Market> {
             ],
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            neg_risk_group: None,
+            tick_size: None,
         },
     ]
 }
@@ -189,6 +199,7 @@ fn create_example_articles() -> Vec<research_agents::sentiment::NewsArticle> {
             themes: "ELECTION;POLITICS;USA;PRESIDENT;CAMPAIGN".to_string(),
             tone: 30.0, // Slightly positive
             timestamp: Utc::now(),
+            source: "example.com".to_string(),
         },
         research_agents::sentiment::NewsArticle {
             id: "article2".to_string(),
@@ -197,6 +208,7 @@ fn create_example_articles() -> Vec<research_agents::sentiment::NewsArticle> {
             themes: "ECONOMY;MARKET;GROWTH;STOCK;FINANCE".to_string(),
             tone: 60.0, // Positive
             timestamp: Utc::now(),
+            source: "example.com".to_string(),
         },
         research_agents::sentiment::NewsArticle {
             id: "article3".to_string(),
@@ -205,6 +217,7 @@ fn create_example_articles() -> Vec<research_agents::sentiment::NewsArticle> {
             themes: "CRYPTO;BITCOIN;FINANCE;ECONOMY;GROWTH".to_string(),
             tone: 70.0, // Strong positive
             timestamp: Utc::now(),
+            source: "example.com".to_string(),
         },
         research_agents::sentiment::NewsArticle {
             id: "article4".to_string(),
@@ -213,6 +226,7 @@ fn create_example_articles() -> Vec<research_agents::sentiment::NewsArticle> {
             themes: "MILITARY;RUSSIA;CONFLICT;GEOPOLITICS;WAR".to_string(),
             tone: -40.0, // Negative
             timestamp: Utc::now(),
+            source: "example.com".to_string(),
         },
         research_agents::sentiment::NewsArticle {
             id: "article5".to_string(),
@@ -221,6 +235,7 @@ fn create_example_articles() -> Vec<research_agents::sentiment::NewsArticle> {
             themes: "ECONOMY;FED;INTEREST_RATE;MONETARY_POLICY".to_string(),
             tone: 50.0, // Positive
             timestamp: Utc::now(),
+            source: "example.com".to_string(),
         },
     ]
 }
@@ -250,7 +265,7 @@ fn print_agent_output(output: &research_agents::agent::AgentOutput) -> Result<()
 
 fn print_example_signal() -> Result<()> {
     let example_signal = SentimentSignal {
-        market_id: Uuid::new_v4(),
+        market_id: MarketId::random(),
         market_category: "Economics".to_string(),
         sentiment: research_agents::sentiment::SentimentScore {
             score: 0.65,
@@ -271,6 +286,9 @@ fn print_example_signal() -> Result<()> {
             "article2".to_string(),
             "article3".to_string(),
         ],
+        momentum: 0.04,
+        previous_score: None,
+        deltas: vec![],
     };
 
     info!("Example SentimentSignal JSON:");
@@ -0,0 +1,52 @@
+//! Feeds the research agents from Layer 0's Kafka topics
+//!
+//! Articles on `news-articles` go to the sentiment agent and markets on
+//! `market-events` to the orchestrator. Ctrl-C hands off what is buffered,
+//! commits it and exits.
+//!
+//! Needs the `kafka` feature:
+//! cargo run --package research-agents --features kafka --example kafka_consumer
+
+use anyhow::Result;
+use research_agents::{
+    AgentBus, AgentBusConfig, EventConsumer, EventConsumerConfig, KafkaConsumer, Orchestrator,
+    OrchestratorConfig, SentimentAgent, SentimentAgentConfig,
+};
+use std::sync::Arc;
+use tracing::{error, info, Level};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_max_level(Level::INFO)
+        .init();
+
+    let bus = Arc::new(AgentBus::new(AgentBusConfig::default()).await?);
+    let mut orchestrator = Orchestrator::new(OrchestratorConfig::default(), bus).await?;
+    let handle = orchestrator.control_handle();
+
+    // Shared so the consumer can add articles to the registered agent
+    let sentiment = Arc::new(SentimentAgent::new(SentimentAgentConfig::default()));
+    handle.register_agent(Box::new(sentiment.clone())).await?;
+
+    let config = EventConsumerConfig::default();
+    let transport = KafkaConsumer::new(&config)?;
+    let mut consumer = EventConsumer::new(config, transport, sentiment, orchestrator.control_handle());
+
+    tokio::spawn(async move {
+        if let Err(e) = orchestrator.run().await {
+            error!("Orchestrator failed: {}", e);
+        }
+    });
+    handle.start().await?;
+
+    let stats = consumer
+        .run(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+    info!("👋 Consumer stopped: {:?}", stats);
+
+    handle.stop().await?;
+    Ok(())
+}
@@ -4,11 +4,17 @@
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, RwLock};
+use tracing::{info, warn};
 use uuid::Uuid;
 use common::Market;
 
+// Lives in common so signal-generation can consume it
+pub use common::AgentOutput;
+
 /// Base configuration for any agent
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentConfig {
@@ -17,6 +23,14 @@ pub struct AgentConfig {
     pub enabled: bool,
     pub max_markets_per_batch: usize,
     pub processing_interval_secs: u64,
+    /// Markets the orchestrator processes at once for this agent: a batch
+    /// is split into this many `process_batch` calls run concurrently
+    #[serde(default = "default_max_concurrent_markets")]
+    pub max_concurrent_markets: usize,
+}
+
+fn default_max_concurrent_markets() -> usize {
+    1
 }
 
 impl Default for AgentConfig {
@@ -27,6 +41,7 @@ impl Default for AgentConfig {
             enabled: true,
             max_markets_per_batch: 100,
             processing_interval_secs: 60,
+            max_concurrent_markets: default_max_concurrent_markets(),
         }
     }
 }
@@ -39,18 +54,6 @@ pub struct AgentInput {
     pub additional_data: Option<serde_json::Value>,
 }
 
-/// Output from agent processing
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AgentOutput {
-    pub agent_id: String,
-    pub market_id: Uuid,
-    pub signal_type: String,
-    pub data: serde_json::Value,
-    pub confidence: f64, // 0.0 to 1.0
-    pub timestamp: DateTime<Utc>,
-    pub processing_time_ms: u64,
-}
-
 /// Current status of an agent
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AgentStatus {
@@ -60,6 +63,61 @@ pub enum AgentStatus {
     Paused,
 }
 
+/// Agent status that can be read from sync code, or from within any
+/// runtime, without blocking
+#[derive(Debug)]
+pub struct AgentStatusCell(AtomicU8);
+
+impl AgentStatusCell {
+    pub fn new(status: AgentStatus) -> Self {
+        Self(AtomicU8::new(status as u8))
+    }
+
+    pub fn get(&self) -> AgentStatus {
+        match self.0.load(Ordering::Acquire) {
+            0 => AgentStatus::Idle,
+            1 => AgentStatus::Processing,
+            2 => AgentStatus::Error,
+            _ => AgentStatus::Paused,
+        }
+    }
+
+    pub fn set(&self, status: AgentStatus) {
+        self.0.store(status as u8, Ordering::Release);
+    }
+
+    /// Mark the agent `Processing` until the guard is dropped
+    ///
+    /// A paused agent stays paused. On drop only `Processing` goes back to
+    /// `Idle`, so a `Pause` that arrives mid-flight is kept.
+    pub fn processing(&self) -> ProcessingGuard<'_> {
+        let _ = self.0.fetch_update(Ordering::AcqRel, Ordering::Acquire, |status| {
+            (status != AgentStatus::Paused as u8).then_some(AgentStatus::Processing as u8)
+        });
+        ProcessingGuard(self)
+    }
+}
+
+/// Returned by `AgentStatusCell::processing`
+pub struct ProcessingGuard<'a>(&'a AgentStatusCell);
+
+impl Drop for ProcessingGuard<'_> {
+    fn drop(&mut self) {
+        let _ = self.0 .0.compare_exchange(
+            AgentStatus::Processing as u8,
+            AgentStatus::Idle as u8,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        );
+    }
+}
+
+impl Default for AgentStatusCell {
+    fn default() -> Self {
+        Self::new(AgentStatus::Idle)
+    }
+}
+
 /// Base trait that all specialist agents must implement
 ///
 /// This trait provides a consistent interface for the orchestrator to manage
@@ -70,6 +128,9 @@ pub trait Agent: Send + Sync {
     fn config(&self) -> &AgentConfig;
 
     /// Get the current status of the agent
+    ///
+    /// Called from async code, so it must not block or enter the runtime;
+    /// back it with an `AgentStatusCell`.
     fn status(&self) -> AgentStatus;
 
     /// Process a single market and generate a signal
@@ -101,12 +162,47 @@ pub trait Agent: Send + Sync {
     async fn on_stop(&self) -> anyhow::Result<()>;
 }
 
+/// A shared agent, so one registered with the orchestrator can still be fed
+/// from elsewhere (e.g. the event consumer adding articles)
+#[async_trait]
+impl<A: Agent + ?Sized> Agent for Arc<A> {
+    fn config(&self) -> &AgentConfig {
+        (**self).config()
+    }
+
+    fn status(&self) -> AgentStatus {
+        (**self).status()
+    }
+
+    async fn process_market(&self, input: AgentInput) -> anyhow::Result<Option<AgentOutput>> {
+        (**self).process_market(input).await
+    }
+
+    async fn process_batch(&self, inputs: Vec<AgentInput>) -> anyhow::Result<Vec<AgentOutput>> {
+        (**self).process_batch(inputs).await
+    }
+
+    async fn handle_control(&self, msg: ControlMessage) -> anyhow::Result<ControlResponse> {
+        (**self).handle_control(msg).await
+    }
+
+    async fn on_start(&self) -> anyhow::Result<()> {
+        (**self).on_start().await
+    }
+
+    async fn on_stop(&self) -> anyhow::Result<()> {
+        (**self).on_stop().await
+    }
+}
+
 /// Control messages from orchestrator to agents
 #[derive(Debug, Clone)]
 pub enum ControlMessage {
     Pause,
     Resume,
-    UpdateConfig(AgentConfig),
+    /// JSON merge patch against the agent's own config type; fields left
+    /// out keep their current value
+    UpdateConfig(serde_json::Value),
     HealthCheck,
     Shutdown,
 }
@@ -117,6 +213,129 @@ pub enum ControlResponse {
     Ok,
     Error(String),
     HealthCheck { status: AgentStatus, uptime_secs: u64 },
+    /// Result of an `UpdateConfig`; nothing is applied if there are errors
+    ConfigUpdated { applied: bool, errors: Vec<String> },
+}
+
+/// Agent config that can be patched at runtime with `ControlMessage::UpdateConfig`
+pub trait PatchableConfig: Serialize + DeserializeOwned {
+    /// Problems with the config, empty if it is valid
+    fn validate(&self) -> Vec<String>;
+
+    /// Tidy a freshly patched config before it is validated
+    fn normalize(&mut self) {}
+
+    /// Apply a JSON merge patch, returning the patched config if it is valid.
+    ///
+    /// Objects are merged key by key and `null` removes a key. The base
+    /// config identifies the agent to the orchestrator and can't be patched.
+    fn patched(&self, patch: &serde_json::Value) -> Result<Self, Vec<String>> {
+        if !patch.is_object() {
+            return Err(vec!["config update must be a JSON object".to_string()]);
+        }
+        if patch.get("base").is_some() {
+            return Err(vec!["base config can't be changed at runtime".to_string()]);
+        }
+
+        let mut value = serde_json::to_value(self).map_err(|e| vec![e.to_string()])?;
+        merge_patch(&mut value, patch);
+
+        let mut config: Self = serde_json::from_value(value).map_err(|e| vec![e.to_string()])?;
+        config.normalize();
+
+        let errors = config.validate();
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// RFC 7396 JSON merge patch
+pub(crate) fn merge_patch(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    let Some(patch) = patch.as_object() else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = serde_json::Value::Object(Default::default());
+    }
+    let target = target.as_object_mut().expect("target is an object");
+
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(target.entry(key.clone()).or_insert(serde_json::Value::Null), value);
+        }
+    }
+}
+
+/// An agent's config, swapped whole on updates so each call works on the
+/// snapshot it started with
+#[derive(Debug)]
+pub struct ConfigCell<C> {
+    /// Names the agent in logs, e.g. "Sentiment agent"
+    label: &'static str,
+    config: RwLock<Arc<C>>,
+}
+
+impl<C: PatchableConfig> ConfigCell<C> {
+    pub fn new(label: &'static str, config: C) -> Self {
+        Self {
+            label,
+            config: RwLock::new(Arc::new(config)),
+        }
+    }
+
+    /// Current config
+    pub fn get(&self) -> Arc<C> {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Patch the config, keeping the current one if the patch is invalid
+    pub fn update(&self, patch: &serde_json::Value) -> ControlResponse {
+        let mut current = self.config.write().unwrap_or_else(|e| e.into_inner());
+
+        match current.patched(patch) {
+            Ok(config) => {
+                info!("{} config updated: {}", self.label, patch);
+                *current = Arc::new(config);
+                ControlResponse::ConfigUpdated { applied: true, errors: vec![] }
+            }
+            Err(errors) => {
+                warn!("Rejected {} config update: {}", self.label.to_lowercase(), errors.join("; "));
+                ControlResponse::ConfigUpdated { applied: false, errors }
+            }
+        }
+    }
+}
+
+/// Answer a control message the way every specialist agent does: Pause and
+/// Resume flip `status`, Shutdown leaves it idle and UpdateConfig patches
+/// `config`
+pub fn handle_common_control<C: PatchableConfig>(
+    msg: ControlMessage,
+    status: &AgentStatusCell,
+    config: &ConfigCell<C>,
+    start_time: std::time::Instant,
+) -> ControlResponse {
+    match msg {
+        ControlMessage::Pause => {
+            status.set(AgentStatus::Paused);
+            ControlResponse::Ok
+        }
+        ControlMessage::Resume | ControlMessage::Shutdown => {
+            status.set(AgentStatus::Idle);
+            ControlResponse::Ok
+        }
+        ControlMessage::HealthCheck => ControlResponse::HealthCheck {
+            status: status.get(),
+            uptime_secs: start_time.elapsed().as_secs(),
+        },
+        ControlMessage::UpdateConfig(patch) => config.update(&patch),
+    }
 }
 
 /// Utility type for agent initialization
@@ -159,6 +378,87 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_status_cell_round_trip() {
+        let cell = AgentStatusCell::default();
+        assert_eq!(cell.get(), AgentStatus::Idle);
+        for status in [AgentStatus::Processing, AgentStatus::Error, AgentStatus::Paused, AgentStatus::Idle] {
+            cell.set(status);
+            assert_eq!(cell.get(), status);
+        }
+    }
+
+    #[test]
+    fn test_processing_keeps_a_pause() {
+        let cell = AgentStatusCell::default();
+        {
+            let _processing = cell.processing();
+            assert_eq!(cell.get(), AgentStatus::Processing);
+        }
+        assert_eq!(cell.get(), AgentStatus::Idle);
+
+        // Paused while processing
+        {
+            let _processing = cell.processing();
+            cell.set(AgentStatus::Paused);
+        }
+        assert_eq!(cell.get(), AgentStatus::Paused);
+
+        // Processing while paused
+        {
+            let _processing = cell.processing();
+            assert_eq!(cell.get(), AgentStatus::Paused);
+        }
+        assert_eq!(cell.get(), AgentStatus::Paused);
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct WindowConfig {
+        window: usize,
+        labels: std::collections::BTreeMap<String, String>,
+    }
+
+    impl PatchableConfig for WindowConfig {
+        fn validate(&self) -> Vec<String> {
+            if self.window == 0 {
+                vec!["window must be positive".to_string()]
+            } else {
+                vec![]
+            }
+        }
+    }
+
+    #[test]
+    fn test_config_cell_applies_only_valid_patches() {
+        let labels = [("a".to_string(), "1".to_string()), ("b".to_string(), "2".to_string())].into();
+        let cell = ConfigCell::new("Test agent", WindowConfig { window: 5, labels });
+        let before = cell.get();
+
+        // Nested objects merge and null removes a key
+        let patch = serde_json::json!({ "labels": { "a": null, "c": "3" } });
+        assert!(matches!(cell.update(&patch), ControlResponse::ConfigUpdated { applied: true, .. }));
+        let after = cell.get();
+        assert_eq!(after.window, 5);
+        assert_eq!(after.labels.keys().collect::<Vec<_>>(), ["b", "c"]);
+        assert_eq!(before.labels.len(), 2);
+
+        for patch in [
+            serde_json::json!({ "window": 0 }),
+            serde_json::json!({ "window": "wide" }),
+            serde_json::json!({ "base": {} }),
+            serde_json::json!([1]),
+        ] {
+            match cell.update(&patch) {
+                ControlResponse::ConfigUpdated { applied, errors } => {
+                    assert!(!applied);
+                    assert!(!errors.is_empty());
+                }
+                other => panic!("Expected ConfigUpdated, got {:?}", other),
+            }
+        }
+        assert_eq!(cell.get().labels, after.labels);
+    }
+
     #[test]
     fn test_agent_config_default() {
         let config = AgentConfig::default();
@@ -0,0 +1,359 @@
+//! Adaptive batching - Sizes each agent's batch to fit its deadline budget
+//!
+//! The scheduler keeps, per agent:
+//! - A rolling per-market latency estimate
+//! - The effective batch size derived from it
+//! - A carry-over queue of markets that did not fit in earlier cycles
+//!
+//! Batches shrink as soon as they stop fitting and grow only after sustained
+//! headroom. Markets age while they wait, so none of them starves.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use common::MarketId;
+
+/// Configuration for adaptive batch sizing
+#[derive(Debug, Clone)]
+pub struct AdaptiveBatchConfig {
+    /// Fraction of an agent's processing interval a batch may take
+    pub budget_fraction: f64,
+    /// Smallest batch ever scheduled
+    pub min_batch_size: usize,
+    /// Weight of the newest observation in the latency estimate (0.0 to 1.0)
+    pub latency_smoothing: f64,
+    /// Multiplier applied to the batch size after a timeout
+    pub timeout_shrink: f64,
+    /// Multiplier applied to the batch size when growing
+    pub growth_factor: f64,
+    /// Consecutive cycles with headroom needed before growing
+    pub headroom_cycles: u32,
+    /// Priority added per cycle a market has waited
+    pub age_boost: f64,
+}
+
+impl Default for AdaptiveBatchConfig {
+    fn default() -> Self {
+        Self {
+            budget_fraction: 0.5,
+            min_batch_size: 1,
+            latency_smoothing: 0.3,
+            timeout_shrink: 0.5,
+            growth_factor: 1.25,
+            headroom_cycles: 3,
+            age_boost: 1.0,
+        }
+    }
+}
+
+/// How a scheduled batch ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchOutcome {
+    Completed,
+    TimedOut,
+    Failed,
+}
+
+/// Batch scheduler for a single agent
+#[derive(Debug, Clone)]
+pub struct BatchScheduler {
+    config: AdaptiveBatchConfig,
+    budget: Duration,
+    max_batch_size: usize,
+    batch_size: usize,
+    /// Smoothed processing time per market (ms), None until the first batch completes
+    latency_per_market_ms: Option<f64>,
+    headroom_streak: u32,
+    /// Cycles each known market has waited since it was last processed
+    waiting: HashMap<MarketId, u32>,
+}
+
+impl BatchScheduler {
+    /// Create a scheduler for an agent with the given processing interval and batch ceiling
+    pub fn new(config: AdaptiveBatchConfig, processing_interval: Duration, max_batch_size: usize) -> Self {
+        let max_batch_size = max_batch_size.max(config.min_batch_size).max(1);
+        let budget = processing_interval.mul_f64(config.budget_fraction.clamp(0.0, 1.0));
+
+        Self {
+            batch_size: config.min_batch_size.max(1),
+            config,
+            budget,
+            max_batch_size,
+            latency_per_market_ms: None,
+            headroom_streak: 0,
+            waiting: HashMap::new(),
+        }
+    }
+
+    /// Time a batch may take before it is abandoned
+    pub fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    /// Effective batch size for the next cycle
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Smoothed processing time per market
+    pub fn latency_per_market(&self) -> Option<Duration> {
+        self.latency_per_market_ms.map(|ms| Duration::from_secs_f64(ms / 1000.0))
+    }
+
+    /// Markets known to the scheduler, processed or not
+    pub fn queued(&self) -> usize {
+        self.waiting.len()
+    }
+
+    /// Cycles a market has waited since it was last processed
+    pub fn waiting_cycles(&self, market_id: MarketId) -> Option<u32> {
+        self.waiting.get(&market_id).copied()
+    }
+
+    /// Pick the next batch from the candidate markets and their base priorities.
+    ///
+    /// Each candidate's priority is boosted by the cycles it has waited, so
+    /// markets carried over from earlier cycles go first and a low base
+    /// priority only delays a market, never starves it.
+    pub fn plan(&mut self, candidates: &[(MarketId, f64)]) -> Vec<MarketId> {
+        // Forget markets that are no longer tracked
+        let known: HashMap<MarketId, f64> = candidates.iter().copied().collect();
+        self.waiting.retain(|market_id, _| known.contains_key(market_id));
+
+        let mut ranked: Vec<(MarketId, f64, u32)> = candidates
+            .iter()
+            .map(|&(market_id, base)| {
+                let waited = *self.waiting.entry(market_id).or_insert(0);
+                (market_id, base + self.config.age_boost * waited as f64, waited)
+            })
+            .collect();
+
+        ranked.sort_by(|a, b| {
+            b.1.total_cmp(&a.1)
+                .then_with(|| b.2.cmp(&a.2))
+                .then_with(|| a.0.cmp(&b.0))
+        });
+
+        let batch: Vec<MarketId> = ranked.iter().take(self.batch_size).map(|r| r.0).collect();
+
+        // Everything left behind ages
+        for (market_id, _, _) in ranked.iter().skip(self.batch_size) {
+            if let Some(waited) = self.waiting.get_mut(market_id) {
+                *waited += 1;
+            }
+        }
+
+        batch
+    }
+
+    /// Record how a planned batch went and adapt the batch size
+    pub fn record(&mut self, batch: &[MarketId], elapsed: Duration, outcome: BatchOutcome) {
+        if batch.is_empty() {
+            return;
+        }
+
+        match outcome {
+            BatchOutcome::Completed => {
+                for market_id in batch {
+                    if let Some(waited) = self.waiting.get_mut(market_id) {
+                        *waited = 0;
+                    }
+                }
+
+                let observed = elapsed.as_secs_f64() * 1000.0 / batch.len() as f64;
+                let alpha = self.config.latency_smoothing.clamp(0.0, 1.0);
+                let first = self.latency_per_market_ms.is_none();
+                let latency = match self.latency_per_market_ms {
+                    Some(previous) => alpha * observed + (1.0 - alpha) * previous,
+                    None => observed,
+                };
+                self.latency_per_market_ms = Some(latency);
+
+                let target = self.target_batch_size(latency);
+                if first || target < self.batch_size {
+                    // Nothing to be cautious about yet, or the batch no longer fits
+                    self.batch_size = target;
+                    self.headroom_streak = 0;
+                } else if target > self.batch_size {
+                    self.headroom_streak += 1;
+                    if self.headroom_streak >= self.config.headroom_cycles {
+                        let grown = (self.batch_size as f64 * self.config.growth_factor).ceil() as usize;
+                        self.batch_size = grown.max(self.batch_size + 1).min(target);
+                        self.headroom_streak = 0;
+                    }
+                } else {
+                    self.headroom_streak = 0;
+                }
+            }
+            BatchOutcome::TimedOut => {
+                self.shrink_after_timeout(batch.len());
+                self.carry_over(batch);
+            }
+            BatchOutcome::Failed => self.carry_over(batch),
+        }
+    }
+
+    /// Record a batch cut off at its deadline after part of it finished
+    ///
+    /// `processed` markets are done and stop waiting; `carried` markets are
+    /// retried ahead of the rest next cycle. The batch size shrinks as after
+    /// any timeout.
+    pub fn record_partial(&mut self, processed: &[MarketId], carried: &[MarketId]) {
+        for market_id in processed {
+            if let Some(waited) = self.waiting.get_mut(market_id) {
+                *waited = 0;
+            }
+        }
+        if carried.is_empty() {
+            return;
+        }
+
+        self.shrink_after_timeout(processed.len() + carried.len());
+        self.carry_over(carried);
+    }
+
+    fn shrink_after_timeout(&mut self, batch_len: usize) {
+        // The whole budget went by for at most this many markets
+        let observed = self.budget.as_secs_f64() * 1000.0 / batch_len as f64;
+        self.latency_per_market_ms = Some(self.latency_per_market_ms.map_or(observed, |l| l.max(observed)));
+
+        let shrunk = (self.batch_size as f64 * self.config.timeout_shrink).floor() as usize;
+        self.batch_size = shrunk.clamp(self.config.min_batch_size.max(1), self.max_batch_size);
+        self.headroom_streak = 0;
+    }
+
+    /// Unprocessed markets keep their place and age like the rest
+    fn carry_over(&mut self, batch: &[MarketId]) {
+        for market_id in batch {
+            if let Some(waited) = self.waiting.get_mut(market_id) {
+                *waited += 1;
+            }
+        }
+    }
+
+    /// Markets processable within the budget at the given latency, within the floor and ceiling
+    fn target_batch_size(&self, latency_per_market_ms: f64) -> usize {
+        let budget_ms = self.budget.as_secs_f64() * 1000.0;
+        let fits = if latency_per_market_ms > 0.0 {
+            (budget_ms / latency_per_market_ms).floor()
+        } else {
+            f64::INFINITY
+        };
+
+        (fits.min(self.max_batch_size as f64) as usize).max(self.config.min_batch_size.max(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduler() -> BatchScheduler {
+        // 10s interval, 5s budget, at most 50 markets per batch
+        BatchScheduler::new(AdaptiveBatchConfig::default(), Duration::from_secs(10), 50)
+    }
+
+    fn markets(n: usize) -> Vec<(MarketId, f64)> {
+        (0..n).map(|_| (MarketId::random(), 0.0)).collect()
+    }
+
+    #[test]
+    fn test_batch_size_tracks_latency() {
+        let mut scheduler = scheduler();
+        let candidates = markets(100);
+        assert_eq!(scheduler.batch_size(), 1);
+
+        // 100ms per market: capped at the ceiling after the first measurement
+        let batch = scheduler.plan(&candidates);
+        scheduler.record(&batch, Duration::from_millis(100), BatchOutcome::Completed);
+        assert_eq!(scheduler.batch_size(), 50);
+
+        // The agent slows down to 500ms per market: the batch shrinks right away
+        let batch = scheduler.plan(&candidates);
+        scheduler.record(&batch, Duration::from_millis(500) * batch.len() as u32, BatchOutcome::Completed);
+        let slowed = scheduler.batch_size();
+        assert!(slowed < 50);
+        let latency = scheduler.latency_per_market().unwrap().as_secs_f64();
+        assert_eq!(slowed, (5.0 / latency) as usize);
+
+        // A timeout halves the batch
+        let batch = scheduler.plan(&candidates);
+        scheduler.record(&batch, scheduler.budget(), BatchOutcome::TimedOut);
+        assert_eq!(scheduler.batch_size(), slowed / 2);
+
+        // Back to fast: growth waits for sustained headroom, then is gradual
+        let shrunk = scheduler.batch_size();
+        let mut sizes = vec![];
+        for _ in 0..6 {
+            let batch = scheduler.plan(&candidates);
+            scheduler.record(&batch, Duration::from_millis(10) * batch.len() as u32, BatchOutcome::Completed);
+            sizes.push(scheduler.batch_size());
+        }
+        assert_eq!(sizes[0], shrunk);
+        assert_eq!(sizes[1], shrunk);
+        assert!(sizes[2] > shrunk && sizes[2] <= (shrunk as f64 * 1.25).ceil() as usize);
+        assert!(sizes[5] > sizes[2]);
+        assert!(sizes.iter().all(|&s| s <= 50));
+    }
+
+    #[test]
+    fn test_markets_carried_over() {
+        let mut scheduler = BatchScheduler::new(AdaptiveBatchConfig::default(), Duration::from_secs(10), 4);
+        let candidates = markets(10);
+
+        let batch = scheduler.plan(&candidates);
+        scheduler.record(&batch, Duration::from_millis(40), BatchOutcome::Completed);
+        assert_eq!(scheduler.batch_size(), 4);
+
+        // The next cycles take the markets left behind before revisiting the first batch
+        let first = scheduler.plan(&candidates);
+        scheduler.record(&first, Duration::from_millis(40), BatchOutcome::Completed);
+        let second = scheduler.plan(&candidates);
+        scheduler.record(&second, Duration::from_millis(40), BatchOutcome::Completed);
+
+        let mut seen: Vec<MarketId> = batch.iter().chain(&first).chain(&second).copied().collect();
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen.len(), 9);
+
+        // A timed-out batch is retried ahead of markets processed since
+        let retried = scheduler.plan(&candidates);
+        scheduler.record(&retried, scheduler.budget(), BatchOutcome::TimedOut);
+        let next = scheduler.plan(&candidates);
+        assert_eq!(next.len(), 2);
+        assert!(next.iter().all(|m| retried.contains(m)));
+        assert_eq!(scheduler.queued(), 10);
+
+        // Removed markets leave the queue
+        scheduler.plan(&candidates[..3]);
+        assert_eq!(scheduler.queued(), 3);
+    }
+
+    #[test]
+    fn test_age_boost_prevents_starvation() {
+        let config = AdaptiveBatchConfig { min_batch_size: 2, ..Default::default() };
+        let mut scheduler = BatchScheduler::new(config.clone(), Duration::from_secs(10), 2);
+
+        // Two high priority markets would fill every batch on priority alone
+        let starved = MarketId::random();
+        let candidates = vec![(MarketId::random(), 10.0), (MarketId::random(), 10.0), (starved, 0.0)];
+
+        let mut cycles = 0;
+        loop {
+            cycles += 1;
+            let batch = scheduler.plan(&candidates);
+            scheduler.record(&batch, Duration::from_millis(10), BatchOutcome::Completed);
+            if batch.contains(&starved) {
+                break;
+            }
+            assert!(cycles < 20, "market starved");
+        }
+        assert_eq!(cycles, 11);
+
+        // Without the boost it never gets a slot
+        let mut unboosted = BatchScheduler::new(AdaptiveBatchConfig { age_boost: 0.0, ..config }, Duration::from_secs(10), 2);
+        for _ in 0..50 {
+            assert!(!unboosted.plan(&candidates).contains(&starved));
+        }
+    }
+}
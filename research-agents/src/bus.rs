@@ -2,13 +2,27 @@
 //!
 //! Provides a publish/subscribe messaging system for agent-to-agent communication.
 //! Currently uses tokio channels, with future support for Redis Streams.
+//!
+//! Unicast sends that cannot be delivered and messages whose consumer keeps
+//! failing are retried with exponential backoff, then moved to a bounded
+//! dead-letter queue (see `AgentBus::dead_letters`).
+//!
+//! `AgentBusHandle::request` sends a `DataRequest` under a fresh correlation
+//! id and waits for the target to `respond` to it, or for the timeout.
 
 use anyhow::Result;
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use tokio::sync::{broadcast, mpsc, RwLock};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{broadcast, mpsc, oneshot, RwLock};
+use tracing::warn;
 use uuid::Uuid;
+use common::MarketId;
 use chrono::{DateTime, Utc};
 
 /// Priority levels for messages
@@ -27,7 +41,7 @@ pub enum AgentMessage {
     /// Signal generated by an agent (e.g., sentiment signal)
     Signal {
         agent_id: String,
-        market_id: Uuid,
+        market_id: MarketId,
         signal_data: serde_json::Value,
         confidence: f64,
         timestamp: DateTime<Utc>,
@@ -38,7 +52,7 @@ pub enum AgentMessage {
         request_id: String,
         requester_id: String,
         target_agent: String,
-        market_id: Uuid,
+        market_id: MarketId,
         query: serde_json::Value,
     },
 
@@ -95,6 +109,14 @@ pub struct AgentBusConfig {
     pub enable_persistence: bool,
     /// Redis connection string (future)
     pub redis_url: Option<String>,
+    /// Retries of a failed delivery or handler before the message is
+    /// dead-lettered (doubled for `MessagePriority::Critical`)
+    pub max_retries: u32,
+    /// Backoff before the first retry, doubling on each further one
+    /// (halved for `MessagePriority::Critical`)
+    pub retry_base_delay_ms: u64,
+    /// Dead letters kept until drained; the oldest are dropped beyond this
+    pub dead_letter_capacity: usize,
 }
 
 impl Default for AgentBusConfig {
@@ -104,6 +126,167 @@ impl Default for AgentBusConfig {
             broadcast_capacity: 100,
             enable_persistence: false,
             redis_url: None,
+            max_retries: 3,
+            retry_base_delay_ms: 50,
+            dead_letter_capacity: 1000,
+        }
+    }
+}
+
+impl AgentBusConfig {
+    /// Retries allowed for a message of the given priority
+    pub fn max_retries_for(&self, priority: MessagePriority) -> u32 {
+        match priority {
+            MessagePriority::Critical => self.max_retries.saturating_mul(2),
+            _ => self.max_retries,
+        }
+    }
+
+    /// Backoff before retry number `retry` (starting at 1) of a message
+    pub fn retry_delay(&self, priority: MessagePriority, retry: u32) -> Duration {
+        let base = match priority {
+            MessagePriority::Critical => self.retry_base_delay_ms / 2,
+            _ => self.retry_base_delay_ms,
+        };
+        let factor = 1u64 << retry.saturating_sub(1).min(16);
+        Duration::from_millis(base.saturating_mul(factor))
+    }
+}
+
+/// A message that could not be delivered or handled within its retries
+#[derive(Debug, Clone)]
+pub struct DeadLetter {
+    pub message: AgentMessage,
+    /// Agent the message was addressed to
+    pub target_agent: String,
+    /// Error of the last attempt
+    pub error: String,
+    pub attempts: u32,
+    pub first_attempt_at: DateTime<Utc>,
+    pub last_attempt_at: DateTime<Utc>,
+}
+
+/// Delivery counters of a bus, shared by all its handles
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AgentBusMetrics {
+    /// Unicast messages accepted by their target's channel
+    pub delivered: u64,
+    /// Messages whose `recv_with` handler succeeded
+    pub handled: u64,
+    /// Retry attempts, of deliveries and handlers alike
+    pub retried: u64,
+    pub dead_lettered: u64,
+    /// Dead letters dropped because the queue was full
+    pub dead_letters_dropped: u64,
+}
+
+type DeadLetterCallback = Arc<dyn Fn(&DeadLetter) + Send + Sync>;
+
+/// Reply channels of in-flight requests by correlation id
+type PendingRequests = Arc<DashMap<String, oneshot::Sender<AgentMessage>>>;
+
+/// Removes an in-flight request when its caller stops waiting, whether it
+/// got a response, timed out or was cancelled
+struct PendingRequest<'a> {
+    pending: &'a PendingRequests,
+    correlation_id: String,
+}
+
+impl Drop for PendingRequest<'_> {
+    fn drop(&mut self) {
+        self.pending.remove(&self.correlation_id);
+    }
+}
+
+/// Retry policy, counters and dead-letter queue shared by a bus and its handles
+struct Delivery {
+    config: AgentBusConfig,
+    delivered: AtomicU64,
+    handled: AtomicU64,
+    retried: AtomicU64,
+    dead_lettered: AtomicU64,
+    dead_letters_dropped: AtomicU64,
+    dead_letters: Mutex<VecDeque<DeadLetter>>,
+    on_dead_letter: Mutex<Option<DeadLetterCallback>>,
+}
+
+impl Delivery {
+    fn new(config: AgentBusConfig) -> Self {
+        Self {
+            config,
+            delivered: AtomicU64::new(0),
+            handled: AtomicU64::new(0),
+            retried: AtomicU64::new(0),
+            dead_lettered: AtomicU64::new(0),
+            dead_letters_dropped: AtomicU64::new(0),
+            dead_letters: Mutex::new(VecDeque::new()),
+            on_dead_letter: Mutex::new(None),
+        }
+    }
+
+    /// Run `attempt` until it succeeds or the message's retries run out,
+    /// dead-lettering it in the latter case
+    async fn with_retries<F, Fut>(&self, message: &AgentMessage, target_agent: &str, mut attempt: F) -> Result<()>
+    where
+        F: FnMut(AgentMessage) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let priority = message.priority();
+        let max_retries = self.config.max_retries_for(priority);
+        let first_attempt_at = Utc::now();
+        let mut attempts = 0;
+
+        loop {
+            attempts += 1;
+            let error = match attempt(message.clone()).await {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            };
+
+            if attempts > max_retries {
+                warn!(
+                    "Dead-lettering message for '{}' after {} attempts: {}",
+                    target_agent, attempts, error
+                );
+                self.dead_letter(DeadLetter {
+                    message: message.clone(),
+                    target_agent: target_agent.to_string(),
+                    error: error.to_string(),
+                    attempts,
+                    first_attempt_at,
+                    last_attempt_at: Utc::now(),
+                });
+                return Err(error);
+            }
+
+            self.retried.fetch_add(1, Ordering::Relaxed);
+            tokio::time::sleep(self.config.retry_delay(priority, attempts)).await;
+        }
+    }
+
+    fn dead_letter(&self, letter: DeadLetter) {
+        self.dead_lettered.fetch_add(1, Ordering::Relaxed);
+
+        let callback = self.on_dead_letter.lock().unwrap().clone();
+        if let Some(callback) = callback {
+            callback(&letter);
+        }
+
+        let mut dead_letters = self.dead_letters.lock().unwrap();
+        dead_letters.push_back(letter);
+        while dead_letters.len() > self.config.dead_letter_capacity {
+            dead_letters.pop_front();
+            self.dead_letters_dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn metrics(&self) -> AgentBusMetrics {
+        AgentBusMetrics {
+            delivered: self.delivered.load(Ordering::Relaxed),
+            handled: self.handled.load(Ordering::Relaxed),
+            retried: self.retried.load(Ordering::Relaxed),
+            dead_lettered: self.dead_lettered.load(Ordering::Relaxed),
+            dead_letters_dropped: self.dead_letters_dropped.load(Ordering::Relaxed),
         }
     }
 }
@@ -123,6 +306,10 @@ pub struct AgentBus {
 
     // Global broadcast for all messages (useful for logging/debugging)
     global_broadcast: broadcast::Sender<AgentMessage>,
+
+    delivery: Arc<Delivery>,
+
+    pending_requests: PendingRequests,
 }
 
 impl AgentBus {
@@ -131,6 +318,8 @@ impl AgentBus {
         let (global_tx, _) = broadcast::channel(config.broadcast_capacity);
 
         Ok(Self {
+            delivery: Arc::new(Delivery::new(config.clone())),
+            pending_requests: Arc::new(DashMap::new()),
             config,
             broadcasts: Arc::new(RwLock::new(DashMap::new())),
             unicast: Arc::new(DashMap::new()),
@@ -155,6 +344,8 @@ impl AgentBus {
             broadcasts: Arc::clone(&self.broadcasts),
             unicast: Arc::clone(&self.unicast),
             global_tx: self.global_broadcast.clone(),
+            delivery: Arc::clone(&self.delivery),
+            pending_requests: Arc::clone(&self.pending_requests),
         })
     }
 
@@ -189,6 +380,26 @@ impl AgentBus {
         }
         Ok(receivers)
     }
+
+    /// Take all dead letters queued so far, oldest first
+    pub fn dead_letters(&self) -> Vec<DeadLetter> {
+        self.delivery.dead_letters.lock().unwrap().drain(..).collect()
+    }
+
+    /// Call `callback` for every message as it is dead-lettered
+    pub fn on_dead_letter(&self, callback: impl Fn(&DeadLetter) + Send + Sync + 'static) {
+        *self.delivery.on_dead_letter.lock().unwrap() = Some(Arc::new(callback));
+    }
+
+    /// Delivery counters of the bus
+    pub fn metrics(&self) -> AgentBusMetrics {
+        self.delivery.metrics()
+    }
+
+    /// Requests still waiting for a response
+    pub fn in_flight_requests(&self) -> usize {
+        self.pending_requests.len()
+    }
 }
 
 /// Handle for an agent to send and receive messages
@@ -200,6 +411,8 @@ pub struct AgentBusHandle {
     broadcasts: Arc<RwLock<DashMap<String, broadcast::Sender<AgentMessage>>>>,
     unicast: Arc<DashMap<String, mpsc::Sender<AgentMessage>>>,
     global_tx: broadcast::Sender<AgentMessage>,
+    delivery: Arc<Delivery>,
+    pending_requests: PendingRequests,
 }
 
 impl AgentBusHandle {
@@ -209,13 +422,26 @@ impl AgentBusHandle {
     }
 
     /// Send a message to a specific agent
+    ///
+    /// An unknown target, a dropped receiver or a full channel is retried
+    /// with backoff; once the retries run out the message is dead-lettered
+    /// and the last error returned.
     pub async fn send_to(&self, target_agent: &str, msg: AgentMessage) -> Result<()> {
-        if let Some(tx) = self.unicast.get(target_agent) {
-            tx.send(msg).await?;
-            Ok(())
-        } else {
-            anyhow::bail!("Target agent '{}' not found", target_agent);
-        }
+        self.delivery
+            .with_retries(&msg, target_agent, |msg| async move {
+                let tx = match self.unicast.get(target_agent) {
+                    Some(tx) => tx.clone(),
+                    None => anyhow::bail!("Target agent '{}' not found", target_agent),
+                };
+                match tx.try_send(msg) {
+                    Ok(()) => Ok(()),
+                    Err(TrySendError::Full(_)) => anyhow::bail!("Channel of agent '{}' is full", target_agent),
+                    Err(TrySendError::Closed(_)) => anyhow::bail!("Agent '{}' dropped its receiver", target_agent),
+                }
+            })
+            .await?;
+        self.delivery.delivered.fetch_add(1, Ordering::Relaxed);
+        Ok(())
     }
 
     /// Broadcast a message to a topic
@@ -235,39 +461,98 @@ impl AgentBusHandle {
         Ok(())
     }
 
-    /// Send a request and wait for a response
+    /// Send a request and wait for its response
+    ///
+    /// `request` must be a `DataRequest`; its `request_id` is replaced by a
+    /// fresh correlation id and `requester_id` by this agent's id. The target
+    /// answers with `respond` or `respond_error`. Fails if delivery and the
+    /// response together take longer than `timeout_ms`, after which a late
+    /// response is rejected.
     pub async fn request(&self, target_agent: &str, request: AgentMessage, timeout_ms: u64) -> Result<AgentMessage> {
-        let _request_id = Uuid::new_v4().to_string();
-
-        // Create a one-shot channel for the response
-        let (_resp_tx, mut resp_rx) = mpsc::channel(1);
+        let AgentMessage::DataRequest { market_id, query, .. } = request else {
+            anyhow::bail!("Only DataRequest messages can be sent as requests");
+        };
 
-        // Store the response channel (in a real implementation, this would be in a registry)
-        // For now, we'll use a simpler approach with a dedicated response topic
+        let correlation_id = Uuid::new_v4().to_string();
+        let (resp_tx, resp_rx) = oneshot::channel();
+        self.pending_requests.insert(correlation_id.clone(), resp_tx);
+        let _pending = PendingRequest {
+            pending: &self.pending_requests,
+            correlation_id: correlation_id.clone(),
+        };
 
-        // Send the request
-        self.send_to(target_agent, request).await?;
+        let request = AgentMessage::DataRequest {
+            request_id: correlation_id,
+            requester_id: self.agent_id.clone(),
+            target_agent: target_agent.to_string(),
+            market_id,
+            query,
+        };
+        let exchange = async {
+            self.send_to(target_agent, request).await?;
+            resp_rx.await.map_err(|_| anyhow::anyhow!("Response channel closed"))
+        };
 
-        // Wait for response
-        let timeout = tokio::time::Duration::from_millis(timeout_ms);
-        tokio::select! {
-            response = resp_rx.recv() => {
-                match response {
-                    Some(msg) => Ok(msg),
-                    None => anyhow::bail!("Response channel closed"),
-                }
-            }
-            _ = tokio::time::sleep(timeout) => {
-                anyhow::bail!("Request timeout after {}ms", timeout_ms)
-            }
+        match tokio::time::timeout(Duration::from_millis(timeout_ms), exchange).await {
+            Ok(result) => result,
+            Err(_) => anyhow::bail!("Request to '{}' timed out after {}ms", target_agent, timeout_ms),
         }
     }
 
+    /// Answer the request with the given correlation id
+    pub fn respond(&self, correlation_id: &str, payload: serde_json::Value) -> Result<()> {
+        self.reply(correlation_id, Some(payload), None)
+    }
+
+    /// Answer the request with the given correlation id with an error
+    pub fn respond_error(&self, correlation_id: &str, error: impl Into<String>) -> Result<()> {
+        self.reply(correlation_id, None, Some(error.into()))
+    }
+
+    fn reply(&self, correlation_id: &str, data: Option<serde_json::Value>, error: Option<String>) -> Result<()> {
+        let Some((_, resp_tx)) = self.pending_requests.remove(correlation_id) else {
+            anyhow::bail!("No request in flight with correlation id {}", correlation_id);
+        };
+
+        let response = AgentMessage::DataResponse {
+            request_id: correlation_id.to_string(),
+            responder_id: self.agent_id.clone(),
+            data,
+            error,
+        };
+        resp_tx
+            .send(response)
+            .map_err(|_| anyhow::anyhow!("Requester of {} stopped waiting", correlation_id))
+    }
+
     /// Receive a message sent to this agent
     pub async fn recv(&mut self) -> Option<AgentMessage> {
         self.rx.recv().await
     }
 
+    /// Receive a message and process it with `handler`, retrying failures
+    /// with backoff and dead-lettering the message once the retries run out
+    ///
+    /// Returns None once the channel is closed, otherwise whether the
+    /// handler eventually succeeded.
+    pub async fn recv_with<F, Fut>(&mut self, handler: F) -> Option<bool>
+    where
+        F: FnMut(AgentMessage) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let msg = self.rx.recv().await?;
+        let handled = self.delivery.with_retries(&msg, &self.agent_id, handler).await.is_ok();
+        if handled {
+            self.delivery.handled.fetch_add(1, Ordering::Relaxed);
+        }
+        Some(handled)
+    }
+
+    /// Delivery counters of the bus this handle belongs to
+    pub fn metrics(&self) -> AgentBusMetrics {
+        self.delivery.metrics()
+    }
+
     /// Receive any message from the global broadcast
     pub async fn recv_global(&mut self) -> Option<AgentMessage> {
         match self.global_rx.recv().await {
@@ -318,11 +603,262 @@ mod tests {
     async fn test_message_priority() {
         let signal = AgentMessage::Signal {
             agent_id: "test".to_string(),
-            market_id: Uuid::new_v4(),
+            market_id: MarketId::random(),
             signal_data: serde_json::json!({}),
             confidence: 0.9,
             timestamp: Utc::now(),
         };
         assert_eq!(signal.priority(), MessagePriority::High);
     }
+
+    fn retry_config() -> AgentBusConfig {
+        AgentBusConfig {
+            max_retries: 2,
+            retry_base_delay_ms: 1,
+            ..AgentBusConfig::default()
+        }
+    }
+
+    fn status(agent_id: &str) -> AgentMessage {
+        AgentMessage::StatusUpdate {
+            agent_id: agent_id.to_string(),
+            status: serde_json::json!({}),
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_retry_policy_by_priority() {
+        let config = AgentBusConfig::default();
+        assert_eq!(config.max_retries_for(MessagePriority::Normal), 3);
+        assert_eq!(config.max_retries_for(MessagePriority::Critical), 6);
+        assert_eq!(config.retry_delay(MessagePriority::Normal, 1), Duration::from_millis(50));
+        assert_eq!(config.retry_delay(MessagePriority::Normal, 3), Duration::from_millis(200));
+        assert_eq!(config.retry_delay(MessagePriority::Critical, 3), Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_consumer_recovers_after_retries() {
+        let bus = AgentBus::new(retry_config()).await.unwrap();
+        let sender = bus.register_agent("sender").await.unwrap();
+        let mut consumer = bus.register_agent("consumer").await.unwrap();
+
+        sender.send_to("consumer", status("sender")).await.unwrap();
+
+        let mut calls = 0;
+        let handled = consumer
+            .recv_with(|_| {
+                calls += 1;
+                let fail = calls <= 2;
+                async move {
+                    if fail {
+                        anyhow::bail!("transient failure");
+                    }
+                    Ok(())
+                }
+            })
+            .await;
+
+        assert_eq!(handled, Some(true));
+        assert_eq!(calls, 3);
+        let metrics = consumer.metrics();
+        assert_eq!(metrics.delivered, 1);
+        assert_eq!(metrics.handled, 1);
+        assert_eq!(metrics.retried, 2);
+        assert_eq!(metrics.dead_lettered, 0);
+        assert!(bus.dead_letters().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failing_consumer_dead_letters() {
+        let config = AgentBusConfig {
+            dead_letter_capacity: 2,
+            ..retry_config()
+        };
+        let bus = AgentBus::new(config).await.unwrap();
+        let seen = Arc::new(AtomicU64::new(0));
+        let callback_seen = Arc::clone(&seen);
+        bus.on_dead_letter(move |_| {
+            callback_seen.fetch_add(1, Ordering::Relaxed);
+        });
+
+        let sender = bus.register_agent("sender").await.unwrap();
+        let mut consumer = bus.register_agent("consumer").await.unwrap();
+        for _ in 0..3 {
+            sender.send_to("consumer", status("sender")).await.unwrap();
+        }
+        for _ in 0..3 {
+            let handled = consumer
+                .recv_with(|_| async { anyhow::bail!("permanent failure") })
+                .await;
+            assert_eq!(handled, Some(false));
+        }
+
+        let metrics = sender.metrics();
+        assert_eq!(metrics.handled, 0);
+        assert_eq!(metrics.retried, 6);
+        assert_eq!(metrics.dead_lettered, 3);
+        assert_eq!(metrics.dead_letters_dropped, 1);
+        assert_eq!(seen.load(Ordering::Relaxed), 3);
+
+        // Only the newest two are kept, and draining empties the queue
+        let letters = bus.dead_letters();
+        assert_eq!(letters.len(), 2);
+        for letter in &letters {
+            assert_eq!(letter.target_agent, "consumer");
+            assert_eq!(letter.attempts, 3);
+            assert_eq!(letter.error, "permanent failure");
+            assert!(letter.last_attempt_at >= letter.first_attempt_at);
+            assert!(matches!(letter.message, AgentMessage::StatusUpdate { .. }));
+        }
+        assert!(bus.dead_letters().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_undeliverable_messages_dead_letter() {
+        let config = AgentBusConfig {
+            channel_capacity: 1,
+            ..retry_config()
+        };
+        let bus = AgentBus::new(config).await.unwrap();
+        let sender = bus.register_agent("sender").await.unwrap();
+        let _consumer = bus.register_agent("consumer").await.unwrap();
+
+        // The second message finds the channel full
+        sender.send_to("consumer", status("sender")).await.unwrap();
+        let err = sender.send_to("consumer", status("sender")).await.unwrap_err();
+        assert!(err.to_string().contains("full"));
+
+        // Critical messages get twice the retries
+        let control = AgentMessage::Control {
+            target: Some("missing".to_string()),
+            command: serde_json::json!({}),
+        };
+        assert!(sender.send_to("missing", control).await.is_err());
+
+        let letters = bus.dead_letters();
+        assert_eq!(letters.len(), 2);
+        assert_eq!(letters[0].attempts, 3);
+        assert_eq!(letters[1].target_agent, "missing");
+        assert_eq!(letters[1].attempts, 5);
+        assert_eq!(bus.metrics().delivered, 1);
+    }
+
+    fn data_request(query: serde_json::Value) -> AgentMessage {
+        AgentMessage::DataRequest {
+            request_id: String::new(),
+            requester_id: String::new(),
+            target_agent: String::new(),
+            market_id: MarketId::random(),
+            query,
+        }
+    }
+
+    /// Answers every request with its query under "echo"
+    fn spawn_echo_agent(mut handle: AgentBusHandle) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            while let Some(msg) = handle.recv().await {
+                if let AgentMessage::DataRequest { request_id, query, .. } = msg {
+                    handle.respond(&request_id, serde_json::json!({ "echo": query })).unwrap();
+                }
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_request_response_round_trip() {
+        let bus = AgentBus::new(AgentBusConfig::default()).await.unwrap();
+        let coordinator = bus.register_agent("coordinator").await.unwrap();
+        let sentiment = bus.register_agent("sentiment").await.unwrap();
+        spawn_echo_agent(sentiment);
+
+        let response = coordinator
+            .request("sentiment", data_request(serde_json::json!({"score": "cached"})), 1000)
+            .await
+            .unwrap();
+        match response {
+            AgentMessage::DataResponse { responder_id, data, error, .. } => {
+                assert_eq!(responder_id, "sentiment");
+                assert_eq!(data.unwrap()["echo"]["score"], "cached");
+                assert!(error.is_none());
+            }
+            other => panic!("unexpected response {:?}", other),
+        }
+        assert_eq!(bus.in_flight_requests(), 0);
+
+        // Only data requests carry a correlation id
+        assert!(coordinator.request("sentiment", status("coordinator"), 100).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unanswered_request_times_out() {
+        let bus = AgentBus::new(AgentBusConfig::default()).await.unwrap();
+        let coordinator = bus.register_agent("coordinator").await.unwrap();
+        let mut silent = bus.register_agent("silent").await.unwrap();
+
+        let err = coordinator
+            .request("silent", data_request(serde_json::json!({})), 20)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+        assert_eq!(bus.in_flight_requests(), 0);
+
+        // The request did arrive, but answering it now is too late
+        let Some(AgentMessage::DataRequest { request_id, requester_id, .. }) = silent.recv().await else {
+            panic!("expected the request");
+        };
+        assert_eq!(requester_id, "coordinator");
+        assert!(silent.respond(&request_id, serde_json::json!({})).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout_covers_delivery_retries() {
+        let config = AgentBusConfig {
+            channel_capacity: 1,
+            max_retries: 3,
+            retry_base_delay_ms: 200,
+            ..AgentBusConfig::default()
+        };
+        let bus = AgentBus::new(config).await.unwrap();
+        let coordinator = bus.register_agent("coordinator").await.unwrap();
+        let _busy = bus.register_agent("busy").await.unwrap();
+        coordinator.send_to("busy", status("coordinator")).await.unwrap();
+
+        // Retrying into the full channel would take 1.4s on its own
+        let started = std::time::Instant::now();
+        let err = coordinator
+            .request("busy", data_request(serde_json::json!({})), 50)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+        assert!(started.elapsed() < Duration::from_millis(500));
+        assert_eq!(bus.in_flight_requests(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_do_not_cross_talk() {
+        let bus = AgentBus::new(AgentBusConfig::default()).await.unwrap();
+        let coordinator = bus.register_agent("coordinator").await.unwrap();
+        spawn_echo_agent(bus.register_agent("sentiment").await.unwrap());
+
+        let responses = futures::future::join_all((0..100).map(|i| {
+            let coordinator = &coordinator;
+            async move {
+                let response = coordinator
+                    .request("sentiment", data_request(serde_json::json!(i)), 5000)
+                    .await
+                    .unwrap();
+                (i, response)
+            }
+        }))
+        .await;
+
+        for (i, response) in responses {
+            let AgentMessage::DataResponse { data, .. } = response else {
+                panic!("expected a response");
+            };
+            assert_eq!(data.unwrap()["echo"], i);
+        }
+        assert_eq!(bus.in_flight_requests(), 0);
+    }
 }
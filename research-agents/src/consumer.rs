@@ -0,0 +1,484 @@
+//! Event Consumer - Feeds Layer 0 events into the research agents
+//!
+//! Subscribes to the topics data-ingestion publishes to:
+//! - `news-articles`: buffered and handed to the SentimentAgent in batches
+//! - `market-events`: market creations and updates go into the
+//!   Orchestrator's registry; resolved or invalidated markets leave it
+//!
+//! Offsets are committed only once their messages have been handed off, so
+//! a crash replays messages instead of losing them (at-least-once). The
+//! broker sits behind `MessageTransport`; `KafkaConsumer` (feature `kafka`)
+//! is the production transport.
+
+use super::orchestrator::OrchestratorHandle;
+use super::sentiment::SentimentAgent;
+use anyhow::Result;
+use async_trait::async_trait;
+use common::{MarketEvent, NewsArticle};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// (topic, partition) -> offset of the last message handed off
+pub type Offsets = HashMap<(String, i32), i64>;
+
+/// A message read from a topic
+#[derive(Debug, Clone)]
+pub struct ConsumedMessage {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+    pub payload: Vec<u8>,
+}
+
+/// Source of messages, so the consumer can run without a broker
+#[async_trait]
+pub trait MessageTransport: Send {
+    /// Next message, None once the source is exhausted
+    async fn recv(&mut self) -> Result<Option<ConsumedMessage>>;
+
+    /// Mark everything up to and including these offsets as consumed
+    async fn commit(&mut self, offsets: &Offsets) -> Result<()>;
+}
+
+/// Configuration for the event consumer
+#[derive(Debug, Clone)]
+pub struct EventConsumerConfig {
+    pub brokers: String,
+    /// Consumer group; instances in one group split the partitions
+    pub group_id: String,
+    /// Where a group without committed offsets starts: "earliest" or "latest"
+    pub auto_offset_reset: String,
+    pub news_topic: String,
+    pub market_topic: String,
+    /// Articles per `add_articles` call
+    pub article_batch_size: usize,
+    /// Buffered articles that force a flush before reading on
+    pub max_buffered_articles: usize,
+    /// How often buffered articles are flushed and offsets committed
+    pub flush_interval: Duration,
+}
+
+impl Default for EventConsumerConfig {
+    fn default() -> Self {
+        Self {
+            brokers: "localhost:9092".to_string(),
+            group_id: "research-agents".to_string(),
+            auto_offset_reset: "earliest".to_string(),
+            news_topic: "news-articles".to_string(),
+            market_topic: "market-events".to_string(),
+            article_batch_size: 200,
+            max_buffered_articles: 2000,
+            flush_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Counters for a consumer run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConsumerStats {
+    pub messages: u64,
+    /// Articles handed to the sentiment agent
+    pub articles: u64,
+    /// Market creations and updates sent to the orchestrator
+    pub markets_added: u64,
+    /// Resolved or invalidated markets removed from the orchestrator
+    pub markets_removed: u64,
+    /// Payloads that did not deserialize
+    pub malformed: u64,
+    /// Market events the research agents don't use, and unknown topics
+    pub ignored: u64,
+    pub commits: u64,
+}
+
+/// A `news-articles` payload: data-ingestion publishes batches, but single
+/// articles are accepted too
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ArticlePayload {
+    Batch(Vec<NewsArticle>),
+    Single(NewsArticle),
+}
+
+/// Consumes news and market events and forwards them to the agents
+pub struct EventConsumer<T: MessageTransport> {
+    config: EventConsumerConfig,
+    transport: T,
+    sentiment: Arc<SentimentAgent>,
+    orchestrator: OrchestratorHandle,
+
+    // Articles read but not yet handed off, and the offsets they came from
+    buffer: Vec<NewsArticle>,
+    buffered_offsets: Offsets,
+
+    // Offsets handed off but not yet committed
+    ready_offsets: Offsets,
+
+    stats: ConsumerStats,
+}
+
+impl<T: MessageTransport> EventConsumer<T> {
+    pub fn new(
+        config: EventConsumerConfig,
+        transport: T,
+        sentiment: Arc<SentimentAgent>,
+        orchestrator: OrchestratorHandle,
+    ) -> Self {
+        Self {
+            config,
+            transport,
+            sentiment,
+            orchestrator,
+            buffer: Vec::new(),
+            buffered_offsets: Offsets::new(),
+            ready_offsets: Offsets::new(),
+            stats: ConsumerStats::default(),
+        }
+    }
+
+    pub fn stats(&self) -> &ConsumerStats {
+        &self.stats
+    }
+
+    /// Consume until `shutdown` completes or the transport is exhausted, then
+    /// hand off what is buffered and commit it
+    ///
+    /// Returns early with an error if a handoff fails; nothing from the
+    /// failed message on is committed.
+    pub async fn run(&mut self, shutdown: impl Future<Output = ()>) -> Result<ConsumerStats> {
+        tokio::pin!(shutdown);
+        let mut flush = tokio::time::interval(self.config.flush_interval);
+        flush.tick().await;
+
+        info!(
+            "Consuming {} and {} as group {}",
+            self.config.news_topic, self.config.market_topic, self.config.group_id
+        );
+
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => {
+                    info!("Shutting down consumer, draining {} buffered articles", self.buffer.len());
+                    break;
+                }
+
+                _ = flush.tick() => self.flush().await,
+
+                message = self.transport.recv() => {
+                    let Some(message) = message? else {
+                        break;
+                    };
+                    self.handle(message).await?;
+                    if self.buffer.len() >= self.config.max_buffered_articles {
+                        self.flush().await;
+                    }
+                }
+            }
+        }
+
+        self.flush().await;
+        Ok(self.stats.clone())
+    }
+
+    /// Forward one message; articles wait in the buffer for the next flush
+    async fn handle(&mut self, message: ConsumedMessage) -> Result<()> {
+        self.stats.messages += 1;
+        let key = (message.topic.clone(), message.partition);
+
+        if message.topic == self.config.news_topic {
+            match serde_json::from_slice(&message.payload) {
+                Ok(ArticlePayload::Batch(articles)) => self.buffer.extend(articles),
+                Ok(ArticlePayload::Single(article)) => self.buffer.push(article),
+                Err(e) => {
+                    warn!("Skipping malformed article at {}/{}@{}: {}", key.0, key.1, message.offset, e);
+                    self.stats.malformed += 1;
+                }
+            }
+            // Committed with the buffer, after the articles before it are handed off
+            self.buffered_offsets.insert(key, message.offset);
+            return Ok(());
+        }
+
+        if message.topic == self.config.market_topic {
+            match serde_json::from_slice::<MarketEvent>(&message.payload) {
+                Ok(MarketEvent::MarketCreated(market)) => {
+                    self.orchestrator.add_markets(vec![market]).await?;
+                    self.stats.markets_added += 1;
+                }
+                Ok(MarketEvent::MarketResolved { market_id, .. })
+                | Ok(MarketEvent::MarketInvalidated { market_id, .. }) => {
+                    self.orchestrator.remove_markets(vec![market_id]).await?;
+                    self.stats.markets_removed += 1;
+                }
+                Ok(_) => self.stats.ignored += 1,
+                Err(e) => {
+                    warn!("Skipping malformed market event at {}/{}@{}: {}", key.0, key.1, message.offset, e);
+                    self.stats.malformed += 1;
+                }
+            }
+        } else {
+            debug!("Ignoring message from unexpected topic {}", message.topic);
+            self.stats.ignored += 1;
+        }
+        self.ready_offsets.insert(key, message.offset);
+        Ok(())
+    }
+
+    /// Hand buffered articles to the sentiment agent, then commit every
+    /// offset handed off. A failed commit is retried on the next flush.
+    async fn flush(&mut self) {
+        let articles = std::mem::take(&mut self.buffer);
+        for batch in articles.chunks(self.config.article_batch_size.max(1)) {
+            self.sentiment.add_articles(batch.to_vec()).await;
+            self.stats.articles += batch.len() as u64;
+        }
+        self.ready_offsets.extend(self.buffered_offsets.drain());
+
+        if self.ready_offsets.is_empty() {
+            return;
+        }
+        match self.transport.commit(&self.ready_offsets).await {
+            Ok(()) => {
+                debug!("Committed offsets for {} partitions", self.ready_offsets.len());
+                self.ready_offsets.clear();
+                self.stats.commits += 1;
+            }
+            Err(e) => warn!("Failed to commit offsets, retrying on the next flush: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "kafka")]
+pub use kafka::KafkaConsumer;
+
+#[cfg(feature = "kafka")]
+mod kafka {
+    use super::{ConsumedMessage, EventConsumerConfig, MessageTransport, Offsets};
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use rdkafka::config::ClientConfig;
+    use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+    use rdkafka::{Message, Offset, TopicPartitionList};
+
+    /// Kafka transport; offsets are only committed by the consumer
+    pub struct KafkaConsumer {
+        consumer: StreamConsumer,
+    }
+
+    impl KafkaConsumer {
+        /// Join the consumer group and subscribe to the news and market topics
+        pub fn new(config: &EventConsumerConfig) -> Result<Self> {
+            let consumer: StreamConsumer = ClientConfig::new()
+                .set("bootstrap.servers", &config.brokers)
+                .set("group.id", &config.group_id)
+                .set("auto.offset.reset", &config.auto_offset_reset)
+                .set("enable.auto.commit", "false")
+                .create()?;
+            consumer.subscribe(&[config.news_topic.as_str(), config.market_topic.as_str()])?;
+
+            Ok(Self { consumer })
+        }
+    }
+
+    #[async_trait]
+    impl MessageTransport for KafkaConsumer {
+        async fn recv(&mut self) -> Result<Option<ConsumedMessage>> {
+            let message = self.consumer.recv().await?;
+            Ok(Some(ConsumedMessage {
+                topic: message.topic().to_string(),
+                partition: message.partition(),
+                offset: message.offset(),
+                payload: message.payload().unwrap_or_default().to_vec(),
+            }))
+        }
+
+        async fn commit(&mut self, offsets: &Offsets) -> Result<()> {
+            let mut list = TopicPartitionList::new();
+            for ((topic, partition), offset) in offsets {
+                // Kafka commits the next offset to read
+                list.add_partition_offset(topic, *partition, Offset::Offset(offset + 1))?;
+            }
+            self.consumer.commit(&list, CommitMode::Sync)?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::{AgentBus, AgentBusConfig};
+    use crate::orchestrator::{Orchestrator, OrchestratorConfig};
+    use crate::sentiment::SentimentAgentConfig;
+    use common::MarketId;
+    use std::collections::{HashSet, VecDeque};
+    use std::sync::Mutex;
+
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/consumer_replay.jsonl");
+
+    /// Replays recorded messages and records commits
+    struct ReplayTransport {
+        messages: VecDeque<ConsumedMessage>,
+        commits: Arc<Mutex<Vec<Offsets>>>,
+        /// Wait for more messages once replayed, like a live broker
+        hold_open: bool,
+    }
+
+    impl ReplayTransport {
+        fn new(messages: Vec<ConsumedMessage>, hold_open: bool) -> (Self, Arc<Mutex<Vec<Offsets>>>) {
+            let commits = Arc::new(Mutex::new(Vec::new()));
+            let transport = Self {
+                messages: messages.into(),
+                commits: commits.clone(),
+                hold_open,
+            };
+            (transport, commits)
+        }
+    }
+
+    #[async_trait]
+    impl MessageTransport for ReplayTransport {
+        async fn recv(&mut self) -> Result<Option<ConsumedMessage>> {
+            match self.messages.pop_front() {
+                Some(message) => Ok(Some(message)),
+                None if self.hold_open => std::future::pending().await,
+                None => Ok(None),
+            }
+        }
+
+        async fn commit(&mut self, offsets: &Offsets) -> Result<()> {
+            self.commits.lock().unwrap().push(offsets.clone());
+            Ok(())
+        }
+    }
+
+    /// Fixture lines are {topic, partition, offset, payload}; string payloads
+    /// are sent as-is, so they can be malformed JSON
+    fn fixture() -> Vec<ConsumedMessage> {
+        std::fs::read_to_string(FIXTURE)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let value: serde_json::Value = serde_json::from_str(line).unwrap();
+                let payload = match &value["payload"] {
+                    serde_json::Value::String(raw) => raw.clone().into_bytes(),
+                    payload => serde_json::to_vec(payload).unwrap(),
+                };
+                ConsumedMessage {
+                    topic: value["topic"].as_str().unwrap().to_string(),
+                    partition: value["partition"].as_i64().unwrap() as i32,
+                    offset: value["offset"].as_i64().unwrap(),
+                    payload,
+                }
+            })
+            .collect()
+    }
+
+    fn market_id(i: u32) -> MarketId {
+        format!("00000000-0000-4000-8000-{:012}", i).parse().unwrap()
+    }
+
+    async fn orchestrator() -> Orchestrator {
+        let bus = Arc::new(AgentBus::new(AgentBusConfig::default()).await.unwrap());
+        Orchestrator::new(OrchestratorConfig::default(), bus).await.unwrap()
+    }
+
+    /// The fixture articles are from the past, so keep them however old
+    fn replay_sentiment_config() -> SentimentAgentConfig {
+        SentimentAgentConfig {
+            max_article_age_secs: 0,
+            ..SentimentAgentConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_feeds_agents_and_commits_every_partition() {
+        let messages = fixture();
+        assert_eq!(messages.len(), 100);
+
+        let mut orchestrator = orchestrator().await;
+        let status = orchestrator.control_handle();
+        let sentiment = Arc::new(SentimentAgent::new(replay_sentiment_config()));
+        let (transport, commits) = ReplayTransport::new(messages, false);
+        let config = EventConsumerConfig {
+            article_batch_size: 10,
+            max_buffered_articles: 25,
+            ..EventConsumerConfig::default()
+        };
+        let mut consumer =
+            EventConsumer::new(config, transport, sentiment.clone(), orchestrator.control_handle());
+
+        let stats = tokio::select! {
+            _ = orchestrator.run() => unreachable!("orchestrator stopped"),
+            stats = async {
+                let stats = consumer.run(std::future::pending()).await.unwrap();
+                // Answered only after every command sent before it
+                status.get_status().await.unwrap();
+                stats
+            } => stats,
+        };
+
+        assert_eq!(stats.messages, 100);
+        assert_eq!(stats.articles, 130);
+        assert_eq!(stats.markets_added, 35);
+        assert_eq!(stats.markets_removed, 5);
+        assert_eq!(stats.malformed, 4);
+        assert_eq!(stats.ignored, 10);
+        assert_eq!(sentiment.article_count().await, 130);
+
+        // 30 created, 5 of them updated, 5 resolved or invalidated
+        let expected: HashSet<MarketId> = (0..30).filter(|i| !(5..10).contains(i)).map(market_id).collect();
+        let markets: HashSet<MarketId> = orchestrator.market_ids().into_iter().collect();
+        assert_eq!(markets, expected);
+
+        let mut committed = Offsets::new();
+        for offsets in commits.lock().unwrap().iter() {
+            committed.extend(offsets.clone());
+        }
+        assert_eq!(committed[&("market-events".to_string(), 0)], 51);
+        assert_eq!(committed[&("news-articles".to_string(), 0)], 23);
+        assert_eq!(committed[&("news-articles".to_string(), 1)], 23);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_drains_buffered_articles() {
+        let news: Vec<ConsumedMessage> = fixture()
+            .into_iter()
+            .filter(|message| message.topic == "news-articles" && message.partition == 0)
+            .take(5)
+            .collect();
+        let last_offset = news.last().unwrap().offset;
+
+        let orchestrator = orchestrator().await;
+        let sentiment = Arc::new(SentimentAgent::new(replay_sentiment_config()));
+        let (transport, commits) = ReplayTransport::new(news, true);
+        let config = EventConsumerConfig {
+            flush_interval: Duration::from_secs(3600),
+            ..EventConsumerConfig::default()
+        };
+        let mut consumer = EventConsumer::new(config, transport, sentiment.clone(), orchestrator.control_handle());
+
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+        let run = consumer.run(async {
+            let _ = stop_rx.await;
+        });
+        let stop = async {
+            // Past the replayed messages, nothing flushed yet
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            assert_eq!(sentiment.article_count().await, 0);
+            assert!(commits.lock().unwrap().is_empty());
+            stop_tx.send(()).unwrap();
+        };
+        let (stats, ()) = tokio::join!(run, stop);
+
+        let stats = stats.unwrap();
+        assert_eq!(sentiment.article_count().await as u64, stats.articles);
+        assert!(stats.articles > 0);
+        let commits = commits.lock().unwrap();
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0][&("news-articles".to_string(), 0)], last_offset);
+    }
+}
@@ -0,0 +1,265 @@
+//! Text embeddings for semantic matching of news to markets
+//!
+//! `HttpEmbedder` calls an OpenAI-compatible `/embeddings` endpoint;
+//! `HashEmbedder` is a deterministic stand-in that needs no model, used in
+//! tests and as a fallback when no endpoint is configured.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Turns texts into fixed-size vectors, similar texts pointing the same way
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    /// Length of every vector returned
+    fn dimensions(&self) -> usize;
+
+    /// Most texts to send in one `embed` call
+    fn max_batch_size(&self) -> usize {
+        64
+    }
+
+    /// One vector per text, in order
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+}
+
+/// Cosine similarity, 0 if either vector is all zeros
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let denominator = norm(a) * norm(b);
+    if denominator == 0.0 {
+        0.0
+    } else {
+        dot / denominator
+    }
+}
+
+/// Words too common to say what a text is about
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "has", "in", "is", "it", "of", "on", "or",
+    "that", "the", "to", "was", "will", "with",
+];
+
+/// Feature-hashed character trigrams of each word
+///
+/// Deterministic across runs and platforms. Trigrams make "rate" close to
+/// "rates" and "fed" to "federal", which is about all the semantics it has.
+#[derive(Debug, Clone)]
+pub struct HashEmbedder {
+    dimensions: usize,
+}
+
+impl HashEmbedder {
+    pub fn new(dimensions: usize) -> Self {
+        Self { dimensions: dimensions.max(1) }
+    }
+
+    fn embed_text(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0f32; self.dimensions];
+        let text = text.to_lowercase();
+        let words = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| word.len() > 1 && !STOPWORDS.contains(word));
+        for word in words {
+            let padded: Vec<char> = format!("#{}#", word).chars().collect();
+            for trigram in padded.windows(3) {
+                let hash = fnv1a(trigram.iter().collect::<String>().as_bytes());
+                let index = (hash % self.dimensions as u64) as usize;
+                vector[index] += if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            }
+        }
+
+        let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|x| *x /= norm);
+        }
+        vector
+    }
+}
+
+impl Default for HashEmbedder {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+#[async_trait]
+impl Embedder for HashEmbedder {
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|text| self.embed_text(text)).collect())
+    }
+}
+
+/// 64-bit FNV-1a, stable unlike `DefaultHasher`
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/// Endpoint and model for `HttpEmbedder`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpEmbedderConfig {
+    /// Full URL of the embeddings endpoint
+    pub endpoint: String,
+    pub model: String,
+    /// Sent as a bearer token when set
+    pub api_key: Option<String>,
+    /// Must match what the model returns
+    pub dimensions: usize,
+    /// Texts per request
+    pub batch_size: usize,
+    pub timeout_secs: u64,
+}
+
+impl Default for HttpEmbedderConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: "https://api.openai.com/v1/embeddings".to_string(),
+            model: "text-embedding-3-small".to_string(),
+            api_key: std::env::var("OPENAI_API_KEY").ok(),
+            dimensions: 1536,
+            batch_size: 64,
+            timeout_secs: 30,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// Embeddings from an OpenAI-compatible API
+pub struct HttpEmbedder {
+    config: HttpEmbedderConfig,
+    client: reqwest::Client,
+}
+
+impl HttpEmbedder {
+    pub fn new(config: HttpEmbedderConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()?;
+        Ok(Self { config, client })
+    }
+
+    /// Vectors in input order, checked against the texts sent
+    fn parse_response(&self, response: EmbeddingResponse, expected: usize) -> Result<Vec<Vec<f32>>> {
+        let mut vectors = vec![None; expected];
+        for data in response.data {
+            if data.embedding.len() != self.config.dimensions {
+                return Err(anyhow!(
+                    "Embedding has {} dimensions, expected {}",
+                    data.embedding.len(),
+                    self.config.dimensions
+                ));
+            }
+            let slot = vectors
+                .get_mut(data.index)
+                .ok_or_else(|| anyhow!("Embedding index {} out of range", data.index))?;
+            *slot = Some(data.embedding);
+        }
+        vectors
+            .into_iter()
+            .enumerate()
+            .map(|(i, vector)| vector.ok_or_else(|| anyhow!("No embedding returned for input {}", i)))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Embedder for HttpEmbedder {
+    fn dimensions(&self) -> usize {
+        self.config.dimensions
+    }
+
+    fn max_batch_size(&self) -> usize {
+        self.config.batch_size.max(1)
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.max_batch_size()) {
+            let mut request = self.client.post(&self.config.endpoint).json(&EmbeddingRequest {
+                model: &self.config.model,
+                input: batch,
+            });
+            if let Some(key) = &self.config.api_key {
+                request = request.bearer_auth(key);
+            }
+            let response: EmbeddingResponse = request
+                .send()
+                .await
+                .context("Embedding request failed")?
+                .error_for_status()?
+                .json()
+                .await
+                .context("Malformed embedding response")?;
+            vectors.extend(self.parse_response(response, batch.len())?);
+        }
+        Ok(vectors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_hash_embedder_is_deterministic_and_topical() {
+        let embedder = HashEmbedder::default();
+        let texts = [
+            "Will the Federal Reserve cut interest rates in March?".to_string(),
+            "Fed officials signal a rate cut as inflation cools".to_string(),
+            "Wildfire forces evacuations across California".to_string(),
+        ];
+        let vectors = embedder.embed(&texts).await.unwrap();
+        assert_eq!(vectors, embedder.embed(&texts).await.unwrap());
+        assert!(vectors.iter().all(|v| v.len() == 256));
+
+        let related = cosine_similarity(&vectors[0], &vectors[1]);
+        let unrelated = cosine_similarity(&vectors[0], &vectors[2]);
+        assert!(related > unrelated + 0.1, "related {} vs unrelated {}", related, unrelated);
+        assert!((cosine_similarity(&vectors[0], &vectors[0]) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_http_response_reordered_and_checked() {
+        let embedder = HttpEmbedder::new(HttpEmbedderConfig {
+            dimensions: 2,
+            ..HttpEmbedderConfig::default()
+        })
+        .unwrap();
+        let response: EmbeddingResponse = serde_json::from_value(serde_json::json!({
+            "object": "list",
+            "data": [
+                { "object": "embedding", "index": 1, "embedding": [0.0, 1.0] },
+                { "object": "embedding", "index": 0, "embedding": [1.0, 0.0] }
+            ],
+            "model": "text-embedding-3-small"
+        }))
+        .unwrap();
+        assert_eq!(embedder.parse_response(response, 2).unwrap(), [vec![1.0, 0.0], vec![0.0, 1.0]]);
+
+        let short: EmbeddingResponse =
+            serde_json::from_value(serde_json::json!({ "data": [{ "index": 0, "embedding": [1.0, 0.0] }] })).unwrap();
+        assert!(embedder.parse_response(short, 2).is_err());
+    }
+}
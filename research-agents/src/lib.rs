@@ -5,18 +5,46 @@
 //! - Base Agent trait for implementing specialist agents
 //! - Orchestrator for coordinating multiple agents across thousands of markets
 //! - Communication bus for agent-to-agent messaging
-//! - Specialist agent implementations (Sentiment, etc.)
+//! - Event consumer feeding Layer 0's Kafka topics into the agents
+//! - Embeddings and a vector store for semantic news-to-market matching
+//! - Pluggable sentiment scoring, keyword-based or transformer-based
+//! - Specialist agent implementations (Sentiment, News Volume, Technical, Whale, etc.)
 
 pub mod agent;
+pub mod batching;
 pub mod orchestrator;
 pub mod bus;
+pub mod consumer;
+pub mod embedding;
+pub mod matcher;
+pub mod news_volume;
 pub mod sentiment;
+pub mod sentiment_backend;
+pub mod sentiment_history;
+pub mod technical;
+pub mod vector_store;
+pub mod whale;
 
 // Re-export commonly used types
-pub use agent::{Agent, AgentConfig, AgentInput, AgentOutput, AgentStatus};
-pub use orchestrator::{Orchestrator, OrchestratorConfig};
-pub use bus::{AgentBus, AgentBusConfig, AgentBusHandle, AgentMessage, MessagePriority};
-pub use sentiment::{SentimentAgent, SentimentAgentConfig, SentimentSignal, SentimentScore};
+pub use agent::{Agent, AgentConfig, AgentInput, AgentOutput, AgentStatus, AgentStatusCell};
+pub use batching::{AdaptiveBatchConfig, BatchOutcome, BatchScheduler};
+pub use orchestrator::{AgentRunStats, Orchestrator, OrchestratorConfig};
+pub use bus::{AgentBus, AgentBusConfig, AgentBusHandle, AgentBusMetrics, AgentMessage, DeadLetter, MessagePriority};
+pub use consumer::{ConsumedMessage, ConsumerStats, EventConsumer, EventConsumerConfig, MessageTransport, Offsets};
+#[cfg(feature = "kafka")]
+pub use consumer::KafkaConsumer;
+pub use embedding::{Embedder, HashEmbedder, HttpEmbedder, HttpEmbedderConfig};
+pub use news_volume::{NewsVolumeAgent, NewsVolumeAgentConfig, NewsVolumeSignal};
+pub use sentiment::{MatchingMode, SentimentAgent, SentimentAgentConfig, SentimentDelta, SentimentScore, SentimentSignal};
+pub use sentiment_backend::{BackendScore, KeywordBackend, PolarityClassifier, SentimentBackend, TransformerBackend};
+#[cfg(feature = "full-nlp")]
+pub use sentiment_backend::RustBertClassifier;
+pub use sentiment_history::SentimentHistory;
+pub use technical::{Bias, CrossoverState, TechnicalAgent, TechnicalAgentConfig, TechnicalSignal, PRICE_TICKS_KEY};
+pub use vector_store::{Collection, EmbeddingStats, MemoryBackend, SemanticMatch, VectorBackend, VectorStore};
+#[cfg(feature = "pgvector")]
+pub use vector_store::PgVectorBackend;
+pub use whale::{WhaleAgent, WhaleAgentConfig, WhaleSignal, TRADES_KEY};
 
 // Re-export common types for convenience
-pub use common::{Market, MarketEvent, PriceTick};
+pub use common::{Market, MarketEvent, PriceTick, Trade};
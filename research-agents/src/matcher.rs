@@ -0,0 +1,113 @@
+//! Matching of news articles to markets
+//!
+//! Shared by the agents that read news, so they agree on which articles
+//! concern which market.
+
+use crate::sentiment::NewsArticle;
+use common::Market;
+use std::collections::HashMap;
+
+/// Keywords for the common Polymarket categories
+pub fn default_category_keywords() -> HashMap<String, Vec<String>> {
+    let mut category_keywords: HashMap<String, Vec<String>> = HashMap::new();
+
+    // Polymarket common categories
+    category_keywords.insert("Politics".to_string(), vec![
+        "election".to_string(), "vote".to_string(), "president".to_string(), "congress".to_string(), "senate".to_string(), "campaign".to_string(),
+        "republican".to_string(), "democrat".to_string(), "policy".to_string(), "government".to_string(), "legislation".to_string(),
+        "trump".to_string(), "biden".to_string(), "white house".to_string(), "capitol".to_string(),
+    ]);
+
+    category_keywords.insert("Economics".to_string(), vec![
+        "inflation".to_string(), "gdp".to_string(), "economy".to_string(), "recession".to_string(), "unemployment".to_string(),
+        "interest rate".to_string(), "federal reserve".to_string(), "fed".to_string(), "stock market".to_string(),
+        "crypto".to_string(), "bitcoin".to_string(), "ethereum".to_string(), "finance".to_string(),
+    ]);
+
+    category_keywords.insert("Geopolitics".to_string(), vec![
+        "war".to_string(), "conflict".to_string(), "invasion".to_string(), "military".to_string(), "sanctions".to_string(),
+        "diplomacy".to_string(), "treaty".to_string(), "nuclear".to_string(), "russia".to_string(), "ukraine".to_string(),
+        "china".to_string(), "israel".to_string(), "palestine".to_string(), "iran".to_string(),
+    ]);
+
+    category_keywords.insert("Technology".to_string(), vec![
+        "ai".to_string(), "artificial intelligence".to_string(), "tech".to_string(), "software".to_string(), "startup".to_string(),
+        "innovation".to_string(), "cybersecurity".to_string(), "data".to_string(), "cloud".to_string(), "platform".to_string(),
+        "regulation".to_string(), "antitrust".to_string(), "monopoly".to_string(),
+    ]);
+
+    category_keywords.insert("Climate".to_string(), vec![
+        "climate".to_string(), "warming".to_string(), "carbon".to_string(), "emissions".to_string(), "renewable".to_string(),
+        "energy".to_string(), "solar".to_string(), "wind".to_string(), "weather".to_string(), "disaster".to_string(),
+        "cop".to_string(), "paris".to_string(), "agreement".to_string(), "green".to_string(),
+    ]);
+
+    category_keywords
+}
+
+/// Trim and lowercase keywords, as articles are matched in lowercase
+pub fn normalize_keywords(category_keywords: &mut HashMap<String, Vec<String>>) {
+    for keywords in category_keywords.values_mut() {
+        for keyword in keywords.iter_mut() {
+            *keyword = keyword.trim().to_lowercase();
+        }
+    }
+}
+
+/// Articles relevant to a market, by its category's keywords
+pub fn match_articles(
+    category_keywords: &HashMap<String, Vec<String>>,
+    market: &Market,
+    articles: &[NewsArticle],
+) -> Vec<NewsArticle> {
+    let question = market.question.to_lowercase();
+    let description = market.description.to_lowercase();
+
+    let keywords = category_keywords
+        .get(&market.category)
+        .cloned()
+        .unwrap_or_default();
+
+    articles.iter()
+        .filter(|article| {
+            let article_text = format!("{} {}", article.title, article.themes).to_lowercase();
+
+            // Check category match
+            let category_match = keywords.iter().any(|kw| article_text.contains(kw));
+
+            // Check keyword match in question/description
+            let keyword_match = keywords.iter()
+                .any(|kw| question.contains(kw) || description.contains(kw));
+
+            // Check for theme overlap
+            let theme_overlap = article.themes.to_lowercase()
+                .split(';')
+                .any(|t| keywords.contains(&t.trim().to_lowercase()));
+
+            category_match || keyword_match || theme_overlap
+        })
+        .cloned()
+        .collect()
+}
+
+/// Most frequent themes across articles
+pub fn extract_themes(articles: &[NewsArticle], limit: usize) -> Vec<String> {
+    let mut theme_counts: HashMap<String, u32> = HashMap::new();
+
+    for article in articles {
+        for theme in article.themes.split(';') {
+            let theme = theme.trim().to_lowercase();
+            if !theme.is_empty() {
+                *theme_counts.entry(theme).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut themes: Vec<(String, u32)> = theme_counts.into_iter().collect();
+    themes.sort_by(|a, b| b.1.cmp(&a.1));
+
+    themes.into_iter()
+        .take(limit)
+        .map(|(t, _)| t)
+        .collect()
+}
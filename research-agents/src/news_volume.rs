@@ -0,0 +1,556 @@
+//! News Volume Agent - Detects abnormal spikes in news coverage of a market
+//!
+//! Sentiment says which way the news leans; volume says how urgent it is.
+//! This agent counts the articles matched to each market per hour, keeps an
+//! exponentially weighted baseline of those hourly counts, and signals when
+//! the current hour stands out from the baseline by more than a z-score
+//! threshold.
+
+use super::agent::{
+    handle_common_control, Agent, AgentConfig, AgentInput, AgentOutput, AgentStatus, AgentStatusCell,
+    ConfigCell, ControlMessage, ControlResponse, PatchableConfig,
+};
+use super::matcher::{self, default_category_keywords};
+use super::sentiment::NewsArticle;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use common::{Market, MarketId};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tokio::sync::RwLock;
+use tracing::{debug, info};
+
+/// News volume spike output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewsVolumeSignal {
+    pub market_id: MarketId,
+    pub market_category: String,
+    /// Start of the hour the spike was seen in
+    pub hour: DateTime<Utc>,
+    /// Articles matched to the market so far this hour
+    pub article_count: u32,
+    /// Current hour's count as a multiple of the baseline mean
+    pub magnitude: f64,
+    pub z_score: f64,
+    pub baseline_mean: f64,
+    pub baseline_std_dev: f64,
+    pub article_ids: Vec<String>,
+    pub top_themes: Vec<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// News volume agent configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewsVolumeAgentConfig {
+    pub base: AgentConfig,
+    pub category_keywords: HashMap<String, Vec<String>>,
+    /// Standard deviations over the baseline that count as a spike
+    pub z_threshold: f64,
+    /// Weight of the latest hour in the baseline mean and variance
+    pub baseline_alpha: f64,
+    /// Completed hours a market needs before spikes are reported
+    pub warmup_hours: u32,
+    /// Fewest articles in an hour that can be a spike
+    pub min_articles: u32,
+    /// Floor on the baseline standard deviation, so a flat baseline doesn't
+    /// turn a single extra article into a spike
+    pub min_std_dev: f64,
+    /// Markets not checked for this long are forgotten
+    #[serde(default = "default_history_ttl_secs")]
+    pub history_ttl_secs: u64,
+}
+
+fn default_history_ttl_secs() -> u64 {
+    48 * 3600
+}
+
+impl Default for NewsVolumeAgentConfig {
+    fn default() -> Self {
+        Self {
+            base: AgentConfig {
+                agent_id: "news-volume-agent".to_string(),
+                name: "News Volume Agent".to_string(),
+                enabled: true,
+                max_markets_per_batch: 100,
+                processing_interval_secs: 300, // 5 minutes
+                max_concurrent_markets: 1,
+            },
+            category_keywords: default_category_keywords(),
+            z_threshold: 3.0,
+            baseline_alpha: 0.1,
+            warmup_hours: 24,
+            min_articles: 3,
+            min_std_dev: 1.0,
+            history_ttl_secs: default_history_ttl_secs(),
+        }
+    }
+}
+
+impl PatchableConfig for NewsVolumeAgentConfig {
+    /// Check the tunables, returning every problem found
+    fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.z_threshold <= 0.0 {
+            errors.push(format!("z_threshold must be positive, got {}", self.z_threshold));
+        }
+        if !(self.baseline_alpha > 0.0 && self.baseline_alpha <= 1.0) {
+            errors.push(format!("baseline_alpha must be in (0, 1], got {}", self.baseline_alpha));
+        }
+        if self.min_std_dev <= 0.0 {
+            errors.push(format!("min_std_dev must be positive, got {}", self.min_std_dev));
+        }
+        if self.history_ttl_secs == 0 {
+            errors.push("history_ttl_secs must be positive".to_string());
+        }
+        for (category, keywords) in &self.category_keywords {
+            if keywords.iter().any(|kw| kw.trim().is_empty()) {
+                errors.push(format!("category {} has an empty keyword", category));
+            }
+        }
+
+        errors
+    }
+
+    fn normalize(&mut self) {
+        matcher::normalize_keywords(&mut self.category_keywords);
+    }
+}
+
+/// Exponentially weighted mean and variance of hourly article counts
+#[derive(Debug, Clone, Default)]
+struct Baseline {
+    mean: f64,
+    variance: f64,
+    hours: u32,
+}
+
+impl Baseline {
+    fn update(&mut self, count: f64, alpha: f64) {
+        if self.hours == 0 {
+            self.mean = count;
+        } else {
+            let diff = count - self.mean;
+            let increment = alpha * diff;
+            self.mean += increment;
+            self.variance = (1.0 - alpha) * (self.variance + diff * increment);
+        }
+        self.hours += 1;
+    }
+
+    fn std_dev(&self, floor: f64) -> f64 {
+        self.variance.sqrt().max(floor)
+    }
+}
+
+/// Hourly article counts for one market
+#[derive(Debug, Clone)]
+struct MarketVolume {
+    baseline: Baseline,
+    /// First hour not yet folded into the baseline
+    next_hour: DateTime<Utc>,
+    /// Ids of the articles seen in each hour from `next_hour` on
+    pending: BTreeMap<DateTime<Utc>, HashSet<String>>,
+    /// Hour of the last spike signalled, so each hour is signalled once
+    last_spike: Option<DateTime<Utc>>,
+    /// When the market was last checked
+    last_checked: DateTime<Utc>,
+}
+
+impl MarketVolume {
+    fn new(first_hour: DateTime<Utc>, now: DateTime<Utc>) -> Self {
+        Self {
+            baseline: Baseline::default(),
+            next_hour: first_hour,
+            pending: BTreeMap::new(),
+            last_spike: None,
+            last_checked: now,
+        }
+    }
+
+    /// Count articles into their hours; articles from hours already folded
+    /// into the baseline are ignored
+    fn add(&mut self, articles: &[NewsArticle]) {
+        for article in articles {
+            let hour = hour_of(article.timestamp);
+            if hour >= self.next_hour {
+                self.pending.entry(hour).or_default().insert(article.id.clone());
+            }
+        }
+    }
+
+    /// Fold every completed hour before `current_hour` into the baseline,
+    /// including hours without articles
+    fn close_hours(&mut self, current_hour: DateTime<Utc>, alpha: f64) {
+        while self.next_hour < current_hour {
+            let count = self.pending.remove(&self.next_hour).map_or(0, |ids| ids.len());
+            self.baseline.update(count as f64, alpha);
+            self.next_hour += Duration::hours(1);
+        }
+    }
+}
+
+fn hour_of(at: DateTime<Utc>) -> DateTime<Utc> {
+    at.duration_trunc(Duration::hours(1)).unwrap_or(at)
+}
+
+/// News Volume Agent - flags markets whose news coverage suddenly jumps
+pub struct NewsVolumeAgent {
+    base: AgentConfig,
+    config: ConfigCell<NewsVolumeAgentConfig>,
+    status: AgentStatusCell,
+    articles: Arc<RwLock<Vec<NewsArticle>>>,
+    volumes: Mutex<HashMap<MarketId, MarketVolume>>,
+    start_time: std::time::Instant,
+}
+
+impl NewsVolumeAgent {
+    /// Create a new news volume agent
+    pub fn new(config: NewsVolumeAgentConfig) -> Self {
+        Self {
+            base: config.base.clone(),
+            config: ConfigCell::new("News volume agent", config),
+            status: AgentStatusCell::default(),
+            articles: Arc::new(RwLock::new(Vec::new())),
+            volumes: Mutex::new(HashMap::new()),
+            start_time: std::time::Instant::now(),
+        }
+    }
+
+    /// Add news articles for processing
+    pub async fn add_articles(&self, articles: Vec<NewsArticle>) {
+        let count = articles.len();
+        let mut store = self.articles.write().await;
+        store.extend(articles);
+        debug!("Added {} articles, total: {}", count, store.len());
+    }
+
+    /// Clear cached articles (call after processing); counts already taken are kept
+    pub async fn clear_articles(&self) {
+        self.articles.write().await.clear();
+    }
+
+    /// Current config
+    pub fn volume_config(&self) -> Arc<NewsVolumeAgentConfig> {
+        self.config.get()
+    }
+
+    /// Update the market's counts with `articles` and check the current hour
+    /// against its baseline. Markets not checked within `history_ttl_secs`
+    /// of `now` are forgotten first.
+    fn check_volume(
+        &self,
+        config: &NewsVolumeAgentConfig,
+        market: &Market,
+        articles: &[NewsArticle],
+        now: DateTime<Utc>,
+    ) -> Option<NewsVolumeSignal> {
+        let current_hour = hour_of(now);
+        let mut volumes = self.volumes.lock().unwrap_or_else(|e| e.into_inner());
+
+        let cutoff = now - Duration::seconds(config.history_ttl_secs as i64);
+        let before = volumes.len();
+        volumes.retain(|_, volume| volume.last_checked >= cutoff);
+        if volumes.len() < before {
+            debug!("Evicted news volume for {} idle markets", before - volumes.len());
+        }
+
+        let volume = volumes.entry(market.id).or_insert_with(|| {
+            // Start the baseline from the oldest article we know of
+            let first = articles.iter().map(|a| hour_of(a.timestamp)).min().unwrap_or(current_hour);
+            MarketVolume::new(first.min(current_hour), now)
+        });
+
+        volume.last_checked = now;
+        volume.add(articles);
+        volume.close_hours(current_hour, config.baseline_alpha);
+
+        let ids = volume.pending.get(&current_hour)?;
+        let article_count = ids.len() as u32;
+        let baseline = &volume.baseline;
+
+        if baseline.hours < config.warmup_hours {
+            debug!("Market {} has {} of {} baseline hours, not checking volume",
+                   market.id, baseline.hours, config.warmup_hours);
+            return None;
+        }
+        if article_count < config.min_articles || volume.last_spike == Some(current_hour) {
+            return None;
+        }
+
+        let std_dev = baseline.std_dev(config.min_std_dev);
+        let z_score = (article_count as f64 - baseline.mean) / std_dev;
+        if z_score < config.z_threshold {
+            return None;
+        }
+
+        let mut article_ids: Vec<String> = ids.iter().cloned().collect();
+        article_ids.sort();
+        let signal = NewsVolumeSignal {
+            market_id: market.id,
+            market_category: market.category.clone(),
+            hour: current_hour,
+            article_count,
+            magnitude: article_count as f64 / baseline.mean.max(1.0),
+            z_score,
+            baseline_mean: baseline.mean,
+            baseline_std_dev: std_dev,
+            article_ids,
+            top_themes: Vec::new(),
+            timestamp: now,
+        };
+        volume.last_spike = Some(current_hour);
+        Some(signal)
+    }
+}
+
+#[async_trait]
+impl Agent for NewsVolumeAgent {
+    fn config(&self) -> &AgentConfig {
+        &self.base
+    }
+
+    fn status(&self) -> AgentStatus {
+        self.status.get()
+    }
+
+    async fn process_market(&self, input: AgentInput) -> Result<Option<AgentOutput>> {
+        let market = input.market;
+        let start = std::time::Instant::now();
+        let config = self.volume_config();
+
+        let _processing = self.status.processing();
+
+        let relevant_articles = {
+            let articles = self.articles.read().await;
+            matcher::match_articles(&config.category_keywords, &market, &articles)
+        };
+
+        let Some(mut signal) = self.check_volume(&config, &market, &relevant_articles, input.timestamp) else {
+            return Ok(None);
+        };
+
+        let spike_articles: Vec<NewsArticle> = relevant_articles.into_iter()
+            .filter(|a| signal.article_ids.contains(&a.id))
+            .collect();
+        signal.top_themes = matcher::extract_themes(&spike_articles, 5);
+
+        info!("News volume spike for market {}: {} articles this hour, z-score {:.2}",
+              market.id, signal.article_count, signal.z_score);
+
+        // More standard deviations, more certainty that coverage really jumped
+        let confidence = signal.z_score / (signal.z_score + config.z_threshold);
+
+        Ok(Some(AgentOutput {
+            agent_id: self.base.agent_id.clone(),
+            market_id: market.id,
+            signal_type: "news_volume_spike".to_string(),
+            data: serde_json::to_value(signal)?,
+            confidence,
+            timestamp: Utc::now(),
+            processing_time_ms: start.elapsed().as_millis() as u64,
+        }))
+    }
+
+    async fn handle_control(&self, msg: ControlMessage) -> Result<ControlResponse> {
+        Ok(handle_common_control(msg, &self.status, &self.config, self.start_time))
+    }
+
+    async fn on_start(&self) -> Result<()> {
+        info!("News volume agent starting");
+        self.status.set(AgentStatus::Idle);
+        Ok(())
+    }
+
+    async fn on_stop(&self) -> Result<()> {
+        info!("News volume agent stopping");
+        self.status.set(AgentStatus::Idle);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn politics_market() -> Arc<Market> {
+        Arc::new(Market {
+            id: MarketId::random(),
+            condition_id: "0xshutdown".into(),
+            question: "Will there be a government shutdown?".to_string(),
+            description: String::new(),
+            category: "Politics".to_string(),
+            outcomes: vec![],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            neg_risk_group: None,
+            tick_size: None,
+        })
+    }
+
+    fn article(id: String, at: DateTime<Utc>, themes: &str) -> NewsArticle {
+        NewsArticle {
+            id,
+            url: "https://example.com".to_string(),
+            title: "Congress budget talks".to_string(),
+            themes: themes.to_string(),
+            tone: -5.0,
+            timestamp: at,
+            source: "example.com".to_string(),
+        }
+    }
+
+    /// `per_hour` articles in each of the `hours` hours before `now`'s hour
+    fn quiet_hours(now: DateTime<Utc>, hours: i64, per_hour: usize) -> Vec<NewsArticle> {
+        let current_hour = hour_of(now);
+        (1..=hours)
+            .flat_map(|h| {
+                (0..per_hour).map(move |i| {
+                    article(format!("quiet-{}-{}", h, i), current_hour - Duration::hours(h) + Duration::minutes(10), "BUDGET")
+                })
+            })
+            .collect()
+    }
+
+    fn burst(now: DateTime<Utc>, count: usize) -> Vec<NewsArticle> {
+        (0..count)
+            .map(|i| article(format!("burst-{}", i), hour_of(now) + Duration::minutes(1), "SHUTDOWN;BUDGET"))
+            .collect()
+    }
+
+    fn input(market: &Arc<Market>, at: DateTime<Utc>) -> AgentInput {
+        AgentInput {
+            market: market.clone(),
+            timestamp: at,
+            additional_data: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_burst_after_quiet_baseline_is_a_spike() {
+        let agent = NewsVolumeAgent::new(NewsVolumeAgentConfig::default());
+        let market = politics_market();
+        let now = hour_of(Utc::now()) + Duration::minutes(30);
+
+        // Two articles an hour for a day and a half, then twelve in one hour
+        agent.add_articles(quiet_hours(now, 36, 2)).await;
+        assert!(agent.process_market(input(&market, now)).await.unwrap().is_none());
+
+        agent.add_articles(burst(now, 12)).await;
+        let output = agent.process_market(input(&market, now)).await.unwrap().unwrap();
+        assert_eq!(output.signal_type, "news_volume_spike");
+        assert_eq!(output.market_id, market.id);
+
+        let signal: NewsVolumeSignal = serde_json::from_value(output.data).unwrap();
+        assert_eq!(signal.article_count, 12);
+        assert_eq!(signal.hour, hour_of(now));
+        assert!((signal.baseline_mean - 2.0).abs() < 1e-9);
+        // A flat baseline has no variance, so the floor applies
+        assert_eq!(signal.baseline_std_dev, 1.0);
+        assert!((signal.z_score - 10.0).abs() < 1e-9);
+        assert!((signal.magnitude - 6.0).abs() < 1e-9);
+        assert_eq!(signal.article_ids.len(), 12);
+        assert!(signal.article_ids.iter().all(|id| id.starts_with("burst-")));
+        let mut themes = signal.top_themes.clone();
+        themes.sort();
+        assert_eq!(themes, vec!["budget", "shutdown"]);
+
+        // Signalled once per hour
+        assert!(agent.process_market(input(&market, now + Duration::minutes(5))).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_noisy_baseline_z_score() {
+        let agent = NewsVolumeAgent::new(NewsVolumeAgentConfig {
+            min_std_dev: 0.1,
+            ..NewsVolumeAgentConfig::default()
+        });
+        let market = politics_market();
+        let now = hour_of(Utc::now()) + Duration::minutes(30);
+
+        // Alternating one and three articles an hour
+        let current_hour = hour_of(now);
+        let articles: Vec<NewsArticle> = (1..=48)
+            .flat_map(|h: i64| {
+                let count = if h % 2 == 0 { 1 } else { 3 };
+                (0..count).map(move |i| {
+                    article(format!("{}-{}", h, i), current_hour - Duration::hours(h), "BUDGET")
+                })
+            })
+            .collect();
+        agent.add_articles(articles).await;
+        agent.add_articles(burst(now, 9)).await;
+
+        let output = agent.process_market(input(&market, now)).await.unwrap().unwrap();
+        let signal: NewsVolumeSignal = serde_json::from_value(output.data).unwrap();
+        assert!((signal.baseline_mean - 2.0).abs() < 0.2, "mean {}", signal.baseline_mean);
+        assert!((signal.baseline_std_dev - 1.0).abs() < 0.1, "std dev {}", signal.baseline_std_dev);
+        let expected = (9.0 - signal.baseline_mean) / signal.baseline_std_dev;
+        assert!((signal.z_score - expected).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_no_signal_during_warm_up() {
+        let agent = NewsVolumeAgent::new(NewsVolumeAgentConfig::default());
+        let market = politics_market();
+        let now = hour_of(Utc::now()) + Duration::minutes(30);
+
+        // Only six hours of history, well short of the 24 hour warm-up
+        agent.add_articles(quiet_hours(now, 6, 2)).await;
+        agent.add_articles(burst(now, 20)).await;
+        assert!(agent.process_market(input(&market, now)).await.unwrap().is_none());
+
+        // Articles are cleared between runs; the counts survive and the
+        // baseline keeps growing with quiet hours
+        agent.clear_articles().await;
+        let later = now + Duration::hours(20);
+        agent.add_articles(burst(later, 20)).await;
+        let output = agent.process_market(input(&market, later)).await.unwrap().unwrap();
+        let signal: NewsVolumeSignal = serde_json::from_value(output.data).unwrap();
+        assert_eq!(signal.article_count, 20);
+    }
+
+    #[tokio::test]
+    async fn test_markets_not_checked_are_forgotten() {
+        let agent = NewsVolumeAgent::new(NewsVolumeAgentConfig::default());
+        let (closed, open) = (politics_market(), politics_market());
+        let now = hour_of(Utc::now()) + Duration::minutes(30);
+
+        agent.add_articles(quiet_hours(now, 6, 2)).await;
+        agent.process_market(input(&closed, now)).await.unwrap();
+        agent.process_market(input(&open, now)).await.unwrap();
+        assert_eq!(agent.volumes.lock().unwrap().len(), 2);
+
+        // Only one market is still being checked two days on
+        agent.process_market(input(&open, now + Duration::hours(30))).await.unwrap();
+        agent.process_market(input(&open, now + Duration::hours(49))).await.unwrap();
+        let volumes = agent.volumes.lock().unwrap();
+        assert_eq!(volumes.len(), 1);
+        assert!(volumes.contains_key(&open.id));
+    }
+
+    #[tokio::test]
+    async fn test_control_messages() {
+        let agent = NewsVolumeAgent::new(NewsVolumeAgentConfig::default());
+        assert_eq!(agent.config().agent_id, "news-volume-agent");
+
+        agent.handle_control(ControlMessage::Pause).await.unwrap();
+        match agent.handle_control(ControlMessage::HealthCheck).await.unwrap() {
+            ControlResponse::HealthCheck { status, .. } => assert_eq!(status, AgentStatus::Paused),
+            other => panic!("Expected HealthCheck, got {:?}", other),
+        }
+        agent.handle_control(ControlMessage::Resume).await.unwrap();
+        assert_eq!(agent.status(), AgentStatus::Idle);
+
+        let patch = serde_json::json!({ "z_threshold": 2.5 });
+        let response = agent.handle_control(ControlMessage::UpdateConfig(patch)).await.unwrap();
+        assert!(matches!(response, ControlResponse::ConfigUpdated { applied: true, .. }));
+        assert_eq!(agent.volume_config().z_threshold, 2.5);
+
+        let patch = serde_json::json!({ "baseline_alpha": 0.0 });
+        let response = agent.handle_control(ControlMessage::UpdateConfig(patch)).await.unwrap();
+        assert!(matches!(response, ControlResponse::ConfigUpdated { applied: false, .. }));
+    }
+}
@@ -5,25 +5,28 @@
 //! - Market distribution to agents
 //! - Aggregation of signals from multiple agents
 //! - Load balancing and scheduling
+//! - Adaptive batch sizing per agent (see `batching`)
+//! - Per-agent concurrency limits, batch deadlines and runtime stats
 //!
 //! Designed to handle ~10k markets efficiently.
 
-use super::agent::{Agent, AgentInput, AgentOutput};
+use super::agent::{Agent, AgentInput, AgentOutput, AgentStatus, ControlMessage};
+use super::batching::{AdaptiveBatchConfig, BatchOutcome, BatchScheduler};
 use super::bus::AgentBus;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use dashmap::DashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, RwLock, Semaphore};
-use tracing::{debug, error, info};
-use uuid::Uuid;
-use common::Market;
+use tracing::{debug, error, info, warn};
+use common::{Market, MarketId};
 
 /// Configuration for the orchestrator
 #[derive(Debug, Clone)]
 pub struct OrchestratorConfig {
-    /// Maximum number of markets to process concurrently
+    /// Upper bound on any single agent's `max_concurrent_markets`
     pub max_concurrent_markets: usize,
     /// Maximum number of agents running concurrently
     pub max_concurrent_agents: usize,
@@ -35,6 +38,13 @@ pub struct OrchestratorConfig {
     pub enable_aggregation: bool,
     /// Minimum confidence threshold for signals
     pub min_confidence_threshold: f64,
+    /// How each agent's batch size adapts to its latency
+    pub adaptive_batching: AdaptiveBatchConfig,
+    /// Consecutive batches cut off at their deadline before an agent is
+    /// paused automatically (0 = never)
+    pub max_consecutive_timeouts: u32,
+    /// Recent `processing_time_ms` samples kept per agent for its stats
+    pub stats_window: usize,
 }
 
 impl Default for OrchestratorConfig {
@@ -46,6 +56,9 @@ impl Default for OrchestratorConfig {
             market_batch_size: 50,
             enable_aggregation: true,
             min_confidence_threshold: 0.3,
+            adaptive_batching: AdaptiveBatchConfig::default(),
+            max_consecutive_timeouts: 3,
+            stats_window: 1000,
         }
     }
 }
@@ -53,7 +66,7 @@ impl Default for OrchestratorConfig {
 /// Signal aggregation result
 #[derive(Debug, Clone)]
 pub struct AggregatedSignal {
-    pub market_id: Uuid,
+    pub market_id: MarketId,
     pub signals: Vec<AgentOutput>,
     pub aggregated_confidence: f64,
     pub consensus_direction: Option<String>,
@@ -69,19 +82,93 @@ pub enum OrchestratorStatus {
     Error,
 }
 
+/// Runtime stats of one agent, from `Orchestrator::agent_stats`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AgentRunStats {
+    /// Markets whose processing finished
+    pub processed: u64,
+    /// Markets left out of the agent's batch because they did not fit
+    pub skipped: u64,
+    /// Markets cut off at a batch deadline and carried over
+    pub timed_out: u64,
+    /// Batches in a row that hit their deadline
+    pub consecutive_timeouts: u32,
+    /// Paused by the orchestrator after too many consecutive timeouts;
+    /// cleared once the agent is resumed
+    pub auto_paused: bool,
+    /// Median `processing_time_ms` of the agent's recent outputs
+    pub p50_processing_time_ms: Option<u64>,
+    /// 95th percentile `processing_time_ms` of the agent's recent outputs
+    pub p95_processing_time_ms: Option<u64>,
+}
+
+/// Scheduling state of a registered agent
+struct AgentRuntime {
+    /// Permits for the agent's concurrent `process_batch` calls
+    semaphore: Arc<Semaphore>,
+    max_concurrent_markets: usize,
+    stats: AgentRunStats,
+    processing_times: VecDeque<u64>,
+    /// When the agent last finished a batch, None if it never has
+    last_run: Option<Instant>,
+}
+
+impl AgentRuntime {
+    fn new(max_concurrent_markets: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_concurrent_markets)),
+            max_concurrent_markets,
+            stats: AgentRunStats::default(),
+            processing_times: VecDeque::new(),
+            last_run: None,
+        }
+    }
+
+    fn stats(&self) -> AgentRunStats {
+        let mut sorted: Vec<u64> = self.processing_times.iter().copied().collect();
+        sorted.sort_unstable();
+        AgentRunStats {
+            p50_processing_time_ms: percentile(&sorted, 0.50),
+            p95_processing_time_ms: percentile(&sorted, 0.95),
+            ..self.stats.clone()
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[u64], p: f64) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
 /// Orchestrator - coordinates multiple agents
 pub struct Orchestrator {
     config: OrchestratorConfig,
     bus: Arc<AgentBus>,
 
     // Registered agents
-    agents: DashMap<String, Box<dyn Agent>>,
+    agents: DashMap<String, Arc<dyn Agent>>,
+
+    // Per-agent batch scheduling
+    schedulers: DashMap<String, BatchScheduler>,
+
+    // Per-agent concurrency limits and stats
+    runtimes: DashMap<String, AgentRuntime>,
 
     // Market cache
-    markets: DashMap<Uuid, Arc<Market>>,
+    markets: DashMap<MarketId, Arc<Market>>,
+
+    // Base scheduling priority per market (default 0.0)
+    market_priorities: DashMap<MarketId, f64>,
 
     // Signal storage
-    signals: DashMap<Uuid, Vec<AgentOutput>>,
+    signals: DashMap<MarketId, Vec<AgentOutput>>,
+
+    // Where stored outputs are also sent, e.g. signal-generation's ResearchAggregator
+    output_tx: Option<mpsc::Sender<AgentOutput>>,
 
     // State
     status: Arc<RwLock<OrchestratorStatus>>,
@@ -107,6 +194,9 @@ pub enum ControlCommand {
     AddMarkets {
         markets: Vec<Market>,
     },
+    RemoveMarkets {
+        market_ids: Vec<MarketId>,
+    },
     GetStatus {
         respond_to: mpsc::Sender<OrchestratorStatus>,
     },
@@ -121,8 +211,12 @@ impl Orchestrator {
             config,
             bus,
             agents: DashMap::new(),
+            schedulers: DashMap::new(),
+            runtimes: DashMap::new(),
             markets: DashMap::new(),
+            market_priorities: DashMap::new(),
             signals: DashMap::new(),
+            output_tx: None,
             status: Arc::new(RwLock::new(OrchestratorStatus::Idle)),
             shutdown_tx: None,
             control_tx,
@@ -203,12 +297,14 @@ impl Orchestrator {
                     error!("Failed to start agent {}: {}", agent_id, e);
                 }
 
-                self.agents.insert(agent_id.clone(), agent);
+                self.register(agent);
             }
 
             ControlCommand::UnregisterAgent { agent_id } => {
                 info!("Unregistering agent: {}", agent_id);
 
+                self.schedulers.remove(&agent_id);
+                self.runtimes.remove(&agent_id);
                 if let Some((_, agent)) = self.agents.remove(&agent_id) {
                     if let Err(e) = agent.on_stop().await {
                         error!("Failed to stop agent {}: {}", agent_id, e);
@@ -225,6 +321,15 @@ impl Orchestrator {
                 info!("Added {} markets, total: {}", count, self.markets.len());
             }
 
+            ControlCommand::RemoveMarkets { market_ids } => {
+                let count = market_ids.len();
+                for market_id in market_ids {
+                    self.markets.remove(&market_id);
+                    self.market_priorities.remove(&market_id);
+                }
+                info!("Removed {} markets, total: {}", count, self.markets.len());
+            }
+
             ControlCommand::GetStatus { respond_to } => {
                 let status = *self.status.read().await;
                 let _ = respond_to.send(status).await;
@@ -234,78 +339,272 @@ impl Orchestrator {
         Ok(())
     }
 
-    /// Process markets through all registered agents
+    /// Add an agent with its batch scheduler and concurrency limit
+    fn register(&self, agent: Box<dyn Agent>) {
+        let config = agent.config();
+        let scheduler = BatchScheduler::new(
+            self.config.adaptive_batching.clone(),
+            Duration::from_secs(config.processing_interval_secs),
+            config.max_markets_per_batch,
+        );
+        let max_concurrent_markets = config
+            .max_concurrent_markets
+            .min(self.config.max_concurrent_markets)
+            .max(1);
+
+        self.schedulers.insert(config.agent_id.clone(), scheduler);
+        self.runtimes.insert(config.agent_id.clone(), AgentRuntime::new(max_concurrent_markets));
+        self.agents.insert(config.agent_id.clone(), Arc::from(agent));
+    }
+
+    /// Process a batch of markets through every enabled agent that isn't paused
+    ///
+    /// Agents that have waited longest since their last batch go first, so
+    /// when `max_concurrent_agents` is reached a slow agent cannot keep the
+    /// others from running.
     async fn process_markets(&self) -> Result<()> {
-        let agent_ids: Vec<String> = self.agents.iter()
-            .map(|entry| entry.key().clone())
+        let mut agents: Vec<Arc<dyn Agent>> = self.agents.iter()
+            .filter(|entry| entry.value().config().enabled)
+            .filter(|entry| entry.value().status() != AgentStatus::Paused)
+            .map(|entry| entry.value().clone())
             .collect();
 
-        if agent_ids.is_empty() {
+        for agent in &agents {
+            // Running again after an automatic pause
+            if let Some(mut runtime) = self.runtimes.get_mut(&agent.config().agent_id) {
+                runtime.stats.auto_paused = false;
+            }
+        }
+        agents.sort_by_cached_key(|agent| {
+            self.runtimes.get(&agent.config().agent_id).and_then(|runtime| runtime.last_run)
+        });
+
+        if agents.is_empty() {
             debug!("No agents registered, skipping market processing");
             return Ok(());
         }
 
-        let market_ids: Vec<Uuid> = self.markets.iter()
-            .map(|entry| *entry.key())
+        let candidates: Vec<(MarketId, f64)> = self.markets.iter()
+            .map(|entry| {
+                let market_id = *entry.key();
+                let priority = self.market_priorities.get(&market_id).map_or(0.0, |p| *p);
+                (market_id, priority)
+            })
             .collect();
 
-        if market_ids.is_empty() {
+        if candidates.is_empty() {
             debug!("No markets to process");
             return Ok(());
         }
 
-        debug!("Processing {} markets with {} agents", market_ids.len(), agent_ids.len());
+        debug!("Processing {} markets with {} agents", candidates.len(), agents.len());
 
-        // Limit concurrent processing
-        let semaphore = Arc::new(Semaphore::new(self.config.max_concurrent_markets));
-        let mut tasks = Vec::new();
-
-        for market_id in market_ids {
+        // Limit concurrent agents
+        let semaphore = Arc::new(Semaphore::new(self.config.max_concurrent_agents.max(1)));
+        let runs = agents.into_iter().map(|agent| {
             let semaphore = semaphore.clone();
-            let markets = self.markets.clone();
-            let _config = self.config.clone();
-            let _bus = self.bus.clone();
-            let task_agent_ids = agent_ids.clone();
-
-            let task = tokio::spawn(async move {
+            let candidates = &candidates;
+            async move {
                 let _permit = semaphore.acquire().await;
+                self.process_agent_batch(agent.as_ref(), candidates).await;
+            }
+        });
+        futures::future::join_all(runs).await;
+
+        Ok(())
+    }
+
+    /// Run one agent's next batch within its deadline budget
+    ///
+    /// The batch is split into up to `max_concurrent_markets` chunks run
+    /// concurrently. Chunks still running at the deadline are abandoned and
+    /// their markets carried over to the next cycle; finished chunks keep
+    /// their outputs.
+    async fn process_agent_batch(&self, agent: &dyn Agent, candidates: &[(MarketId, f64)]) {
+        let agent_id = agent.config().agent_id.clone();
+        let Some((batch, budget)) = self.schedulers.get_mut(&agent_id)
+            .map(|mut scheduler| (scheduler.plan(candidates), scheduler.budget()))
+        else {
+            return;
+        };
+        let Some((semaphore, concurrency)) = self.runtimes.get(&agent_id)
+            .map(|runtime| (runtime.semaphore.clone(), runtime.max_concurrent_markets))
+        else {
+            return;
+        };
+
+        let inputs: Vec<AgentInput> = batch.iter()
+            .filter_map(|market_id| self.markets.get(market_id).map(|m| m.value().clone()))
+            .map(|market| AgentInput {
+                market,
+                timestamp: Utc::now(),
+                additional_data: None,
+            })
+            .collect();
 
-                let market_opt = markets.get(&market_id);
-                if market_opt.is_none() {
-                    return;
+        let started = tokio::time::Instant::now();
+        let deadline = started + budget;
+        let chunk_size = inputs.len().div_ceil(concurrency).max(1);
+        let chunks = inputs.chunks(chunk_size).map(|chunk| {
+            let semaphore = semaphore.clone();
+            let market_ids: Vec<MarketId> = chunk.iter().map(|input| input.market.id).collect();
+            let chunk = chunk.to_vec();
+            async move {
+                let result = tokio::time::timeout_at(deadline, async {
+                    let _permit = semaphore.acquire().await;
+                    agent.process_batch(chunk).await
+                })
+                .await;
+                (market_ids, result)
+            }
+        });
+
+        let mut processed = Vec::new();
+        let mut failed = Vec::new();
+        let mut timed_out = Vec::new();
+        let mut outputs = Vec::new();
+        for (market_ids, result) in futures::future::join_all(chunks).await {
+            match result {
+                Ok(Ok(chunk_outputs)) => {
+                    processed.extend(market_ids);
+                    outputs.extend(chunk_outputs);
+                }
+                Ok(Err(e)) => {
+                    error!("Agent {} failed on a batch of {} markets: {}", agent_id, market_ids.len(), e);
+                    failed.extend(market_ids);
                 }
+                Err(_) => timed_out.extend(market_ids),
+            }
+        }
+        let elapsed = started.elapsed();
+
+        if !timed_out.is_empty() {
+            warn!(
+                "Agent {} hit its {:?} deadline: {} of {} markets carried over",
+                agent_id, budget, timed_out.len(), batch.len()
+            );
+        }
 
-                let market = market_opt.unwrap().value().clone();
+        if let Some(mut scheduler) = self.schedulers.get_mut(&agent_id) {
+            let previous = scheduler.batch_size();
+            if !timed_out.is_empty() {
+                let carried: Vec<MarketId> = timed_out.iter().chain(&failed).copied().collect();
+                scheduler.record_partial(&processed, &carried);
+            } else if failed.is_empty() {
+                scheduler.record(&batch, elapsed, BatchOutcome::Completed);
+            } else {
+                scheduler.record(&processed, elapsed, BatchOutcome::Completed);
+                scheduler.record(&failed, elapsed, BatchOutcome::Failed);
+            }
 
-                let _input = AgentInput {
-                    market,
-                    timestamp: Utc::now(),
-                    additional_data: None,
-                };
+            if scheduler.batch_size() != previous {
+                info!(
+                    "Agent {} batch size {} -> {} (latency {:?}/market)",
+                    agent_id, previous, scheduler.batch_size(), scheduler.latency_per_market()
+                );
+            }
+        }
 
-                // Note: In a full implementation, we would need to access the agents
-                // This is a simplified version that demonstrates the architecture
-                debug!("Would process market {} with agents: {:?}", market_id, task_agent_ids);
-            });
+        let pause = self.runtimes.get_mut(&agent_id).is_some_and(|mut runtime| {
+            runtime.stats.processed += processed.len() as u64;
+            runtime.stats.skipped += candidates.len().saturating_sub(batch.len()) as u64;
+            runtime.stats.timed_out += timed_out.len() as u64;
+            runtime.last_run = Some(Instant::now());
 
-            tasks.push(task);
+            let window = self.config.stats_window.max(1);
+            runtime.processing_times.extend(outputs.iter().map(|o| o.processing_time_ms));
+            while runtime.processing_times.len() > window {
+                runtime.processing_times.pop_front();
+            }
+
+            if timed_out.is_empty() {
+                runtime.stats.consecutive_timeouts = 0;
+                return false;
+            }
+            runtime.stats.consecutive_timeouts += 1;
+            let limit = self.config.max_consecutive_timeouts;
+            if limit > 0 && runtime.stats.consecutive_timeouts >= limit {
+                runtime.stats.consecutive_timeouts = 0;
+                runtime.stats.auto_paused = true;
+                return true;
+            }
+            false
+        });
+        self.store_outputs(outputs);
+
+        if pause {
+            warn!(
+                "Pausing agent {} after {} consecutive batch deadlines",
+                agent_id, self.config.max_consecutive_timeouts
+            );
+            if let Err(e) = agent.handle_control(ControlMessage::Pause).await {
+                error!("Failed to pause agent {}: {}", agent_id, e);
+            }
         }
+    }
 
-        // Wait for all tasks to complete
-        for task in tasks {
-            let _ = task.await;
+    /// Keep outputs above the confidence threshold
+    fn store_outputs(&self, outputs: Vec<AgentOutput>) {
+        for output in outputs {
+            if output.confidence >= self.config.min_confidence_threshold {
+                if let Some(tx) = &self.output_tx {
+                    if let Err(e) = tx.try_send(output.clone()) {
+                        warn!("Not forwarding output from {}: {}", output.agent_id, e);
+                    }
+                }
+                self.signals.entry(output.market_id).or_default().push(output);
+            }
         }
+    }
 
-        Ok(())
+    /// Also send every stored output to a channel
+    pub fn forward_outputs(&mut self, tx: mpsc::Sender<AgentOutput>) {
+        self.output_tx = Some(tx);
+    }
+
+    /// Set the base scheduling priority of a market; waiting markets gain priority over time
+    pub fn set_market_priority(&self, market_id: MarketId, priority: f64) {
+        self.market_priorities.insert(market_id, priority);
+    }
+
+    /// Effective batch size of an agent
+    pub fn batch_size(&self, agent_id: &str) -> Option<usize> {
+        self.schedulers.get(agent_id).map(|s| s.batch_size())
+    }
+
+    /// Current status of every agent
+    pub fn agent_statuses(&self) -> HashMap<String, AgentStatus> {
+        self.agents.iter()
+            .map(|entry| (entry.key().clone(), entry.value().status()))
+            .collect()
+    }
+
+    /// Runtime stats of every agent
+    pub fn agent_stats(&self) -> HashMap<String, AgentRunStats> {
+        self.runtimes.iter()
+            .map(|entry| (entry.key().clone(), entry.value().stats()))
+            .collect()
+    }
+
+    /// Effective batch size of every agent
+    pub fn batch_sizes(&self) -> HashMap<String, usize> {
+        self.schedulers.iter()
+            .map(|entry| (entry.key().clone(), entry.value().batch_size()))
+            .collect()
     }
 
     /// Get aggregated signals for a market
-    pub fn get_signals(&self, market_id: Uuid) -> Option<Vec<AgentOutput>> {
+    pub fn get_signals(&self, market_id: MarketId) -> Option<Vec<AgentOutput>> {
         self.signals.get(&market_id).map(|v| v.clone())
     }
 
+    /// Bus shared with the agents
+    pub fn bus(&self) -> &Arc<AgentBus> {
+        &self.bus
+    }
+
     /// Get all market IDs
-    pub fn market_ids(&self) -> Vec<Uuid> {
+    pub fn market_ids(&self) -> Vec<MarketId> {
         self.markets.iter().map(|entry| *entry.key()).collect()
     }
 
@@ -363,6 +662,12 @@ impl OrchestratorHandle {
             .map_err(|e| anyhow::anyhow!("Failed to add markets: {}", e))
     }
 
+    /// Stop processing markets, e.g. once they resolve
+    pub async fn remove_markets(&self, market_ids: Vec<MarketId>) -> Result<()> {
+        self.tx.send(ControlCommand::RemoveMarkets { market_ids }).await
+            .map_err(|e| anyhow::anyhow!("Failed to remove markets: {}", e))
+    }
+
     /// Get the current status
     pub async fn get_status(&self) -> Result<OrchestratorStatus> {
         let (tx, mut rx) = mpsc::channel(1);
@@ -377,6 +682,303 @@ impl OrchestratorHandle {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agent::{AgentConfig, AgentStatusCell, ControlResponse};
+    use async_trait::async_trait;
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// Sleeps a configurable time per market and records every batch it is given
+    struct SlowAgent {
+        config: AgentConfig,
+        delay_ms: Arc<AtomicU64>,
+        batches: Arc<Mutex<Vec<Vec<MarketId>>>>,
+        /// Markets that never finish within any deadline
+        stuck: HashSet<MarketId>,
+        status: AgentStatusCell,
+        in_flight: Arc<AtomicUsize>,
+        peak_in_flight: Arc<AtomicUsize>,
+    }
+
+    impl SlowAgent {
+        fn new(config: AgentConfig, delay_ms: u64) -> Self {
+            Self {
+                config,
+                delay_ms: Arc::new(AtomicU64::new(delay_ms)),
+                batches: Arc::new(Mutex::new(Vec::new())),
+                stuck: HashSet::new(),
+                status: AgentStatusCell::default(),
+                in_flight: Arc::new(AtomicUsize::new(0)),
+                peak_in_flight: Arc::new(AtomicUsize::new(0)),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Agent for SlowAgent {
+        fn config(&self) -> &AgentConfig {
+            &self.config
+        }
+
+        fn status(&self) -> AgentStatus {
+            self.status.get()
+        }
+
+        async fn process_market(&self, input: AgentInput) -> Result<Option<AgentOutput>> {
+            let delay_ms = if self.stuck.contains(&input.market.id) {
+                60_000
+            } else {
+                self.delay_ms.load(Ordering::SeqCst)
+            };
+            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+            Ok(Some(AgentOutput {
+                agent_id: self.config.agent_id.clone(),
+                market_id: input.market.id,
+                signal_type: "test".to_string(),
+                data: serde_json::json!({}),
+                confidence: 0.9,
+                timestamp: Utc::now(),
+                processing_time_ms: delay_ms,
+            }))
+        }
+
+        async fn process_batch(&self, inputs: Vec<AgentInput>) -> Result<Vec<AgentOutput>> {
+            self.batches.lock().unwrap().push(inputs.iter().map(|i| i.market.id).collect());
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+
+            let mut outputs = Vec::new();
+            for input in inputs {
+                outputs.extend(self.process_market(input).await?);
+            }
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(outputs)
+        }
+
+        async fn handle_control(&self, msg: ControlMessage) -> Result<ControlResponse> {
+            match msg {
+                ControlMessage::Pause => self.status.set(AgentStatus::Paused),
+                ControlMessage::Resume => self.status.set(AgentStatus::Idle),
+                _ => {}
+            }
+            Ok(ControlResponse::Ok)
+        }
+
+        async fn on_start(&self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn on_stop(&self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn market() -> Market {
+        Market {
+            id: MarketId::random(),
+            condition_id: "test".into(),
+            question: "test".to_string(),
+            description: String::new(),
+            category: "politics".to_string(),
+            outcomes: vec![],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            neg_risk_group: None,
+            tick_size: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stored_outputs_are_forwarded() {
+        let bus = Arc::new(AgentBus::new(crate::bus::AgentBusConfig::default()).await.unwrap());
+        let mut orchestrator = Orchestrator::new(OrchestratorConfig::default(), bus).await.unwrap();
+        let (tx, mut rx) = mpsc::channel(10);
+        orchestrator.forward_outputs(tx);
+
+        orchestrator.register(Box::new(SlowAgent::new(
+            AgentConfig { agent_id: "fast".to_string(), ..Default::default() },
+            0,
+        )));
+        let market = market();
+        orchestrator.markets.insert(market.id, Arc::new(market.clone()));
+        orchestrator.process_markets().await.unwrap();
+
+        let forwarded = rx.try_recv().unwrap();
+        assert_eq!(forwarded.agent_id, "fast");
+        assert_eq!(forwarded.market_id, market.id);
+        assert_eq!(orchestrator.get_signals(market.id).unwrap().len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_batch_size_adapts_to_agent_latency() {
+        let bus = Arc::new(AgentBus::new(crate::bus::AgentBusConfig::default()).await.unwrap());
+        let orchestrator = Orchestrator::new(OrchestratorConfig::default(), bus).await.unwrap();
+
+        // The clock is paused, so the agent's sleeps and the measured latency are exact.
+        // 1s interval: each batch gets a 500ms budget, and at most 20 markets
+        let agent = SlowAgent::new(
+            AgentConfig {
+                agent_id: "slow".to_string(),
+                max_markets_per_batch: 20,
+                processing_interval_secs: 1,
+                ..Default::default()
+            },
+            5,
+        );
+        let delay_ms = agent.delay_ms.clone();
+        let batches = agent.batches.clone();
+        orchestrator.register(Box::new(agent));
+        let markets: Vec<Market> = (0..30).map(|_| market()).collect();
+        for market in &markets {
+            orchestrator.markets.insert(market.id, Arc::new(market.clone()));
+        }
+
+        // A single market measures the latency, then the batch jumps to the ceiling
+        orchestrator.process_markets().await.unwrap();
+        assert_eq!(orchestrator.batch_size("slow"), Some(20));
+        orchestrator.process_markets().await.unwrap();
+        assert_eq!(orchestrator.batch_sizes()["slow"], 20);
+
+        // The agent slows down: 20 markets no longer fit and the batch is halved
+        delay_ms.store(40, Ordering::SeqCst);
+        orchestrator.process_markets().await.unwrap();
+        assert_eq!(orchestrator.batch_size("slow"), Some(10));
+
+        // The timed out markets are retried first and fit now
+        orchestrator.process_markets().await.unwrap();
+        assert_eq!(orchestrator.batch_size("slow"), Some(10));
+
+        let batches = batches.lock().unwrap();
+        assert_eq!(batches.iter().map(Vec::len).collect::<Vec<_>>(), vec![1, 20, 20, 10]);
+        assert!(batches[3].iter().all(|m| batches[2].contains(m)));
+
+        // Every market got a turn within four cycles
+        let processed: std::collections::HashSet<MarketId> =
+            orchestrator.signals.iter().map(|entry| *entry.key()).collect();
+        assert_eq!(processed.len(), 30);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_paused_agents_are_skipped() {
+        use crate::sentiment::{SentimentAgent, SentimentAgentConfig};
+
+        let bus = Arc::new(AgentBus::new(crate::bus::AgentBusConfig::default()).await.unwrap());
+        let orchestrator = Orchestrator::new(OrchestratorConfig::default(), bus).await.unwrap();
+        orchestrator.register(Box::new(SentimentAgent::new(SentimentAgentConfig::default())));
+        orchestrator.register(Box::new(SlowAgent::new(
+            AgentConfig {
+                agent_id: "slow".to_string(),
+                ..Default::default()
+            },
+            0,
+        )));
+        let market = market();
+        orchestrator.markets.insert(market.id, Arc::new(market));
+
+        let agent = orchestrator.agents.get("sentiment-agent").unwrap().clone();
+        agent.handle_control(ControlMessage::Pause).await.unwrap();
+        let statuses = orchestrator.agent_statuses();
+        assert_eq!(statuses["sentiment-agent"], AgentStatus::Paused);
+        assert_eq!(statuses["slow"], AgentStatus::Idle);
+
+        // Only the running agent measures a batch and grows past warm-up
+        orchestrator.process_markets().await.unwrap();
+        assert_eq!(orchestrator.batch_size("sentiment-agent"), Some(1));
+        assert_eq!(orchestrator.batch_size("slow"), Some(100));
+    }
+
+    #[tokio::test]
+    async fn test_deadline_carries_over_and_pauses_slow_agent() {
+        let bus = Arc::new(AgentBus::new(crate::bus::AgentBusConfig::default()).await.unwrap());
+        let config = OrchestratorConfig {
+            // 1s interval: 100ms per batch, always all four markets
+            adaptive_batching: AdaptiveBatchConfig {
+                budget_fraction: 0.1,
+                min_batch_size: 4,
+                ..Default::default()
+            },
+            max_consecutive_timeouts: 2,
+            max_concurrent_agents: 1,
+            ..Default::default()
+        };
+        let orchestrator = Orchestrator::new(config, bus).await.unwrap();
+
+        let markets: Vec<Market> = (0..4).map(|_| market()).collect();
+        for market in &markets {
+            orchestrator.markets.insert(market.id, Arc::new(market.clone()));
+        }
+        let agent_config = |agent_id: &str, max_concurrent_markets| AgentConfig {
+            agent_id: agent_id.to_string(),
+            max_markets_per_batch: 4,
+            processing_interval_secs: 1,
+            max_concurrent_markets,
+            ..Default::default()
+        };
+
+        // One market per call, one of which never finishes
+        let mut slow = SlowAgent::new(agent_config("slow", 4), 20);
+        let stuck = markets[0].id;
+        slow.stuck.insert(stuck);
+        orchestrator.register(Box::new(slow));
+
+        // Two calls of two markets each
+        let fast = SlowAgent::new(agent_config("fast", 2), 1);
+        let fast_batches = fast.batches.clone();
+        let fast_peak = fast.peak_in_flight.clone();
+        orchestrator.register(Box::new(fast));
+
+        orchestrator.process_markets().await.unwrap();
+        let stats = orchestrator.agent_stats();
+        assert_eq!((stats["slow"].processed, stats["slow"].timed_out), (3, 1));
+        assert_eq!(stats["slow"].consecutive_timeouts, 1);
+        assert_eq!(stats["slow"].p50_processing_time_ms, Some(20));
+        assert!(!stats["slow"].auto_paused);
+
+        // The stuck market is carried over, the finished ones kept their signals
+        let scheduler = orchestrator.schedulers.get("slow").unwrap();
+        assert_eq!(scheduler.waiting_cycles(stuck), Some(1));
+        assert!(markets[1..].iter().all(|m| scheduler.waiting_cycles(m.id) == Some(0)));
+        drop(scheduler);
+        let slow_signals = |market_id: MarketId| {
+            orchestrator.get_signals(market_id).unwrap_or_default().iter().filter(|o| o.agent_id == "slow").count()
+        };
+        assert_eq!(slow_signals(stuck), 0);
+        assert_eq!(slow_signals(markets[1].id), 1);
+
+        // The fast agent completed its whole batch despite the slow one
+        assert_eq!((stats["fast"].processed, stats["fast"].timed_out), (4, 0));
+        assert_eq!(fast_batches.lock().unwrap().iter().map(Vec::len).collect::<Vec<_>>(), vec![2, 2]);
+        assert_eq!(fast_peak.load(Ordering::SeqCst), 2);
+
+        // A second deadline in a row pauses the slow agent
+        orchestrator.process_markets().await.unwrap();
+        let stats = orchestrator.agent_stats();
+        assert!(stats["slow"].auto_paused);
+        assert_eq!(stats["slow"].timed_out, 2);
+        assert_eq!(orchestrator.agent_statuses()["slow"], AgentStatus::Paused);
+
+        // Paused, it is skipped while the fast agent keeps going
+        orchestrator.process_markets().await.unwrap();
+        let stats = orchestrator.agent_stats();
+        assert_eq!(stats["slow"].processed, 6);
+        assert_eq!(stats["fast"].processed, 12);
+        assert!(stats["slow"].auto_paused);
+
+        // Resumed, the flag clears
+        let slow = orchestrator.agents.get("slow").unwrap().clone();
+        slow.handle_control(ControlMessage::Resume).await.unwrap();
+        orchestrator.process_markets().await.unwrap();
+        assert!(!orchestrator.agent_stats()["slow"].auto_paused);
+    }
+
+    #[test]
+    fn test_percentile() {
+        let samples: Vec<u64> = (1..=20).collect();
+        assert_eq!(percentile(&samples, 0.50), Some(10));
+        assert_eq!(percentile(&samples, 0.95), Some(19));
+        assert_eq!(percentile(&[7], 0.95), Some(7));
+        assert_eq!(percentile(&[], 0.5), None);
+    }
 
     #[tokio::test]
     async fn test_orchestrator_creation() {
@@ -1,26 +1,29 @@
 //! Sentiment Agent - Analyzes news sentiment for market impact
 //!
 //! This agent processes news data from Layer 0 (GDELT, etc.) and:
-//! - Calculates sentiment scores using simple NLP
-//! - Matches news themes to market categories
+//! - Calculates sentiment scores from GDELT tone blended with a pluggable
+//!   text backend (keyword lexicon by default, transformer with `full-nlp`)
+//! - Matches news to markets by category keywords, embeddings, or both
 //! - Generates sentiment signals with confidence scores
-//!
-//! Future enhancements:
-//! - Use rust-bert for advanced sentiment analysis
-//! - Topic modeling for better theme matching
-//! - Temporal sentiment tracking
-
-use super::agent::{Agent, AgentConfig, AgentInput, AgentOutput, AgentStatus, ControlMessage, ControlResponse};
+//! - Tracks sentiment over time, reporting momentum and windowed deltas
+
+use super::agent::{
+    handle_common_control, Agent, AgentConfig, AgentInput, AgentOutput, AgentStatus, AgentStatusCell,
+    ConfigCell, ControlMessage, ControlResponse, PatchableConfig,
+};
+use super::matcher::{self, default_category_keywords};
+use super::sentiment_backend::{KeywordBackend, SentimentBackend};
+use super::sentiment_history::SentimentHistory;
+use super::vector_store::VectorStore;
 use anyhow::Result;
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
-use uuid::Uuid;
-use common::Market;
+use common::{Market, MarketId};
 
 /// Sentiment score with metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,23 +37,45 @@ pub struct SentimentScore {
 /// Sentiment signal output
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SentimentSignal {
-    pub market_id: Uuid,
+    pub market_id: MarketId,
     pub market_category: String,
     pub sentiment: SentimentScore,
     pub top_themes: Vec<String>,
     pub timestamp: DateTime<Utc>,
     pub sources: Vec<String>,
+    /// Slope of the score over the momentum window, per hour
+    #[serde(default)]
+    pub momentum: f64,
+    /// Score the market had the previous time it was processed
+    #[serde(default)]
+    pub previous_score: Option<SentimentScore>,
+    /// Change in score over each delta window
+    #[serde(default)]
+    pub deltas: Vec<SentimentDelta>,
 }
 
-/// News article from GDELT (simplified)
+/// Change in sentiment score over a window
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct NewsArticle {
-    pub id: String,
-    pub url: String,
-    pub title: String,
-    pub themes: String,
-    pub tone: f64,
-    pub timestamp: DateTime<Utc>,
+pub struct SentimentDelta {
+    pub window_secs: u64,
+    /// None if the window holds fewer than two scores
+    pub change: Option<f64>,
+}
+
+/// News article from GDELT
+pub use common::NewsArticle;
+
+/// How articles are matched to markets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchingMode {
+    /// The market category's keywords
+    #[default]
+    Keyword,
+    /// Embedding similarity to the market question, via the vector store
+    Semantic,
+    /// Similarity and keyword hits weighted by `semantic_weight`
+    Blended,
 }
 
 /// Sentiment agent configuration
@@ -59,46 +84,65 @@ pub struct SentimentAgentConfig {
     pub base: AgentConfig,
     pub min_articles_threshold: u32,
     pub sentiment_threshold: f64,
+    /// Weight of the backend's text score, scaled by its confidence
     pub theme_weight: f64,
     pub tone_weight: f64,
     pub category_keywords: HashMap<String, Vec<String>>,
+    /// Scores kept per market
+    pub history_capacity: usize,
+    /// Window the momentum slope is fitted over
+    pub momentum_window_secs: u64,
+    /// Windows to report score deltas over
+    pub delta_windows_secs: Vec<u64>,
+    /// Suppress a signal unless the score moved by more than this since the
+    /// last signal for the market (0 disables suppression)
+    pub min_score_change: f64,
+    /// Forget markets not processed for this long
+    pub history_ttl_secs: u64,
+    /// Keyword matching unless a vector store is attached
+    #[serde(default)]
+    pub matching: MatchingMode,
+    /// Most articles semantic matching considers per market
+    #[serde(default = "default_semantic_top_k")]
+    pub semantic_top_k: usize,
+    /// Score an article needs to be relevant: its similarity when semantic,
+    /// the weighted blend when blended
+    #[serde(default = "default_semantic_min_similarity")]
+    pub semantic_min_similarity: f64,
+    /// Share of a blended score from similarity; a keyword hit adds the rest
+    #[serde(default = "default_semantic_weight")]
+    pub semantic_weight: f64,
+    /// Age at which an article counts half as much as one published now
+    /// (0 weights every article equally)
+    #[serde(default = "default_decay_half_life_secs")]
+    pub decay_half_life_secs: u64,
+    /// Articles older than this are dropped as they are added (0 keeps all)
+    #[serde(default = "default_max_article_age_secs")]
+    pub max_article_age_secs: u64,
+}
+
+fn default_semantic_top_k() -> usize {
+    50
+}
+
+fn default_semantic_min_similarity() -> f64 {
+    0.3
+}
+
+fn default_semantic_weight() -> f64 {
+    0.7
+}
+
+fn default_decay_half_life_secs() -> u64 {
+    12 * 3600
+}
+
+fn default_max_article_age_secs() -> u64 {
+    72 * 3600
 }
 
 impl Default for SentimentAgentConfig {
     fn default() -> Self {
-        let mut category_keywords: HashMap<String, Vec<String>> = HashMap::new();
-
-        // Polymarket common categories
-        category_keywords.insert("Politics".to_string(), vec![
-            "election".to_string(), "vote".to_string(), "president".to_string(), "congress".to_string(), "senate".to_string(), "campaign".to_string(),
-            "republican".to_string(), "democrat".to_string(), "policy".to_string(), "government".to_string(), "legislation".to_string(),
-            "trump".to_string(), "biden".to_string(), "white house".to_string(), "capitol".to_string(),
-        ]);
-
-        category_keywords.insert("Economics".to_string(), vec![
-            "inflation".to_string(), "gdp".to_string(), "economy".to_string(), "recession".to_string(), "unemployment".to_string(),
-            "interest rate".to_string(), "federal reserve".to_string(), "fed".to_string(), "stock market".to_string(),
-            "crypto".to_string(), "bitcoin".to_string(), "ethereum".to_string(), "finance".to_string(),
-        ]);
-
-        category_keywords.insert("Geopolitics".to_string(), vec![
-            "war".to_string(), "conflict".to_string(), "invasion".to_string(), "military".to_string(), "sanctions".to_string(),
-            "diplomacy".to_string(), "treaty".to_string(), "nuclear".to_string(), "russia".to_string(), "ukraine".to_string(),
-            "china".to_string(), "israel".to_string(), "palestine".to_string(), "iran".to_string(),
-        ]);
-
-        category_keywords.insert("Technology".to_string(), vec![
-            "ai".to_string(), "artificial intelligence".to_string(), "tech".to_string(), "software".to_string(), "startup".to_string(),
-            "innovation".to_string(), "cybersecurity".to_string(), "data".to_string(), "cloud".to_string(), "platform".to_string(),
-            "regulation".to_string(), "antitrust".to_string(), "monopoly".to_string(),
-        ]);
-
-        category_keywords.insert("Climate".to_string(), vec![
-            "climate".to_string(), "warming".to_string(), "carbon".to_string(), "emissions".to_string(), "renewable".to_string(),
-            "energy".to_string(), "solar".to_string(), "wind".to_string(), "weather".to_string(), "disaster".to_string(),
-            "cop".to_string(), "paris".to_string(), "agreement".to_string(), "green".to_string(),
-        ]);
-
         Self {
             base: AgentConfig {
                 agent_id: "sentiment-agent".to_string(),
@@ -106,52 +150,199 @@ impl Default for SentimentAgentConfig {
                 enabled: true,
                 max_markets_per_batch: 50,
                 processing_interval_secs: 300, // 5 minutes
+                max_concurrent_markets: 1,
             },
             min_articles_threshold: 3,
             sentiment_threshold: 0.2,
             theme_weight: 0.6,
             tone_weight: 0.4,
-            category_keywords,
+            category_keywords: default_category_keywords(),
+            history_capacity: 288, // 24h at the processing interval
+            momentum_window_secs: 6 * 3600,
+            delta_windows_secs: vec![3600, 6 * 3600, 24 * 3600],
+            min_score_change: 0.0,
+            history_ttl_secs: 48 * 3600,
+            matching: MatchingMode::Keyword,
+            semantic_top_k: default_semantic_top_k(),
+            semantic_min_similarity: default_semantic_min_similarity(),
+            semantic_weight: default_semantic_weight(),
+            decay_half_life_secs: default_decay_half_life_secs(),
+            max_article_age_secs: default_max_article_age_secs(),
         }
     }
 }
 
+impl PatchableConfig for SentimentAgentConfig {
+    /// Check the tunables, returning every problem found
+    fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.min_articles_threshold == 0 {
+            errors.push("min_articles_threshold must be at least 1".to_string());
+        }
+        for (name, value) in [
+            ("sentiment_threshold", self.sentiment_threshold),
+            ("theme_weight", self.theme_weight),
+            ("tone_weight", self.tone_weight),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                errors.push(format!("{} must be in [0, 1], got {}", name, value));
+            }
+        }
+        if !(0.0..=2.0).contains(&self.min_score_change) {
+            errors.push(format!("min_score_change must be in [0, 2], got {}", self.min_score_change));
+        }
+        if self.history_capacity < 2 {
+            errors.push("history_capacity must be at least 2".to_string());
+        }
+        if self.momentum_window_secs == 0 || self.delta_windows_secs.contains(&0) {
+            errors.push("momentum and delta windows must be positive".to_string());
+        }
+        if self.history_ttl_secs == 0 {
+            errors.push("history_ttl_secs must be positive".to_string());
+        }
+        if self.semantic_top_k == 0 {
+            errors.push("semantic_top_k must be at least 1".to_string());
+        }
+        if !(-1.0..=1.0).contains(&self.semantic_min_similarity) {
+            errors.push(format!("semantic_min_similarity must be in [-1, 1], got {}", self.semantic_min_similarity));
+        }
+        if !(0.0..=1.0).contains(&self.semantic_weight) {
+            errors.push(format!("semantic_weight must be in [0, 1], got {}", self.semantic_weight));
+        }
+        let weights = self.theme_weight + self.tone_weight;
+        if weights <= 0.0 || weights > 1.0 + 1e-9 {
+            errors.push(format!("theme_weight + tone_weight must be in (0, 1], got {}", weights));
+        }
+        for (category, keywords) in &self.category_keywords {
+            if keywords.iter().any(|kw| kw.trim().is_empty()) {
+                errors.push(format!("category {} has an empty keyword", category));
+            }
+        }
+
+        errors
+    }
+
+    /// Patched keywords are matched like configured ones, so a patch can
+    /// replace a single category's keywords
+    fn normalize(&mut self) {
+        matcher::normalize_keywords(&mut self.category_keywords);
+    }
+}
+
+impl SentimentAgentConfig {
+    /// How much an article published at `timestamp` counts at `now`, in (0, 1]
+    ///
+    /// Halves every `decay_half_life_secs`; articles stamped in the future
+    /// count as fresh.
+    pub fn recency_weight(&self, timestamp: DateTime<Utc>, now: DateTime<Utc>) -> f64 {
+        if self.decay_half_life_secs == 0 {
+            return 1.0;
+        }
+        let age_secs = (now - timestamp).num_seconds().max(0) as f64;
+        0.5f64.powf(age_secs / self.decay_half_life_secs as f64)
+    }
+}
+
 /// Sentiment Agent - analyzes news for market sentiment
 pub struct SentimentAgent {
-    config: SentimentAgentConfig,
-    status: Arc<RwLock<AgentStatus>>,
+    base: AgentConfig,
+    config: ConfigCell<SentimentAgentConfig>,
+    status: AgentStatusCell,
     articles: Arc<RwLock<Vec<NewsArticle>>>,
-    sentiment_cache: Arc<RwLock<HashMap<Uuid, SentimentScore>>>,
+    sentiment_cache: Arc<RwLock<HashMap<MarketId, SentimentScore>>>,
+    history: Arc<RwLock<SentimentHistory>>,
+    /// Embeds added articles and serves semantic matching
+    vector_store: Option<Arc<VectorStore>>,
+    /// Scores article text; blended with the GDELT tone
+    backend: Arc<dyn SentimentBackend>,
     start_time: std::time::Instant,
 }
 
 impl SentimentAgent {
     /// Create a new sentiment agent
     pub fn new(config: SentimentAgentConfig) -> Self {
+        let history = SentimentHistory::new(config.history_capacity);
         Self {
-            config,
-            status: Arc::new(RwLock::new(AgentStatus::Idle)),
+            base: config.base.clone(),
+            config: ConfigCell::new("Sentiment agent", config),
+            status: AgentStatusCell::default(),
             articles: Arc::new(RwLock::new(Vec::new())),
             sentiment_cache: Arc::new(RwLock::new(HashMap::new())),
+            history: Arc::new(RwLock::new(history)),
+            vector_store: None,
+            backend: Arc::new(KeywordBackend),
             start_time: std::time::Instant::now(),
         }
     }
 
-    /// Add news articles for processing
-    pub async fn add_articles(&self, articles: Vec<NewsArticle>) {
+    /// Embed articles into `store` as they are added, for semantic matching
+    pub fn with_vector_store(mut self, store: Arc<VectorStore>) -> Self {
+        self.vector_store = Some(store);
+        self
+    }
+
+    /// Score article text with `backend` instead of the keyword lexicon
+    pub fn with_backend(mut self, backend: Arc<dyn SentimentBackend>) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Scores recorded per market
+    pub fn history(&self) -> Arc<RwLock<SentimentHistory>> {
+        self.history.clone()
+    }
+
+    /// Add news articles for processing, dropping any older than
+    /// `max_article_age_secs` along with stored ones that have aged out
+    pub async fn add_articles(&self, mut articles: Vec<NewsArticle>) {
+        let max_age_secs = self.sentiment_config().max_article_age_secs;
+        let cutoff = (max_age_secs > 0).then(|| Utc::now() - Duration::seconds(max_age_secs as i64));
+        if let Some(cutoff) = cutoff {
+            articles.retain(|a| a.timestamp >= cutoff);
+        }
+
         let count = articles.len();
+        if let Some(store) = &self.vector_store {
+            if let Err(e) = store.index_articles(&articles).await {
+                warn!("Failed to embed {} articles: {}", count, e);
+            }
+        }
         let mut store = self.articles.write().await;
+        if let Some(cutoff) = cutoff {
+            let before = store.len();
+            store.retain(|a| a.timestamp >= cutoff);
+            if store.len() < before {
+                debug!("Pruned {} articles older than {}s", before - store.len(), max_age_secs);
+            }
+        }
         store.extend(articles);
         debug!("Added {} articles, total: {}", count, store.len());
     }
 
+    /// Articles waiting to be processed
+    pub async fn article_count(&self) -> usize {
+        self.articles.read().await.len()
+    }
+
     /// Clear cached articles (call after processing)
     pub async fn clear_articles(&self) {
         self.articles.write().await.clear();
     }
 
-    /// Calculate sentiment from articles using simple NLP
-    fn calculate_sentiment(&self, articles: &[NewsArticle]) -> SentimentScore {
+    /// Current config
+    pub fn sentiment_config(&self) -> Arc<SentimentAgentConfig> {
+        self.config.get()
+    }
+
+    /// Calculate sentiment from the articles' tone and the backend's reading
+    /// of their text, recent articles counting for more
+    fn calculate_sentiment(
+        &self,
+        config: &SentimentAgentConfig,
+        articles: &[NewsArticle],
+        now: DateTime<Utc>,
+    ) -> SentimentScore {
         if articles.is_empty() {
             return SentimentScore {
                 score: 0.0,
@@ -162,28 +353,40 @@ impl SentimentAgent {
         }
 
         let article_count = articles.len() as u32;
-
-        // Method 1: Use GDELT's tone score (-100 to +100)
+        // Every use of the weights is a ratio, so measuring age from the
+        // newest article gives the same result as from `now` without
+        // underflowing to zero for a batch of old articles
+        let newest = articles.iter().map(|a| a.timestamp).max().unwrap_or(now).min(now);
+        let weights: Vec<f64> = articles.iter()
+            .map(|a| config.recency_weight(a.timestamp, newest))
+            .collect();
+
+        // Method 1: Use GDELT's tone score (-100 to +100), weighted by recency
         let tone_sum: f64 = articles.iter()
-            .map(|a| a.tone)
+            .zip(&weights)
+            .map(|(a, weight)| a.tone * weight)
             .sum();
 
-        let avg_tone = tone_sum / articles.len() as f64;
+        let avg_tone = tone_sum / weights.iter().sum::<f64>();
         let tone_sentiment = (avg_tone / 100.0).clamp(-1.0, 1.0);
 
-        // Method 2: Simple keyword-based sentiment (very basic)
-        let keyword_sentiment = self.calculate_keyword_sentiment(articles);
+        // Method 2: The backend's reading of the text, counted for as much as
+        // it is sure of
+        let text = self.backend.score(articles, &weights).unwrap_or_else(|e| {
+            warn!("{} sentiment backend failed, scoring by keyword: {}", self.backend.name(), e);
+            KeywordBackend.score(articles, &weights).expect("keyword scoring is infallible")
+        });
 
         // Combine methods
-        let score = (tone_sentiment * self.config.tone_weight) +
-                   (keyword_sentiment * self.config.theme_weight);
+        let score = (tone_sentiment * config.tone_weight) +
+                   (text.sentiment * text.confidence.clamp(0.0, 1.0) * config.theme_weight);
 
         // Magnitude = strength of sentiment (absolute value)
         let magnitude = score.abs();
 
         // Confidence based on article count and consistency
-        let confidence = if article_count >= self.config.min_articles_threshold {
-            let count_factor = (article_count as f64 / self.config.min_articles_threshold as f64)
+        let confidence = if article_count >= config.min_articles_threshold {
+            let count_factor = (article_count as f64 / config.min_articles_threshold as f64)
                 .min(2.0) / 2.0;
             let consistency_factor = 1.0 - (articles.iter()
                 .map(|a| a.tone)
@@ -193,7 +396,7 @@ impl SentimentAgent {
                 .sum::<f64>() / articles.len().saturating_sub(1).max(1) as f64);
             (count_factor + consistency_factor) / 2.0
         } else {
-            (article_count as f64 / self.config.min_articles_threshold as f64).max(0.1)
+            (article_count as f64 / config.min_articles_threshold as f64).max(0.1)
         };
 
         SentimentScore {
@@ -204,159 +407,126 @@ impl SentimentAgent {
         }
     }
 
-    /// Simple keyword-based sentiment analysis
-    fn calculate_keyword_sentiment(&self, articles: &[NewsArticle]) -> f64 {
-        // Very basic sentiment lexicon (negative/positive words)
-        let negative_words = vec![
-            "crisis", "crash", "drop", "fall", "decline", "decrease",
-            "loss", "fail", "bad", "negative", "worst", "downward",
-            "bearish", "sell", "dump", "collapse", "risk", "danger",
-            "threat", "attack", "war", "conflict", "inflation", "recession",
-        ];
-
-        let positive_words = vec![
-            "growth", "rise", "increase", "gain", "profit", "success",
-            "good", "positive", "best", "upward", "bullish", "buy",
-            "recovery", "boom", "breakthrough", "win", "victory",
-            "peace", "agreement", "deal", "lower", "cut", "reduce",
-        ];
-
-        let mut total_score = 0.0;
-        let mut total_words = 0.0;
-
-        for article in articles {
-            let text = format!("{} {}", article.title, article.themes).to_lowercase();
-
-            for word in &negative_words {
-                if text.contains(word) {
-                    total_score -= 1.0;
-                    total_words += 1.0;
-                }
-            }
-
-            for word in &positive_words {
-                if text.contains(word) {
-                    total_score += 1.0;
-                    total_words += 1.0;
-                }
-            }
-        }
-
-        if total_words > 0.0 {
-            let result: f64 = total_score / total_words;
-            result.clamp(-1.0, 1.0)
-        } else {
-            0.0
-        }
-    }
-
     /// Extract themes from articles
     fn extract_themes(&self, articles: &[NewsArticle], limit: usize) -> Vec<String> {
-        let mut theme_counts: HashMap<String, u32> = HashMap::new();
+        matcher::extract_themes(articles, limit)
+    }
 
-        for article in articles {
-            for theme in article.themes.split(';') {
-                let theme = theme.trim().to_lowercase();
-                if !theme.is_empty() {
-                    *theme_counts.entry(theme).or_insert(0) += 1;
+    /// Match articles to market category
+    async fn match_to_category(&self, config: &SentimentAgentConfig, market: &Market) -> Vec<NewsArticle> {
+        let similarities = match (config.matching, &self.vector_store) {
+            (MatchingMode::Keyword, _) => None,
+            (_, None) => {
+                debug!("No vector store attached, matching market {} by keyword", market.id);
+                None
+            }
+            (mode, Some(store)) => {
+                // Blending needs the similarity of articles that fall short by it alone
+                let floor = if mode == MatchingMode::Blended { -1.0 } else { config.semantic_min_similarity as f32 };
+                match store.semantic_match(market, config.semantic_top_k, floor).await {
+                    Ok(matches) => Some(
+                        matches.into_iter().map(|m| (m.article_id, m.similarity as f64)).collect::<HashMap<_, _>>(),
+                    ),
+                    Err(e) => {
+                        warn!("Semantic matching failed for market {}, matching by keyword: {}", market.id, e);
+                        None
+                    }
                 }
             }
-        }
-
-        let mut themes: Vec<(String, u32)> = theme_counts.into_iter().collect();
-        themes.sort_by(|a, b| b.1.cmp(&a.1));
-
-        themes.into_iter()
-            .take(limit)
-            .map(|(t, _)| t)
-            .collect()
-    }
+        };
 
-    /// Match articles to market category
-    fn match_to_category(&self, market: &Market) -> Vec<NewsArticle> {
-        let _category = market.category.to_lowercase();
-        let question = market.question.to_lowercase();
-        let description = market.description.to_lowercase();
+        let articles = self.articles.read().await;
+        let Some(similarities) = similarities else {
+            return matcher::match_articles(&config.category_keywords, market, &articles);
+        };
+        if config.matching == MatchingMode::Semantic {
+            return articles.iter().filter(|a| similarities.contains_key(&a.id)).cloned().collect();
+        }
 
-        let keywords = self.config.category_keywords
-            .get(&market.category)
+        let keyword_hits: HashSet<String> = matcher::match_articles(&config.category_keywords, market, &articles)
+            .into_iter()
+            .map(|a| a.id)
+            .collect();
+        articles
+            .iter()
+            .filter(|article| {
+                let similarity = similarities.get(&article.id).copied().unwrap_or(0.0);
+                let keyword = if keyword_hits.contains(&article.id) { 1.0 } else { 0.0 };
+                let score = config.semantic_weight * similarity + (1.0 - config.semantic_weight) * keyword;
+                score >= config.semantic_min_similarity
+            })
             .cloned()
-            .unwrap_or_default();
-
-        let articles = self.articles.try_read();
-
-        if let Ok(articles) = articles {
-            articles.iter()
-                .filter(|article| {
-                    let article_text = format!("{} {}", article.title, article.themes).to_lowercase();
-
-                    // Check category match
-                    let category_match = if let Some(cats) = self.config.category_keywords.get(&market.category) {
-                        cats.iter().any(|kw| article_text.contains(kw))
-                    } else {
-                        false
-                    };
-
-                    // Check keyword match in question/description
-                    let keyword_match = keywords.iter()
-                        .any(|kw| question.contains(kw) || description.contains(kw));
-
-                    // Check for theme overlap
-                    let theme_overlap = article.themes.to_lowercase()
-                        .split(';')
-                        .any(|t| keywords.contains(&t.trim().to_lowercase()));
-
-                    category_match || keyword_match || theme_overlap
-                })
-                .cloned()
-                .collect()
-        } else {
-            Vec::new()
-        }
+            .collect()
     }
 }
 
 #[async_trait]
 impl Agent for SentimentAgent {
     fn config(&self) -> &AgentConfig {
-        &self.config.base
+        &self.base
     }
 
     fn status(&self) -> AgentStatus {
-        tokio::task::block_in_place(|| {
-            tokio::runtime::Handle::current().block_on(async {
-                *self.status.read().await
-            })
-        })
+        self.status.get()
     }
 
     async fn process_market(&self, input: AgentInput) -> Result<Option<AgentOutput>> {
         let market = input.market;
         let start = std::time::Instant::now();
+        let config = self.sentiment_config();
 
-        // Update status
-        *self.status.write().await = AgentStatus::Processing;
+        let _processing = self.status.processing();
 
         // Match articles to this market
-        let relevant_articles = self.match_to_category(&market);
+        let relevant_articles = self.match_to_category(&config, &market).await;
 
         if relevant_articles.is_empty() {
             debug!("No relevant articles for market {}", market.id);
-            *self.status.write().await = AgentStatus::Idle;
             return Ok(None);
         }
 
         // Calculate sentiment
-        let sentiment = self.calculate_sentiment(&relevant_articles);
+        let now = Utc::now();
+        let sentiment = self.calculate_sentiment(&config, &relevant_articles, now);
+
+        // Track every score, so momentum reflects moves below the threshold too
+        let mut history = self.history.write().await;
+        history.set_capacity(config.history_capacity);
+        let evicted = history.evict_stale(Duration::seconds(config.history_ttl_secs as i64), now);
+        if evicted > 0 {
+            debug!("Evicted sentiment history for {} idle markets", evicted);
+        }
+        let previous_score = history.record(market.id, now, sentiment.clone());
 
         // Check threshold
-        if sentiment.magnitude < self.config.sentiment_threshold {
+        if sentiment.magnitude < config.sentiment_threshold {
             debug!("Sentiment magnitude {} below threshold {} for market {}",
-                   sentiment.magnitude, self.config.sentiment_threshold, market.id);
-            *self.status.write().await = AgentStatus::Idle;
+                   sentiment.magnitude, config.sentiment_threshold, market.id);
             return Ok(None);
         }
 
+        // Skip signals that repeat the last one
+        if config.min_score_change > 0.0 {
+            if let Some(last) = history.last_emitted(market.id) {
+                let change = (sentiment.score - last.score).abs();
+                if change <= config.min_score_change {
+                    debug!("Sentiment for market {} moved {:.3} since the last signal, under {}",
+                           market.id, change, config.min_score_change);
+                    return Ok(None);
+                }
+            }
+        }
+
+        let momentum = history.momentum(market.id, Duration::seconds(config.momentum_window_secs as i64), now);
+        let deltas = config.delta_windows_secs.iter()
+            .map(|&window_secs| SentimentDelta {
+                window_secs,
+                change: history.delta(market.id, Duration::seconds(window_secs as i64), now),
+            })
+            .collect();
+        history.mark_emitted(market.id, sentiment.clone());
+        drop(history);
+
         // Extract themes
         let top_themes = self.extract_themes(&relevant_articles, 5);
 
@@ -366,20 +536,21 @@ impl Agent for SentimentAgent {
             market_category: market.category.clone(),
             sentiment: sentiment.clone(),
             top_themes,
-            timestamp: Utc::now(),
+            timestamp: now,
             sources: relevant_articles.iter()
                 .take(10)
                 .map(|a| a.id.clone())
                 .collect(),
+            momentum,
+            previous_score,
+            deltas,
         };
 
         // Cache sentiment
         self.sentiment_cache.write().await.insert(market.id, sentiment.clone());
 
-        *self.status.write().await = AgentStatus::Idle;
-
         Ok(Some(AgentOutput {
-            agent_id: self.config.base.agent_id.clone(),
+            agent_id: self.base.agent_id.clone(),
             market_id: market.id,
             signal_type: "sentiment".to_string(),
             data: serde_json::to_value(signal)?,
@@ -390,42 +561,18 @@ impl Agent for SentimentAgent {
     }
 
     async fn handle_control(&self, msg: ControlMessage) -> Result<ControlResponse> {
-        match msg {
-            ControlMessage::Pause => {
-                *self.status.write().await = AgentStatus::Paused;
-                Ok(ControlResponse::Ok)
-            }
-            ControlMessage::Resume => {
-                *self.status.write().await = AgentStatus::Idle;
-                Ok(ControlResponse::Ok)
-            }
-            ControlMessage::HealthCheck => {
-                let uptime = self.start_time.elapsed().as_secs();
-                Ok(ControlResponse::HealthCheck {
-                    status: *self.status.read().await,
-                    uptime_secs: uptime,
-                })
-            }
-            ControlMessage::Shutdown => {
-                *self.status.write().await = AgentStatus::Idle;
-                Ok(ControlResponse::Ok)
-            }
-            ControlMessage::UpdateConfig(_config) => {
-                warn!("Sentiment agent config updates not implemented yet");
-                Ok(ControlResponse::Ok)
-            }
-        }
+        Ok(handle_common_control(msg, &self.status, &self.config, self.start_time))
     }
 
     async fn on_start(&self) -> Result<()> {
         info!("Sentiment agent starting");
-        *self.status.write().await = AgentStatus::Idle;
+        self.status.set(AgentStatus::Idle);
         Ok(())
     }
 
     async fn on_stop(&self) -> Result<()> {
         info!("Sentiment agent stopping");
-        *self.status.write().await = AgentStatus::Idle;
+        self.status.set(AgentStatus::Idle);
         Ok(())
     }
 }
@@ -433,6 +580,7 @@ impl Agent for SentimentAgent {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sentiment_backend::BackendScore;
 
     #[tokio::test]
     async fn test_sentiment_agent_creation() {
@@ -445,11 +593,112 @@ mod tests {
     fn test_calculate_sentiment_empty() {
         let config = SentimentAgentConfig::default();
         let agent = SentimentAgent::new(config);
-        let sentiment = agent.calculate_sentiment(&[]);
+        let sentiment = agent.calculate_sentiment(&agent.sentiment_config(), &[], Utc::now());
         assert_eq!(sentiment.article_count, 0);
         assert_eq!(sentiment.confidence, 0.0);
     }
 
+    /// Stands in for a transformer: the same reading of every batch
+    struct FixedBackend(Option<BackendScore>);
+
+    impl SentimentBackend for FixedBackend {
+        fn name(&self) -> &'static str {
+            "fixed"
+        }
+
+        fn score(&self, _articles: &[NewsArticle], _weights: &[f64]) -> Result<BackendScore> {
+            self.0.ok_or_else(|| anyhow::anyhow!("model unavailable"))
+        }
+    }
+
+    #[test]
+    fn test_keyword_backend_blends_with_tone() {
+        let agent = SentimentAgent::new(SentimentAgentConfig::default());
+        let config = agent.sentiment_config();
+        let mut article = mild_article("1");
+        article.title = "Peace deal reached".to_string();
+
+        // Tone 10 reads as 0.1, the lexicon as fully positive
+        let sentiment = agent.calculate_sentiment(&config, &[article], Utc::now());
+        assert!((sentiment.score - (0.1 * 0.4 + 0.6)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_backend_output_blends_with_tone() {
+        let config = SentimentAgentConfig::default();
+        let articles = vec![mild_article("1"), mild_article("2")];
+
+        let sure = SentimentAgent::new(config.clone())
+            .with_backend(Arc::new(FixedBackend(Some(BackendScore { sentiment: -0.5, confidence: 1.0 }))));
+        let sentiment = sure.calculate_sentiment(&config, &articles, Utc::now());
+        assert!((sentiment.score - (0.1 * 0.4 - 0.5 * 0.6)).abs() < 1e-9);
+
+        // Half as sure, half as much pull against the tone
+        let unsure = SentimentAgent::new(config.clone())
+            .with_backend(Arc::new(FixedBackend(Some(BackendScore { sentiment: -0.5, confidence: 0.5 }))));
+        let sentiment = unsure.calculate_sentiment(&config, &articles, Utc::now());
+        assert!((sentiment.score - (0.1 * 0.4 - 0.25 * 0.6)).abs() < 1e-9);
+
+        // A failing backend falls back to the lexicon, which finds nothing here
+        let failing = SentimentAgent::new(config.clone()).with_backend(Arc::new(FixedBackend(None)));
+        let sentiment = failing.calculate_sentiment(&config, &articles, Utc::now());
+        assert!((sentiment.score - 0.1 * 0.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fresh_negative_article_outweighs_stale_positive_ones() {
+        let agent = SentimentAgent::new(SentimentAgentConfig::default());
+        let config = agent.sentiment_config();
+        let now = Utc::now();
+
+        let mut articles: Vec<NewsArticle> = (0..4)
+            .map(|i| NewsArticle {
+                title: "Peace deal brings recovery".to_string(),
+                tone: 60.0,
+                timestamp: now - Duration::days(3),
+                ..mild_article(&i.to_string())
+            })
+            .collect();
+        articles.push(NewsArticle {
+            title: "Talks collapse as markets crash".to_string(),
+            tone: -60.0,
+            timestamp: now - Duration::minutes(5),
+            ..mild_article("fresh")
+        });
+
+        // Six half-lives old, the four positive articles count 1/16 as much together
+        let sentiment = agent.calculate_sentiment(&config, &articles, now);
+        assert!(sentiment.score < -0.5, "score {}", sentiment.score);
+
+        // Weighted equally they win
+        let undecayed = SentimentAgentConfig { decay_half_life_secs: 0, ..(*config).clone() };
+        assert!(agent.calculate_sentiment(&undecayed, &articles, now).score > 0.3);
+    }
+
+    #[test]
+    fn test_recency_weight_halves_each_half_life() {
+        let config = SentimentAgentConfig::default();
+        let now = Utc::now();
+        assert_eq!(config.recency_weight(now, now), 1.0);
+        assert!((config.recency_weight(now - Duration::hours(12), now) - 0.5).abs() < 1e-12);
+        assert!((config.recency_weight(now - Duration::hours(36), now) - 0.125).abs() < 1e-12);
+        assert_eq!(config.recency_weight(now + Duration::hours(1), now), 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_add_articles_prunes_old_articles() {
+        let agent = SentimentAgent::new(SentimentAgentConfig::default());
+        let old = |id: &str, days: i64| NewsArticle { timestamp: Utc::now() - Duration::days(days), ..mild_article(id) };
+
+        agent.add_articles(vec![mild_article("1"), old("2", 2), old("3", 4)]).await;
+        assert_eq!(agent.article_count().await, 2);
+
+        // Stored articles that have since aged out go too
+        agent.articles.write().await[1].timestamp = Utc::now() - Duration::days(5);
+        agent.add_articles(vec![mild_article("4")]).await;
+        assert_eq!(agent.article_count().await, 2);
+    }
+
     #[test]
     fn test_extract_themes() {
         let config = SentimentAgentConfig::default();
@@ -463,10 +712,255 @@ mod tests {
                 themes: "ELECTION;POLITICS;USA".to_string(),
                 tone: 50.0,
                 timestamp: Utc::now(),
+                source: "example.com".to_string(),
             },
         ];
 
         let themes = agent.extract_themes(&articles, 5);
         assert!(!themes.is_empty());
     }
+
+    fn politics_market() -> Arc<Market> {
+        Arc::new(Market {
+            id: MarketId::random(),
+            condition_id: "0xelection".into(),
+            question: "Will turnout exceed 60%?".to_string(),
+            description: String::new(),
+            category: "Politics".to_string(),
+            outcomes: vec![],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            neg_risk_group: None,
+            tick_size: None,
+        })
+    }
+
+    fn mild_article(id: &str) -> NewsArticle {
+        NewsArticle {
+            id: id.to_string(),
+            url: "https://example.com".to_string(),
+            title: "Election turnout tally".to_string(),
+            themes: "ELECTION".to_string(),
+            tone: 10.0,
+            timestamp: Utc::now(),
+            source: "example.com".to_string(),
+        }
+    }
+
+    fn input(market: &Arc<Market>) -> AgentInput {
+        AgentInput {
+            market: market.clone(),
+            timestamp: Utc::now(),
+            additional_data: None,
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_status_on_current_thread_runtime() {
+        let agent = SentimentAgent::new(SentimentAgentConfig::default());
+        assert_eq!(agent.status(), AgentStatus::Idle);
+
+        agent.handle_control(ControlMessage::Pause).await.unwrap();
+        assert_eq!(agent.status(), AgentStatus::Paused);
+
+        match agent.handle_control(ControlMessage::HealthCheck).await.unwrap() {
+            ControlResponse::HealthCheck { status, .. } => assert_eq!(status, AgentStatus::Paused),
+            other => panic!("Expected HealthCheck, got {:?}", other),
+        }
+
+        // Transitions inside process_market land in the snapshot too
+        agent.handle_control(ControlMessage::Resume).await.unwrap();
+        agent.add_articles(vec![mild_article("1")]).await;
+        agent.process_market(input(&politics_market())).await.unwrap();
+        assert_eq!(agent.status(), AgentStatus::Idle);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_pause_during_processing_sticks() {
+        let agent = Arc::new(SentimentAgent::new(SentimentAgentConfig::default()));
+        agent.add_articles(vec![mild_article("1")]).await;
+
+        // Hold the history lock so processing stalls midway
+        let history = agent.history.write().await;
+        let task = tokio::spawn({
+            let agent = agent.clone();
+            async move { agent.process_market(input(&politics_market())).await }
+        });
+        while agent.status() != AgentStatus::Processing {
+            tokio::task::yield_now().await;
+        }
+
+        agent.handle_control(ControlMessage::Pause).await.unwrap();
+        drop(history);
+        task.await.unwrap().unwrap();
+        assert_eq!(agent.status(), AgentStatus::Paused);
+    }
+
+    #[tokio::test]
+    async fn test_lowered_threshold_lets_signal_through() {
+        let agent = SentimentAgent::new(SentimentAgentConfig::default());
+        agent.add_articles((0..3).map(|i| mild_article(&i.to_string())).collect()).await;
+        let market = politics_market();
+
+        // Tone 10 gives a magnitude of 0.04, under the default 0.2
+        assert!(agent.process_market(input(&market)).await.unwrap().is_none());
+
+        let response = agent
+            .handle_control(ControlMessage::UpdateConfig(serde_json::json!({ "sentiment_threshold": 0.03 })))
+            .await
+            .unwrap();
+        match response {
+            ControlResponse::ConfigUpdated { applied, errors } => {
+                assert!(applied);
+                assert!(errors.is_empty());
+            }
+            other => panic!("Expected ConfigUpdated, got {:?}", other),
+        }
+
+        let output = agent.process_market(input(&market)).await.unwrap().unwrap();
+        assert_eq!(output.market_id, market.id);
+        assert_eq!(agent.sentiment_config().min_articles_threshold, 3);
+    }
+
+    #[tokio::test]
+    async fn test_raised_threshold_holds_back_passing_market() {
+        let agent = SentimentAgent::new(SentimentAgentConfig::default());
+        let articles = (0..3).map(|i| NewsArticle { tone: 60.0, ..mild_article(&i.to_string()) }).collect();
+        agent.add_articles(articles).await;
+        let market = politics_market();
+        assert!(agent.process_market(input(&market)).await.unwrap().is_some());
+
+        let patch = serde_json::json!({ "sentiment_threshold": 0.9 });
+        let response = agent.handle_control(ControlMessage::UpdateConfig(patch)).await.unwrap();
+        assert!(matches!(response, ControlResponse::ConfigUpdated { applied: true, .. }));
+        assert!(agent.process_market(input(&politics_market())).await.unwrap().is_none());
+
+        // Keywords apply on the next call too: none of these match the articles
+        let patch = serde_json::json!({
+            "sentiment_threshold": 0.0,
+            "category_keywords": { "Politics": ["filibuster"] },
+        });
+        agent.handle_control(ControlMessage::UpdateConfig(patch)).await.unwrap();
+        assert!(agent.process_market(input(&politics_market())).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_partial_and_invalid_config_updates() {
+        let agent = SentimentAgent::new(SentimentAgentConfig::default());
+        let snapshot = agent.sentiment_config();
+
+        // Only Politics changes; keywords are normalised to lowercase
+        let patch = serde_json::json!({ "category_keywords": { "Politics": ["Nominee ", "primary"], "Climate": null } });
+        let response = agent.handle_control(ControlMessage::UpdateConfig(patch)).await.unwrap();
+        assert!(matches!(response, ControlResponse::ConfigUpdated { applied: true, .. }));
+
+        let config = agent.sentiment_config();
+        assert_eq!(config.category_keywords["Politics"], vec!["nominee", "primary"]);
+        assert!(!config.category_keywords.contains_key("Climate"));
+        assert_eq!(config.category_keywords["Economics"], snapshot.category_keywords["Economics"]);
+        assert_eq!(config.sentiment_threshold, snapshot.sentiment_threshold);
+        // Snapshots taken earlier are unaffected
+        assert!(snapshot.category_keywords.contains_key("Climate"));
+
+        // Every problem is reported and nothing is applied
+        let patch = serde_json::json!({ "sentiment_threshold": 1.5, "theme_weight": 0.9 });
+        match agent.handle_control(ControlMessage::UpdateConfig(patch)).await.unwrap() {
+            ControlResponse::ConfigUpdated { applied, errors } => {
+                assert!(!applied);
+                assert_eq!(errors.len(), 2);
+            }
+            other => panic!("Expected ConfigUpdated, got {:?}", other),
+        }
+        assert_eq!(agent.sentiment_config().sentiment_threshold, snapshot.sentiment_threshold);
+
+        for patch in [
+            serde_json::json!({ "sentiment_threshold": "high" }),
+            serde_json::json!({ "base": { "agent_id": "other" } }),
+            serde_json::json!([1, 2]),
+        ] {
+            let response = agent.handle_control(ControlMessage::UpdateConfig(patch)).await.unwrap();
+            assert!(matches!(response, ControlResponse::ConfigUpdated { applied: false, .. }));
+        }
+        assert_eq!(agent.config().agent_id, "sentiment-agent");
+    }
+
+    #[tokio::test]
+    async fn test_flat_sentiment_is_suppressed_until_it_moves() {
+        let config = SentimentAgentConfig {
+            sentiment_threshold: 0.03,
+            min_score_change: 0.05,
+            ..SentimentAgentConfig::default()
+        };
+        let agent = SentimentAgent::new(config);
+        agent.add_articles((0..3).map(|i| mild_article(&i.to_string())).collect()).await;
+        let market = politics_market();
+
+        let first = agent.process_market(input(&market)).await.unwrap().unwrap();
+        let first: SentimentSignal = serde_json::from_value(first.data).unwrap();
+        assert!(first.previous_score.is_none());
+        assert_eq!(first.deltas.len(), 3);
+
+        // Same articles, same score: nothing new to say
+        for _ in 0..3 {
+            assert!(agent.process_market(input(&market)).await.unwrap().is_none());
+        }
+        assert_eq!(agent.history().read().await.samples(market.id).count(), 4);
+
+        agent.add_articles((3..6).map(|i| NewsArticle { tone: 60.0, ..mild_article(&i.to_string()) }).collect()).await;
+        let moved = agent.process_market(input(&market)).await.unwrap().unwrap();
+        let moved: SentimentSignal = serde_json::from_value(moved.data).unwrap();
+        let previous = moved.previous_score.unwrap();
+        assert!((previous.score - first.sentiment.score).abs() < 1e-9);
+        assert!(moved.sentiment.score > previous.score + 0.05);
+        assert!(moved.momentum > 0.0);
+        assert!(moved.deltas.iter().all(|d| d.change.unwrap() > 0.05));
+    }
+
+    #[tokio::test]
+    async fn test_semantic_matching_drops_incidental_keyword_hits() {
+        use crate::embedding::HashEmbedder;
+
+        let fixture = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/semantic_articles.jsonl");
+        let articles: Vec<NewsArticle> = std::fs::read_to_string(fixture)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let market = Arc::new(Market {
+            question: "Will the Federal Reserve cut interest rates in March?".to_string(),
+            category: "Economics".to_string(),
+            ..(*politics_market()).clone()
+        });
+        async fn sources(agent: SentimentAgent, articles: &[NewsArticle], market: &Arc<Market>) -> Vec<String> {
+            agent.add_articles(articles.to_vec()).await;
+            let output = agent.process_market(input(market)).await.unwrap().unwrap();
+            serde_json::from_value::<SentimentSignal>(output.data).unwrap().sources
+        }
+        let config = |matching: MatchingMode, semantic_min_similarity: f64| SentimentAgentConfig {
+            min_articles_threshold: 1,
+            sentiment_threshold: 0.0,
+            matching,
+            semantic_min_similarity,
+            semantic_weight: 0.5,
+            // The fixture articles are from March 2026
+            max_article_age_secs: 0,
+            ..SentimentAgentConfig::default()
+        };
+        let store = || Arc::new(VectorStore::new(Arc::new(HashEmbedder::default())));
+
+        // "federal reserve" is in the question, so every article matches by keyword
+        let keyword = SentimentAgent::new(config(MatchingMode::Keyword, 0.25)).with_vector_store(store());
+        assert_eq!(sources(keyword, &articles, &market).await.len(), articles.len());
+
+        let semantic = SentimentAgent::new(config(MatchingMode::Semantic, 0.25)).with_vector_store(store());
+        assert_eq!(sources(semantic, &articles, &market).await, ["fed-rate-cut"]);
+
+        // Half keyword hit, half similarity: only the related article clears 0.6
+        let blended = SentimentAgent::new(config(MatchingMode::Blended, 0.6)).with_vector_store(store());
+        assert_eq!(sources(blended, &articles, &market).await, ["fed-rate-cut"]);
+
+        // Without a store, semantic matching falls back to keywords
+        let fallback = SentimentAgent::new(config(MatchingMode::Semantic, 0.25));
+        assert_eq!(sources(fallback, &articles, &market).await.len(), articles.len());
+    }
 }
@@ -0,0 +1,276 @@
+//! Article sentiment scoring for the sentiment agent
+//!
+//! `KeywordBackend` counts lexicon hits in titles and themes and needs no
+//! model; it is the default. `TransformerBackend` asks a polarity classifier
+//! about each article's text; with the `full-nlp` feature `RustBertClassifier`
+//! provides one backed by a fine-tuned DistilBERT from rust-bert.
+
+use anyhow::{anyhow, Result};
+use common::NewsArticle;
+
+/// Sentiment of a set of articles as judged by one backend
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BackendScore {
+    /// -1.0 (very negative) to 1.0 (very positive)
+    pub sentiment: f64,
+    /// 0.0 (no idea) to 1.0 (certain)
+    pub confidence: f64,
+}
+
+impl BackendScore {
+    pub const NEUTRAL: BackendScore = BackendScore { sentiment: 0.0, confidence: 0.0 };
+}
+
+/// Scores the text of news articles
+pub trait SentimentBackend: Send + Sync {
+    /// Short name for logs, e.g. "keyword"
+    fn name(&self) -> &'static str;
+
+    /// One score for all of `articles` together, each counting in proportion
+    /// to its entry in `weights` (e.g. for recency); equal weights give the
+    /// unweighted score
+    fn score(&self, articles: &[NewsArticle], weights: &[f64]) -> Result<BackendScore>;
+}
+
+const NEGATIVE_WORDS: &[&str] = &[
+    "crisis", "crash", "drop", "fall", "decline", "decrease",
+    "loss", "fail", "bad", "negative", "worst", "downward",
+    "bearish", "sell", "dump", "collapse", "risk", "danger",
+    "threat", "attack", "war", "conflict", "inflation", "recession",
+];
+
+const POSITIVE_WORDS: &[&str] = &[
+    "growth", "rise", "increase", "gain", "profit", "success",
+    "good", "positive", "best", "upward", "bullish", "buy",
+    "recovery", "boom", "breakthrough", "win", "victory",
+    "peace", "agreement", "deal", "lower", "cut", "reduce",
+];
+
+/// Weighted share of positive minus negative lexicon hits in titles and themes
+///
+/// Always fully confident: the lexicon has no notion of doubt, and reporting
+/// 1.0 keeps the agent's blend what it was before backends were pluggable.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KeywordBackend;
+
+impl SentimentBackend for KeywordBackend {
+    fn name(&self) -> &'static str {
+        "keyword"
+    }
+
+    fn score(&self, articles: &[NewsArticle], weights: &[f64]) -> Result<BackendScore> {
+        let mut total_score: f64 = 0.0;
+        let mut total_words = 0.0;
+
+        for (article, weight) in articles.iter().zip(weights) {
+            let text = format!("{} {}", article.title, article.themes).to_lowercase();
+
+            for word in NEGATIVE_WORDS {
+                if text.contains(word) {
+                    total_score -= weight;
+                    total_words += weight;
+                }
+            }
+
+            for word in POSITIVE_WORDS {
+                if text.contains(word) {
+                    total_score += weight;
+                    total_words += weight;
+                }
+            }
+        }
+
+        let sentiment = if total_words > 0.0 {
+            (total_score / total_words).clamp(-1.0, 1.0)
+        } else {
+            0.0
+        };
+        Ok(BackendScore { sentiment, confidence: 1.0 })
+    }
+}
+
+/// Two-class text classifier, e.g. a model fine-tuned on SST-2
+pub trait PolarityClassifier: Send + Sync {
+    /// Probability that each text is positive, in order
+    fn positive_probability(&self, texts: &[String]) -> Result<Vec<f64>>;
+}
+
+/// Scores each article's title and themes with a classifier
+///
+/// An article classified positive with probability `p` scores `2p - 1`; the
+/// backend reports the weighted mean of those as the sentiment and the
+/// weighted mean of their absolute values as the confidence, so a batch the model is unsure about,
+/// or one split evenly between very positive and very negative articles,
+/// counts for little against the tone.
+pub struct TransformerBackend<C> {
+    classifier: C,
+    /// Longest text passed to the classifier, in characters
+    max_chars: usize,
+}
+
+impl<C: PolarityClassifier> TransformerBackend<C> {
+    pub fn new(classifier: C) -> Self {
+        Self { classifier, max_chars: 512 }
+    }
+
+    /// Truncate article text to `max_chars` before classifying it
+    pub fn with_max_chars(mut self, max_chars: usize) -> Self {
+        self.max_chars = max_chars.max(1);
+        self
+    }
+
+    fn article_text(&self, article: &NewsArticle) -> String {
+        let themes = article.themes.replace([';', '_'], " ").to_lowercase();
+        format!("{}. {}", article.title, themes).chars().take(self.max_chars).collect()
+    }
+}
+
+impl<C: PolarityClassifier> SentimentBackend for TransformerBackend<C> {
+    fn name(&self) -> &'static str {
+        "transformer"
+    }
+
+    fn score(&self, articles: &[NewsArticle], weights: &[f64]) -> Result<BackendScore> {
+        let total_weight: f64 = weights.iter().take(articles.len()).sum();
+        if articles.is_empty() || total_weight <= 0.0 {
+            return Ok(BackendScore::NEUTRAL);
+        }
+
+        let texts: Vec<String> = articles.iter().map(|a| self.article_text(a)).collect();
+        let probabilities = self.classifier.positive_probability(&texts)?;
+        if probabilities.len() != texts.len() {
+            return Err(anyhow!(
+                "classifier returned {} scores for {} articles",
+                probabilities.len(),
+                texts.len()
+            ));
+        }
+
+        let polarities: Vec<(f64, f64)> = probabilities
+            .iter()
+            .zip(weights)
+            .map(|(p, weight)| ((2.0 * p.clamp(0.0, 1.0)) - 1.0, *weight))
+            .collect();
+        Ok(BackendScore {
+            sentiment: polarities.iter().map(|(s, w)| s * w).sum::<f64>() / total_weight,
+            confidence: polarities.iter().map(|(s, w)| s.abs() * w).sum::<f64>() / total_weight,
+        })
+    }
+}
+
+/// rust-bert's default sentiment pipeline (DistilBERT fine-tuned on SST-2)
+///
+/// Downloads the model on first use and needs libtorch at build time.
+#[cfg(feature = "full-nlp")]
+pub struct RustBertClassifier {
+    /// The model is not `Sync`; predictions are serialised
+    model: std::sync::Mutex<rust_bert::pipelines::sentiment::SentimentModel>,
+}
+
+#[cfg(feature = "full-nlp")]
+impl RustBertClassifier {
+    pub fn new() -> Result<Self> {
+        use rust_bert::pipelines::sentiment::{SentimentConfig, SentimentModel};
+
+        let model = SentimentModel::new(SentimentConfig::default())?;
+        Ok(Self { model: std::sync::Mutex::new(model) })
+    }
+}
+
+#[cfg(feature = "full-nlp")]
+impl PolarityClassifier for RustBertClassifier {
+    fn positive_probability(&self, texts: &[String]) -> Result<Vec<f64>> {
+        use rust_bert::pipelines::sentiment::SentimentPolarity;
+
+        let inputs: Vec<&str> = texts.iter().map(String::as_str).collect();
+        let model = self.model.lock().map_err(|_| anyhow!("sentiment model lock poisoned"))?;
+        Ok(model
+            .predict(&inputs)
+            .into_iter()
+            .map(|prediction| match prediction.polarity {
+                SentimentPolarity::Positive => prediction.score,
+                SentimentPolarity::Negative => 1.0 - prediction.score,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn article(title: &str, themes: &str) -> NewsArticle {
+        NewsArticle {
+            id: title.to_string(),
+            url: "https://example.com".to_string(),
+            title: title.to_string(),
+            themes: themes.to_string(),
+            tone: 0.0,
+            timestamp: Utc::now(),
+            source: "example.com".to_string(),
+        }
+    }
+
+    /// Answers from a fixed table of title prefix to probability
+    struct FixedClassifier(Vec<(&'static str, f64)>);
+
+    impl PolarityClassifier for FixedClassifier {
+        fn positive_probability(&self, texts: &[String]) -> Result<Vec<f64>> {
+            Ok(texts
+                .iter()
+                .map(|text| self.0.iter().find(|(prefix, _)| text.starts_with(prefix)).map_or(0.5, |(_, p)| *p))
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_keyword_backend_counts_lexicon_hits() {
+        let articles = vec![
+            article("Peace deal signed", "AGREEMENT"),
+            article("Markets crash on war fears", "CONFLICT"),
+        ];
+        // peace, deal, agreement against crash, war, conflict
+        let score = KeywordBackend.score(&articles, &[1.0, 1.0]).unwrap();
+        assert_eq!(score, BackendScore { sentiment: 0.0, confidence: 1.0 });
+
+        let score = KeywordBackend.score(&articles[..1], &[1.0]).unwrap();
+        assert_eq!(score.sentiment, 1.0);
+        assert_eq!(KeywordBackend.score(&[article("Turnout tally", "ELECTION")], &[1.0]).unwrap().sentiment, 0.0);
+
+        // Hits count by their article's weight
+        let score = KeywordBackend.score(&articles, &[1.0, 0.5]).unwrap();
+        assert!((score.sentiment - 1.5 / 4.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_transformer_backend_averages_polarity() {
+        let backend = TransformerBackend::new(FixedClassifier(vec![("Upbeat", 0.9), ("Grim", 0.2)]));
+        let articles = vec![article("Upbeat outlook", ""), article("Grim outlook", "")];
+
+        // Polarities 0.8 and -0.6
+        let score = backend.score(&articles, &[1.0, 1.0]).unwrap();
+        assert!((score.sentiment - 0.1).abs() < 1e-9);
+        assert!((score.confidence - 0.7).abs() < 1e-9);
+
+        let score = backend.score(&articles, &[3.0, 1.0]).unwrap();
+        assert!((score.sentiment - 0.45).abs() < 1e-9);
+        assert!((score.confidence - 0.75).abs() < 1e-9);
+
+        let unsure = backend.score(&[article("Turnout tally", "")], &[1.0]).unwrap();
+        assert_eq!(unsure, BackendScore { sentiment: 0.0, confidence: 0.0 });
+        assert_eq!(backend.score(&[], &[]).unwrap(), BackendScore::NEUTRAL);
+    }
+
+    #[test]
+    fn test_transformer_backend_rejects_short_answers() {
+        struct Silent;
+        impl PolarityClassifier for Silent {
+            fn positive_probability(&self, _texts: &[String]) -> Result<Vec<f64>> {
+                Ok(vec![])
+            }
+        }
+
+        assert!(TransformerBackend::new(Silent).score(&[article("Upbeat outlook", "")], &[1.0]).is_err());
+    }
+}
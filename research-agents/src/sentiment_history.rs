@@ -0,0 +1,195 @@
+//! Sentiment History - per-market record of sentiment scores over time
+//!
+//! Keeps the most recent scores for each market in a ring buffer so the
+//! sentiment agent can tell whether sentiment is improving or deteriorating,
+//! not just where it stands.
+
+use crate::sentiment::SentimentScore;
+use chrono::{DateTime, Duration, Utc};
+use common::MarketId;
+use std::collections::{HashMap, VecDeque};
+
+/// Scores recorded for one market
+#[derive(Debug, Clone, Default)]
+struct MarketHistory {
+    samples: VecDeque<(DateTime<Utc>, SentimentScore)>,
+    /// Score of the last signal emitted for the market
+    last_emitted: Option<SentimentScore>,
+}
+
+impl MarketHistory {
+    fn last_seen(&self) -> Option<DateTime<Utc>> {
+        self.samples.back().map(|(at, _)| *at)
+    }
+
+    /// Samples no older than `window` before `now`
+    fn window(&self, window: Duration, now: DateTime<Utc>) -> impl Iterator<Item = &(DateTime<Utc>, SentimentScore)> {
+        let since = now - window;
+        self.samples.iter().filter(move |(at, _)| *at >= since && *at <= now)
+    }
+}
+
+/// Ring buffer of timestamped sentiment scores per market
+#[derive(Debug, Clone)]
+pub struct SentimentHistory {
+    capacity: usize,
+    markets: HashMap<MarketId, MarketHistory>,
+}
+
+impl SentimentHistory {
+    /// Keep up to `capacity` scores per market
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            markets: HashMap::new(),
+        }
+    }
+
+    /// Change the per-market capacity; longer buffers are trimmed on their next record
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+    }
+
+    /// Record a score, returning the market's previous one
+    pub fn record(&mut self, market_id: MarketId, at: DateTime<Utc>, score: SentimentScore) -> Option<SentimentScore> {
+        let history = self.markets.entry(market_id).or_default();
+        let previous = history.samples.back().map(|(_, score)| score.clone());
+
+        history.samples.push_back((at, score));
+        while history.samples.len() > self.capacity {
+            history.samples.pop_front();
+        }
+
+        previous
+    }
+
+    /// Most recent score for a market
+    pub fn latest(&self, market_id: MarketId) -> Option<&SentimentScore> {
+        self.markets.get(&market_id)?.samples.back().map(|(_, score)| score)
+    }
+
+    /// Scores recorded for a market, oldest first
+    pub fn samples(&self, market_id: MarketId) -> impl Iterator<Item = &(DateTime<Utc>, SentimentScore)> {
+        self.markets.get(&market_id).into_iter().flat_map(|h| h.samples.iter())
+    }
+
+    /// Change in score over `window`: the latest score in the window minus the oldest.
+    ///
+    /// None if the window holds fewer than two scores.
+    pub fn delta(&self, market_id: MarketId, window: Duration, now: DateTime<Utc>) -> Option<f64> {
+        let history = self.markets.get(&market_id)?;
+        let mut samples = history.window(window, now);
+        let first = samples.next()?;
+        let last = samples.last()?;
+        Some(last.1.score - first.1.score)
+    }
+
+    /// Least-squares slope of score over `window`, in score per hour.
+    ///
+    /// 0.0 if the window holds fewer than two scores or they all share a timestamp.
+    pub fn momentum(&self, market_id: MarketId, window: Duration, now: DateTime<Utc>) -> f64 {
+        let Some(history) = self.markets.get(&market_id) else {
+            return 0.0;
+        };
+        let points: Vec<(f64, f64)> = history
+            .window(window, now)
+            .map(|(at, score)| ((*at - now).num_microseconds().unwrap_or_default() as f64 / 3_600_000_000.0, score.score))
+            .collect();
+        if points.len() < 2 {
+            return 0.0;
+        }
+
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let covariance: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+        let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+
+        if variance == 0.0 {
+            0.0
+        } else {
+            covariance / variance
+        }
+    }
+
+    /// Score of the last signal emitted for a market
+    pub fn last_emitted(&self, market_id: MarketId) -> Option<&SentimentScore> {
+        self.markets.get(&market_id)?.last_emitted.as_ref()
+    }
+
+    /// Remember that a signal was emitted with `score`
+    pub fn mark_emitted(&mut self, market_id: MarketId, score: SentimentScore) {
+        self.markets.entry(market_id).or_default().last_emitted = Some(score);
+    }
+
+    /// Drop markets with no score since `max_age` before `now`, returning how many
+    pub fn evict_stale(&mut self, max_age: Duration, now: DateTime<Utc>) -> usize {
+        let cutoff = now - max_age;
+        let before = self.markets.len();
+        self.markets
+            .retain(|_, history| history.last_seen().is_some_and(|at| at >= cutoff));
+        before - self.markets.len()
+    }
+
+    /// Number of markets tracked
+    pub fn len(&self) -> usize {
+        self.markets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.markets.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(score: f64) -> SentimentScore {
+        SentimentScore {
+            score,
+            magnitude: score.abs(),
+            confidence: 0.8,
+            article_count: 5,
+        }
+    }
+
+    #[test]
+    fn test_rising_sentiment_has_positive_momentum() {
+        let mut history = SentimentHistory::new(10);
+        let market = MarketId::random();
+        let now = Utc::now();
+
+        for (hours_ago, value) in [(5, 0.1), (4, 0.2), (3, 0.25), (2, 0.4), (1, 0.5), (0, 0.6)] {
+            history.record(market, now - Duration::hours(hours_ago), score(value));
+        }
+
+        let momentum = history.momentum(market, Duration::hours(6), now);
+        assert!((momentum - 0.1014).abs() < 1e-3, "momentum {}", momentum);
+        assert!((history.delta(market, Duration::hours(6), now).unwrap() - 0.5).abs() < 1e-9);
+        assert!((history.delta(market, Duration::hours(1), now).unwrap() - 0.1).abs() < 1e-9);
+        assert_eq!(history.momentum(market, Duration::minutes(30), now), 0.0);
+        assert!(history.delta(market, Duration::minutes(30), now).is_none());
+    }
+
+    #[test]
+    fn test_capacity_and_eviction() {
+        let mut history = SentimentHistory::new(3);
+        let stale = MarketId::random();
+        let fresh = MarketId::random();
+        let now = Utc::now();
+
+        for i in 0..5 {
+            let previous = history.record(fresh, now - Duration::minutes(5 - i), score(i as f64 / 10.0));
+            assert_eq!(previous.map(|s| s.score), (i > 0).then(|| (i - 1) as f64 / 10.0));
+        }
+        history.record(stale, now - Duration::hours(30), score(0.3));
+
+        let kept: Vec<f64> = history.samples(fresh).map(|(_, s)| s.score).collect();
+        assert_eq!(kept, vec![0.2, 0.3, 0.4]);
+
+        assert_eq!(history.evict_stale(Duration::hours(24), now), 1);
+        assert!(history.latest(stale).is_none());
+        assert_eq!(history.len(), 1);
+    }
+}
@@ -0,0 +1,568 @@
+//! Technical Analysis Agent - Reads direction from a market's own price history
+//!
+//! The other specialists look at the news; this one looks at the tape. It
+//! keeps the recent price ticks of each market's first outcome, computes a
+//! Wilder RSI and a fast/slow moving-average crossover on them, and combines
+//! the two into a directional bias: an overbought RSI leans bearish and an
+//! oversold one bullish, while the crossover says which way the trend runs.
+
+use super::agent::{
+    handle_common_control, Agent, AgentConfig, AgentInput, AgentOutput, AgentStatus, AgentStatusCell,
+    ConfigCell, ControlMessage, ControlResponse, PatchableConfig,
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use common::{Market, MarketId, OutcomeId, PriceTick};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info};
+
+/// Key in `AgentInput::additional_data` holding a list of `PriceTick`s
+pub const PRICE_TICKS_KEY: &str = "price_ticks";
+
+/// Where the fast moving average stands against the slow one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrossoverState {
+    /// Fast average moved above the slow one on the latest tick
+    CrossedAbove,
+    /// Fast average moved below the slow one on the latest tick
+    CrossedBelow,
+    Above,
+    Below,
+}
+
+impl CrossoverState {
+    /// Trend vote in [-1, 1]; a fresh cross counts for more than a standing one
+    fn vote(self) -> f64 {
+        match self {
+            CrossoverState::CrossedAbove => 1.0,
+            CrossoverState::Above => 0.4,
+            CrossoverState::Below => -0.4,
+            CrossoverState::CrossedBelow => -1.0,
+        }
+    }
+}
+
+/// Which way the indicators lean
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Bias {
+    Bullish,
+    Bearish,
+    Neutral,
+}
+
+/// Technical analysis output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TechnicalSignal {
+    pub market_id: MarketId,
+    /// Outcome whose prices were analysed
+    pub outcome_id: OutcomeId,
+    /// Latest price
+    pub price: f64,
+    /// 0 to 100
+    pub rsi: f64,
+    pub fast_ma: f64,
+    pub slow_ma: f64,
+    pub crossover: CrossoverState,
+    pub bias: Bias,
+    /// 0.0 to 1.0
+    pub confidence: f64,
+    /// Ticks the indicators were computed from
+    pub ticks_used: usize,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Technical analysis agent configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TechnicalAgentConfig {
+    pub base: AgentConfig,
+    /// Price changes averaged into the RSI
+    pub rsi_period: usize,
+    /// RSI at or above which the market counts as overbought
+    pub overbought: f64,
+    /// RSI at or below which the market counts as oversold
+    pub oversold: f64,
+    /// Ticks in the fast moving average
+    pub fast_period: usize,
+    /// Ticks in the slow moving average
+    pub slow_period: usize,
+    /// Share of the bias taken from the RSI; the rest comes from the crossover
+    pub rsi_weight: f64,
+    /// Weakest combined bias worth reporting; a standing crossover with a
+    /// neutral RSI stays under the default
+    pub min_bias_strength: f64,
+    /// Ticks kept per market
+    pub max_history: usize,
+}
+
+impl Default for TechnicalAgentConfig {
+    fn default() -> Self {
+        Self {
+            base: AgentConfig {
+                agent_id: "technical-agent".to_string(),
+                name: "Technical Analysis Agent".to_string(),
+                enabled: true,
+                max_markets_per_batch: 100,
+                processing_interval_secs: 60,
+                max_concurrent_markets: 10,
+            },
+            rsi_period: 14,
+            overbought: 70.0,
+            oversold: 30.0,
+            fast_period: 5,
+            slow_period: 20,
+            rsi_weight: 0.7,
+            min_bias_strength: 0.2,
+            max_history: 500,
+        }
+    }
+}
+
+impl TechnicalAgentConfig {
+    /// Ticks needed before a market is analysed: enough for the RSI and for
+    /// the slow average on both the latest and the previous tick
+    pub fn required_history(&self) -> usize {
+        (self.rsi_period + 1).max(self.slow_period + 1)
+    }
+}
+
+impl PatchableConfig for TechnicalAgentConfig {
+    /// Check the tunables, returning every problem found
+    fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.rsi_period == 0 {
+            errors.push("rsi_period must be positive".to_string());
+        }
+        if !(0.0 < self.oversold && self.oversold < self.overbought && self.overbought < 100.0) {
+            errors.push(format!(
+                "need 0 < oversold < overbought < 100, got oversold {} and overbought {}",
+                self.oversold, self.overbought
+            ));
+        }
+        if self.fast_period == 0 || self.fast_period >= self.slow_period {
+            errors.push(format!(
+                "need 0 < fast_period < slow_period, got {} and {}",
+                self.fast_period, self.slow_period
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.rsi_weight) {
+            errors.push(format!("rsi_weight must be in [0, 1], got {}", self.rsi_weight));
+        }
+        if !(0.0..=1.0).contains(&self.min_bias_strength) {
+            errors.push(format!("min_bias_strength must be in [0, 1], got {}", self.min_bias_strength));
+        }
+        if self.max_history < self.required_history() {
+            errors.push(format!(
+                "max_history must hold at least {} ticks, got {}",
+                self.required_history(),
+                self.max_history
+            ));
+        }
+
+        errors
+    }
+}
+
+/// Wilder's relative strength index over `prices`, oldest first
+///
+/// Seeds the average gain and loss with the first `period` changes and
+/// smooths the rest in. `None` with fewer than `period + 1` prices.
+pub fn rsi(prices: &[f64], period: usize) -> Option<f64> {
+    if period == 0 || prices.len() < period + 1 {
+        return None;
+    }
+
+    let changes: Vec<f64> = prices.windows(2).map(|w| w[1] - w[0]).collect();
+    let (seed, rest) = changes.split_at(period);
+    let mut avg_gain = seed.iter().map(|c| c.max(0.0)).sum::<f64>() / period as f64;
+    let mut avg_loss = seed.iter().map(|c| (-c).max(0.0)).sum::<f64>() / period as f64;
+
+    for change in rest {
+        avg_gain = (avg_gain * (period - 1) as f64 + change.max(0.0)) / period as f64;
+        avg_loss = (avg_loss * (period - 1) as f64 + (-change).max(0.0)) / period as f64;
+    }
+
+    Some(match (avg_gain > 0.0, avg_loss > 0.0) {
+        (_, true) => 100.0 - 100.0 / (1.0 + avg_gain / avg_loss),
+        (true, false) => 100.0,
+        // Flat prices
+        (false, false) => 50.0,
+    })
+}
+
+/// Mean of the last `period` prices
+fn sma(prices: &[f64], period: usize) -> Option<f64> {
+    if period == 0 || prices.len() < period {
+        return None;
+    }
+    Some(prices[prices.len() - period..].iter().sum::<f64>() / period as f64)
+}
+
+/// Fast against slow average on the latest tick, compared with the tick before
+/// to tell a fresh cross from a standing one. Returns the state and both
+/// latest averages.
+pub fn crossover(prices: &[f64], fast_period: usize, slow_period: usize) -> Option<(CrossoverState, f64, f64)> {
+    if prices.len() < slow_period + 1 {
+        return None;
+    }
+    let fast = sma(prices, fast_period)?;
+    let slow = sma(prices, slow_period)?;
+    let previous = &prices[..prices.len() - 1];
+    let was_above = sma(previous, fast_period)? > sma(previous, slow_period)?;
+
+    let state = match (was_above, fast > slow) {
+        (false, true) => CrossoverState::CrossedAbove,
+        (true, false) => CrossoverState::CrossedBelow,
+        (true, true) => CrossoverState::Above,
+        (false, false) => CrossoverState::Below,
+    };
+    Some((state, fast, slow))
+}
+
+/// Price of one outcome by tick time
+type PriceHistory = BTreeMap<DateTime<Utc>, f64>;
+
+/// Technical Analysis Agent - RSI and moving-average crossovers on price ticks
+pub struct TechnicalAgent {
+    base: AgentConfig,
+    config: ConfigCell<TechnicalAgentConfig>,
+    status: AgentStatusCell,
+    history: Mutex<HashMap<MarketId, HashMap<OutcomeId, PriceHistory>>>,
+    start_time: std::time::Instant,
+}
+
+impl TechnicalAgent {
+    /// Create a new technical analysis agent
+    pub fn new(config: TechnicalAgentConfig) -> Self {
+        Self {
+            base: config.base.clone(),
+            config: ConfigCell::new("Technical agent", config),
+            status: AgentStatusCell::default(),
+            history: Mutex::new(HashMap::new()),
+            start_time: std::time::Instant::now(),
+        }
+    }
+
+    /// Add price ticks to the history; a tick at a time already held
+    /// replaces the earlier one
+    pub fn add_ticks(&self, ticks: Vec<PriceTick>) {
+        let max_history = self.technical_config().max_history;
+        let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        let count = ticks.len();
+
+        for tick in ticks {
+            let prices = history.entry(tick.market_id).or_default().entry(tick.outcome_id).or_default();
+            prices.insert(tick.timestamp, tick.price);
+            while prices.len() > max_history {
+                prices.pop_first();
+            }
+        }
+        debug!("Added {} price ticks", count);
+    }
+
+    /// Current config
+    pub fn technical_config(&self) -> Arc<TechnicalAgentConfig> {
+        self.config.get()
+    }
+
+    /// The outcome to analyse and its prices, oldest first: the market's
+    /// first outcome, or when the market lists none, whichever outcome
+    /// ticked last
+    fn price_history(&self, market: &Market) -> Option<(OutcomeId, Vec<f64>)> {
+        let history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        let outcomes = history.get(&market.id)?;
+
+        let (outcome_id, prices) = match market.outcomes.first() {
+            Some(outcome) => outcomes.get_key_value(&outcome.id)?,
+            None => outcomes.iter().max_by_key(|(_, prices)| prices.keys().next_back().copied())?,
+        };
+        Some((outcome_id.clone(), prices.values().copied().collect()))
+    }
+
+    /// Compute the indicators for a market, `None` while its history is too short
+    fn analyze(&self, config: &TechnicalAgentConfig, market: &Market, now: DateTime<Utc>) -> Option<TechnicalSignal> {
+        let (outcome_id, prices) = self.price_history(market)?;
+
+        if prices.len() < config.required_history() {
+            debug!("Market {} has {} of {} ticks, not analysing",
+                   market.id, prices.len(), config.required_history());
+            return None;
+        }
+
+        let rsi = rsi(&prices, config.rsi_period)?;
+        let (crossover, fast_ma, slow_ma) = crossover(&prices, config.fast_period, config.slow_period)?;
+
+        // Past a band the RSI votes half its weight at once, growing to all
+        // of it at the extreme
+        let rsi_vote = if rsi >= config.overbought {
+            -(0.5 + 0.5 * (rsi - config.overbought) / (100.0 - config.overbought))
+        } else if rsi <= config.oversold {
+            0.5 + 0.5 * (config.oversold - rsi) / config.oversold
+        } else {
+            0.0
+        };
+        let score = config.rsi_weight * rsi_vote + (1.0 - config.rsi_weight) * crossover.vote();
+
+        let bias = if score.abs() < config.min_bias_strength {
+            Bias::Neutral
+        } else if score > 0.0 {
+            Bias::Bullish
+        } else {
+            Bias::Bearish
+        };
+
+        Some(TechnicalSignal {
+            market_id: market.id,
+            outcome_id,
+            price: *prices.last()?,
+            rsi,
+            fast_ma,
+            slow_ma,
+            crossover,
+            bias,
+            confidence: score.abs().min(1.0),
+            ticks_used: prices.len(),
+            timestamp: now,
+        })
+    }
+}
+
+/// Ticks passed in `additional_data` under `PRICE_TICKS_KEY`, if any
+fn input_ticks(input: &AgentInput) -> Result<Vec<PriceTick>> {
+    match input.additional_data.as_ref().and_then(|data| data.get(PRICE_TICKS_KEY)) {
+        Some(ticks) => serde_json::from_value(ticks.clone())
+            .with_context(|| format!("additional_data.{} is not a list of price ticks", PRICE_TICKS_KEY)),
+        None => Ok(Vec::new()),
+    }
+}
+
+#[async_trait]
+impl Agent for TechnicalAgent {
+    fn config(&self) -> &AgentConfig {
+        &self.base
+    }
+
+    fn status(&self) -> AgentStatus {
+        self.status.get()
+    }
+
+    async fn process_market(&self, input: AgentInput) -> Result<Option<AgentOutput>> {
+        let start = std::time::Instant::now();
+        let config = self.technical_config();
+        let ticks = input_ticks(&input)?;
+        let market = input.market;
+
+        let _processing = self.status.processing();
+
+        // Only this market's ticks; a caller passing a shared list shouldn't
+        // file other markets' prices under this one
+        self.add_ticks(ticks.into_iter().filter(|t| t.market_id == market.id).collect());
+
+        let signal = match self.analyze(&config, &market, input.timestamp) {
+            Some(signal) if signal.bias != Bias::Neutral => signal,
+            _ => return Ok(None),
+        };
+
+        info!("Technical bias for market {}: {:?} (RSI {:.1}, {:?}, confidence {:.2})",
+              market.id, signal.bias, signal.rsi, signal.crossover, signal.confidence);
+
+        let confidence = signal.confidence;
+
+        Ok(Some(AgentOutput {
+            agent_id: self.base.agent_id.clone(),
+            market_id: market.id,
+            signal_type: "technical".to_string(),
+            data: serde_json::to_value(signal)?,
+            confidence,
+            timestamp: Utc::now(),
+            processing_time_ms: start.elapsed().as_millis() as u64,
+        }))
+    }
+
+    async fn handle_control(&self, msg: ControlMessage) -> Result<ControlResponse> {
+        Ok(handle_common_control(msg, &self.status, &self.config, self.start_time))
+    }
+
+    async fn on_start(&self) -> Result<()> {
+        info!("Technical agent starting");
+        self.status.set(AgentStatus::Idle);
+        Ok(())
+    }
+
+    async fn on_stop(&self) -> Result<()> {
+        info!("Technical agent stopping");
+        self.status.set(AgentStatus::Idle);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use common::Outcome;
+
+    fn binary_market() -> Arc<Market> {
+        Arc::new(Market {
+            id: MarketId::random(),
+            condition_id: "0xrate-cut".into(),
+            question: "Will the Fed cut rates in March?".to_string(),
+            description: String::new(),
+            category: "Economics".to_string(),
+            outcomes: vec![
+                Outcome { id: "YES".into(), name: "Yes".to_string(), price: 0.5, liquidity: 1000.0 },
+                Outcome { id: "NO".into(), name: "No".to_string(), price: 0.5, liquidity: 1000.0 },
+            ],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            neg_risk_group: None,
+            tick_size: None,
+        })
+    }
+
+    /// One YES tick a minute ending at `end`
+    fn ticks(market: &Market, prices: &[f64], end: DateTime<Utc>) -> Vec<PriceTick> {
+        prices
+            .iter()
+            .enumerate()
+            .map(|(i, price)| PriceTick {
+                market_id: market.id,
+                outcome_id: "YES".into(),
+                price: *price,
+                volume_24h: 5000.0,
+                liquidity: 1000.0,
+                timestamp: end - Duration::minutes((prices.len() - 1 - i) as i64),
+            })
+            .collect()
+    }
+
+    fn input(market: &Arc<Market>, ticks: Vec<PriceTick>, at: DateTime<Utc>) -> AgentInput {
+        AgentInput {
+            market: market.clone(),
+            timestamp: at,
+            additional_data: Some(serde_json::json!({ PRICE_TICKS_KEY: ticks })),
+        }
+    }
+
+    /// Climbs a cent a tick, giving back half a cent every fourth
+    fn rally(len: usize) -> Vec<f64> {
+        let mut price = 0.40;
+        (0..len)
+            .map(|i| {
+                price += if i % 4 == 3 { -0.005 } else { 0.01 };
+                price
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_indicators() {
+        assert_eq!(rsi(&[0.5; 20], 14), Some(50.0));
+        assert_eq!(rsi(&rally(10), 14), None);
+        // Every change up: no losses to weigh against
+        let rising: Vec<f64> = (0..20).map(|i| 0.3 + 0.01 * i as f64).collect();
+        assert_eq!(rsi(&rising, 14), Some(100.0));
+        // Equal gains and losses
+        let choppy: Vec<f64> = (0..21).map(|i| if i % 2 == 0 { 0.50 } else { 0.52 }).collect();
+        assert!((rsi(&choppy, 14).unwrap() - 50.0).abs() < 1.0);
+
+        // A long slide, then a jump that lifts the fast average over the slow one
+        let mut prices: Vec<f64> = (0..20).map(|i| 0.60 - 0.005 * i as f64).collect();
+        assert_eq!(crossover(&prices, 5, 20), None);
+        prices.push(0.50);
+        assert_eq!(crossover(&prices, 5, 20).unwrap().0, CrossoverState::Below);
+        prices.push(0.80);
+        let (state, fast, slow) = crossover(&prices, 5, 20).unwrap();
+        assert_eq!(state, CrossoverState::CrossedAbove);
+        assert!(fast > slow);
+        prices.push(0.81);
+        assert_eq!(crossover(&prices, 5, 20).unwrap().0, CrossoverState::Above);
+    }
+
+    #[tokio::test]
+    async fn test_overbought_rsi_is_bearish() {
+        let agent = TechnicalAgent::new(TechnicalAgentConfig::default());
+        let market = binary_market();
+        let now = Utc::now();
+
+        let output = agent
+            .process_market(input(&market, ticks(&market, &rally(40), now), now))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(output.signal_type, "technical");
+        assert_eq!(output.market_id, market.id);
+
+        let signal: TechnicalSignal = serde_json::from_value(output.data).unwrap();
+        assert_eq!(signal.outcome_id, "YES");
+        assert!(signal.rsi > 80.0, "rsi {}", signal.rsi);
+        // Overbought despite the uptrend the averages still show
+        assert_eq!(signal.crossover, CrossoverState::Above);
+        assert!(signal.fast_ma > signal.slow_ma);
+        assert_eq!(signal.bias, Bias::Bearish);
+        assert_eq!(signal.ticks_used, 40);
+        assert!(output.confidence > 0.2 && output.confidence < 1.0);
+        assert_eq!(output.confidence, signal.confidence);
+    }
+
+    #[tokio::test]
+    async fn test_insufficient_history_is_none() {
+        let agent = TechnicalAgent::new(TechnicalAgentConfig::default());
+        let market = binary_market();
+        let now = Utc::now();
+        let prices = rally(40);
+
+        // No ticks at all, and then fewer than the 21 needed
+        let empty = AgentInput { market: market.clone(), timestamp: now, additional_data: None };
+        assert!(agent.process_market(empty).await.unwrap().is_none());
+        let early = ticks(&market, &prices[..15], now - Duration::minutes(25));
+        assert!(agent.process_market(input(&market, early, now)).await.unwrap().is_none());
+        assert_eq!(agent.status(), AgentStatus::Idle);
+
+        // The history builds up across calls, so the rest of the rally is enough
+        let late = ticks(&market, &prices[15..], now);
+        let output = agent.process_market(input(&market, late, now)).await.unwrap().unwrap();
+        let signal: TechnicalSignal = serde_json::from_value(output.data).unwrap();
+        assert_eq!(signal.ticks_used, 40);
+
+        // Ticks for another market are not this one's history
+        let other = binary_market();
+        let foreign = ticks(&market, &prices, now);
+        assert!(agent.process_market(input(&other, foreign, now)).await.unwrap().is_none());
+
+        let malformed = AgentInput {
+            market: market.clone(),
+            timestamp: now,
+            additional_data: Some(serde_json::json!({ PRICE_TICKS_KEY: "not ticks" })),
+        };
+        assert!(agent.process_market(malformed).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_control_messages() {
+        let agent = TechnicalAgent::new(TechnicalAgentConfig::default());
+        assert_eq!(agent.config().agent_id, "technical-agent");
+
+        agent.handle_control(ControlMessage::Pause).await.unwrap();
+        match agent.handle_control(ControlMessage::HealthCheck).await.unwrap() {
+            ControlResponse::HealthCheck { status, .. } => assert_eq!(status, AgentStatus::Paused),
+            other => panic!("Expected HealthCheck, got {:?}", other),
+        }
+        agent.handle_control(ControlMessage::Resume).await.unwrap();
+        assert_eq!(agent.status(), AgentStatus::Idle);
+
+        let patch = serde_json::json!({ "overbought": 80.0 });
+        let response = agent.handle_control(ControlMessage::UpdateConfig(patch)).await.unwrap();
+        assert!(matches!(response, ControlResponse::ConfigUpdated { applied: true, .. }));
+        assert_eq!(agent.technical_config().overbought, 80.0);
+
+        let patch = serde_json::json!({ "fast_period": 30 });
+        let response = agent.handle_control(ControlMessage::UpdateConfig(patch)).await.unwrap();
+        assert!(matches!(response, ControlResponse::ConfigUpdated { applied: false, .. }));
+    }
+}
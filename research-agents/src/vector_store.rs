@@ -0,0 +1,411 @@
+//! Embeddings of news articles and market questions, for semantic matching
+//!
+//! `VectorStore` embeds through an `Embedder` and keeps the vectors in a
+//! `VectorBackend`: in memory by default, or in Postgres with pgvector behind
+//! the `pgvector` feature. Articles are embedded once per id and markets once
+//! per question text, in batches of the embedder's size, so repeated
+//! matching costs no further embedding calls.
+
+use crate::embedding::{cosine_similarity, Embedder};
+use crate::sentiment::NewsArticle;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use common::Market;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::debug;
+
+/// What a vector embeds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Collection {
+    Articles,
+    Markets,
+}
+
+impl Collection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Collection::Articles => "articles",
+            Collection::Markets => "markets",
+        }
+    }
+}
+
+/// Storage and nearest-neighbour search for vectors keyed by id
+#[async_trait]
+pub trait VectorBackend: Send + Sync {
+    async fn upsert(&self, collection: Collection, items: Vec<(String, Vec<f32>)>) -> Result<()>;
+
+    /// Vectors stored for whichever of `ids` have one
+    async fn get(&self, collection: Collection, ids: &[String]) -> Result<HashMap<String, Vec<f32>>>;
+
+    /// Up to `top_k` (id, cosine similarity) pairs, most similar first
+    async fn search(&self, collection: Collection, vector: &[f32], top_k: usize) -> Result<Vec<(String, f32)>>;
+}
+
+/// Brute-force search over vectors held in memory
+#[derive(Default)]
+pub struct MemoryBackend {
+    vectors: RwLock<HashMap<Collection, HashMap<String, Vec<f32>>>>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl VectorBackend for MemoryBackend {
+    async fn upsert(&self, collection: Collection, items: Vec<(String, Vec<f32>)>) -> Result<()> {
+        self.vectors.write().await.entry(collection).or_default().extend(items);
+        Ok(())
+    }
+
+    async fn get(&self, collection: Collection, ids: &[String]) -> Result<HashMap<String, Vec<f32>>> {
+        let vectors = self.vectors.read().await;
+        let Some(stored) = vectors.get(&collection) else {
+            return Ok(HashMap::new());
+        };
+        Ok(ids
+            .iter()
+            .filter_map(|id| Some((id.clone(), stored.get(id)?.clone())))
+            .collect())
+    }
+
+    async fn search(&self, collection: Collection, vector: &[f32], top_k: usize) -> Result<Vec<(String, f32)>> {
+        let vectors = self.vectors.read().await;
+        let mut scored: Vec<(String, f32)> = vectors
+            .get(&collection)
+            .map(|stored| {
+                stored
+                    .iter()
+                    .map(|(id, stored)| (id.clone(), cosine_similarity(vector, stored)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        scored.truncate(top_k);
+        Ok(scored)
+    }
+}
+
+/// Vectors in Postgres, searched with pgvector's cosine distance
+#[cfg(feature = "pgvector")]
+pub struct PgVectorBackend {
+    pool: sqlx::PgPool,
+}
+
+#[cfg(feature = "pgvector")]
+impl PgVectorBackend {
+    /// Create the extension and one table per collection, sized to `dimensions`
+    pub async fn new(pool: sqlx::PgPool, dimensions: usize) -> Result<Self> {
+        sqlx::query("CREATE EXTENSION IF NOT EXISTS vector").execute(&pool).await?;
+        for collection in [Collection::Articles, Collection::Markets] {
+            sqlx::query(&format!(
+                "CREATE TABLE IF NOT EXISTS {}_embeddings (id TEXT PRIMARY KEY, embedding vector({}) NOT NULL)",
+                collection.as_str(),
+                dimensions
+            ))
+            .execute(&pool)
+            .await?;
+        }
+        Ok(Self { pool })
+    }
+
+    /// pgvector's text form, `[x,y,...]`
+    fn literal(vector: &[f32]) -> String {
+        let values: Vec<String> = vector.iter().map(|x| x.to_string()).collect();
+        format!("[{}]", values.join(","))
+    }
+
+    fn parse(literal: &str) -> Result<Vec<f32>> {
+        literal
+            .trim_matches(|c| c == '[' || c == ']')
+            .split(',')
+            .filter(|value| !value.is_empty())
+            .map(|value| value.trim().parse::<f32>().map_err(|e| anyhow!("Bad vector value {}: {}", value, e)))
+            .collect()
+    }
+}
+
+#[cfg(feature = "pgvector")]
+#[async_trait]
+impl VectorBackend for PgVectorBackend {
+    async fn upsert(&self, collection: Collection, items: Vec<(String, Vec<f32>)>) -> Result<()> {
+        if items.is_empty() {
+            return Ok(());
+        }
+        let mut query = sqlx::QueryBuilder::<sqlx::Postgres>::new(format!(
+            "INSERT INTO {}_embeddings (id, embedding) ",
+            collection.as_str()
+        ));
+        query.push_values(items, |mut row, (id, vector)| {
+            row.push_bind(id).push_bind(Self::literal(&vector)).push_unseparated("::vector");
+        });
+        query.push(" ON CONFLICT (id) DO UPDATE SET embedding = EXCLUDED.embedding");
+        query.build().execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get(&self, collection: Collection, ids: &[String]) -> Result<HashMap<String, Vec<f32>>> {
+        let rows: Vec<(String, String)> = sqlx::query_as(&format!(
+            "SELECT id, embedding::text FROM {}_embeddings WHERE id = ANY($1)",
+            collection.as_str()
+        ))
+        .bind(ids)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter().map(|(id, vector)| Ok((id, Self::parse(&vector)?))).collect()
+    }
+
+    async fn search(&self, collection: Collection, vector: &[f32], top_k: usize) -> Result<Vec<(String, f32)>> {
+        let rows: Vec<(String, f64)> = sqlx::query_as(&format!(
+            "SELECT id, 1 - (embedding <=> $1::vector) AS similarity FROM {}_embeddings \
+             ORDER BY embedding <=> $1::vector LIMIT $2",
+            collection.as_str()
+        ))
+        .bind(Self::literal(vector))
+        .bind(top_k as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(|(id, similarity)| (id, similarity as f32)).collect())
+    }
+}
+
+/// An article matched to a market
+#[derive(Debug, Clone, PartialEq)]
+pub struct SemanticMatch {
+    pub article_id: String,
+    pub similarity: f32,
+}
+
+/// Embedding work done, to keep an eye on cost
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EmbeddingStats {
+    /// `Embedder::embed` calls
+    pub requests: u64,
+    pub texts_embedded: u64,
+    /// Articles and markets that already had a vector
+    pub cache_hits: u64,
+}
+
+/// Article and market embeddings with semantic search between them
+pub struct VectorStore {
+    embedder: Arc<dyn Embedder>,
+    backend: Arc<dyn VectorBackend>,
+    /// Question text each market was last embedded from
+    market_texts: RwLock<HashMap<String, String>>,
+    requests: AtomicU64,
+    texts_embedded: AtomicU64,
+    cache_hits: AtomicU64,
+}
+
+impl VectorStore {
+    /// Store vectors in memory
+    pub fn new(embedder: Arc<dyn Embedder>) -> Self {
+        Self::with_backend(embedder, Arc::new(MemoryBackend::new()))
+    }
+
+    pub fn with_backend(embedder: Arc<dyn Embedder>, backend: Arc<dyn VectorBackend>) -> Self {
+        Self {
+            embedder,
+            backend,
+            market_texts: RwLock::new(HashMap::new()),
+            requests: AtomicU64::new(0),
+            texts_embedded: AtomicU64::new(0),
+            cache_hits: AtomicU64::new(0),
+        }
+    }
+
+    /// Embed and store articles not stored yet, returning how many were new
+    pub async fn index_articles(&self, articles: &[NewsArticle]) -> Result<usize> {
+        let mut seen = HashSet::new();
+        let articles: Vec<&NewsArticle> = articles.iter().filter(|a| seen.insert(a.id.as_str())).collect();
+        let ids: Vec<String> = articles.iter().map(|a| a.id.clone()).collect();
+        let stored = self.backend.get(Collection::Articles, &ids).await?;
+        self.cache_hits.fetch_add(stored.len() as u64, Ordering::Relaxed);
+
+        let new: Vec<&NewsArticle> = articles.into_iter().filter(|a| !stored.contains_key(&a.id)).collect();
+        if new.is_empty() {
+            return Ok(0);
+        }
+        let texts: Vec<String> = new.iter().map(|a| article_text(a)).collect();
+        let vectors = self.embed_batched(&texts).await?;
+        let items = new.iter().map(|a| a.id.clone()).zip(vectors).collect();
+        self.backend.upsert(Collection::Articles, items).await?;
+
+        debug!("Embedded {} new articles", new.len());
+        Ok(new.len())
+    }
+
+    /// Articles most similar to the market's question and description, most
+    /// similar first, down to `min_similarity`
+    pub async fn semantic_match(&self, market: &Market, top_k: usize, min_similarity: f32) -> Result<Vec<SemanticMatch>> {
+        let vector = self.market_vector(market).await?;
+        let matches = self.backend.search(Collection::Articles, &vector, top_k).await?;
+        Ok(matches
+            .into_iter()
+            .filter(|(_, similarity)| *similarity >= min_similarity)
+            .map(|(article_id, similarity)| SemanticMatch { article_id, similarity })
+            .collect())
+    }
+
+    pub fn stats(&self) -> EmbeddingStats {
+        EmbeddingStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            texts_embedded: self.texts_embedded.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+        }
+    }
+
+    /// The market's vector, embedded again only if its text changed
+    async fn market_vector(&self, market: &Market) -> Result<Vec<f32>> {
+        let id = market.id.to_string();
+        let text = format!("{} {}", market.question, market.description);
+        if self.market_texts.read().await.get(&id) == Some(&text) {
+            if let Some(vector) = self.backend.get(Collection::Markets, std::slice::from_ref(&id)).await?.remove(&id) {
+                self.cache_hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(vector);
+            }
+        }
+
+        let vector = self
+            .embed_batched(std::slice::from_ref(&text))
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow!("No embedding for market {}", id))?;
+        self.backend.upsert(Collection::Markets, vec![(id.clone(), vector.clone())]).await?;
+        self.market_texts.write().await.insert(id, text);
+        Ok(vector)
+    }
+
+    async fn embed_batched(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.embedder.max_batch_size().max(1)) {
+            let embedded = self.embedder.embed(batch).await?;
+            if embedded.len() != batch.len() {
+                return Err(anyhow!("Embedder returned {} vectors for {} texts", embedded.len(), batch.len()));
+            }
+            self.requests.fetch_add(1, Ordering::Relaxed);
+            self.texts_embedded.fetch_add(batch.len() as u64, Ordering::Relaxed);
+            vectors.extend(embedded);
+        }
+        Ok(vectors)
+    }
+}
+
+/// Title plus GDELT themes, whose codes read as words once split
+fn article_text(article: &NewsArticle) -> String {
+    format!("{} {}", article.title, article.themes.replace([';', '_'], " "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding::HashEmbedder;
+    use chrono::Utc;
+    use common::MarketId;
+
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/semantic_articles.jsonl");
+
+    fn fixture_articles() -> Vec<NewsArticle> {
+        std::fs::read_to_string(FIXTURE)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    fn market(question: &str) -> Market {
+        Market {
+            id: MarketId::random(),
+            condition_id: "0xsemantic".into(),
+            question: question.to_string(),
+            description: String::new(),
+            category: "Economics".to_string(),
+            outcomes: vec![],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            neg_risk_group: None,
+            tick_size: None,
+        }
+    }
+
+    /// Counts texts per call, delegating to a hash embedder
+    struct BatchRecorder {
+        inner: HashEmbedder,
+        batches: std::sync::Mutex<Vec<usize>>,
+    }
+
+    #[async_trait]
+    impl Embedder for BatchRecorder {
+        fn dimensions(&self) -> usize {
+            self.inner.dimensions()
+        }
+
+        fn max_batch_size(&self) -> usize {
+            2
+        }
+
+        async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            self.batches.lock().unwrap().push(texts.len());
+            self.inner.embed(texts).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_question_retrieves_related_article_first() {
+        let store = VectorStore::new(Arc::new(HashEmbedder::default()));
+        let articles = fixture_articles();
+        assert_eq!(store.index_articles(&articles).await.unwrap(), articles.len());
+
+        let fed = market("Will the Federal Reserve cut interest rates in March?");
+        let matches = store.semantic_match(&fed, 10, -1.0).await.unwrap();
+        assert_eq!(matches.len(), articles.len());
+        assert_eq!(matches[0].article_id, "fed-rate-cut");
+        let similarity = |id: &str| matches.iter().find(|m| m.article_id == id).unwrap().similarity;
+        assert!(similarity("fed-rate-cut") > similarity("wildfire-evacuations"));
+
+        // Only the related article clears a moderate threshold
+        let matches = store.semantic_match(&fed, 10, 0.25).await.unwrap();
+        assert_eq!(matches.iter().map(|m| m.article_id.as_str()).collect::<Vec<_>>(), ["fed-rate-cut"]);
+
+        let spacex = market("Will SpaceX launch Starship to orbit this year?");
+        let matches = store.semantic_match(&spacex, 1, 0.0).await.unwrap();
+        assert_eq!(matches[0].article_id, "starship-launch");
+    }
+
+    #[tokio::test]
+    async fn test_embeds_in_batches_and_once_per_id() {
+        let embedder = Arc::new(BatchRecorder {
+            inner: HashEmbedder::default(),
+            batches: std::sync::Mutex::new(Vec::new()),
+        });
+        let store = VectorStore::new(embedder.clone());
+        let articles = fixture_articles();
+        assert!(articles.len() > 2);
+
+        store.index_articles(&articles).await.unwrap();
+        let expected: Vec<usize> = articles.chunks(2).map(|chunk| chunk.len()).collect();
+        assert_eq!(*embedder.batches.lock().unwrap(), expected);
+
+        // Already stored: nothing to embed
+        assert_eq!(store.index_articles(&articles[..2]).await.unwrap(), 0);
+        assert_eq!(store.stats().cache_hits, 2);
+
+        // The market is embedded once until its question changes
+        let mut fed = market("Will the Federal Reserve cut interest rates in March?");
+        store.semantic_match(&fed, 3, 0.0).await.unwrap();
+        store.semantic_match(&fed, 3, 0.0).await.unwrap();
+        fed.question = "Will the Federal Reserve hike rates in March?".to_string();
+        store.semantic_match(&fed, 3, 0.0).await.unwrap();
+
+        let stats = store.stats();
+        assert_eq!(stats.requests as usize, expected.len() + 2);
+        assert_eq!(stats.texts_embedded as usize, articles.len() + 2);
+        assert_eq!(stats.cache_hits, 3);
+    }
+}
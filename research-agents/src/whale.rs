@@ -0,0 +1,526 @@
+//! Whale Agent - Flags trades far larger than a market usually sees
+//!
+//! A trader moving size often knows something, or at least moves the price.
+//! This agent keeps a rolling window of recent trade sizes per market and
+//! signals when a trade lands above a high percentile of that window and
+//! well above its mean, with the trade's direction and z-score.
+
+use super::agent::{
+    handle_common_control, Agent, AgentConfig, AgentInput, AgentOutput, AgentStatus, AgentStatusCell,
+    ConfigCell, ControlMessage, ControlResponse, PatchableConfig,
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use common::{MarketId, OrderSide, OutcomeId, Trade, Uuid};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, RwLock};
+use tracing::{debug, info};
+
+/// Key in `AgentInput::additional_data` holding a list of `Trade`s
+pub const TRADES_KEY: &str = "trades";
+
+/// Whale trade output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhaleSignal {
+    pub market_id: MarketId,
+    pub trade_id: Uuid,
+    pub outcome_id: OutcomeId,
+    /// Whether the whale bought or sold the outcome
+    pub direction: OrderSide,
+    pub size: f64,
+    pub price: f64,
+    /// Size times price
+    pub notional: f64,
+    /// Standard deviations over the mean size of the window
+    pub z_score: f64,
+    /// Size at the configured percentile of the window
+    pub size_threshold: f64,
+    pub mean_size: f64,
+    /// Trades in the window the trade was judged against
+    pub window_trades: usize,
+    /// Whale trades in this batch; the signal describes the largest
+    pub whale_trades: usize,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Whale agent configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhaleAgentConfig {
+    pub base: AgentConfig,
+    /// Share of the window a trade must be larger than
+    pub percentile: f64,
+    /// Standard deviations over the mean a trade must also be, so a market
+    /// of evenly sized trades doesn't call its largest few percent whales
+    pub min_z_score: f64,
+    /// Recent trades kept per market
+    pub window_size: usize,
+    /// Trades a market needs before whales are reported
+    pub min_history: usize,
+    /// Markets without a new trade for this long are forgotten
+    #[serde(default = "default_history_ttl_secs")]
+    pub history_ttl_secs: u64,
+}
+
+fn default_history_ttl_secs() -> u64 {
+    48 * 3600
+}
+
+impl Default for WhaleAgentConfig {
+    fn default() -> Self {
+        Self {
+            base: AgentConfig {
+                agent_id: "whale-agent".to_string(),
+                name: "Whale Tracking Agent".to_string(),
+                enabled: true,
+                max_markets_per_batch: 100,
+                processing_interval_secs: 60,
+                max_concurrent_markets: 10,
+            },
+            percentile: 0.95,
+            min_z_score: 2.0,
+            window_size: 500,
+            min_history: 30,
+            history_ttl_secs: default_history_ttl_secs(),
+        }
+    }
+}
+
+impl PatchableConfig for WhaleAgentConfig {
+    /// Check the tunables, returning every problem found
+    fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if !(self.percentile > 0.0 && self.percentile < 1.0) {
+            errors.push(format!("percentile must be in (0, 1), got {}", self.percentile));
+        }
+        if self.min_z_score < 0.0 {
+            errors.push(format!("min_z_score can't be negative, got {}", self.min_z_score));
+        }
+        if self.min_history < 2 {
+            errors.push(format!("min_history must be at least 2, got {}", self.min_history));
+        }
+        if self.window_size < self.min_history {
+            errors.push(format!(
+                "window_size must hold min_history ({}) trades, got {}",
+                self.min_history, self.window_size
+            ));
+        }
+        if self.history_ttl_secs == 0 {
+            errors.push("history_ttl_secs must be positive".to_string());
+        }
+
+        errors
+    }
+}
+
+/// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[f64], p: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+/// Recent trade sizes of one market, with their statistics cached
+#[derive(Debug, Clone, Default)]
+struct TradeSizes {
+    /// (trade id, size), oldest first
+    recent: VecDeque<(Uuid, f64)>,
+    threshold: f64,
+    mean: f64,
+    std_dev: f64,
+    /// When the market last had a new trade
+    last_trade_at: DateTime<Utc>,
+}
+
+impl TradeSizes {
+    fn contains(&self, trade_id: Uuid) -> bool {
+        self.recent.iter().any(|(id, _)| *id == trade_id)
+    }
+
+    fn push(&mut self, trade_id: Uuid, size: f64, window_size: usize) {
+        self.recent.push_back((trade_id, size));
+        while self.recent.len() > window_size {
+            self.recent.pop_front();
+        }
+    }
+
+    /// Recompute the cached statistics for `p`
+    fn refresh(&mut self, p: f64) {
+        let mut sorted: Vec<f64> = self.recent.iter().map(|(_, size)| *size).collect();
+        sorted.sort_by(f64::total_cmp);
+
+        let n = sorted.len().max(1) as f64;
+        self.mean = sorted.iter().sum::<f64>() / n;
+        self.std_dev = (sorted.iter().map(|s| (s - self.mean).powi(2)).sum::<f64>() / n).sqrt();
+        self.threshold = percentile(&sorted, p).unwrap_or(0.0);
+    }
+
+    /// Standard deviations `size` is over the mean; the spread is floored at
+    /// 1% of the mean so a window of identical sizes still gives a finite score
+    fn z_score(&self, size: f64) -> f64 {
+        let std_dev = self.std_dev.max(self.mean.abs() * 0.01).max(f64::MIN_POSITIVE);
+        (size - self.mean) / std_dev
+    }
+}
+
+/// A trade that beat the window it was judged against
+struct WhaleTrade {
+    trade: Trade,
+    z_score: f64,
+    threshold: f64,
+    mean: f64,
+    window_trades: usize,
+}
+
+/// Whale Agent - flags unusually large trades per market
+pub struct WhaleAgent {
+    base: AgentConfig,
+    config: ConfigCell<WhaleAgentConfig>,
+    status: AgentStatusCell,
+    sizes: RwLock<HashMap<MarketId, TradeSizes>>,
+    start_time: std::time::Instant,
+}
+
+impl WhaleAgent {
+    /// Create a new whale agent
+    pub fn new(config: WhaleAgentConfig) -> Self {
+        Self {
+            base: config.base.clone(),
+            config: ConfigCell::new("Whale agent", config),
+            status: AgentStatusCell::default(),
+            sizes: RwLock::new(HashMap::new()),
+            start_time: std::time::Instant::now(),
+        }
+    }
+
+    /// Current config
+    pub fn whale_config(&self) -> Arc<WhaleAgentConfig> {
+        self.config.get()
+    }
+
+    /// Size a trade in the market must beat to be a whale, None while the
+    /// market has fewer than `min_history` trades
+    pub fn size_threshold(&self, market_id: MarketId) -> Option<f64> {
+        let config = self.whale_config();
+        let sizes = self.sizes.read().unwrap_or_else(|e| e.into_inner());
+        sizes
+            .get(&market_id)
+            .filter(|s| s.recent.len() >= config.min_history)
+            .map(|s| s.threshold)
+    }
+
+    /// Judge each new trade against the window before it, then add it to the
+    /// window; trades already seen are skipped. Markets idle for longer than
+    /// `history_ttl_secs` at `now` are forgotten first.
+    fn record_trades(
+        &self,
+        config: &WhaleAgentConfig,
+        market_id: MarketId,
+        mut trades: Vec<Trade>,
+        now: DateTime<Utc>,
+    ) -> Vec<WhaleTrade> {
+        trades.sort_by_key(|t| t.timestamp);
+        let mut sizes = self.sizes.write().unwrap_or_else(|e| e.into_inner());
+
+        let cutoff = now - Duration::seconds(config.history_ttl_secs as i64);
+        let before = sizes.len();
+        sizes.retain(|_, window| window.last_trade_at >= cutoff);
+        if sizes.len() < before {
+            debug!("Evicted trade sizes for {} idle markets", before - sizes.len());
+        }
+        if trades.is_empty() {
+            return Vec::new();
+        }
+
+        let window = sizes.entry(market_id).or_default();
+        let mut whales = Vec::new();
+
+        for trade in trades {
+            if window.contains(trade.id) {
+                continue;
+            }
+            window.last_trade_at = now;
+
+            if window.recent.len() >= config.min_history {
+                let z_score = window.z_score(trade.size);
+                if trade.size > window.threshold && z_score >= config.min_z_score {
+                    whales.push(WhaleTrade {
+                        z_score,
+                        threshold: window.threshold,
+                        mean: window.mean,
+                        window_trades: window.recent.len(),
+                        trade: trade.clone(),
+                    });
+                }
+            }
+
+            window.push(trade.id, trade.size, config.window_size);
+            window.refresh(config.percentile);
+        }
+
+        whales
+    }
+}
+
+/// Trades passed in `additional_data` under `TRADES_KEY`, if any
+fn input_trades(input: &AgentInput) -> Result<Vec<Trade>> {
+    match input.additional_data.as_ref().and_then(|data| data.get(TRADES_KEY)) {
+        Some(trades) => serde_json::from_value(trades.clone())
+            .with_context(|| format!("additional_data.{} is not a list of trades", TRADES_KEY)),
+        None => Ok(Vec::new()),
+    }
+}
+
+#[async_trait]
+impl Agent for WhaleAgent {
+    fn config(&self) -> &AgentConfig {
+        &self.base
+    }
+
+    fn status(&self) -> AgentStatus {
+        self.status.get()
+    }
+
+    async fn process_market(&self, input: AgentInput) -> Result<Option<AgentOutput>> {
+        let start = std::time::Instant::now();
+        let config = self.whale_config();
+        let trades = input_trades(&input)?;
+        let market = input.market;
+
+        let _processing = self.status.processing();
+
+        let trades: Vec<Trade> = trades.into_iter().filter(|t| t.market_id == market.id).collect();
+        let whales = self.record_trades(&config, market.id, trades, input.timestamp);
+        let whale_trades = whales.len();
+
+        let Some(whale) = whales.into_iter().max_by(|a, b| a.z_score.total_cmp(&b.z_score)) else {
+            debug!("No whale trades in market {}", market.id);
+            return Ok(None);
+        };
+
+        info!("Whale {:?} of {:.0} in market {} (z-score {:.2}, threshold {:.0})",
+              whale.trade.side, whale.trade.size, market.id, whale.z_score, whale.threshold);
+
+        // Three standard deviations out is even odds; further is surer
+        let confidence = whale.z_score / (whale.z_score + 3.0);
+
+        let signal = WhaleSignal {
+            market_id: market.id,
+            trade_id: whale.trade.id,
+            outcome_id: whale.trade.outcome_id,
+            direction: whale.trade.side,
+            size: whale.trade.size,
+            price: whale.trade.price,
+            notional: whale.trade.size * whale.trade.price,
+            z_score: whale.z_score,
+            size_threshold: whale.threshold,
+            mean_size: whale.mean,
+            window_trades: whale.window_trades,
+            whale_trades,
+            timestamp: input.timestamp,
+        };
+
+        Ok(Some(AgentOutput {
+            agent_id: self.base.agent_id.clone(),
+            market_id: market.id,
+            signal_type: "whale_trade".to_string(),
+            data: serde_json::to_value(signal)?,
+            confidence,
+            timestamp: Utc::now(),
+            processing_time_ms: start.elapsed().as_millis() as u64,
+        }))
+    }
+
+    async fn handle_control(&self, msg: ControlMessage) -> Result<ControlResponse> {
+        Ok(handle_common_control(msg, &self.status, &self.config, self.start_time))
+    }
+
+    async fn on_start(&self) -> Result<()> {
+        info!("Whale agent starting");
+        self.status.set(AgentStatus::Idle);
+        Ok(())
+    }
+
+    async fn on_stop(&self) -> Result<()> {
+        info!("Whale agent stopping");
+        self.status.set(AgentStatus::Idle);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use common::Market;
+
+    fn politics_market() -> Arc<Market> {
+        Arc::new(Market {
+            id: MarketId::random(),
+            condition_id: "0xelection".into(),
+            question: "Will the incumbent win?".to_string(),
+            description: String::new(),
+            category: "Politics".to_string(),
+            outcomes: vec![],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            neg_risk_group: None,
+            tick_size: None,
+        })
+    }
+
+    fn trade(market: &Market, size: f64, side: OrderSide, at: DateTime<Utc>) -> Trade {
+        Trade {
+            id: Uuid::new_v4(),
+            market_id: market.id,
+            outcome_id: "YES".into(),
+            price: 0.6,
+            size,
+            side,
+            timestamp: at,
+        }
+    }
+
+    /// 100 buys of 50 to 149 shares, one a second before `now`
+    fn ordinary_flow(market: &Market, now: DateTime<Utc>) -> Vec<Trade> {
+        (0..100)
+            .map(|i: i64| trade(market, 50.0 + ((i * 37) % 100) as f64, OrderSide::Buy, now - Duration::seconds(100 - i)))
+            .collect()
+    }
+
+    fn input(market: &Arc<Market>, trades: &[Trade]) -> AgentInput {
+        AgentInput {
+            market: market.clone(),
+            timestamp: Utc::now(),
+            additional_data: Some(serde_json::json!({ TRADES_KEY: trades })),
+        }
+    }
+
+    #[test]
+    fn test_percentile() {
+        let sorted: Vec<f64> = (1..=100).map(f64::from).collect();
+        assert_eq!(percentile(&sorted, 0.95), Some(95.0));
+        assert_eq!(percentile(&sorted[..10], 0.95), Some(10.0));
+        assert_eq!(percentile(&[], 0.95), None);
+    }
+
+    #[tokio::test]
+    async fn test_normal_sized_trade_is_not_a_whale() {
+        let agent = WhaleAgent::new(WhaleAgentConfig::default());
+        let market = politics_market();
+        let now = Utc::now();
+
+        // Evenly spread sizes never stand two standard deviations out
+        assert!(agent.process_market(input(&market, &ordinary_flow(&market, now))).await.unwrap().is_none());
+        let threshold = agent.size_threshold(market.id).unwrap();
+        assert_eq!(threshold, 144.0);
+
+        let normal = trade(&market, 120.0, OrderSide::Sell, now);
+        assert!(agent.process_market(input(&market, &[normal])).await.unwrap().is_none());
+        assert_eq!(agent.status(), AgentStatus::Idle);
+        // Another market has no history yet
+        assert_eq!(agent.size_threshold(MarketId::random()), None);
+    }
+
+    #[tokio::test]
+    async fn test_whale_trade_is_flagged_with_direction() {
+        let agent = WhaleAgent::new(WhaleAgentConfig::default());
+        let market = politics_market();
+        let now = Utc::now();
+        agent.process_market(input(&market, &ordinary_flow(&market, now))).await.unwrap();
+
+        let whale = trade(&market, 5000.0, OrderSide::Sell, now + Duration::seconds(1));
+        let output = agent.process_market(input(&market, std::slice::from_ref(&whale))).await.unwrap().unwrap();
+        assert_eq!(output.signal_type, "whale_trade");
+        assert_eq!(output.market_id, market.id);
+        assert!(output.confidence > 0.9);
+
+        let signal: WhaleSignal = serde_json::from_value(output.data).unwrap();
+        assert_eq!(signal.trade_id, whale.id);
+        assert_eq!(signal.direction, OrderSide::Sell);
+        assert_eq!(signal.outcome_id, "YES");
+        assert_eq!(signal.window_trades, 100);
+        assert_eq!(signal.whale_trades, 1);
+        assert!((signal.mean_size - 99.5).abs() < 1e-9);
+        assert!((signal.notional - 3000.0).abs() < 1e-9);
+        // 100 sizes 50..=149 have a standard deviation of about 28.9
+        assert!((signal.z_score - (5000.0 - 99.5) / 28.866).abs() < 0.1, "z {}", signal.z_score);
+
+        // Seeing the same trade again doesn't signal it twice
+        assert!(agent.process_market(input(&market, &[whale])).await.unwrap().is_none());
+
+        // Trades for other markets are ignored
+        let elsewhere = trade(&politics_market(), 9000.0, OrderSide::Buy, now);
+        assert!(agent.process_market(input(&market, &[elsewhere])).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_no_whales_before_min_history() {
+        let agent = WhaleAgent::new(WhaleAgentConfig::default());
+        let market = politics_market();
+        let now = Utc::now();
+
+        let mut trades = ordinary_flow(&market, now);
+        trades.truncate(10);
+        trades.push(trade(&market, 5000.0, OrderSide::Buy, now));
+        assert!(agent.process_market(input(&market, &trades)).await.unwrap().is_none());
+
+        let malformed = AgentInput {
+            market: market.clone(),
+            timestamp: now,
+            additional_data: Some(serde_json::json!({ TRADES_KEY: 42 })),
+        };
+        assert!(agent.process_market(malformed).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_idle_markets_are_forgotten() {
+        let agent = WhaleAgent::new(WhaleAgentConfig::default());
+        let (quiet, busy) = (politics_market(), politics_market());
+        let now = Utc::now();
+
+        let at = |timestamp, market: &Arc<Market>, trades: &[Trade]| AgentInput { timestamp, ..input(market, trades) };
+        agent.process_market(at(now, &quiet, &ordinary_flow(&quiet, now))).await.unwrap();
+        agent.process_market(at(now, &busy, &ordinary_flow(&busy, now))).await.unwrap();
+
+        // A day on only one market has traded
+        let later = now + Duration::hours(24);
+        let next = trade(&busy, 100.0, OrderSide::Buy, later);
+        agent.process_market(at(later, &busy, &[next])).await.unwrap();
+        assert!(agent.size_threshold(quiet.id).is_some());
+
+        // Another 25 hours on the quiet market has been idle for over 48
+        let later = later + Duration::hours(25);
+        agent.process_market(at(later, &busy, &[])).await.unwrap();
+        assert_eq!(agent.size_threshold(quiet.id), None);
+        assert!(agent.size_threshold(busy.id).is_some());
+        assert_eq!(agent.sizes.read().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_control_messages() {
+        let agent = WhaleAgent::new(WhaleAgentConfig::default());
+        assert_eq!(agent.config().agent_id, "whale-agent");
+
+        agent.handle_control(ControlMessage::Pause).await.unwrap();
+        match agent.handle_control(ControlMessage::HealthCheck).await.unwrap() {
+            ControlResponse::HealthCheck { status, .. } => assert_eq!(status, AgentStatus::Paused),
+            other => panic!("Expected HealthCheck, got {:?}", other),
+        }
+        agent.handle_control(ControlMessage::Resume).await.unwrap();
+        assert_eq!(agent.status(), AgentStatus::Idle);
+
+        let patch = serde_json::json!({ "percentile": 0.99 });
+        let response = agent.handle_control(ControlMessage::UpdateConfig(patch)).await.unwrap();
+        assert!(matches!(response, ControlResponse::ConfigUpdated { applied: true, .. }));
+        assert_eq!(agent.whale_config().percentile, 0.99);
+
+        let patch = serde_json::json!({ "window_size": 5 });
+        let response = agent.handle_control(ControlMessage::UpdateConfig(patch)).await.unwrap();
+        assert!(matches!(response, ControlResponse::ConfigUpdated { applied: false, .. }));
+    }
+}
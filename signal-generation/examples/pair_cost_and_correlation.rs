@@ -0,0 +1,109 @@
+// Example: Per-Market and Batch Generators in One Pipeline
+// PairCostGenerator runs on each market, CorrelationGenerator over the whole snapshot
+
+use chrono::Utc;
+use common::{Market, MarketId, OrderSide, Outcome};
+use rust_decimal::Decimal;
+use signal_generation::{
+    CorrelationEdge, CorrelationGenerator, CorrelationType, Level, OrderBookSnapshot, PairCostConfig,
+    PairCostGenerator, PairSide, PipelineConfig, ResearchOutput, SentimentScore, SignalInput, SignalPipeline,
+    StateUpdate,
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    println!("=== Signal Generation Framework - Pair Cost and Correlation Example ===\n");
+
+    // "Candidate wins the primary" implies "candidate is on the ballot", yet
+    // the primary trades above the ballot market
+    let primary = MarketId::random();
+    let ballot = MarketId::random();
+
+    let mut correlation = CorrelationGenerator::new();
+    correlation.add_correlation(CorrelationEdge {
+        from_market: primary,
+        to_market: ballot,
+        correlation_type: CorrelationType::Implies,
+        min_spread: Decimal::new(3, 2),
+    });
+
+    let mut pipeline = SignalPipeline::new(PipelineConfig::default())
+        .add_generator(Box::new(PairCostGenerator::new(PairCostConfig::default())))
+        .add_batch_generator(Box::new(correlation));
+
+    let inputs = vec![
+        create_input(primary, "Wins the primary?", Decimal::new(39, 2), Decimal::new(41, 2)),
+        create_input(ballot, "On the November ballot?", Decimal::new(29, 2), Decimal::new(31, 2)),
+    ];
+
+    let signals = pipeline.process_batch(&inputs).await?;
+    println!("First snapshot: {} signals", signals.len());
+    for signal in &signals {
+        println!("  {:?} {:?} @ {} - {}", signal.signal_type, signal.direction, signal.entry_price, signal.reasoning);
+    }
+
+    // Fills are fed back so the pair cost generator tracks its YES and NO legs
+    pipeline.update_state(
+        ballot,
+        &StateUpdate::TradeExecution {
+            signal_id: None,
+            outcome_id: Some("yes".to_string()),
+            outcome_side: PairSide::Yes,
+            side: OrderSide::Buy,
+            size: Decimal::from(10),
+            price: Decimal::new(31, 2),
+        },
+    );
+
+    // The same violation is within the correlation cooldown
+    let signals = pipeline.process_batch(&inputs).await?;
+    println!("\nSecond snapshot: {} signals", signals.len());
+    for signal in &signals {
+        println!("  {:?} {:?} @ {} - {}", signal.signal_type, signal.direction, signal.entry_price, signal.reasoning);
+    }
+
+    Ok(())
+}
+
+fn create_input(market_id: MarketId, question: &str, bid: Decimal, ask: Decimal) -> SignalInput {
+    let level = |price| Level {
+        outcome_id: "yes".to_string(),
+        price,
+        size: Decimal::from(1000),
+    };
+    SignalInput {
+        market: Market {
+            id: market_id,
+            condition_id: market_id.to_string().into(),
+            question: question.to_string(),
+            description: String::new(),
+            category: "politics".to_string(),
+            outcomes: vec![Outcome {
+                id: "yes".into(),
+                name: "Yes".to_string(),
+                price: 0.5,
+                liquidity: 10000.0,
+            }],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            neg_risk_group: None,
+            tick_size: None,
+        },
+        research_output: ResearchOutput {
+            market_id,
+            analysis: String::new(),
+            sentiment: SentimentScore { overall: 0.0, sources: vec![] },
+            confidence: 0.5,
+            probability_estimate: None,
+            key_factors: vec![],
+            timestamp: Utc::now(),
+        },
+        order_book: Some(OrderBookSnapshot {
+            market_id,
+            bids: vec![level(bid)],
+            asks: vec![level(ask)],
+            timestamp: Utc::now(),
+        }),
+        price_history: vec![],
+    }
+}
@@ -2,16 +2,15 @@
 // Demonstrates how to use the signal generation framework to generate trade signals
 
 use chrono::Utc;
-use common::Market;
+use common::{Market, MarketId};
 use rust_decimal::prelude::*;
 use signal_generation::{
     PipelineConfig, SignalPipeline, SpreadArbitrageGenerator,
     EdgeThresholdValidator, ConfidenceValidator, LiquidityValidator,
-    ExpectedValueValidator, InMemoryStorage, SignalStorage,
+    ExpectedValueValidator, StalenessValidator, InMemoryStorage, SignalStorage,
     SignalInput, ResearchOutput, SentimentScore, SentimentSource,
     PriceSnapshot,
 };
-use uuid::Uuid;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -95,17 +94,19 @@ async fn main() -> anyhow::Result<()> {
         max_signals_per_cycle: 5,
         min_confidence: 0.6,
         min_edge: Decimal::from_str_exact("0.03").unwrap(), // 3%
+        ..PipelineConfig::default()
     };
 
     println!("=== Creating Signal Pipeline ===\n");
 
     // Build the pipeline with generators and validators
-    let pipeline = SignalPipeline::new(config)
+    let mut pipeline = SignalPipeline::new(config)
         .add_generator(Box::new(SpreadArbitrageGenerator::default()))
         .add_validator(Box::new(EdgeThresholdValidator::default()))
         .add_validator(Box::new(ConfidenceValidator::default()))
         .add_validator(Box::new(LiquidityValidator::default()))
         .add_validator(Box::new(ExpectedValueValidator::default()))
+        .add_validator(Box::new(StalenessValidator::default()))
         .with_storage(Box::new(InMemoryStorage::new()));
 
     println!("Pipeline configured with:");
@@ -152,20 +153,20 @@ async fn main() -> anyhow::Result<()> {
 
 fn create_sample_market() -> Market {
     Market {
-        id: Uuid::new_v4(),
-        condition_id: "0x123...".to_string(),
+        id: MarketId::random(),
+        condition_id: "0x123...".into(),
         question: "Will Bitcoin exceed $100,000 by end of 2026?".to_string(),
         description: "This market resolves YES if Bitcoin trades above $100,000 on any major exchange before December 31, 2026.".to_string(),
         category: "Cryptocurrency".to_string(),
         outcomes: vec![
             common::Outcome {
-                id: "yes".to_string(),
+                id: "yes".into(),
                 name: "Yes".to_string(),
                 price: 0.50,
                 liquidity: 10000.0,
             },
             common::Outcome {
-                id: "no".to_string(),
+                id: "no".into(),
                 name: "No".to_string(),
                 price: 0.45,
                 liquidity: 8000.0,
@@ -173,6 +174,8 @@ fn create_sample_market() -> Market {
         ],
         created_at: Utc::now() - chrono::Duration::days(30),
         updated_at: Utc::now(),
+        neg_risk_group: None,
+        tick_size: None,
     }
 }
 
@@ -232,5 +235,10 @@ fn explain_validators() {
     println!("   - EV = (win_probability × win_amount) - (lose_probability × loss_amount)");
     println!();
 
+    println!("5. Staleness Validator");
+    println!("   - Checks the order book, last price and research were fresh");
+    println!("   - Default: book under 30s, price under 2 min, research under 30 min old");
+    println!();
+
     println!("All validators must pass for a signal to be approved for execution.");
 }
@@ -2,7 +2,7 @@
 // Demonstrates a case where a signal is successfully generated
 
 use chrono::Utc;
-use common::Market;
+use common::{Market, MarketId};
 use rust_decimal::prelude::*;
 use signal_generation::{
     PipelineConfig, SignalPipeline, SpreadArbitrageGenerator,
@@ -14,7 +14,6 @@ use signal_generation::{
     SignalInput, ResearchOutput, SentimentScore, SentimentSource,
     PriceSnapshot,
 };
-use uuid::Uuid;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -106,12 +105,13 @@ async fn main() -> anyhow::Result<()> {
         max_signals_per_cycle: 5,
         min_confidence: 0.5,
         min_edge: Decimal::from_str_exact("0.02").unwrap(), // 2%
+        ..PipelineConfig::default()
     };
 
     println!("=== Creating Signal Pipeline ===\n");
 
     // Build the pipeline with generators and custom validators (lower thresholds for demo)
-    let pipeline = SignalPipeline::new(config)
+    let mut pipeline = SignalPipeline::new(config)
         .add_generator(Box::new(SpreadArbitrageGenerator::default()))
         .add_validator(Box::new(EdgeThresholdValidator::new(EdgeThresholdConfig {
             min_edge: Decimal::from_str_exact("0.02").unwrap(), // 2%
@@ -122,9 +122,11 @@ async fn main() -> anyhow::Result<()> {
         .add_validator(Box::new(LiquidityValidator::new(LiquidityValidatorConfig {
             min_liquidity_score: 0.2,
             max_position_liquidity_ratio: 0.2, // More lenient
+            ..Default::default()
         })))
         .add_validator(Box::new(ExpectedValueValidator::new(ExpectedValueValidatorConfig {
             min_expected_value: Decimal::from_str_exact("0.1").unwrap(), // $0.10 for demo
+            ..Default::default()
         })))
         .with_storage(Box::new(InMemoryStorage::new()));
 
@@ -174,20 +176,20 @@ fn create_arbitrage_market() -> Market {
     // Create market with clear arbitrage opportunity
     // Total probability: 0.45 + 0.42 = 0.87 (13% edge)
     Market {
-        id: Uuid::new_v4(),
-        condition_id: "0x456...".to_string(),
+        id: MarketId::random(),
+        condition_id: "0x456...".into(),
         question: "Will Ethereum exceed $5,000 by end of Q2 2026?".to_string(),
         description: "This market resolves YES if Ethereum trades above $5,000 on any major exchange before June 30, 2026.".to_string(),
         category: "Cryptocurrency".to_string(),
         outcomes: vec![
             common::Outcome {
-                id: "yes".to_string(),
+                id: "yes".into(),
                 name: "Yes".to_string(),
                 price: 0.45,  // Undervalued
                 liquidity: 15000.0,
             },
             common::Outcome {
-                id: "no".to_string(),
+                id: "no".into(),
                 name: "No".to_string(),
                 price: 0.42,  // Also undervalued
                 liquidity: 12000.0,
@@ -195,6 +197,8 @@ fn create_arbitrage_market() -> Market {
         ],
         created_at: Utc::now() - chrono::Duration::days(30),
         updated_at: Utc::now(),
+        neg_risk_group: None,
+        tick_size: None,
     }
 }
 
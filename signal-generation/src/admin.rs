@@ -0,0 +1,230 @@
+// Admin API
+// HTTP endpoints for operators to inspect a running pipeline
+
+use crate::correlation::{CorrelationGenerator, RulePreview};
+use crate::pipeline::SignalPipeline;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use common::MarketId;
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// Cycles of diagnostics returned when the request doesn't ask for a number
+pub const DEFAULT_DIAGNOSTICS_CYCLES: usize = 20;
+
+/// Admin endpoints over the pipeline a process runs
+///
+/// The pipeline is shared with the loop that drives it. A request holds the
+/// lock only while it copies out its response, so it waits for a cycle in
+/// progress to finish rather than seeing it half done.
+#[derive(Clone)]
+pub struct AdminApi {
+    pipeline: Arc<Mutex<SignalPipeline>>,
+    correlation: Option<Arc<std::sync::Mutex<CorrelationGenerator>>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DiagnosticsQuery {
+    cycles: Option<usize>,
+}
+
+/// Approval of the staged correlation rule change with id `preview_id`
+#[derive(Debug, Deserialize)]
+struct ApplyStaged {
+    preview_id: Uuid,
+    approved_by: String,
+}
+
+impl AdminApi {
+    pub fn new(pipeline: Arc<Mutex<SignalPipeline>>) -> Self {
+        Self { pipeline, correlation: None }
+    }
+
+    /// Review and approve the rule changes `generator` stages; it must be the
+    /// one added to the pipeline with `add_batch_generator`
+    pub fn with_correlation(mut self, generator: Arc<std::sync::Mutex<CorrelationGenerator>>) -> Self {
+        self.correlation = Some(generator);
+        self
+    }
+
+    /// - `GET /markets/{id}/diagnostics?cycles=N`: the market's last N cycles
+    ///   of diagnostics, oldest first
+    /// - `GET /correlation/staged`: preview of the staged rule change
+    /// - `POST /correlation/staged/apply`: apply it, given `preview_id` and `approved_by`
+    pub fn router(&self) -> Router {
+        let mut router = Router::new().route("/markets/:id/diagnostics", get(market_diagnostics));
+        if self.correlation.is_some() {
+            router = router
+                .route("/correlation/staged", get(staged_change))
+                .route("/correlation/staged/apply", post(apply_staged));
+        }
+        router.with_state(self.clone())
+    }
+
+    /// Serve the admin API on `listener`; only returns if accepting fails
+    pub async fn serve(self, listener: TcpListener) -> std::io::Result<()> {
+        axum::serve(listener, self.router()).await
+    }
+}
+
+async fn market_diagnostics(
+    State(api): State<AdminApi>,
+    Path(market_id): Path<MarketId>,
+    Query(query): Query<DiagnosticsQuery>,
+) -> Json<serde_json::Value> {
+    let cycles = query.cycles.unwrap_or(DEFAULT_DIAGNOSTICS_CYCLES);
+    Json(api.pipeline.lock().await.diagnostics_log().to_json(market_id, cycles))
+}
+
+fn locked_correlation(api: &AdminApi) -> std::sync::MutexGuard<'_, CorrelationGenerator> {
+    // Only routed when a generator was given
+    let generator = api.correlation.as_ref().expect("correlation routes need a generator");
+    generator.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+async fn staged_change(State(api): State<AdminApi>) -> Result<Json<RulePreview>, (StatusCode, String)> {
+    locked_correlation(&api)
+        .staged_change()
+        .cloned()
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, "No correlation rule change is staged".to_string()))
+}
+
+/// Conflict when nothing is staged or `preview_id` is not the staged change,
+/// so an approval never applies rules its approver didn't review
+async fn apply_staged(
+    State(api): State<AdminApi>,
+    Json(request): Json<ApplyStaged>,
+) -> Result<Json<RulePreview>, (StatusCode, String)> {
+    locked_correlation(&api)
+        .apply_staged(request.preview_id, &request.approved_by)
+        .map(Json)
+        .map_err(|e| (StatusCode::CONFLICT, format!("{:#}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_audit::InMemoryConfigAudit;
+    use crate::correlation::ReloadOutcome;
+    use crate::pipeline::PipelineConfig;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    async fn send(api: &AdminApi, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+        let response = api.router().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null))
+    }
+
+    async fn get(api: &AdminApi, uri: &str) -> (StatusCode, serde_json::Value) {
+        send(api, Request::get(uri).body(Body::empty()).unwrap()).await
+    }
+
+    async fn post(api: &AdminApi, uri: &str, body: serde_json::Value) -> (StatusCode, serde_json::Value) {
+        let request = Request::post(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        send(api, request).await
+    }
+
+    #[tokio::test]
+    async fn test_market_diagnostics_endpoint() {
+        let pipeline = Arc::new(Mutex::new(SignalPipeline::new(PipelineConfig::default())));
+        let api = AdminApi::new(pipeline.clone());
+        let market = MarketId::random();
+        pipeline.lock().await.record_filtered(market, "min_volume");
+
+        let (status, body) = get(&api, &format!("/markets/{}/diagnostics?cycles=5", market)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["market_id"], market.to_string());
+        let cycles = body["cycles"].as_array().unwrap();
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0]["outcomes"][0]["stage"], "filtered");
+        assert_eq!(cycles[0]["outcomes"][0]["criterion"], "min_volume");
+
+        // Defaults to the last 20 cycles; unknown markets have none
+        let (status, body) = get(&api, &format!("/markets/{}/diagnostics", MarketId::random())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["cycles"].as_array().unwrap().is_empty());
+
+        let (status, _) = get(&api, "/markets/not-a-market/diagnostics").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_staged_correlation_change_is_applied_through_the_api() {
+        let path = std::env::temp_dir().join(format!("correlation-{}.toml", Uuid::new_v4()));
+        std::fs::write(&path, "").unwrap();
+        let audit = Arc::new(InMemoryConfigAudit::new());
+        let correlation = Arc::new(std::sync::Mutex::new(
+            CorrelationGenerator::from_config_file(&path)
+                .unwrap()
+                .with_apply_gate(1)
+                .with_audit(audit.clone()),
+        ));
+        let pipeline = SignalPipeline::new(PipelineConfig::default()).add_batch_generator(Box::new(correlation.clone()));
+        let pipeline = Arc::new(Mutex::new(pipeline));
+        let api = AdminApi::new(pipeline.clone()).with_correlation(correlation.clone());
+
+        let quote = |condition_id: &str, bid: &str, ask: &str| {
+            crate::test_support::input(MarketId::random())
+                .market(|market| market.condition_id = condition_id.into())
+                .quote(bid, ask)
+                .build()
+        };
+        let inputs = [quote("a", "0.59", "0.61"), quote("b", "0.39", "0.41")];
+        pipeline.lock().await.process_batch(&inputs).await.unwrap();
+
+        let (status, _) = get(&api, "/correlation/staged").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // A change that would signal right away waits for approval
+        std::fs::write(&path, r#"edges = [{ from = "a", to = "b", type = "implies" }]"#).unwrap();
+        let ReloadOutcome::Staged(staged) = correlation.lock().unwrap().reload_config().unwrap() else {
+            panic!("Expected the change to be staged");
+        };
+        let (status, preview) = get(&api, "/correlation/staged").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(preview["id"], staged.id.to_string());
+        assert_eq!(preview["added_edges"][0]["correlation_type"], "implies");
+        assert_eq!(preview["new_violations"].as_array().unwrap().len(), 1);
+
+        // Approving a preview other than the staged one applies nothing
+        let (status, _) = post(
+            &api,
+            "/correlation/staged/apply",
+            json!({ "preview_id": Uuid::new_v4(), "approved_by": "alice" }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert!(correlation.lock().unwrap().edges().is_empty());
+
+        let (status, applied) = post(
+            &api,
+            "/correlation/staged/apply",
+            json!({ "preview_id": staged.id, "approved_by": "alice" }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(applied["id"], staged.id.to_string());
+        assert_eq!(correlation.lock().unwrap().edges().len(), 1);
+        assert_eq!(audit.changes()[0].approved_by.as_deref(), Some("alice"));
+
+        // The pipeline runs the approved rules
+        assert_eq!(pipeline.lock().await.process_batch(&inputs).await.unwrap().len(), 2);
+        let (status, _) = get(&api, "/correlation/staged").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
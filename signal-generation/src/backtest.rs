@@ -0,0 +1,778 @@
+// Backtester
+// Replays recorded market events through the signal pipeline, or replays stored
+// signals against them, and simulates execution
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use common::{Market, MarketEvent, MarketId, OrderBook, OrderSide, OutcomeId, PriceTick, RefundPolicy};
+use rust_decimal::prelude::*;
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use super::pipeline::SignalPipeline;
+use super::signals::{
+    Level, OrderBookSnapshot, PairSide, PriceSnapshot, ResearchOutput, SentimentScore, SignalDirection, SignalInput,
+    StateUpdate, TradeSignal,
+};
+use super::storage::{BacktestStats, ExecutionStorage, ExitReason, InMemoryExecutionStorage, SignalExecutionResult};
+
+/// How simulated orders are filled
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FillModel {
+    /// Fill the whole size at the signal's entry price, and exit at the
+    /// tick price, each moved against us by `slippage_bps`
+    Immediate { slippage_bps: u32 },
+    /// Walk the last recorded book: Longs lift the asks and Shorts hit the
+    /// bids, for as much size as the book shows. Exits fill at the tick price.
+    CrossBook,
+}
+
+impl Default for FillModel {
+    fn default() -> Self {
+        FillModel::Immediate { slippage_bps: 0 }
+    }
+}
+
+/// Backtest configuration
+#[derive(Debug, Clone)]
+pub struct BacktestConfig {
+    pub fill_model: FillModel,
+    /// Price snapshots per market passed to generators as `price_history`
+    pub price_history_len: usize,
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        Self {
+            fill_model: FillModel::default(),
+            price_history_len: 50,
+        }
+    }
+}
+
+/// A market as reconstructed from the event stream
+struct MarketReplay {
+    market: Market,
+    order_book: Option<OrderBookSnapshot>,
+    price_history: VecDeque<PriceSnapshot>,
+    /// Last YES price
+    mark: Option<Decimal>,
+}
+
+/// A filled signal waiting for its exit
+struct OpenPosition {
+    signal: TradeSignal,
+    entry_price: Decimal,
+    size: Decimal,
+    opened_at: DateTime<Utc>,
+}
+
+/// Replays an ordered stream of `MarketEvent`s through a `SignalPipeline`.
+///
+/// Every order book update and YES price tick rebuilds the market's
+/// `SignalInput` as of the event's timestamp and runs the pipeline on it.
+/// Signals are filled with the `FillModel` and held until the YES price
+/// crosses their target or stop, they expire, or the market resolves; each
+/// exit is stored as a `SignalExecutionResult`. Prices are YES prices, so a
+/// Short profits when YES falls. Positions still open when the events run out
+/// are closed at the last price with `ExitReason::Timeout`.
+///
+/// Built with `from_signals` instead, there is no pipeline: the given signals
+/// are entered as the clock reaches their `created_at`, keeping their ids,
+/// and exit the same way.
+///
+/// Simulated time is the latest event timestamp, and signal ids are assigned
+/// in order, so the same events give the same results.
+pub struct Backtester {
+    config: BacktestConfig,
+    pipeline: Option<SignalPipeline>,
+    storage: Box<dyn ExecutionStorage + Send + Sync>,
+    markets: HashMap<MarketId, MarketReplay>,
+    open: Vec<OpenPosition>,
+    /// Stored signals not yet entered, earliest first
+    queued: VecDeque<TradeSignal>,
+    clock: Option<DateTime<Utc>>,
+    signals_generated: u64,
+}
+
+impl Backtester {
+    pub fn new(config: BacktestConfig, pipeline: SignalPipeline) -> Self {
+        Self {
+            config,
+            pipeline: Some(pipeline),
+            storage: Box::new(InMemoryExecutionStorage::new()),
+            markets: HashMap::new(),
+            open: Vec::new(),
+            queued: VecDeque::new(),
+            clock: None,
+            signals_generated: 0,
+        }
+    }
+
+    /// Replay already generated `signals`, such as those in a `SignalStorage`,
+    /// rather than running a pipeline
+    pub fn from_signals(config: BacktestConfig, signals: impl IntoIterator<Item = TradeSignal>) -> Self {
+        let mut signals: Vec<TradeSignal> = signals.into_iter().filter(|s| !s.is_cancel()).collect();
+        signals.sort_by_key(|s| (s.created_at, s.id));
+        Self {
+            config,
+            pipeline: None,
+            storage: Box::new(InMemoryExecutionStorage::new()),
+            markets: HashMap::new(),
+            open: Vec::new(),
+            queued: signals.into(),
+            clock: None,
+            signals_generated: 0,
+        }
+    }
+
+    /// Store execution results somewhere other than in memory
+    pub fn with_storage(mut self, storage: Box<dyn ExecutionStorage + Send + Sync>) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    /// Where the execution results went
+    pub fn storage(&self) -> &dyn ExecutionStorage {
+        self.storage.as_ref()
+    }
+
+    /// Read events from a JSONL file, one `MarketEvent` per line
+    pub fn load_events(path: impl AsRef<Path>) -> Result<Vec<MarketEvent>> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+
+        let mut events = Vec::new();
+        for (i, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event = serde_json::from_str(&line)
+                .with_context(|| format!("Invalid event on line {} of {}", i + 1, path.display()))?;
+            events.push(event);
+        }
+        Ok(events)
+    }
+
+    /// Replay `events` in order and report on the closed trades
+    pub async fn run(&mut self, events: impl IntoIterator<Item = MarketEvent>) -> Result<BacktestStats> {
+        let mut start = None;
+        let mut count = 0;
+        for event in events {
+            let at = self.advance(&event);
+            start.get_or_insert(at);
+            self.apply(&event, at).await?;
+            count += 1;
+        }
+
+        let end = self.clock.unwrap_or(DateTime::UNIX_EPOCH);
+        for position in std::mem::take(&mut self.open) {
+            let exit = self.markets.get(&position.signal.market_id).and_then(|m| m.mark);
+            let exit = exit.map_or(position.entry_price, |mark| self.exit_price(&position, mark));
+            self.close(position, exit, ExitReason::Timeout, end).await?;
+        }
+
+        if !self.queued.is_empty() {
+            debug!("{} stored signals were never entered", self.queued.len());
+        }
+        info!("Backtested {} events, {} signals", count, self.signals_generated);
+        self.storage.get_backtest_stats(start.unwrap_or(end), end).await
+    }
+
+    /// Move the clock to the event; events without a timestamp of their own
+    /// happen at the current time
+    fn advance(&mut self, event: &MarketEvent) -> DateTime<Utc> {
+        let at = match event {
+            MarketEvent::MarketResolved { .. }
+            | MarketEvent::ConditionResolved { .. }
+            | MarketEvent::ResolutionReversed { .. }
+            | MarketEvent::MarketInvalidated { .. } => self.clock.unwrap_or(DateTime::UNIX_EPOCH),
+            _ => event.timestamp(),
+        };
+        let at = self.clock.map_or(at, |clock| clock.max(at));
+        self.clock = Some(at);
+        at
+    }
+
+    async fn apply(&mut self, event: &MarketEvent, at: DateTime<Utc>) -> Result<()> {
+        self.expire(at).await?;
+
+        match event {
+            MarketEvent::MarketCreated(market) => {
+                self.markets.insert(
+                    market.id,
+                    MarketReplay {
+                        market: market.clone(),
+                        order_book: None,
+                        price_history: VecDeque::new(),
+                        mark: None,
+                    },
+                );
+            }
+            MarketEvent::OrderBookUpdate(book) => {
+                if let Some(replay) = self.markets.get_mut(&book.market_id) {
+                    replay.order_book = Some(book_snapshot(book));
+                    self.generate(book.market_id, at).await?;
+                }
+            }
+            MarketEvent::PriceTick(tick) => {
+                if self.record_tick(tick) {
+                    self.check_exits(tick.market_id, at).await?;
+                    self.generate(tick.market_id, at).await?;
+                }
+            }
+            MarketEvent::MarketResolved { market_id, outcome_id } => {
+                self.resolve(*market_id, outcome_id, at).await?;
+            }
+            // Recorded straight off the feed, before it was routed to a market
+            MarketEvent::ConditionResolved { condition_id, outcome_id } => {
+                let market_id = self
+                    .markets
+                    .values()
+                    .find(|replay| replay.market.condition_id == *condition_id)
+                    .map(|replay| replay.market.id);
+                if let Some(market_id) = market_id {
+                    self.resolve(market_id, outcome_id, at).await?;
+                }
+            }
+            MarketEvent::MarketInvalidated { market_id, refund_policy } => {
+                self.markets.remove(market_id);
+                for position in self.take_open(*market_id) {
+                    let exit = match refund_policy {
+                        RefundPolicy::FiftyFifty => Decimal::new(5, 1),
+                        RefundPolicy::AtCost => position.entry_price,
+                    };
+                    self.close(position, exit, ExitReason::MarketResolved, at).await?;
+                }
+            }
+            // Settled positions are not reopened; trades are other participants' fills
+            MarketEvent::ResolutionReversed { .. } | MarketEvent::Trade(_) => {}
+        }
+
+        self.enter_queued(at);
+        Ok(())
+    }
+
+    /// Enter the stored signals created by `at`, on the markets' books as of now
+    fn enter_queued(&mut self, at: DateTime<Utc>) {
+        while self.queued.front().is_some_and(|s| s.created_at <= at) {
+            let Some(signal) = self.queued.pop_front() else {
+                break;
+            };
+            let Some(replay) = self.markets.get(&signal.market_id) else {
+                debug!("Signal {} is for a market that is not open", signal.id);
+                continue;
+            };
+            let order_book = replay.order_book.clone();
+            self.signals_generated += 1;
+            self.enter(signal, order_book.as_ref(), at);
+        }
+    }
+
+    /// Add a tick to its market's history; false if it isn't a price for a
+    /// known market
+    fn record_tick(&mut self, tick: &PriceTick) -> bool {
+        let Some(replay) = self.markets.get_mut(&tick.market_id) else {
+            return false;
+        };
+        let (Some(yes), Some(price)) = (replay.market.outcomes.first(), Decimal::from_f64(tick.price)) else {
+            return false;
+        };
+        let yes_price = if tick.outcome_id == yes.id {
+            price
+        } else if replay.market.outcomes.get(1).is_some_and(|no| no.id == tick.outcome_id) {
+            Decimal::ONE - price
+        } else {
+            return false;
+        };
+
+        replay.mark = Some(yes_price);
+        replay.price_history.push_back(PriceSnapshot {
+            outcome_id: yes.id.to_string(),
+            price: yes_price,
+            volume: Decimal::from_f64(tick.volume_24h).unwrap_or_default(),
+            liquidity: Decimal::from_f64(tick.liquidity).unwrap_or_default(),
+            timestamp: tick.timestamp,
+        });
+        while replay.price_history.len() > self.config.price_history_len {
+            replay.price_history.pop_front();
+        }
+        true
+    }
+
+    /// Run the pipeline on the market as of `at` and fill what it emits
+    async fn generate(&mut self, market_id: MarketId, at: DateTime<Utc>) -> Result<()> {
+        let (Some(replay), Some(pipeline)) = (self.markets.get(&market_id), self.pipeline.as_mut()) else {
+            return Ok(());
+        };
+        let input = SignalInput {
+            market: replay.market.clone(),
+            research_output: ResearchOutput {
+                market_id,
+                analysis: String::new(),
+                sentiment: SentimentScore { overall: 0.0, sources: vec![] },
+                confidence: 0.0,
+                probability_estimate: None,
+                key_factors: vec![],
+                timestamp: at,
+            },
+            order_book: replay.order_book.clone(),
+            price_history: replay.price_history.iter().cloned().collect(),
+        };
+
+        for mut signal in pipeline.process_at(&input, at).await? {
+            // Orders fill or miss as they arrive, so there is never a resting quote to cancel
+            if signal.is_cancel() {
+                continue;
+            }
+
+            // One position per market and direction
+            let holding = self
+                .open
+                .iter()
+                .any(|p| p.signal.market_id == signal.market_id && p.signal.direction == signal.direction);
+            if holding {
+                continue;
+            }
+
+            self.signals_generated += 1;
+            signal.id = Uuid::from_u64_pair(0, self.signals_generated);
+            let ttl = signal.expires_at.map(|expires_at| expires_at - signal.created_at);
+            signal.created_at = at;
+            signal.expires_at = ttl.map(|ttl| at + ttl);
+
+            self.enter(signal, input.order_book.as_ref(), at);
+        }
+
+        Ok(())
+    }
+
+    /// Fill `signal` and hold it, telling the generators about the fill
+    fn enter(&mut self, signal: TradeSignal, order_book: Option<&OrderBookSnapshot>, at: DateTime<Utc>) {
+        let Some((entry_price, size)) = self.entry_fill(&signal, order_book) else {
+            debug!("No fill for signal {} in market {}", signal.id, signal.market_id);
+            return;
+        };
+
+        self.feed_back(
+            signal.market_id,
+            &StateUpdate::TradeExecution {
+                signal_id: Some(signal.id),
+                outcome_id: signal.outcome_id.as_ref().map(|o| o.to_string()),
+                outcome_side: fill_side(&signal.direction),
+                side: OrderSide::Buy,
+                size,
+                price: entry_price,
+            },
+        );
+        debug!("Filled signal {} at {} for {}", signal.id, entry_price, size);
+        self.open.push(OpenPosition {
+            signal,
+            entry_price,
+            size,
+            opened_at: at,
+        });
+    }
+
+    fn feed_back(&mut self, market_id: MarketId, update: &StateUpdate) {
+        if let Some(pipeline) = &mut self.pipeline {
+            pipeline.update_state(market_id, update);
+        }
+    }
+
+    /// Price and size an entry fills at, or None if it doesn't fill
+    fn entry_fill(&self, signal: &TradeSignal, order_book: Option<&OrderBookSnapshot>) -> Option<(Decimal, Decimal)> {
+        let buying = match signal.direction {
+            SignalDirection::Long => true,
+            SignalDirection::Short => false,
+            SignalDirection::Neutral => return None,
+        };
+
+        match self.config.fill_model {
+            FillModel::Immediate { slippage_bps } => {
+                let slippage = signal.entry_price * Decimal::from(slippage_bps) / Decimal::from(10_000);
+                let price = if buying { signal.entry_price + slippage } else { signal.entry_price - slippage };
+                Some((price, signal.position_size))
+            }
+            FillModel::CrossBook => {
+                let book = order_book?;
+                let mut levels: Vec<&Level> = if buying { book.asks.iter().collect() } else { book.bids.iter().collect() };
+                if buying {
+                    levels.sort_by_key(|l| l.price);
+                } else {
+                    levels.sort_by_key(|l| std::cmp::Reverse(l.price));
+                }
+
+                let mut filled = Decimal::ZERO;
+                let mut cost = Decimal::ZERO;
+                for level in levels {
+                    let take = level.size.min(signal.position_size - filled);
+                    filled += take;
+                    cost += take * level.price;
+                    if filled >= signal.position_size {
+                        break;
+                    }
+                }
+                (filled > Decimal::ZERO).then(|| (cost / filled, filled))
+            }
+        }
+    }
+
+    /// Price an exit at `mark` fills at
+    fn exit_price(&self, position: &OpenPosition, mark: Decimal) -> Decimal {
+        match self.config.fill_model {
+            FillModel::Immediate { slippage_bps } => {
+                let slippage = mark * Decimal::from(slippage_bps) / Decimal::from(10_000);
+                match position.signal.direction {
+                    SignalDirection::Short => mark + slippage,
+                    _ => mark - slippage,
+                }
+            }
+            FillModel::CrossBook => mark,
+        }
+    }
+
+    /// Close the market's positions whose target or stop the mark has crossed
+    async fn check_exits(&mut self, market_id: MarketId, at: DateTime<Utc>) -> Result<()> {
+        let Some(mark) = self.markets.get(&market_id).and_then(|m| m.mark) else {
+            return Ok(());
+        };
+
+        let (exits, open): (Vec<_>, Vec<_>) = std::mem::take(&mut self.open)
+            .into_iter()
+            .map(|position| {
+                let signal = &position.signal;
+                let reason = if signal.market_id != market_id {
+                    None
+                } else {
+                    match signal.direction {
+                        SignalDirection::Long if mark >= signal.target_price => Some(ExitReason::TargetHit),
+                        SignalDirection::Long if mark <= signal.stop_loss => Some(ExitReason::StopLoss),
+                        SignalDirection::Short if mark <= signal.target_price => Some(ExitReason::TargetHit),
+                        SignalDirection::Short if mark >= signal.stop_loss => Some(ExitReason::StopLoss),
+                        _ => None,
+                    }
+                };
+                (position, reason)
+            })
+            .partition(|(_, reason)| reason.is_some());
+        self.open = open.into_iter().map(|(position, _)| position).collect();
+
+        for (position, reason) in exits {
+            let exit = self.exit_price(&position, mark);
+            self.close(position, exit, reason.unwrap_or(ExitReason::Manual), at).await?;
+        }
+        Ok(())
+    }
+
+    /// Close positions whose signal has expired, at the last price
+    async fn expire(&mut self, at: DateTime<Utc>) -> Result<()> {
+        let (expired, open): (Vec<_>, Vec<_>) = std::mem::take(&mut self.open)
+            .into_iter()
+            .partition(|p| p.signal.expires_at.is_some_and(|expires_at| expires_at <= at));
+        self.open = open;
+
+        for position in expired {
+            let mark = self.markets.get(&position.signal.market_id).and_then(|m| m.mark);
+            let exit = mark.map_or(position.entry_price, |mark| self.exit_price(&position, mark));
+            self.close(position, exit, ExitReason::SignalExpired, at).await?;
+        }
+        Ok(())
+    }
+
+    /// Close a resolved market's positions at 1 if YES won, 0 otherwise
+    async fn resolve(&mut self, market_id: MarketId, outcome_id: &OutcomeId, at: DateTime<Utc>) -> Result<()> {
+        let Some(replay) = self.markets.remove(&market_id) else {
+            return Ok(());
+        };
+        let yes_won = replay.market.outcomes.first().is_some_and(|yes| yes.id == *outcome_id);
+        let payout = if yes_won { Decimal::ONE } else { Decimal::ZERO };
+        for position in self.take_open(market_id) {
+            self.close(position, payout, ExitReason::MarketResolved, at).await?;
+        }
+        Ok(())
+    }
+
+    fn take_open(&mut self, market_id: MarketId) -> Vec<OpenPosition> {
+        let (closing, open): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut self.open).into_iter().partition(|p| p.signal.market_id == market_id);
+        self.open = open;
+        closing
+    }
+
+    /// Record the exit and feed it back to the generators
+    async fn close(&mut self, position: OpenPosition, exit_price: Decimal, reason: ExitReason, at: DateTime<Utc>) -> Result<()> {
+        let signal = &position.signal;
+        let pnl = match signal.direction {
+            SignalDirection::Short => (position.entry_price - exit_price) * position.size,
+            _ => (exit_price - position.entry_price) * position.size,
+        };
+        let cost = position.entry_price * position.size;
+        let pnl_percentage = (cost > Decimal::ZERO).then(|| pnl / cost * Decimal::from(100));
+
+        let result = SignalExecutionResult {
+            signal_id: signal.id,
+            market_id: signal.market_id,
+            outcome_id: signal.outcome_id.as_ref().map(|o| o.to_string()),
+            executed_at: position.opened_at,
+            entry_price: position.entry_price,
+            exit_price: Some(exit_price),
+            position_size: position.size,
+            pnl: Some(pnl),
+            pnl_percentage,
+            holding_period_hours: Some((at - position.opened_at).num_milliseconds() as f64 / 3_600_000.0),
+            exit_reason: reason,
+            rebate_pnl: None,
+            signal_type: Some(signal.signal_type.clone()),
+        };
+        debug!("Closed signal {} at {} ({:?}), pnl {}", signal.id, exit_price, result.exit_reason, pnl);
+        self.storage.store(&result).await?;
+
+        self.feed_back(
+            signal.market_id,
+            &StateUpdate::PositionClosed {
+                outcome_id: result.outcome_id.clone(),
+                outcome_side: fill_side(&signal.direction),
+                side: OrderSide::Buy,
+                size: position.size,
+                realized_pnl: pnl,
+            },
+        );
+        Ok(())
+    }
+}
+
+/// Outcome a fill is reported on; every signal is a buy, a Short of NO
+fn fill_side(direction: &SignalDirection) -> PairSide {
+    match direction {
+        SignalDirection::Short => PairSide::No,
+        _ => PairSide::Yes,
+    }
+}
+
+fn book_snapshot(book: &OrderBook) -> OrderBookSnapshot {
+    let levels = |orders: &[common::Order]| {
+        orders
+            .iter()
+            .map(|o| Level {
+                outcome_id: o.outcome_id.to_string(),
+                price: Decimal::from_f64(o.price).unwrap_or_default(),
+                size: Decimal::from_f64(o.size).unwrap_or_default(),
+            })
+            .collect()
+    };
+
+    OrderBookSnapshot {
+        market_id: book.market_id,
+        bids: levels(&book.bids),
+        asks: levels(&book.asks),
+        timestamp: book.timestamp,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_book_imbalance::{OrderBookImbalanceConfig, OrderBookImbalanceGenerator};
+    use crate::pipeline::PipelineConfig;
+    use common::SignalType;
+
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/backtest_scenario.jsonl");
+
+    fn backtester(fill_model: FillModel) -> Backtester {
+        let pipeline = SignalPipeline::new(PipelineConfig {
+            min_edge: Decimal::new(1, 2),
+            ..PipelineConfig::default()
+        })
+        .add_generator(Box::new(OrderBookImbalanceGenerator::new(OrderBookImbalanceConfig::default())));
+        Backtester::new(BacktestConfig { fill_model, ..BacktestConfig::default() }, pipeline)
+    }
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str_exact(s).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_fixture_replay_known_pnl() {
+        // A: bid-heavy book, Long 100 @ 0.50, YES ticks to 0.53: +3.00
+        // B: ask-heavy book, Short 100 @ 0.50, YES ticks to 0.515: -1.50
+        // C: bid-heavy book, Long 100 @ 0.50, resolves NO: -50.00
+        let events = Backtester::load_events(FIXTURE).unwrap();
+        let mut replay = backtester(FillModel::default());
+        let stats = replay.run(events.clone()).await.unwrap();
+
+        assert_eq!(stats.total_trades, 3);
+        assert_eq!(stats.winning_trades, 1);
+        assert_eq!(stats.losing_trades, 2);
+        assert_eq!(stats.total_pnl, dec("-48.5"));
+        assert_eq!(stats.by_signal_type["Momentum"].total_trades, 3);
+
+        let market_a = events[0].market_id();
+        let results = replay.storage().get_by_market(market_a).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].exit_reason, ExitReason::TargetHit);
+        assert_eq!(results[0].pnl, Some(dec("3")));
+        assert_eq!(results[0].signal_type, Some(SignalType::Momentum));
+
+        // Same events, same report
+        let again = backtester(FillModel::default()).run(events).await.unwrap();
+        assert_eq!(serde_json::to_value(&again).unwrap(), serde_json::to_value(&stats).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_fill_models() {
+        let events = Backtester::load_events(FIXTURE).unwrap();
+        let market_a = events[0].market_id();
+
+        // 100 bps: in at 0.505, out at 0.5247: +1.97
+        let mut slipped = backtester(FillModel::Immediate { slippage_bps: 100 });
+        slipped.run(events.clone()).await.unwrap();
+        let result = &slipped.storage().get_by_market(market_a).await.unwrap()[0];
+        assert_eq!(result.entry_price, dec("0.505"));
+        assert_eq!(result.pnl, Some(dec("1.97")));
+
+        // A's asks are 60 @ 0.51 and 40 @ 0.52: in at 0.514, out at 0.53: +1.60
+        let mut crossed = backtester(FillModel::CrossBook);
+        crossed.run(events).await.unwrap();
+        let result = &crossed.storage().get_by_market(market_a).await.unwrap()[0];
+        assert_eq!(result.entry_price, dec("0.514"));
+        assert_eq!(result.position_size, dec("100"));
+        assert_eq!(result.pnl, Some(dec("1.6")));
+    }
+
+    fn at(minutes: i64) -> DateTime<Utc> {
+        DateTime::UNIX_EPOCH + chrono::Duration::minutes(minutes)
+    }
+
+    fn tick(market_id: MarketId, minutes: i64, price: &str) -> MarketEvent {
+        MarketEvent::PriceTick(PriceTick {
+            market_id,
+            outcome_id: "yes".into(),
+            price: price.parse().unwrap(),
+            volume_24h: 0.0,
+            liquidity: 0.0,
+            timestamp: at(minutes),
+        })
+    }
+
+    fn stored(
+        market_id: MarketId,
+        signal_type: SignalType,
+        direction: SignalDirection,
+        target: &str,
+        stop: &str,
+        expires_after: Option<i64>,
+    ) -> TradeSignal {
+        TradeSignal {
+            id: Uuid::new_v4(),
+            market_id,
+            signal_type,
+            direction,
+            outcome_id: Some("yes".into()),
+            entry_price: dec("0.50"),
+            target_price: dec(target),
+            stop_loss: dec(stop),
+            position_size: Decimal::from(100),
+            confidence: 0.7,
+            expected_value: Decimal::ONE,
+            edge: dec("0.05"),
+            kelly_fraction: 0.1,
+            reasoning: String::new(),
+            metadata: crate::signals::SignalMetadata {
+                research_sources: vec![],
+                data_points: 0,
+                liquidity_score: 0.5,
+                volatility_score: 0.0,
+                price_age_ms: None,
+                orderbook_age_ms: None,
+                research_age_ms: None,
+                custom_fields: serde_json::json!({}),
+            },
+            created_at: at(1),
+            expires_at: expires_after.map(|minutes| at(1 + minutes)),
+            variant: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stored_signals_classify_exits() {
+        let market_id = MarketId::random();
+        let outcome = |id: &str| common::Outcome {
+            id: id.into(),
+            name: id.into(),
+            price: 0.5,
+            liquidity: 0.0,
+        };
+        let market = Market {
+            id: market_id,
+            condition_id: "0xscript".into(),
+            question: "Scripted".into(),
+            description: String::new(),
+            category: "politics".into(),
+            outcomes: vec![outcome("yes"), outcome("no")],
+            created_at: at(0),
+            updated_at: at(0),
+            neg_risk_group: None,
+            tick_size: None,
+        };
+
+        let win = stored(market_id, SignalType::Momentum, SignalDirection::Long, "0.55", "0.45", None);
+        let stopped = stored(market_id, SignalType::MeanReversion, SignalDirection::Short, "0.40", "0.55", None);
+        let expired = stored(market_id, SignalType::Momentum, SignalDirection::Long, "0.70", "0.30", Some(2));
+        let resolved = stored(market_id, SignalType::Value, SignalDirection::Long, "0.90", "0.10", None);
+        // Created after the market resolved: never entered
+        let late = TradeSignal {
+            created_at: at(10),
+            ..stored(market_id, SignalType::Value, SignalDirection::Long, "0.90", "0.10", None)
+        };
+        let ids = [win.id, stopped.id, expired.id, resolved.id, late.id];
+
+        // All enter at 0.50 at minute 1; expiry is checked before minute 3's tick,
+        // at the 0.53 mark, and 0.56 then crosses a target and a stop
+        let events = vec![
+            MarketEvent::MarketCreated(market),
+            tick(market_id, 1, "0.50"),
+            tick(market_id, 2, "0.53"),
+            tick(market_id, 3, "0.56"),
+            tick(market_id, 4, "0.60"),
+            MarketEvent::MarketResolved { market_id, outcome_id: "yes".into() },
+            tick(market_id, 10, "1.0"),
+        ];
+        let signals = vec![late, resolved, expired, stopped, win];
+        let mut replay = Backtester::from_signals(BacktestConfig::default(), signals.clone());
+        let stats = replay.run(events.clone()).await.unwrap();
+
+        let expected = [
+            (ExitReason::TargetHit, "6"),
+            (ExitReason::StopLoss, "-6"),
+            (ExitReason::SignalExpired, "3"),
+            (ExitReason::MarketResolved, "50"),
+        ];
+        for (id, (reason, pnl)) in ids.iter().zip(&expected) {
+            let result = replay.storage().get_by_signal(*id).await.unwrap().unwrap();
+            assert_eq!(result.exit_reason, *reason);
+            assert_eq!(result.pnl, Some(dec(pnl)));
+            assert_eq!(result.executed_at, at(1));
+        }
+        assert!(replay.storage().get_by_signal(ids[4]).await.unwrap().is_none());
+
+        assert_eq!(stats.total_trades, 4);
+        assert_eq!(stats.winning_trades, 3);
+        assert_eq!(stats.losing_trades, 1);
+        assert_eq!(stats.total_pnl, dec("53"));
+        assert_eq!(stats.by_signal_type["Momentum"].total_trades, 2);
+        assert_eq!(stats.by_signal_type["Momentum"].total_pnl, dec("9"));
+        assert_eq!(stats.by_signal_type["MeanReversion"].winning_trades, 0);
+
+        // Same signals in any order, same report
+        let again = Backtester::from_signals(BacktestConfig::default(), signals.into_iter().rev())
+            .run(events)
+            .await
+            .unwrap();
+        assert_eq!(serde_json::to_value(&again).unwrap(), serde_json::to_value(&stats).unwrap());
+    }
+}
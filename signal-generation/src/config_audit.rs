@@ -0,0 +1,4 @@
+// Config Audit
+// Record of configuration changes applied at runtime; shared with other layers via common
+
+pub use common::{ConfigAudit, ConfigChange, InMemoryConfigAudit};
@@ -1,19 +1,33 @@
 // Correlation Analysis & Logical Arbitrage
 // Detects pricing inconsistencies between correlated markets
 
-use chrono::{DateTime, Utc};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use common::{Market, MarketId, OutcomeId};
+use rust_decimal::prelude::FromPrimitive;
 use rust_decimal::Decimal;
+use serde::Serialize;
 use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-use common::Market;
+use crate::config_audit::{ConfigAudit, ConfigChange};
+use crate::correlation_config::{
+    default_min_spread, CorrelationConfig, CorrelationRule, MarketRegistry,
+};
+use crate::diagnostics::SkipReason;
 use crate::signals::{
-    SignalInput, SignalDirection, SignalGenerator, SignalMetadata, SignalType, TradeSignal,
-    StateUpdate, MultiSignalGenerator,
+    BatchSignalGenerator, SignalInput, SignalDirection, SignalMetadata, SignalType,
+    TradeSignal,
 };
 
 /// Correlation relationship type
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum CorrelationType {
     /// A implies B (100% correlation) - e.g., "Trump wins" implies "Republican wins"
     Implies,
@@ -28,19 +42,38 @@ pub enum CorrelationType {
 }
 
 /// Correlation edge between markets
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct CorrelationEdge {
-    pub from_market: Uuid,
-    pub to_market: Uuid,
+    pub from_market: MarketId,
+    pub to_market: MarketId,
     pub correlation_type: CorrelationType,
     pub min_spread: Decimal, // Minimum spread to trigger arbitrage
 }
 
-/// Logical arbitrage opportunity
+/// Kind of constraint on a group of markets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupType {
+    /// Exactly one member resolves YES - prices must sum to 100%
+    MutuallyExclusive,
+    /// At most one member resolves YES - prices must sum to <= 100%
+    Cumulative,
+}
+
+/// Constraint on the prices of a set of markets, e.g. "which candidate wins the nomination"
 #[derive(Debug, Clone)]
+pub struct CorrelationGroup {
+    pub group_type: GroupType,
+    pub markets: Vec<MarketId>,
+    pub min_spread: Decimal, // Minimum deviation of the sum from 100% to trigger arbitrage
+    /// Members with less order book liquidity are left out of the trades
+    pub min_liquidity: Decimal,
+}
+
+/// Logical arbitrage opportunity
+#[derive(Debug, Clone, Serialize)]
 pub struct LogicalArbitrageOpportunity {
     pub id: Uuid,
-    pub markets: Vec<Uuid>,
+    pub markets: Vec<MarketId>,
     pub opportunity_type: String,
     pub violation_amount: Decimal, // How much the probabilities violate logic
     pub description: String,
@@ -49,31 +82,26 @@ pub struct LogicalArbitrageOpportunity {
 }
 
 /// Trade in an arbitrage opportunity
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ArbitrageTrade {
-    pub market_id: Uuid,
-    pub outcome_id: Option<String>,
+    pub market_id: MarketId,
+    pub outcome_id: Option<OutcomeId>,
     pub direction: SignalDirection,
     pub entry_price: Decimal,
     pub position_size: Decimal,
 }
 
 /// Correlation graph for market relationships
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct CorrelationGraph {
     /// Market UUID -> Price/Probability
-    market_prices: HashMap<Uuid, Decimal>,
+    market_prices: HashMap<MarketId, Decimal>,
+    /// Market UUID -> Order book liquidity
+    market_liquidity: HashMap<MarketId, Decimal>,
     /// Correlation edges
     edges: Vec<CorrelationEdge>,
-}
-
-impl Default for CorrelationGraph {
-    fn default() -> Self {
-        CorrelationGraph {
-            market_prices: HashMap::new(),
-            edges: Vec::new(),
-        }
-    }
+    /// Constraints on more than two markets
+    groups: Vec<CorrelationGroup>,
 }
 
 impl CorrelationGraph {
@@ -82,15 +110,70 @@ impl CorrelationGraph {
     }
 
     /// Update market price
-    pub fn update_price(&mut self, market_id: Uuid, price: Decimal) {
+    pub fn update_price(&mut self, market_id: MarketId, price: Decimal) {
         self.market_prices.insert(market_id, price);
     }
 
+    /// Update market liquidity
+    pub fn update_liquidity(&mut self, market_id: MarketId, liquidity: Decimal) {
+        self.market_liquidity.insert(market_id, liquidity);
+    }
+
     /// Add correlation edge
     pub fn add_edge(&mut self, edge: CorrelationEdge) {
         self.edges.push(edge);
     }
 
+    /// Add `Implies` edges between consecutive markets, so `[a, b, c]`
+    /// adds a ⇒ b and b ⇒ c; a ⇒ c follows from them in `find_violations`
+    pub fn add_implication_chain(&mut self, markets: &[MarketId]) {
+        for pair in markets.windows(2) {
+            self.add_edge(CorrelationEdge {
+                from_market: pair[0],
+                to_market: pair[1],
+                correlation_type: CorrelationType::Implies,
+                min_spread: default_min_spread(),
+            });
+        }
+    }
+
+    /// Add correlation group
+    pub fn add_group(&mut self, group: CorrelationGroup) {
+        self.groups.push(group);
+    }
+
+    /// Add a `MutuallyExclusive` group over every outcome of one question,
+    /// at the default spread and with no liquidity minimum; the set is
+    /// shorted when its prices sum past 1 + spread and bought below 1 - spread
+    pub fn add_exclusive_set(&mut self, markets: Vec<MarketId>) {
+        self.add_group(CorrelationGroup {
+            group_type: GroupType::MutuallyExclusive,
+            markets,
+            min_spread: default_min_spread(),
+            min_liquidity: Decimal::ZERO,
+        });
+    }
+
+    /// Current correlation groups
+    pub fn groups(&self) -> &[CorrelationGroup] {
+        &self.groups
+    }
+
+    /// Replace all correlation groups, keeping known prices
+    pub fn set_groups(&mut self, groups: Vec<CorrelationGroup>) {
+        self.groups = groups;
+    }
+
+    /// Replace all correlation edges, keeping known prices
+    pub fn set_edges(&mut self, edges: Vec<CorrelationEdge>) {
+        self.edges = edges;
+    }
+
+    /// Current correlation edges
+    pub fn edges(&self) -> &[CorrelationEdge] {
+        &self.edges
+    }
+
     /// Find logical violations
     pub fn find_violations(&self) -> Vec<LogicalArbitrageOpportunity> {
         let mut violations = Vec::new();
@@ -126,13 +209,153 @@ impl CorrelationGraph {
             }
         }
 
+        for (edge, chain) in self.implication_chains() {
+            if let Some(mut violation) = self.check_implication(&edge) {
+                let via: Vec<String> = chain[1..chain.len() - 1].iter().map(|m| m.to_string()).collect();
+                violation.opportunity_type = "Transitive Implication Violation".to_string();
+                violation.description = format!("{} via {}", violation.description, via.join(", "));
+                violations.push(violation);
+            }
+        }
+
+        for group in &self.groups {
+            if let Some(violation) = self.check_group(group) {
+                violations.push(violation);
+            }
+        }
+
         violations
     }
 
+    /// Implications that follow from chains of `Implies` edges without being
+    /// edges themselves, each with the chain it follows from
+    ///
+    /// A derived edge takes the largest `min_spread` along its chain. Each
+    /// source visits a market at most once, so cycles end the walk.
+    fn implication_chains(&self) -> Vec<(CorrelationEdge, Vec<MarketId>)> {
+        let mut implies: HashMap<MarketId, Vec<(MarketId, Decimal)>> = HashMap::new();
+        let mut sources = Vec::new();
+        for edge in &self.edges {
+            if edge.correlation_type == CorrelationType::Implies {
+                if !implies.contains_key(&edge.from_market) {
+                    sources.push(edge.from_market);
+                }
+                implies.entry(edge.from_market).or_default().push((edge.to_market, edge.min_spread));
+            }
+        }
+
+        let mut derived = Vec::new();
+        for source in sources {
+            let mut visited = HashSet::from([source]);
+            let mut queue = std::collections::VecDeque::from([(vec![source], Decimal::ZERO)]);
+            while let Some((chain, spread)) = queue.pop_front() {
+                let last = *chain.last().unwrap();
+                for &(next, min_spread) in implies.get(&last).into_iter().flatten() {
+                    if !visited.insert(next) {
+                        continue;
+                    }
+                    let mut chain = chain.clone();
+                    chain.push(next);
+                    let spread = spread.max(min_spread);
+                    if chain.len() > 2 {
+                        derived.push((
+                            CorrelationEdge {
+                                from_market: source,
+                                to_market: next,
+                                correlation_type: CorrelationType::Implies,
+                                min_spread: spread,
+                            },
+                            chain.clone(),
+                        ));
+                    }
+                    queue.push_back((chain, spread));
+                }
+            }
+        }
+        derived
+    }
+
+    /// Check if a group's prices sum past 100% (short the set) or, for an
+    /// exhaustive group, below it (buy the whole set).
+    ///
+    /// Trades hold equal shares of every member, so each is sized in
+    /// proportion to its price and exactly one payout is covered.
+    fn check_group(&self, group: &CorrelationGroup) -> Option<LogicalArbitrageOpportunity> {
+        // Every member needs a price, or the sum means nothing
+        let prices: Vec<(MarketId, Decimal)> = group
+            .markets
+            .iter()
+            .map(|market_id| Some((*market_id, *self.market_prices.get(market_id)?)))
+            .collect::<Option<_>>()?;
+
+        // Markets with no known liquidity are assumed tradable
+        let (tradable, illiquid): (Vec<_>, Vec<_>) =
+            prices.into_iter().partition(|(market_id, _)| {
+                self.market_liquidity
+                    .get(market_id)
+                    .is_none_or(|liquidity| *liquidity >= group.min_liquidity)
+            });
+
+        let sum: Decimal = tradable.iter().map(|(_, price)| *price).sum();
+        if tradable.len() < 2 || sum <= Decimal::ZERO {
+            return None;
+        }
+
+        // Shorting any subset is safe, since at most one member pays out.
+        // Buying the set is only safe if it is exhaustive and complete.
+        let (direction, violation, label) = if sum > Decimal::ONE + group.min_spread {
+            (SignalDirection::Short, sum - Decimal::ONE, "Overpriced")
+        } else if group.group_type == GroupType::MutuallyExclusive
+            && illiquid.is_empty()
+            && sum < Decimal::ONE - group.min_spread
+        {
+            (SignalDirection::Long, Decimal::ONE - sum, "Underpriced")
+        } else {
+            return None;
+        };
+
+        let notional = Decimal::from(100);
+        let shares = notional / sum;
+        let group_name = match group.group_type {
+            GroupType::MutuallyExclusive => "Mutually Exclusive",
+            GroupType::Cumulative => "Cumulative",
+        };
+
+        Some(LogicalArbitrageOpportunity {
+            id: Uuid::new_v4(),
+            markets: tradable.iter().map(|(market_id, _)| *market_id).collect(),
+            opportunity_type: format!("{} Group {}", group_name, label),
+            violation_amount: violation,
+            description: format!(
+                "{} group of {} markets sums to {:.4}% (violation: {:.4}%{})",
+                group_name,
+                tradable.len(),
+                sum * Decimal::from(100),
+                violation * Decimal::from(100),
+                if illiquid.is_empty() {
+                    String::new()
+                } else {
+                    format!(", {} illiquid markets excluded", illiquid.len())
+                }
+            ),
+            trades: tradable
+                .iter()
+                .map(|(market_id, price)| ArbitrageTrade {
+                    market_id: *market_id,
+                    outcome_id: None,
+                    direction: direction.clone(),
+                    entry_price: *price,
+                    position_size: shares * *price,
+                })
+                .collect(),
+            expected_profit: shares * violation,
+        })
+    }
+
     /// Check if A implies B but P(A) > P(B) (logical impossibility)
     fn check_implication(&self, edge: &CorrelationEdge) -> Option<LogicalArbitrageOpportunity> {
-        let price_a = self.market_prices.get(&edge.from_market)?;
-        let price_b = self.market_prices.get(&edge.to_market)?;
+        let price_a = *self.market_prices.get(&edge.from_market)?;
+        let price_b = *self.market_prices.get(&edge.to_market)?;
 
         // If A implies B, then P(A) cannot be > P(B)
         if price_a > price_b {
@@ -181,8 +404,8 @@ impl CorrelationGraph {
         edge: &CorrelationEdge,
         strength: Decimal,
     ) -> Option<LogicalArbitrageOpportunity> {
-        let price_a = self.market_prices.get(&edge.from_market)?;
-        let price_b = self.market_prices.get(&edge.to_market)?;
+        let price_a = *self.market_prices.get(&edge.from_market)?;
+        let price_b = *self.market_prices.get(&edge.to_market)?;
 
         // If A suggests B with strength S, then P(A) <= P(B) * S
         let implied_price_b = price_a / strength;
@@ -233,8 +456,8 @@ impl CorrelationGraph {
         &self,
         edge: &CorrelationEdge,
     ) -> Option<LogicalArbitrageOpportunity> {
-        let price_a = self.market_prices.get(&edge.from_market)?;
-        let price_b = self.market_prices.get(&edge.to_market)?;
+        let price_a = *self.market_prices.get(&edge.from_market)?;
+        let price_b = *self.market_prices.get(&edge.to_market)?;
 
         let sum = price_a + price_b;
 
@@ -290,8 +513,8 @@ impl CorrelationGraph {
         &self,
         edge: &CorrelationEdge,
     ) -> Option<LogicalArbitrageOpportunity> {
-        let price_a = self.market_prices.get(&edge.from_market)?;
-        let price_b = self.market_prices.get(&edge.to_market)?;
+        let price_a = *self.market_prices.get(&edge.from_market)?;
+        let price_b = *self.market_prices.get(&edge.to_market)?;
 
         let diff = (price_a - price_b).abs();
 
@@ -327,12 +550,114 @@ impl CorrelationGraph {
 /// Correlation-based signal generator
 pub struct CorrelationGenerator {
     graph: CorrelationGraph,
+    /// Minimum time before the same opportunity is signalled again
+    cooldown: Duration,
+    /// (markets, opportunity type) -> last time it was signalled
+    recently_signalled: HashMap<(Vec<MarketId>, String), DateTime<Utc>>,
+    /// Markets of opportunities held back by the cooldown in the last batch
+    cooldown_skips: Vec<MarketId>,
+    /// Edges added in code, kept across config reloads
+    manual_edges: Vec<CorrelationEdge>,
+    /// Groups added in code, kept across config reloads
+    manual_groups: Vec<CorrelationGroup>,
+    /// Rules from the config, keyed by market identifier
+    rules: Vec<CorrelationRule>,
+    /// Rules whose markets have not all been seen yet
+    pending_rules: usize,
+    /// Identifiers already reported as unknown
+    unknown_logged: HashSet<String>,
+    registry: MarketRegistry,
+    config_file: Option<ConfigFile>,
+    reload_requested: Arc<AtomicBool>,
+    /// Reloads that would emit more signals than this right away wait for `apply_staged`
+    max_auto_apply_signals: usize,
+    /// Reloaded rules waiting for approval
+    staged: Option<StagedRules>,
+    audit: Option<Arc<dyn ConfigAudit>>,
+}
+
+/// Default for `with_apply_gate`
+pub const DEFAULT_MAX_AUTO_APPLY_SIGNALS: usize = 5;
+
+/// What a rule change would do at current prices
+#[derive(Debug, Clone, Serialize)]
+pub struct RulePreview {
+    pub id: Uuid,
+    /// Rules in the new config
+    pub rules: usize,
+    /// Rules whose markets have not all been seen yet
+    pub pending_rules: usize,
+    pub added_edges: Vec<CorrelationEdge>,
+    pub removed_edges: Vec<CorrelationEdge>,
+    /// Violations the new rules report right away that the current ones don't
+    pub new_violations: Vec<LogicalArbitrageOpportunity>,
+    /// Current violations that the new rules no longer report
+    pub cleared_violations: Vec<LogicalArbitrageOpportunity>,
+}
+
+impl RulePreview {
+    /// Signals the change would generate on the next batch
+    pub fn signal_count(&self) -> usize {
+        self.new_violations.iter().map(|v| v.trades.len()).sum()
+    }
+
+    /// One line for logs and the CLI
+    pub fn summary(&self) -> String {
+        format!(
+            "{} rules: +{} / -{} edges, {} new violations ({} signals)",
+            self.rules,
+            self.added_edges.len(),
+            self.removed_edges.len(),
+            self.new_violations.len(),
+            self.signal_count()
+        )
+    }
+}
+
+/// Result of a config reload
+#[derive(Debug, Clone)]
+pub enum ReloadOutcome {
+    /// The new rules are in effect
+    Applied(RulePreview),
+    /// The new rules would generate too many signals at once and wait for `apply_staged`
+    Staged(RulePreview),
+    /// The generator has no config file
+    NoConfigFile,
+}
+
+struct StagedRules {
+    preview: RulePreview,
+    rules: Vec<CorrelationRule>,
+    source: String,
+}
+
+/// Config file backing the rules, for hot reload
+struct ConfigFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    /// How often to check the file for changes, if at all
+    watch_interval: Option<std::time::Duration>,
+    last_checked: Instant,
 }
 
 impl Default for CorrelationGenerator {
     fn default() -> Self {
         CorrelationGenerator {
             graph: CorrelationGraph::new(),
+            cooldown: Duration::minutes(10), // Signal validity
+            recently_signalled: HashMap::new(),
+            cooldown_skips: Vec::new(),
+            manual_edges: Vec::new(),
+            manual_groups: Vec::new(),
+            rules: Vec::new(),
+            pending_rules: 0,
+            unknown_logged: HashSet::new(),
+            registry: MarketRegistry::new(),
+            config_file: None,
+            reload_requested: Arc::new(AtomicBool::new(false)),
+            max_auto_apply_signals: DEFAULT_MAX_AUTO_APPLY_SIGNALS,
+            staged: None,
+            audit: None,
         }
     }
 }
@@ -342,11 +667,350 @@ impl CorrelationGenerator {
         Self::default()
     }
 
+    /// Set the re-signal cooldown
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Create a generator whose edges come from a TOML correlation config.
+    ///
+    /// Rules reference markets by condition_id (or any alias registered via
+    /// `registry_mut`). Rules on markets not seen yet are kept and resolved
+    /// as soon as the market shows up in a batch.
+    pub fn from_config_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let config = CorrelationConfig::from_file(path)?;
+
+        let mut generator = Self::new().with_config(config)?;
+        generator.config_file = Some(ConfigFile {
+            path: path.to_path_buf(),
+            modified: file_modified(path),
+            watch_interval: None,
+            last_checked: Instant::now(),
+        });
+
+        info!("Loaded {} correlation rules from {}", generator.rules.len(), path.display());
+        Ok(generator)
+    }
+
+    /// Replace the config-derived rules
+    pub fn with_config(mut self, config: CorrelationConfig) -> Result<Self> {
+        self.rules = config.rules()?;
+        self.rebuild_edges();
+        Ok(self)
+    }
+
+    /// Re-read the config file whenever it changes, checking at most once per `interval`
+    pub fn with_watch_interval(mut self, interval: std::time::Duration) -> Self {
+        if let Some(file) = &mut self.config_file {
+            file.watch_interval = Some(interval);
+        }
+        self
+    }
+
+    /// Require `apply_staged` for reloads that would immediately generate
+    /// more than `max_signals` signals (default 5)
+    pub fn with_apply_gate(mut self, max_signals: usize) -> Self {
+        self.max_auto_apply_signals = max_signals;
+        self
+    }
+
+    /// Record applied rule changes
+    pub fn with_audit(mut self, audit: Arc<dyn ConfigAudit>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Flag that makes the next batch reload the config file (see `reload_on_sighup`)
+    pub fn reload_handle(&self) -> Arc<AtomicBool> {
+        self.reload_requested.clone()
+    }
+
+    /// Re-read the config file now.
+    ///
+    /// The new rules are validated against known markets and previewed at
+    /// current prices. They take effect right away unless they would
+    /// generate more signals than the apply gate allows, in which case they
+    /// are staged for `apply_staged`. On error the current rules stay in
+    /// effect.
+    pub fn reload_config(&mut self) -> Result<ReloadOutcome> {
+        let Some(file) = &mut self.config_file else {
+            return Ok(ReloadOutcome::NoConfigFile);
+        };
+
+        file.last_checked = Instant::now();
+        file.modified = file_modified(&file.path);
+        let path = file.path.clone();
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read correlation config {}", path.display()))?;
+        let config = CorrelationConfig::validate(&content, Some(&self.registry))
+            .with_context(|| format!("Invalid correlation config {}", path.display()))?;
+
+        let rules = config.rules()?;
+        let preview = self.preview(&rules);
+        let staged = StagedRules {
+            preview: preview.clone(),
+            rules,
+            source: path.display().to_string(),
+        };
+
+        if preview.signal_count() > self.max_auto_apply_signals {
+            warn!(
+                "Correlation config {} staged for approval, it would generate {} signals at once: {}",
+                path.display(),
+                preview.signal_count(),
+                preview.summary()
+            );
+            self.staged = Some(staged);
+            return Ok(ReloadOutcome::Staged(preview));
+        }
+
+        self.staged = None;
+        self.apply(staged, None);
+        Ok(ReloadOutcome::Applied(preview))
+    }
+
+    /// Preview a config against known markets and current prices without applying it
+    pub fn preview_config(&self, config: &CorrelationConfig) -> Result<RulePreview> {
+        Ok(self.preview(&config.rules()?))
+    }
+
+    /// Reloaded rules waiting for approval, if any
+    pub fn staged_change(&self) -> Option<&RulePreview> {
+        self.staged.as_ref().map(|staged| &staged.preview)
+    }
+
+    /// Apply the staged rules, if `preview_id` is the preview that was reviewed
+    pub fn apply_staged(&mut self, preview_id: Uuid, approved_by: &str) -> Result<RulePreview> {
+        match &self.staged {
+            None => bail!("No correlation rule change is staged"),
+            Some(staged) if staged.preview.id != preview_id => bail!(
+                "Preview {} is not the staged change {}, review it again",
+                preview_id,
+                staged.preview.id
+            ),
+            Some(_) => {}
+        }
+
+        let staged = self.staged.take().unwrap();
+        let preview = staged.preview.clone();
+        self.apply(staged, Some(approved_by));
+        Ok(preview)
+    }
+
+    /// Drop the staged rules, keeping the current ones
+    pub fn discard_staged(&mut self) -> Option<RulePreview> {
+        self.staged.take().map(|staged| staged.preview)
+    }
+
+    /// Put reloaded rules into effect and record the change
+    fn apply(&mut self, staged: StagedRules, approved_by: Option<&str>) {
+        info!(
+            "Applied {} correlation rules from {}: {}",
+            staged.rules.len(),
+            staged.source,
+            staged.preview.summary()
+        );
+        self.rules = staged.rules;
+        self.unknown_logged.clear();
+        self.rebuild_edges();
+
+        if let Some(audit) = &self.audit {
+            audit.record(ConfigChange {
+                id: staged.preview.id,
+                component: "correlation_rules".to_string(),
+                source: staged.source,
+                summary: staged.preview.summary(),
+                approved_by: approved_by.map(str::to_string),
+                applied_at: Utc::now(),
+            });
+        }
+    }
+
+    /// Compare the graph `rules` would give with the current one
+    fn preview(&self, rules: &[CorrelationRule]) -> RulePreview {
+        let (edges, groups, pending) = self.resolve_rules(rules);
+        let mut graph = self.graph.clone();
+        graph.set_edges(edges);
+        graph.set_groups(groups);
+
+        let key = |v: &LogicalArbitrageOpportunity| (v.markets.clone(), v.opportunity_type.clone());
+        let current = self.graph.find_violations();
+        let proposed = graph.find_violations();
+        let current_keys: HashSet<_> = current.iter().map(key).collect();
+        let proposed_keys: HashSet<_> = proposed.iter().map(key).collect();
+
+        RulePreview {
+            id: Uuid::new_v4(),
+            rules: rules.len(),
+            pending_rules: pending.len(),
+            added_edges: graph
+                .edges()
+                .iter()
+                .filter(|e| !self.graph.edges().contains(e))
+                .cloned()
+                .collect(),
+            removed_edges: self
+                .graph
+                .edges()
+                .iter()
+                .filter(|e| !graph.edges().contains(e))
+                .cloned()
+                .collect(),
+            new_violations: proposed.into_iter().filter(|v| !current_keys.contains(&key(v))).collect(),
+            cleared_violations: current.into_iter().filter(|v| !proposed_keys.contains(&key(v))).collect(),
+        }
+    }
+
+    /// Map identifiers such as slugs to markets
+    pub fn registry_mut(&mut self) -> &mut MarketRegistry {
+        &mut self.registry
+    }
+
+    /// Make a market resolvable by config rules.
+    ///
+    /// Members of a negRisk group are linked to the rest of the group as
+    /// soon as they show up.
+    pub fn register_market(&mut self, market: &Market) {
+        if self.registry.register(market)
+            && (self.pending_rules > 0 || market.neg_risk_group.is_some())
+        {
+            self.rebuild_edges();
+        }
+    }
+
+    /// Register a market and take its price and liquidity from its first
+    /// (YES) outcome, for previews run outside the pipeline where there is
+    /// no order book
+    pub fn observe_market(&mut self, market: &Market) {
+        self.register_market(market);
+        if let Some(yes) = market.outcomes.first() {
+            if let Some(price) = Decimal::from_f64(yes.price) {
+                self.graph.update_price(market.id, price);
+            }
+            if let Some(liquidity) = Decimal::from_f64(yes.liquidity) {
+                self.graph.update_liquidity(market.id, liquidity);
+            }
+        }
+    }
+
     /// Add a correlation relationship
     pub fn add_correlation(&mut self, edge: CorrelationEdge) {
+        self.manual_edges.push(edge.clone());
         self.graph.add_edge(edge);
     }
 
+    /// Add a constraint on a group of markets
+    pub fn add_group(&mut self, group: CorrelationGroup) {
+        self.manual_groups.push(group.clone());
+        self.graph.add_group(group);
+    }
+
+    /// Current correlation edges, from code, config and negRisk groups
+    pub fn edges(&self) -> &[CorrelationEdge] {
+        self.graph.edges()
+    }
+
+    /// Current correlation groups, from code and negRisk groups
+    pub fn groups(&self) -> &[CorrelationGroup] {
+        self.graph.groups()
+    }
+
+    /// Resolve config rules against known markets and rebuild the graph's
+    /// edges and groups
+    fn rebuild_edges(&mut self) {
+        let (edges, groups, pending) = self.resolve_rules(&self.rules);
+
+        for rule in &pending {
+            for identifier in [&rule.from, &rule.to] {
+                if self.registry.resolve(identifier).is_none()
+                    && self.unknown_logged.insert(identifier.clone())
+                {
+                    warn!("Correlation rule references unknown market {}, waiting for it to appear", identifier);
+                }
+            }
+        }
+
+        let pending = pending.len();
+        if pending < self.pending_rules {
+            info!("Resolved {} pending correlation rules", self.pending_rules - pending);
+        }
+        self.pending_rules = pending;
+
+        self.graph.set_edges(edges);
+        self.graph.set_groups(groups);
+    }
+
+    /// Edges and groups from code, `rules` and negRisk groups, plus the
+    /// rules that can't be resolved yet
+    fn resolve_rules<'a>(
+        &self,
+        rules: &'a [CorrelationRule],
+    ) -> (Vec<CorrelationEdge>, Vec<CorrelationGroup>, Vec<&'a CorrelationRule>) {
+        let mut edges = self.manual_edges.clone();
+        let mut groups = self.manual_groups.clone();
+        let mut pending = Vec::new();
+
+        for rule in rules {
+            match rule.resolve(&self.registry) {
+                Some(edge) => edges.push(edge),
+                None => pending.push(rule),
+            }
+        }
+
+        // Members of a negRisk group are pairwise mutually exclusive. More
+        // members may still be listed, so the group as a whole is only
+        // known to be cumulative and is never bought.
+        let mut neg_risk_groups: Vec<_> = self.registry.neg_risk_groups().collect();
+        neg_risk_groups.sort_by_key(|(group_id, _)| *group_id);
+        for (_, members) in neg_risk_groups.into_iter().filter(|(_, m)| m.len() >= 2) {
+            for (i, from) in members.iter().enumerate() {
+                for to in &members[i + 1..] {
+                    edges.push(CorrelationEdge {
+                        from_market: *from,
+                        to_market: *to,
+                        correlation_type: CorrelationType::MutuallyExclusive,
+                        min_spread: default_min_spread(),
+                    });
+                }
+            }
+
+            if members.len() > 2 {
+                groups.push(CorrelationGroup {
+                    group_type: GroupType::Cumulative,
+                    markets: members.to_vec(),
+                    min_spread: default_min_spread(),
+                    min_liquidity: Decimal::ZERO,
+                });
+            }
+        }
+
+        (edges, groups, pending)
+    }
+
+    /// Reload the config file if requested or if the watched file changed
+    fn maybe_reload(&mut self) {
+        let requested = self.reload_requested.swap(false, Ordering::SeqCst);
+
+        let changed = match &mut self.config_file {
+            Some(file) => match file.watch_interval {
+                Some(interval) if file.last_checked.elapsed() >= interval => {
+                    file.last_checked = Instant::now();
+                    file_modified(&file.path) != file.modified
+                }
+                _ => false,
+            },
+            None => false,
+        };
+
+        if requested || changed {
+            if let Err(e) = self.reload_config() {
+                warn!("Failed to reload correlation config, keeping current rules: {:#}", e);
+            }
+        }
+    }
+
     /// Update market prices from all inputs
     pub fn update_prices(&mut self, inputs: &[SignalInput]) {
         for input in inputs {
@@ -356,32 +1020,66 @@ impl CorrelationGenerator {
                     let mid = (order_book.bids[0].price + order_book.asks[0].price) / Decimal::from(2);
                     self.graph.update_price(input.market.id, mid);
                 }
+
+                let liquidity: Decimal = order_book.bids.iter()
+                    .chain(&order_book.asks)
+                    .map(|level| level.price * level.size)
+                    .sum();
+                self.graph.update_liquidity(input.market.id, liquidity);
             }
         }
     }
 }
 
-impl MultiSignalGenerator for CorrelationGenerator {
-    fn generate(&mut self, input: &SignalInput) -> Vec<TradeSignal> {
-        // This generator needs multiple market inputs
-        // For now, return empty - should be called with all markets
-        vec![]
-    }
+impl CorrelationGenerator {
+    /// Signals for every violation across a snapshot of markets: prices from
+    /// `inputs` update the graph, then each opportunity outside its cooldown
+    /// becomes one signal per leg. This is what `process_batch` runs.
+    pub fn generate_across(&mut self, inputs: &[SignalInput]) -> Vec<TradeSignal> {
+        self.maybe_reload();
+        for input in inputs {
+            self.register_market(&input.market);
+        }
+        self.update_prices(inputs);
+
+        let now = Utc::now();
+        let cooldown = self.cooldown;
+        self.recently_signalled.retain(|_, at| now - *at < cooldown);
+        self.cooldown_skips.clear();
 
-    fn update_state(&mut self, _market_id: Uuid, _update: &crate::signals::StateUpdate) {
-        // Not applicable for correlation generator
+        let mut signals = Vec::new();
+        for opportunity in self.find_arbitrage_opportunities() {
+            let key = (opportunity.markets.clone(), opportunity.opportunity_type.clone());
+            if self.recently_signalled.contains_key(&key) {
+                debug!("Skipping {} on {:?}, still in cooldown", key.1, key.0);
+                self.cooldown_skips.extend(&key.0);
+                continue;
+            }
+
+            signals.extend(self.opportunity_to_signals(&opportunity));
+            self.recently_signalled.insert(key, now);
+        }
+
+        signals
     }
 }
 
-// Implement old SignalGenerator trait for backward compatibility
-impl SignalGenerator for CorrelationGenerator {
-    fn generate(&self, _input: &SignalInput) -> anyhow::Result<Option<TradeSignal>> {
-        Ok(None)
+impl BatchSignalGenerator for CorrelationGenerator {
+    fn generate_batch(&mut self, inputs: &[SignalInput]) -> Vec<TradeSignal> {
+        self.generate_across(inputs)
     }
 
     fn signal_type(&self) -> SignalType {
         SignalType::SpreadArbitrage
     }
+
+    fn last_skips(&self) -> Vec<(MarketId, SkipReason)> {
+        self.cooldown_skips.iter().map(|&market_id| (market_id, SkipReason::Cooldown)).collect()
+    }
+}
+
+fn file_modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
 }
 
 impl CorrelationGenerator {
@@ -402,14 +1100,14 @@ impl CorrelationGenerator {
                 id: Uuid::new_v4(),
                 market_id: trade.market_id,
                 signal_type: SignalType::SpreadArbitrage,
-                direction: trade.direction,
+                direction: trade.direction.clone(),
                 outcome_id: trade.outcome_id.clone(),
                 entry_price: trade.entry_price,
                 target_price: Decimal::ONE,
                 stop_loss: trade.entry_price * Decimal::from_str_exact("1.1").unwrap(),
                 position_size: trade.position_size,
                 confidence: 0.95, // High confidence - mathematical edge
-                expected_value: opportunity.expected_profit / opportunity.trades.len() as i64,
+                expected_value: opportunity.expected_profit / Decimal::from(opportunity.trades.len() as i64),
                 edge: opportunity.violation_amount / trade.entry_price,
                 kelly_fraction: 0.15,
                 reasoning: opportunity.description.clone(),
@@ -418,6 +1116,9 @@ impl CorrelationGenerator {
                     data_points: opportunity.markets.len() as u32,
                     liquidity_score: 0.8,
                     volatility_score: 0.5,
+                    price_age_ms: None,
+                    orderbook_age_ms: None,
+                    research_age_ms: None,
                     custom_fields: serde_json::json!({
                         "strategy": "correlation_arbitrage",
                         "opportunity_id": opportunity.id.to_string(),
@@ -429,6 +1130,7 @@ impl CorrelationGenerator {
                 },
                 created_at: Utc::now(),
                 expires_at: Some(Utc::now() + chrono::Duration::minutes(10)),
+                variant: None,
             })
             .collect()
     }
@@ -437,25 +1139,74 @@ impl CorrelationGenerator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config_audit::InMemoryConfigAudit;
 
     #[test]
     fn test_implication_violation() {
         let mut graph = CorrelationGraph::new();
 
-        graph.update_price(uuid::Uuid::new_v4(), Decimal::from_str_exact("0.40").unwrap());
-        graph.update_price(uuid::Uuid::new_v4(), Decimal::from_str_exact("0.30").unwrap());
+        let market1 = MarketId::random();
+        let market2 = MarketId::random();
+
+        graph.update_price(market1, Decimal::from_str_exact("0.40").unwrap());
+        graph.update_price(market2, Decimal::from_str_exact("0.30").unwrap());
+
+        // No edges, no violations
+        assert!(graph.find_violations().is_empty());
+
+        graph.add_edge(CorrelationEdge {
+            from_market: market1,
+            to_market: market2,
+            correlation_type: CorrelationType::Implies,
+            min_spread: Decimal::from_str_exact("0.03").unwrap(),
+        });
 
-        // Should find violations
+        // Should find violation since market1 implies market2 but 0.40 > 0.30
         let violations = graph.find_violations();
-        assert!(violations.len() >= 0);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].violation_amount, Decimal::from_str_exact("0.10").unwrap());
+    }
+
+    #[test]
+    fn test_implication_chain_derives_violation() {
+        let mut graph = CorrelationGraph::new();
+        let (a, b, c) = (MarketId::random(), MarketId::random(), MarketId::random());
+        graph.update_price(a, Decimal::from_str_exact("0.40").unwrap());
+        graph.update_price(b, Decimal::from_str_exact("0.38").unwrap());
+        graph.update_price(c, Decimal::from_str_exact("0.36").unwrap());
+
+        // Each step is within its spread; only A ⇒ C is violated by enough
+        for (from, to) in [(a, b), (b, c)] {
+            graph.add_edge(CorrelationEdge {
+                from_market: from,
+                to_market: to,
+                correlation_type: CorrelationType::Implies,
+                min_spread: Decimal::from_str_exact("0.03").unwrap(),
+            });
+        }
+
+        let violations = graph.find_violations();
+        assert_eq!(violations.len(), 1);
+        let violation = &violations[0];
+        assert_eq!(violation.opportunity_type, "Transitive Implication Violation");
+        assert_eq!(violation.markets, vec![a, c]);
+        assert_eq!(violation.violation_amount, Decimal::from_str_exact("0.04").unwrap());
+        assert!(violation.description.contains(&b.to_string()));
+        assert_eq!(violation.trades[0].market_id, c);
+        assert_eq!(violation.trades[0].direction, SignalDirection::Long);
+
+        // Closing the loop doesn't hang or add violations at these prices
+        graph.add_implication_chain(&[c, a]);
+        assert_eq!(graph.edges().len(), 3);
+        assert_eq!(graph.find_violations().len(), 1);
     }
 
     #[test]
     fn test_mutually_exclusive() {
         let mut graph = CorrelationGraph::new();
 
-        let market1 = uuid::Uuid::new_v4();
-        let market2 = uuid::Uuid::new_v4();
+        let market1 = MarketId::random();
+        let market2 = MarketId::random();
 
         graph.update_price(market1, Decimal::from_str_exact("0.60").unwrap());
         graph.update_price(market2, Decimal::from_str_exact("0.50").unwrap());
@@ -471,4 +1222,361 @@ mod tests {
         // Should find violation since 0.60 + 0.50 = 1.10 > 1.00
         assert!(!violations.is_empty());
     }
+
+    fn group_graph(prices: &[&str]) -> (CorrelationGraph, Vec<MarketId>) {
+        let mut graph = CorrelationGraph::new();
+        let markets: Vec<MarketId> = prices.iter().map(|_| MarketId::random()).collect();
+        for (market_id, price) in markets.iter().zip(prices) {
+            graph.update_price(*market_id, Decimal::from_str_exact(price).unwrap());
+        }
+        graph.add_group(CorrelationGroup {
+            group_type: GroupType::MutuallyExclusive,
+            markets: markets.clone(),
+            min_spread: Decimal::new(2, 2),
+            min_liquidity: Decimal::from(50),
+        });
+        (graph, markets)
+    }
+
+    #[test]
+    fn test_overpriced_group_shorts_every_member() {
+        // Four candidates summing to 108%
+        let (graph, markets) = group_graph(&["0.45", "0.30", "0.21", "0.12"]);
+
+        let violations = graph.find_violations();
+        assert_eq!(violations.len(), 1);
+        let opportunity = &violations[0];
+        assert_eq!(opportunity.opportunity_type, "Mutually Exclusive Group Overpriced");
+        assert_eq!(opportunity.violation_amount, Decimal::new(8, 2));
+        assert_eq!(opportunity.markets, markets);
+
+        // Equal shares of every member: sizes proportional to price
+        let shares = Decimal::from(100) / Decimal::new(108, 2);
+        assert_eq!(opportunity.trades.len(), 4);
+        for (trade, market_id) in opportunity.trades.iter().zip(&markets) {
+            assert_eq!(trade.market_id, *market_id);
+            assert_eq!(trade.direction, SignalDirection::Short);
+            assert_eq!(trade.position_size, shares * trade.entry_price);
+        }
+        let total: Decimal = opportunity.trades.iter().map(|t| t.position_size).sum();
+        assert!((total - Decimal::from(100)).abs() < Decimal::new(1, 10));
+        assert_eq!(opportunity.expected_profit, shares * Decimal::new(8, 2));
+    }
+
+    #[test]
+    fn test_exclusive_set_of_four_candidates_summing_to_115() {
+        let mut graph = CorrelationGraph::new();
+        let markets: Vec<MarketId> = (0..4).map(|_| MarketId::random()).collect();
+        for (market_id, price) in markets.iter().zip(["0.50", "0.35", "0.20", "0.10"]) {
+            graph.update_price(*market_id, Decimal::from_str_exact(price).unwrap());
+        }
+        graph.add_exclusive_set(markets.clone());
+
+        let violations = graph.find_violations();
+        assert_eq!(violations.len(), 1);
+        let opportunity = &violations[0];
+        assert_eq!(opportunity.opportunity_type, "Mutually Exclusive Group Overpriced");
+        assert_eq!(opportunity.violation_amount, Decimal::new(15, 2));
+        assert_eq!(opportunity.trades.len(), 4);
+        assert!(opportunity.trades.iter().all(|t| t.direction == SignalDirection::Short));
+        let legs: Vec<MarketId> = opportunity.trades.iter().map(|t| t.market_id).collect();
+        assert_eq!(legs, markets);
+    }
+
+    #[test]
+    fn test_underpriced_group_buys_the_set() {
+        // Four months summing to 94%
+        let (mut graph, markets) = group_graph(&["0.40", "0.30", "0.16", "0.08"]);
+
+        let violations = graph.find_violations();
+        assert_eq!(violations.len(), 1);
+        let opportunity = &violations[0];
+        assert_eq!(opportunity.opportunity_type, "Mutually Exclusive Group Underpriced");
+        assert_eq!(opportunity.violation_amount, Decimal::new(6, 2));
+        assert!(opportunity.trades.iter().all(|t| t.direction == SignalDirection::Long));
+
+        // Buying 100/0.94 shares of each costs 100 and pays 100/0.94
+        let shares = Decimal::from(100) / Decimal::new(94, 2);
+        assert_eq!(opportunity.expected_profit, shares * Decimal::new(6, 2));
+        assert!((opportunity.expected_profit - Decimal::new(638, 2)).abs() < Decimal::new(1, 2));
+
+        // An illiquid member can't be bought, so the set is no longer a sure thing
+        graph.update_liquidity(markets[3], Decimal::from(10));
+        assert!(graph.find_violations().is_empty());
+    }
+
+    #[test]
+    fn test_group_edge_cases() {
+        // A member without a price skips the group
+        let (mut graph, markets) = group_graph(&["0.45", "0.30", "0.21", "0.12"]);
+        graph.market_prices.remove(&markets[2]);
+        assert!(graph.find_violations().is_empty());
+
+        // Illiquid members are excluded from an overpriced set
+        let (mut graph, markets) = group_graph(&["0.50", "0.35", "0.20", "0.05"]);
+        graph.update_liquidity(markets[3], Decimal::from(10));
+        graph.update_liquidity(markets[0], Decimal::from(5000));
+        let violations = graph.find_violations();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].markets, markets[..3].to_vec());
+        assert_eq!(violations[0].violation_amount, Decimal::new(5, 2));
+
+        // Within min_spread of 100% is no opportunity
+        let (graph, _) = group_graph(&["0.50", "0.30", "0.11", "0.10"]);
+        assert!(graph.find_violations().is_empty());
+
+        // A cumulative group is never bought
+        let (mut graph, _) = group_graph(&["0.40", "0.30", "0.16", "0.08"]);
+        graph.groups[0].group_type = GroupType::Cumulative;
+        assert!(graph.find_violations().is_empty());
+    }
+
+    fn create_input(condition_id: &str, mid: &str) -> SignalInput {
+        let mid = Decimal::from_str_exact(mid).unwrap();
+        let spread = Decimal::new(1, 2);
+        crate::test_support::input(MarketId::random())
+            .market(|market| {
+                market.condition_id = condition_id.into();
+                market.question = condition_id.to_string();
+            })
+            .quote(&(mid - spread).to_string(), &(mid + spread).to_string())
+            .build()
+    }
+
+    #[test]
+    fn test_generate_across_shorts_overpriced_exclusive_pair() {
+        let inputs = vec![create_input("a", "0.60"), create_input("b", "0.50")];
+        let mut generator = CorrelationGenerator::new();
+        generator.add_correlation(CorrelationEdge {
+            from_market: inputs[0].market.id,
+            to_market: inputs[1].market.id,
+            correlation_type: CorrelationType::MutuallyExclusive,
+            min_spread: Decimal::from_str_exact("0.03").unwrap(),
+        });
+
+        // One market's price alone shows no violation
+        assert!(generator.generate_across(&inputs[..1]).is_empty());
+
+        // 0.60 + 0.50 = 1.10: short both
+        let signals = generator.generate_across(&inputs);
+        assert_eq!(signals.len(), 2);
+        assert!(signals.iter().all(|s| s.direction == SignalDirection::Short));
+        let mut shorted: Vec<MarketId> = signals.iter().map(|s| s.market_id).collect();
+        let mut expected = vec![inputs[0].market.id, inputs[1].market.id];
+        shorted.sort();
+        expected.sort();
+        assert_eq!(shorted, expected);
+        assert_eq!(signals[0].metadata.custom_fields["violation_amount"], "0.10");
+    }
+
+    #[test]
+    fn test_config_rules_wait_for_unknown_markets() {
+        let config = CorrelationConfig::from_toml(
+            r#"edges = [{ from = "trump-wins", to = "gop-wins", type = "implies", min_spread = 0.02 }]"#,
+        )
+        .unwrap();
+        let mut generator = CorrelationGenerator::new().with_config(config).unwrap();
+
+        let trump = create_input("trump-wins", "0.40");
+        let gop = create_input("gop-wins", "0.30");
+
+        // Only one side of the rule is known yet
+        assert!(generator.generate_batch(std::slice::from_ref(&trump)).is_empty());
+        assert!(generator.edges().is_empty());
+
+        // Retried once the other market appears
+        let signals = generator.generate_batch(&[trump.clone(), gop.clone()]);
+        assert_eq!(generator.edges().len(), 1);
+        assert_eq!(generator.edges()[0].from_market, trump.market.id);
+        assert_eq!(generator.edges()[0].to_market, gop.market.id);
+        assert_eq!(signals.len(), 2);
+    }
+
+    #[test]
+    fn test_neg_risk_group_links_members() {
+        let mut generator = CorrelationGenerator::new();
+        let inputs: Vec<SignalInput> = ["0.30", "0.25", "0.20", "0.12", "0.08", "0.05"]
+            .iter()
+            .enumerate()
+            .map(|(i, mid)| {
+                let mut input = create_input(&format!("nominee-{}", i), mid);
+                input.market.neg_risk_group = Some("dem-nominee-2028".to_string());
+                input
+            })
+            .collect();
+        let unrelated = create_input("unrelated", "0.50");
+
+        generator.generate_batch(&inputs[..1]);
+        assert!(generator.edges().is_empty());
+
+        // 6 members give 15 pairwise edges and one group over all of them
+        let signals = generator.generate_batch(&[inputs.clone(), vec![unrelated]].concat());
+        assert!(signals.is_empty());
+        assert_eq!(generator.edges().len(), 15);
+        assert!(generator
+            .edges()
+            .iter()
+            .all(|e| e.correlation_type == CorrelationType::MutuallyExclusive));
+
+        let members: Vec<MarketId> = inputs.iter().map(|i| i.market.id).collect();
+        assert_eq!(generator.groups().len(), 1);
+        assert_eq!(generator.groups()[0].group_type, GroupType::Cumulative);
+        assert_eq!(generator.groups()[0].markets, members);
+    }
+
+    #[test]
+    fn test_config_file_reload_keeps_manual_edges() {
+        let path = std::env::temp_dir().join(format!("correlation-{}.toml", Uuid::new_v4()));
+        std::fs::write(&path, r#"edges = [{ from = "a", to = "b", type = "implies" }]"#).unwrap();
+
+        let mut generator = CorrelationGenerator::from_config_file(&path).unwrap();
+        let inputs: Vec<SignalInput> = ["a", "b", "c"]
+            .iter()
+            .map(|id| create_input(id, "0.50"))
+            .collect();
+        generator.add_correlation(CorrelationEdge {
+            from_market: inputs[0].market.id,
+            to_market: inputs[2].market.id,
+            correlation_type: CorrelationType::SameOutcome,
+            min_spread: Decimal::new(2, 2),
+        });
+        generator.generate_batch(&inputs);
+        assert_eq!(generator.edges().len(), 2);
+
+        std::fs::write(
+            &path,
+            r#"
+            [[mutually_exclusive]]
+            markets = ["a", "b", "c"]
+            "#,
+        )
+        .unwrap();
+        generator.reload_handle().store(true, Ordering::SeqCst);
+        generator.generate_batch(&inputs);
+
+        let edges = generator.edges();
+        assert_eq!(edges.len(), 4);
+        assert_eq!(edges[0].correlation_type, CorrelationType::SameOutcome);
+        assert!(edges[1..].iter().all(|e| e.correlation_type == CorrelationType::MutuallyExclusive));
+
+        // A broken file keeps the current rules
+        std::fs::write(&path, "edges = [{ from = \"a\" }]").unwrap();
+        assert!(generator.reload_config().is_err());
+        assert_eq!(generator.edges().len(), 4);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_preview_shows_violation_a_rule_introduces() {
+        let mut generator = CorrelationGenerator::new();
+        let inputs = [create_input("a", "0.60"), create_input("b", "0.40"), create_input("c", "0.50")];
+        generator.generate_batch(&inputs);
+
+        let config = CorrelationConfig::from_toml(
+            r#"
+            edges = [
+                { from = "a", to = "b", type = "implies", min_spread = 0.02 },
+                { from = "b", to = "unseen", type = "implies" },
+            ]
+            "#,
+        )
+        .unwrap();
+        let preview = generator.preview_config(&config).unwrap();
+
+        assert_eq!(preview.rules, 2);
+        assert_eq!(preview.pending_rules, 1);
+        assert_eq!(preview.added_edges.len(), 1);
+        assert_eq!(preview.added_edges[0].from_market, inputs[0].market.id);
+        assert!(preview.removed_edges.is_empty());
+        assert_eq!(preview.new_violations.len(), 1);
+        assert_eq!(preview.new_violations[0].markets, vec![inputs[0].market.id, inputs[1].market.id]);
+        assert_eq!(preview.signal_count(), 2);
+
+        // Nothing was applied
+        assert!(generator.edges().is_empty());
+    }
+
+    #[test]
+    fn test_preview_from_observed_markets() {
+        let market = |condition_id: &str, price: f64| Market {
+            outcomes: vec![common::Outcome {
+                id: "yes".into(),
+                name: "Yes".to_string(),
+                price,
+                liquidity: 1000.0,
+            }],
+            ..create_input(condition_id, "0.50").market
+        };
+        let (a, b) = (market("a", 0.60), market("b", 0.40));
+
+        let mut generator = CorrelationGenerator::new();
+        generator.observe_market(&a);
+        generator.observe_market(&b);
+
+        let config = CorrelationConfig::validate(
+            r#"edges = [{ from = "a", to = "b", type = "implies", min_spread = 0.02 }]"#,
+            Some(generator.registry_mut()),
+        )
+        .unwrap();
+        let preview = generator.preview_config(&config).unwrap();
+
+        assert_eq!(preview.pending_rules, 0);
+        assert_eq!(preview.new_violations.len(), 1);
+        assert_eq!(preview.new_violations[0].markets, vec![a.id, b.id]);
+        assert_eq!(preview.signal_count(), 2);
+    }
+
+    #[test]
+    fn test_reload_over_signal_threshold_needs_explicit_apply() {
+        let path = std::env::temp_dir().join(format!("correlation-{}.toml", Uuid::new_v4()));
+        std::fs::write(&path, "").unwrap();
+
+        let audit = Arc::new(InMemoryConfigAudit::new());
+        let mut generator = CorrelationGenerator::from_config_file(&path)
+            .unwrap()
+            .with_apply_gate(1)
+            .with_audit(audit.clone());
+        let inputs = [create_input("a", "0.60"), create_input("b", "0.40")];
+        generator.generate_batch(&inputs);
+
+        // Unknown markets are rejected on reload
+        std::fs::write(&path, r#"edges = [{ from = "a", to = "typo", type = "implies" }]"#).unwrap();
+        let err = generator.reload_config().unwrap_err();
+        assert!(format!("{:#}", err).contains("typo"), "{:#}", err);
+
+        std::fs::write(&path, r#"edges = [{ from = "a", to = "b", type = "implies" }]"#).unwrap();
+        let ReloadOutcome::Staged(preview) = generator.reload_config().unwrap() else {
+            panic!("Expected the change to be staged");
+        };
+        assert_eq!(preview.signal_count(), 2);
+        assert!(generator.edges().is_empty());
+        assert_eq!(generator.staged_change().map(|p| p.id), Some(preview.id));
+
+        // Approving something other than what was previewed is refused
+        assert!(generator.apply_staged(Uuid::new_v4(), "alice").is_err());
+        assert!(generator.edges().is_empty());
+
+        generator.apply_staged(preview.id, "alice").unwrap();
+        assert_eq!(generator.edges().len(), 1);
+        assert!(generator.staged_change().is_none());
+        assert_eq!(generator.generate_batch(&inputs).len(), 2);
+
+        let changes = audit.changes();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].id, preview.id);
+        assert_eq!(changes[0].approved_by.as_deref(), Some("alice"));
+
+        // Removing the rule generates nothing and applies right away
+        std::fs::write(&path, "").unwrap();
+        let ReloadOutcome::Applied(preview) = generator.reload_config().unwrap() else {
+            panic!("Expected the change to be applied");
+        };
+        assert_eq!(preview.removed_edges.len(), 1);
+        assert_eq!(preview.cleared_violations.len(), 1);
+        assert!(generator.edges().is_empty());
+        assert_eq!(audit.changes()[1].approved_by, None);
+
+        std::fs::remove_file(&path).unwrap();
+    }
 }
@@ -0,0 +1,554 @@
+// Correlation Config
+// Operator-maintained market relationships, resolved to market UUIDs at runtime
+
+use anyhow::{bail, Context, Result};
+use common::{Market, MarketId};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tracing::info;
+
+use crate::correlation::{CorrelationEdge, CorrelationType};
+
+/// Correlation rules as written by operators, keyed by market identifier.
+///
+/// ```toml
+/// edges = [
+///     { from = "trump-wins-2024", to = "republican-wins-2024", type = "implies", min_spread = 0.02 },
+///     { from = "trump-wins-2024", to = "gop-senate-2024", type = "suggests", strength = 0.8 },
+/// ]
+///
+/// [[mutually_exclusive]]
+/// markets = ["trump-wins-2024", "harris-wins-2024", "rfk-wins-2024"]
+/// min_spread = 0.03
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CorrelationConfig {
+    #[serde(default)]
+    pub edges: Vec<EdgeRule>,
+    /// Groups of markets of which at most one can resolve YES
+    #[serde(default)]
+    pub mutually_exclusive: Vec<MutuallyExclusiveGroup>,
+}
+
+/// Relationship between two markets
+#[derive(Debug, Clone, Deserialize)]
+pub struct EdgeRule {
+    pub from: String,
+    pub to: String,
+    #[serde(flatten)]
+    pub relation: Relation,
+    #[serde(default = "default_min_spread")]
+    pub min_spread: Decimal,
+}
+
+/// Relationship kind, tagged by `type`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Relation {
+    Implies,
+    Suggests { strength: Decimal },
+    MutuallyExclusive,
+    Cumulative,
+    SameOutcome,
+}
+
+/// Markets that are pairwise mutually exclusive
+#[derive(Debug, Clone, Deserialize)]
+pub struct MutuallyExclusiveGroup {
+    pub markets: Vec<String>,
+    #[serde(default = "default_min_spread")]
+    pub min_spread: Decimal,
+}
+
+pub(crate) fn default_min_spread() -> Decimal {
+    Decimal::new(2, 2)
+}
+
+/// A single edge still keyed by market identifier
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorrelationRule {
+    pub from: String,
+    pub to: String,
+    pub correlation_type: CorrelationType,
+    pub min_spread: Decimal,
+}
+
+impl CorrelationRule {
+    /// Build the edge once both markets are known
+    pub fn resolve(&self, registry: &MarketRegistry) -> Option<CorrelationEdge> {
+        Some(CorrelationEdge {
+            from_market: registry.resolve(&self.from)?,
+            to_market: registry.resolve(&self.to)?,
+            correlation_type: self.correlation_type,
+            min_spread: self.min_spread,
+        })
+    }
+}
+
+impl CorrelationConfig {
+    /// Load a config from a TOML file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read correlation config {}", path.display()))?;
+        Self::from_toml(&content)
+            .with_context(|| format!("Failed to parse correlation config {}", path.display()))
+    }
+
+    /// Parse a config from TOML
+    pub fn from_toml(content: &str) -> Result<Self> {
+        Ok(Self::validate(content, None)?)
+    }
+
+    /// Parse and check a config, collecting every problem with its line.
+    ///
+    /// Rejects malformed strengths and spreads, degenerate groups and
+    /// implication cycles; with a registry, also rules on markets it does
+    /// not know.
+    pub fn validate(content: &str, registry: Option<&MarketRegistry>) -> Result<Self, RuleValidationError> {
+        let raw: SpannedConfig = toml::from_str(content).map_err(|e| RuleValidationError {
+            errors: vec![RuleError {
+                line: e.span().map(|span| line_of(content, span.start)),
+                message: e.message().to_string(),
+            }],
+        })?;
+
+        let mut errors = Vec::new();
+        let mut error = |span: &std::ops::Range<usize>, message: String| {
+            errors.push(RuleError { line: Some(line_of(content, span.start)), message });
+        };
+        let unknown = |identifier: &str| registry.is_some_and(|r| r.resolve(identifier).is_none());
+
+        for edge in &raw.edges {
+            let span = edge.span();
+            let edge = edge.get_ref();
+            if let Relation::Suggests { strength } = edge.relation {
+                if strength <= Decimal::ZERO || strength > Decimal::ONE {
+                    error(&span, format!("Suggests strength for {} -> {} must be in (0, 1], got {}", edge.from, edge.to, strength));
+                }
+            }
+            if edge.min_spread < Decimal::ZERO {
+                error(&span, format!("min_spread for {} -> {} is negative", edge.from, edge.to));
+            }
+            if edge.from == edge.to {
+                error(&span, format!("Edge from {} to itself", edge.from));
+            }
+            for identifier in [&edge.from, &edge.to] {
+                if unknown(identifier) {
+                    error(&span, format!("Unknown market {}", identifier));
+                }
+            }
+        }
+
+        for group in &raw.mutually_exclusive {
+            let span = group.span();
+            let group = group.get_ref();
+            if group.markets.len() < 2 {
+                error(&span, format!("Mutually exclusive group {:?} needs at least 2 markets", group.markets));
+            }
+            if group.min_spread < Decimal::ZERO {
+                error(&span, format!("min_spread for group {:?} is negative", group.markets));
+            }
+            for identifier in group.markets.iter().filter(|m| unknown(m)) {
+                error(&span, format!("Unknown market {}", identifier));
+            }
+        }
+
+        for (span, cycle) in implication_cycles(&raw.edges) {
+            error(&span, format!("Implication cycle {}", cycle.join(" -> ")));
+        }
+
+        if !errors.is_empty() {
+            errors.sort_by_key(|e| e.line);
+            return Err(RuleValidationError { errors });
+        }
+
+        Ok(Self {
+            edges: raw.edges.into_iter().map(toml::Spanned::into_inner).collect(),
+            mutually_exclusive: raw.mutually_exclusive.into_iter().map(toml::Spanned::into_inner).collect(),
+        })
+    }
+
+    /// Flatten the config into pairwise rules.
+    ///
+    /// A mutually exclusive group of n markets expands into its n(n-1)/2 pairs.
+    pub fn rules(&self) -> Result<Vec<CorrelationRule>> {
+        let mut rules = Vec::new();
+
+        for edge in &self.edges {
+            let correlation_type = match edge.relation {
+                Relation::Implies => CorrelationType::Implies,
+                Relation::Suggests { strength } => {
+                    if strength <= Decimal::ZERO || strength > Decimal::ONE {
+                        bail!(
+                            "Suggests strength for {} -> {} must be in (0, 1], got {}",
+                            edge.from,
+                            edge.to,
+                            strength
+                        );
+                    }
+                    CorrelationType::Suggests(strength)
+                }
+                Relation::MutuallyExclusive => CorrelationType::MutuallyExclusive,
+                Relation::Cumulative => CorrelationType::Cumulative,
+                Relation::SameOutcome => CorrelationType::SameOutcome,
+            };
+
+            rules.push(CorrelationRule {
+                from: edge.from.clone(),
+                to: edge.to.clone(),
+                correlation_type,
+                min_spread: edge.min_spread,
+            });
+        }
+
+        for group in &self.mutually_exclusive {
+            if group.markets.len() < 2 {
+                bail!("Mutually exclusive group {:?} needs at least 2 markets", group.markets);
+            }
+
+            for (i, from) in group.markets.iter().enumerate() {
+                for to in &group.markets[i + 1..] {
+                    rules.push(CorrelationRule {
+                        from: from.clone(),
+                        to: to.clone(),
+                        correlation_type: CorrelationType::MutuallyExclusive,
+                        min_spread: group.min_spread,
+                    });
+                }
+            }
+        }
+
+        Ok(rules)
+    }
+}
+
+/// Config with the position of every entry, for line-level errors
+#[derive(Deserialize)]
+struct SpannedConfig {
+    #[serde(default)]
+    edges: Vec<toml::Spanned<EdgeRule>>,
+    #[serde(default)]
+    mutually_exclusive: Vec<toml::Spanned<MutuallyExclusiveGroup>>,
+}
+
+/// 1-based line of a byte offset
+fn line_of(content: &str, offset: usize) -> usize {
+    content[..offset.min(content.len())].matches('\n').count() + 1
+}
+
+/// Cycles among `implies` edges, each with the span of the edge closing it
+fn implication_cycles(edges: &[toml::Spanned<EdgeRule>]) -> Vec<(std::ops::Range<usize>, Vec<String>)> {
+    let mut implies: HashMap<&str, Vec<&toml::Spanned<EdgeRule>>> = HashMap::new();
+    for edge in edges.iter().filter(|e| matches!(e.get_ref().relation, Relation::Implies)) {
+        implies.entry(edge.get_ref().from.as_str()).or_default().push(edge);
+    }
+
+    // Depth-first search; an edge back onto the current path closes a cycle
+    fn visit<'a>(
+        market: &'a str,
+        implies: &HashMap<&'a str, Vec<&'a toml::Spanned<EdgeRule>>>,
+        path: &mut Vec<&'a str>,
+        done: &mut std::collections::HashSet<&'a str>,
+        cycles: &mut Vec<(std::ops::Range<usize>, Vec<String>)>,
+    ) {
+        path.push(market);
+        for edge in implies.get(market).into_iter().flatten() {
+            let to = edge.get_ref().to.as_str();
+            if let Some(start) = path.iter().position(|m| *m == to) {
+                let mut cycle: Vec<String> = path[start..].iter().map(|m| m.to_string()).collect();
+                cycle.push(to.to_string());
+                cycles.push((edge.span(), cycle));
+            } else if !done.contains(to) {
+                visit(to, implies, path, done, cycles);
+            }
+        }
+        path.pop();
+        done.insert(market);
+    }
+
+    let mut roots: Vec<&str> = implies.keys().copied().collect();
+    roots.sort();
+    let mut cycles = Vec::new();
+    let mut done = std::collections::HashSet::new();
+    for root in roots {
+        if !done.contains(root) {
+            visit(root, &implies, &mut Vec::new(), &mut done, &mut cycles);
+        }
+    }
+    cycles
+}
+
+/// A problem with one entry of a correlation config
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleError {
+    /// 1-based line of the offending entry, if known
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+/// Every problem found in a correlation config
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleValidationError {
+    pub errors: Vec<RuleError>,
+}
+
+impl fmt::Display for RuleValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} invalid correlation rule(s)", self.errors.len())?;
+        for error in &self.errors {
+            write!(f, "\n  {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RuleValidationError {}
+
+/// Maps operator-facing market identifiers to market UUIDs.
+///
+/// Markets are known by their condition_id; other identifiers such as
+/// slugs can be added as aliases. Members of Polymarket negRisk groups are
+/// also tracked by group id.
+#[derive(Debug, Clone, Default)]
+pub struct MarketRegistry {
+    ids: HashMap<String, MarketId>,
+    /// negRisk group id -> member markets, in registration order
+    neg_risk_groups: HashMap<String, Vec<MarketId>>,
+}
+
+impl MarketRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a market under its condition_id, and in its negRisk group if
+    /// it has one. Returns true if it was not known yet.
+    pub fn register(&mut self, market: &Market) -> bool {
+        let mut new = self.register_alias(market.condition_id.as_str(), market.id);
+
+        if let Some(group_id) = &market.neg_risk_group {
+            let members = self.neg_risk_groups.entry(group_id.clone()).or_default();
+            if !members.contains(&market.id) {
+                members.push(market.id);
+                new = true;
+            }
+        }
+
+        new
+    }
+
+    /// Register an additional identifier for a market. Returns true if it was not known yet.
+    pub fn register_alias(&mut self, identifier: &str, market_id: MarketId) -> bool {
+        self.ids.insert(identifier.to_string(), market_id) != Some(market_id)
+    }
+
+    pub fn resolve(&self, identifier: &str) -> Option<MarketId> {
+        self.ids.get(identifier).copied()
+    }
+
+    /// Members of a negRisk group seen so far
+    pub fn neg_risk_group(&self, group_id: &str) -> &[MarketId] {
+        self.neg_risk_groups
+            .get(group_id)
+            .map(|members| members.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// All negRisk groups seen so far, by group id
+    pub fn neg_risk_groups(&self) -> impl Iterator<Item = (&str, &[MarketId])> {
+        self.neg_risk_groups
+            .iter()
+            .map(|(group_id, members)| (group_id.as_str(), members.as_slice()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+}
+
+/// Request a config reload on every SIGHUP.
+///
+/// The flag is the generator's `reload_handle`; the reload itself happens
+/// on its next batch. Must be called from within a Tokio runtime.
+#[cfg(unix)]
+pub fn reload_on_sighup(reload: Arc<AtomicBool>) -> std::io::Result<tokio::task::JoinHandle<()>> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = signal(SignalKind::hangup())?;
+    Ok(tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            info!("SIGHUP received, reloading correlation config");
+            reload.store(true, Ordering::SeqCst);
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+        edges = [
+            { from = "trump-wins-2024", to = "republican-wins-2024", type = "implies", min_spread = 0.02 },
+            { from = "trump-wins-2024", to = "gop-senate-2024", type = "suggests", strength = 0.8 },
+        ]
+
+        [[mutually_exclusive]]
+        markets = ["trump-wins-2024", "harris-wins-2024", "rfk-wins-2024"]
+        min_spread = 0.03
+    "#;
+
+    fn market(condition_id: &str) -> Market {
+        Market {
+            condition_id: condition_id.into(),
+            question: condition_id.to_string(),
+            outcomes: vec![],
+            ..crate::test_support::test_market(MarketId::random())
+        }
+    }
+
+    #[test]
+    fn test_parse_sample_config() {
+        let rules = CorrelationConfig::from_toml(SAMPLE).unwrap().rules().unwrap();
+
+        assert_eq!(rules.len(), 5);
+        assert_eq!(
+            rules[0],
+            CorrelationRule {
+                from: "trump-wins-2024".to_string(),
+                to: "republican-wins-2024".to_string(),
+                correlation_type: CorrelationType::Implies,
+                min_spread: Decimal::new(2, 2),
+            }
+        );
+        assert_eq!(rules[1].correlation_type, CorrelationType::Suggests(Decimal::new(8, 1)));
+        assert_eq!(rules[1].min_spread, default_min_spread());
+
+        // The group expands into every pair
+        let pairs: Vec<(&str, &str)> = rules[2..]
+            .iter()
+            .inspect(|r| {
+                assert_eq!(r.correlation_type, CorrelationType::MutuallyExclusive);
+                assert_eq!(r.min_spread, Decimal::new(3, 2));
+            })
+            .map(|r| (r.from.as_str(), r.to.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("trump-wins-2024", "harris-wins-2024"),
+                ("trump-wins-2024", "rfk-wins-2024"),
+                ("harris-wins-2024", "rfk-wins-2024"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rules_resolve_through_registry() {
+        let rules = CorrelationConfig::from_toml(SAMPLE).unwrap().rules().unwrap();
+        let trump = market("trump-wins-2024");
+        let republican = market("republican-wins-2024");
+
+        let mut registry = MarketRegistry::new();
+        assert!(registry.register(&trump));
+        assert!(!registry.register(&trump));
+        assert!(rules[0].resolve(&registry).is_none());
+
+        // Slugs resolve through aliases
+        registry.register_alias("republican-wins-2024", republican.id);
+        let edge = rules[0].resolve(&registry).unwrap();
+        assert_eq!(edge.from_market, trump.id);
+        assert_eq!(edge.to_market, republican.id);
+        assert_eq!(edge.correlation_type, CorrelationType::Implies);
+    }
+
+    #[test]
+    fn test_registry_tracks_neg_risk_groups() {
+        let mut registry = MarketRegistry::new();
+        let mut members = Vec::new();
+        for i in 0..3 {
+            let mut member = market(&format!("nominee-{}", i));
+            member.neg_risk_group = Some("dem-nominee-2028".to_string());
+            assert!(registry.register(&member));
+            members.push(member.id);
+        }
+        registry.register(&market("unrelated"));
+
+        assert_eq!(registry.neg_risk_group("dem-nominee-2028"), members.as_slice());
+        assert!(registry.neg_risk_group("gop-nominee-2028").is_empty());
+        assert_eq!(registry.neg_risk_groups().count(), 1);
+    }
+
+    #[test]
+    fn test_invalid_config_rejected() {
+        let bad_strength = r#"edges = [{ from = "a", to = "b", type = "suggests", strength = 1.5 }]"#;
+        assert!(CorrelationConfig::from_toml(bad_strength).is_err());
+
+        let unknown_type = r#"edges = [{ from = "a", to = "b", type = "causes" }]"#;
+        assert!(CorrelationConfig::from_toml(unknown_type).is_err());
+
+        let lonely_group = "[[mutually_exclusive]]\nmarkets = [\"a\"]";
+        assert!(CorrelationConfig::from_toml(lonely_group).is_err());
+    }
+
+    #[test]
+    fn test_validation_reports_every_error_with_its_line() {
+        let config = r#"
+edges = [
+    { from = "a", to = "b", type = "implies" },
+    { from = "b", to = "c", type = "suggests", strength = 1.5 },
+    { from = "b", to = "a", type = "implies" },
+    { from = "c", to = "d", type = "same_outcome" },
+]
+
+[[mutually_exclusive]]
+markets = ["a", "b"]
+min_spread = -0.01
+"#;
+        let err = CorrelationConfig::validate(config, None).unwrap_err();
+        let errors: Vec<(Option<usize>, &str)> = err.errors.iter().map(|e| (e.line, e.message.as_str())).collect();
+        assert_eq!(
+            errors,
+            vec![
+                (Some(4), "Suggests strength for b -> c must be in (0, 1], got 1.5"),
+                (Some(5), "Implication cycle a -> b -> a"),
+                (Some(9), "min_spread for group [\"a\", \"b\"] is negative"),
+            ]
+        );
+        assert!(err.to_string().contains("line 5: Implication cycle"));
+
+        // Against known markets, unknown references are rejected too
+        let mut registry = MarketRegistry::new();
+        for id in ["a", "b", "c"] {
+            registry.register(&market(id));
+        }
+        let config = "edges = [\n  { from = \"a\", to = \"b\", type = \"implies\" },\n  { from = \"c\", to = \"d\", type = \"implies\" },\n]";
+        let err = CorrelationConfig::validate(config, Some(&registry)).unwrap_err();
+        assert_eq!(err.errors, vec![RuleError { line: Some(3), message: "Unknown market d".to_string() }]);
+        assert!(CorrelationConfig::validate(config, None).is_ok());
+
+        // Syntax errors point at the line too
+        let err = CorrelationConfig::validate("edges = [\n  { from = \"a\" },\n]", None).unwrap_err();
+        assert_eq!(err.errors.len(), 1);
+        assert_eq!(err.errors[0].line, Some(2));
+    }
+}
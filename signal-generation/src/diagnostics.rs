@@ -0,0 +1,330 @@
+// Pipeline Diagnostics
+// Per-market record of why each cycle did or did not produce a signal
+
+use chrono::{DateTime, Utc};
+use common::{MarketId, SignalType};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+
+/// Why a generator produced nothing for a market
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum SkipReason {
+    /// An input the generator requires was absent, e.g. "order_book"
+    MissingData { data: &'static str },
+    /// The input was present but unusable, e.g. "one_sided_book"
+    DegradedData { detail: &'static str },
+    /// The same opportunity was signalled too recently
+    Cooldown,
+    /// A quality check inside the generator held it back
+    QualityGovernor { check: &'static str },
+}
+
+impl SkipReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SkipReason::MissingData { .. } => "missing_data",
+            SkipReason::DegradedData { .. } => "degraded_data",
+            SkipReason::Cooldown => "cooldown",
+            SkipReason::QualityGovernor { .. } => "quality_governor",
+        }
+    }
+}
+
+/// What happened to a market at one stage of a cycle
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub enum StageOutcome {
+    /// Left out before generation, e.g. by the orchestrator's market filter
+    Filtered { criterion: &'static str },
+    /// A generator ran and had nothing to say
+    NoSignal { generator: SignalType },
+    /// A generator ran and explained why it produced nothing
+    Skipped { generator: SignalType, reason: SkipReason },
+    GeneratorError { generator: SignalType, error: String },
+    /// Dropped by the pipeline's own thresholds ("min_confidence" or "min_edge")
+    BelowThreshold {
+        signal_type: SignalType,
+        threshold: &'static str,
+        value: f64,
+        required: f64,
+    },
+    /// Rejected by a validator; `score` is the value it judged, when it has one
+    Rejected {
+        signal_type: SignalType,
+        validator: &'static str,
+        score: Option<f64>,
+    },
+    /// Dropped by the pre-trade risk check
+    RiskRejected { signal_type: SignalType, violation: String },
+    /// Dropped by a rate limit, e.g. "max_signals_per_cycle"
+    Throttled { signal_type: SignalType, limit: &'static str },
+    /// Dropped against another signal on the same outcome ("duplicate" or "contradicted")
+    Deduplicated { signal_type: SignalType, reason: &'static str },
+    /// Left the pipeline as a signal
+    Emitted { signal_type: SignalType },
+    /// Held back by execution for later
+    Deferred { signal_type: SignalType, reason: &'static str },
+    Executed { signal_type: SignalType },
+}
+
+impl StageOutcome {
+    /// One-line explanation for operators
+    pub fn describe(&self) -> String {
+        match self {
+            StageOutcome::Filtered { criterion } => format!("filtered before generation ({})", criterion),
+            StageOutcome::NoSignal { generator } => format!("{} found no opportunity", generator.as_str()),
+            StageOutcome::Skipped { generator, reason } => {
+                format!("{} skipped: {}", generator.as_str(), reason.as_str())
+            }
+            StageOutcome::GeneratorError { generator, error } => format!("{} failed: {}", generator.as_str(), error),
+            StageOutcome::BelowThreshold { signal_type, threshold, value, required } => format!(
+                "{} signal below {} ({:.4} < {:.4})",
+                signal_type.as_str(),
+                threshold,
+                value,
+                required
+            ),
+            StageOutcome::Rejected { signal_type, validator, score } => match score {
+                Some(score) => format!("{} signal rejected by {} (score {:.4})", signal_type.as_str(), validator, score),
+                None => format!("{} signal rejected by {}", signal_type.as_str(), validator),
+            },
+            StageOutcome::RiskRejected { signal_type, violation } => {
+                format!("{} signal rejected by risk: {}", signal_type.as_str(), violation)
+            }
+            StageOutcome::Throttled { signal_type, limit } => {
+                format!("{} signal throttled by {}", signal_type.as_str(), limit)
+            }
+            StageOutcome::Deduplicated { signal_type, reason } => {
+                format!("{} signal dropped as {}", signal_type.as_str(), reason)
+            }
+            StageOutcome::Emitted { signal_type } => format!("{} signal emitted", signal_type.as_str()),
+            StageOutcome::Deferred { signal_type, reason } => {
+                format!("{} signal deferred ({})", signal_type.as_str(), reason)
+            }
+            StageOutcome::Executed { signal_type } => format!("{} signal executed", signal_type.as_str()),
+        }
+    }
+}
+
+/// Everything recorded for one market in one cycle
+#[derive(Debug, Clone, Serialize)]
+pub struct CycleDiagnostics {
+    pub market_id: MarketId,
+    pub cycle: u64,
+    pub recorded_at: DateTime<Utc>,
+    pub outcomes: Vec<StageOutcome>,
+}
+
+impl CycleDiagnostics {
+    /// Whether any signal for the market made it out of the pipeline
+    pub fn emitted(&self) -> bool {
+        self.outcomes
+            .iter()
+            .any(|o| matches!(o, StageOutcome::Emitted { .. } | StageOutcome::Executed { .. }))
+    }
+}
+
+/// Bounds on the diagnostics kept in memory
+#[derive(Debug, Clone)]
+pub struct DiagnosticsConfig {
+    /// Cycles kept per market
+    pub cycles_per_market: usize,
+    /// Markets tracked at once; the one updated longest ago is dropped first
+    pub max_markets: usize,
+}
+
+impl Default for DiagnosticsConfig {
+    fn default() -> Self {
+        Self {
+            cycles_per_market: 50,
+            max_markets: 5000,
+        }
+    }
+}
+
+/// Ring buffer of recent cycle diagnostics for each market
+#[derive(Debug, Default)]
+pub struct DiagnosticsLog {
+    config: DiagnosticsConfig,
+    markets: HashMap<MarketId, VecDeque<CycleDiagnostics>>,
+}
+
+impl DiagnosticsLog {
+    pub fn new(config: DiagnosticsConfig) -> Self {
+        Self {
+            config,
+            markets: HashMap::new(),
+        }
+    }
+
+    /// Add an outcome to a market's entry for `cycle`
+    pub fn record(&mut self, market_id: MarketId, cycle: u64, outcome: StageOutcome) {
+        if self.config.cycles_per_market == 0 || self.config.max_markets == 0 {
+            return;
+        }
+
+        if !self.markets.contains_key(&market_id) && self.markets.len() >= self.config.max_markets {
+            self.evict_stalest();
+        }
+
+        let history = self.markets.entry(market_id).or_default();
+        match history.back_mut() {
+            Some(latest) if latest.cycle == cycle => latest.outcomes.push(outcome),
+            _ => {
+                history.push_back(CycleDiagnostics {
+                    market_id,
+                    cycle,
+                    recorded_at: Utc::now(),
+                    outcomes: vec![outcome],
+                });
+                while history.len() > self.config.cycles_per_market {
+                    history.pop_front();
+                }
+            }
+        }
+    }
+
+    /// The last `cycles` entries for a market, oldest first
+    pub fn recent(&self, market_id: MarketId, cycles: usize) -> Vec<CycleDiagnostics> {
+        self.markets
+            .get(&market_id)
+            .map(|history| history.iter().skip(history.len().saturating_sub(cycles)).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// The last `cycles` cycles for a market as JSON, the body `AdminApi`
+    /// returns for `GET /markets/{id}/diagnostics?cycles=N`
+    pub fn to_json(&self, market_id: MarketId, cycles: usize) -> serde_json::Value {
+        serde_json::json!({
+            "market_id": market_id,
+            "cycles": self.recent(market_id, cycles),
+        })
+    }
+
+    /// Write every buffered entry as one JSON object per line, the format
+    /// `polyctl why` reads
+    pub fn write_jsonl<W: Write>(&self, mut writer: W) -> anyhow::Result<()> {
+        for history in self.markets.values() {
+            for entry in history {
+                serde_json::to_writer(&mut writer, entry)?;
+                writer.write_all(b"\n")?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn market_count(&self) -> usize {
+        self.markets.len()
+    }
+
+    fn evict_stalest(&mut self) {
+        let stalest = self
+            .markets
+            .iter()
+            .min_by_key(|(_, history)| history.back().map(|entry| entry.cycle).unwrap_or(0))
+            .map(|(market_id, _)| *market_id);
+        if let Some(market_id) = stalest {
+            self.markets.remove(&market_id);
+        }
+    }
+}
+
+/// Running counts for one signal type
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GeneratorStats {
+    pub generated: u64,
+    /// Cleared the pipeline's thresholds and every validator
+    pub validated: u64,
+    pub rejected: u64,
+    /// Rejections keyed by threshold ("min_confidence", "min_edge") or validator name
+    pub rejection_reasons: HashMap<&'static str, u64>,
+}
+
+impl GeneratorStats {
+    /// Share of generated signals that were validated
+    pub fn pass_rate(&self) -> f64 {
+        if self.generated == 0 {
+            return 0.0;
+        }
+        self.validated as f64 / self.generated as f64
+    }
+}
+
+/// Generated, validated and rejected counts per signal type, accumulated
+/// across cycles until reset
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PipelineStats {
+    pub by_type: HashMap<SignalType, GeneratorStats>,
+}
+
+impl PipelineStats {
+    pub fn record_generated(&mut self, signal_type: &SignalType) {
+        self.entry(signal_type).generated += 1;
+    }
+
+    pub fn record_validated(&mut self, signal_type: &SignalType) {
+        self.entry(signal_type).validated += 1;
+    }
+
+    pub fn record_rejected(&mut self, signal_type: &SignalType, reason: &'static str) {
+        let stats = self.entry(signal_type);
+        stats.rejected += 1;
+        *stats.rejection_reasons.entry(reason).or_default() += 1;
+    }
+
+    /// Counts summed over every signal type
+    pub fn totals(&self) -> GeneratorStats {
+        let mut totals = GeneratorStats::default();
+        for stats in self.by_type.values() {
+            totals.generated += stats.generated;
+            totals.validated += stats.validated;
+            totals.rejected += stats.rejected;
+            for (reason, count) in &stats.rejection_reasons {
+                *totals.rejection_reasons.entry(reason).or_default() += count;
+            }
+        }
+        totals
+    }
+
+    fn entry(&mut self, signal_type: &SignalType) -> &mut GeneratorStats {
+        self.by_type.entry(signal_type.clone()).or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_buffer_bounds() {
+        let mut log = DiagnosticsLog::new(DiagnosticsConfig {
+            cycles_per_market: 3,
+            max_markets: 2,
+        });
+        let market = MarketId::random();
+        let no_signal = StageOutcome::NoSignal { generator: SignalType::Value };
+
+        for cycle in 1..=5 {
+            log.record(market, cycle, no_signal.clone());
+            log.record(market, cycle, no_signal.clone());
+        }
+        let recent = log.recent(market, 20);
+        assert_eq!(recent.iter().map(|c| c.cycle).collect::<Vec<_>>(), vec![3, 4, 5]);
+        assert!(recent.iter().all(|c| c.outcomes.len() == 2));
+        assert_eq!(log.recent(market, 1)[0].cycle, 5);
+
+        // A third market pushes out the one updated longest ago
+        let quiet = MarketId::random();
+        log.record(quiet, 2, no_signal.clone());
+        log.record(MarketId::random(), 6, no_signal.clone());
+        assert_eq!(log.market_count(), 2);
+        assert!(log.recent(quiet, 20).is_empty());
+        assert_eq!(log.recent(market, 20).len(), 3);
+
+        let json = log.to_json(market, 2);
+        assert_eq!(json["cycles"].as_array().unwrap().len(), 2);
+        assert_eq!(json["cycles"][0]["outcomes"][0]["stage"], "no_signal");
+    }
+}
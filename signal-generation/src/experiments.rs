@@ -0,0 +1,366 @@
+// Generator Experiments
+// A/B testing of config variants of a single generator within one strategy
+
+use super::signals::{SignalGenerator, SignalInput, SignalType, StateUpdate, TradeSignal, VariantTag};
+use super::diagnostics::SkipReason;
+use anyhow::{bail, Result};
+use chrono::Utc;
+use common::{
+    experiment_bucket, AbTest, AbTestMethod, AbTestStatus, AlphaSpending, AssignmentSink, ExperimentAssignment,
+    MarketId,
+};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::debug;
+use uuid::Uuid;
+
+/// A named config variant of a generator
+pub struct GeneratorVariant {
+    pub name: String,
+    /// Relative share of markets routed to this variant
+    pub weight: f64,
+    generator: Box<dyn SignalGenerator + Send>,
+}
+
+impl GeneratorVariant {
+    /// Create a new variant
+    pub fn new(
+        name: impl Into<String>,
+        weight: f64,
+        generator: Box<dyn SignalGenerator + Send>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            weight,
+            generator,
+        }
+    }
+}
+
+/// Splits markets deterministically across generator variants.
+///
+/// Every variant runs under the parent strategy, so the pipeline's throttles
+/// and the parent's risk limits apply to them as one strategy. Only
+/// attribution is split, via a per-variant strategy_version_id.
+pub struct GeneratorExperiment {
+    experiment_id: Uuid,
+    parent_strategy: String,
+    variants: Vec<GeneratorVariant>,
+    assignments: Option<Arc<dyn AssignmentSink>>,
+    /// Markets whose assignment was recorded since startup
+    recorded: HashSet<MarketId>,
+}
+
+impl GeneratorExperiment {
+    /// Create a new experiment with its control variant
+    pub fn new(experiment_id: Uuid, parent_strategy: impl Into<String>, control: GeneratorVariant) -> Self {
+        Self {
+            experiment_id,
+            parent_strategy: parent_strategy.into(),
+            variants: vec![control],
+            assignments: None,
+            recorded: HashSet::new(),
+        }
+    }
+
+    /// Record each market's variant the first time it is routed, e.g. into
+    /// the A/B test's assignments. After a restart markets are recorded
+    /// again; the assignment itself never changes.
+    pub fn with_assignments(mut self, sink: Arc<dyn AssignmentSink>) -> Self {
+        self.assignments = Some(sink);
+        self
+    }
+
+    /// Add a variant
+    pub fn add_variant(mut self, variant: GeneratorVariant) -> Self {
+        self.variants.push(variant);
+        self
+    }
+
+    pub fn experiment_id(&self) -> Uuid {
+        self.experiment_id
+    }
+
+    pub fn parent_strategy(&self) -> &str {
+        &self.parent_strategy
+    }
+
+    /// Strategy id used to attribute trades of a variant
+    pub fn strategy_version_id(&self, variant: &str) -> String {
+        format!("{}:{}", self.parent_strategy, variant)
+    }
+
+    /// Get the variant a market is assigned to
+    pub fn assign(&self, market_id: MarketId) -> &GeneratorVariant {
+        &self.variants[self.assign_index(market_id)]
+    }
+
+    fn assign_index(&self, market_id: MarketId) -> usize {
+        let total: f64 = self.variants.iter().map(|v| v.weight.max(0.0)).sum();
+        if total <= 0.0 {
+            return 0;
+        }
+
+        let bucket = experiment_bucket(self.experiment_id, market_id);
+        let mut cumulative = 0.0;
+        for (index, variant) in self.variants.iter().enumerate() {
+            cumulative += variant.weight.max(0.0);
+            if bucket < cumulative / total {
+                return index;
+            }
+        }
+
+        self.variants.len() - 1
+    }
+
+    /// Build the A/B test record for a two-variant experiment.
+    ///
+    /// The test uses the experiment id and the variants' strategy_version_ids,
+    /// so `AbTest::assign_strategy` agrees with `assign`.
+    pub fn to_ab_test(
+        &self,
+        name: impl Into<String>,
+        min_sample_size: i64,
+        statistical_significance: f64,
+    ) -> Result<AbTest> {
+        if self.variants.len() != 2 {
+            bail!(
+                "A/B test requires exactly 2 variants, experiment {} has {}",
+                self.experiment_id,
+                self.variants.len()
+            );
+        }
+
+        let a = &self.variants[0];
+        let b = &self.variants[1];
+        let total = a.weight.max(0.0) + b.weight.max(0.0);
+
+        Ok(AbTest {
+            id: self.experiment_id,
+            name: name.into(),
+            strategy_a: self.strategy_version_id(&a.name),
+            strategy_b: self.strategy_version_id(&b.name),
+            start_time: Utc::now(),
+            end_time: None,
+            status: AbTestStatus::Running,
+            allocation_ratio: if total > 0.0 { a.weight.max(0.0) / total } else { 1.0 },
+            min_sample_size,
+            statistical_significance,
+            method: AbTestMethod::default(),
+            looks_planned: 0,
+            alpha_spending: AlphaSpending::default(),
+            current_look: 0,
+        })
+    }
+}
+
+impl SignalGenerator for GeneratorExperiment {
+    fn generate(&mut self, input: &SignalInput) -> Result<Vec<TradeSignal>> {
+        let index = self.assign_index(input.market.id);
+        let name = &self.variants[index].name;
+        debug!(
+            "Market {} assigned to variant {} of experiment {}",
+            input.market.id, name, self.experiment_id
+        );
+
+        if let Some(sink) = &self.assignments {
+            if self.recorded.insert(input.market.id) {
+                sink.record(ExperimentAssignment {
+                    experiment_id: self.experiment_id,
+                    market_id: input.market.id,
+                    strategy: self.strategy_version_id(name),
+                    category: input.market.category.clone(),
+                    assigned_at: Utc::now(),
+                });
+            }
+        }
+
+        let tag = VariantTag {
+            experiment_id: self.experiment_id,
+            variant: name.clone(),
+            parent_strategy: self.parent_strategy.clone(),
+            strategy_version_id: self.strategy_version_id(name),
+        };
+
+        let signals = self.variants[index]
+            .generator
+            .generate(input)?
+            .into_iter()
+            .map(|mut signal| {
+                signal.variant = Some(tag.clone());
+                signal
+            })
+            .collect();
+
+        Ok(signals)
+    }
+
+    /// Routed to the variant that owns the market, so its state stays consistent
+    fn update_state(&mut self, market_id: MarketId, update: &StateUpdate) {
+        let index = self.assign_index(market_id);
+        self.variants[index].generator.update_state(market_id, update);
+    }
+
+    /// Each variant's state, keyed by variant name
+    fn snapshot_state(&self) -> Option<serde_json::Value> {
+        let states: serde_json::Map<String, serde_json::Value> = self
+            .variants
+            .iter()
+            .filter_map(|v| Some((v.name.clone(), v.generator.snapshot_state()?)))
+            .collect();
+
+        (!states.is_empty()).then_some(serde_json::Value::Object(states))
+    }
+
+    fn restore_state(&mut self, state: serde_json::Value) -> Result<()> {
+        let serde_json::Value::Object(mut states) = state else {
+            bail!("Experiment {} state is not an object", self.experiment_id);
+        };
+
+        for variant in &mut self.variants {
+            if let Some(state) = states.remove(&variant.name) {
+                variant.generator.restore_state(state)?;
+            }
+        }
+
+        if let Some(name) = states.keys().next() {
+            bail!("Experiment {} has no variant {}", self.experiment_id, name);
+        }
+
+        Ok(())
+    }
+
+    fn skip_reason(&self, input: &SignalInput) -> Option<SkipReason> {
+        let index = self.assign_index(input.market.id);
+        self.variants[index].generator.skip_reason(input)
+    }
+
+    fn signal_type(&self) -> SignalType {
+        self.variants[0].generator.signal_type()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signals::{SignalDirection, SignalMetadata};
+    use crate::test_support::input;
+    use rust_decimal::Decimal;
+    use std::collections::HashMap;
+
+    /// Emits a signal whose edge is the variant's configured min edge
+    struct FixedEdgeGenerator {
+        min_edge: Decimal,
+    }
+
+    impl SignalGenerator for FixedEdgeGenerator {
+        fn generate(&mut self, input: &SignalInput) -> Result<Vec<TradeSignal>> {
+            Ok(vec![TradeSignal {
+                id: Uuid::new_v4(),
+                market_id: input.market.id,
+                signal_type: SignalType::Value,
+                direction: SignalDirection::Long,
+                outcome_id: None,
+                entry_price: Decimal::new(5, 1),
+                target_price: Decimal::new(6, 1),
+                stop_loss: Decimal::new(4, 1),
+                position_size: Decimal::from(100),
+                confidence: 0.8,
+                expected_value: Decimal::from(5),
+                edge: self.min_edge,
+                kelly_fraction: 0.05,
+                reasoning: "test".to_string(),
+                metadata: SignalMetadata {
+                    research_sources: vec![],
+                    data_points: 0,
+                    liquidity_score: 0.5,
+                    volatility_score: 0.5,
+                    price_age_ms: None,
+                    orderbook_age_ms: None,
+                    research_age_ms: None,
+                    custom_fields: serde_json::json!({}),
+                },
+                created_at: Utc::now(),
+                expires_at: None,
+                variant: None,
+            }])
+        }
+
+        fn signal_type(&self) -> SignalType {
+            SignalType::Value
+        }
+    }
+
+    fn create_experiment(experiment_id: Uuid) -> GeneratorExperiment {
+        GeneratorExperiment::new(
+            experiment_id,
+            "value",
+            GeneratorVariant::new("edge_3", 1.0, Box::new(FixedEdgeGenerator { min_edge: Decimal::new(3, 2) })),
+        )
+        .add_variant(GeneratorVariant::new(
+            "edge_5",
+            1.0,
+            Box::new(FixedEdgeGenerator { min_edge: Decimal::new(5, 2) }),
+        ))
+    }
+
+    #[test]
+    fn test_assignment_is_deterministic() {
+        let experiment_id = Uuid::new_v4();
+        let first = create_experiment(experiment_id);
+        let second = create_experiment(experiment_id);
+        let ab_test = first.to_ab_test("edge", 10, 0.95).unwrap();
+
+        for _ in 0..200 {
+            let market_id = MarketId::random();
+            let variant = &first.assign(market_id).name;
+            assert_eq!(variant, &first.assign(market_id).name);
+            assert_eq!(variant, &second.assign(market_id).name);
+            assert_eq!(first.strategy_version_id(variant), ab_test.assign_strategy(market_id));
+        }
+    }
+
+    #[test]
+    fn test_signals_attributed_per_variant() {
+        let mut experiment = create_experiment(Uuid::new_v4());
+        let mut by_version: HashMap<String, Vec<Decimal>> = HashMap::new();
+
+        for _ in 0..200 {
+            let market_id = MarketId::random();
+            let signal = experiment.generate(&input(market_id).build()).unwrap().remove(0);
+            let tag = signal.variant.clone().unwrap();
+
+            assert_eq!(tag.parent_strategy, "value");
+            assert_eq!(signal.strategy_id("value"), tag.strategy_version_id);
+            assert_eq!(tag.variant, experiment.assign(market_id).name);
+            by_version.entry(tag.strategy_version_id).or_default().push(signal.edge);
+        }
+
+        assert_eq!(by_version.len(), 2);
+        assert!(by_version["value:edge_3"].iter().all(|e| *e == Decimal::new(3, 2)));
+        assert!(by_version["value:edge_5"].iter().all(|e| *e == Decimal::new(5, 2)));
+    }
+
+    #[test]
+    fn test_assignments_recorded_once_per_market() {
+        let sink = Arc::new(common::InMemoryAssignmentSink::new());
+        let mut experiment = create_experiment(Uuid::new_v4()).with_assignments(sink.clone());
+        let ab_test = experiment.to_ab_test("edge", 10, 0.95).unwrap();
+        let markets: Vec<MarketId> = (0..20).map(|_| MarketId::random()).collect();
+
+        for _ in 0..3 {
+            for market_id in &markets {
+                experiment.generate(&input(*market_id).build()).unwrap();
+            }
+        }
+
+        let assignments = sink.assignments();
+        assert_eq!(assignments.len(), markets.len());
+        for (assignment, market_id) in assignments.iter().zip(&markets) {
+            assert_eq!(assignment.experiment_id, ab_test.id);
+            assert_eq!(assignment.market_id, *market_id);
+            assert_eq!(assignment.strategy, ab_test.assign_strategy(*market_id));
+            assert_eq!(assignment.category, "politics");
+        }
+    }
+}
@@ -1,15 +1,56 @@
 // Signal Generation Framework (Layer 2)
 // Processes research outputs and market data to generate trade signals
 
+pub mod admin;
+pub mod backtest;
+pub mod config_audit;
+pub mod correlation;
+pub mod correlation_config;
+pub mod diagnostics;
+pub mod experiments;
+pub mod market_making;
+pub mod mean_reversion;
+pub mod momentum;
+pub mod order_book_imbalance;
+pub mod pair_cost_arbitrage;
 pub mod pipeline;
+pub mod postgres_storage;
+pub mod research_adapter;
+pub mod sentiment_polarity;
 pub mod signals;
+pub mod slippage;
 pub mod validators;
 pub mod storage;
+pub mod volatility;
 
-pub use pipeline::{PipelineConfig, SignalPipeline};
+#[cfg(test)]
+mod test_support;
+
+pub use admin::{AdminApi, DEFAULT_DIAGNOSTICS_CYCLES};
+pub use backtest::{BacktestConfig, Backtester, FillModel};
+pub use config_audit::{ConfigAudit, ConfigChange, InMemoryConfigAudit};
+pub use correlation::{CorrelationEdge, CorrelationGenerator, CorrelationGraph, CorrelationGroup, CorrelationType, GroupType, ReloadOutcome, RulePreview, DEFAULT_MAX_AUTO_APPLY_SIGNALS};
+pub use correlation_config::{CorrelationConfig, CorrelationRule, EdgeRule, MarketRegistry, MutuallyExclusiveGroup, Relation, RuleError, RuleValidationError};
+#[cfg(unix)]
+pub use correlation_config::reload_on_sighup;
+pub use diagnostics::{CycleDiagnostics, DiagnosticsConfig, DiagnosticsLog, GeneratorStats, PipelineStats, SkipReason, StageOutcome};
+pub use experiments::{GeneratorExperiment, GeneratorVariant};
+pub use market_making::{MarketMakingConfig, MarketMakingGenerator};
+pub use mean_reversion::{MeanReversionConfig, MeanReversionGenerator};
+pub use momentum::{MomentumConfig, MomentumGenerator};
+pub use order_book_imbalance::{OrderBookImbalanceConfig, OrderBookImbalanceGenerator};
+pub use pair_cost_arbitrage::{PairCostConfig, PairCostGenerator, PairCostSnapshot};
+pub use pipeline::{PipelineConfig, RankingWeights, SignalPipeline};
+pub use postgres_storage::{PostgresExecutionStorage, PostgresSignalStorage};
+pub use research_adapter::{ResearchAggregator, ResearchAggregatorConfig};
+pub use sentiment_polarity::{DirectionalSentiment, Polarity, PolarityResolution, PolaritySource, SentimentPolarityResolver};
 pub use signals::{
     TradeSignal, SignalInput, ResearchOutput, SignalGenerator, SignalType, SignalDirection,
-    SpreadArbitrageGenerator, SignalMetadata, PriceSnapshot, OrderBookSnapshot, Level, SentimentScore, SentimentSource
+    SpreadArbitrageGenerator, SignalMetadata, PriceSnapshot, OrderBookSnapshot, Level, SentimentScore, SentimentSource, VariantTag,
+    StateUpdate, PairSide, SingleSignalGenerator, SingleSignalAdapter, BatchSignalGenerator,
+    round_to_tick, DEFAULT_TICK_SIZE,
 };
-pub use validators::{SignalValidator, EdgeThresholdValidator, EdgeThresholdConfig, ConfidenceValidator, ConfidenceValidatorConfig, LiquidityValidator, LiquidityValidatorConfig, ExpectedValueValidator, ExpectedValueValidatorConfig, CompositeValidator};
+pub use slippage::{SlippageModel, SlippageModelConfig};
+pub use validators::{SignalValidator, EdgeThresholdValidator, EdgeThresholdConfig, ConfidenceValidator, ConfidenceValidatorConfig, LiquidityValidator, LiquidityValidatorConfig, ExpectedValueValidator, ExpectedValueValidatorConfig, StalenessValidator, StalenessValidatorConfig, CompositeValidator, ScoringPolicy, ValidationResult, ValidatorScore, MAX_VALIDATION_SCORE};
+pub use volatility::{VolatilityCalibration, VolatilityEstimator, VolatilityEstimatorConfig, VolatilityScore};
 pub use storage::{SignalStorage, InMemoryStorage, StorageStats, ExecutionStorage, InMemoryExecutionStorage, SignalExecutionResult, ExitReason, BacktestStats, SignalTypeStats};
@@ -1,14 +1,19 @@
 // Market Making Signal Generator
 // Generates signals for providing liquidity on both sides of markets
 
+use anyhow::Result;
 use chrono::{DateTime, Utc};
-use common::{Market, OrderSide};
+use common::{MarketId, OrderSide, RewardsModel};
+use rust_decimal::prelude::FromPrimitive;
 use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use uuid::Uuid;
 
+use crate::diagnostics::SkipReason;
 use crate::signals::{
-    SignalInput, SignalDirection, SignalGenerator, SignalMetadata, SignalType, TradeSignal,
-    OrderBookSnapshot, Level, StateUpdate, MultiSignalGenerator
+    PairSide, SignalInput, SignalDirection, SignalGenerator, SignalMetadata, SignalType, TradeSignal,
+    StateUpdate, round_to_tick,
 };
 
 /// Market making configuration
@@ -26,6 +31,15 @@ pub struct MarketMakingConfig {
     pub inventory_adjustment: Decimal,
     /// Widen spreads during breaking news
     pub news_spread_multiplier: Decimal,
+    /// Shift of both quotes per unit of imbalance, away from the side held:
+    /// long YES moves both quotes down
+    pub inventory_skew: Decimal,
+    /// Pull of the research probability on the quotes' anchor, scaled by
+    /// research confidence: 0 quotes around the mid, 1 at full confidence
+    /// quotes around the research probability
+    pub fair_value_skew: Decimal,
+    /// Volatility score above which every resting quote is pulled
+    pub cancel_volatility: f64,
 }
 
 impl Default for MarketMakingConfig {
@@ -37,18 +51,34 @@ impl Default for MarketMakingConfig {
             volatility_multiplier: Decimal::from_str_exact("1.5").unwrap(), // 1.5x spread in high vol
             inventory_adjustment: Decimal::from_str_exact("0.1").unwrap(), // 10% adjustment per imbalance
             news_spread_multiplier: Decimal::from_str_exact("2.0").unwrap(), // 2x spread during news
+            inventory_skew: Decimal::from_str_exact("0.01").unwrap(), // 1 cent at full imbalance
+            fair_value_skew: Decimal::ZERO, // quote around the mid
+            cancel_volatility: 0.9,
         }
     }
 }
 
 /// Market making state
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarketMakingState {
     pub yes_inventory: Decimal,
     pub no_inventory: Decimal,
     pub total_invested: Decimal,
     pub last_spread: Decimal,
     pub volatility_score: f64,
+    /// Resting YES quote; requotes amend it until it is cancelled
+    #[serde(default)]
+    pub yes_quote_id: Option<Uuid>,
+    /// Resting NO quote
+    #[serde(default)]
+    pub no_quote_id: Option<Uuid>,
+    /// Side that went past `max_inventory_imbalance`; it is not quoted again
+    /// until the inventory is balanced
+    #[serde(default)]
+    pub unwinding: Option<PairSide>,
+    /// Until when the last unwind order is live; a fill lets the next one out early
+    #[serde(default)]
+    pub unwind_expires_at: Option<DateTime<Utc>>,
 }
 
 impl Default for MarketMakingState {
@@ -59,6 +89,10 @@ impl Default for MarketMakingState {
             total_invested: Decimal::ZERO,
             last_spread: Decimal::ZERO,
             volatility_score: 0.0,
+            yes_quote_id: None,
+            no_quote_id: None,
+            unwinding: None,
+            unwind_expires_at: None,
         }
     }
 }
@@ -66,17 +100,32 @@ impl Default for MarketMakingState {
 /// Market making signal generator
 pub struct MarketMakingGenerator {
     config: MarketMakingConfig,
-    states: std::collections::HashMap<Uuid, MarketMakingState>,
+    states: HashMap<MarketId, MarketMakingState>,
+    rewards: RewardsModel,
 }
 
 impl MarketMakingGenerator {
     pub fn new(config: MarketMakingConfig) -> Self {
         MarketMakingGenerator {
             config,
-            states: std::collections::HashMap::new(),
+            states: HashMap::new(),
+            rewards: RewardsModel::default(),
         }
     }
 
+    /// Count liquidity rebates on a quote's fill towards its expected value
+    pub fn with_rewards(mut self, rewards: RewardsModel) -> Self {
+        self.rewards = rewards;
+        self
+    }
+
+    /// Rebate a quote earns if filled now at `price`
+    fn expected_rebate(&self, market_id: MarketId, price: Decimal) -> Decimal {
+        let notional = price * self.config.base_position_size;
+        let rate = self.rewards.rebate_rate(market_id, Utc::now());
+        Decimal::from_f64(rate).unwrap_or(Decimal::ZERO) * notional
+    }
+
     /// Calculate inventory imbalance (-1 to 1)
     fn calculate_imbalance(&self, state: &MarketMakingState) -> Decimal {
         if state.yes_inventory + state.no_inventory == Decimal::ZERO {
@@ -94,72 +143,211 @@ impl MarketMakingGenerator {
         base_spread + adjustment
     }
 
+    /// Overweight side being unwound: set once the imbalance passes the
+    /// limit, cleared once it no longer leans that way
+    fn unwinding_side(&self, state: &MarketMakingState, imbalance: Decimal) -> Option<PairSide> {
+        let imbalance_threshold = self.config.max_inventory_imbalance;
+        if imbalance > imbalance_threshold {
+            return Some(PairSide::Yes);
+        }
+        if imbalance < -imbalance_threshold {
+            return Some(PairSide::No);
+        }
+        state.unwinding.filter(|side| match side {
+            PairSide::Yes => imbalance > Decimal::ZERO,
+            PairSide::No => imbalance < Decimal::ZERO,
+        })
+    }
+
     /// Check if we should provide liquidity on a side
     fn should_provide_liquidity(
         &self,
         state: &MarketMakingState,
         side: OrderSide,
-        imbalance: Decimal,
     ) -> bool {
-        let imbalance_threshold = self.config.max_inventory_imbalance;
-
         match side {
-            OrderSide::Buy => {
-                // Don't buy YES if we have too much YES inventory
-                if imbalance > imbalance_threshold {
-                    return false;
-                }
-                true
-            }
-            OrderSide::Sell => {
-                // Don't sell NO if we have too much NO inventory
-                if imbalance < -imbalance_threshold {
-                    return false;
-                }
-                true
-            }
+            // Don't buy YES while unwinding too much YES inventory
+            OrderSide::Buy => state.unwinding != Some(PairSide::Yes),
+            // Don't sell NO while unwinding too much NO inventory
+            OrderSide::Sell => state.unwinding != Some(PairSide::No),
         }
     }
 
-    /// Generate limit order prices
+    /// Price to quote around: the mid, moved toward the research probability
+    /// by `fair_value_skew` times the research confidence
+    fn fair_value_anchor(&self, mid_price: Decimal, input: &SignalInput) -> Decimal {
+        let research = &input.research_output;
+        let (Some(probability), Some(confidence)) = (
+            research.probability_estimate.and_then(Decimal::from_f64),
+            Decimal::from_f64(research.confidence.clamp(0.0, 1.0)),
+        ) else {
+            return mid_price;
+        };
+        let weight = (self.config.fair_value_skew * confidence).min(Decimal::ONE);
+        mid_price + weight * (probability - mid_price)
+    }
+
+    /// Generate limit order prices: a YES bid and a NO bid, the NO bid being
+    /// the complement of the YES ask. Both sit around a reservation price:
+    /// the anchor shaded against the inventory held.
     fn generate_order_prices(
         &self,
-        market_price: Decimal,
+        anchor: Decimal,
         spread: Decimal,
+        imbalance: Decimal,
     ) -> (Decimal, Decimal) {
         let half_spread = spread / Decimal::from_str_exact("2").unwrap();
+        let reservation = anchor - imbalance * self.config.inventory_skew;
 
-        let yes_price = market_price - half_spread;
-        let no_price = Decimal::ONE - yes_price;
+        let yes_price = reservation - half_spread;
+        let no_price = Decimal::ONE - (reservation + half_spread);
 
         let min_price = Decimal::from_str_exact("0.01").unwrap();
         (yes_price.max(min_price), no_price.max(min_price))
     }
+
+    /// Signal flattening the overweight side by buying the other one at its
+    /// touch, crossing the spread
+    fn unwind_signal(
+        &self,
+        input: &SignalInput,
+        state: &MarketMakingState,
+        (best_bid, best_ask): (Decimal, Decimal),
+        anchor: Decimal,
+        expires_at: DateTime<Utc>,
+    ) -> Option<TradeSignal> {
+        let (direction, entry_price, fair_value, size, side) = match state.unwinding? {
+            // The NO ask is the complement of the YES bid
+            PairSide::Yes => (
+                SignalDirection::Short,
+                Decimal::ONE - best_bid,
+                Decimal::ONE - anchor,
+                state.yes_inventory - state.no_inventory,
+                "yes",
+            ),
+            PairSide::No => (
+                SignalDirection::Long,
+                best_ask,
+                anchor,
+                state.no_inventory - state.yes_inventory,
+                "no",
+            ),
+        };
+        if size <= Decimal::ZERO || entry_price <= Decimal::ZERO {
+            return None;
+        }
+        let tick_size = input.tick_size();
+        let (entry_price, fair_value) = (round_to_tick(entry_price, tick_size), round_to_tick(fair_value, tick_size));
+
+        Some(TradeSignal {
+            id: Uuid::new_v4(),
+            market_id: input.market.id,
+            signal_type: SignalType::MeanReversion,
+            direction,
+            outcome_id: input.market.outcomes.first().map(|o| o.id.clone()),
+            entry_price,
+            target_price: fair_value,
+            stop_loss: Decimal::ZERO,
+            position_size: size,
+            confidence: 1.0,
+            expected_value: (fair_value - entry_price) * size,
+            edge: (fair_value - entry_price) / entry_price,
+            kelly_fraction: 0.0,
+            reasoning: format!(
+                "Market making: unwinding {} {} inventory at {:.4}, crossing the spread",
+                size, side.to_uppercase(), entry_price
+            ),
+            metadata: SignalMetadata {
+                research_sources: vec!["market_making".to_string()],
+                data_points: 1,
+                liquidity_score: 0.9,
+                volatility_score: state.volatility_score,
+                price_age_ms: None,
+                orderbook_age_ms: None,
+                research_age_ms: None,
+                custom_fields: serde_json::json!({
+                    "strategy": "market_making",
+                    "unwind": true,
+                    "overweight_side": side,
+                    "fair_value": anchor.to_string(),
+                    "yes_inventory": state.yes_inventory.to_string(),
+                    "no_inventory": state.no_inventory.to_string(),
+                }),
+            },
+            created_at: Utc::now(),
+            expires_at: Some(expires_at),
+            variant: None,
+        })
+    }
+
+    /// Signal pulling the resting quote `quote_id` on the `direction` side
+    fn cancel_signal(
+        &self,
+        input: &SignalInput,
+        state: &MarketMakingState,
+        direction: SignalDirection,
+        quote_id: Uuid,
+        reason: &str,
+    ) -> TradeSignal {
+        let side = if direction == SignalDirection::Long { "YES" } else { "NO" };
+        TradeSignal {
+            id: Uuid::new_v4(),
+            market_id: input.market.id,
+            signal_type: SignalType::CancelQuote,
+            direction,
+            outcome_id: input.market.outcomes.first().map(|o| o.id.clone()),
+            entry_price: Decimal::ZERO,
+            target_price: Decimal::ZERO,
+            stop_loss: Decimal::ZERO,
+            position_size: Decimal::ZERO,
+            confidence: 1.0,
+            expected_value: Decimal::ZERO,
+            edge: Decimal::ZERO,
+            kelly_fraction: 0.0,
+            reasoning: format!("Market making: cancelling {} quote {}, {}", side, quote_id, reason),
+            metadata: SignalMetadata {
+                research_sources: vec!["market_making".to_string()],
+                data_points: 1,
+                liquidity_score: 0.9,
+                volatility_score: state.volatility_score,
+                price_age_ms: None,
+                orderbook_age_ms: None,
+                research_age_ms: None,
+                custom_fields: serde_json::json!({
+                    "strategy": "market_making",
+                    "quote_id": quote_id.to_string(),
+                    "cancel_reason": reason,
+                    "yes_inventory": state.yes_inventory.to_string(),
+                    "no_inventory": state.no_inventory.to_string(),
+                }),
+            },
+            created_at: Utc::now(),
+            expires_at: None,
+            variant: None,
+        }
+    }
 }
 
 impl SignalGenerator for MarketMakingGenerator {
-    fn generate(&mut self, input: &SignalInput) -> Vec<TradeSignal> {
+    fn generate(&mut self, input: &SignalInput) -> Result<Vec<TradeSignal>> {
         let order_book = match &input.order_book {
             Some(ob) => ob,
-            None => return vec![], // Need order book for market making
+            None => return Ok(vec![]), // Need order book for market making
         };
 
         // Get or create state
-        let state = self.states
-            .entry(input.market.id)
-            .or_insert_with(MarketMakingState::default);
+        let mut state = self.states.entry(input.market.id).or_default().clone();
 
         // Calculate current market price (midpoint)
-        let mid_price = if !order_book.bids.is_empty() && !order_book.asks.is_empty() {
-            let best_bid = order_book.bids.first().unwrap().price;
-            let best_ask = order_book.asks.first().unwrap().price;
-            (best_bid + best_ask) / Decimal::from(2)
-        } else {
-            return vec![];
+        let (best_bid, best_ask) = match (order_book.bids.first(), order_book.asks.first()) {
+            (Some(bid), Some(ask)) => (bid.price, ask.price),
+            _ => return Ok(vec![]),
         };
+        let mid_price = (best_bid + best_ask) / Decimal::from(2);
 
         // Calculate inventory imbalance
-        let imbalance = self.calculate_imbalance(state);
+        let imbalance = self.calculate_imbalance(&state);
+        state.unwinding = self.unwinding_side(&state, imbalance);
 
         // Calculate spread
         let base_spread = if state.volatility_score > 0.7 {
@@ -170,26 +358,53 @@ impl SignalGenerator for MarketMakingGenerator {
 
         let adjusted_spread = self.calculate_adjusted_spread(base_spread, imbalance);
 
-        // Generate order prices
-        let (yes_price, no_price) = self.generate_order_prices(mid_price, adjusted_spread);
+        // Generate order prices; the anchor is also what fills are valued at
+        let anchor = self.fair_value_anchor(mid_price, input);
+        let (yes_price, no_price) = self.generate_order_prices(anchor, adjusted_spread, imbalance);
+        let tick_size = input.tick_size();
+        let (yes_price, no_price) = (round_to_tick(yes_price, tick_size), round_to_tick(no_price, tick_size));
 
         let mut signals = Vec::new();
 
+        // Pull everything in a volatility spike, otherwise only the side
+        // inventory rules out
+        let volatile = state.volatility_score > self.config.cancel_volatility;
+        let quote_yes = !volatile && self.should_provide_liquidity(&state, OrderSide::Buy);
+        let quote_no = !volatile && self.should_provide_liquidity(&state, OrderSide::Sell);
+        let reason = if volatile { "volatility" } else { "inventory_imbalance" };
+        let yes_quote_id = match (quote_yes, state.yes_quote_id) {
+            (true, id) => Some(id.unwrap_or_else(Uuid::new_v4)),
+            (false, Some(id)) => {
+                signals.push(self.cancel_signal(input, &state, SignalDirection::Long, id, reason));
+                None
+            }
+            (false, None) => None,
+        };
+        let no_quote_id = match (quote_no, state.no_quote_id) {
+            (true, id) => Some(id.unwrap_or_else(Uuid::new_v4)),
+            (false, Some(id)) => {
+                signals.push(self.cancel_signal(input, &state, SignalDirection::Short, id, reason));
+                None
+            }
+            (false, None) => None,
+        };
+
         // Generate YES liquidity signal (buy YES at lower price)
-        if self.should_provide_liquidity(state, OrderSide::Buy, imbalance) {
+        if let Some(quote_id) = yes_quote_id {
+            let rebate = self.expected_rebate(input.market.id, yes_price);
             let yes_signal = TradeSignal {
                 id: Uuid::new_v4(),
                 market_id: input.market.id,
                 signal_type: SignalType::MeanReversion, // Using MeanReversion for liquidity
                 direction: SignalDirection::Long,
-                outcome_id: input.market.outcomes.get(0).map(|o| o.id.clone()),
+                outcome_id: input.market.outcomes.first().map(|o| o.id.clone()),
                 entry_price: yes_price,
-                target_price: mid_price,
-                stop_loss: yes_price * Decimal::from_str_exact("0.95").unwrap(), // 5% stop loss
+                target_price: round_to_tick(anchor, tick_size),
+                stop_loss: round_to_tick(yes_price * Decimal::from_str_exact("0.95").unwrap(), tick_size), // 5% stop loss
                 position_size: self.config.base_position_size,
                 confidence: 0.85, // High confidence for market making
-                expected_value: (mid_price - yes_price) * self.config.base_position_size,
-                edge: (mid_price - yes_price) / yes_price,
+                expected_value: (anchor - yes_price) * self.config.base_position_size + rebate,
+                edge: (anchor - yes_price) / yes_price,
                 kelly_fraction: 0.1, // Conservative position sizing
                 reasoning: format!(
                     "Market making: providing YES liquidity at {:.4}, current mid {:.4}, spread {:.4}%",
@@ -200,35 +415,43 @@ impl SignalGenerator for MarketMakingGenerator {
                     data_points: 1,
                     liquidity_score: 0.9,
                     volatility_score: state.volatility_score,
+                    price_age_ms: None,
+                    orderbook_age_ms: None,
+                    research_age_ms: None,
                     custom_fields: serde_json::json!({
                         "strategy": "market_making",
                         "inventory_imbalance": imbalance.to_string(),
                         "spread": adjusted_spread.to_string(),
+                        "fair_value": anchor.to_string(),
                         "yes_inventory": state.yes_inventory.to_string(),
                         "no_inventory": state.no_inventory.to_string(),
+                        "expected_rebate": rebate.to_string(),
+                        "quote_id": quote_id.to_string(),
                     }),
                 },
                 created_at: Utc::now(),
                 expires_at: Some(Utc::now() + chrono::Duration::minutes(30)), // 30 min validity
+                variant: None,
             };
             signals.push(yes_signal);
         }
 
         // Generate NO liquidity signal (buy NO at lower price = sell YES)
-        if self.should_provide_liquidity(state, OrderSide::Sell, imbalance) {
+        if let Some(quote_id) = no_quote_id {
+            let rebate = self.expected_rebate(input.market.id, no_price);
             let no_signal = TradeSignal {
                 id: Uuid::new_v4(),
                 market_id: input.market.id,
                 signal_type: SignalType::MeanReversion,
                 direction: SignalDirection::Short,
-                outcome_id: input.market.outcomes.get(0).map(|o| o.id.clone()),
+                outcome_id: input.market.outcomes.first().map(|o| o.id.clone()),
                 entry_price: no_price,
-                target_price: Decimal::ONE - mid_price,
-                stop_loss: no_price * Decimal::from_str_exact("1.05").unwrap(), // 5% stop loss
+                target_price: round_to_tick(Decimal::ONE - anchor, tick_size),
+                stop_loss: round_to_tick(no_price * Decimal::from_str_exact("1.05").unwrap(), tick_size), // 5% stop loss
                 position_size: self.config.base_position_size,
                 confidence: 0.85,
-                expected_value: ((Decimal::ONE - mid_price) - no_price) * self.config.base_position_size,
-                edge: ((Decimal::ONE - mid_price) - no_price) / no_price,
+                expected_value: ((Decimal::ONE - anchor) - no_price) * self.config.base_position_size + rebate,
+                edge: ((Decimal::ONE - anchor) - no_price) / no_price,
                 kelly_fraction: 0.1,
                 reasoning: format!(
                     "Market making: providing NO liquidity at {:.4}, current NO price {:.4}, spread {:.4}%",
@@ -239,92 +462,331 @@ impl SignalGenerator for MarketMakingGenerator {
                     data_points: 1,
                     liquidity_score: 0.9,
                     volatility_score: state.volatility_score,
+                    price_age_ms: None,
+                    orderbook_age_ms: None,
+                    research_age_ms: None,
                     custom_fields: serde_json::json!({
                         "strategy": "market_making",
                         "inventory_imbalance": imbalance.to_string(),
                         "spread": adjusted_spread.to_string(),
+                        "fair_value": anchor.to_string(),
                         "yes_inventory": state.yes_inventory.to_string(),
                         "no_inventory": state.no_inventory.to_string(),
+                        "expected_rebate": rebate.to_string(),
+                        "quote_id": quote_id.to_string(),
                     }),
                 },
                 created_at: Utc::now(),
                 expires_at: Some(Utc::now() + chrono::Duration::minutes(30)),
+                variant: None,
             };
             signals.push(no_signal);
         }
 
-        state.last_spread = adjusted_spread;
+        // Past the limit, flatten actively too, one unwind order at a time
+        let now = Utc::now();
+        let mut unwind_expires_at = state.unwind_expires_at;
+        let over_limit = imbalance.abs() > self.config.max_inventory_imbalance;
+        if over_limit && unwind_expires_at.is_none_or(|expiry| expiry <= now) {
+            let expires_at = now + chrono::Duration::minutes(1);
+            if let Some(unwind) = self.unwind_signal(input, &state, (best_bid, best_ask), anchor, expires_at) {
+                signals.push(unwind);
+                unwind_expires_at = Some(expires_at);
+            }
+        }
 
-        signals
+        if let Some(entry) = self.states.get_mut(&input.market.id) {
+            entry.last_spread = adjusted_spread;
+            entry.yes_quote_id = yes_quote_id;
+            entry.no_quote_id = no_quote_id;
+            entry.unwinding = state.unwinding;
+            entry.unwind_expires_at = unwind_expires_at;
+        }
+
+        Ok(signals)
     }
 
-    fn update_state(&mut self, market_id: Uuid, update: &crate::signals::StateUpdate) {
-        let state = self.states.entry(market_id).or_insert_with(MarketMakingState::default());
+    fn update_state(&mut self, market_id: MarketId, update: &StateUpdate) {
+        let state = self.states.entry(market_id).or_default();
 
         match update {
-            crate::signals::StateUpdate::TradeExecution { side, size, price, .. } => {
-                let cost = *size * *price;
-
+            StateUpdate::TradeExecution { outcome_side, side, size, price, .. } => {
+                state.unwind_expires_at = None;
+                let inventory = match outcome_side {
+                    PairSide::Yes => &mut state.yes_inventory,
+                    PairSide::No => &mut state.no_inventory,
+                };
                 match side {
                     OrderSide::Buy => {
-                        state.yes_inventory += *size;
-                        state.total_invested += cost;
+                        *inventory += *size;
+                        state.total_invested += *size * *price;
                     }
                     OrderSide::Sell => {
-                        state.no_inventory += *size;
-                        state.total_invested += cost;
+                        *inventory = (*inventory - *size).max(Decimal::ZERO);
+                        state.total_invested -= *size * *price;
                     }
                 }
             }
-            crate::signals::StateUpdate::PositionClosed { side, size, realized_pnl, .. } => {
-                match side {
-                    OrderSide::Buy => {
-                        state.yes_inventory -= *size;
-                    }
-                    OrderSide::Sell => {
-                        state.no_inventory -= *size;
-                    }
-                }
+            StateUpdate::PositionClosed { outcome_side, size, realized_pnl, .. } => {
+                state.unwind_expires_at = None;
+                let inventory = match outcome_side {
+                    PairSide::Yes => &mut state.yes_inventory,
+                    PairSide::No => &mut state.no_inventory,
+                };
+                *inventory = (*inventory - *size).max(Decimal::ZERO);
                 state.total_invested += *realized_pnl;
             }
-            crate::signals::StateUpdate::VolatilityUpdate { score } => {
+            StateUpdate::VolatilityUpdate { score, .. } => {
                 state.volatility_score = *score;
             }
         }
     }
+
+    fn snapshot_state(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(&self.states).ok()
+    }
+
+    fn restore_state(&mut self, state: serde_json::Value) -> Result<()> {
+        self.states = serde_json::from_value(state)?;
+        Ok(())
+    }
+
+    fn skip_reason(&self, input: &SignalInput) -> Option<SkipReason> {
+        match &input.order_book {
+            None => Some(SkipReason::MissingData { data: "order_book" }),
+            Some(ob) if ob.bids.is_empty() || ob.asks.is_empty() => {
+                Some(SkipReason::DegradedData { detail: "one_sided_book" })
+            }
+            Some(_) => None,
+        }
+    }
+
+    fn signal_type(&self) -> SignalType {
+        SignalType::MeanReversion
+    }
 }
 
-// Implement old SignalGenerator trait for backward compatibility
-impl SignalGenerator for MarketMakingGenerator {
-    fn generate(&self, input: &SignalInput) -> anyhow::Result<Option<TradeSignal>> {
-        let order_book = match &input.order_book {
-            Some(ob) => ob,
-            None => return Ok(None),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::input;
+    use common::RewardCampaign;
+
+    fn create_input(market_id: MarketId) -> SignalInput {
+        input(market_id)
+            // Fine enough for the half-cent skews the tests assert
+            .market(|market| market.tick_size = Some(0.001))
+            .quote("0.49", "0.51")
+            .build()
+    }
+
+    #[test]
+    fn test_rebates_raise_expected_value_by_rate() {
+        let market_id = MarketId::random();
+        let input = create_input(market_id);
+        let campaign = RewardCampaign {
+            market_id: Some(market_id),
+            rate: 0.004,
+            starts_at: Utc::now() - chrono::Duration::hours(1),
+            ends_at: Utc::now() + chrono::Duration::hours(1),
         };
 
-        // Calculate current market price (midpoint)
-        let mid_price = if !order_book.bids.is_empty() && !order_book.asks.is_empty() {
-            let best_bid = order_book.bids.first().unwrap().price;
-            let best_ask = order_book.asks.first().unwrap().price;
-            (best_bid + best_ask) / Decimal::from_str_exact("2").unwrap()
-        } else {
-            return Ok(None);
+        let plain = MarketMakingGenerator::new(MarketMakingConfig::default())
+            .generate(&input)
+            .unwrap();
+        let rewarded = MarketMakingGenerator::new(MarketMakingConfig::default())
+            .with_rewards(RewardsModel::new().with_campaign(campaign.clone()))
+            .generate(&input)
+            .unwrap();
+        assert_eq!(plain.len(), 2);
+        assert_eq!(rewarded.len(), 2);
+
+        let rate = Decimal::from_str_exact("0.004").unwrap();
+        for (plain, rewarded) in plain.iter().zip(&rewarded) {
+            let expected = rate * rewarded.entry_price * rewarded.position_size;
+            assert_eq!(rewarded.expected_value - plain.expected_value, expected);
+            assert_eq!(rewarded.metadata.custom_fields["expected_rebate"], expected.to_string());
+        }
+
+        // A campaign that has ended adds nothing
+        let ended = RewardCampaign {
+            ends_at: Utc::now() - chrono::Duration::minutes(1),
+            ..campaign
         };
+        let after = MarketMakingGenerator::new(MarketMakingConfig::default())
+            .with_rewards(RewardsModel::new().with_campaign(ended))
+            .generate(&input)
+            .unwrap();
+        for (plain, after) in plain.iter().zip(&after) {
+            assert_eq!(after.expected_value, plain.expected_value);
+        }
+    }
 
-        // Get state (non-mutable for old trait)
-        let state = self.states.get(&input.market.id);
+    fn fill(outcome_side: PairSide, size: i64) -> StateUpdate {
+        StateUpdate::TradeExecution {
+            signal_id: None,
+            outcome_id: Some("yes".to_string()),
+            outcome_side,
+            side: OrderSide::Buy,
+            size: Decimal::from(size),
+            price: Decimal::from_str_exact("0.49").unwrap(),
+        }
+    }
 
-        // Just return first signal if any
-        let mut temp_gen = MarketMakingGenerator {
-            config: self.config.clone(),
-            states: self.states.clone(),
+    #[test]
+    fn test_yes_inventory_pulls_yes_quote_and_shades_prices() {
+        let market_id = MarketId::random();
+        let input = create_input(market_id);
+        // Keep the spread fixed so only the skew moves the quotes
+        let config = MarketMakingConfig {
+            inventory_adjustment: Decimal::ZERO,
+            ..MarketMakingConfig::default()
         };
-        let signals = temp_gen.generate(input);
+        let mut generator = MarketMakingGenerator::new(config);
+
+        let balanced = generator.generate(&input).unwrap();
+        assert_eq!(balanced.len(), 2);
+        let yes_quote = balanced[0].quote_id().unwrap();
+        let no_quote = balanced[1].quote_id().unwrap();
+
+        // Requoting amends the same resting orders
+        let requoted = generator.generate(&input).unwrap();
+        assert_eq!(requoted[0].quote_id(), Some(yes_quote));
+        assert_eq!(requoted[1].quote_id(), Some(no_quote));
+
+        // 300 YES against 100 NO is an imbalance of 0.5
+        generator.update_state(market_id, &fill(PairSide::Yes, 300));
+        generator.update_state(market_id, &fill(PairSide::No, 100));
+        let signals = generator.generate(&input).unwrap();
+        // Plus an unwind, see test_unwinds_inventory_past_the_limit
+        assert_eq!(signals.len(), 3);
+        assert!(signals[2].is_unwind());
+
+        let cancel = &signals[0];
+        assert!(cancel.is_cancel());
+        assert_eq!(cancel.direction, SignalDirection::Long);
+        assert_eq!(cancel.quote_id(), Some(yes_quote));
+        assert_eq!(cancel.metadata.custom_fields["cancel_reason"], "inventory_imbalance");
+
+        // A reservation of 0.50 - 0.5 * 0.01 moves the YES ask down from
+        // 0.51 to 0.505, so the NO bid rises from 0.49 to 0.495
+        let quote = &signals[1];
+        assert_eq!(quote.direction, SignalDirection::Short);
+        assert_eq!(quote.signal_type, SignalType::MeanReversion);
+        assert_eq!(quote.quote_id(), Some(no_quote));
+        assert_eq!(quote.entry_price, Decimal::from_str_exact("0.495").unwrap());
+        assert_eq!(balanced[1].entry_price, Decimal::from_str_exact("0.49").unwrap());
+
+        // Already cancelled: nothing more to pull
+        let signals = generator.generate(&input).unwrap();
+        assert_eq!(signals.len(), 1);
+        assert!(!signals[0].is_cancel());
+
+        // Selling the YES back down lets a fresh YES quote out
+        generator.update_state(
+            market_id,
+            &StateUpdate::PositionClosed {
+                outcome_id: Some("yes".to_string()),
+                outcome_side: PairSide::Yes,
+                side: OrderSide::Buy,
+                size: Decimal::from(200),
+                realized_pnl: Decimal::ZERO,
+            },
+        );
+        let signals = generator.generate(&input).unwrap();
+        assert_eq!(signals.len(), 2);
+        assert_ne!(signals[0].quote_id(), Some(yes_quote));
+        assert_eq!(signals[1].quote_id(), Some(no_quote));
+    }
 
-        Ok(signals.into_iter().next())
+    #[test]
+    fn test_volatility_spike_pulls_every_quote() {
+        let market_id = MarketId::random();
+        let input = create_input(market_id);
+        let mut generator = MarketMakingGenerator::new(MarketMakingConfig::default());
+        assert_eq!(generator.generate(&input).unwrap().len(), 2);
+
+        generator.update_state(market_id, &StateUpdate::VolatilityUpdate { score: 0.95, raw_sigma: 0.02 });
+        let signals = generator.generate(&input).unwrap();
+        assert_eq!(signals.len(), 2);
+        assert!(signals.iter().all(|s| s.is_cancel()));
+        assert!(signals.iter().all(|s| s.metadata.custom_fields["cancel_reason"] == "volatility"));
+        assert!(generator.generate(&input).unwrap().is_empty());
     }
 
-    fn signal_type(&self) -> SignalType {
-        SignalType::MeanReversion
+    #[test]
+    fn test_research_probability_skews_quotes() {
+        let quotes = |probability: f64, confidence: f64| {
+            let mut input = create_input(MarketId::random());
+            input.research_output.probability_estimate = Some(probability);
+            input.research_output.confidence = confidence;
+            let config = MarketMakingConfig {
+                fair_value_skew: Decimal::from_str_exact("0.8").unwrap(),
+                ..MarketMakingConfig::default()
+            };
+            let signals = MarketMakingGenerator::new(config).generate(&input).unwrap();
+            assert_eq!(signals.len(), 2);
+            (signals[0].entry_price, signals[1].entry_price, signals[0].target_price)
+        };
+        let price = |price: &str| Decimal::from_str_exact(price).unwrap();
+
+        // A weight of 0.8 * 0.5 moves the anchor 40% of the way from the 0.50
+        // mid to 0.75: the YES bid rises a dime and the NO bid falls one
+        let (yes, no, anchor) = quotes(0.75, 0.5);
+        assert_eq!(anchor, price("0.60"));
+        assert_eq!((yes, no), (price("0.59"), price("0.39")));
+
+        let (yes, no, anchor) = quotes(0.25, 0.5);
+        assert_eq!(anchor, price("0.40"));
+        assert_eq!((yes, no), (price("0.39"), price("0.59")));
+
+        // Without confidence the quotes stay around the mid
+        let (yes, no, anchor) = quotes(0.75, 0.0);
+        assert_eq!(anchor, price("0.50"));
+        assert_eq!((yes, no), (price("0.49"), price("0.49")));
+    }
+
+    #[test]
+    fn test_unwinds_inventory_past_the_limit() {
+        let market_id = MarketId::random();
+        let input = create_input(market_id);
+        let mut generator = MarketMakingGenerator::new(MarketMakingConfig::default());
+        generator.generate(&input).unwrap();
+
+        // 400 YES against 100 NO is an imbalance of 0.6, over the 0.3 limit
+        generator.update_state(market_id, &fill(PairSide::Yes, 400));
+        generator.update_state(market_id, &fill(PairSide::No, 100));
+        let signals = generator.generate(&input).unwrap();
+        let unwind = signals.iter().find(|s| s.is_unwind()).expect("no unwind");
+        assert_eq!(unwind.direction, SignalDirection::Short);
+        assert_eq!(unwind.position_size, Decimal::from(300));
+        // Buys NO at its ask, the complement of the 0.49 YES bid
+        assert_eq!(unwind.entry_price, Decimal::from_str_exact("0.51").unwrap());
+        assert_eq!(unwind.metadata.custom_fields["overweight_side"], "yes");
+        assert!(signals.iter().all(|s| s.direction == SignalDirection::Short || s.is_cancel()));
+
+        // One unwind order at a time until it fills or expires
+        let signals = generator.generate(&input).unwrap();
+        assert!(!signals.iter().any(|s| s.is_unwind()));
+
+        // Partly filled, the imbalance of 0.23 is within the limit but YES
+        // stays unquoted until the book is balanced
+        generator.update_state(market_id, &fill(PairSide::No, 150));
+        let signals = generator.generate(&input).unwrap();
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].direction, SignalDirection::Short);
+        assert!(!signals[0].is_unwind());
+
+        generator.update_state(market_id, &fill(PairSide::No, 150));
+        let signals = generator.generate(&input).unwrap();
+        assert_eq!(signals.len(), 2);
+        assert!(signals.iter().any(|s| s.direction == SignalDirection::Long));
+
+        // Overweight NO unwinds by buying YES at the ask
+        let mut generator = MarketMakingGenerator::new(MarketMakingConfig::default());
+        generator.update_state(market_id, &fill(PairSide::No, 200));
+        let unwind = generator.generate(&input).unwrap().into_iter().find(|s| s.is_unwind()).unwrap();
+        assert_eq!(unwind.direction, SignalDirection::Long);
+        assert_eq!(unwind.entry_price, Decimal::from_str_exact("0.51").unwrap());
     }
 }
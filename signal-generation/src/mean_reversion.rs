@@ -0,0 +1,459 @@
+// Mean Reversion Signal Generator
+// Fades price spikes that stray too far from their rolling mean
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use common::MarketId;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, VecDeque};
+use uuid::Uuid;
+
+use crate::diagnostics::SkipReason;
+use crate::sentiment_polarity::SentimentPolarityResolver;
+use crate::signals::{
+    PriceSnapshot, SignalDirection, SignalGenerator, SignalInput, SignalMetadata, SignalType, StateUpdate,
+    TradeSignal,
+};
+
+/// Mean reversion configuration
+#[derive(Debug, Clone)]
+pub struct MeanReversionConfig {
+    /// Snapshots in the rolling mean and standard deviation, not counting
+    /// the latest one being judged
+    pub lookback: usize,
+    /// Distance from the mean, in standard deviations, that counts as a spike
+    pub zscore_entry: f64,
+    /// Distance from the mean the target is set at, on the spike's side;
+    /// 0.0 targets the mean itself
+    pub zscore_exit: f64,
+    /// Minimum time between signals on one market
+    pub cooldown_minutes: i64,
+    /// Signals allowed per market in any 24 hours
+    pub max_signals_per_market_per_day: usize,
+    /// Research sentiment towards the move, times research confidence, at
+    /// which the move is taken as justified and not faded
+    pub sentiment_agreement_threshold: f64,
+    /// Stop distance beyond the spike's extreme, in standard deviations
+    pub stop_buffer: f64,
+    /// Market volatility score above which a spike is taken as a regime
+    /// change and not faded; 1.0 fades every spike
+    pub max_volatility_score: f64,
+    /// Base position size
+    pub base_position_size: Decimal,
+}
+
+impl Default for MeanReversionConfig {
+    fn default() -> Self {
+        Self {
+            lookback: 20,
+            zscore_entry: 2.5,
+            zscore_exit: 0.0,
+            cooldown_minutes: 60,
+            max_signals_per_market_per_day: 3,
+            sentiment_agreement_threshold: 0.3,
+            stop_buffer: 1.0,
+            max_volatility_score: 1.0,
+            base_position_size: Decimal::from(100),
+        }
+    }
+}
+
+/// A latest price far enough from its rolling mean to fade
+#[derive(Debug, Clone)]
+struct Spike {
+    mean: f64,
+    std_dev: f64,
+    zscore: f64,
+    /// Furthest price reached since the series was last on the other side of the mean
+    extreme: f64,
+}
+
+/// Fades overreactions: when the latest price in `price_history` strays
+/// more than `zscore_entry` standard deviations from the mean of the
+/// `lookback` snapshots before it, emits Short after an upward spike and
+/// Long after a downward one, targeting the mean.
+///
+/// A move that research sentiment agrees with is left alone. Signals on a
+/// market are spaced by `cooldown_minutes` and capped per day, measured on
+/// snapshot timestamps so replayed history behaves like live data. Reads the
+/// market's first outcome; sentiment is read as towards that outcome unless
+/// a polarity resolver is set.
+pub struct MeanReversionGenerator {
+    config: MeanReversionConfig,
+    polarity: Option<SentimentPolarityResolver>,
+    /// Times of recent signals per market, oldest first
+    signalled: HashMap<MarketId, VecDeque<DateTime<Utc>>>,
+    /// Latest volatility score per market, from `StateUpdate::VolatilityUpdate`
+    volatility: HashMap<MarketId, f64>,
+    /// Why the last `generate` call held back a spike
+    last_skip: Option<(MarketId, SkipReason)>,
+}
+
+impl MeanReversionGenerator {
+    pub fn new(config: MeanReversionConfig) -> Self {
+        Self {
+            config,
+            polarity: None,
+            signalled: HashMap::new(),
+            volatility: HashMap::new(),
+            last_skip: None,
+        }
+    }
+
+    /// Turn research sentiment towards YES before comparing it with the move;
+    /// markets the resolver finds ambiguous are faded regardless of sentiment
+    pub fn with_polarity(mut self, resolver: SentimentPolarityResolver) -> Self {
+        self.polarity = Some(resolver);
+        self
+    }
+
+    /// Prices of the market's first outcome, oldest first
+    fn series<'a>(&self, input: &'a SignalInput) -> Vec<&'a PriceSnapshot> {
+        let outcome = input.market.outcomes.first().map(|o| o.id.as_str());
+        let mut series: Vec<&PriceSnapshot> = input
+            .price_history
+            .iter()
+            .filter(|s| outcome.is_none_or(|id| s.outcome_id == id))
+            .collect();
+        series.sort_by_key(|s| s.timestamp);
+        series
+    }
+
+    /// Spike at the last price, if it clears `zscore_entry`
+    fn detect(&self, prices: &[f64]) -> Option<Spike> {
+        let lookback = self.config.lookback;
+        if lookback < 2 || prices.len() < lookback + 1 {
+            return None;
+        }
+
+        let end = prices.len() - 1;
+        let window = &prices[end - lookback..end];
+        let mean = window.iter().sum::<f64>() / lookback as f64;
+        let std_dev = (window.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / lookback as f64).sqrt();
+        let last = prices[end];
+        let zscore = (last - mean) / std_dev.max(f64::EPSILON);
+        if zscore.abs() < self.config.zscore_entry {
+            return None;
+        }
+
+        let up = zscore > 0.0;
+        let extreme = prices
+            .iter()
+            .rev()
+            .take_while(|&&p| if up { p > mean } else { p < mean })
+            .fold(last, |extreme, &p| if up { extreme.max(p) } else { extreme.min(p) });
+
+        Some(Spike {
+            mean,
+            std_dev,
+            zscore,
+            extreme,
+        })
+    }
+
+    /// Research sentiment in the direction of the move, weighted by confidence;
+    /// negative when research disagrees with it
+    fn sentiment_towards_move(&self, input: &SignalInput, spike: &Spike) -> f64 {
+        let research = &input.research_output;
+        let yes_score = match &self.polarity {
+            Some(resolver) => match resolver.directional(&input.market, research.sentiment.overall) {
+                Some(directional) => directional.yes_score,
+                None => return 0.0,
+            },
+            None => research.sentiment.overall,
+        };
+        yes_score * spike.zscore.signum() * research.confidence
+    }
+
+    /// Whether the cooldown or the daily cap holds a new signal back at `now`
+    fn in_cooldown(&mut self, market_id: MarketId, now: DateTime<Utc>) -> bool {
+        let Some(times) = self.signalled.get_mut(&market_id) else {
+            return false;
+        };
+        while times.front().is_some_and(|&at| now - at >= Duration::days(1)) {
+            times.pop_front();
+        }
+
+        let cooling = times
+            .back()
+            .is_some_and(|&at| now - at < Duration::minutes(self.config.cooldown_minutes));
+        cooling || times.len() >= self.config.max_signals_per_market_per_day
+    }
+
+    /// Confidence from how far past the entry band the spike is and the history's depth;
+    /// `edge` and `expected_value` follow from it and the distances to target and stop
+    fn confidence(&self, spike: &Spike, data_points: usize) -> f64 {
+        let excess = (spike.zscore.abs() - self.config.zscore_entry).max(0.0);
+        let strength = excess / (1.0 + excess);
+        let depth = (data_points as f64 / (2 * self.config.lookback) as f64).min(1.0);
+        ((0.5 + 0.5 * strength) * (0.5 + 0.5 * depth)).clamp(0.0, 1.0)
+    }
+}
+
+impl SignalGenerator for MeanReversionGenerator {
+    fn generate(&mut self, input: &SignalInput) -> Result<Vec<TradeSignal>> {
+        self.last_skip = None;
+        let market_id = input.market.id;
+        let series = self.series(input);
+        let prices: Vec<f64> = series.iter().map(|s| s.price.to_f64().unwrap_or(0.0)).collect();
+
+        let Some(spike) = self.detect(&prices) else {
+            return Ok(vec![]);
+        };
+
+        let agreement = self.sentiment_towards_move(input, &spike);
+        if agreement >= self.config.sentiment_agreement_threshold {
+            self.last_skip = Some((market_id, SkipReason::QualityGovernor { check: "sentiment_agrees" }));
+            return Ok(vec![]);
+        }
+
+        let volatility = self.volatility.get(&market_id).copied();
+        if volatility.is_some_and(|score| score > self.config.max_volatility_score) {
+            self.last_skip = Some((market_id, SkipReason::QualityGovernor { check: "volatility" }));
+            return Ok(vec![]);
+        }
+
+        let last = series.last().expect("detect needs history");
+        if self.in_cooldown(market_id, last.timestamp) {
+            self.last_skip = Some((market_id, SkipReason::Cooldown));
+            return Ok(vec![]);
+        }
+
+        let entry = last.price;
+        let entry_f = prices[prices.len() - 1];
+        if entry_f <= 0.0 {
+            return Ok(vec![]);
+        }
+
+        // Fade the move: the spike's side of the mean is `sign`
+        let sign = spike.zscore.signum();
+        let direction = if sign > 0.0 { SignalDirection::Short } else { SignalDirection::Long };
+        let clamp = |price: f64| Decimal::from_f64(price.clamp(0.01, 0.99)).unwrap_or(entry).round_dp(4);
+        let target_price = clamp(spike.mean + sign * self.config.zscore_exit * spike.std_dev);
+        let stop_loss = clamp(spike.extreme + sign * self.config.stop_buffer * spike.std_dev);
+
+        let confidence = self.confidence(&spike, series.len());
+        let reward = (target_price - entry).abs().to_f64().unwrap_or(0.0);
+        let risk = (entry - stop_loss).abs().to_f64().unwrap_or(0.0);
+        let expected_move = reward * confidence - risk * (1.0 - confidence);
+        // Half Kelly, capped at a quarter of the bankroll
+        let kelly_fraction = if reward > 0.0 && risk > 0.0 {
+            let b = reward / risk;
+            (((b * confidence - (1.0 - confidence)) / b) * 0.5).clamp(0.0, 0.25)
+        } else {
+            0.0
+        };
+
+        self.signalled.entry(market_id).or_default().push_back(last.timestamp);
+
+        let position_size = self.config.base_position_size;
+        let signal = TradeSignal {
+            id: Uuid::new_v4(),
+            market_id,
+            signal_type: SignalType::MeanReversion,
+            direction,
+            outcome_id: input.market.outcomes.first().map(|o| o.id.clone()),
+            entry_price: entry,
+            target_price,
+            stop_loss,
+            position_size,
+            confidence,
+            expected_value: Decimal::from_f64(expected_move).unwrap_or(Decimal::ZERO) * position_size,
+            edge: Decimal::from_f64(expected_move / entry_f).unwrap_or(Decimal::ZERO),
+            kelly_fraction,
+            reasoning: format!(
+                "Mean reversion: price {:.4} is {:.2} standard deviations {} its {}-snapshot mean {:.4}",
+                entry_f,
+                spike.zscore.abs(),
+                if sign > 0.0 { "above" } else { "below" },
+                self.config.lookback,
+                spike.mean
+            ),
+            metadata: SignalMetadata {
+                research_sources: vec!["price_history".to_string()],
+                data_points: series.len() as u32,
+                liquidity_score: (last.liquidity.to_f64().unwrap_or(0.0) / 10000.0).min(1.0),
+                volatility_score: volatility.unwrap_or((spike.std_dev / entry_f).min(1.0)),
+                price_age_ms: None,
+                orderbook_age_ms: None,
+                research_age_ms: None,
+                custom_fields: serde_json::json!({
+                    "strategy": "mean_reversion",
+                    "rolling_mean": spike.mean,
+                    "rolling_std": spike.std_dev,
+                    "zscore": spike.zscore,
+                    "spike_extreme": spike.extreme,
+                    "sentiment_towards_move": agreement,
+                }),
+            },
+            created_at: Utc::now(),
+            expires_at: Some(Utc::now() + chrono::Duration::minutes(30)), // 30 min validity
+            variant: None,
+        };
+
+        Ok(vec![signal])
+    }
+
+    fn update_state(&mut self, market_id: MarketId, update: &StateUpdate) {
+        if let StateUpdate::VolatilityUpdate { score, .. } = update {
+            self.volatility.insert(market_id, *score);
+        }
+    }
+
+    fn skip_reason(&self, input: &SignalInput) -> Option<SkipReason> {
+        if self.series(input).len() <= self.config.lookback {
+            return Some(SkipReason::MissingData { data: "price_history" });
+        }
+        self.last_skip
+            .as_ref()
+            .filter(|(market_id, _)| *market_id == input.market.id)
+            .map(|(_, reason)| reason.clone())
+    }
+
+    fn signal_type(&self) -> SignalType {
+        SignalType::MeanReversion
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    /// Snapshots ten minutes apart, the last at `end`
+    fn input(market_id: MarketId, prices: &[f64], end: DateTime<Utc>, sentiment: f64) -> SignalInput {
+        let start = end - Duration::minutes(10 * (prices.len() as i64 - 1));
+        test_support::input(market_id)
+            .research(|research| research.sentiment.overall = sentiment)
+            .history(
+                prices
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &price)| PriceSnapshot {
+                        outcome_id: "yes".to_string(),
+                        price: Decimal::from_f64(price).unwrap(),
+                        volume: Decimal::from(100),
+                        liquidity: Decimal::from(5000),
+                        timestamp: start + Duration::minutes(10 * i as i64),
+                    })
+                    .collect(),
+            )
+            .build()
+    }
+
+    /// Quiet trading around 0.50, then `spike`
+    fn spiked(spike: f64) -> Vec<f64> {
+        let mut prices: Vec<f64> = (0..30).map(|i| if i % 2 == 0 { 0.49 } else { 0.51 }).collect();
+        prices.push(spike);
+        prices
+    }
+
+    #[test]
+    fn test_fades_spikes_towards_the_mean() {
+        let mut generator = MeanReversionGenerator::new(MeanReversionConfig::default());
+        let now = Utc::now();
+
+        let signals = generator.generate(&input(MarketId::random(), &spiked(0.62), now, 0.0)).unwrap();
+        assert_eq!(signals.len(), 1);
+        let short = &signals[0];
+        assert_eq!(short.direction, SignalDirection::Short);
+        assert_eq!(short.signal_type, SignalType::MeanReversion);
+        assert_eq!(short.entry_price, Decimal::from_f64(0.62).unwrap());
+        assert_eq!(short.target_price, Decimal::from_f64(0.50).unwrap());
+        assert!(short.stop_loss > short.entry_price);
+        assert!(short.metadata.custom_fields["zscore"].as_f64().unwrap() > 2.5);
+
+        let signals = generator.generate(&input(MarketId::random(), &spiked(0.40), now, 0.0)).unwrap();
+        let long = &signals[0];
+        assert_eq!(long.direction, SignalDirection::Long);
+        assert!(long.stop_loss < long.entry_price && long.entry_price < long.target_price);
+
+        // Ordinary noise is not a spike
+        assert!(generator.generate(&input(MarketId::random(), &spiked(0.51), now, 0.0)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_larger_deviation_scores_higher() {
+        let mut generator = MeanReversionGenerator::new(MeanReversionConfig::default());
+        let now = Utc::now();
+        let mut fade = |spike: f64| generator.generate(&input(MarketId::random(), &spiked(spike), now, 0.0)).unwrap();
+
+        // Oversold by 3 and by 8 standard deviations
+        let (mild, deep) = (fade(0.47).remove(0), fade(0.42).remove(0));
+        assert_eq!(mild.direction, SignalDirection::Long);
+        assert!(deep.confidence > mild.confidence);
+        assert!(deep.edge > mild.edge);
+        assert!(deep.expected_value > mild.expected_value);
+        assert!(deep.edge > Decimal::ZERO);
+
+        // Just inside the band
+        assert!(fade(0.524).is_empty());
+    }
+
+    #[test]
+    fn test_agreeing_sentiment_blocks_the_fade() {
+        let mut generator = MeanReversionGenerator::new(MeanReversionConfig::default());
+        let market_id = MarketId::random();
+
+        // Bullish research explains the jump up
+        let bullish = input(market_id, &spiked(0.62), Utc::now(), 0.7);
+        assert!(generator.generate(&bullish).unwrap().is_empty());
+        assert_eq!(
+            generator.skip_reason(&bullish),
+            Some(SkipReason::QualityGovernor { check: "sentiment_agrees" })
+        );
+
+        // Bearish research does not, so the jump is faded
+        let bearish = input(market_id, &spiked(0.62), Utc::now(), -0.7);
+        assert_eq!(generator.generate(&bearish).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_volatile_markets_are_not_faded() {
+        let mut generator = MeanReversionGenerator::new(MeanReversionConfig {
+            max_volatility_score: 0.8,
+            ..Default::default()
+        });
+        let market_id = MarketId::random();
+        let spike = input(market_id, &spiked(0.62), Utc::now(), 0.0);
+
+        generator.update_state(market_id, &StateUpdate::VolatilityUpdate { score: 0.9, raw_sigma: 0.018 });
+        assert!(generator.generate(&spike).unwrap().is_empty());
+        assert_eq!(generator.skip_reason(&spike), Some(SkipReason::QualityGovernor { check: "volatility" }));
+
+        generator.update_state(market_id, &StateUpdate::VolatilityUpdate { score: 0.3, raw_sigma: 0.006 });
+        let signals = generator.generate(&spike).unwrap();
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].metadata.volatility_score, 0.3);
+    }
+
+    #[test]
+    fn test_cooldown_spaces_signals_on_one_market() {
+        let mut generator = MeanReversionGenerator::new(MeanReversionConfig {
+            max_signals_per_market_per_day: 2,
+            ..Default::default()
+        });
+        let market_id = MarketId::random();
+        let start = Utc::now();
+
+        // The move extends on the next snapshot; no second entry
+        let mut prices = spiked(0.62);
+        assert_eq!(generator.generate(&input(market_id, &prices, start, 0.0)).unwrap().len(), 1);
+        prices.push(0.66);
+        let extended = input(market_id, &prices, start + Duration::minutes(10), 0.0);
+        assert!(generator.generate(&extended).unwrap().is_empty());
+        assert_eq!(generator.skip_reason(&extended), Some(SkipReason::Cooldown));
+
+        // Other markets are unaffected
+        assert_eq!(generator.generate(&input(MarketId::random(), &spiked(0.62), start, 0.0)).unwrap().len(), 1);
+
+        // A fresh spike after the cooldown signals, up to the daily cap
+        let later = start + Duration::minutes(90);
+        assert_eq!(generator.generate(&input(market_id, &spiked(0.62), later, 0.0)).unwrap().len(), 1);
+        let evening = start + Duration::hours(5);
+        assert!(generator.generate(&input(market_id, &spiked(0.62), evening, 0.0)).unwrap().is_empty());
+        let next_day = start + Duration::hours(25);
+        assert_eq!(generator.generate(&input(market_id, &spiked(0.62), next_day, 0.0)).unwrap().len(), 1);
+    }
+}
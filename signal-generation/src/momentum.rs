@@ -0,0 +1,428 @@
+// Momentum Signal Generator
+// Trades moving-average crossovers in a market's price history
+
+use anyhow::Result;
+use chrono::Utc;
+use common::MarketId;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::diagnostics::SkipReason;
+use crate::signals::{
+    PriceSnapshot, SignalDirection, SignalGenerator, SignalInput, SignalMetadata, SignalType, StateUpdate,
+    TradeSignal,
+};
+
+/// Momentum configuration
+#[derive(Debug, Clone)]
+pub struct MomentumConfig {
+    /// Snapshots in the fast moving average
+    pub short_window: usize,
+    /// Snapshots in the slow moving average, and in the volatility estimate
+    pub long_window: usize,
+    /// Snapshots needed before any signal; never fewer than `long_window + 1`
+    pub min_history: usize,
+    /// Gap between the averages, in units of volatility, that counts as a
+    /// cross; smaller gaps leave the trend unchanged so chop doesn't flip it
+    pub min_crossover_strength: f64,
+    /// Short-window volume must be at least this multiple of long-window volume
+    pub min_volume_ratio: f64,
+    /// Stop distance from entry, in units of volatility
+    pub stop_multiple: f64,
+    /// Target distance from entry, in units of volatility
+    pub target_multiple: f64,
+    /// Market volatility score above which crossovers are taken as whipsaw
+    /// and not traded; 1.0 trades every crossover
+    pub max_volatility_score: f64,
+    /// Base position size
+    pub base_position_size: Decimal,
+}
+
+impl Default for MomentumConfig {
+    fn default() -> Self {
+        Self {
+            short_window: 5,
+            long_window: 20,
+            min_history: 21,
+            min_crossover_strength: 0.25,
+            min_volume_ratio: 1.0,
+            stop_multiple: 2.0,
+            target_multiple: 3.0,
+            max_volatility_score: 1.0,
+            base_position_size: Decimal::from(100),
+        }
+    }
+}
+
+/// Side the short average is on, once the gap clears the crossover band
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Trend {
+    Up,
+    Down,
+}
+
+/// State of the averages at the latest snapshot
+#[derive(Debug, Clone)]
+struct Crossover {
+    trend: Trend,
+    short_ma: f64,
+    long_ma: f64,
+    /// Change in the short average over the last snapshot
+    slope: f64,
+    /// Mean absolute change per snapshot over the long window
+    volatility: f64,
+    /// |short - long| / volatility
+    strength: f64,
+    volume_ratio: f64,
+}
+
+/// Directional generator on short/long moving-average crossovers.
+///
+/// The trend only changes when the averages separate by more than
+/// `min_crossover_strength` volatilities, and a signal is emitted on the
+/// snapshot where it changes, if volume confirms, unless the market's
+/// volatility score is above `max_volatility_score`. Beyond those scores the
+/// generator keeps no state: each call replays the given history, so a
+/// crossover is signalled once, on the cycle whose history ends with it.
+/// Needs `min_history` snapshots of the market's first outcome, and a long
+/// window longer than the short one.
+pub struct MomentumGenerator {
+    config: MomentumConfig,
+    /// Latest volatility score per market, from `StateUpdate::VolatilityUpdate`
+    volatility: HashMap<MarketId, f64>,
+    /// Why the last `generate` call held back a crossover
+    last_skip: Option<(MarketId, SkipReason)>,
+}
+
+impl MomentumGenerator {
+    pub fn new(config: MomentumConfig) -> Self {
+        Self {
+            config,
+            volatility: HashMap::new(),
+            last_skip: None,
+        }
+    }
+
+    /// Prices of the market's first outcome, oldest first
+    fn series<'a>(&self, input: &'a SignalInput) -> Vec<&'a PriceSnapshot> {
+        let outcome = input.market.outcomes.first().map(|o| o.id.as_str());
+        let mut series: Vec<&PriceSnapshot> = input
+            .price_history
+            .iter()
+            .filter(|s| outcome.is_none_or(|id| s.outcome_id == id))
+            .collect();
+        series.sort_by_key(|s| s.timestamp);
+        series
+    }
+
+    /// Snapshots needed to judge a crossover
+    fn required_history(&self) -> usize {
+        self.config.min_history.max(self.config.long_window + 1)
+    }
+
+    /// Short MA, long MA, volatility and strength over the snapshots before `end`
+    fn averages(&self, prices: &[f64], end: usize) -> (f64, f64, f64, f64) {
+        let (short, long) = (self.config.short_window, self.config.long_window);
+        let short_ma = mean(&prices[end - short..end]);
+        let long_ma = mean(&prices[end - long..end]);
+        let volatility = prices[end - long - 1..end]
+            .windows(2)
+            .map(|w| (w[1] - w[0]).abs())
+            .sum::<f64>()
+            / long as f64;
+        let strength = (short_ma - long_ma).abs() / volatility.max(f64::EPSILON);
+        (short_ma, long_ma, volatility, strength)
+    }
+
+    /// Trend after the snapshots before `end`, given the trend before them
+    fn trend_at(&self, prices: &[f64], end: usize, previous: Option<Trend>) -> Option<Trend> {
+        let (short_ma, long_ma, _, strength) = self.averages(prices, end);
+        if strength < self.config.min_crossover_strength {
+            previous
+        } else if short_ma > long_ma {
+            Some(Trend::Up)
+        } else {
+            Some(Trend::Down)
+        }
+    }
+
+    /// Crossover that completes at the last price, if any
+    fn detect(&self, prices: &[f64], volumes: &[f64]) -> Option<Crossover> {
+        let (short, long) = (self.config.short_window, self.config.long_window);
+        if short == 0 || long <= short || prices.len() < self.required_history() {
+            return None;
+        }
+
+        let end = prices.len();
+        let before = (long + 1..end).fold(None, |trend, bar_end| self.trend_at(prices, bar_end, trend));
+        let trend = self.trend_at(prices, end, before)?;
+        if before.is_none_or(|before| before == trend) {
+            return None;
+        }
+
+        let (short_ma, long_ma, volatility, strength) = self.averages(prices, end);
+        let long_volume = mean(&volumes[end - long..end]);
+        Some(Crossover {
+            trend,
+            short_ma,
+            long_ma,
+            slope: short_ma - mean(&prices[end - short - 1..end - 1]),
+            volatility,
+            strength,
+            volume_ratio: if long_volume > 0.0 {
+                mean(&volumes[end - short..end]) / long_volume
+            } else {
+                0.0
+            },
+        })
+    }
+
+    /// Confidence from the crossover's strength and the history's depth
+    fn confidence(&self, crossover: &Crossover, data_points: usize) -> f64 {
+        let strength = crossover.strength / (1.0 + crossover.strength);
+        let depth = (data_points as f64 / (2 * self.config.long_window) as f64).min(1.0);
+        ((0.5 + 0.5 * strength) * (0.5 + 0.5 * depth)).clamp(0.0, 1.0)
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+impl SignalGenerator for MomentumGenerator {
+    fn generate(&mut self, input: &SignalInput) -> Result<Vec<TradeSignal>> {
+        self.last_skip = None;
+        let series = self.series(input);
+        let prices: Vec<f64> = series.iter().map(|s| s.price.to_f64().unwrap_or(0.0)).collect();
+        let volumes: Vec<f64> = series.iter().map(|s| s.volume.to_f64().unwrap_or(0.0)).collect();
+
+        let Some(crossover) = self.detect(&prices, &volumes) else {
+            return Ok(vec![]);
+        };
+        if crossover.volume_ratio < self.config.min_volume_ratio {
+            return Ok(vec![]);
+        }
+        let volatility = self.volatility.get(&input.market.id).copied();
+        if volatility.is_some_and(|score| score > self.config.max_volatility_score) {
+            self.last_skip = Some((input.market.id, SkipReason::QualityGovernor { check: "volatility" }));
+            return Ok(vec![]);
+        }
+
+        // Enter at the book's mid, or the last traded price without one
+        let last = series.last().expect("detect needs history");
+        let entry = input
+            .order_book
+            .as_ref()
+            .and_then(|ob| Some((ob.bids.first()?.price + ob.asks.first()?.price) / Decimal::from(2)))
+            .unwrap_or(last.price);
+        let entry_f = entry.to_f64().unwrap_or(0.0);
+        if entry_f <= 0.0 {
+            return Ok(vec![]);
+        }
+
+        let (direction, sign) = match crossover.trend {
+            Trend::Up => (SignalDirection::Long, 1.0),
+            Trend::Down => (SignalDirection::Short, -1.0),
+        };
+        let clamp = |price: f64| Decimal::from_f64(price.clamp(0.01, 0.99)).unwrap_or(entry).round_dp(4);
+        let stop_loss = clamp(entry_f - sign * self.config.stop_multiple * crossover.volatility);
+        let target_price = clamp(entry_f + sign * self.config.target_multiple * crossover.volatility);
+
+        let confidence = self.confidence(&crossover, series.len());
+        let reward = (target_price - entry).abs().to_f64().unwrap_or(0.0);
+        let risk = (entry - stop_loss).abs().to_f64().unwrap_or(0.0);
+        let expected_move = reward * confidence - risk * (1.0 - confidence);
+        // Half Kelly, capped at a quarter of the bankroll
+        let kelly_fraction = if reward > 0.0 && risk > 0.0 {
+            let b = reward / risk;
+            (((b * confidence - (1.0 - confidence)) / b) * 0.5).clamp(0.0, 0.25)
+        } else {
+            0.0
+        };
+
+        let position_size = self.config.base_position_size;
+        let signal = TradeSignal {
+            id: Uuid::new_v4(),
+            market_id: input.market.id,
+            signal_type: SignalType::Momentum,
+            direction,
+            outcome_id: input.market.outcomes.first().map(|o| o.id.clone()),
+            entry_price: entry,
+            target_price,
+            stop_loss,
+            position_size,
+            confidence,
+            expected_value: Decimal::from_f64(expected_move).unwrap_or(Decimal::ZERO) * position_size,
+            edge: Decimal::from_f64(expected_move / entry_f).unwrap_or(Decimal::ZERO),
+            kelly_fraction,
+            reasoning: format!(
+                "Momentum: {}-snapshot MA {:.4} crossed {} {}-snapshot MA {:.4} ({:.2} volatilities, volume x{:.2})",
+                self.config.short_window,
+                crossover.short_ma,
+                if crossover.trend == Trend::Up { "above" } else { "below" },
+                self.config.long_window,
+                crossover.long_ma,
+                crossover.strength,
+                crossover.volume_ratio
+            ),
+            metadata: SignalMetadata {
+                research_sources: vec!["price_history".to_string()],
+                data_points: series.len() as u32,
+                liquidity_score: (last.liquidity.to_f64().unwrap_or(0.0) / 10000.0).min(1.0),
+                volatility_score: volatility.unwrap_or((crossover.volatility / entry_f).min(1.0)),
+                price_age_ms: None,
+                orderbook_age_ms: None,
+                research_age_ms: None,
+                custom_fields: serde_json::json!({
+                    "strategy": "momentum",
+                    "short_ma": crossover.short_ma,
+                    "long_ma": crossover.long_ma,
+                    "short_ma_slope": crossover.slope,
+                    "volatility": crossover.volatility,
+                    "crossover_strength": crossover.strength,
+                    "volume_ratio": crossover.volume_ratio,
+                }),
+            },
+            created_at: Utc::now(),
+            expires_at: Some(Utc::now() + chrono::Duration::minutes(30)), // 30 min validity
+            variant: None,
+        };
+
+        Ok(vec![signal])
+    }
+
+    fn update_state(&mut self, market_id: MarketId, update: &StateUpdate) {
+        if let StateUpdate::VolatilityUpdate { score, .. } = update {
+            self.volatility.insert(market_id, *score);
+        }
+    }
+
+    fn skip_reason(&self, input: &SignalInput) -> Option<SkipReason> {
+        if self.series(input).len() < self.required_history() {
+            return Some(SkipReason::MissingData { data: "price_history" });
+        }
+        self.last_skip
+            .as_ref()
+            .filter(|(market_id, _)| *market_id == input.market.id)
+            .map(|(_, reason)| reason.clone())
+    }
+
+    fn signal_type(&self) -> SignalType {
+        SignalType::Momentum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use chrono::Duration;
+    use common::MarketId;
+
+    fn input(prices: &[(f64, f64)]) -> SignalInput {
+        let start = Utc::now() - Duration::hours(prices.len() as i64);
+        test_support::input(MarketId::random())
+            .research(|research| research.confidence = 0.0)
+            .history(
+                prices
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &(price, volume))| PriceSnapshot {
+                        outcome_id: "yes".to_string(),
+                        price: Decimal::from_f64(price).unwrap(),
+                        volume: Decimal::from_f64(volume).unwrap(),
+                        liquidity: Decimal::from(5000),
+                        timestamp: start + Duration::hours(i as i64),
+                    })
+                    .collect(),
+            )
+            .build()
+    }
+
+    /// Run the generator once per snapshot, as a live loop would
+    fn replay(series: &[(f64, f64)]) -> Vec<(usize, TradeSignal)> {
+        let mut generator = MomentumGenerator::new(MomentumConfig::default());
+        (1..=series.len())
+            .flat_map(|n| {
+                generator
+                    .generate(&input(&series[..n]))
+                    .unwrap()
+                    .into_iter()
+                    .map(move |signal| (n, signal))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_uptrend_crossover_signals_once() {
+        // A slow decline, then a rally on higher volume
+        let mut series: Vec<(f64, f64)> = (0..30).map(|i| (0.60 - 0.004 * i as f64, 100.0)).collect();
+        series.extend((1..=30).map(|i| (0.484 + 0.01 * i as f64, 200.0)));
+
+        let signals = replay(&series);
+        assert_eq!(signals.len(), 1);
+        let (at, signal) = &signals[0];
+        assert!(*at > 30, "crossed at snapshot {}", at);
+        assert_eq!(signal.direction, SignalDirection::Long);
+        assert_eq!(signal.signal_type, SignalType::Momentum);
+        assert_eq!(signal.entry_price, Decimal::from_f64(series[at - 1].0).unwrap());
+        assert!(signal.stop_loss < signal.entry_price && signal.entry_price < signal.target_price);
+        assert!(signal.confidence > 0.5 && signal.confidence <= 1.0);
+
+        let fields = &signal.metadata.custom_fields;
+        assert!(fields["short_ma"].as_f64().unwrap() > fields["long_ma"].as_f64().unwrap());
+        assert!(fields["short_ma_slope"].as_f64().unwrap() > 0.0);
+        assert!(fields["volume_ratio"].as_f64().unwrap() >= 1.0);
+    }
+
+    #[test]
+    fn test_downtrend_crossover_signals_short() {
+        // A slow climb, then a sell-off on higher volume
+        let mut series: Vec<(f64, f64)> = (0..30).map(|i| (0.40 + 0.004 * i as f64, 100.0)).collect();
+        series.extend((1..=30).map(|i| (0.516 - 0.01 * i as f64, 200.0)));
+
+        let signals = replay(&series);
+        assert_eq!(signals.len(), 1);
+        let (at, signal) = &signals[0];
+        assert!(*at > 30, "crossed at snapshot {}", at);
+        assert_eq!(signal.direction, SignalDirection::Short);
+        assert!(signal.target_price < signal.entry_price && signal.entry_price < signal.stop_loss);
+        assert!(signal.confidence > 0.5 && signal.confidence <= 1.0);
+
+        let fields = &signal.metadata.custom_fields;
+        assert!(fields["short_ma"].as_f64().unwrap() < fields["long_ma"].as_f64().unwrap());
+        assert!(fields["short_ma_slope"].as_f64().unwrap() < 0.0);
+        assert!(fields["crossover_strength"].as_f64().unwrap() >= 0.25);
+    }
+
+    #[test]
+    fn test_choppy_series_signals_nothing() {
+        let series: Vec<(f64, f64)> = (0..60).map(|i| (if i % 2 == 0 { 0.50 } else { 0.52 }, 100.0)).collect();
+        assert!(replay(&series).is_empty());
+    }
+
+    #[test]
+    fn test_short_history_is_skipped() {
+        let mut generator = MomentumGenerator::new(MomentumConfig::default());
+        let series: Vec<(f64, f64)> = (0..20).map(|i| (0.4 + 0.01 * i as f64, 100.0)).collect();
+        let short = input(&series);
+        assert!(generator.generate(&short).unwrap().is_empty());
+        assert_eq!(
+            generator.skip_reason(&short),
+            Some(SkipReason::MissingData { data: "price_history" })
+        );
+
+        // Enough for the windows, but not the configured minimum
+        let mut series: Vec<(f64, f64)> = (0..30).map(|i| (0.60 - 0.004 * i as f64, 100.0)).collect();
+        series.extend((1..=30).map(|i| (0.484 + 0.01 * i as f64, 200.0)));
+        let mut generator = MomentumGenerator::new(MomentumConfig {
+            min_history: 100,
+            ..MomentumConfig::default()
+        });
+        let full = input(&series);
+        assert!((1..=series.len()).all(|n| generator.generate(&input(&series[..n])).unwrap().is_empty()));
+        assert_eq!(generator.skip_reason(&full), Some(SkipReason::MissingData { data: "price_history" }));
+    }
+}
@@ -0,0 +1,263 @@
+// Order Book Imbalance Signal Generator
+// Short-horizon directional signals from lopsided top-of-book depth
+
+use anyhow::Result;
+use chrono::Utc;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::diagnostics::SkipReason;
+use crate::signals::{
+    Level, OrderBookSnapshot, SignalDirection, SignalGenerator, SignalInput, SignalMetadata, SignalType, TradeSignal,
+};
+
+/// Order book imbalance configuration
+#[derive(Debug, Clone)]
+pub struct OrderBookImbalanceConfig {
+    /// Best levels counted on each side
+    pub depth_levels: usize,
+    /// |imbalance| needed to signal, 0.0 to 1.0
+    pub imbalance_threshold: f64,
+    /// Combined size of the counted levels below which the book is too thin to read
+    pub min_depth: Decimal,
+    /// Target distance from entry, in price
+    pub target_move: Decimal,
+    /// Stop distance from entry, in price
+    pub stop_move: Decimal,
+    /// Minutes until the signal expires
+    pub horizon_minutes: i64,
+    /// Base position size
+    pub base_position_size: Decimal,
+}
+
+impl Default for OrderBookImbalanceConfig {
+    fn default() -> Self {
+        Self {
+            depth_levels: 5,
+            imbalance_threshold: 0.3,
+            min_depth: Decimal::from(100),
+            target_move: Decimal::new(2, 2),
+            stop_move: Decimal::new(1, 2),
+            horizon_minutes: 5,
+            base_position_size: Decimal::from(100),
+        }
+    }
+}
+
+/// Depth on each side of the counted levels
+#[derive(Debug, Clone)]
+struct Imbalance {
+    bid_depth: Decimal,
+    ask_depth: Decimal,
+    /// (bid - ask) / (bid + ask)
+    value: f64,
+}
+
+/// Trades order-flow pressure: when the top `depth_levels` of bids outweigh
+/// the asks by more than `imbalance_threshold`, price tends to tick up, and
+/// the reverse. Emits Long on bid-heavy books and Short on ask-heavy ones at
+/// the mid, expiring after `horizon_minutes`.
+pub struct OrderBookImbalanceGenerator {
+    config: OrderBookImbalanceConfig,
+}
+
+impl OrderBookImbalanceGenerator {
+    pub fn new(config: OrderBookImbalanceConfig) -> Self {
+        Self { config }
+    }
+
+    /// Summed size of the best `depth_levels` levels
+    fn depth(&self, levels: &[Level], best_first: impl Fn(&Level, &Level) -> std::cmp::Ordering) -> Decimal {
+        let mut levels: Vec<&Level> = levels.iter().collect();
+        levels.sort_by(|a, b| best_first(a, b));
+        levels.iter().take(self.config.depth_levels).map(|l| l.size).sum()
+    }
+
+    fn imbalance(&self, order_book: &OrderBookSnapshot) -> Imbalance {
+        let bid_depth = self.depth(&order_book.bids, |a, b| b.price.cmp(&a.price));
+        let ask_depth = self.depth(&order_book.asks, |a, b| a.price.cmp(&b.price));
+        let total = bid_depth + ask_depth;
+        let value = if total > Decimal::ZERO {
+            ((bid_depth - ask_depth) / total).to_f64().unwrap_or(0.0)
+        } else {
+            0.0
+        };
+        Imbalance {
+            bid_depth,
+            ask_depth,
+            value,
+        }
+    }
+
+    /// Confidence from how far past the threshold the imbalance is
+    fn confidence(&self, imbalance: &Imbalance) -> f64 {
+        let threshold = self.config.imbalance_threshold;
+        let excess = ((imbalance.value.abs() - threshold) / (1.0 - threshold).max(f64::EPSILON)).clamp(0.0, 1.0);
+        0.5 + 0.4 * excess
+    }
+}
+
+impl SignalGenerator for OrderBookImbalanceGenerator {
+    fn generate(&mut self, input: &SignalInput) -> Result<Vec<TradeSignal>> {
+        let Some(order_book) = &input.order_book else {
+            return Ok(vec![]);
+        };
+        let (Some(best_bid), Some(best_ask)) = (
+            order_book.bids.iter().map(|l| l.price).max(),
+            order_book.asks.iter().map(|l| l.price).min(),
+        ) else {
+            return Ok(vec![]);
+        };
+
+        let imbalance = self.imbalance(order_book);
+        if imbalance.bid_depth + imbalance.ask_depth < self.config.min_depth
+            || imbalance.value.abs() < self.config.imbalance_threshold
+        {
+            return Ok(vec![]);
+        }
+
+        let entry = (best_bid + best_ask) / Decimal::from(2);
+        let entry_f = entry.to_f64().unwrap_or(0.0);
+        if entry_f <= 0.0 {
+            return Ok(vec![]);
+        }
+
+        let (direction, sign) = if imbalance.value > 0.0 {
+            (SignalDirection::Long, Decimal::ONE)
+        } else {
+            (SignalDirection::Short, -Decimal::ONE)
+        };
+        let clamp = |price: Decimal| price.clamp(Decimal::new(1, 2), Decimal::new(99, 2)).round_dp(4);
+        let target_price = clamp(entry + sign * self.config.target_move);
+        let stop_loss = clamp(entry - sign * self.config.stop_move);
+
+        let confidence = self.confidence(&imbalance);
+        let reward = (target_price - entry).abs().to_f64().unwrap_or(0.0);
+        let risk = (entry - stop_loss).abs().to_f64().unwrap_or(0.0);
+        let expected_move = reward * confidence - risk * (1.0 - confidence);
+        // Half Kelly, capped at a quarter of the bankroll
+        let kelly_fraction = if reward > 0.0 && risk > 0.0 {
+            let b = reward / risk;
+            (((b * confidence - (1.0 - confidence)) / b) * 0.5).clamp(0.0, 0.25)
+        } else {
+            0.0
+        };
+
+        let position_size = self.config.base_position_size;
+        let total_depth = (imbalance.bid_depth + imbalance.ask_depth).to_f64().unwrap_or(0.0);
+        let signal = TradeSignal {
+            id: Uuid::new_v4(),
+            market_id: input.market.id,
+            signal_type: SignalType::Momentum,
+            direction,
+            outcome_id: input.market.outcomes.first().map(|o| o.id.clone()),
+            entry_price: entry,
+            target_price,
+            stop_loss,
+            position_size,
+            confidence,
+            expected_value: Decimal::from_f64(expected_move).unwrap_or(Decimal::ZERO) * position_size,
+            edge: Decimal::from_f64(expected_move / entry_f).unwrap_or(Decimal::ZERO),
+            kelly_fraction,
+            reasoning: format!(
+                "Order book imbalance {:+.2} over the top {} levels (bids {}, asks {})",
+                imbalance.value, self.config.depth_levels, imbalance.bid_depth, imbalance.ask_depth
+            ),
+            metadata: SignalMetadata {
+                research_sources: vec!["order_book".to_string()],
+                data_points: (order_book.bids.len() + order_book.asks.len()) as u32,
+                liquidity_score: (total_depth / 10000.0).min(1.0),
+                volatility_score: 0.0,
+                price_age_ms: None,
+                orderbook_age_ms: None,
+                research_age_ms: None,
+                custom_fields: serde_json::json!({
+                    "strategy": "order_book_imbalance",
+                    "imbalance": imbalance.value,
+                    "bid_depth": imbalance.bid_depth.to_string(),
+                    "ask_depth": imbalance.ask_depth.to_string(),
+                    "depth_levels": self.config.depth_levels,
+                }),
+            },
+            created_at: Utc::now(),
+            expires_at: Some(Utc::now() + chrono::Duration::minutes(self.config.horizon_minutes)),
+            variant: None,
+        };
+
+        Ok(vec![signal])
+    }
+
+    fn skip_reason(&self, input: &SignalInput) -> Option<SkipReason> {
+        let Some(order_book) = &input.order_book else {
+            return Some(SkipReason::MissingData { data: "order_book" });
+        };
+        if order_book.bids.is_empty() || order_book.asks.is_empty() {
+            return Some(SkipReason::DegradedData { detail: "one_sided_book" });
+        }
+        let imbalance = self.imbalance(order_book);
+        (imbalance.bid_depth + imbalance.ask_depth < self.config.min_depth)
+            .then_some(SkipReason::DegradedData { detail: "thin_book" })
+    }
+
+    fn signal_type(&self) -> SignalType {
+        SignalType::Momentum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use common::MarketId;
+
+    /// Book with the given (price, size) levels on each side
+    fn input(bids: &[(&str, i64)], asks: &[(&str, i64)]) -> SignalInput {
+        test_support::input(MarketId::random())
+            .research(|research| research.confidence = 0.0)
+            .book(bids, asks)
+            .build()
+    }
+
+    fn generate(input: &SignalInput) -> Vec<TradeSignal> {
+        OrderBookImbalanceGenerator::new(OrderBookImbalanceConfig::default())
+            .generate(input)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_heavy_bids_go_long() {
+        let signals = generate(&input(&[("0.49", 400), ("0.48", 400)], &[("0.51", 100), ("0.52", 100)]));
+        assert_eq!(signals.len(), 1);
+        let signal = &signals[0];
+        assert_eq!(signal.direction, SignalDirection::Long);
+        assert_eq!(signal.entry_price, Decimal::from_str_exact("0.50").unwrap());
+        assert_eq!(signal.target_price, Decimal::from_str_exact("0.52").unwrap());
+        assert_eq!(signal.stop_loss, Decimal::from_str_exact("0.49").unwrap());
+        assert!((signal.metadata.custom_fields["imbalance"].as_f64().unwrap() - 0.6).abs() < 1e-9);
+        assert!(signal.expires_at.unwrap() <= Utc::now() + chrono::Duration::minutes(5));
+    }
+
+    #[test]
+    fn test_heavy_asks_go_short() {
+        // Only the best five levels count: the far 0.40 bid doesn't offset the asks
+        let bids = [("0.49", 50), ("0.48", 50), ("0.47", 50), ("0.46", 50), ("0.45", 50), ("0.40", 5000)];
+        let signals = generate(&input(&bids, &[("0.51", 500), ("0.52", 500)]));
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].direction, SignalDirection::Short);
+        assert!(signals[0].target_price < signals[0].entry_price);
+        assert!(signals[0].metadata.custom_fields["imbalance"].as_f64().unwrap() < -0.3);
+    }
+
+    #[test]
+    fn test_balanced_book_signals_nothing() {
+        let generator = OrderBookImbalanceGenerator::new(OrderBookImbalanceConfig::default());
+        let balanced = input(&[("0.49", 300), ("0.48", 200)], &[("0.51", 250), ("0.52", 250)]);
+        assert!(generate(&balanced).is_empty());
+        assert_eq!(generator.skip_reason(&balanced), None);
+
+        let thin = input(&[("0.49", 40)], &[("0.51", 5)]);
+        assert!(generate(&thin).is_empty());
+        assert_eq!(generator.skip_reason(&thin), Some(SkipReason::DegradedData { detail: "thin_book" }));
+    }
+}
@@ -1,14 +1,16 @@
 // Pair Cost Arbitrage Generator (gabagool style)
 // Generates signals based on maintaining avg_YES + avg_NO < 1.00
 
-use chrono::{DateTime, Utc};
-use common::{Market, OrderSide};
+use anyhow::Result;
+use chrono::Utc;
+use common::{MarketId, OrderSide};
 use rust_decimal::Decimal;
+use serde::Serialize;
 use uuid::Uuid;
 
 use crate::signals::{
     SignalInput, SignalDirection, SignalGenerator, SignalMetadata, SignalType, TradeSignal,
-    OrderBookSnapshot, StateUpdate, MultiSignalGenerator,
+    OrderBookSnapshot, PairSide, StateUpdate, round_to_tick,
 };
 
 /// Pair cost configuration
@@ -26,6 +28,9 @@ pub struct PairCostConfig {
     pub max_total_size: Decimal,
     /// Minimum edge required to enter position
     pub min_edge: Decimal,
+    /// Once profit is locked, sell the pairs early if both legs' bids add
+    /// up to at least 1.00 minus this; 0 exits only at or above fair value
+    pub early_exit_max_discount: Decimal,
 }
 
 impl Default for PairCostConfig {
@@ -37,6 +42,7 @@ impl Default for PairCostConfig {
             max_imbalance_ratio: Decimal::from_str_exact("1.5").unwrap(), // 1.5:1 max ratio
             max_total_size: Decimal::from_str_exact("1000").unwrap(), // $1000 max position
             min_edge: Decimal::from_str_exact("0.01").unwrap(), // 1% minimum edge
+            early_exit_max_discount: Decimal::ZERO,
         }
     }
 }
@@ -72,18 +78,26 @@ impl Default for PairCostState {
 impl PairCostState {
     /// Calculate current pair cost
     pub fn calculate_pair_cost(&mut self) {
-        if self.yes_qty > Decimal::ZERO {
-            self.avg_yes_price = self.yes_cost / self.yes_qty;
-        }
-        if self.no_qty > Decimal::ZERO {
-            self.avg_no_price = self.no_cost / self.no_qty;
-        }
+        self.avg_yes_price = if self.yes_qty > Decimal::ZERO {
+            self.yes_cost / self.yes_qty
+        } else {
+            Decimal::ZERO
+        };
+        self.avg_no_price = if self.no_qty > Decimal::ZERO {
+            self.no_cost / self.no_qty
+        } else {
+            Decimal::ZERO
+        };
         self.pair_cost = self.avg_yes_price + self.avg_no_price;
     }
 
-    /// Check if we have locked in profit
+    /// Check if we have locked in profit: both legs held, under the target
+    /// pair cost, and the smaller leg pays out more than everything cost
     pub fn has_locked_profit(&self, config: &PairCostConfig) -> bool {
-        self.pair_cost > Decimal::ZERO && self.pair_cost < config.target_pair_cost * config.safety_margin
+        self.yes_qty > Decimal::ZERO
+            && self.no_qty > Decimal::ZERO
+            && self.pair_cost < config.target_pair_cost * config.safety_margin
+            && self.guaranteed_profit() > Decimal::ZERO
     }
 
     /// Calculate guaranteed profit
@@ -93,6 +107,12 @@ impl PairCostState {
         min_qty - total_cost
     }
 
+    /// Neither leg held. A leg then only opens below half the safety margin,
+    /// where the other leg at the same price would still complete the pair
+    fn is_flat(&self) -> bool {
+        self.yes_qty == Decimal::ZERO && self.no_qty == Decimal::ZERO
+    }
+
     /// Check if we should add YES position
     pub fn should_buy_yes(&self, price: Decimal, config: &PairCostConfig) -> bool {
         // Check if adding YES at this price improves our position
@@ -102,6 +122,10 @@ impl PairCostState {
 
         let new_pair_cost = new_avg_yes + self.avg_no_price;
 
+        if self.is_flat() && price * Decimal::from(2) >= config.safety_margin {
+            return false;
+        }
+
         // Check if we exceed max size
         if self.yes_qty + config.min_position_size > config.max_total_size {
             return false;
@@ -115,8 +139,10 @@ impl PairCostState {
             }
         }
 
-        // Check if this improves our pair cost
-        new_pair_cost < self.pair_cost && new_pair_cost < config.safety_margin
+        // Opening the leg only has to keep the pair under the margin; adding
+        // to it must also improve our pair cost
+        let improves = self.yes_qty == Decimal::ZERO || new_pair_cost < self.pair_cost;
+        improves && new_pair_cost < config.safety_margin
     }
 
     /// Check if we should add NO position
@@ -128,6 +154,10 @@ impl PairCostState {
 
         let new_pair_cost = self.avg_yes_price + new_avg_no;
 
+        if self.is_flat() && price * Decimal::from(2) >= config.safety_margin {
+            return false;
+        }
+
         // Check if we exceed max size
         if self.no_qty + config.min_position_size > config.max_total_size {
             return false;
@@ -135,14 +165,16 @@ impl PairCostState {
 
         // Check if imbalance is acceptable
         if self.yes_qty > Decimal::ZERO {
-            let new_ratio = self.no_qty / self.yes_qty;
+            let new_ratio = new_no_qty / self.yes_qty;
             if new_ratio > config.max_imbalance_ratio {
                 return false;
             }
         }
 
-        // Check if this improves our pair cost
-        new_pair_cost < self.pair_cost && new_pair_cost < config.safety_margin
+        // Opening the leg only has to keep the pair under the margin; adding
+        // to it must also improve our pair cost
+        let improves = self.no_qty == Decimal::ZERO || new_pair_cost < self.pair_cost;
+        improves && new_pair_cost < config.safety_margin
     }
 
     /// Update state after buying YES
@@ -160,12 +192,45 @@ impl PairCostState {
         self.total_invested += qty * price;
         self.calculate_pair_cost();
     }
+
+    /// Update state after selling YES; the rest keeps its average price
+    pub fn remove_yes(&mut self, qty: Decimal) {
+        let qty = qty.min(self.yes_qty);
+        let cost = qty * self.avg_yes_price;
+        self.yes_qty -= qty;
+        self.yes_cost -= cost;
+        self.total_invested -= cost;
+        self.calculate_pair_cost();
+    }
+
+    /// Update state after selling NO; the rest keeps its average price
+    pub fn remove_no(&mut self, qty: Decimal) {
+        let qty = qty.min(self.no_qty);
+        let cost = qty * self.avg_no_price;
+        self.no_qty -= qty;
+        self.no_cost -= cost;
+        self.total_invested -= cost;
+        self.calculate_pair_cost();
+    }
+}
+
+/// Current position in one market, for dashboards
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PairCostSnapshot {
+    pub yes_qty: Decimal,
+    pub no_qty: Decimal,
+    pub avg_yes_price: Decimal,
+    pub avg_no_price: Decimal,
+    pub pair_cost: Decimal,
+    pub guaranteed_profit: Decimal,
+    /// The generator stops entering once this holds
+    pub locked: bool,
 }
 
 /// Pair cost arbitrage generator
 pub struct PairCostGenerator {
     config: PairCostConfig,
-    states: std::collections::HashMap<Uuid, PairCostState>,
+    states: std::collections::HashMap<MarketId, PairCostState>,
 }
 
 impl PairCostGenerator {
@@ -176,11 +241,100 @@ impl PairCostGenerator {
         }
     }
 
+    /// Position in a market the generator has seen fills for
+    pub fn state_snapshot(&self, market_id: MarketId) -> Option<PairCostSnapshot> {
+        self.states.get(&market_id).map(|state| PairCostSnapshot {
+            yes_qty: state.yes_qty,
+            no_qty: state.no_qty,
+            avg_yes_price: state.avg_yes_price,
+            avg_no_price: state.avg_no_price,
+            pair_cost: state.pair_cost,
+            guaranteed_profit: state.guaranteed_profit(),
+            locked: state.has_locked_profit(&self.config),
+        })
+    }
+
+    /// Once profit is locked, an informational signal for the executor:
+    /// hold the pairs to resolution, or sell them now when the book bids
+    /// enough for every pair. None until locked
+    pub fn generate_exit(&self, input: &SignalInput) -> Option<TradeSignal> {
+        let state = self.states.get(&input.market.id)?;
+        if !state.has_locked_profit(&self.config) {
+            return None;
+        }
+        let pairs = state.yes_qty.min(state.no_qty);
+        let total_cost = state.yes_cost + state.no_cost;
+        let guaranteed_profit = state.guaranteed_profit();
+
+        // Selling NO hits a NO bid, which is 1 - the YES ask
+        let early_exit_price = input.order_book.as_ref().and_then(|order_book| {
+            let (yes_bid, yes_ask) = (order_book.bids.first()?, order_book.asks.first()?);
+            let pair_bid = yes_bid.price + (Decimal::ONE - yes_ask.price);
+            let fills = yes_bid.size >= pairs && yes_ask.size >= pairs;
+            (fills && pair_bid >= Decimal::ONE - self.config.early_exit_max_discount).then_some(pair_bid)
+        });
+        let (action, exit_price, profit) = match early_exit_price {
+            Some(price) => ("exit_early", price, pairs * price - total_cost),
+            None => ("hold_to_resolution", Decimal::ONE, guaranteed_profit),
+        };
+        let reasoning = match early_exit_price {
+            Some(price) => format!(
+                "Pair Cost Arbitrage: {} pairs locked at {:.4}, sell both legs now for {:.4} a pair, profit {:.4}",
+                pairs, state.pair_cost, price, profit
+            ),
+            None => format!(
+                "Pair Cost Arbitrage: {} pairs locked at {:.4}, hold to resolution for {:.4} guaranteed",
+                pairs, state.pair_cost, guaranteed_profit
+            ),
+        };
+
+        Some(TradeSignal {
+            id: Uuid::new_v4(),
+            market_id: input.market.id,
+            signal_type: SignalType::SpreadArbitrage,
+            direction: SignalDirection::Neutral,
+            outcome_id: input.market.outcomes.first().map(|o| o.id.clone()),
+            entry_price: round_to_tick(exit_price, input.tick_size()),
+            target_price: Decimal::ONE,
+            stop_loss: Decimal::ZERO,
+            position_size: pairs,
+            confidence: 1.0, // Nothing left to go wrong
+            expected_value: profit,
+            edge: profit / total_cost,
+            kelly_fraction: 0.0, // Nothing to buy
+            reasoning,
+            metadata: SignalMetadata {
+                research_sources: vec!["pair_cost_arbitrage".to_string()],
+                data_points: 1,
+                liquidity_score: 0.85,
+                volatility_score: 0.5,
+                price_age_ms: None,
+                orderbook_age_ms: None,
+                research_age_ms: None,
+                custom_fields: serde_json::json!({
+                    "strategy": "pair_cost_arbitrage",
+                    "exit": true,
+                    "action": action,
+                    "locked_pairs": pairs.to_string(),
+                    "current_pair_cost": state.pair_cost.to_string(),
+                    "yes_qty": state.yes_qty.to_string(),
+                    "no_qty": state.no_qty.to_string(),
+                    "guaranteed_profit": guaranteed_profit.to_string(),
+                    "early_exit_price": early_exit_price.map(|price| price.to_string()),
+                }),
+            },
+            created_at: Utc::now(),
+            expires_at: Some(Utc::now() + chrono::Duration::minutes(15)),
+            variant: None,
+        })
+    }
+
     /// Find optimal entry points for pair cost arbitrage
     fn find_entry_opportunity(
         &self,
         order_book: &OrderBookSnapshot,
         state: &PairCostState,
+        tick_size: Decimal,
     ) -> (Option<TradeSignal>, Option<TradeSignal>) {
         let mut yes_signal = None;
         let mut no_signal = None;
@@ -188,7 +342,7 @@ impl PairCostGenerator {
         // Check YES entry (look for cheap YES)
         if let Some(best_ask) = order_book.asks.first() {
             if state.should_buy_yes(best_ask.price, &self.config) {
-                yes_signal = Some(self.create_yes_signal(best_ask.price, state));
+                yes_signal = Some(self.create_yes_signal(round_to_tick(best_ask.price, tick_size), state, tick_size));
             }
         }
 
@@ -197,26 +351,26 @@ impl PairCostGenerator {
             // NO price = 1 - YES price
             let no_price = Decimal::ONE - best_bid.price;
             if state.should_buy_no(no_price, &self.config) {
-                no_signal = Some(self.create_no_signal(no_price, state));
+                no_signal = Some(self.create_no_signal(round_to_tick(no_price, tick_size), state, tick_size));
             }
         }
 
         (yes_signal, no_signal)
     }
 
-    fn create_yes_signal(&self, price: Decimal, state: &PairCostState) -> TradeSignal {
+    fn create_yes_signal(&self, price: Decimal, state: &PairCostState, tick_size: Decimal) -> TradeSignal {
         let qty = self.config.min_position_size;
         let cost = qty * price;
 
         TradeSignal {
             id: Uuid::new_v4(),
-            market_id: uuid::Uuid::new_v4(), // Will be set by caller
+            market_id: MarketId::random(), // Will be set by caller
             signal_type: SignalType::SpreadArbitrage,
             direction: SignalDirection::Long,
             outcome_id: None,
             entry_price: price,
             target_price: Decimal::ONE, // Resolves to $1.00 if correct
-            stop_loss: price * Decimal::from_str_exact("0.9").unwrap(),
+            stop_loss: round_to_tick(price * Decimal::from_str_exact("0.9").unwrap(), tick_size),
             position_size: qty,
             confidence: 0.95, // High confidence - mathematical edge
             expected_value: cost * (Decimal::ONE - price),
@@ -231,6 +385,9 @@ impl PairCostGenerator {
                 data_points: 1,
                 liquidity_score: 0.85,
                 volatility_score: 0.5,
+                price_age_ms: None,
+                orderbook_age_ms: None,
+                research_age_ms: None,
                 custom_fields: serde_json::json!({
                     "strategy": "pair_cost_arbitrage",
                     "current_pair_cost": state.pair_cost.to_string(),
@@ -242,22 +399,23 @@ impl PairCostGenerator {
             },
             created_at: Utc::now(),
             expires_at: Some(Utc::now() + chrono::Duration::minutes(15)), // 15 min validity
+            variant: None,
         }
     }
 
-    fn create_no_signal(&self, price: Decimal, state: &PairCostState) -> TradeSignal {
+    fn create_no_signal(&self, price: Decimal, state: &PairCostState, tick_size: Decimal) -> TradeSignal {
         let qty = self.config.min_position_size;
         let cost = qty * price;
 
         TradeSignal {
             id: Uuid::new_v4(),
-            market_id: uuid::Uuid::new_v4(),
+            market_id: MarketId::random(),
             signal_type: SignalType::SpreadArbitrage,
             direction: SignalDirection::Short,
             outcome_id: None,
             entry_price: price,
             target_price: Decimal::ONE,
-            stop_loss: price * Decimal::from_str_exact("1.1").unwrap(),
+            stop_loss: round_to_tick(price * Decimal::from_str_exact("1.1").unwrap(), tick_size),
             position_size: qty,
             confidence: 0.95,
             expected_value: cost * (Decimal::ONE - price),
@@ -272,6 +430,9 @@ impl PairCostGenerator {
                 data_points: 1,
                 liquidity_score: 0.85,
                 volatility_score: 0.5,
+                price_age_ms: None,
+                orderbook_age_ms: None,
+                research_age_ms: None,
                 custom_fields: serde_json::json!({
                     "strategy": "pair_cost_arbitrage",
                     "current_pair_cost": state.pair_cost.to_string(),
@@ -283,96 +444,247 @@ impl PairCostGenerator {
             },
             created_at: Utc::now(),
             expires_at: Some(Utc::now() + chrono::Duration::minutes(15)),
+            variant: None,
         }
     }
 }
 
 impl SignalGenerator for PairCostGenerator {
-    fn generate(&mut self, input: &SignalInput) -> Vec<TradeSignal> {
+    fn generate(&mut self, input: &SignalInput) -> Result<Vec<TradeSignal>> {
         let order_book = match &input.order_book {
             Some(ob) => ob,
-            None => return vec![],
+            None => return Ok(vec![]),
         };
 
         // Get or create state
-        let state = self.states
-            .entry(input.market.id)
-            .or_insert_with(PairCostState::default());
+        let state = self.states.entry(input.market.id).or_default().clone();
 
-        // Check if we already have locked profit - no more entries needed
+        // Check if we already have locked profit - no more entries needed,
+        // only how to get out
         if state.has_locked_profit(&self.config) {
-            return vec![];
+            return Ok(self.generate_exit(input).into_iter().collect());
         }
 
         // Find entry opportunities
-        let (yes_signal, no_signal) = self.find_entry_opportunity(order_book, state);
+        let (yes_signal, no_signal) = self.find_entry_opportunity(order_book, &state, input.tick_size());
 
         let mut signals = Vec::new();
 
         if let Some(mut s) = yes_signal {
             s.market_id = input.market.id;
-            s.outcome_id = input.market.outcomes.get(0).map(|o| o.id.clone());
+            s.outcome_id = input.market.outcomes.first().map(|o| o.id.clone());
             signals.push(s);
         }
 
         if let Some(mut s) = no_signal {
             s.market_id = input.market.id;
-            s.outcome_id = input.market.outcomes.get(0).map(|o| o.id.clone());
+            s.outcome_id = input.market.outcomes.first().map(|o| o.id.clone());
             signals.push(s);
         }
 
-        signals
+        Ok(signals)
     }
 
-    fn update_state(&mut self, market_id: Uuid, update: &StateUpdate) {
-        let state = self.states.entry(market_id).or_insert_with(PairCostState::default());
+    fn update_state(&mut self, market_id: MarketId, update: &StateUpdate) {
+        let state = self.states.entry(market_id).or_default();
 
         match update {
-            StateUpdate::TradeExecution { side, size, price, .. } => {
-                match side {
-                    OrderSide::Buy => {
-                        // Determine if this is YES or NO based on price
-                        // YES: price < 0.5 typically, NO: price > 0.5 typically
-                        // But we need explicit signal from execution
-                    }
-                    OrderSide::Sell => {
-                        // Selling YES = buying NO
-                    }
-                }
-            }
-            StateUpdate::PositionClosed { side, size, realized_pnl, .. } => {
-                match side {
-                    OrderSide::Buy => {
-                        state.yes_qty -= *size;
-                        state.total_invested += *realized_pnl;
-                    }
-                    OrderSide::Sell => {
-                        state.no_qty -= *size;
-                        state.total_invested += *realized_pnl;
-                    }
+            StateUpdate::TradeExecution { outcome_side, side, size, price, .. } => {
+                match (side, outcome_side) {
+                    (OrderSide::Buy, PairSide::Yes) => state.add_yes(*size, *price),
+                    (OrderSide::Buy, PairSide::No) => state.add_no(*size, *price),
+                    (OrderSide::Sell, PairSide::Yes) => state.remove_yes(*size),
+                    (OrderSide::Sell, PairSide::No) => state.remove_no(*size),
                 }
-                state.calculate_pair_cost();
             }
+            StateUpdate::PositionClosed { outcome_side, size, .. } => match outcome_side {
+                PairSide::Yes => state.remove_yes(*size),
+                PairSide::No => state.remove_no(*size),
+            },
             StateUpdate::VolatilityUpdate { .. } => {
                 // Not used for pair cost arbitrage
             }
         }
     }
+
+    fn signal_type(&self) -> SignalType {
+        SignalType::SpreadArbitrage
+    }
 }
 
-// Implement old SignalGenerator trait for backward compatibility
-impl SignalGenerator for PairCostGenerator {
-    fn generate(&self, input: &SignalInput) -> anyhow::Result<Option<TradeSignal>> {
-        let mut temp_gen = PairCostGenerator {
-            config: self.config.clone(),
-            states: self.states.clone(),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(outcome_side: PairSide, side: OrderSide, size: i64, price: &str) -> StateUpdate {
+        StateUpdate::TradeExecution {
+            signal_id: None,
+            outcome_id: None,
+            outcome_side,
+            side,
+            size: Decimal::from(size),
+            price: Decimal::from_str_exact(price).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_executions_update_matching_side() {
+        let mut generator = PairCostGenerator::new(PairCostConfig::default());
+        let market_id = MarketId::random();
+        generator.update_state(market_id, &fill(PairSide::Yes, OrderSide::Buy, 100, "0.40"));
+        generator.update_state(market_id, &fill(PairSide::Yes, OrderSide::Buy, 100, "0.44"));
+        generator.update_state(market_id, &fill(PairSide::No, OrderSide::Buy, 200, "0.50"));
+
+        let state = &generator.states[&market_id];
+        assert_eq!(state.yes_qty, Decimal::from(200));
+        assert_eq!(state.no_qty, Decimal::from(200));
+        assert_eq!(state.avg_yes_price, Decimal::from_str_exact("0.42").unwrap());
+        assert_eq!(state.pair_cost, Decimal::from_str_exact("0.92").unwrap());
+        assert_eq!(state.guaranteed_profit(), Decimal::from(16));
+
+        // Selling YES keeps the remaining average
+        generator.update_state(market_id, &fill(PairSide::Yes, OrderSide::Sell, 50, "0.60"));
+        let state = &generator.states[&market_id];
+        assert_eq!(state.yes_qty, Decimal::from(150));
+        assert_eq!(state.avg_yes_price, Decimal::from_str_exact("0.42").unwrap());
+        assert_eq!(state.pair_cost, Decimal::from_str_exact("0.92").unwrap());
+    }
+
+    /// YES trades at `yes_ask` bid `yes_bid`, so NO costs 1 - `yes_bid`
+    fn input(market_id: MarketId, yes_bid: &str, yes_ask: &str) -> SignalInput {
+        crate::test_support::input(market_id)
+            .market(|market| market.category = "crypto".to_string())
+            .research(|research| research.confidence = 0.5)
+            .book(&[(yes_bid, 1000)], &[(yes_ask, 1000)])
+            .build()
+    }
+
+    /// Fill `signal` as the execution layer would, on the leg it names
+    fn fill_signal(generator: &mut PairCostGenerator, signal: &TradeSignal) {
+        let outcome_side = match signal.direction {
+            SignalDirection::Short => PairSide::No,
+            _ => PairSide::Yes,
         };
-        let signals = temp_gen.generate(input);
+        generator.update_state(
+            signal.market_id,
+            &StateUpdate::TradeExecution {
+                signal_id: Some(signal.id),
+                outcome_id: None,
+                outcome_side,
+                side: OrderSide::Buy,
+                size: signal.position_size,
+                price: signal.entry_price,
+            },
+        );
+    }
 
-        Ok(signals.into_iter().next())
+    #[test]
+    fn test_only_exits_once_profit_is_locked() {
+        let mut generator = PairCostGenerator::new(PairCostConfig::default());
+        let market_id = MarketId::random();
+        assert!(generator.state_snapshot(market_id).is_none());
+
+        // YES dips to 0.45: open the YES leg
+        let cheap_yes = input(market_id, "0.40", "0.45");
+        let signals = generator.generate(&cheap_yes).unwrap();
+        let yes = signals.iter().find(|s| s.direction == SignalDirection::Long).unwrap();
+        fill_signal(&mut generator, yes);
+        let snapshot = generator.state_snapshot(market_id).unwrap();
+        assert_eq!(snapshot.yes_qty, Decimal::from(10));
+        assert!(!snapshot.locked);
+
+        // YES bid 0.55 makes NO 0.45: 0.90 for the pair
+        let cheap_no = input(market_id, "0.55", "0.60");
+        let signals = generator.generate(&cheap_no).unwrap();
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].direction, SignalDirection::Short);
+        assert_eq!(signals[0].entry_price, Decimal::from_str_exact("0.45").unwrap());
+        fill_signal(&mut generator, &signals[0]);
+
+        let snapshot = generator.state_snapshot(market_id).unwrap();
+        assert_eq!(snapshot.pair_cost, Decimal::from_str_exact("0.90").unwrap());
+        assert_eq!(snapshot.guaranteed_profit, Decimal::ONE);
+        assert!(snapshot.locked);
+        for locked in [&cheap_yes, &cheap_no] {
+            let signals = generator.generate(locked).unwrap();
+            assert_eq!(signals.len(), 1);
+            assert!(signals[0].is_exit());
+        }
+
+        // Closing the NO leg unlocks it again
+        generator.update_state(
+            market_id,
+            &StateUpdate::PositionClosed {
+                outcome_id: None,
+                outcome_side: PairSide::No,
+                side: OrderSide::Sell,
+                size: Decimal::from(10),
+                realized_pnl: Decimal::ZERO,
+            },
+        );
+        let snapshot = generator.state_snapshot(market_id).unwrap();
+        assert_eq!(snapshot.no_qty, Decimal::ZERO);
+        assert_eq!(snapshot.pair_cost, Decimal::from_str_exact("0.45").unwrap());
+        assert!(!snapshot.locked);
+        assert!(!generator.generate(&cheap_no).unwrap().is_empty());
     }
 
-    fn signal_type(&self) -> SignalType {
-        SignalType::SpreadArbitrage
+    #[test]
+    fn test_locked_pair_carries_guaranteed_profit_and_early_exit() {
+        let mut generator = PairCostGenerator::new(PairCostConfig {
+            early_exit_max_discount: Decimal::from_str_exact("0.01").unwrap(),
+            ..PairCostConfig::default()
+        });
+        let market_id = MarketId::random();
+        let wide = input(market_id, "0.45", "0.55");
+
+        generator.update_state(market_id, &fill(PairSide::Yes, OrderSide::Buy, 120, "0.40"));
+        assert!(generator.generate_exit(&wide).is_none());
+
+        // 100 pairs pay 100 for 48 + 50
+        generator.update_state(market_id, &fill(PairSide::No, OrderSide::Buy, 100, "0.50"));
+        let exit = generator.generate_exit(&wide).unwrap();
+        let field = |signal: &TradeSignal, name: &str| signal.metadata.custom_fields[name].clone();
+        let decimal = |signal: &TradeSignal, name: &str| field(signal, name).as_str().unwrap().parse::<Decimal>().unwrap();
+        assert!(exit.is_exit());
+        assert_eq!(exit.direction, SignalDirection::Neutral);
+        assert_eq!(exit.market_id, market_id);
+        assert_eq!(exit.position_size, Decimal::from(100));
+        assert_eq!(decimal(&exit, "guaranteed_profit"), Decimal::from(2));
+        assert_eq!(exit.expected_value, Decimal::from(2));
+        // Pairs only bid 0.90 here
+        assert_eq!(field(&exit, "action"), "hold_to_resolution");
+        assert!(field(&exit, "early_exit_price").is_null());
+
+        // 0.52 for YES plus 0.47 for NO is within the discount of 1.00
+        let tight = input(market_id, "0.52", "0.53");
+        let exit = generator.generate(&tight).unwrap().pop().unwrap();
+        assert_eq!(field(&exit, "action"), "exit_early");
+        assert_eq!(decimal(&exit, "early_exit_price"), Decimal::from_str_exact("0.99").unwrap());
+        assert_eq!(exit.entry_price, Decimal::from_str_exact("0.99").unwrap());
+        assert_eq!(exit.expected_value, Decimal::ONE);
+        assert_eq!(decimal(&exit, "guaranteed_profit"), Decimal::from(2));
+    }
+
+    #[test]
+    fn test_no_lock_when_pairs_cost_over_one() {
+        let mut generator = PairCostGenerator::new(PairCostConfig::default());
+        let market_id = MarketId::random();
+
+        // YES 0.55 and NO 0.50: every pair costs 1.05, so nothing opens
+        let expensive = input(market_id, "0.50", "0.55");
+        for _ in 0..5 {
+            assert!(generator.generate(&expensive).unwrap().is_empty());
+        }
+
+        // Fills on both legs anyway never lock anything in
+        generator.update_state(market_id, &fill(PairSide::Yes, OrderSide::Buy, 10, "0.55"));
+        generator.update_state(market_id, &fill(PairSide::No, OrderSide::Buy, 10, "0.50"));
+        let snapshot = generator.state_snapshot(market_id).unwrap();
+        assert_eq!(snapshot.pair_cost, Decimal::from_str_exact("1.05").unwrap());
+        assert_eq!(snapshot.guaranteed_profit, Decimal::from_str_exact("-0.5").unwrap());
+        assert!(!snapshot.locked);
+        assert!(generator.generate(&expensive).unwrap().is_empty());
+        assert!(!generator.state_snapshot(market_id).unwrap().locked);
     }
 }
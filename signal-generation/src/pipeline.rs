@@ -1,11 +1,24 @@
 // Signal Generation Pipeline
 // Orchestrates signal generation from research outputs and market data
 
-use super::signals::{SignalGenerator, SignalInput, TradeSignal};
-use super::validators::SignalValidator;
+use super::diagnostics::{CycleDiagnostics, DiagnosticsConfig, DiagnosticsLog, PipelineStats, StageOutcome};
+use super::experiments::GeneratorExperiment;
+use super::research_adapter::ResearchAggregator;
+use super::slippage::SlippageModel;
+use super::volatility::{VolatilityEstimator, VolatilityScore};
+use super::signals::{
+    BatchSignalGenerator, OrderBookSnapshot, PriceSnapshot, ResearchOutput, SentimentScore, SignalDirection,
+    SignalGenerator, SignalInput, SignalType, StateUpdate, TradeSignal,
+};
+use super::validators::{SignalValidator, ValidationResult};
 use super::storage::SignalStorage;
 use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
 use rust_decimal::prelude::*;
+use common::{Market, MarketId, OrderSide, OutcomeId};
+use portfolio_risk::{PortfolioRiskManager, RiskViolation};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use tracing::{debug, info, warn};
 
 /// Configuration for the signal generation pipeline
@@ -19,6 +32,14 @@ pub struct PipelineConfig {
     pub min_confidence: f64,
     /// Minimum edge threshold for any signal
     pub min_edge: Decimal,
+    /// How surviving signals are ordered before `max_signals_per_cycle` applies
+    pub ranking: RankingWeights,
+    /// A long and a short on one outcome from different generators are both
+    /// dropped unless one's expected value is at least this multiple of the other's
+    pub contradiction_ev_ratio: Decimal,
+    /// Lifetime of each signal type's signals counted from creation,
+    /// replacing the expiry its generator set
+    pub expiry_overrides: HashMap<SignalType, Duration>,
 }
 
 impl Default for PipelineConfig {
@@ -28,16 +49,53 @@ impl Default for PipelineConfig {
             max_signals_per_cycle: 10,
             min_confidence: 0.6,
             min_edge: Decimal::from_str_exact("0.03").unwrap(), // 3%
+            ranking: RankingWeights::default(),
+            contradiction_ev_ratio: Decimal::from(2),
+            expiry_overrides: HashMap::new(),
         }
     }
 }
 
+/// Weights of the ranking score, `confidence * (edge * w_edge + expected_value * w_ev)`
+#[derive(Debug, Clone)]
+pub struct RankingWeights {
+    pub edge: f64,
+    /// Expected value is in dollars rather than a fraction, so a small weight
+    /// goes a long way
+    pub expected_value: f64,
+}
+
+impl Default for RankingWeights {
+    fn default() -> Self {
+        Self {
+            edge: 1.0,
+            expected_value: 0.0,
+        }
+    }
+}
+
+impl RankingWeights {
+    pub fn score(&self, signal: &TradeSignal) -> f64 {
+        let edge = signal.edge.to_f64().unwrap_or(0.0);
+        let expected_value = signal.expected_value.to_f64().unwrap_or(0.0);
+        signal.confidence * (edge * self.edge + expected_value * self.expected_value)
+    }
+}
+
 /// Signal generation pipeline
 pub struct SignalPipeline {
-    generators: Vec<Box<dyn SignalGenerator + Send + Sync>>,
+    generators: Vec<Box<dyn SignalGenerator + Send>>,
+    batch_generators: Vec<Box<dyn BatchSignalGenerator + Send>>,
     validators: Vec<Box<dyn SignalValidator + Send + Sync>>,
     storage: Option<Box<dyn SignalStorage + Send + Sync>>,
     config: PipelineConfig,
+    diagnostics: DiagnosticsLog,
+    stats: PipelineStats,
+    research: Option<Arc<ResearchAggregator>>,
+    slippage: Option<SlippageModel>,
+    volatility: Option<VolatilityEstimator>,
+    /// Number of the cycle last processed
+    cycle: u64,
 }
 
 impl SignalPipeline {
@@ -45,19 +103,43 @@ impl SignalPipeline {
     pub fn new(config: PipelineConfig) -> Self {
         Self {
             generators: Vec::new(),
+            batch_generators: Vec::new(),
             validators: Vec::new(),
             storage: None,
             config,
+            diagnostics: DiagnosticsLog::default(),
+            stats: PipelineStats::default(),
+            research: None,
+            slippage: None,
+            volatility: None,
+            cycle: 0,
         }
     }
 
     /// Add a signal generator
-    pub fn add_generator(mut self, generator: Box<dyn SignalGenerator + Send + Sync>) -> Self {
+    pub fn add_generator(mut self, generator: Box<dyn SignalGenerator + Send>) -> Self {
         info!("Adding signal generator: {:?}", generator.signal_type());
         self.generators.push(generator);
         self
     }
 
+    /// Add a generator that runs once per batch over all markets
+    pub fn add_batch_generator(mut self, generator: Box<dyn BatchSignalGenerator + Send>) -> Self {
+        info!("Adding batch signal generator: {:?}", generator.signal_type());
+        self.batch_generators.push(generator);
+        self
+    }
+
+    /// Add a generator experiment; its variants share this pipeline's throttles
+    pub fn add_experiment(self, experiment: GeneratorExperiment) -> Self {
+        info!(
+            "Adding generator experiment {} for strategy {}",
+            experiment.experiment_id(),
+            experiment.parent_strategy()
+        );
+        self.add_generator(Box::new(experiment))
+    }
+
     /// Add a signal validator
     pub fn add_validator(mut self, validator: Box<dyn SignalValidator + Send + Sync>) -> Self {
         info!("Adding signal validator");
@@ -72,64 +154,284 @@ impl SignalPipeline {
         self
     }
 
-    /// Process a signal input and generate signals
-    pub async fn process(&self, input: &SignalInput) -> Result<Vec<TradeSignal>> {
+    /// Set how much per-market diagnostic history to keep
+    pub fn with_diagnostics(mut self, config: DiagnosticsConfig) -> Self {
+        self.diagnostics = DiagnosticsLog::new(config);
+        self
+    }
+
+    /// Adjust each signal's entry and size for the depth in its order book
+    /// before validation
+    pub fn with_slippage_model(mut self, model: SlippageModel) -> Self {
+        info!("Setting slippage model");
+        self.slippage = Some(model);
+        self
+    }
+
+    /// Estimate each market's volatility from its price history and feed it
+    /// to every generator as a `StateUpdate::VolatilityUpdate` before it runs
+    pub fn with_volatility_estimator(mut self, estimator: VolatilityEstimator) -> Self {
+        info!("Setting volatility estimator");
+        self.volatility = Some(estimator);
+        self
+    }
+
+    /// Take research for `build_input` from an aggregator
+    pub fn with_research(mut self, research: Arc<ResearchAggregator>) -> Self {
+        info!("Setting research aggregator");
+        self.research = Some(research);
+        self
+    }
+
+    /// Input for a market with the aggregator's current research, or empty
+    /// research (zero confidence, no estimate) when there is none
+    pub fn build_input(
+        &self,
+        market: Market,
+        order_book: Option<OrderBookSnapshot>,
+        price_history: Vec<PriceSnapshot>,
+    ) -> SignalInput {
+        let now = Utc::now();
+        let research_output = self
+            .research
+            .as_ref()
+            .and_then(|research| research.research(&market, now))
+            .unwrap_or_else(|| ResearchOutput {
+                market_id: market.id,
+                analysis: String::new(),
+                sentiment: SentimentScore { overall: 0.0, sources: vec![] },
+                confidence: 0.0,
+                probability_estimate: None,
+                key_factors: vec![],
+                timestamp: now,
+            });
+
+        SignalInput {
+            market,
+            research_output,
+            order_book,
+            price_history,
+        }
+    }
+
+    /// Process a signal input and generate signals.
+    ///
+    /// Only per-market generators run; batch generators need `process_batch`.
+    pub async fn process(&mut self, input: &SignalInput) -> Result<Vec<TradeSignal>> {
+        self.process_at(input, Utc::now()).await
+    }
+
+    /// `process` with input ages measured at `now` instead of the wall
+    /// clock, for replaying recorded data
+    pub async fn process_at(&mut self, input: &SignalInput, now: DateTime<Utc>) -> Result<Vec<TradeSignal>> {
+        if !self.config.enabled {
+            debug!("Pipeline is disabled, skipping signal generation");
+            return Ok(Vec::new());
+        }
+
+        self.cycle += 1;
+        let signals = self.generate(input, now);
+        self.finalize(signals, None).await
+    }
+
+    /// `process` with every surviving signal gated by `risk`, see
+    /// `process_batch_with_risk`
+    pub async fn process_with_risk(
+        &mut self,
+        input: &SignalInput,
+        risk: &PortfolioRiskManager,
+    ) -> Result<Vec<TradeSignal>> {
         if !self.config.enabled {
             debug!("Pipeline is disabled, skipping signal generation");
             return Ok(Vec::new());
         }
 
+        self.cycle += 1;
+        let signals = self.generate(input, Utc::now());
+        self.finalize(signals, Some(RiskGate { manager: risk, inputs: std::slice::from_ref(input) })).await
+    }
+
+    /// Process a snapshot of all markets: per-market generators run on each
+    /// input, batch generators run once over the whole slice
+    pub async fn process_batch(&mut self, inputs: &[SignalInput]) -> Result<Vec<TradeSignal>> {
+        let signals = self.generate_batch(inputs);
+        self.finalize(signals, None).await
+    }
+
+    /// `process_batch` with a pre-trade risk check: each signal that
+    /// survives ranking goes through `PortfolioRiskManager::evaluate_trade`
+    /// before throttling and storage. Signals raising a `RiskViolation` are
+    /// dropped and recorded as `RiskRejected`; one over only its Kelly limit
+    /// is cut down to it instead. Each signal is checked against the
+    /// portfolio as it stands, not with the others in the cycle filled.
+    pub async fn process_batch_with_risk(
+        &mut self,
+        inputs: &[SignalInput],
+        risk: &PortfolioRiskManager,
+    ) -> Result<Vec<TradeSignal>> {
+        let signals = self.generate_batch(inputs);
+        self.finalize(signals, Some(RiskGate { manager: risk, inputs })).await
+    }
+
+    /// Run every generator over a snapshot of all markets
+    fn generate_batch(&mut self, inputs: &[SignalInput]) -> Vec<TradeSignal> {
+        if !self.config.enabled {
+            debug!("Pipeline is disabled, skipping signal generation");
+            return Vec::new();
+        }
+
+        self.cycle += 1;
+        let mut signals = Vec::new();
+        let now = Utc::now();
+        for input in inputs {
+            signals.extend(self.generate(input, now));
+        }
+
+        for generator in &mut self.batch_generators {
+            let mut generated = generator.generate_batch(inputs);
+            for signal in &mut generated {
+                if let Some(input) = inputs.iter().find(|i| i.market.id == signal.market_id) {
+                    signal.metadata.record_input_ages(input, now);
+                    if let (Some(model), Some(order_book)) = (&self.slippage, &input.order_book) {
+                        model.apply(signal, order_book);
+                    }
+                }
+            }
+            debug!(
+                "Generated {} signals from batch generator {:?} over {} markets",
+                generated.len(),
+                generator.signal_type(),
+                inputs.len()
+            );
+            signals.extend(generated);
+
+            for (market_id, reason) in generator.last_skips() {
+                let generator = generator.signal_type();
+                self.diagnostics
+                    .record(market_id, self.cycle, StageOutcome::Skipped { generator, reason });
+            }
+        }
+
+        signals
+    }
+
+    /// Run every per-market generator on one input
+    fn generate(&mut self, input: &SignalInput, now: DateTime<Utc>) -> Vec<TradeSignal> {
         let mut signals = Vec::new();
 
+        if let Some(estimate) = self.volatility.as_mut().and_then(|v| v.observe_input(input)) {
+            let update = StateUpdate::VolatilityUpdate {
+                score: estimate.score,
+                raw_sigma: estimate.raw_sigma,
+            };
+            self.update_state(input.market.id, &update);
+        }
+        for validator in &mut self.validators {
+            validator.observe_input(input);
+        }
+
         // Generate signals from all generators
-        for generator in &self.generators {
+        for generator in &mut self.generators {
             match generator.generate(input) {
-                Ok(Some(signal)) => {
-                    debug!("Generated signal: {:?} for market {:?}", signal.signal_type, signal.market_id);
-                    signals.push(signal);
-                }
-                Ok(None) => {
+                Ok(generated) if generated.is_empty() => {
                     debug!("No signal generated from {:?}", generator.signal_type());
+                    let outcome = match generator.skip_reason(input) {
+                        Some(reason) => StageOutcome::Skipped { generator: generator.signal_type(), reason },
+                        None => StageOutcome::NoSignal { generator: generator.signal_type() },
+                    };
+                    self.diagnostics.record(input.market.id, self.cycle, outcome);
+                }
+                Ok(generated) => {
+                    for mut signal in generated {
+                        debug!("Generated signal: {:?} for market {:?}", signal.signal_type, signal.market_id);
+                        signal.metadata.record_input_ages(input, now);
+                        if let (Some(model), Some(order_book), false) =
+                            (&self.slippage, &input.order_book, signal.is_cancel())
+                        {
+                            model.apply(&mut signal, order_book);
+                        }
+                        signals.push(signal);
+                    }
                 }
                 Err(e) => {
                     warn!("Error generating signal from {:?}: {}", generator.signal_type(), e);
+                    let outcome = StageOutcome::GeneratorError {
+                        generator: generator.signal_type(),
+                        error: e.to_string(),
+                    };
+                    self.diagnostics.record(input.market.id, self.cycle, outcome);
                 }
             }
         }
 
+        signals
+    }
+
+    /// Filter, validate, rank, risk check, truncate and store generated signals
+    async fn finalize(&mut self, generated: Vec<TradeSignal>, risk: Option<RiskGate<'_>>) -> Result<Vec<TradeSignal>> {
         // Apply global filters
-        signals = signals
-            .into_iter()
-            .filter(|s| s.confidence >= self.config.min_confidence)
-            .filter(|s| s.edge >= self.config.min_edge)
-            .collect();
+        let mut signals = Vec::with_capacity(generated.len());
+        for mut signal in generated {
+            self.stats.record_generated(&signal.signal_type);
+            if let Some(lifetime) = self.config.expiry_overrides.get(&signal.signal_type) {
+                signal.expires_at = Some(signal.created_at + *lifetime);
+            }
+            match self.threshold_miss(&signal) {
+                Some(outcome) => {
+                    if let StageOutcome::BelowThreshold { threshold, .. } = &outcome {
+                        self.stats.record_rejected(&signal.signal_type, threshold);
+                    }
+                    self.diagnostics.record(signal.market_id, self.cycle, outcome);
+                }
+                None => signals.push(signal),
+            }
+        }
 
         // Validate signals
         let mut validated_signals = Vec::new();
-        for signal in &signals {
-            if self.validate(signal).await? {
-                validated_signals.push(signal.clone());
-            } else {
-                debug!("Signal rejected by validators: {:?}", signal.signal_type);
+        for mut signal in signals {
+            let results = self.validation_results(&signal).await?;
+            match results.last() {
+                Some((validator, score, result)) if !result.passed => {
+                    debug!("Signal rejected by validator {}: {:?}", validator, signal.signal_type);
+                    self.stats.record_rejected(&signal.signal_type, validator);
+                    let outcome = StageOutcome::Rejected {
+                        signal_type: signal.signal_type.clone(),
+                        validator,
+                        score: *score,
+                    };
+                    self.diagnostics.record(signal.market_id, self.cycle, outcome);
+                }
+                _ => {
+                    self.stats.record_validated(&signal.signal_type);
+                    attach_validation(&mut signal, results);
+                    validated_signals.push(signal);
+                }
             }
         }
 
-        // Limit number of signals
-        validated_signals.sort_by(|a, b| {
-            // Sort by expected value * confidence
-            let a_score = a.expected_value * Decimal::from_f64(a.confidence).unwrap_or(Decimal::ZERO);
-            let b_score = b.expected_value * Decimal::from_f64(b.confidence).unwrap_or(Decimal::ZERO);
-            b_score.partial_cmp(&a_score).unwrap_or(std::cmp::Ordering::Equal)
-        });
-
-        validated_signals.truncate(self.config.max_signals_per_cycle);
+        // Limit number of signals, best ranked first
+        let mut validated_signals = self.dedupe_and_rank(validated_signals);
+        if let Some(risk) = risk {
+            validated_signals = self.risk_check(validated_signals, risk);
+        }
+        let throttled = validated_signals.split_off(self.config.max_signals_per_cycle.min(validated_signals.len()));
+        for signal in throttled {
+            let outcome = StageOutcome::Throttled {
+                signal_type: signal.signal_type,
+                limit: "max_signals_per_cycle",
+            };
+            self.diagnostics.record(signal.market_id, self.cycle, outcome);
+        }
+        for signal in &validated_signals {
+            let outcome = StageOutcome::Emitted { signal_type: signal.signal_type.clone() };
+            self.diagnostics.record(signal.market_id, self.cycle, outcome);
+        }
 
         // Store signals if storage is configured
         if let Some(storage) = &self.storage {
-            for signal in &validated_signals {
-                if let Err(e) = storage.store(signal).await {
-                    warn!("Failed to store signal {:?}: {}", signal.id, e);
-                }
+            if let Err(e) = storage.store_batch(&validated_signals).await {
+                warn!("Failed to store {} signals: {}", validated_signals.len(), e);
             }
         }
 
@@ -137,20 +439,251 @@ impl SignalPipeline {
         Ok(validated_signals)
     }
 
-    /// Validate a signal against all validators
-    async fn validate(&self, signal: &TradeSignal) -> Result<bool> {
+    /// Drop duplicate and contradictory signals, then rank the rest.
+    ///
+    /// Signals are grouped by market and outcome, and only the best ranked of
+    /// each type and direction in a group is kept. A long and a short from
+    /// different generators contradict each other: the side with the higher
+    /// expected value survives if it beats the other by
+    /// `contradiction_ev_ratio`, otherwise both sides go. One generator
+    /// quoting both sides, as market making does, is not a contradiction.
+    /// Quote cancels never contradict anything and rank ahead of every trade,
+    /// so throttling cannot strand a quote its generator has pulled; inventory
+    /// unwinds rank next, and never count as duplicates of a quote.
+    fn dedupe_and_rank(&mut self, signals: Vec<TradeSignal>) -> Vec<TradeSignal> {
+        let mut scored: Vec<(f64, TradeSignal)> =
+            signals.into_iter().map(|s| (self.config.ranking.score(&s), s)).collect();
+        scored.sort_by(|a, b| {
+            b.1.is_cancel()
+                .cmp(&a.1.is_cancel())
+                .then(b.1.is_unwind().cmp(&a.1.is_unwind()))
+                .then(b.0.total_cmp(&a.0))
+        });
+
+        let mut dropped: Vec<Option<&'static str>> = vec![None; scored.len()];
+        let mut seen = HashSet::new();
+        let mut groups: HashMap<(MarketId, Option<OutcomeId>), Vec<usize>> = HashMap::new();
+        for (i, (_, signal)) in scored.iter().enumerate() {
+            let key = (signal.market_id, signal.outcome_id.clone());
+            let kind = (signal.signal_type.clone(), signal.direction.clone(), signal.is_unwind());
+            if seen.insert((key.clone(), kind)) {
+                groups.entry(key).or_default().push(i);
+            } else {
+                dropped[i] = Some("duplicate");
+            }
+        }
+
+        let ratio = self.config.contradiction_ev_ratio;
+        let dominates = |a: Decimal, b: Decimal| a > Decimal::ZERO && (b <= Decimal::ZERO || a >= b * ratio);
+        for members in groups.values() {
+            let side = |direction: SignalDirection| -> Vec<usize> {
+                members
+                    .iter()
+                    .copied()
+                    .filter(|&i| scored[i].1.direction == direction && !scored[i].1.is_cancel())
+                    .collect()
+            };
+            let (longs, shorts) = (side(SignalDirection::Long), side(SignalDirection::Short));
+            let contradicted = longs
+                .iter()
+                .any(|&l| shorts.iter().any(|&s| scored[l].1.signal_type != scored[s].1.signal_type));
+            if !contradicted {
+                continue;
+            }
+
+            let best_ev = |side: &[usize]| side.iter().map(|&i| scored[i].1.expected_value).max().unwrap_or_default();
+            let (long_ev, short_ev) = (best_ev(&longs), best_ev(&shorts));
+            let losers = if dominates(long_ev, short_ev) {
+                shorts
+            } else if dominates(short_ev, long_ev) {
+                longs
+            } else {
+                [longs, shorts].concat()
+            };
+            for i in losers {
+                dropped[i] = Some("contradicted");
+            }
+        }
+
+        let mut ranked = Vec::with_capacity(scored.len());
+        for ((_, signal), reason) in scored.into_iter().zip(dropped) {
+            match reason {
+                Some(reason) => {
+                    debug!("Dropping {:?} signal for market {:?} as {}", signal.signal_type, signal.market_id, reason);
+                    let outcome = StageOutcome::Deduplicated { signal_type: signal.signal_type, reason };
+                    self.diagnostics.record(signal.market_id, self.cycle, outcome);
+                }
+                None => ranked.push(signal),
+            }
+        }
+        ranked
+    }
+
+    /// Drop signals the risk manager rejects and cut the rest to their
+    /// Kelly limit
+    fn risk_check(&mut self, signals: Vec<TradeSignal>, risk: RiskGate<'_>) -> Vec<TradeSignal> {
+        let mut approved = Vec::with_capacity(signals.len());
+        for mut signal in signals {
+            // A cancel, an unwind or an exit adds no new risk to check
+            if signal.is_cancel() || signal.is_unwind() || signal.is_exit() {
+                approved.push(signal);
+                continue;
+            }
+            let Some(outcome_id) = risk.outcome_bought(&signal) else {
+                approved.push(signal);
+                continue;
+            };
+
+            let price = signal.entry_price.to_f64().unwrap_or(0.0);
+            let size = signal.position_size.to_f64().unwrap_or(0.0);
+            let edge = signal.edge.to_f64().map(|edge| edge * price);
+            let kelly_limit = match risk
+                .manager
+                .evaluate_trade_with_edge(signal.market_id, &outcome_id, OrderSide::Buy, price, size, edge)
+            {
+                Ok(evaluation) => evaluation.kelly_limit,
+                Err(RiskViolation::KellyLimitExceeded { kelly_limit, .. }) if kelly_limit > 0.0 => kelly_limit,
+                Err(violation) => {
+                    debug!("Signal rejected by risk manager: {:?} {}", signal.signal_type, violation);
+                    let outcome = StageOutcome::RiskRejected {
+                        signal_type: signal.signal_type.clone(),
+                        violation: violation.to_string(),
+                    };
+                    self.diagnostics.record(signal.market_id, self.cycle, outcome);
+                    continue;
+                }
+            };
+
+            if price > 0.0 && price * size > kelly_limit {
+                let clamped = Decimal::from_f64(kelly_limit / price).unwrap_or_default().round_dp(4);
+                debug!(
+                    "Cutting {:?} signal from {} to {} shares at its Kelly limit",
+                    signal.signal_type, signal.position_size, clamped
+                );
+                signal.position_size = clamped;
+            }
+            approved.push(signal);
+        }
+        approved
+    }
+
+    /// Feed a state update for a market to every generator
+    pub fn update_state(&mut self, market_id: MarketId, update: &StateUpdate) {
+        for generator in &mut self.generators {
+            generator.update_state(market_id, update);
+        }
+    }
+
+    /// State of every per-market generator, in registration order
+    pub fn snapshot_generator_states(&self) -> Vec<Option<serde_json::Value>> {
+        self.generators.iter().map(|g| g.snapshot_state()).collect()
+    }
+
+    /// Restore generator states from `snapshot_generator_states`.
+    ///
+    /// The pipeline must be built with the same generators in the same order.
+    pub fn restore_generator_states(&mut self, states: Vec<Option<serde_json::Value>>) -> Result<()> {
+        if states.len() != self.generators.len() {
+            anyhow::bail!(
+                "Snapshot has {} generator states but the pipeline has {} generators",
+                states.len(),
+                self.generators.len()
+            );
+        }
+
+        for (generator, state) in self.generators.iter_mut().zip(states) {
+            if let Some(state) = state {
+                generator.restore_state(state)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The global threshold a signal misses, if any
+    fn threshold_miss(&self, signal: &TradeSignal) -> Option<StageOutcome> {
+        // A cancel, an unwind or an exit has no edge to clear
+        if signal.is_cancel() || signal.is_unwind() || signal.is_exit() {
+            return None;
+        }
+        if signal.confidence < self.config.min_confidence {
+            return Some(StageOutcome::BelowThreshold {
+                signal_type: signal.signal_type.clone(),
+                threshold: "min_confidence",
+                value: signal.confidence,
+                required: self.config.min_confidence,
+            });
+        }
+        if signal.edge < self.config.min_edge {
+            return Some(StageOutcome::BelowThreshold {
+                signal_type: signal.signal_type.clone(),
+                threshold: "min_edge",
+                value: signal.edge.to_f64().unwrap_or(0.0),
+                required: self.config.min_edge.to_f64().unwrap_or(0.0),
+            });
+        }
+        None
+    }
+
+    /// Name, raw score and result of each validator, up to the first that
+    /// rejects the signal
+    async fn validation_results(
+        &self,
+        signal: &TradeSignal,
+    ) -> Result<Vec<(&'static str, Option<f64>, ValidationResult)>> {
+        let mut results = Vec::with_capacity(self.validators.len());
         for validator in &self.validators {
-            if !validator.validate(signal).await? {
-                debug!("Signal rejected by validator");
-                return Ok(false);
+            let result = validator.evaluate(signal).await?;
+            let passed = result.passed;
+            results.push((validator.name(), validator.score(signal), result));
+            if !passed {
+                break;
             }
         }
-        Ok(true)
+        Ok(results)
+    }
+
+    /// Record a market the caller left out before this cycle ran, e.g. one
+    /// the orchestrator's filter dropped; it lands in the next cycle
+    pub fn record_filtered(&mut self, market_id: MarketId, criterion: &'static str) {
+        self.diagnostics
+            .record(market_id, self.cycle + 1, StageOutcome::Filtered { criterion });
+    }
+
+    /// Record what happened downstream to a signal from the last cycle,
+    /// e.g. `Deferred` or `Executed`
+    pub fn record_outcome(&mut self, market_id: MarketId, outcome: StageOutcome) {
+        self.diagnostics.record(market_id, self.cycle, outcome);
+    }
+
+    /// The last `cycles` cycles of diagnostics for a market, oldest first
+    pub fn diagnostics(&self, market_id: MarketId, cycles: usize) -> Vec<CycleDiagnostics> {
+        self.diagnostics.recent(market_id, cycles)
+    }
+
+    /// Current volatility of every market the estimator has scored
+    pub fn volatility_scores(&self) -> HashMap<MarketId, VolatilityScore> {
+        self.volatility.as_ref().map(|v| v.scores()).unwrap_or_default()
+    }
+
+    pub fn diagnostics_log(&self) -> &DiagnosticsLog {
+        &self.diagnostics
+    }
+
+    /// Generated, validated and rejected counts per signal type since the
+    /// pipeline was built or last reset
+    pub fn stats(&self) -> &PipelineStats {
+        &self.stats
+    }
+
+    /// Start the counts over, e.g. at the top of each reporting interval
+    pub fn reset_stats(&mut self) {
+        self.stats = PipelineStats::default();
     }
 
     /// Get the number of registered generators
     pub fn generator_count(&self) -> usize {
-        self.generators.len()
+        self.generators.len() + self.batch_generators.len()
     }
 
     /// Get the number of registered validators
@@ -159,9 +692,90 @@ impl SignalPipeline {
     }
 }
 
+/// Risk manager a cycle's signals are checked against, with the inputs they
+/// came from
+#[derive(Clone, Copy)]
+struct RiskGate<'a> {
+    manager: &'a PortfolioRiskManager,
+    inputs: &'a [SignalInput],
+}
+
+impl RiskGate<'_> {
+    /// Outcome a signal buys: its own for a long, the market's second (NO)
+    /// outcome for a short. None for a neutral signal
+    fn outcome_bought(&self, signal: &TradeSignal) -> Option<String> {
+        let outcomes = self
+            .inputs
+            .iter()
+            .find(|input| input.market.id == signal.market_id)
+            .map(|input| input.market.outcomes.as_slice())
+            .unwrap_or_default();
+        match signal.direction {
+            SignalDirection::Long => signal
+                .outcome_id
+                .clone()
+                .or_else(|| outcomes.first().map(|o| o.id.clone()))
+                .map(|id| id.to_string())
+                .or_else(|| Some("YES".to_string())),
+            SignalDirection::Short => {
+                Some(outcomes.get(1).map(|o| o.id.to_string()).unwrap_or_else(|| "NO".to_string()))
+            }
+            SignalDirection::Neutral => None,
+        }
+    }
+}
+
+/// Record each validator's result under `custom_fields.validation`, keyed by
+/// validator name, so outcomes can later be compared against PnL
+fn attach_validation(signal: &mut TradeSignal, results: Vec<(&'static str, Option<f64>, ValidationResult)>) {
+    if results.is_empty() {
+        return;
+    }
+    if !signal.metadata.custom_fields.is_object() {
+        signal.metadata.custom_fields = serde_json::json!({});
+    }
+    let validation: serde_json::Map<String, serde_json::Value> = results
+        .into_iter()
+        .map(|(name, _, result)| (name.to_string(), serde_json::to_value(result).unwrap_or_default()))
+        .collect();
+    signal.metadata.custom_fields["validation"] = serde_json::Value::Object(validation);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::diagnostics::SkipReason;
+    use crate::correlation::{CorrelationEdge, CorrelationGenerator, CorrelationType};
+    use crate::market_making::{MarketMakingConfig, MarketMakingGenerator};
+    use crate::volatility::VolatilityEstimatorConfig;
+    use crate::validators::StalenessValidator;
+    use crate::signals::{
+        PairSide, PriceSnapshot, SignalDirection, SignalType, SingleSignalAdapter, SingleSignalGenerator,
+    };
+    use crate::test_support::input;
+    use chrono::Utc;
+    use common::OrderSide;
+
+    /// Old-style generator that never emits
+    struct SilentGenerator;
+
+    impl SingleSignalGenerator for SilentGenerator {
+        fn generate(&self, _input: &SignalInput) -> Result<Option<TradeSignal>> {
+            Ok(None)
+        }
+
+        fn signal_type(&self) -> SignalType {
+            SignalType::Value
+        }
+    }
+
+    fn create_input(market_id: MarketId) -> SignalInput {
+        input(market_id).quote("0.48", "0.52").build()
+    }
+
+    fn book_input(market_id: MarketId, bid: &str, ask: &str) -> SignalInput {
+        input(market_id).quote(bid, ask).build()
+    }
 
     #[test]
     fn test_default_config() {
@@ -169,4 +783,574 @@ mod tests {
         assert!(config.enabled);
         assert_eq!(config.max_signals_per_cycle, 10);
     }
+
+    #[tokio::test]
+    async fn test_stateful_and_adapted_generators() {
+        // Let every generated quote through the global filters
+        let config = PipelineConfig {
+            min_confidence: 0.0,
+            min_edge: Decimal::MIN,
+            ..PipelineConfig::default()
+        };
+        let mut pipeline = SignalPipeline::new(config)
+            .add_generator(Box::new(MarketMakingGenerator::new(MarketMakingConfig::default())))
+            .add_generator(Box::new(SingleSignalAdapter(SilentGenerator)));
+        assert_eq!(pipeline.generator_count(), 2);
+
+        let market_id = MarketId::random();
+        let signals = pipeline.process(&create_input(market_id)).await.unwrap();
+
+        // Balanced inventory: quote both sides
+        assert_eq!(signals.len(), 2);
+        assert!(signals.iter().all(|s| s.market_id == market_id));
+        let yes_quote = signals.iter().find(|s| s.direction == SignalDirection::Long).unwrap().quote_id();
+
+        // Filled heavily on YES; the generator should stop adding YES
+        pipeline.update_state(
+            market_id,
+            &StateUpdate::TradeExecution {
+                signal_id: None,
+                outcome_id: Some("yes".to_string()),
+                outcome_side: PairSide::Yes,
+                side: OrderSide::Buy,
+                size: Decimal::from(500),
+                price: Decimal::from_str_exact("0.49").unwrap(),
+            },
+        );
+
+        // and pull the YES quote it left resting, then unwind the YES it holds
+        let signals = pipeline.process(&create_input(market_id)).await.unwrap();
+        assert_eq!(signals.len(), 3);
+        assert!(signals[0].is_cancel());
+        assert_eq!(signals[0].quote_id(), yes_quote);
+        assert!(signals[1].is_unwind());
+        assert!(signals[1..].iter().all(|s| s.direction == SignalDirection::Short));
+    }
+
+    #[tokio::test]
+    async fn test_generator_states_survive_restart() {
+        let config = PipelineConfig {
+            min_confidence: 0.0,
+            min_edge: Decimal::MIN,
+            ..PipelineConfig::default()
+        };
+        let build = || {
+            SignalPipeline::new(config.clone())
+                .add_generator(Box::new(MarketMakingGenerator::new(MarketMakingConfig::default())))
+                .add_generator(Box::new(SingleSignalAdapter(SilentGenerator)))
+        };
+
+        let market_id = MarketId::random();
+        let mut pipeline = build();
+        pipeline.update_state(
+            market_id,
+            &StateUpdate::TradeExecution {
+                signal_id: None,
+                outcome_id: Some("yes".to_string()),
+                outcome_side: PairSide::Yes,
+                side: OrderSide::Buy,
+                size: Decimal::from(500),
+                price: Decimal::from_str_exact("0.49").unwrap(),
+            },
+        );
+
+        // Round-trip through JSON as a shutdown would
+        let states = pipeline.snapshot_generator_states();
+        assert!(states[0].is_some());
+        assert!(states[1].is_none());
+        let states = serde_json::from_str(&serde_json::to_string(&states).unwrap()).unwrap();
+
+        let mut restarted = build();
+        restarted.restore_generator_states(states).unwrap();
+        let signals = restarted.process(&create_input(market_id)).await.unwrap();
+        assert_eq!(signals.len(), 2);
+        assert!(signals.iter().all(|s| s.direction == SignalDirection::Short));
+        // Unwinding the restored inventory
+        assert!(signals[0].is_unwind());
+        assert_eq!(signals[0].position_size, Decimal::from(500));
+
+        assert!(build().restore_generator_states(vec![None]).is_err());
+    }
+
+    /// Validator that only lets long signals through
+    struct NoShorts;
+
+    #[async_trait::async_trait]
+    impl SignalValidator for NoShorts {
+        async fn validate(&self, signal: &TradeSignal) -> Result<bool> {
+            Ok(signal.direction != SignalDirection::Short)
+        }
+
+        fn name(&self) -> &'static str {
+            "no_shorts"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_signals_on_stale_books_are_rejected() {
+        let config = PipelineConfig {
+            min_confidence: 0.0,
+            min_edge: Decimal::MIN,
+            ..PipelineConfig::default()
+        };
+        let mut pipeline = SignalPipeline::new(config)
+            .add_generator(Box::new(MarketMakingGenerator::new(MarketMakingConfig::default())))
+            .add_validator(Box::new(StalenessValidator::default()));
+
+        let signals = pipeline.process(&create_input(MarketId::random())).await.unwrap();
+        assert!(!signals.is_empty());
+        assert!(signals.iter().all(|s| s.metadata.orderbook_age_ms.is_some_and(|age| age < 30_000)));
+
+        let market = MarketId::random();
+        let mut stale = create_input(market);
+        stale.order_book.as_mut().unwrap().timestamp = Utc::now() - chrono::Duration::minutes(30);
+        assert!(pipeline.process(&stale).await.unwrap().is_empty());
+        let cycle = pipeline.diagnostics(market, 1).pop().unwrap();
+        assert!(cycle.outcomes.iter().any(|o| matches!(o, StageOutcome::Rejected { validator: "staleness", .. })));
+    }
+
+    #[tokio::test]
+    async fn test_diagnostics_explain_each_cycle() {
+        let config = PipelineConfig {
+            min_confidence: 0.0,
+            min_edge: Decimal::MIN,
+            max_signals_per_cycle: 1,
+            ..PipelineConfig::default()
+        };
+        let mut pipeline = SignalPipeline::new(config)
+            .add_generator(Box::new(MarketMakingGenerator::new(MarketMakingConfig::default())))
+            .add_generator(Box::new(SingleSignalAdapter(SilentGenerator)))
+            .add_validator(Box::new(NoShorts));
+
+        let market = MarketId::random();
+        let other = MarketId::random();
+
+        // Cycle 1: the orchestrator filters the market out, another runs
+        pipeline.record_filtered(market, "min_volume");
+        pipeline.process(&create_input(other)).await.unwrap();
+
+        // Cycle 2: no order book
+        let mut no_book = create_input(market);
+        no_book.order_book = None;
+        assert!(pipeline.process(&no_book).await.unwrap().is_empty());
+
+        // Cycle 3: the book has no asks
+        let mut one_sided = create_input(market);
+        one_sided.order_book.as_mut().unwrap().asks.clear();
+        assert!(pipeline.process(&one_sided).await.unwrap().is_empty());
+
+        // Cycle 4: the short quote is rejected, the long one executes
+        assert_eq!(pipeline.process(&create_input(market)).await.unwrap().len(), 1);
+        pipeline.record_outcome(market, StageOutcome::Executed { signal_type: SignalType::MeanReversion });
+
+        // Cycle 5: both markets quote long, only one fits in the cycle
+        let signals = pipeline
+            .process_batch(&[create_input(market), create_input(other)])
+            .await
+            .unwrap();
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].metadata.custom_fields["validation"]["no_shorts"]["passed"], true);
+
+        let cycles = pipeline.diagnostics(market, 20);
+        assert_eq!(cycles.iter().map(|c| c.cycle).collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+        assert_eq!(cycles[0].outcomes, vec![StageOutcome::Filtered { criterion: "min_volume" }]);
+        assert!(cycles[1].outcomes.contains(&StageOutcome::Skipped {
+            generator: SignalType::MeanReversion,
+            reason: SkipReason::MissingData { data: "order_book" },
+        }));
+        assert!(cycles[1].outcomes.contains(&StageOutcome::NoSignal { generator: SignalType::Value }));
+        assert!(cycles[2].outcomes.contains(&StageOutcome::Skipped {
+            generator: SignalType::MeanReversion,
+            reason: SkipReason::DegradedData { detail: "one_sided_book" },
+        }));
+        assert!(!cycles[2].emitted());
+        assert!(cycles[3].outcomes.iter().any(|o| matches!(o, StageOutcome::Rejected { validator: "no_shorts", .. })));
+        assert!(cycles[3].outcomes.contains(&StageOutcome::Executed { signal_type: SignalType::MeanReversion }));
+        assert!(cycles[3].emitted());
+
+        // Exactly one of the two markets lost out to the per-cycle limit
+        let throttled = |c: &CycleDiagnostics| {
+            c.outcomes.iter().any(|o| matches!(o, StageOutcome::Throttled { limit: "max_signals_per_cycle", .. }))
+        };
+        let other_last = pipeline.diagnostics(other, 1).pop().unwrap();
+        assert_eq!(other_last.cycle, 5);
+        assert!(throttled(&cycles[4]) != throttled(&other_last));
+        assert!(cycles[4].emitted() != other_last.emitted());
+
+        assert_eq!(pipeline.diagnostics(market, 2).len(), 2);
+        let json = serde_json::to_value(&cycles[1]).unwrap();
+        assert_eq!(json["outcomes"][0]["stage"], "skipped");
+        assert_eq!(json["outcomes"][0]["reason"]["reason"], "missing_data");
+    }
+
+    #[test]
+    fn test_build_input_pulls_research() {
+        let aggregator = Arc::new(ResearchAggregator::new(Default::default()));
+        let pipeline = SignalPipeline::new(PipelineConfig::default()).with_research(aggregator.clone());
+        let market = create_input(MarketId::random()).market;
+
+        let input = pipeline.build_input(market.clone(), None, vec![]);
+        assert_eq!(input.research_output.confidence, 0.0);
+        assert!(input.research_output.probability_estimate.is_none());
+
+        aggregator
+            .ingest(common::AgentOutput {
+                agent_id: "sentiment".to_string(),
+                market_id: market.id,
+                signal_type: "sentiment".to_string(),
+                data: serde_json::json!({"sentiment": {"score": 0.5}, "top_themes": ["polls"]}),
+                confidence: 0.9,
+                timestamp: Utc::now(),
+                processing_time_ms: 1,
+            })
+            .unwrap();
+        let input = pipeline.build_input(market, None, vec![]);
+        assert_eq!(input.research_output.confidence, 0.9);
+        assert_eq!(input.research_output.key_factors, vec!["polls"]);
+        assert!((input.research_output.probability_estimate.unwrap() - 0.55).abs() < 1e-12);
+    }
+
+    #[tokio::test]
+    async fn test_correlation_batch_through_pipeline() {
+        let market_a = MarketId::random();
+        let market_b = MarketId::random();
+
+        let mut correlation = CorrelationGenerator::new();
+        correlation.add_correlation(CorrelationEdge {
+            from_market: market_a,
+            to_market: market_b,
+            correlation_type: CorrelationType::Implies,
+            min_spread: Decimal::from_str_exact("0.03").unwrap(),
+        });
+
+        let mut pipeline = SignalPipeline::new(PipelineConfig::default())
+            .add_batch_generator(Box::new(correlation));
+
+        // A implies B, but A trades at 0.40 and B at 0.30
+        let inputs = vec![
+            book_input(market_a, "0.39", "0.41"),
+            book_input(market_b, "0.29", "0.31"),
+        ];
+
+        // Per-market processing never sees both prices
+        assert!(pipeline.process(&inputs[0]).await.unwrap().is_empty());
+
+        let signals = pipeline.process_batch(&inputs).await.unwrap();
+        assert_eq!(signals.len(), 2);
+
+        let long = signals.iter().find(|s| s.direction == SignalDirection::Long).unwrap();
+        let short = signals.iter().find(|s| s.direction == SignalDirection::Short).unwrap();
+        assert_eq!(long.market_id, market_b);
+        assert_eq!(long.entry_price, Decimal::from_str_exact("0.30").unwrap());
+        assert_eq!(short.market_id, market_a);
+        assert_eq!(short.entry_price, Decimal::from_str_exact("0.40").unwrap());
+
+        // Same violation within the cooldown is not signalled again
+        assert!(pipeline.process_batch(&inputs).await.unwrap().is_empty());
+        let last = pipeline.diagnostics(market_a, 1).pop().unwrap();
+        assert_eq!(
+            last.outcomes,
+            vec![StageOutcome::Skipped { generator: SignalType::SpreadArbitrage, reason: SkipReason::Cooldown }]
+        );
+    }
+
+    /// Emits one signal per input with fixed numbers
+    struct FixedGenerator {
+        signal_type: SignalType,
+        direction: SignalDirection,
+        outcome_id: &'static str,
+        edge: &'static str,
+        expected_value: i64,
+        confidence: f64,
+    }
+
+    impl SingleSignalGenerator for FixedGenerator {
+        fn generate(&self, input: &SignalInput) -> Result<Option<TradeSignal>> {
+            Ok(Some(TradeSignal {
+                id: uuid::Uuid::new_v4(),
+                market_id: input.market.id,
+                signal_type: self.signal_type.clone(),
+                direction: self.direction.clone(),
+                outcome_id: Some(self.outcome_id.into()),
+                entry_price: Decimal::from_str_exact("0.50").unwrap(),
+                target_price: Decimal::from_str_exact("0.60").unwrap(),
+                stop_loss: Decimal::from_str_exact("0.40").unwrap(),
+                position_size: Decimal::from(100),
+                confidence: self.confidence,
+                expected_value: Decimal::from(self.expected_value),
+                edge: Decimal::from_str_exact(self.edge).unwrap(),
+                kelly_fraction: 0.1,
+                reasoning: String::new(),
+                metadata: crate::signals::SignalMetadata {
+                    research_sources: vec![],
+                    data_points: 0,
+                    liquidity_score: 0.5,
+                    volatility_score: 0.0,
+                    price_age_ms: None,
+                    orderbook_age_ms: None,
+                    research_age_ms: None,
+                    custom_fields: serde_json::json!({}),
+                },
+                created_at: Utc::now(),
+                expires_at: None,
+                variant: None,
+            }))
+        }
+
+        fn signal_type(&self) -> SignalType {
+            self.signal_type.clone()
+        }
+    }
+
+    fn fixed(
+        signal_type: SignalType,
+        direction: SignalDirection,
+        outcome_id: &'static str,
+        edge: &'static str,
+        expected_value: i64,
+        confidence: f64,
+    ) -> Box<SingleSignalAdapter<FixedGenerator>> {
+        Box::new(SingleSignalAdapter(FixedGenerator {
+            signal_type,
+            direction,
+            outcome_id,
+            edge,
+            expected_value,
+            confidence,
+        }))
+    }
+
+    fn permissive_config() -> PipelineConfig {
+        PipelineConfig {
+            min_confidence: 0.0,
+            min_edge: Decimal::MIN,
+            ..PipelineConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_contradictory_signals_are_dropped() {
+        use SignalDirection::{Long, Short};
+
+        // EV 3 against 2 is under the 2x ratio: neither side is trusted
+        let mut pipeline = SignalPipeline::new(permissive_config())
+            .add_generator(fixed(SignalType::Momentum, Long, "yes", "0.05", 3, 0.8))
+            .add_generator(fixed(SignalType::MeanReversion, Short, "yes", "0.05", 2, 0.8));
+        let market = MarketId::random();
+        assert!(pipeline.process(&create_input(market)).await.unwrap().is_empty());
+        let cycle = pipeline.diagnostics(market, 1).pop().unwrap();
+        let contradicted = cycle
+            .outcomes
+            .iter()
+            .filter(|o| matches!(o, StageOutcome::Deduplicated { reason: "contradicted", .. }))
+            .count();
+        assert_eq!(contradicted, 2);
+
+        // EV 5 against 2 wins outright
+        let mut pipeline = SignalPipeline::new(permissive_config())
+            .add_generator(fixed(SignalType::Momentum, Long, "yes", "0.05", 5, 0.8))
+            .add_generator(fixed(SignalType::MeanReversion, Short, "yes", "0.05", 2, 0.8));
+        let signals = pipeline.process(&create_input(market)).await.unwrap();
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].signal_type, SignalType::Momentum);
+
+        // Opposite sides of different outcomes do not contradict
+        let mut pipeline = SignalPipeline::new(permissive_config())
+            .add_generator(fixed(SignalType::Momentum, Long, "yes", "0.05", 3, 0.8))
+            .add_generator(fixed(SignalType::MeanReversion, Short, "no", "0.05", 2, 0.8));
+        assert_eq!(pipeline.process(&create_input(market)).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_expiry_overrides_replace_generator_expiry() {
+        use SignalDirection::Long;
+
+        let config = PipelineConfig {
+            expiry_overrides: HashMap::from([(SignalType::SpreadArbitrage, Duration::minutes(1))]),
+            ..permissive_config()
+        };
+        let mut pipeline = SignalPipeline::new(config)
+            .add_generator(fixed(SignalType::SpreadArbitrage, Long, "yes", "0.05", 3, 0.8))
+            .add_generator(fixed(SignalType::Momentum, Long, "no", "0.05", 3, 0.8));
+        let signals = pipeline.process(&create_input(MarketId::random())).await.unwrap();
+        assert_eq!(signals.len(), 2);
+
+        let spread = signals.iter().find(|s| s.signal_type == SignalType::SpreadArbitrage).unwrap();
+        assert_eq!(spread.expires_at, Some(spread.created_at + Duration::minutes(1)));
+        // Types without an override keep what their generator set
+        let momentum = signals.iter().find(|s| s.signal_type == SignalType::Momentum).unwrap();
+        assert_eq!(momentum.expires_at, None);
+    }
+
+    #[tokio::test]
+    async fn test_stats_reconcile_across_generators_and_validators() {
+        use SignalDirection::{Long, Short};
+
+        let config = PipelineConfig {
+            min_confidence: 0.5,
+            min_edge: Decimal::MIN,
+            ..PipelineConfig::default()
+        };
+        let mut pipeline = SignalPipeline::new(config)
+            .add_generator(fixed(SignalType::Value, Long, "yes", "0.05", 5, 0.9))
+            .add_generator(fixed(SignalType::Momentum, Short, "no", "0.05", 5, 0.9))
+            .add_generator(fixed(SignalType::Sentiment, Long, "no", "0.05", 5, 0.3))
+            .add_validator(Box::new(NoShorts));
+
+        let inputs: Vec<_> = (0..3).map(|_| create_input(MarketId::random())).collect();
+        pipeline.process_batch(&inputs).await.unwrap();
+        pipeline.process(&inputs[0]).await.unwrap();
+
+        let stats = pipeline.stats();
+        let value = &stats.by_type[&SignalType::Value];
+        assert_eq!((value.generated, value.validated, value.rejected), (4, 4, 0));
+        let momentum = &stats.by_type[&SignalType::Momentum];
+        assert_eq!((momentum.generated, momentum.validated, momentum.rejected), (4, 0, 4));
+        assert_eq!(momentum.rejection_reasons["no_shorts"], 4);
+        let sentiment = &stats.by_type[&SignalType::Sentiment];
+        assert_eq!(sentiment.rejection_reasons["min_confidence"], 4);
+        assert_eq!(sentiment.pass_rate(), 0.0);
+
+        for stats in stats.by_type.values() {
+            assert_eq!(stats.generated, stats.validated + stats.rejected);
+            assert_eq!(stats.rejected, stats.rejection_reasons.values().sum::<u64>());
+        }
+        let totals = stats.totals();
+        assert_eq!((totals.generated, totals.validated, totals.rejected), (12, 4, 8));
+
+        pipeline.reset_stats();
+        assert!(pipeline.stats().by_type.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_risk_check_rejects_and_clamps_signals() {
+        use portfolio_risk::{RiskConfig, RiskLimits};
+        use SignalDirection::Long;
+
+        let manager = |max_position_size: f64| {
+            PortfolioRiskManager::with_config(RiskConfig {
+                risk_limits: RiskLimits {
+                    max_position_size,
+                    max_total_exposure: 1000.0,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        let mut pipeline = SignalPipeline::new(permissive_config())
+            .add_generator(fixed(SignalType::Value, Long, "yes", "0.10", 3, 0.8));
+        let market = MarketId::random();
+
+        // 100 shares at 0.50 is over a 40 position limit
+        let signals = pipeline.process_with_risk(&create_input(market), &manager(40.0)).await.unwrap();
+        assert!(signals.is_empty());
+        let cycle = pipeline.diagnostics(market, 1).pop().unwrap();
+        assert!(cycle
+            .outcomes
+            .iter()
+            .any(|o| matches!(o, StageOutcome::RiskRejected { signal_type: SignalType::Value, .. })));
+
+        // Within the limit, quarter Kelly at a 5 point edge allows 25 of the
+        // 1000 bankroll: 50 shares
+        let signals = pipeline.process_with_risk(&create_input(market), &manager(100.0)).await.unwrap();
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].position_size, Decimal::from(50));
+    }
+
+    #[tokio::test]
+    async fn test_compatible_signals_are_deduplicated_and_ranked() {
+        use SignalDirection::Long;
+
+        let build = |config: PipelineConfig| {
+            SignalPipeline::new(config)
+                .add_generator(fixed(SignalType::Momentum, Long, "yes", "0.05", 2, 0.9))
+                // Same type and side as the one above, ranked lower
+                .add_generator(fixed(SignalType::Momentum, Long, "yes", "0.04", 9, 0.9))
+                .add_generator(fixed(SignalType::SpreadArbitrage, Long, "yes", "0.10", 1, 0.7))
+                .add_generator(fixed(SignalType::Value, Long, "no", "0.04", 4, 0.8))
+        };
+
+        // edge * confidence: 0.07, 0.045, 0.032
+        let market = MarketId::random();
+        let mut pipeline = build(permissive_config());
+        let signals = pipeline.process(&create_input(market)).await.unwrap();
+        let ranked: Vec<_> = signals.iter().map(|s| (s.signal_type.clone(), s.edge)).collect();
+        assert_eq!(
+            ranked,
+            vec![
+                (SignalType::SpreadArbitrage, Decimal::from_str_exact("0.10").unwrap()),
+                (SignalType::Momentum, Decimal::from_str_exact("0.05").unwrap()),
+                (SignalType::Value, Decimal::from_str_exact("0.04").unwrap()),
+            ]
+        );
+        let cycle = pipeline.diagnostics(market, 1).pop().unwrap();
+        assert!(cycle.outcomes.contains(&StageOutcome::Deduplicated {
+            signal_type: SignalType::Momentum,
+            reason: "duplicate",
+        }));
+
+        // Weighting expected value instead keeps the EV 9 momentum signal
+        let mut pipeline = build(PipelineConfig {
+            ranking: RankingWeights { edge: 0.0, expected_value: 1.0 },
+            ..permissive_config()
+        });
+        let signals = pipeline.process(&create_input(market)).await.unwrap();
+        let ranked: Vec<_> = signals.iter().map(|s| s.expected_value).collect();
+        assert_eq!(ranked, vec![Decimal::from(9), Decimal::from(4), Decimal::from(1)]);
+    }
+
+    #[tokio::test]
+    async fn test_volatility_estimate_widens_then_relaxes_market_making_spread() {
+        let estimator = VolatilityEstimator::new(VolatilityEstimatorConfig {
+            half_life: 5.0,
+            ..VolatilityEstimatorConfig::default()
+        });
+        // Never pull the quotes, so the widened spread shows
+        let market_making = MarketMakingConfig {
+            cancel_volatility: 1.0,
+            ..MarketMakingConfig::default()
+        };
+        let mut pipeline = SignalPipeline::new(permissive_config())
+            .add_generator(Box::new(MarketMakingGenerator::new(market_making)))
+            .with_volatility_estimator(estimator);
+
+        let market_id = MarketId::random();
+        let start = Utc::now() - chrono::Duration::hours(1);
+        let snapshot = |i: i64, price: &str| PriceSnapshot {
+            outcome_id: "yes".to_string(),
+            price: Decimal::from_str_exact(price).unwrap(),
+            volume: Decimal::from(100),
+            liquidity: Decimal::from(5000),
+            timestamp: start + chrono::Duration::minutes(i),
+        };
+        let spread = |signals: &[TradeSignal]| -> Decimal {
+            signals[0].metadata.custom_fields["spread"].as_str().unwrap().parse().unwrap()
+        };
+
+        // Ticking a tenth of a cent: the base 2% spread
+        let mut input = create_input(market_id);
+        input.price_history = (0..20).map(|i| snapshot(i, if i % 2 == 0 { "0.500" } else { "0.501" })).collect();
+        let calm = pipeline.process(&input).await.unwrap();
+        let calm_score = pipeline.volatility_scores()[&market_id];
+        assert!(calm_score.score < 0.1, "calm {:?}", calm_score);
+        assert_eq!(spread(&calm), Decimal::from_str_exact("0.02").unwrap());
+
+        // Swinging three cents: widened by the volatility multiplier
+        input.price_history.extend((20..30).map(|i| snapshot(i, if i % 2 == 0 { "0.50" } else { "0.53" })));
+        let jumpy = pipeline.process(&input).await.unwrap();
+        let jumpy_score = pipeline.volatility_scores()[&market_id];
+        assert!(jumpy_score.score > 0.7, "jumpy {:?}", jumpy_score);
+        assert!(jumpy_score.raw_sigma > calm_score.raw_sigma);
+        assert_eq!(spread(&jumpy), Decimal::from_str_exact("0.03").unwrap());
+        assert_eq!(jumpy[0].metadata.volatility_score, jumpy_score.score);
+
+        // Calm again: four half-lives on, the burst has faded and the spread relaxes
+        input.price_history.extend((30..50).map(|i| snapshot(i, if i % 2 == 0 { "0.500" } else { "0.501" })));
+        let relaxed = pipeline.process(&input).await.unwrap();
+        let relaxed_score = pipeline.volatility_scores()[&market_id];
+        assert!(relaxed_score.score < 0.7, "relaxed {:?}", relaxed_score);
+        assert!(relaxed_score.raw_sigma < jumpy_score.raw_sigma);
+        assert_eq!(spread(&relaxed), Decimal::from_str_exact("0.02").unwrap());
+    }
 }
@@ -0,0 +1,731 @@
+// Postgres Signal Storage
+// Durable SignalStorage and ExecutionStorage backends for backtesting and attribution
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use common::MarketId;
+use rust_decimal::Decimal;
+use serde::{de::DeserializeOwned, Serialize};
+use sqlx::postgres::{PgPool, Postgres};
+use sqlx::{FromRow, QueryBuilder};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use super::signals::{SignalMetadata, TradeSignal};
+use super::storage::{BacktestStats, ExecutionStorage, SignalExecutionResult, SignalStorage, StorageStats};
+
+/// Rows per INSERT; keeps a batch well under Postgres' 65535 bind parameters
+const BATCH_ROWS: usize = 1000;
+
+const SIGNAL_COLUMNS: &[&str] = &[
+    "id",
+    "market_id",
+    "signal_type",
+    "direction",
+    "outcome_id",
+    "entry_price",
+    "target_price",
+    "stop_loss",
+    "position_size",
+    "confidence",
+    "expected_value",
+    "edge",
+    "kelly_fraction",
+    "reasoning",
+    "metadata",
+    "created_at",
+    "expires_at",
+    "variant",
+];
+
+const EXECUTION_COLUMNS: &[&str] = &[
+    "signal_id",
+    "market_id",
+    "outcome_id",
+    "executed_at",
+    "entry_price",
+    "exit_price",
+    "position_size",
+    "pnl",
+    "pnl_percentage",
+    "holding_period_hours",
+    "exit_reason",
+    "rebate_pnl",
+    "signal_type",
+];
+
+// Decimals are bound and read as text so no precision is lost to f64
+const SELECT_SIGNAL: &str = r#"
+    SELECT id, market_id, signal_type, direction, outcome_id,
+        entry_price::TEXT AS entry_price, target_price::TEXT AS target_price,
+        stop_loss::TEXT AS stop_loss, position_size::TEXT AS position_size,
+        confidence, expected_value::TEXT AS expected_value, edge::TEXT AS edge,
+        kelly_fraction, reasoning, metadata, created_at, expires_at, variant
+    FROM trade_signals
+"#;
+
+const SELECT_EXECUTION: &str = r#"
+    SELECT signal_id, market_id, outcome_id, executed_at,
+        entry_price::TEXT AS entry_price, exit_price::TEXT AS exit_price,
+        position_size::TEXT AS position_size, pnl::TEXT AS pnl,
+        pnl_percentage::TEXT AS pnl_percentage, holding_period_hours, exit_reason,
+        rebate_pnl::TEXT AS rebate_pnl, signal_type
+    FROM signal_execution_results
+"#;
+
+/// Signal storage backed by Postgres
+///
+/// Prices and sizes are NUMERIC columns, metadata and variant JSONB.
+pub struct PostgresSignalStorage {
+    db_pool: Arc<PgPool>,
+}
+
+impl PostgresSignalStorage {
+    /// Connect the storage, creating its table if it doesn't exist
+    pub async fn new(db_pool: Arc<PgPool>) -> Result<Self> {
+        let storage = Self { db_pool };
+        storage.initialize().await?;
+        Ok(storage)
+    }
+
+    /// Create the signal table; safe to run repeatedly
+    pub async fn initialize(&self) -> Result<()> {
+        sqlx::raw_sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS trade_signals (
+                id UUID PRIMARY KEY,
+                market_id UUID NOT NULL,
+                signal_type TEXT NOT NULL,
+                direction TEXT NOT NULL,
+                outcome_id TEXT,
+                entry_price NUMERIC NOT NULL,
+                target_price NUMERIC NOT NULL,
+                stop_loss NUMERIC NOT NULL,
+                position_size NUMERIC NOT NULL,
+                confidence DOUBLE PRECISION NOT NULL,
+                expected_value NUMERIC NOT NULL,
+                edge NUMERIC NOT NULL,
+                kelly_fraction DOUBLE PRECISION NOT NULL,
+                reasoning TEXT NOT NULL,
+                metadata JSONB NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL,
+                expires_at TIMESTAMPTZ,
+                variant JSONB
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_trade_signals_market_id ON trade_signals(market_id);
+            CREATE INDEX IF NOT EXISTS idx_trade_signals_signal_type ON trade_signals(signal_type);
+            CREATE INDEX IF NOT EXISTS idx_trade_signals_created_at ON trade_signals(created_at);
+            "#,
+        )
+        .execute(self.db_pool.as_ref())
+        .await
+        .context("Failed to create signal table")?;
+
+        info!("Signal storage table initialized");
+        Ok(())
+    }
+
+    async fn query_signals(&self, filter: &str, bind: impl FnOnce(&mut QueryBuilder<'_, Postgres>)) -> Result<Vec<TradeSignal>> {
+        let mut query = QueryBuilder::new(SELECT_SIGNAL);
+        query.push(filter);
+        bind(&mut query);
+        query.push(" ORDER BY created_at");
+
+        let rows: Vec<SignalRow> = query
+            .build_query_as()
+            .fetch_all(self.db_pool.as_ref())
+            .await
+            .context("Failed to fetch signals")?;
+        rows.into_iter().map(TradeSignal::try_from).collect()
+    }
+}
+
+/// Execution result storage backed by Postgres
+pub struct PostgresExecutionStorage {
+    db_pool: Arc<PgPool>,
+}
+
+impl PostgresExecutionStorage {
+    /// Connect the storage, creating its table if it doesn't exist
+    pub async fn new(db_pool: Arc<PgPool>) -> Result<Self> {
+        let storage = Self { db_pool };
+        storage.initialize().await?;
+        Ok(storage)
+    }
+
+    /// Create the execution results table; safe to run repeatedly
+    pub async fn initialize(&self) -> Result<()> {
+        sqlx::raw_sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS signal_execution_results (
+                signal_id UUID PRIMARY KEY,
+                market_id UUID NOT NULL,
+                outcome_id TEXT,
+                executed_at TIMESTAMPTZ NOT NULL,
+                entry_price NUMERIC NOT NULL,
+                exit_price NUMERIC,
+                position_size NUMERIC NOT NULL,
+                pnl NUMERIC,
+                pnl_percentage NUMERIC,
+                holding_period_hours DOUBLE PRECISION,
+                exit_reason TEXT NOT NULL,
+                rebate_pnl NUMERIC,
+                signal_type TEXT
+            );
+            ALTER TABLE signal_execution_results ADD COLUMN IF NOT EXISTS signal_type TEXT;
+
+            CREATE INDEX IF NOT EXISTS idx_signal_execution_results_market_id ON signal_execution_results(market_id);
+            CREATE INDEX IF NOT EXISTS idx_signal_execution_results_executed_at ON signal_execution_results(executed_at);
+            "#,
+        )
+        .execute(self.db_pool.as_ref())
+        .await
+        .context("Failed to create execution results table")?;
+
+        info!("Execution storage table initialized");
+        Ok(())
+    }
+
+    /// Store several execution results in as few round trips as possible
+    pub async fn store_results(&self, results: &[SignalExecutionResult]) -> Result<()> {
+        for chunk in results.chunks(BATCH_ROWS) {
+            let rows = chunk
+                .iter()
+                .map(|r| Ok((r, enum_name(&r.exit_reason)?)))
+                .collect::<Result<Vec<_>>>()?;
+
+            let mut query = insert_into("signal_execution_results", EXECUTION_COLUMNS);
+            query.push_values(rows, |mut b, (r, exit_reason)| {
+                b.push_bind(r.signal_id)
+                    .push_bind(r.market_id)
+                    .push_bind(r.outcome_id.clone())
+                    .push_bind(r.executed_at)
+                    .push_bind(r.entry_price.to_string())
+                    .push_unseparated("::NUMERIC")
+                    .push_bind(r.exit_price.map(|d| d.to_string()))
+                    .push_unseparated("::NUMERIC")
+                    .push_bind(r.position_size.to_string())
+                    .push_unseparated("::NUMERIC")
+                    .push_bind(r.pnl.map(|d| d.to_string()))
+                    .push_unseparated("::NUMERIC")
+                    .push_bind(r.pnl_percentage.map(|d| d.to_string()))
+                    .push_unseparated("::NUMERIC")
+                    .push_bind(r.holding_period_hours)
+                    .push_bind(exit_reason)
+                    .push_bind(r.rebate_pnl.map(|d| d.to_string()))
+                    .push_unseparated("::NUMERIC")
+                    .push_bind(r.signal_type.as_ref().map(|t| t.as_str()));
+            });
+            push_upsert(&mut query, "signal_id", EXECUTION_COLUMNS);
+
+            query
+                .build()
+                .execute(self.db_pool.as_ref())
+                .await
+                .context("Failed to store execution results")?;
+        }
+
+        debug!("Stored {} execution results", results.len());
+        Ok(())
+    }
+
+    async fn query_results(&self, filter: &str, bind: impl FnOnce(&mut QueryBuilder<'_, Postgres>)) -> Result<Vec<SignalExecutionResult>> {
+        let mut query = QueryBuilder::new(SELECT_EXECUTION);
+        query.push(filter);
+        bind(&mut query);
+        query.push(" ORDER BY executed_at");
+
+        let rows: Vec<ExecutionRow> = query
+            .build_query_as()
+            .fetch_all(self.db_pool.as_ref())
+            .await
+            .context("Failed to fetch execution results")?;
+        rows.into_iter().map(SignalExecutionResult::try_from).collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl SignalStorage for PostgresSignalStorage {
+    async fn store(&self, signal: &TradeSignal) -> Result<()> {
+        self.store_batch(std::slice::from_ref(signal)).await
+    }
+
+    async fn store_batch(&self, signals: &[TradeSignal]) -> Result<()> {
+        for chunk in signals.chunks(BATCH_ROWS) {
+            let rows = chunk
+                .iter()
+                .map(|s| {
+                    let metadata = serde_json::to_value(&s.metadata)?;
+                    let variant = s.variant.as_ref().map(serde_json::to_value).transpose()?;
+                    Ok((s, enum_name(&s.direction)?, metadata, variant))
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            let mut query = insert_into("trade_signals", SIGNAL_COLUMNS);
+            query.push_values(rows, |mut b, (s, direction, metadata, variant)| {
+                b.push_bind(s.id)
+                    .push_bind(s.market_id)
+                    .push_bind(s.signal_type.as_str())
+                    .push_bind(direction)
+                    .push_bind(s.outcome_id.as_ref().map(|o| o.as_str().to_string()))
+                    .push_bind(s.entry_price.to_string())
+                    .push_unseparated("::NUMERIC")
+                    .push_bind(s.target_price.to_string())
+                    .push_unseparated("::NUMERIC")
+                    .push_bind(s.stop_loss.to_string())
+                    .push_unseparated("::NUMERIC")
+                    .push_bind(s.position_size.to_string())
+                    .push_unseparated("::NUMERIC")
+                    .push_bind(s.confidence)
+                    .push_bind(s.expected_value.to_string())
+                    .push_unseparated("::NUMERIC")
+                    .push_bind(s.edge.to_string())
+                    .push_unseparated("::NUMERIC")
+                    .push_bind(s.kelly_fraction)
+                    .push_bind(s.reasoning.clone())
+                    .push_bind(metadata)
+                    .push_bind(s.created_at)
+                    .push_bind(s.expires_at)
+                    .push_bind(variant);
+            });
+            push_upsert(&mut query, "id", SIGNAL_COLUMNS);
+
+            query
+                .build()
+                .execute(self.db_pool.as_ref())
+                .await
+                .context("Failed to store signals")?;
+        }
+
+        debug!("Stored {} signals", signals.len());
+        Ok(())
+    }
+
+    async fn get(&self, signal_id: Uuid) -> Result<Option<TradeSignal>> {
+        let signals = self
+            .query_signals(" WHERE id = ", |q| {
+                q.push_bind(signal_id);
+            })
+            .await?;
+        Ok(signals.into_iter().next())
+    }
+
+    async fn get_by_market(&self, market_id: MarketId) -> Result<Vec<TradeSignal>> {
+        self.query_signals(" WHERE market_id = ", |q| {
+            q.push_bind(market_id);
+        })
+        .await
+    }
+
+    async fn get_by_time_range(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<TradeSignal>> {
+        self.query_signals(" WHERE created_at BETWEEN ", |q| {
+            q.push_bind(start).push(" AND ").push_bind(end);
+        })
+        .await
+    }
+
+    async fn get_by_type(&self, signal_type: &str) -> Result<Vec<TradeSignal>> {
+        let signal_type = signal_type.to_string();
+        self.query_signals(" WHERE signal_type = ", |q| {
+            q.push_bind(signal_type);
+        })
+        .await
+    }
+
+    async fn get_all(&self) -> Result<Vec<TradeSignal>> {
+        self.query_signals("", |_| {}).await
+    }
+
+    async fn delete(&self, signal_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM trade_signals WHERE id = $1")
+            .bind(signal_id)
+            .execute(self.db_pool.as_ref())
+            .await
+            .context("Failed to delete signal")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn stats(&self) -> Result<StorageStats> {
+        let rows = sqlx::query_as::<_, (String, i64, DateTime<Utc>, DateTime<Utc>)>(
+            r#"
+            SELECT signal_type, COUNT(*), MIN(created_at), MAX(created_at)
+            FROM trade_signals
+            GROUP BY signal_type
+            "#,
+        )
+        .fetch_all(self.db_pool.as_ref())
+        .await
+        .context("Failed to fetch signal stats")?;
+
+        let size: i64 = sqlx::query_scalar("SELECT pg_total_relation_size('trade_signals')::BIGINT")
+            .fetch_one(self.db_pool.as_ref())
+            .await
+            .context("Failed to fetch signal table size")?;
+
+        let mut signals_by_type = HashMap::new();
+        let mut oldest_signal: Option<DateTime<Utc>> = None;
+        let mut newest_signal: Option<DateTime<Utc>> = None;
+        for (signal_type, count, oldest, newest) in rows {
+            signals_by_type.insert(signal_type, count as usize);
+            oldest_signal = Some(oldest_signal.map_or(oldest, |o| o.min(oldest)));
+            newest_signal = Some(newest_signal.map_or(newest, |n| n.max(newest)));
+        }
+
+        Ok(StorageStats {
+            total_signals: signals_by_type.values().sum(),
+            signals_by_type,
+            oldest_signal,
+            newest_signal,
+            storage_size_bytes: Some(size as usize),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl ExecutionStorage for PostgresExecutionStorage {
+    async fn store(&self, result: &SignalExecutionResult) -> Result<()> {
+        self.store_results(std::slice::from_ref(result)).await
+    }
+
+    async fn get_by_signal(&self, signal_id: Uuid) -> Result<Option<SignalExecutionResult>> {
+        let results = self
+            .query_results(" WHERE signal_id = ", |q| {
+                q.push_bind(signal_id);
+            })
+            .await?;
+        Ok(results.into_iter().next())
+    }
+
+    async fn get_by_market(&self, market_id: MarketId) -> Result<Vec<SignalExecutionResult>> {
+        self.query_results(" WHERE market_id = ", |q| {
+            q.push_bind(market_id);
+        })
+        .await
+    }
+
+    async fn get_backtest_stats(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<BacktestStats> {
+        let results = self
+            .query_results(" WHERE executed_at BETWEEN ", |q| {
+                q.push_bind(start).push(" AND ").push_bind(end);
+            })
+            .await?;
+        Ok(BacktestStats::from_results(start, end, &results))
+    }
+}
+
+/// `INSERT INTO table (columns) ` ready for `push_values`
+fn insert_into<'a>(table: &str, columns: &[&str]) -> QueryBuilder<'a, Postgres> {
+    QueryBuilder::new(format!("INSERT INTO {} ({}) ", table, columns.join(", ")))
+}
+
+/// Overwrite existing rows, matching the in-memory stores' insert semantics
+fn push_upsert(query: &mut QueryBuilder<'_, Postgres>, key: &str, columns: &[&str]) {
+    let updates: Vec<String> = columns
+        .iter()
+        .filter(|c| **c != key)
+        .map(|c| format!("{c} = EXCLUDED.{c}"))
+        .collect();
+    query.push(format!(" ON CONFLICT ({}) DO UPDATE SET {}", key, updates.join(", ")));
+}
+
+/// Serde name of a unit enum variant, e.g. "TargetHit"
+fn enum_name<T: Serialize>(value: &T) -> Result<String> {
+    match serde_json::to_value(value)? {
+        serde_json::Value::String(name) => Ok(name),
+        other => anyhow::bail!("expected a unit variant, got {}", other),
+    }
+}
+
+fn parse_enum<T: DeserializeOwned>(name: &str) -> Result<T> {
+    serde_json::from_value(serde_json::Value::String(name.to_string())).with_context(|| format!("Unknown variant {}", name))
+}
+
+fn parse_decimal(text: &str) -> Result<Decimal> {
+    Decimal::from_str(text).with_context(|| format!("Invalid decimal {}", text))
+}
+
+fn parse_optional_decimal(text: Option<String>) -> Result<Option<Decimal>> {
+    text.as_deref().map(parse_decimal).transpose()
+}
+
+#[derive(FromRow)]
+struct SignalRow {
+    id: Uuid,
+    market_id: Uuid,
+    signal_type: String,
+    direction: String,
+    outcome_id: Option<String>,
+    entry_price: String,
+    target_price: String,
+    stop_loss: String,
+    position_size: String,
+    confidence: f64,
+    expected_value: String,
+    edge: String,
+    kelly_fraction: f64,
+    reasoning: String,
+    metadata: serde_json::Value,
+    created_at: DateTime<Utc>,
+    expires_at: Option<DateTime<Utc>>,
+    variant: Option<serde_json::Value>,
+}
+
+impl TryFrom<SignalRow> for TradeSignal {
+    type Error = anyhow::Error;
+
+    fn try_from(row: SignalRow) -> Result<Self> {
+        let metadata: SignalMetadata = serde_json::from_value(row.metadata).context("Invalid signal metadata")?;
+        Ok(TradeSignal {
+            id: row.id,
+            market_id: row.market_id.into(),
+            signal_type: row.signal_type.parse().map_err(anyhow::Error::msg)?,
+            direction: parse_enum(&row.direction)?,
+            outcome_id: row.outcome_id.map(Into::into),
+            entry_price: parse_decimal(&row.entry_price)?,
+            target_price: parse_decimal(&row.target_price)?,
+            stop_loss: parse_decimal(&row.stop_loss)?,
+            position_size: parse_decimal(&row.position_size)?,
+            confidence: row.confidence,
+            expected_value: parse_decimal(&row.expected_value)?,
+            edge: parse_decimal(&row.edge)?,
+            kelly_fraction: row.kelly_fraction,
+            reasoning: row.reasoning,
+            metadata,
+            created_at: row.created_at,
+            expires_at: row.expires_at,
+            variant: row.variant.map(serde_json::from_value).transpose().context("Invalid signal variant")?,
+        })
+    }
+}
+
+#[derive(FromRow)]
+struct ExecutionRow {
+    signal_id: Uuid,
+    market_id: Uuid,
+    outcome_id: Option<String>,
+    executed_at: DateTime<Utc>,
+    entry_price: String,
+    exit_price: Option<String>,
+    position_size: String,
+    pnl: Option<String>,
+    pnl_percentage: Option<String>,
+    holding_period_hours: Option<f64>,
+    exit_reason: String,
+    rebate_pnl: Option<String>,
+    signal_type: Option<String>,
+}
+
+impl TryFrom<ExecutionRow> for SignalExecutionResult {
+    type Error = anyhow::Error;
+
+    fn try_from(row: ExecutionRow) -> Result<Self> {
+        Ok(SignalExecutionResult {
+            signal_id: row.signal_id,
+            market_id: row.market_id.into(),
+            outcome_id: row.outcome_id,
+            executed_at: row.executed_at,
+            entry_price: parse_decimal(&row.entry_price)?,
+            exit_price: parse_optional_decimal(row.exit_price)?,
+            position_size: parse_decimal(&row.position_size)?,
+            pnl: parse_optional_decimal(row.pnl)?,
+            pnl_percentage: parse_optional_decimal(row.pnl_percentage)?,
+            holding_period_hours: row.holding_period_hours,
+            exit_reason: parse_enum(&row.exit_reason)?,
+            rebate_pnl: parse_optional_decimal(row.rebate_pnl)?,
+            signal_type: row.signal_type.map(|t| t.parse()).transpose().map_err(anyhow::Error::msg)?,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "postgres-tests"))]
+mod tests {
+    use super::*;
+    use crate::signals::{SignalDirection, SignalType, VariantTag};
+    use crate::storage::{ExitReason, InMemoryExecutionStorage, InMemoryStorage};
+    use chrono::Duration;
+
+    fn signal(i: usize, market_id: MarketId, created_at: DateTime<Utc>) -> TradeSignal {
+        let types = [SignalType::SpreadArbitrage, SignalType::Momentum, SignalType::MeanReversion, SignalType::Value];
+        TradeSignal {
+            id: Uuid::new_v4(),
+            market_id,
+            signal_type: types[i % types.len()].clone(),
+            direction: if i.is_multiple_of(2) { SignalDirection::Long } else { SignalDirection::Short },
+            outcome_id: (!i.is_multiple_of(3)).then(|| "yes".into()),
+            entry_price: Decimal::new(4000 + i as i64, 4),
+            target_price: Decimal::new(55, 2),
+            stop_loss: Decimal::new(35, 2),
+            position_size: Decimal::from(100),
+            confidence: 0.7,
+            expected_value: Decimal::new(1234567, 6),
+            edge: Decimal::new(-5, 3),
+            kelly_fraction: 0.1,
+            reasoning: format!("signal {}", i),
+            metadata: SignalMetadata {
+                research_sources: vec!["news".to_string()],
+                data_points: i as u32,
+                liquidity_score: 0.5,
+                volatility_score: 0.2,
+                price_age_ms: Some(1500),
+                orderbook_age_ms: None,
+                research_age_ms: Some(60_000),
+                custom_fields: serde_json::json!({ "strategy": "test", "i": i }),
+            },
+            created_at,
+            expires_at: i.is_multiple_of(2).then(|| created_at + Duration::hours(1)),
+            variant: i.is_multiple_of(5).then(|| VariantTag {
+                experiment_id: Uuid::new_v4(),
+                variant: "treatment".to_string(),
+                parent_strategy: "test".to_string(),
+                strategy_version_id: "test@v2".to_string(),
+            }),
+        }
+    }
+
+    fn sorted_ids(signals: &[TradeSignal]) -> Vec<Uuid> {
+        let mut ids: Vec<Uuid> = signals.iter().map(|s| s.id).collect();
+        ids.sort();
+        ids
+    }
+
+    /// Pool on a fresh schema of the Postgres at TEST_DATABASE_URL
+    async fn scratch_pool() -> (Arc<PgPool>, String) {
+        use sqlx::postgres::PgPoolOptions;
+
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL not set");
+        // Single connection so the scratch schema stays on the search path
+        let pool = Arc::new(PgPoolOptions::new().max_connections(1).connect(&url).await.unwrap());
+        let schema = format!("signal_storage_{}", Uuid::new_v4().simple());
+        sqlx::raw_sql(&format!("CREATE SCHEMA {schema}; SET search_path TO {schema}"))
+            .execute(pool.as_ref())
+            .await
+            .unwrap();
+        (pool, schema)
+    }
+
+    async fn drop_schema(pool: &PgPool, schema: &str) {
+        sqlx::raw_sql(&format!("DROP SCHEMA {schema} CASCADE")).execute(pool).await.unwrap();
+    }
+
+    /// `TEST_DATABASE_URL=... cargo test -p signal-generation --features postgres-tests`
+    #[tokio::test]
+    async fn test_signal_round_trip_and_stats() {
+        let (pool, schema) = scratch_pool().await;
+        let storage = PostgresSignalStorage::new(pool.clone()).await.unwrap();
+
+        let created_at = DateTime::from_timestamp(Utc::now().timestamp(), 0).unwrap();
+        let market_id = MarketId::random();
+        let first = signal(5, market_id, created_at);
+        let second = signal(1, market_id, created_at + Duration::minutes(5));
+        storage.store(&first).await.unwrap();
+        storage.store(&second).await.unwrap();
+
+        // NUMERIC keeps every digit, JSONB every metadata field
+        let stored = storage.get(first.id).await.unwrap().unwrap();
+        assert_eq!(stored.expected_value, Decimal::new(1234567, 6));
+        assert_eq!(stored.metadata.custom_fields["i"], 5);
+        assert_eq!(serde_json::to_value(&stored).unwrap(), serde_json::to_value(&first).unwrap());
+
+        let stats = storage.stats().await.unwrap();
+        assert_eq!(stats.total_signals, 2);
+        assert_eq!(stats.signals_by_type["Momentum"], 2);
+        assert_eq!(stats.oldest_signal, Some(first.created_at));
+        assert_eq!(stats.newest_signal, Some(second.created_at));
+        assert!(stats.storage_size_bytes.is_some_and(|size| size > 0));
+
+        drop_schema(&pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_postgres_matches_in_memory() {
+        let (pool, schema) = scratch_pool().await;
+        let postgres = PostgresSignalStorage::new(pool.clone()).await.unwrap();
+        // Idempotent
+        postgres.initialize().await.unwrap();
+        let memory = InMemoryStorage::new();
+
+        // Whole seconds so timestamps survive Postgres' microsecond precision
+        let base = DateTime::from_timestamp(Utc::now().timestamp() - 100_000, 0).unwrap();
+        let markets: Vec<MarketId> = (0..10).map(|_| MarketId::random()).collect();
+        let signals: Vec<TradeSignal> = (0..1000)
+            .map(|i| signal(i, markets[i % markets.len()], base + Duration::minutes(i as i64)))
+            .collect();
+        postgres.store_batch(&signals).await.unwrap();
+        memory.store_batch(&signals).await.unwrap();
+
+        let stored = postgres.get(signals[5].id).await.unwrap().unwrap();
+        assert_eq!(serde_json::to_value(&stored).unwrap(), serde_json::to_value(&signals[5]).unwrap());
+
+        for market_id in &markets {
+            assert_eq!(
+                sorted_ids(&postgres.get_by_market(*market_id).await.unwrap()),
+                sorted_ids(&memory.get_by_market(*market_id).await.unwrap())
+            );
+        }
+        for signal_type in ["SpreadArbitrage", "Momentum", "MeanReversion", "Value", "Sentiment"] {
+            assert_eq!(
+                sorted_ids(&postgres.get_by_type(signal_type).await.unwrap()),
+                sorted_ids(&memory.get_by_type(signal_type).await.unwrap())
+            );
+        }
+        let (start, end) = (base + Duration::minutes(100), base + Duration::minutes(250));
+        let in_range = postgres.get_by_time_range(start, end).await.unwrap();
+        assert_eq!(in_range.len(), 151);
+        assert_eq!(sorted_ids(&in_range), sorted_ids(&memory.get_by_time_range(start, end).await.unwrap()));
+
+        let (pg_stats, mem_stats) = (postgres.stats().await.unwrap(), memory.stats().await.unwrap());
+        assert_eq!(pg_stats.total_signals, 1000);
+        assert_eq!(pg_stats.total_signals, mem_stats.total_signals);
+        assert_eq!(pg_stats.signals_by_type, mem_stats.signals_by_type);
+        assert_eq!(pg_stats.oldest_signal, mem_stats.oldest_signal);
+        assert_eq!(pg_stats.newest_signal, mem_stats.newest_signal);
+
+        assert!(postgres.delete(signals[0].id).await.unwrap());
+        assert!(!postgres.delete(signals[0].id).await.unwrap());
+
+        let postgres = PostgresExecutionStorage::new(pool.clone()).await.unwrap();
+        let executions = InMemoryExecutionStorage::new();
+        let results: Vec<SignalExecutionResult> = signals
+            .iter()
+            .take(200)
+            .enumerate()
+            .map(|(i, s)| SignalExecutionResult {
+                signal_id: s.id,
+                market_id: s.market_id,
+                outcome_id: Some("yes".into()),
+                executed_at: s.created_at,
+                entry_price: s.entry_price,
+                exit_price: (!i.is_multiple_of(4)).then_some(Decimal::new(45, 2)),
+                position_size: s.position_size,
+                pnl: Some(Decimal::new(i as i64 - 90, 2)),
+                pnl_percentage: None,
+                holding_period_hours: Some(1.5),
+                exit_reason: if i.is_multiple_of(2) { ExitReason::TargetHit } else { ExitReason::StopLoss },
+                rebate_pnl: i.is_multiple_of(3).then_some(Decimal::new(1, 2)),
+                signal_type: Some(s.signal_type.clone()),
+            })
+            .collect();
+        postgres.store_results(&results).await.unwrap();
+        for result in &results {
+            ExecutionStorage::store(&executions, result).await.unwrap();
+        }
+
+        let stored = postgres.get_by_signal(results[1].signal_id).await.unwrap().unwrap();
+        assert_eq!(serde_json::to_value(&stored).unwrap(), serde_json::to_value(&results[1]).unwrap());
+        assert_eq!(
+            postgres.get_by_market(markets[0]).await.unwrap().len(),
+            executions.get_by_market(markets[0]).await.unwrap().len()
+        );
+
+        let (start, end) = (base, base + Duration::minutes(150));
+        let pg_stats = postgres.get_backtest_stats(start, end).await.unwrap();
+        let mem_stats = executions.get_backtest_stats(start, end).await.unwrap();
+        assert_eq!(serde_json::to_value(&pg_stats).unwrap(), serde_json::to_value(&mem_stats).unwrap());
+
+        drop_schema(&pool, &schema).await;
+    }
+}
@@ -0,0 +1,359 @@
+// Research Adapter
+// Merges research agent outputs into the ResearchOutput generators consume
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use common::{AgentOutput, Market, MarketId};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::sentiment_polarity::SentimentPolarityResolver;
+use crate::signals::{ResearchOutput, SentimentScore, SentimentSource};
+
+/// How agent outputs are combined into one ResearchOutput
+#[derive(Debug, Clone)]
+pub struct ResearchAggregatorConfig {
+    /// Outputs older than this are ignored and pruned
+    pub ttl: Duration,
+    /// Probability shift for sentiment of 1.0 towards YES
+    pub sentiment_probability_scale: f64,
+    /// Weight per agent id when averaging; unlisted agents weigh 1.0
+    pub agent_weights: HashMap<String, f64>,
+    /// Themes kept as key factors after merging
+    pub max_key_factors: usize,
+}
+
+impl Default for ResearchAggregatorConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::minutes(30),
+            sentiment_probability_scale: 0.1,
+            agent_weights: HashMap::new(),
+            max_key_factors: 10,
+        }
+    }
+}
+
+/// Fields of a `SentimentSignal` the adapter reads
+#[derive(Debug, Deserialize)]
+struct SentimentData {
+    sentiment: SentimentReading,
+    #[serde(default)]
+    top_themes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SentimentReading {
+    score: f64,
+}
+
+/// Latest research from one agent on one market
+#[derive(Debug, Clone)]
+struct AgentResearch {
+    agent_id: String,
+    /// -1.0 to 1.0; None for agents that don't report sentiment
+    sentiment: Option<f64>,
+    confidence: f64,
+    themes: Vec<String>,
+    timestamp: DateTime<Utc>,
+}
+
+/// Keeps the latest output per (market, agent) and merges them per market.
+///
+/// Confidence is the weighted average over agents. Sentiment is averaged
+/// with weight times confidence, turned towards YES by the polarity resolver
+/// when one is set, and shifts the market's YES price by up to
+/// `sentiment_probability_scale` to give the probability estimate. Key
+/// factors are the union of the agents' top themes, most trusted agent first.
+pub struct ResearchAggregator {
+    config: ResearchAggregatorConfig,
+    polarity: Option<SentimentPolarityResolver>,
+    latest: RwLock<HashMap<(MarketId, String), AgentResearch>>,
+}
+
+impl ResearchAggregator {
+    pub fn new(config: ResearchAggregatorConfig) -> Self {
+        Self {
+            config,
+            polarity: None,
+            latest: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Resolve which outcome sentiment supports before mapping it to a probability;
+    /// markets the resolver finds ambiguous get no probability estimate
+    pub fn with_polarity(mut self, resolver: SentimentPolarityResolver) -> Self {
+        self.polarity = Some(resolver);
+        self
+    }
+
+    /// Record an agent output, unless a newer one from the same agent is held
+    pub fn ingest(&self, output: AgentOutput) -> Result<()> {
+        let (sentiment, themes) = match output.signal_type.as_str() {
+            "sentiment" => {
+                let data: SentimentData = serde_json::from_value(output.data)
+                    .with_context(|| format!("Malformed sentiment output from {}", output.agent_id))?;
+                (Some(data.sentiment.score.clamp(-1.0, 1.0)), data.top_themes)
+            }
+            other => {
+                debug!("Output of type {} from {} contributes confidence only", other, output.agent_id);
+                (None, Vec::new())
+            }
+        };
+
+        let research = AgentResearch {
+            agent_id: output.agent_id.clone(),
+            sentiment,
+            confidence: output.confidence.clamp(0.0, 1.0),
+            themes,
+            timestamp: output.timestamp,
+        };
+
+        let mut latest = self.latest.write().unwrap();
+        let key = (output.market_id, output.agent_id);
+        if latest.get(&key).is_some_and(|held| held.timestamp > research.timestamp) {
+            debug!("Ignoring out-of-order output from {} for market {}", key.1, key.0);
+            return Ok(());
+        }
+        latest.insert(key, research);
+        Ok(())
+    }
+
+    /// Record an `AgentOutput` serialized as JSON
+    pub fn ingest_json(&self, json: &str) -> Result<()> {
+        let output: AgentOutput = serde_json::from_str(json).context("Invalid AgentOutput JSON")?;
+        self.ingest(output)
+    }
+
+    /// Ingest every output arriving on a channel until it closes
+    pub fn subscribe(self: &Arc<Self>, mut outputs: mpsc::Receiver<AgentOutput>) -> JoinHandle<()> {
+        let aggregator = Arc::clone(self);
+        tokio::spawn(async move {
+            while let Some(output) = outputs.recv().await {
+                if let Err(e) = aggregator.ingest(output) {
+                    warn!("Dropping agent output: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Merged research on a market from outputs still within the TTL
+    pub fn research(&self, market: &Market, now: DateTime<Utc>) -> Option<ResearchOutput> {
+        let latest = self.latest.read().unwrap();
+        let mut fresh: Vec<&AgentResearch> = latest
+            .iter()
+            .filter(|((market_id, _), research)| *market_id == market.id && now - research.timestamp <= self.config.ttl)
+            .map(|(_, research)| research)
+            .collect();
+        if fresh.is_empty() {
+            return None;
+        }
+
+        // Most trusted first, so their themes survive the key factor cap
+        fresh.sort_by(|a, b| {
+            let trust = |r: &AgentResearch| self.weight(&r.agent_id) * r.confidence;
+            trust(b).total_cmp(&trust(a)).then_with(|| a.agent_id.cmp(&b.agent_id))
+        });
+
+        let total_weight: f64 = fresh.iter().map(|r| self.weight(&r.agent_id)).sum();
+        let confidence = if total_weight > 0.0 {
+            fresh.iter().map(|r| self.weight(&r.agent_id) * r.confidence).sum::<f64>() / total_weight
+        } else {
+            0.0
+        };
+
+        let sources: Vec<SentimentSource> = fresh
+            .iter()
+            .filter_map(|r| {
+                Some(SentimentSource {
+                    name: r.agent_id.clone(),
+                    score: r.sentiment?,
+                    weight: self.weight(&r.agent_id) * r.confidence,
+                })
+            })
+            .collect();
+        let sentiment_weight: f64 = sources.iter().map(|s| s.weight).sum();
+        let overall = if sentiment_weight > 0.0 {
+            sources.iter().map(|s| s.score * s.weight).sum::<f64>() / sentiment_weight
+        } else if !sources.is_empty() {
+            sources.iter().map(|s| s.score).sum::<f64>() / sources.len() as f64
+        } else {
+            0.0
+        };
+
+        let probability_estimate = if sources.is_empty() {
+            None
+        } else {
+            self.probability_estimate(market, overall)
+        };
+
+        let mut seen = HashSet::new();
+        let key_factors: Vec<String> = fresh
+            .iter()
+            .flat_map(|r| r.themes.iter())
+            .filter(|theme| seen.insert(theme.to_lowercase()))
+            .take(self.config.max_key_factors)
+            .cloned()
+            .collect();
+
+        let agents: Vec<&str> = fresh.iter().map(|r| r.agent_id.as_str()).collect();
+        Some(ResearchOutput {
+            market_id: market.id,
+            analysis: format!("Merged research from {}", agents.join(", ")),
+            sentiment: SentimentScore { overall, sources },
+            confidence,
+            probability_estimate,
+            key_factors,
+            timestamp: fresh.iter().map(|r| r.timestamp).max().unwrap_or(now),
+        })
+    }
+
+    /// Drop outputs older than the TTL, returning how many were removed
+    pub fn expire(&self, now: DateTime<Utc>) -> usize {
+        let mut latest = self.latest.write().unwrap();
+        let before = latest.len();
+        latest.retain(|_, research| now - research.timestamp <= self.config.ttl);
+        before - latest.len()
+    }
+
+    /// Number of (market, agent) outputs held
+    pub fn len(&self) -> usize {
+        self.latest.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn weight(&self, agent_id: &str) -> f64 {
+        self.config.agent_weights.get(agent_id).copied().unwrap_or(1.0).max(0.0)
+    }
+
+    /// YES price shifted by sentiment towards YES
+    fn probability_estimate(&self, market: &Market, sentiment: f64) -> Option<f64> {
+        let yes_price = market.outcomes.first()?.price;
+        let yes_score = match &self.polarity {
+            Some(resolver) => resolver.directional(market, sentiment)?.yes_score,
+            None => sentiment,
+        };
+        Some((yes_price + yes_score * self.config.sentiment_probability_scale).clamp(0.0, 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `AgentOutput` as serialized by `SentimentAgent`
+    const SENTIMENT_OUTPUT: &str = r#"{
+        "agent_id": "sentiment-1",
+        "market_id": "6f1e0a3c-1111-4222-8333-444455556666",
+        "signal_type": "sentiment",
+        "data": {
+            "market_id": "6f1e0a3c-1111-4222-8333-444455556666",
+            "market_category": "economics",
+            "sentiment": {"score": 0.6, "magnitude": 0.7, "confidence": 0.8, "article_count": 12},
+            "top_themes": ["rate cuts", "inflation", "jobs report"],
+            "timestamp": "2026-10-14T12:00:00Z",
+            "sources": ["a1", "a2"],
+            "momentum": 0.05,
+            "previous_score": null,
+            "deltas": []
+        },
+        "confidence": 0.8,
+        "timestamp": "2026-10-14T12:00:00Z",
+        "processing_time_ms": 42
+    }"#;
+
+    fn create_market(question: &str) -> Market {
+        Market {
+            question: question.to_string(),
+            category: "economics".to_string(),
+            ..crate::test_support::test_market("6f1e0a3c-1111-4222-8333-444455556666".parse().unwrap())
+        }
+    }
+
+    fn at(time: &str) -> DateTime<Utc> {
+        time.parse().unwrap()
+    }
+
+    #[test]
+    fn test_sentiment_output_becomes_research() {
+        let aggregator = ResearchAggregator::new(ResearchAggregatorConfig::default());
+        aggregator.ingest_json(SENTIMENT_OUTPUT).unwrap();
+
+        let market = create_market("Will the Fed cut rates in December?");
+        let research = aggregator.research(&market, at("2026-10-14T12:05:00Z")).unwrap();
+        assert_eq!(research.market_id, market.id);
+        assert!((research.confidence - 0.8).abs() < 1e-12);
+        assert!((research.sentiment.overall - 0.6).abs() < 1e-12);
+        assert_eq!(research.sentiment.sources[0].name, "sentiment-1");
+        assert!((research.probability_estimate.unwrap() - 0.56).abs() < 1e-12);
+        assert_eq!(research.key_factors, vec!["rate cuts", "inflation", "jobs report"]);
+
+        // A second agent: confidence averages by weight, themes are merged once
+        let mut config = ResearchAggregatorConfig::default();
+        config.agent_weights.insert("sentiment-2".to_string(), 3.0);
+        let aggregator = ResearchAggregator::new(config);
+        aggregator.ingest_json(SENTIMENT_OUTPUT).unwrap();
+        let mut second: AgentOutput = serde_json::from_str(SENTIMENT_OUTPUT).unwrap();
+        second.agent_id = "sentiment-2".to_string();
+        second.confidence = 0.4;
+        second.data["sentiment"]["score"] = serde_json::json!(-0.2);
+        second.data["top_themes"] = serde_json::json!(["Inflation", "housing"]);
+        aggregator.ingest(second).unwrap();
+
+        let research = aggregator.research(&market, at("2026-10-14T12:05:00Z")).unwrap();
+        assert!((research.confidence - (0.8 + 3.0 * 0.4) / 4.0).abs() < 1e-12);
+        let overall = (0.6 * 0.8 + -0.2 * 1.2) / 2.0;
+        assert!((research.sentiment.overall - overall).abs() < 1e-12);
+        assert_eq!(research.key_factors, vec!["Inflation", "housing", "rate cuts", "jobs report"]);
+
+        // Good news is bad for YES on a negatively framed question
+        let aggregator = ResearchAggregator::new(ResearchAggregatorConfig::default())
+            .with_polarity(SentimentPolarityResolver::new());
+        aggregator.ingest_json(SENTIMENT_OUTPUT).unwrap();
+        let recession = create_market("Will there be a recession in 2027?");
+        let research = aggregator.research(&recession, at("2026-10-14T12:05:00Z")).unwrap();
+        assert!((research.probability_estimate.unwrap() - 0.44).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_research_expires_after_ttl() {
+        let aggregator = ResearchAggregator::new(ResearchAggregatorConfig {
+            ttl: Duration::minutes(10),
+            ..ResearchAggregatorConfig::default()
+        });
+        aggregator.ingest_json(SENTIMENT_OUTPUT).unwrap();
+        let market = create_market("Will the Fed cut rates in December?");
+
+        assert!(aggregator.research(&market, at("2026-10-14T12:10:00Z")).is_some());
+        assert!(aggregator.research(&market, at("2026-10-14T12:10:01Z")).is_none());
+        assert_eq!(aggregator.expire(at("2026-10-14T12:09:00Z")), 0);
+        assert_eq!(aggregator.expire(at("2026-10-14T12:10:01Z")), 1);
+        assert!(aggregator.is_empty());
+
+        // An older output never replaces a newer one
+        aggregator.ingest_json(SENTIMENT_OUTPUT).unwrap();
+        let mut stale: AgentOutput = serde_json::from_str(SENTIMENT_OUTPUT).unwrap();
+        stale.timestamp = at("2026-10-14T11:00:00Z");
+        aggregator.ingest(stale).unwrap();
+        assert!(aggregator.research(&market, at("2026-10-14T12:05:00Z")).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_ingests_channel() {
+        let aggregator = Arc::new(ResearchAggregator::new(ResearchAggregatorConfig::default()));
+        let (tx, rx) = mpsc::channel(8);
+        let task = aggregator.subscribe(rx);
+
+        tx.send(serde_json::from_str(SENTIMENT_OUTPUT).unwrap()).await.unwrap();
+        drop(tx);
+        task.await.unwrap();
+        assert_eq!(aggregator.len(), 1);
+    }
+}
@@ -0,0 +1,306 @@
+// Sentiment Polarity
+// Decides per market whether positive news supports YES or NO
+
+use common::{Market, MarketId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Which outcome positive news about a market's subject supports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Polarity {
+    /// "Will Bitcoin reach $100k?": good news is bullish for YES
+    YesOnPositive,
+    /// "Will there be a recession in 2025?": good news is bearish for YES
+    NoOnPositive,
+}
+
+impl Polarity {
+    /// Multiplier that turns raw sentiment into sentiment towards YES
+    pub fn sign(&self) -> f64 {
+        match self {
+            Polarity::YesOnPositive => 1.0,
+            Polarity::NoOnPositive => -1.0,
+        }
+    }
+
+    fn flipped(self) -> Self {
+        match self {
+            Polarity::YesOnPositive => Polarity::NoOnPositive,
+            Polarity::NoOnPositive => Polarity::YesOnPositive,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Polarity::YesOnPositive => "yes_on_positive",
+            Polarity::NoOnPositive => "no_on_positive",
+        }
+    }
+}
+
+/// Where a resolved polarity came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PolaritySource {
+    Override,
+    Rule,
+    CategoryDefault,
+}
+
+impl PolaritySource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PolaritySource::Override => "override",
+            PolaritySource::Rule => "rule",
+            PolaritySource::CategoryDefault => "default",
+        }
+    }
+}
+
+/// Polarity of one market and how it was decided
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolarityResolution {
+    pub polarity: Polarity,
+    pub source: PolaritySource,
+    /// Framing terms that decided a rule match, e.g. ["recession"]
+    pub matched_terms: Vec<String>,
+}
+
+impl PolarityResolution {
+    /// Fields to merge into a signal's `custom_fields`
+    pub fn metadata(&self) -> serde_json::Value {
+        serde_json::json!({
+            "sentiment_polarity": self.polarity.as_str(),
+            "sentiment_polarity_source": self.source.as_str(),
+            "sentiment_polarity_terms": self.matched_terms,
+        })
+    }
+}
+
+/// Sentiment re-expressed as support for the YES outcome
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectionalSentiment {
+    /// -1.0 (supports NO) to 1.0 (supports YES)
+    pub yes_score: f64,
+    pub resolution: PolarityResolution,
+}
+
+/// Infers how each market's question is framed relative to its subject
+///
+/// Resolution order is: manual override, then rule patterns over the
+/// question text, then a default for the market's category. Rules score the
+/// question's framing terms (positive ones like "win" or "reach", negative
+/// ones like "recession" or "crash"); a negator such as "not" or "avoid"
+/// shortly before a term flips it. When the terms tie, or none match and the
+/// category has no default, the market is ambiguous and `resolve` returns
+/// None so callers abstain rather than guess.
+#[derive(Debug, Clone)]
+pub struct SentimentPolarityResolver {
+    overrides: HashMap<MarketId, Polarity>,
+    category_defaults: HashMap<String, Polarity>,
+    positive_terms: Vec<String>,
+    negative_terms: Vec<String>,
+    negators: Vec<String>,
+}
+
+/// Words after a negator that it still applies to
+const NEGATION_REACH: usize = 3;
+
+impl Default for SentimentPolarityResolver {
+    fn default() -> Self {
+        let words = |list: &[&str]| list.iter().map(|w| w.to_string()).collect();
+        Self {
+            overrides: HashMap::new(),
+            category_defaults: [("crypto", Polarity::YesOnPositive), ("sports", Polarity::YesOnPositive)]
+                .into_iter()
+                .map(|(category, polarity)| (category.to_string(), polarity))
+                .collect(),
+            positive_terms: words(&[
+                "win", "wins", "pass", "passes", "approve", "approved", "reach", "reaches", "exceed", "exceeds",
+                "above", "rise", "rises", "grow", "grows", "beat", "beats", "launch", "launches", "succeed",
+                "re-elected", "elected", "increase", "hit", "record",
+            ]),
+            negative_terms: words(&[
+                "recession", "crash", "crashes", "fail", "fails", "lose", "loses", "default", "defaults",
+                "resign", "resigns", "impeached", "shutdown", "bankrupt", "bankruptcy", "below", "fall", "falls",
+                "drop", "drops", "decline", "declines", "ban", "banned", "war", "collapse", "collapses",
+                "indicted", "recall", "layoffs",
+            ]),
+            negators: words(&["not", "no", "never", "avoid", "avoids", "prevent", "prevents", "without", "won't"]),
+        }
+    }
+}
+
+impl SentimentPolarityResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fix a market's polarity regardless of its question text
+    pub fn with_override(mut self, market_id: MarketId, polarity: Polarity) -> Self {
+        self.overrides.insert(market_id, polarity);
+        self
+    }
+
+    /// Polarity for markets of a category that no rule matches
+    pub fn with_category_default(mut self, category: &str, polarity: Polarity) -> Self {
+        self.category_defaults.insert(category.to_lowercase(), polarity);
+        self
+    }
+
+    /// Drop the default for a category, so its unmatched markets abstain
+    pub fn without_category_default(mut self, category: &str) -> Self {
+        self.category_defaults.remove(&category.to_lowercase());
+        self
+    }
+
+    /// Polarity of a market, None if it is ambiguous
+    pub fn resolve(&self, market: &Market) -> Option<PolarityResolution> {
+        if let Some(&polarity) = self.overrides.get(&market.id) {
+            return Some(PolarityResolution {
+                polarity,
+                source: PolaritySource::Override,
+                matched_terms: Vec::new(),
+            });
+        }
+
+        let (score, matched_terms) = self.score_question(&market.question);
+        if score != 0 {
+            let polarity = if score > 0 { Polarity::YesOnPositive } else { Polarity::NoOnPositive };
+            return Some(PolarityResolution {
+                polarity,
+                source: PolaritySource::Rule,
+                matched_terms,
+            });
+        }
+
+        // Terms that cancel out are a conflict, not an absence of framing
+        if !matched_terms.is_empty() {
+            return None;
+        }
+
+        self.category_defaults
+            .get(&market.category.to_lowercase())
+            .map(|&polarity| PolarityResolution {
+                polarity,
+                source: PolaritySource::CategoryDefault,
+                matched_terms: Vec::new(),
+            })
+    }
+
+    /// Map raw sentiment (-1.0 to 1.0) onto the YES outcome, None to abstain
+    pub fn directional(&self, market: &Market, sentiment: f64) -> Option<DirectionalSentiment> {
+        let resolution = self.resolve(market)?;
+        Some(DirectionalSentiment {
+            yes_score: sentiment * resolution.polarity.sign(),
+            resolution,
+        })
+    }
+
+    /// Net framing score of a question (+1 per positive term, -1 per
+    /// negative one, flipped by a preceding negator) and the terms counted
+    fn score_question(&self, question: &str) -> (i32, Vec<String>) {
+        let lower = question.to_lowercase();
+        let tokens: Vec<&str> = lower
+            .split(|c: char| !(c.is_alphanumeric() || c == '\'' || c == '-'))
+            .filter(|t| !t.is_empty())
+            .collect();
+
+        let mut score = 0;
+        let mut matched = Vec::new();
+        let mut negated_until = None;
+
+        for (i, token) in tokens.iter().enumerate() {
+            if self.negators.iter().any(|n| n == token) {
+                negated_until = Some(i + NEGATION_REACH);
+                continue;
+            }
+
+            let polarity = if self.positive_terms.iter().any(|t| t == token) {
+                Polarity::YesOnPositive
+            } else if self.negative_terms.iter().any(|t| t == token) {
+                Polarity::NoOnPositive
+            } else {
+                continue;
+            };
+
+            let negated = negated_until.is_some_and(|until| i <= until);
+            let polarity = if negated { polarity.flipped() } else { polarity };
+            score += polarity.sign() as i32;
+            matched.push(if negated { format!("not {}", token) } else { token.to_string() });
+            negated_until = None;
+        }
+
+        (score, matched)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn market(question: &str, category: &str) -> Market {
+        Market {
+            question: question.to_string(),
+            category: category.to_string(),
+            outcomes: vec![],
+            ..crate::test_support::test_market(MarketId::random())
+        }
+    }
+
+    #[test]
+    fn test_straight_and_negative_framing() {
+        let resolver = SentimentPolarityResolver::new();
+
+        let straight = market("Will Bitcoin reach $100k by June?", "crypto");
+        let resolved = resolver.directional(&straight, 0.6).unwrap();
+        assert_eq!(resolved.resolution.polarity, Polarity::YesOnPositive);
+        assert_eq!(resolved.resolution.source, PolaritySource::Rule);
+        assert!((resolved.yes_score - 0.6).abs() < 1e-12);
+
+        // Good economic news is bad news for YES
+        let negative = market("Will there be a recession in 2025?", "economics");
+        let resolved = resolver.directional(&negative, 0.6).unwrap();
+        assert_eq!(resolved.resolution.polarity, Polarity::NoOnPositive);
+        assert_eq!(resolved.resolution.matched_terms, vec!["recession"]);
+        assert!((resolved.yes_score + 0.6).abs() < 1e-12);
+        assert_eq!(resolved.resolution.metadata()["sentiment_polarity_source"], "rule");
+
+        // A negator flips the term it governs
+        let avoided = market("Will the Fed avoid a recession?", "economics");
+        assert_eq!(resolver.resolve(&avoided).unwrap().polarity, Polarity::YesOnPositive);
+        let not_passed = market("Will the bill not pass the Senate?", "politics");
+        assert_eq!(resolver.resolve(&not_passed).unwrap().polarity, Polarity::NoOnPositive);
+    }
+
+    #[test]
+    fn test_override_wins_over_rules() {
+        let crash = market("Will Tesla stock crash below $100?", "business");
+        let resolver = SentimentPolarityResolver::new().with_override(crash.id, Polarity::YesOnPositive);
+
+        let resolved = resolver.resolve(&crash).unwrap();
+        assert_eq!(resolved.polarity, Polarity::YesOnPositive);
+        assert_eq!(resolved.source, PolaritySource::Override);
+        assert_eq!(resolved.metadata()["sentiment_polarity"], "yes_on_positive");
+    }
+
+    #[test]
+    fn test_ambiguous_markets_abstain() {
+        let resolver = SentimentPolarityResolver::new();
+
+        // No framing terms and no default for the category
+        let unframed = market("Will it snow in New York on Christmas?", "weather");
+        assert!(resolver.resolve(&unframed).is_none());
+        assert!(resolver.directional(&unframed, 0.9).is_none());
+
+        // Conflicting terms are not settled by the category default
+        let conflicted = market("Will the Lakers win after star player injury causes a losing streak and they lose?", "sports");
+        assert_eq!(resolver.score_question(&conflicted.question).0, 0);
+        assert!(resolver.resolve(&conflicted).is_none());
+
+        // Unframed questions fall back to the category default when there is one
+        let unframed_sports = market("Lakers vs Celtics: Lakers?", "sports");
+        assert_eq!(resolver.resolve(&unframed_sports).unwrap().source, PolaritySource::CategoryDefault);
+        let resolver = resolver.without_category_default("sports");
+        assert!(resolver.resolve(&unframed_sports).is_none());
+    }
+}
@@ -1,25 +1,21 @@
 use chrono::{DateTime, Utc};
-use common::Market;
-use rust_decimal::Decimal;
+use common::{Market, MarketId, OrderSide, OutcomeId};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
 use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
+use crate::diagnostics::SkipReason;
+
 pub mod spread_arbitrage;
 
 pub use spread_arbitrage::SpreadArbitrageGenerator;
 
-/// Signal type enumeration
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub enum SignalType {
-    SpreadArbitrage,
-    Momentum,
-    MeanReversion,
-    Value,
-    Sentiment,
-}
+pub use common::SignalType;
 
 /// Signal direction
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum SignalDirection {
     Long,
     Short,
@@ -30,10 +26,10 @@ pub enum SignalDirection {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TradeSignal {
     pub id: Uuid,
-    pub market_id: Uuid,
+    pub market_id: MarketId,
     pub signal_type: SignalType,
     pub direction: SignalDirection,
-    pub outcome_id: Option<String>,
+    pub outcome_id: Option<OutcomeId>,
     pub entry_price: Decimal,
     pub target_price: Decimal,
     pub stop_loss: Decimal,
@@ -46,6 +42,52 @@ pub struct TradeSignal {
     pub metadata: SignalMetadata,
     pub created_at: DateTime<Utc>,
     pub expires_at: Option<DateTime<Utc>>,
+    /// Experiment variant that produced this signal, if any
+    #[serde(default)]
+    pub variant: Option<VariantTag>,
+}
+
+/// Identifies the experiment variant a signal was generated under
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VariantTag {
+    pub experiment_id: Uuid,
+    pub variant: String,
+    /// Parent strategy whose risk limits and throttles apply
+    pub parent_strategy: String,
+    /// Strategy id used for attribution and A/B analysis
+    pub strategy_version_id: String,
+}
+
+impl TradeSignal {
+    /// Strategy id to attribute this signal's trades to
+    pub fn strategy_id<'a>(&'a self, default: &'a str) -> &'a str {
+        self.variant
+            .as_ref()
+            .map(|v| v.strategy_version_id.as_str())
+            .unwrap_or(default)
+    }
+
+    /// Whether this signal pulls a resting quote rather than trading
+    pub fn is_cancel(&self) -> bool {
+        self.signal_type == SignalType::CancelQuote
+    }
+
+    /// Resting quote this signal places or cancels, if it is a quote
+    pub fn quote_id(&self) -> Option<Uuid> {
+        self.metadata.custom_fields.get("quote_id")?.as_str()?.parse().ok()
+    }
+
+    /// Whether this signal flattens an overweight inventory rather than
+    /// chasing edge
+    pub fn is_unwind(&self) -> bool {
+        self.metadata.custom_fields.get("unwind").and_then(|v| v.as_bool()).unwrap_or(false)
+    }
+
+    /// Whether this signal reports a locked-in position and how to exit it
+    /// rather than opening one
+    pub fn is_exit(&self) -> bool {
+        self.metadata.custom_fields.get("exit").and_then(|v| v.as_bool()).unwrap_or(false)
+    }
 }
 
 /// Additional metadata for the signal
@@ -55,9 +97,31 @@ pub struct SignalMetadata {
     pub data_points: u32,
     pub liquidity_score: f64,
     pub volatility_score: f64,
+    /// Age of the newest price snapshot when the signal was generated
+    #[serde(default)]
+    pub price_age_ms: Option<i64>,
+    /// Age of the order book when the signal was generated
+    #[serde(default)]
+    pub orderbook_age_ms: Option<i64>,
+    /// Age of the research output when the signal was generated
+    #[serde(default)]
+    pub research_age_ms: Option<i64>,
     pub custom_fields: serde_json::Value,
 }
 
+impl SignalMetadata {
+    /// Fill in the ages of the input's data at `now`, keeping any a
+    /// generator already set
+    pub fn record_input_ages(&mut self, input: &SignalInput, now: DateTime<Utc>) {
+        let age = |at: DateTime<Utc>| (now - at).num_milliseconds().max(0);
+        self.price_age_ms = self
+            .price_age_ms
+            .or_else(|| input.price_history.iter().map(|s| s.timestamp).max().map(age));
+        self.orderbook_age_ms = self.orderbook_age_ms.or_else(|| input.order_book.as_ref().map(|ob| age(ob.timestamp)));
+        self.research_age_ms = self.research_age_ms.or(Some(age(input.research_output.timestamp)));
+    }
+}
+
 /// Signal generation input
 #[derive(Debug, Clone)]
 pub struct SignalInput {
@@ -67,10 +131,34 @@ pub struct SignalInput {
     pub price_history: Vec<PriceSnapshot>,
 }
 
+impl SignalInput {
+    /// The market's tick size, or `DEFAULT_TICK_SIZE` when the feed has not
+    /// reported a usable one
+    pub fn tick_size(&self) -> Decimal {
+        self.market
+            .tick_size
+            .and_then(Decimal::from_f64)
+            .filter(|tick| *tick > Decimal::ZERO)
+            .unwrap_or(DEFAULT_TICK_SIZE)
+    }
+}
+
+/// Tick size assumed for markets that do not report one
+pub const DEFAULT_TICK_SIZE: Decimal = Decimal::from_parts(1, 0, 0, false, 2);
+
+/// Round `price` to the nearest multiple of `tick_size`, halves rounding up;
+/// a non-positive tick leaves the price as it is
+pub fn round_to_tick(price: Decimal, tick_size: Decimal) -> Decimal {
+    if tick_size <= Decimal::ZERO {
+        return price;
+    }
+    (price / tick_size).round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero) * tick_size
+}
+
 /// Research agent output
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResearchOutput {
-    pub market_id: Uuid,
+    pub market_id: MarketId,
     pub analysis: String,
     pub sentiment: SentimentScore,
     pub confidence: f64,
@@ -96,7 +184,7 @@ pub struct SentimentSource {
 /// Order book snapshot
 #[derive(Debug, Clone)]
 pub struct OrderBookSnapshot {
-    pub market_id: Uuid,
+    pub market_id: MarketId,
     pub bids: Vec<Level>,
     pub asks: Vec<Level>,
     pub timestamp: DateTime<Utc>,
@@ -119,8 +207,144 @@ pub struct PriceSnapshot {
     pub timestamp: DateTime<Utc>,
 }
 
+/// Which half of a binary market a fill was in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PairSide {
+    Yes,
+    No,
+}
+
+/// State change fed back to generators after signals are acted on
+#[derive(Debug, Clone)]
+pub enum StateUpdate {
+    /// An order from one of our signals was filled
+    TradeExecution {
+        /// Signal whose order filled, if it came from one
+        signal_id: Option<Uuid>,
+        outcome_id: Option<String>,
+        /// Whether the filled outcome is the market's YES or NO
+        outcome_side: PairSide,
+        side: OrderSide,
+        size: Decimal,
+        price: Decimal,
+    },
+    /// A position was closed out
+    PositionClosed {
+        outcome_id: Option<String>,
+        /// Whether the closed outcome is the market's YES or NO
+        outcome_side: PairSide,
+        side: OrderSide,
+        size: Decimal,
+        realized_pnl: Decimal,
+    },
+    /// New volatility estimate for the market
+    VolatilityUpdate {
+        /// Calibrated score, 0.0 to 1.0
+        score: f64,
+        /// Standard deviation of price changes the score came from
+        raw_sigma: f64,
+    },
+}
+
 /// Signal generator trait
+///
+/// Generators may keep per-market state; `update_state` feeds back fills and
+/// market conditions between calls to `generate`.
 pub trait SignalGenerator {
+    fn generate(&mut self, input: &SignalInput) -> anyhow::Result<Vec<TradeSignal>>;
+
+    fn update_state(&mut self, _market_id: MarketId, _update: &StateUpdate) {}
+
+    /// State to carry across a restart; None for stateless generators
+    fn snapshot_state(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Restore state captured by `snapshot_state`
+    fn restore_state(&mut self, _state: serde_json::Value) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Why `generate` returned nothing for this input, if the generator can
+    /// tell; only asked after an empty result
+    fn skip_reason(&self, _input: &SignalInput) -> Option<SkipReason> {
+        None
+    }
+
+    fn signal_type(&self) -> SignalType;
+}
+
+/// Generator that needs every market's input at once, e.g. to compare
+/// prices across correlated markets
+pub trait BatchSignalGenerator {
+    fn generate_batch(&mut self, inputs: &[SignalInput]) -> Vec<TradeSignal>;
+    fn signal_type(&self) -> SignalType;
+
+    /// Markets the last `generate_batch` deliberately passed over, and why
+    fn last_skips(&self) -> Vec<(MarketId, SkipReason)> {
+        Vec::new()
+    }
+}
+
+/// Stateless generator that emits at most one signal per input
+pub trait SingleSignalGenerator {
     fn generate(&self, input: &SignalInput) -> anyhow::Result<Option<TradeSignal>>;
     fn signal_type(&self) -> SignalType;
 }
+
+/// Adapts a `SingleSignalGenerator` to the `SignalGenerator` trait
+pub struct SingleSignalAdapter<G>(pub G);
+
+impl<G: SingleSignalGenerator> SignalGenerator for SingleSignalAdapter<G> {
+    fn generate(&mut self, input: &SignalInput) -> anyhow::Result<Vec<TradeSignal>> {
+        Ok(self.0.generate(input)?.into_iter().collect())
+    }
+
+    fn signal_type(&self) -> SignalType {
+        self.0.signal_type()
+    }
+}
+
+/// A batch generator the pipeline shares with other tasks, e.g. a
+/// `CorrelationGenerator` whose staged rule changes the admin API approves
+impl<G: BatchSignalGenerator> BatchSignalGenerator for Arc<Mutex<G>> {
+    fn generate_batch(&mut self, inputs: &[SignalInput]) -> Vec<TradeSignal> {
+        self.lock().unwrap_or_else(|e| e.into_inner()).generate_batch(inputs)
+    }
+
+    fn signal_type(&self) -> SignalType {
+        self.lock().unwrap_or_else(|e| e.into_inner()).signal_type()
+    }
+
+    fn last_skips(&self) -> Vec<(MarketId, SkipReason)> {
+        self.lock().unwrap_or_else(|e| e.into_inner()).last_skips()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str_exact(s).unwrap()
+    }
+
+    #[test]
+    fn test_round_to_tick_half_up() {
+        let cases = [
+            ("0.123456789", "0.01", "0.12"),
+            ("0.125", "0.01", "0.13"),
+            ("0.124999", "0.01", "0.12"),
+            ("0.4975", "0.001", "0.498"),
+            ("0.4974", "0.001", "0.497"),
+            ("0.475", "0.05", "0.50"),
+            ("0.474", "0.05", "0.45"),
+            ("0.55", "0.1", "0.6"),
+            ("0.50", "0.01", "0.50"),
+        ];
+        for (price, tick, expected) in cases {
+            assert_eq!(round_to_tick(dec(price), dec(tick)), dec(expected), "{} at tick {}", price, tick);
+        }
+        assert_eq!(round_to_tick(dec("0.123456789"), Decimal::ZERO), dec("0.123456789"));
+    }
+}
@@ -3,12 +3,14 @@
 
 use super::super::{
     SignalGenerator, SignalInput, TradeSignal, SignalType, SignalDirection,
-    PriceSnapshot, SignalMetadata,
+    PriceSnapshot, SignalMetadata, Level, round_to_tick,
 };
+use crate::diagnostics::SkipReason;
 use anyhow::Result;
 use chrono::{Duration, Utc};
 use rust_decimal::prelude::*;
 use tracing::{debug, info};
+use common::{CostModel, MarketId, OrderSide, OutcomeId};
 use uuid::Uuid;
 
 /// Configuration for spread arbitrage signal generator
@@ -28,6 +30,9 @@ pub struct SpreadArbitrageConfig {
     pub target_pct: Decimal,
     /// Signal expiration time in hours
     pub signal_expiration_hours: i64,
+    /// Fees and slippage paid on entry and exit, walked through the input's
+    /// order book at the proposed size
+    pub costs: CostModel,
 }
 
 impl Default for SpreadArbitrageConfig {
@@ -40,6 +45,7 @@ impl Default for SpreadArbitrageConfig {
             stop_loss_pct: Decimal::from_str_exact("0.10").unwrap(), // 10%
             target_pct: Decimal::from_str_exact("0.15").unwrap(), // 15%
             signal_expiration_hours: 24,
+            costs: CostModel::default(),
         }
     }
 }
@@ -47,12 +53,14 @@ impl Default for SpreadArbitrageConfig {
 /// Spread arbitrage signal generator
 pub struct SpreadArbitrageGenerator {
     config: SpreadArbitrageConfig,
+    /// Why the last `generate` call dropped an opportunity
+    last_skip: Option<(MarketId, SkipReason)>,
 }
 
 impl SpreadArbitrageGenerator {
     /// Create a new spread arbitrage generator
     pub fn new(config: SpreadArbitrageConfig) -> Self {
-        Self { config }
+        Self { config, last_skip: None }
     }
 
     /// Create with default configuration
@@ -60,17 +68,51 @@ impl SpreadArbitrageGenerator {
         Self::new(SpreadArbitrageConfig::default())
     }
 
+    /// Round-trip cost per share of `size` of an outcome, in price units, and
+    /// the average price the entry would fill at
+    fn round_trip_cost(
+        &self,
+        input: &SignalInput,
+        outcome_id: &OutcomeId,
+        entry_price: Decimal,
+        size: Decimal,
+    ) -> (Decimal, Decimal) {
+        let levels = |levels: &[Level]| -> Vec<(f64, f64)> {
+            levels
+                .iter()
+                .filter(|l| l.outcome_id == outcome_id.as_str())
+                .map(|l| (l.price.to_f64().unwrap_or(0.0), l.size.to_f64().unwrap_or(0.0)))
+                .collect()
+        };
+        let (asks, bids) = input
+            .order_book
+            .as_ref()
+            .map(|book| (levels(&book.asks), levels(&book.bids)))
+            .unwrap_or_default();
+        let quote = entry_price.to_f64().unwrap_or(0.0);
+        let size = size.to_f64().unwrap_or(0.0);
+
+        let costs = &self.config.costs;
+        let cost = costs.round_trip_cost(&asks, &bids, quote, size);
+        let fill = costs.fill(OrderSide::Buy, &asks, quote, size);
+        // Well under a tick; keeps f64 noise out of the reported edge
+        let to_decimal = |x: f64| Decimal::from_f64(x).unwrap_or(Decimal::ZERO).round_dp(6).normalize();
+        (to_decimal(cost), to_decimal(fill.avg_price))
+    }
+
     /// Calculate expected value for a trade (in dollars)
     /// EV = (probability_of_win * win_amount) - (probability_of_loss * loss_amount)
+    /// where the round-trip `fee` comes off the win and adds to the loss
     fn calculate_ev(
         entry_price: Decimal,
         target_price: Decimal,
         stop_loss: Decimal,
         win_probability: f64,
         position_size: Decimal,
+        fee: Decimal,
     ) -> Decimal {
-        let win_amount = target_price - entry_price;
-        let loss_amount = entry_price - stop_loss;
+        let win_amount = target_price - entry_price - fee;
+        let loss_amount = entry_price - stop_loss + fee;
         let lose_probability = 1.0 - win_probability;
 
         // EV in price units, then multiply by position size for dollar EV
@@ -86,17 +128,19 @@ impl SpreadArbitrageGenerator {
     ///   b = odds received on the wager (win/loss ratio)
     ///   p = probability of winning
     ///   q = probability of losing (1 - p)
+    /// with win and loss net of the round-trip `fee`
     fn calculate_kelly_fraction(
         entry_price: Decimal,
         target_price: Decimal,
         stop_loss: Decimal,
         win_probability: f64,
         max_fraction: f64,
+        fee: Decimal,
     ) -> f64 {
-        let win_amount = target_price - entry_price;
-        let loss_amount = entry_price - stop_loss;
+        let win_amount = target_price - entry_price - fee;
+        let loss_amount = entry_price - stop_loss + fee;
 
-        if loss_amount == Decimal::ZERO {
+        if loss_amount == Decimal::ZERO || win_amount <= Decimal::ZERO {
             return 0.0;
         }
 
@@ -111,7 +155,7 @@ impl SpreadArbitrageGenerator {
     }
 
     /// Detect price spread across outcomes
-    fn detect_spread(&self, input: &SignalInput) -> Option<SpreadOpportunity> {
+    fn detect_spread(&mut self, input: &SignalInput) -> Option<SpreadOpportunity> {
         let market = &input.market;
 
         if market.outcomes.len() < 2 {
@@ -122,13 +166,13 @@ impl SpreadArbitrageGenerator {
         // Check for arbitrage: sum of probabilities < 1.0
         let total_prob: f64 = market.outcomes.iter().map(|o| o.price).sum();
 
-        // Calculate edge
-        let edge = Decimal::ONE - Decimal::from_f64(total_prob).unwrap_or(Decimal::ONE);
+        // Costs only take edge away, so a thin gross edge needs no book walk
+        let gross_edge = Decimal::ONE - Decimal::from_f64(total_prob).unwrap_or(Decimal::ONE);
 
-        if edge < self.config.min_edge {
+        if gross_edge < self.config.min_edge {
             debug!(
                 "Insufficient edge: {:.2}% (min: {:.2}%)",
-                edge * Decimal::from(100),
+                gross_edge * Decimal::from(100),
                 self.config.min_edge * Decimal::from(100)
             );
             return None;
@@ -162,10 +206,42 @@ impl SpreadArbitrageGenerator {
             return None;
         }
 
-        // Calculate prices
-        let entry_price = Decimal::from_f64(best_outcome.price).unwrap_or(Decimal::ZERO);
-        let stop_loss = entry_price * (Decimal::ONE - self.config.stop_loss_pct);
-        let target_price = entry_price * (Decimal::ONE + self.config.target_pct);
+        // Calculate prices, on the market's tick
+        let tick_size = input.tick_size();
+        let entry_price = round_to_tick(Decimal::from_f64(best_outcome.price).unwrap_or(Decimal::ZERO), tick_size);
+        let stop_loss = round_to_tick(entry_price * (Decimal::ONE - self.config.stop_loss_pct), tick_size);
+        let target_price = round_to_tick(entry_price * (Decimal::ONE + self.config.target_pct), tick_size);
+        if entry_price <= Decimal::ZERO {
+            return None;
+        }
+
+        // Costs are estimated at the size Kelly proposes before them; netting
+        // them out can only shrink the position, so this errs high
+        let proposed_size = self.config.default_position_size
+            * Decimal::from_f64(Self::calculate_kelly_fraction(
+                entry_price,
+                target_price,
+                stop_loss,
+                win_probability,
+                self.config.max_kelly_fraction,
+                Decimal::ZERO,
+            ))
+            .unwrap_or(Decimal::ZERO);
+        let (cost, entry_fill_price) = self.round_trip_cost(input, &best_outcome.id, entry_price, proposed_size);
+
+        let edge = gross_edge - cost / entry_price;
+        if edge <= Decimal::ZERO || edge < self.config.min_edge {
+            info!(
+                "Dropping spread opportunity on market {:?}: edge {:.2}% after costs (gross: {:.2}%, round trip: {} per share at size {})",
+                market.id,
+                edge * Decimal::from(100),
+                gross_edge * Decimal::from(100),
+                cost,
+                proposed_size
+            );
+            self.last_skip = Some((market.id, SkipReason::QualityGovernor { check: "edge_after_costs" }));
+            return None;
+        }
 
         // Calculate Kelly fraction
         let kelly_fraction = Self::calculate_kelly_fraction(
@@ -174,13 +250,14 @@ impl SpreadArbitrageGenerator {
             stop_loss,
             win_probability,
             self.config.max_kelly_fraction,
+            cost,
         );
 
         // Position size based on Kelly fraction
         let position_size = self.config.default_position_size * Decimal::from_f64(kelly_fraction).unwrap_or(Decimal::ZERO);
 
         // Calculate expected value using the position size
-        let expected_value = Self::calculate_ev(entry_price, target_price, stop_loss, win_probability, position_size);
+        let expected_value = Self::calculate_ev(entry_price, target_price, stop_loss, win_probability, position_size, cost);
 
         // Confidence based on edge, liquidity, and research confidence
         let edge_score = (edge / self.config.min_edge).to_f64().unwrap_or(1.0).min(2.0) / 2.0;
@@ -203,6 +280,9 @@ impl SpreadArbitrageGenerator {
             position_size,
             expected_value,
             edge,
+            gross_edge,
+            round_trip_cost: cost,
+            entry_fill_price,
             kelly_fraction,
             confidence,
             win_probability,
@@ -235,11 +315,13 @@ impl SpreadArbitrageGenerator {
 }
 
 impl SignalGenerator for SpreadArbitrageGenerator {
-    fn generate(&self, input: &SignalInput) -> Result<Option<TradeSignal>> {
+    fn generate(&mut self, input: &SignalInput) -> Result<Vec<TradeSignal>> {
+        self.last_skip = None;
+
         // Detect spread opportunity
         let opportunity = match self.detect_spread(input) {
             Some(opp) => opp,
-            None => return Ok(None),
+            None => return Ok(vec![]),
         };
 
         // Calculate volatility score
@@ -247,12 +329,12 @@ impl SignalGenerator for SpreadArbitrageGenerator {
 
         // Build reasoning
         let reasoning = format!(
-            "Spread arbitrage opportunity detected. Market total probability: {:.2}% (edge: {:.2}%). \
+            "Spread arbitrage opportunity detected. Market total probability: {:.2}% (edge: {:.2}% after costs). \
             Research confidence: {:.2}%. \
             Estimated win probability: {:.2}%. \
             Liquidity score: {:.2}. \
             Volatility: {:.2}.",
-            (Decimal::ONE - opportunity.edge) * Decimal::from(100),
+            (Decimal::ONE - opportunity.gross_edge) * Decimal::from(100),
             opportunity.edge * Decimal::from(100),
             input.research_output.confidence * 100.0,
             opportunity.win_probability * 100.0,
@@ -268,7 +350,17 @@ impl SignalGenerator for SpreadArbitrageGenerator {
         );
         custom_fields.insert(
             "total_market_probability".to_string(),
-            serde_json::json!((Decimal::ONE - opportunity.edge).to_string()),
+            serde_json::json!((Decimal::ONE - opportunity.gross_edge).to_string()),
+        );
+        custom_fields.insert("gross_edge".to_string(), serde_json::json!(opportunity.gross_edge.to_string()));
+        custom_fields.insert("fee_bps".to_string(), serde_json::json!(self.config.costs.taker_fee_bps));
+        custom_fields.insert(
+            "round_trip_cost".to_string(),
+            serde_json::json!(opportunity.round_trip_cost.to_string()),
+        );
+        custom_fields.insert(
+            "entry_fill_price".to_string(),
+            serde_json::json!(opportunity.entry_fill_price.to_string()),
         );
 
         // Create signal
@@ -297,13 +389,24 @@ impl SignalGenerator for SpreadArbitrageGenerator {
                 data_points: input.price_history.len() as u32,
                 liquidity_score: opportunity.liquidity_score,
                 volatility_score,
+                price_age_ms: None,
+                orderbook_age_ms: None,
+                research_age_ms: None,
                 custom_fields: serde_json::Value::Object(custom_fields),
             },
             created_at: Utc::now(),
             expires_at: Some(Utc::now() + Duration::hours(self.config.signal_expiration_hours)),
+            variant: None,
         };
 
-        Ok(Some(signal))
+        Ok(vec![signal])
+    }
+
+    fn skip_reason(&self, input: &SignalInput) -> Option<SkipReason> {
+        self.last_skip
+            .as_ref()
+            .filter(|(market_id, _)| *market_id == input.market.id)
+            .map(|(_, reason)| reason.clone())
     }
 
     fn signal_type(&self) -> SignalType {
@@ -314,14 +417,19 @@ impl SignalGenerator for SpreadArbitrageGenerator {
 /// Spread opportunity detected
 #[derive(Debug, Clone)]
 struct SpreadOpportunity {
-    market_id: Uuid,
-    outcome_id: String,
+    market_id: MarketId,
+    outcome_id: OutcomeId,
     entry_price: Decimal,
     target_price: Decimal,
     stop_loss: Decimal,
     position_size: Decimal,
     expected_value: Decimal,
+    /// Edge after round-trip costs
     edge: Decimal,
+    gross_edge: Decimal,
+    /// Per share, in price units
+    round_trip_cost: Decimal,
+    entry_fill_price: Decimal,
     kelly_fraction: f64,
     confidence: f64,
     win_probability: f64,
@@ -332,6 +440,21 @@ struct SpreadOpportunity {
 mod tests {
     use super::*;
 
+    /// Two-outcome market with research putting YES at `probability`
+    fn binary_input(yes: f64, no: f64, probability: f64) -> SignalInput {
+        use common::Outcome;
+
+        crate::test_support::input(MarketId::random())
+            .market(|market| {
+                market.outcomes = vec![
+                    Outcome { id: "yes".into(), name: "Yes".to_string(), price: yes, liquidity: 8000.0 },
+                    Outcome { id: "no".into(), name: "No".to_string(), price: no, liquidity: 6000.0 },
+                ]
+            })
+            .research(|research| research.probability_estimate = Some(probability))
+            .build()
+    }
+
     #[test]
     fn test_calculate_ev() {
         let entry = Decimal::from_str_exact("0.5").unwrap();
@@ -340,7 +463,7 @@ mod tests {
         let win_prob = 0.6;
         let position_size = Decimal::from_str_exact("100.0").unwrap();
 
-        let ev = SpreadArbitrageGenerator::calculate_ev(entry, target, stop, win_prob, position_size);
+        let ev = SpreadArbitrageGenerator::calculate_ev(entry, target, stop, win_prob, position_size, Decimal::ZERO);
 
         // EV = ((0.6 * 0.1) - (0.4 * 0.1)) * 100 = (0.06 - 0.04) * 100 = 2.0
         assert!((ev - Decimal::from_str_exact("2.0").unwrap()).abs() < Decimal::from_str_exact("0.1").unwrap());
@@ -353,7 +476,7 @@ mod tests {
         let stop = Decimal::from_str_exact("0.4").unwrap();
         let win_prob = 0.6;
 
-        let kelly = SpreadArbitrageGenerator::calculate_kelly_fraction(entry, target, stop, win_prob, 0.5);
+        let kelly = SpreadArbitrageGenerator::calculate_kelly_fraction(entry, target, stop, win_prob, 0.5, Decimal::ZERO);
 
         // b = 0.1/0.1 = 1.0
         // f* = (1.0 * 0.6 - 0.4) / 1.0 = 0.2
@@ -368,7 +491,7 @@ mod tests {
         let stop = Decimal::from_str_exact("0.4").unwrap();
         let win_prob = 0.8;
 
-        let kelly = SpreadArbitrageGenerator::calculate_kelly_fraction(entry, target, stop, win_prob, 0.1);
+        let kelly = SpreadArbitrageGenerator::calculate_kelly_fraction(entry, target, stop, win_prob, 0.1, Decimal::ZERO);
 
         // Even though the formula would suggest higher Kelly, it should be capped
         assert!(kelly <= 0.1);
@@ -381,9 +504,138 @@ mod tests {
         let stop = Decimal::from_str_exact("0.45").unwrap();
         let win_prob = 0.4;
 
-        let kelly = SpreadArbitrageGenerator::calculate_kelly_fraction(entry, target, stop, win_prob, 0.1);
+        let kelly = SpreadArbitrageGenerator::calculate_kelly_fraction(entry, target, stop, win_prob, 0.1, Decimal::ZERO);
 
         // With low win probability, Kelly should be 0 or very close
         assert!(kelly >= 0.0);
     }
+
+    #[test]
+    fn test_fees_reduce_ev() {
+        let entry = Decimal::from_str_exact("0.5").unwrap();
+        let target = Decimal::from_str_exact("0.6").unwrap();
+        let stop = Decimal::from_str_exact("0.4").unwrap();
+        let position_size = Decimal::from(100);
+        let fee = Decimal::from_f64(CostModel::new().with_taker_fee_bps(100.0).round_trip_cost(&[], &[], 0.5, 100.0))
+            .unwrap()
+            .round_dp(6);
+        assert_eq!(fee, Decimal::from_str_exact("0.01").unwrap());
+
+        // ((0.6 * 0.09) - (0.4 * 0.11)) * 100 = 1.0
+        let ev = SpreadArbitrageGenerator::calculate_ev(entry, target, stop, 0.6, position_size, fee);
+        assert!((ev - Decimal::ONE).abs() < Decimal::from_str_exact("0.0001").unwrap());
+
+        // Profitable before fees, a loser after
+        let gross = SpreadArbitrageGenerator::calculate_ev(entry, target, stop, 0.52, position_size, Decimal::ZERO);
+        let net = SpreadArbitrageGenerator::calculate_ev(entry, target, stop, 0.52, position_size, fee);
+        assert!(gross > Decimal::ZERO);
+        assert!(net < Decimal::ZERO);
+        assert!(SpreadArbitrageGenerator::calculate_kelly_fraction(entry, target, stop, 0.52, 0.5, Decimal::ZERO) > 0.0);
+        assert_eq!(SpreadArbitrageGenerator::calculate_kelly_fraction(entry, target, stop, 0.52, 0.5, fee), 0.0);
+    }
+
+    #[test]
+    fn test_fees_suppress_thin_edge() {
+        // YES 0.55 + NO 0.39: 6% gross edge
+        let input = binary_input(0.55, 0.39, 0.65);
+
+        let signals = SpreadArbitrageGenerator::default().generate(&input).unwrap();
+        assert_eq!(signals.len(), 1);
+        assert!(signals[0].expected_value > Decimal::ZERO);
+
+        // 1% a side: 4% net edge is under the 5% minimum
+        let mut with_fees = SpreadArbitrageGenerator::new(SpreadArbitrageConfig {
+            costs: CostModel::new().with_taker_fee_bps(100.0),
+            ..SpreadArbitrageConfig::default()
+        });
+        assert!(with_fees.generate(&input).unwrap().is_empty());
+        assert_eq!(
+            with_fees.skip_reason(&input),
+            Some(SkipReason::QualityGovernor { check: "edge_after_costs" })
+        );
+
+        // A smaller fee passes, with the edge reported net
+        let mut low_fees = SpreadArbitrageGenerator::new(SpreadArbitrageConfig {
+            costs: CostModel::new().with_taker_fee_bps(25.0),
+            ..SpreadArbitrageConfig::default()
+        });
+        let signals = low_fees.generate(&input).unwrap();
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].edge, Decimal::from_str_exact("0.055").unwrap());
+        assert_eq!(signals[0].metadata.custom_fields["gross_edge"], "0.06");
+    }
+
+    #[test]
+    fn test_prices_land_on_the_market_tick() {
+        let mut input = binary_input(0.50, 0.40, 0.7);
+        let config = SpreadArbitrageConfig {
+            min_edge: Decimal::from_str_exact("0.02").unwrap(),
+            ..SpreadArbitrageConfig::default()
+        };
+
+        // The 0.575 target rounds half up to a cent by default
+        let signal = &SpreadArbitrageGenerator::new(config.clone()).generate(&input).unwrap()[0];
+        assert_eq!(signal.entry_price, Decimal::from_str_exact("0.50").unwrap());
+        assert_eq!(signal.target_price, Decimal::from_str_exact("0.58").unwrap());
+        assert_eq!(signal.stop_loss, Decimal::from_str_exact("0.45").unwrap());
+
+        input.market.tick_size = Some(0.05);
+        let signal = &SpreadArbitrageGenerator::new(config).generate(&input).unwrap()[0];
+        assert_eq!(signal.target_price, Decimal::from_str_exact("0.60").unwrap());
+        assert_eq!(signal.stop_loss, Decimal::from_str_exact("0.45").unwrap());
+    }
+
+    #[test]
+    fn test_thin_book_costs_reduce_ev() {
+        use crate::signals::OrderBookSnapshot;
+
+        // 10% gross edge; Kelly caps the proposed size at 1000 shares of YES
+        let mut input = binary_input(0.50, 0.40, 0.7);
+        // A 0.001 tick keeps the 0.575 target off the 0.58 a cent tick gives
+        input.market.tick_size = Some(0.001);
+        let config = SpreadArbitrageConfig {
+            min_edge: Decimal::from_str_exact("0.02").unwrap(),
+            default_position_size: Decimal::from(10_000),
+            ..SpreadArbitrageConfig::default()
+        };
+        let tolerance = Decimal::from_str_exact("0.0001").unwrap();
+
+        // Filled at the quote: ((0.7 * 0.075) - (0.3 * 0.05)) * 1000
+        let signals = SpreadArbitrageGenerator::new(config.clone()).generate(&input).unwrap();
+        assert_eq!(signals[0].position_size, Decimal::from(1000));
+        assert!((signals[0].expected_value - Decimal::from_str_exact("37.5").unwrap()).abs() < tolerance);
+
+        let level = |price: &str, size: i64| Level {
+            outcome_id: "yes".to_string(),
+            price: Decimal::from_str_exact(price).unwrap(),
+            size: Decimal::from(size),
+        };
+        input.order_book = Some(OrderBookSnapshot {
+            market_id: input.market.id,
+            bids: vec![level("0.49", 400), level("0.47", 2000)],
+            asks: vec![level("0.50", 400), level("0.51", 400), level("0.53", 1000)],
+            timestamp: Utc::now(),
+        });
+
+        // Buying 1000 takes the asks up two levels to a 0.51 average; selling
+        // them back averages 0.478, so the round trip costs 0.032 a share
+        let signals = SpreadArbitrageGenerator::new(config.clone()).generate(&input).unwrap();
+        let fields = &signals[0].metadata.custom_fields;
+        assert_eq!(fields["entry_fill_price"], "0.51");
+        assert_eq!(fields["round_trip_cost"], "0.032");
+        // ((0.7 * 0.043) - (0.3 * 0.082)) * 1000, on 10% - 0.032 / 0.50 of edge
+        assert!((signals[0].expected_value - Decimal::from_str_exact("5.5").unwrap()).abs() < tolerance);
+        assert_eq!(signals[0].edge, Decimal::from_str_exact("0.036").unwrap());
+
+        // 3% fees on top push the edge below zero
+        let mut expensive = SpreadArbitrageGenerator::new(SpreadArbitrageConfig {
+            costs: CostModel::new().with_taker_fee_bps(300.0),
+            ..config
+        });
+        assert!(expensive.generate(&input).unwrap().is_empty());
+        assert_eq!(
+            expensive.skip_reason(&input),
+            Some(SkipReason::QualityGovernor { check: "edge_after_costs" })
+        );
+    }
 }
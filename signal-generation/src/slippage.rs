@@ -0,0 +1,185 @@
+// Slippage Model
+// Adjusts signal entry price and size for the depth actually available in the book
+
+use rust_decimal::Decimal;
+
+use crate::signals::{Level, OrderBookSnapshot, SignalDirection, TradeSignal};
+
+/// Slippage model configuration
+#[derive(Debug, Clone)]
+pub struct SlippageModelConfig {
+    /// Best levels counted as available depth; 1 is top of book
+    pub depth_levels: usize,
+    /// Slippage in bps when the order takes the whole counted depth; scales
+    /// linearly with size / depth
+    pub impact_bps_at_full_depth: Decimal,
+    /// Largest share of the counted depth a signal may take
+    pub max_depth_fraction: Decimal,
+}
+
+impl Default for SlippageModelConfig {
+    fn default() -> Self {
+        Self {
+            depth_levels: 1,
+            impact_bps_at_full_depth: Decimal::from(100),
+            max_depth_fraction: Decimal::new(5, 1),
+        }
+    }
+}
+
+/// Linear market-impact model applied to signals before validation.
+///
+/// A Long takes the asks and a Short the bids. The signal's size is capped at
+/// `max_depth_fraction` of that side's depth, and its entry price is moved
+/// against it by `impact_bps_at_full_depth * size / depth`, rounded to 4 dp.
+/// Expected value is rescaled to the new size and charged the slippage.
+/// Signals on an empty side are left alone.
+#[derive(Debug, Clone, Default)]
+pub struct SlippageModel {
+    config: SlippageModelConfig,
+}
+
+impl SlippageModel {
+    pub fn new(config: SlippageModelConfig) -> Self {
+        Self { config }
+    }
+
+    /// Depth on the side `direction` trades against
+    fn depth(&self, direction: &SignalDirection, order_book: &OrderBookSnapshot) -> Option<Decimal> {
+        let mut levels: Vec<&Level> = match direction {
+            SignalDirection::Long => order_book.asks.iter().collect(),
+            SignalDirection::Short => order_book.bids.iter().collect(),
+            SignalDirection::Neutral => return None,
+        };
+        match direction {
+            SignalDirection::Long => levels.sort_by_key(|l| l.price),
+            _ => levels.sort_by_key(|l| std::cmp::Reverse(l.price)),
+        }
+        let depth: Decimal = levels.iter().take(self.config.depth_levels).map(|l| l.size).sum();
+        (depth > Decimal::ZERO).then_some(depth)
+    }
+
+    /// Adjust `signal` for the book it was generated on
+    pub fn apply(&self, signal: &mut TradeSignal, order_book: &OrderBookSnapshot) {
+        let Some(depth) = self.depth(&signal.direction, order_book) else {
+            return;
+        };
+
+        let requested_size = signal.position_size;
+        let size = requested_size.min(depth * self.config.max_depth_fraction);
+        let slippage_bps = self.config.impact_bps_at_full_depth * size / depth;
+        let slippage = signal.entry_price * slippage_bps / Decimal::from(10_000);
+        let model_entry = signal.entry_price;
+        let entry = match signal.direction {
+            SignalDirection::Short => model_entry - slippage,
+            _ => model_entry + slippage,
+        }
+        .round_dp(4);
+
+        if requested_size > Decimal::ZERO {
+            signal.expected_value = signal.expected_value * size / requested_size - (entry - model_entry).abs() * size;
+        }
+        signal.entry_price = entry;
+        signal.position_size = size;
+
+        if !signal.metadata.custom_fields.is_object() {
+            signal.metadata.custom_fields = serde_json::json!({});
+        }
+        signal.metadata.custom_fields["slippage"] = serde_json::json!({
+            "model_entry": model_entry.to_string(),
+            "slippage_bps": slippage_bps.round_dp(2).normalize().to_string(),
+            "depth": depth.to_string(),
+            "requested_size": requested_size.to_string(),
+            "capped": size < requested_size,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signals::{SignalMetadata, SignalType};
+    use chrono::Utc;
+    use common::MarketId;
+    use uuid::Uuid;
+
+    fn signal(direction: SignalDirection) -> TradeSignal {
+        TradeSignal {
+            id: Uuid::new_v4(),
+            market_id: MarketId::random(),
+            signal_type: SignalType::Momentum,
+            direction,
+            outcome_id: Some("yes".into()),
+            entry_price: Decimal::new(50, 2),
+            target_price: Decimal::new(55, 2),
+            stop_loss: Decimal::new(45, 2),
+            position_size: Decimal::from(100),
+            confidence: 0.7,
+            expected_value: Decimal::from(2),
+            edge: Decimal::new(4, 2),
+            kelly_fraction: 0.1,
+            reasoning: String::new(),
+            metadata: SignalMetadata {
+                research_sources: vec![],
+                data_points: 0,
+                liquidity_score: 0.5,
+                volatility_score: 0.0,
+                price_age_ms: None,
+                orderbook_age_ms: None,
+                research_age_ms: None,
+                custom_fields: serde_json::json!({ "strategy": "test" }),
+            },
+            created_at: Utc::now(),
+            expires_at: None,
+            variant: None,
+        }
+    }
+
+    fn book(bid_size: i64, ask_size: i64) -> OrderBookSnapshot {
+        let level = |price, size| Level {
+            outcome_id: "yes".to_string(),
+            price,
+            size: Decimal::from(size),
+        };
+        OrderBookSnapshot {
+            market_id: MarketId::random(),
+            bids: vec![level(Decimal::new(49, 2), bid_size), level(Decimal::new(48, 2), 10_000)],
+            asks: vec![level(Decimal::new(51, 2), ask_size), level(Decimal::new(52, 2), 10_000)],
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_deep_book_leaves_signal_alone() {
+        let mut long = signal(SignalDirection::Long);
+        SlippageModel::default().apply(&mut long, &book(100_000, 100_000));
+
+        assert_eq!(long.entry_price, Decimal::new(50, 2));
+        assert_eq!(long.position_size, Decimal::from(100));
+        assert_eq!(long.expected_value, Decimal::from(2));
+        assert_eq!(long.metadata.custom_fields["slippage"]["capped"], false);
+        assert_eq!(long.metadata.custom_fields["strategy"], "test");
+    }
+
+    #[test]
+    fn test_thin_book_caps_size_and_worsens_entry() {
+        // 80 on the best ask: capped to 40, which is half the depth: 50 bps
+        let mut long = signal(SignalDirection::Long);
+        SlippageModel::default().apply(&mut long, &book(100_000, 80));
+
+        assert_eq!(long.position_size, Decimal::from(40));
+        assert_eq!(long.entry_price, Decimal::new(5025, 4));
+        // 2 * 40/100 - 0.0025 * 40
+        assert_eq!(long.expected_value, Decimal::new(7, 1));
+        let slippage = &long.metadata.custom_fields["slippage"];
+        assert_eq!(slippage["capped"], true);
+        assert_eq!(slippage["slippage_bps"], "50");
+        assert_eq!(slippage["requested_size"], "100");
+
+        // A Short sells into the bids, lower
+        let mut short = signal(SignalDirection::Short);
+        SlippageModel::default().apply(&mut short, &book(80, 100_000));
+        assert_eq!(short.position_size, Decimal::from(40));
+        assert_eq!(short.entry_price, Decimal::new(4975, 4));
+    }
+}
@@ -142,10 +142,10 @@ impl SpreadArbitrageGenerator {
 }
 
 impl crate::signals::SignalGenerator for SpreadArbitrageGenerator {
-    fn generate(&self, input: &SignalInput) -> anyhow::Result<Option<TradeSignal>> {
+    fn generate(&mut self, input: &SignalInput) -> anyhow::Result<Vec<TradeSignal>> {
         let opportunity = match self.calculate_spread_opportunity(input) {
             Some(opp) => opp,
-            None => return Ok(None),
+            None => return Ok(vec![]),
         };
 
         // Check thresholds
@@ -155,7 +155,7 @@ impl crate::signals::SignalGenerator for SpreadArbitrageGenerator {
                 opportunity.confidence,
                 self.min_confidence
             );
-            return Ok(None);
+            return Ok(vec![]);
         }
 
         // Get outcome details
@@ -174,7 +174,7 @@ impl crate::signals::SignalGenerator for SpreadArbitrageGenerator {
                 outcome_liquidity,
                 self.min_liquidity
             );
-            return Ok(None);
+            return Ok(vec![]);
         }
 
         // Calculate position size based on Kelly
@@ -203,6 +203,9 @@ impl crate::signals::SignalGenerator for SpreadArbitrageGenerator {
             data_points: input.price_history.len() as u32,
             liquidity_score: opportunity.liquidity_score,
             volatility_score: 0.5, // Placeholder - could calculate from history
+            price_age_ms: None,
+            orderbook_age_ms: None,
+            research_age_ms: None,
             custom_fields: serde_json::json!({
                 "spread_edge": opportunity.edge.to_string(),
                 "win_probability": opportunity.win_probability.to_string(),
@@ -228,6 +231,7 @@ impl crate::signals::SignalGenerator for SpreadArbitrageGenerator {
             metadata,
             created_at: Utc::now(),
             expires_at: Some(Utc::now() + Duration::hours(1)), // Signal valid for 1 hour
+            variant: None,
         };
 
         tracing::info!(
@@ -237,7 +241,7 @@ impl crate::signals::SignalGenerator for SpreadArbitrageGenerator {
             signal.confidence
         );
 
-        Ok(Some(signal))
+        Ok(vec![signal])
     }
 
     fn signal_type(&self) -> SignalType {
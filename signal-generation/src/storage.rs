@@ -1,10 +1,11 @@
 // Signal Storage Interface
 // Provides persistence for signals for backtesting and analysis
 
-use super::signals::TradeSignal;
+use super::signals::{SignalType, TradeSignal};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use common::MarketId;
 use uuid::Uuid;
 use std::collections::HashMap;
 
@@ -14,11 +15,20 @@ pub trait SignalStorage: Send + Sync {
     /// Store a signal
     async fn store(&self, signal: &TradeSignal) -> Result<()>;
 
+    /// Store several signals; backends that can write them in one round trip
+    /// should override this
+    async fn store_batch(&self, signals: &[TradeSignal]) -> Result<()> {
+        for signal in signals {
+            self.store(signal).await?;
+        }
+        Ok(())
+    }
+
     /// Retrieve a signal by ID
     async fn get(&self, signal_id: Uuid) -> Result<Option<TradeSignal>>;
 
     /// Retrieve all signals for a market
-    async fn get_by_market(&self, market_id: Uuid) -> Result<Vec<TradeSignal>>;
+    async fn get_by_market(&self, market_id: MarketId) -> Result<Vec<TradeSignal>>;
 
     /// Retrieve signals within a time range
     async fn get_by_time_range(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<TradeSignal>>;
@@ -78,7 +88,7 @@ impl SignalStorage for InMemoryStorage {
         Ok(signals.get(&signal_id).cloned())
     }
 
-    async fn get_by_market(&self, market_id: Uuid) -> Result<Vec<TradeSignal>> {
+    async fn get_by_market(&self, market_id: MarketId) -> Result<Vec<TradeSignal>> {
         let signals = self.signals.read().await;
         let market_signals = signals
             .values()
@@ -152,16 +162,23 @@ impl SignalStorage for InMemoryStorage {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SignalExecutionResult {
     pub signal_id: Uuid,
-    pub market_id: Uuid,
+    pub market_id: MarketId,
     pub outcome_id: Option<String>,
     pub executed_at: DateTime<Utc>,
     pub entry_price: rust_decimal::Decimal,
     pub exit_price: Option<rust_decimal::Decimal>,
     pub position_size: rust_decimal::Decimal,
+    /// Trading P&L, excluding any liquidity rebate
     pub pnl: Option<rust_decimal::Decimal>,
     pub pnl_percentage: Option<rust_decimal::Decimal>,
     pub holding_period_hours: Option<f64>,
     pub exit_reason: ExitReason,
+    /// Liquidity rebate earned by the fill, if it was a maker fill
+    #[serde(default)]
+    pub rebate_pnl: Option<rust_decimal::Decimal>,
+    /// Type of the signal that was executed, for per-type stats
+    #[serde(default)]
+    pub signal_type: Option<SignalType>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -184,7 +201,7 @@ pub trait ExecutionStorage: Send + Sync {
     async fn get_by_signal(&self, signal_id: Uuid) -> Result<Option<SignalExecutionResult>>;
 
     /// Get all execution results for a market
-    async fn get_by_market(&self, market_id: Uuid) -> Result<Vec<SignalExecutionResult>>;
+    async fn get_by_market(&self, market_id: MarketId) -> Result<Vec<SignalExecutionResult>>;
 
     /// Get backtesting statistics
     async fn get_backtest_stats(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<BacktestStats>;
@@ -199,7 +216,10 @@ pub struct BacktestStats {
     pub winning_trades: usize,
     pub losing_trades: usize,
     pub win_rate: f64,
+    /// Trading P&L, excluding rebates; wins and losses are judged on it too
     pub total_pnl: rust_decimal::Decimal,
+    /// Liquidity rebates earned by maker fills
+    pub rebate_pnl: rust_decimal::Decimal,
     pub average_pnl: rust_decimal::Decimal,
     pub average_win: rust_decimal::Decimal,
     pub average_loss: rust_decimal::Decimal,
@@ -217,57 +237,11 @@ pub struct SignalTypeStats {
     pub average_pnl: rust_decimal::Decimal,
 }
 
-/// In-memory execution storage (for backtesting)
-pub struct InMemoryExecutionStorage {
-    results: tokio::sync::RwLock<HashMap<Uuid, SignalExecutionResult>>,
-}
-
-impl InMemoryExecutionStorage {
-    pub fn new() -> Self {
-        Self {
-            results: tokio::sync::RwLock::new(HashMap::new()),
-        }
-    }
-}
-
-impl Default for InMemoryExecutionStorage {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-#[async_trait::async_trait]
-impl ExecutionStorage for InMemoryExecutionStorage {
-    async fn store(&self, result: &SignalExecutionResult) -> Result<()> {
-        let mut results = self.results.write().await;
-        results.insert(result.signal_id, result.clone());
-        Ok(())
-    }
-
-    async fn get_by_signal(&self, signal_id: Uuid) -> Result<Option<SignalExecutionResult>> {
-        let results = self.results.read().await;
-        Ok(results.get(&signal_id).cloned())
-    }
-
-    async fn get_by_market(&self, market_id: Uuid) -> Result<Vec<SignalExecutionResult>> {
-        let results = self.results.read().await;
-        let market_results = results
-            .values()
-            .filter(|r| r.market_id == market_id)
-            .cloned()
-            .collect();
-        Ok(market_results)
-    }
-
-    async fn get_backtest_stats(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<BacktestStats> {
-        let results = self.results.read().await;
-
-        let filtered: Vec<_> = results
-            .values()
-            .filter(|r| r.executed_at >= start && r.executed_at <= end)
-            .filter(|r| r.exit_price.is_some())
-            .cloned()
-            .collect();
+impl BacktestStats {
+    /// Stats over the closed trades among `results`, which the caller has
+    /// already limited to the period
+    pub fn from_results(start: DateTime<Utc>, end: DateTime<Utc>, results: &[SignalExecutionResult]) -> Self {
+        let filtered: Vec<_> = results.iter().filter(|r| r.exit_price.is_some()).collect();
 
         let total_trades = filtered.len();
         let winning_trades = filtered.iter().filter(|r| r.pnl.map_or(false, |p| p > rust_decimal::Decimal::ZERO)).count();
@@ -283,6 +257,11 @@ impl ExecutionStorage for InMemoryExecutionStorage {
             .filter_map(|r| r.pnl)
             .fold(rust_decimal::Decimal::ZERO, |acc, p| acc + p);
 
+        let rebate_pnl = filtered
+            .iter()
+            .filter_map(|r| r.rebate_pnl)
+            .fold(rust_decimal::Decimal::ZERO, |acc, p| acc + p);
+
         let average_pnl = if total_trades > 0 {
             total_pnl / rust_decimal::Decimal::from(total_trades as i64)
         } else {
@@ -313,10 +292,34 @@ impl ExecutionStorage for InMemoryExecutionStorage {
             rust_decimal::Decimal::ZERO
         };
 
+        let mut by_signal_type: HashMap<String, SignalTypeStats> = HashMap::new();
+        for result in &filtered {
+            let Some(signal_type) = &result.signal_type else {
+                continue;
+            };
+            let stats = by_signal_type.entry(signal_type.to_string()).or_insert(SignalTypeStats {
+                total_trades: 0,
+                winning_trades: 0,
+                win_rate: 0.0,
+                total_pnl: rust_decimal::Decimal::ZERO,
+                average_pnl: rust_decimal::Decimal::ZERO,
+            });
+            let pnl = result.pnl.unwrap_or_default();
+            stats.total_trades += 1;
+            stats.total_pnl += pnl;
+            if pnl > rust_decimal::Decimal::ZERO {
+                stats.winning_trades += 1;
+            }
+        }
+        for stats in by_signal_type.values_mut() {
+            stats.win_rate = stats.winning_trades as f64 / stats.total_trades as f64;
+            stats.average_pnl = stats.total_pnl / rust_decimal::Decimal::from(stats.total_trades as i64);
+        }
+
         // Calculate max drawdown (simplified)
         let max_drawdown = rust_decimal::Decimal::ZERO; // TODO: Implement proper drawdown calculation
 
-        Ok(BacktestStats {
+        BacktestStats {
             period_start: start,
             period_end: end,
             total_trades,
@@ -324,13 +327,67 @@ impl ExecutionStorage for InMemoryExecutionStorage {
             losing_trades,
             win_rate,
             total_pnl,
+            rebate_pnl,
             average_pnl,
             average_win,
             average_loss,
             max_drawdown,
             sharpe_ratio: None, // TODO: Implement Sharpe ratio calculation
-            by_signal_type: HashMap::new(), // TODO: Implement per-signal-type stats
-        })
+            by_signal_type,
+        }
+    }
+}
+
+/// In-memory execution storage (for backtesting)
+pub struct InMemoryExecutionStorage {
+    results: tokio::sync::RwLock<HashMap<Uuid, SignalExecutionResult>>,
+}
+
+impl InMemoryExecutionStorage {
+    pub fn new() -> Self {
+        Self {
+            results: tokio::sync::RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl Default for InMemoryExecutionStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl ExecutionStorage for InMemoryExecutionStorage {
+    async fn store(&self, result: &SignalExecutionResult) -> Result<()> {
+        let mut results = self.results.write().await;
+        results.insert(result.signal_id, result.clone());
+        Ok(())
+    }
+
+    async fn get_by_signal(&self, signal_id: Uuid) -> Result<Option<SignalExecutionResult>> {
+        let results = self.results.read().await;
+        Ok(results.get(&signal_id).cloned())
+    }
+
+    async fn get_by_market(&self, market_id: MarketId) -> Result<Vec<SignalExecutionResult>> {
+        let results = self.results.read().await;
+        let market_results = results
+            .values()
+            .filter(|r| r.market_id == market_id)
+            .cloned()
+            .collect();
+        Ok(market_results)
+    }
+
+    async fn get_backtest_stats(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<BacktestStats> {
+        let results = self.results.read().await;
+        let in_range: Vec<_> = results
+            .values()
+            .filter(|r| r.executed_at >= start && r.executed_at <= end)
+            .cloned()
+            .collect();
+        Ok(BacktestStats::from_results(start, end, &in_range))
     }
 }
 
@@ -346,10 +403,10 @@ mod tests {
 
         let signal = TradeSignal {
             id: Uuid::new_v4(),
-            market_id: Uuid::new_v4(),
+            market_id: MarketId::random(),
             signal_type: SignalType::SpreadArbitrage,
             direction: SignalDirection::Long,
-            outcome_id: Some("test".to_string()),
+            outcome_id: Some("test".into()),
             entry_price: rust_decimal::Decimal::from_f64(0.5).unwrap(),
             target_price: rust_decimal::Decimal::from_f64(0.6).unwrap(),
             stop_loss: rust_decimal::Decimal::from_f64(0.4).unwrap(),
@@ -364,10 +421,14 @@ mod tests {
                 data_points: 10,
                 liquidity_score: 0.5,
                 volatility_score: 0.5,
+                price_age_ms: None,
+                orderbook_age_ms: None,
+                research_age_ms: None,
                 custom_fields: serde_json::json!({}),
             },
             created_at: Utc::now(),
             expires_at: None,
+            variant: None,
         };
 
         storage.store(&signal).await.unwrap();
@@ -378,4 +439,42 @@ mod tests {
         let stats = storage.stats().await.unwrap();
         assert_eq!(stats.total_signals, 1);
     }
+
+    #[tokio::test]
+    async fn test_backtest_stats_separate_rebates() {
+        let storage = InMemoryExecutionStorage::new();
+        let execution = |pnl: &str, rebate: Option<&str>| SignalExecutionResult {
+            signal_id: Uuid::new_v4(),
+            market_id: MarketId::random(),
+            outcome_id: Some("yes".into()),
+            executed_at: Utc::now(),
+            entry_price: Decimal::from_str("0.5").unwrap(),
+            exit_price: Some(Decimal::from_str("0.5").unwrap()),
+            position_size: Decimal::from(100),
+            pnl: Some(Decimal::from_str(pnl).unwrap()),
+            pnl_percentage: None,
+            holding_period_hours: None,
+            exit_reason: ExitReason::TargetHit,
+            rebate_pnl: rebate.map(|r| Decimal::from_str(r).unwrap()),
+            signal_type: Some(SignalType::Momentum),
+        };
+
+        // Unprofitable ex-rebates, profitable with them
+        storage.store(&execution("-1.5", Some("1.0"))).await.unwrap();
+        storage.store(&execution("0.5", Some("1.0"))).await.unwrap();
+        storage.store(&execution("-0.5", None)).await.unwrap();
+
+        let stats = storage
+            .get_backtest_stats(Utc::now() - chrono::Duration::hours(1), Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(stats.total_pnl, Decimal::from_str("-1.5").unwrap());
+        assert_eq!(stats.rebate_pnl, Decimal::from(2));
+        assert_eq!(stats.winning_trades, 1);
+
+        let momentum = &stats.by_signal_type["Momentum"];
+        assert_eq!(momentum.total_trades, 3);
+        assert_eq!(momentum.winning_trades, 1);
+        assert_eq!(momentum.average_pnl, Decimal::from_str("-0.5").unwrap());
+    }
 }
@@ -0,0 +1,100 @@
+// Test Support
+// Fixtures shared by the unit tests
+
+use chrono::Utc;
+use common::{Market, MarketId, Outcome};
+use rust_decimal::Decimal;
+
+use crate::signals::{Level, OrderBookSnapshot, PriceSnapshot, ResearchOutput, SentimentScore, SignalInput};
+
+/// A "test" politics market with one YES outcome at 0.50
+pub(crate) fn test_market(market_id: MarketId) -> Market {
+    Market {
+        id: market_id,
+        condition_id: "test".into(),
+        question: "test".to_string(),
+        description: String::new(),
+        category: "politics".to_string(),
+        outcomes: vec![Outcome {
+            id: "yes".into(),
+            name: "Yes".to_string(),
+            price: 0.5,
+            liquidity: 5000.0,
+        }],
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        neg_risk_group: None,
+        tick_size: None,
+    }
+}
+
+/// Builds a `SignalInput` for `test_market`, with neutral research at 0.8
+/// confidence and no order book or price history until given
+pub(crate) fn input(market_id: MarketId) -> InputBuilder {
+    InputBuilder {
+        input: SignalInput {
+            market: test_market(market_id),
+            research_output: ResearchOutput {
+                market_id,
+                analysis: String::new(),
+                sentiment: SentimentScore { overall: 0.0, sources: vec![] },
+                confidence: 0.8,
+                probability_estimate: None,
+                key_factors: vec![],
+                timestamp: Utc::now(),
+            },
+            order_book: None,
+            price_history: vec![],
+        },
+    }
+}
+
+pub(crate) struct InputBuilder {
+    input: SignalInput,
+}
+
+impl InputBuilder {
+    pub(crate) fn market(mut self, edit: impl FnOnce(&mut Market)) -> Self {
+        edit(&mut self.input.market);
+        self
+    }
+
+    pub(crate) fn research(mut self, edit: impl FnOnce(&mut ResearchOutput)) -> Self {
+        edit(&mut self.input.research_output);
+        self
+    }
+
+    /// Book of YES `(price, size)` levels on each side
+    pub(crate) fn book(mut self, bids: &[(&str, i64)], asks: &[(&str, i64)]) -> Self {
+        let levels = |side: &[(&str, i64)]| {
+            side.iter()
+                .map(|&(price, size)| Level {
+                    outcome_id: "yes".to_string(),
+                    price: Decimal::from_str_exact(price).unwrap(),
+                    size: Decimal::from(size),
+                })
+                .collect()
+        };
+        self.input.order_book = Some(OrderBookSnapshot {
+            market_id: self.input.market.id,
+            bids: levels(bids),
+            asks: levels(asks),
+            timestamp: Utc::now(),
+        });
+        self
+    }
+
+    /// One 500-share level at `bid` and at `ask`
+    pub(crate) fn quote(self, bid: &str, ask: &str) -> Self {
+        self.book(&[(bid, 500)], &[(ask, 500)])
+    }
+
+    pub(crate) fn history(mut self, price_history: Vec<PriceSnapshot>) -> Self {
+        self.input.price_history = price_history;
+        self
+    }
+
+    pub(crate) fn build(self) -> SignalInput {
+        self.input
+    }
+}
@@ -1,15 +1,97 @@
 // Signal Validators
 // Validates signals before they are sent to execution
 
-use super::signals::TradeSignal;
+use super::signals::{Level, OrderBookSnapshot, SignalDirection, SignalInput, TradeSignal};
 use anyhow::Result;
+use chrono::{DateTime, Utc};
+use common::MarketId;
 use rust_decimal::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
 use tracing::debug;
 
+/// Cap on a graded validation score, so one dimension can only make up so
+/// much for the others under weighted scoring
+pub const MAX_VALIDATION_SCORE: f64 = 3.0;
+
+/// Graded outcome of a validator
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationResult {
+    pub passed: bool,
+    /// Value over the validator's threshold: 1.0 just passes, 0.0 is
+    /// nothing, capped at `MAX_VALIDATION_SCORE`
+    pub score: f64,
+    pub reason: String,
+    /// Results a composite combined; empty for single validators
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub breakdown: Vec<ValidatorScore>,
+}
+
+impl ValidationResult {
+    fn new(passed: bool, score: f64, reason: String) -> Self {
+        Self {
+            passed,
+            score,
+            reason,
+            breakdown: Vec::new(),
+        }
+    }
+
+    /// (validator, reason) for each validator in the breakdown that
+    /// rejected the signal
+    pub fn rejections(&self) -> Vec<(&'static str, &str)> {
+        self.breakdown
+            .iter()
+            .filter(|b| !b.passed)
+            .map(|b| (b.validator, b.reason.as_str()))
+            .collect()
+    }
+}
+
+/// One validator's part in a composite result
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidatorScore {
+    pub validator: &'static str,
+    pub weight: f64,
+    pub passed: bool,
+    pub score: f64,
+    pub reason: String,
+}
+
+/// `value / threshold`, bounded to 0.0..=MAX_VALIDATION_SCORE
+fn threshold_score(value: f64, threshold: f64) -> f64 {
+    if threshold <= 0.0 {
+        return if value >= threshold { MAX_VALIDATION_SCORE } else { 0.0 };
+    }
+    (value / threshold).clamp(0.0, MAX_VALIDATION_SCORE)
+}
+
 /// Trait for signal validators
 #[async_trait::async_trait]
 pub trait SignalValidator: Send + Sync {
     async fn validate(&self, signal: &TradeSignal) -> Result<bool>;
+
+    /// Graded version of `validate`; by default a pass scores 1.0 and a
+    /// rejection 0.0
+    async fn evaluate(&self, signal: &TradeSignal) -> Result<ValidationResult> {
+        let passed = self.validate(signal).await?;
+        let reason = if passed { "passed" } else { "rejected" };
+        Ok(ValidationResult::new(passed, if passed { 1.0 } else { 0.0 }, reason.to_string()))
+    }
+
+    /// Name reported in pipeline diagnostics when this validator rejects
+    fn name(&self) -> &'static str {
+        "custom"
+    }
+
+    /// The value this validator judges a signal on, for diagnostics
+    fn score(&self, _signal: &TradeSignal) -> Option<f64> {
+        None
+    }
+
+    /// Called by the pipeline with every input before the signals generated
+    /// from it are validated, for validators that check market data
+    fn observe_input(&mut self, _input: &SignalInput) {}
 }
 
 /// Configuration for edge threshold validator
@@ -52,6 +134,24 @@ impl SignalValidator for EdgeThresholdValidator {
         );
         Ok(passes)
     }
+
+    async fn evaluate(&self, signal: &TradeSignal) -> Result<ValidationResult> {
+        let edge = signal.edge.to_f64().unwrap_or(0.0);
+        let min_edge = self.config.min_edge.to_f64().unwrap_or(0.0);
+        Ok(ValidationResult::new(
+            self.validate(signal).await?,
+            threshold_score(edge, min_edge),
+            format!("edge {:.4} vs {:.4}", edge, min_edge),
+        ))
+    }
+
+    fn name(&self) -> &'static str {
+        "edge_threshold"
+    }
+
+    fn score(&self, signal: &TradeSignal) -> Option<f64> {
+        signal.edge.to_f64()
+    }
 }
 
 /// Configuration for confidence validator
@@ -94,6 +194,22 @@ impl SignalValidator for ConfidenceValidator {
         );
         Ok(passes)
     }
+
+    async fn evaluate(&self, signal: &TradeSignal) -> Result<ValidationResult> {
+        Ok(ValidationResult::new(
+            self.validate(signal).await?,
+            threshold_score(signal.confidence, self.config.min_confidence),
+            format!("confidence {:.2} vs {:.2}", signal.confidence, self.config.min_confidence),
+        ))
+    }
+
+    fn name(&self) -> &'static str {
+        "confidence"
+    }
+
+    fn score(&self, signal: &TradeSignal) -> Option<f64> {
+        Some(signal.confidence)
+    }
 }
 
 /// Configuration for liquidity validator
@@ -103,6 +219,9 @@ pub struct LiquidityValidatorConfig {
     pub min_liquidity_score: f64,
     /// Minimum position size relative to liquidity (e.g., 0.1 = max 10% of liquidity)
     pub max_position_liquidity_ratio: f64,
+    /// Largest gap between the average price of filling the whole position
+    /// against the book and the best price, in bps
+    pub max_slippage_bps: f64,
 }
 
 impl Default for LiquidityValidatorConfig {
@@ -110,23 +229,80 @@ impl Default for LiquidityValidatorConfig {
         Self {
             min_liquidity_score: 0.3,
             max_position_liquidity_ratio: 0.1,
+            max_slippage_bps: 100.0,
         }
     }
 }
 
 /// Validates that there is sufficient liquidity for the position
+///
+/// Besides the liquidity score, a signal whose market's latest book was
+/// observed must be fillable against it within `max_slippage_bps`: a Long
+/// walks the asks and a Short the bids for its whole `position_size`, and a
+/// book too thin to fill it fails. Resting quotes and their cancels take no
+/// liquidity and skip the depth check, as do markets with no book seen.
 pub struct LiquidityValidator {
     config: LiquidityValidatorConfig,
+    books: HashMap<MarketId, OrderBookSnapshot>,
 }
 
 impl LiquidityValidator {
     pub fn new(config: LiquidityValidatorConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            books: HashMap::new(),
+        }
     }
 
     pub fn default() -> Self {
         Self::new(LiquidityValidatorConfig::default())
     }
+
+    /// Position size relative to liquidity
+    fn position_ratio(&self, signal: &TradeSignal) -> f64 {
+        signal.position_size.to_f64().unwrap_or(0.0)
+            / (signal.metadata.liquidity_score * 10000.0).max(1.0) // Normalize to rough dollar value
+    }
+
+    /// Slippage in bps of filling the signal against its market's book;
+    /// None if there is nothing to check, infinite if the book is too thin
+    fn slippage_bps(&self, signal: &TradeSignal) -> Option<f64> {
+        if signal.quote_id().is_some() || signal.position_size <= Decimal::ZERO {
+            return None;
+        }
+        let book = self.books.get(&signal.market_id)?;
+        let mut levels: Vec<&Level> = match signal.direction {
+            SignalDirection::Long => book.asks.iter().collect(),
+            SignalDirection::Short => book.bids.iter().collect(),
+            SignalDirection::Neutral => return None,
+        };
+        match signal.direction {
+            SignalDirection::Long => levels.sort_by_key(|l| l.price),
+            _ => levels.sort_by_key(|l| std::cmp::Reverse(l.price)),
+        }
+
+        let best = levels.first().map(|l| l.price).filter(|price| *price > Decimal::ZERO);
+        let (Some(best), Some(fill)) = (best, fill_price(&levels, signal.position_size)) else {
+            return Some(f64::INFINITY);
+        };
+        ((fill - best).abs() / best * Decimal::from(10_000)).to_f64()
+    }
+}
+
+/// Average price of taking `size` from `levels`, best first; None if they
+/// hold less than that
+fn fill_price(levels: &[&Level], size: Decimal) -> Option<Decimal> {
+    let mut remaining = size;
+    let mut cost = Decimal::ZERO;
+    for level in levels {
+        let taken = remaining.min(level.size);
+        cost += taken * level.price;
+        remaining -= taken;
+        if remaining <= Decimal::ZERO {
+            return Some(cost / size);
+        }
+    }
+    None
 }
 
 #[async_trait::async_trait]
@@ -134,38 +310,98 @@ impl SignalValidator for LiquidityValidator {
     async fn validate(&self, signal: &TradeSignal) -> Result<bool> {
         let liquidity_passes = signal.metadata.liquidity_score >= self.config.min_liquidity_score;
 
-        // Calculate position size relative to liquidity
-        let position_ratio = signal.position_size.to_f64().unwrap_or(0.0)
-            / (signal.metadata.liquidity_score * 10000.0).max(1.0); // Normalize to rough dollar value
+        let position_ratio = self.position_ratio(signal);
         let size_passes = position_ratio <= self.config.max_position_liquidity_ratio;
 
-        let passes = liquidity_passes && size_passes;
+        let slippage_bps = self.slippage_bps(signal);
+        let depth_passes = slippage_bps.is_none_or(|bps| bps <= self.config.max_slippage_bps);
+
+        let passes = liquidity_passes && size_passes && depth_passes;
 
         debug!(
-            "Liquidity validation: score={:.2}, ratio={:.2}? {}",
-            signal.metadata.liquidity_score, position_ratio, passes
+            "Liquidity validation: score={:.2}, ratio={:.2}, slippage={:?}bps? {}",
+            signal.metadata.liquidity_score, position_ratio, slippage_bps, passes
         );
 
         Ok(passes)
     }
+
+    /// The weaker of the liquidity score and the position size against their limits
+    async fn evaluate(&self, signal: &TradeSignal) -> Result<ValidationResult> {
+        let position_ratio = self.position_ratio(signal);
+        let size_score = if position_ratio > 0.0 {
+            threshold_score(self.config.max_position_liquidity_ratio, position_ratio)
+        } else {
+            MAX_VALIDATION_SCORE
+        };
+        let mut score = threshold_score(signal.metadata.liquidity_score, self.config.min_liquidity_score).min(size_score);
+        let mut reason = format!(
+            "liquidity score {:.2} vs {:.2}, position ratio {:.2} vs {:.2}",
+            signal.metadata.liquidity_score,
+            self.config.min_liquidity_score,
+            position_ratio,
+            self.config.max_position_liquidity_ratio
+        );
+        if let Some(slippage_bps) = self.slippage_bps(signal) {
+            let depth_score = if slippage_bps > 0.0 {
+                threshold_score(self.config.max_slippage_bps, slippage_bps)
+            } else {
+                MAX_VALIDATION_SCORE
+            };
+            score = score.min(depth_score);
+            reason.push_str(&format!(
+                ", slippage {:.1} bps vs {:.1}",
+                slippage_bps, self.config.max_slippage_bps
+            ));
+        }
+        Ok(ValidationResult::new(self.validate(signal).await?, score, reason))
+    }
+
+    fn name(&self) -> &'static str {
+        "liquidity"
+    }
+
+    fn score(&self, signal: &TradeSignal) -> Option<f64> {
+        Some(signal.metadata.liquidity_score)
+    }
+
+    /// Keep the market's latest book to walk
+    fn observe_input(&mut self, input: &SignalInput) {
+        if let Some(book) = &input.order_book {
+            self.books.insert(input.market.id, book.clone());
+        }
+    }
 }
 
 /// Configuration for expected value validator
 #[derive(Debug, Clone)]
 pub struct ExpectedValueValidatorConfig {
-    /// Minimum expected value (in dollars)
+    /// Minimum expected value (in dollars), after fees
     pub min_expected_value: Decimal,
+    /// Fee on the position size, in bps, for costs the generator did not
+    /// already net out of EV
+    pub fee_bps: Decimal,
+    /// Minimum fee-adjusted EV per hour between `created_at` and
+    /// `expires_at`, so capital tied up for a day must earn more than a
+    /// quick trade (0 disables the check)
+    pub min_ev_per_hour: Decimal,
+    /// Exposure assumed for signals without an expiry, in seconds
+    pub default_horizon_secs: i64,
 }
 
 impl Default for ExpectedValueValidatorConfig {
     fn default() -> Self {
         Self {
             min_expected_value: Decimal::from_str_exact("5.0").unwrap(), // $5
+            fee_bps: Decimal::ZERO,
+            min_ev_per_hour: Decimal::ZERO,
+            default_horizon_secs: 24 * 3600,
         }
     }
 }
 
-/// Validates that the signal has positive expected value above threshold
+/// Validates that the signal has positive expected value above threshold,
+/// net of fees and, optionally, per hour of exposure
 pub struct ExpectedValueValidator {
     config: ExpectedValueValidatorConfig,
 }
@@ -178,30 +414,202 @@ impl ExpectedValueValidator {
     pub fn default() -> Self {
         Self::new(ExpectedValueValidatorConfig::default())
     }
+
+    /// EV less the fee on the position
+    fn fee_adjusted_ev(&self, signal: &TradeSignal) -> Decimal {
+        signal.expected_value - signal.position_size.abs() * self.config.fee_bps / Decimal::from(10_000)
+    }
+
+    /// Hours from creation to expiry, or the default horizon; None if the
+    /// signal expires before it is created
+    fn exposure_hours(&self, signal: &TradeSignal) -> Option<f64> {
+        let secs = match signal.expires_at {
+            Some(expires_at) => (expires_at - signal.created_at).num_seconds(),
+            None => self.config.default_horizon_secs,
+        };
+        (secs > 0).then(|| secs as f64 / 3600.0)
+    }
+
+    /// Fee-adjusted EV per hour of exposure, 0 without any exposure
+    fn ev_per_hour(&self, signal: &TradeSignal) -> f64 {
+        let expected_value = self.fee_adjusted_ev(signal).to_f64().unwrap_or(0.0);
+        self.exposure_hours(signal).map_or(0.0, |hours| expected_value / hours)
+    }
+
+    fn checks_rate(&self) -> bool {
+        self.config.min_ev_per_hour > Decimal::ZERO
+    }
 }
 
 #[async_trait::async_trait]
 impl SignalValidator for ExpectedValueValidator {
     async fn validate(&self, signal: &TradeSignal) -> Result<bool> {
-        let passes = signal.expected_value >= self.config.min_expected_value
-            && signal.expected_value > Decimal::ZERO;
+        let expected_value = self.fee_adjusted_ev(signal);
+        let min_ev_per_hour = self.config.min_ev_per_hour.to_f64().unwrap_or(0.0);
+        let passes = expected_value >= self.config.min_expected_value
+            && expected_value > Decimal::ZERO
+            && (!self.checks_rate() || self.ev_per_hour(signal) >= min_ev_per_hour);
         debug!(
-            "Expected value validation: {} >= {}? {}",
-            signal.expected_value, self.config.min_expected_value, passes
+            "Expected value validation: {} (after fees) >= {}, {:.2}/h >= {}? {}",
+            expected_value,
+            self.config.min_expected_value,
+            self.ev_per_hour(signal),
+            self.config.min_ev_per_hour,
+            passes
         );
         Ok(passes)
     }
+
+    /// Scored on the weaker of total EV and, when checked, EV per hour
+    async fn evaluate(&self, signal: &TradeSignal) -> Result<ValidationResult> {
+        let expected_value = self.fee_adjusted_ev(signal).to_f64().unwrap_or(0.0);
+        let min_expected_value = self.config.min_expected_value.to_f64().unwrap_or(0.0);
+        let mut score = if expected_value > 0.0 {
+            threshold_score(expected_value, min_expected_value)
+        } else {
+            0.0
+        };
+        let mut reason = format!("expected value {:.2} vs {:.2}", expected_value, min_expected_value);
+        if self.checks_rate() {
+            let min_ev_per_hour = self.config.min_ev_per_hour.to_f64().unwrap_or(0.0);
+            let ev_per_hour = self.ev_per_hour(signal);
+            score = score.min(threshold_score(ev_per_hour.max(0.0), min_ev_per_hour));
+            reason.push_str(&format!(", {:.2}/h vs {:.2}/h", ev_per_hour, min_ev_per_hour));
+        }
+        Ok(ValidationResult::new(self.validate(signal).await?, score, reason))
+    }
+
+    fn name(&self) -> &'static str {
+        "expected_value"
+    }
+
+    fn score(&self, signal: &TradeSignal) -> Option<f64> {
+        self.fee_adjusted_ev(signal).to_f64()
+    }
 }
 
-/// Combines multiple validators with AND logic
+/// Configuration for staleness validator
+#[derive(Debug, Clone)]
+pub struct StalenessValidatorConfig {
+    /// Oldest order book a signal may rest on, in milliseconds
+    pub max_orderbook_age_ms: i64,
+    /// Oldest last price a signal may rest on, in milliseconds
+    pub max_price_age_ms: i64,
+    /// Oldest research a signal may rest on, in milliseconds
+    pub max_research_age_ms: i64,
+}
+
+impl Default for StalenessValidatorConfig {
+    fn default() -> Self {
+        Self {
+            max_orderbook_age_ms: 30_000,
+            max_price_age_ms: 120_000,
+            max_research_age_ms: 30 * 60_000, // Research aggregator TTL
+        }
+    }
+}
+
+/// Rejects signals built on old market data or research
+///
+/// Ages are those recorded in the signal's metadata plus the time since it
+/// was created; inputs with no recorded age are not judged.
+pub struct StalenessValidator {
+    config: StalenessValidatorConfig,
+}
+
+impl StalenessValidator {
+    pub fn new(config: StalenessValidatorConfig) -> Self {
+        Self { config }
+    }
+
+    /// (input, current age, limit) for each input with a recorded age
+    fn ages(&self, signal: &TradeSignal, now: DateTime<Utc>) -> Vec<(&'static str, i64, i64)> {
+        let since_created = (now - signal.created_at).num_milliseconds().max(0);
+        let metadata = &signal.metadata;
+        [
+            ("orderbook", metadata.orderbook_age_ms, self.config.max_orderbook_age_ms),
+            ("price", metadata.price_age_ms, self.config.max_price_age_ms),
+            ("research", metadata.research_age_ms, self.config.max_research_age_ms),
+        ]
+        .into_iter()
+        .filter_map(|(input, age, limit)| Some((input, age? + since_created, limit)))
+        .collect()
+    }
+}
+
+impl Default for StalenessValidator {
+    fn default() -> Self {
+        Self::new(StalenessValidatorConfig::default())
+    }
+}
+
+#[async_trait::async_trait]
+impl SignalValidator for StalenessValidator {
+    async fn validate(&self, signal: &TradeSignal) -> Result<bool> {
+        let ages = self.ages(signal, Utc::now());
+        let passes = ages.iter().all(|(_, age, limit)| age <= limit);
+        debug!("Staleness validation: {:?}? {}", ages, passes);
+        Ok(passes)
+    }
+
+    /// The stalest input against its limit
+    async fn evaluate(&self, signal: &TradeSignal) -> Result<ValidationResult> {
+        let ages = self.ages(signal, Utc::now());
+        let passed = ages.iter().all(|(_, age, limit)| age <= limit);
+        let score = ages
+            .iter()
+            .map(|&(_, age, limit)| if age > 0 { threshold_score(limit as f64, age as f64) } else { MAX_VALIDATION_SCORE })
+            .fold(MAX_VALIDATION_SCORE, f64::min);
+        let reason = if ages.is_empty() {
+            "no input ages recorded".to_string()
+        } else {
+            ages.iter()
+                .map(|(input, age, limit)| format!("{} {}ms vs {}ms", input, age, limit))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        Ok(ValidationResult::new(passed, score, reason))
+    }
+
+    fn name(&self) -> &'static str {
+        "staleness"
+    }
+
+    /// Age of the stalest input as a fraction of its limit
+    fn score(&self, signal: &TradeSignal) -> Option<f64> {
+        self.ages(signal, Utc::now())
+            .iter()
+            .map(|&(_, age, limit)| age as f64 / limit.max(1) as f64)
+            .reduce(f64::max)
+    }
+}
+
+/// How a composite turns its validators' results into a verdict
+#[derive(Debug, Clone, Default)]
+pub enum ScoringPolicy {
+    /// Every validator must pass
+    #[default]
+    AllMustPass,
+    /// At least one validator must pass
+    AnyPass,
+    /// The weighted mean of the validators' scores must reach `min_score`;
+    /// validators are weighted by name, 1.0 if not listed
+    WeightedThreshold { weights: HashMap<String, f64>, min_score: f64 },
+}
+
+/// Combines multiple validators, by default with AND logic
 pub struct CompositeValidator {
     validators: Vec<Box<dyn SignalValidator + Send + Sync>>,
+    policy: ScoringPolicy,
+    short_circuit: bool,
 }
 
 impl CompositeValidator {
     pub fn new() -> Self {
         Self {
             validators: Vec::new(),
+            policy: ScoringPolicy::default(),
+            short_circuit: false,
         }
     }
 
@@ -209,6 +617,45 @@ impl CompositeValidator {
         self.validators.push(validator);
         self
     }
+
+    /// Every built-in validator at its default configuration
+    pub fn standard() -> Self {
+        Self::new()
+            .add_validator(Box::new(EdgeThresholdValidator::default()))
+            .add_validator(Box::new(ConfidenceValidator::default()))
+            .add_validator(Box::new(LiquidityValidator::default()))
+            .add_validator(Box::new(ExpectedValueValidator::default()))
+            .add_validator(Box::new(StalenessValidator::default()))
+    }
+
+    pub fn with_policy(mut self, policy: ScoringPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Stop evaluating once the verdict is known: at the first rejection
+    /// under `AllMustPass`, the first pass under `AnyPass`. The breakdown
+    /// then ends there. Weighted scoring always runs every validator.
+    pub fn with_short_circuit(mut self, short_circuit: bool) -> Self {
+        self.short_circuit = short_circuit;
+        self
+    }
+
+    /// Whether a validator's result settles the verdict under the policy
+    fn decisive(&self, passed: bool) -> bool {
+        match self.policy {
+            ScoringPolicy::AllMustPass => !passed,
+            ScoringPolicy::AnyPass => passed,
+            ScoringPolicy::WeightedThreshold { .. } => false,
+        }
+    }
+
+    fn weight(&self, validator: &str) -> f64 {
+        match &self.policy {
+            ScoringPolicy::AllMustPass | ScoringPolicy::AnyPass => 1.0,
+            ScoringPolicy::WeightedThreshold { weights, .. } => weights.get(validator).copied().unwrap_or(1.0).max(0.0),
+        }
+    }
 }
 
 impl Default for CompositeValidator {
@@ -220,12 +667,87 @@ impl Default for CompositeValidator {
 #[async_trait::async_trait]
 impl SignalValidator for CompositeValidator {
     async fn validate(&self, signal: &TradeSignal) -> Result<bool> {
+        match self.policy {
+            ScoringPolicy::AllMustPass => {
+                for validator in &self.validators {
+                    if !validator.validate(signal).await? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            ScoringPolicy::AnyPass => {
+                for validator in &self.validators {
+                    if validator.validate(signal).await? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            ScoringPolicy::WeightedThreshold { .. } => Ok(self.evaluate(signal).await?.passed),
+        }
+    }
+
+    /// Runs the validators and reports each one's part in the verdict
+    async fn evaluate(&self, signal: &TradeSignal) -> Result<ValidationResult> {
+        let mut breakdown = Vec::with_capacity(self.validators.len());
         for validator in &self.validators {
-            if !validator.validate(signal).await? {
-                return Ok(false);
+            let result = validator.evaluate(signal).await?;
+            let decisive = self.short_circuit && self.decisive(result.passed);
+            breakdown.push(ValidatorScore {
+                validator: validator.name(),
+                weight: self.weight(validator.name()),
+                passed: result.passed,
+                score: result.score,
+                reason: result.reason,
+            });
+            if decisive {
+                break;
+            }
+        }
+
+        let total_weight: f64 = breakdown.iter().map(|b| b.weight).sum();
+        let score = if total_weight > 0.0 {
+            breakdown.iter().map(|b| b.weight * b.score).sum::<f64>() / total_weight
+        } else {
+            0.0
+        };
+
+        let (passed, reason) = match &self.policy {
+            ScoringPolicy::AllMustPass => {
+                let failed: Vec<&str> = breakdown.iter().filter(|b| !b.passed).map(|b| b.validator).collect();
+                if failed.is_empty() {
+                    (true, "all validators passed".to_string())
+                } else {
+                    (false, format!("rejected by {}", failed.join(", ")))
+                }
             }
+            ScoringPolicy::AnyPass => match breakdown.iter().find(|b| b.passed) {
+                Some(b) => (true, format!("passed by {}", b.validator)),
+                None => (false, "no validator passed".to_string()),
+            },
+            ScoringPolicy::WeightedThreshold { min_score, .. } => {
+                (score >= *min_score, format!("weighted score {:.2} vs {:.2}", score, min_score))
+            }
+        };
+
+        debug!("Composite validation: {}", reason);
+        Ok(ValidationResult {
+            passed,
+            score,
+            reason,
+            breakdown,
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "composite"
+    }
+
+    fn observe_input(&mut self, input: &SignalInput) {
+        for validator in &mut self.validators {
+            validator.observe_input(input);
         }
-        Ok(true)
     }
 }
 
@@ -236,14 +758,15 @@ mod tests {
 
     fn create_test_signal(edge: f64, confidence: f64, liquidity_score: f64) -> TradeSignal {
         use super::super::signals::{SignalMetadata, SignalType, SignalDirection};
+        use common::MarketId;
         use uuid::Uuid;
 
         TradeSignal {
             id: Uuid::new_v4(),
-            market_id: Uuid::new_v4(),
+            market_id: MarketId::random(),
             signal_type: SignalType::SpreadArbitrage,
             direction: SignalDirection::Long,
-            outcome_id: Some("test".to_string()),
+            outcome_id: Some("test".into()),
             entry_price: Decimal::from_f64(0.5).unwrap(),
             target_price: Decimal::from_f64(0.6).unwrap(),
             stop_loss: Decimal::from_f64(0.4).unwrap(),
@@ -258,10 +781,14 @@ mod tests {
                 data_points: 10,
                 liquidity_score,
                 volatility_score: 0.5,
+                price_age_ms: None,
+                orderbook_age_ms: None,
+                research_age_ms: None,
                 custom_fields: serde_json::json!({}),
             },
             created_at: Utc::now(),
             expires_at: None,
+            variant: None,
         }
     }
 
@@ -297,4 +824,193 @@ mod tests {
         let bad_signal = create_test_signal(0.06, 0.8, 0.2);
         assert!(!validator.validate(&bad_signal).await.unwrap());
     }
+
+    fn book_input(market_id: MarketId, asks: &[(&str, i64)]) -> SignalInput {
+        crate::test_support::input(market_id).book(&[], asks).build()
+    }
+
+    #[tokio::test]
+    async fn test_liquidity_validator_walks_book_depth() {
+        let mut validator = LiquidityValidator::default();
+        let signal = create_test_signal(0.06, 0.8, 0.8);
+
+        // Unchecked until a book for the market has been seen
+        assert!(validator.validate(&signal).await.unwrap());
+
+        // 100 shares fill at the touch of a deep book
+        validator.observe_input(&book_input(signal.market_id, &[("0.50", 500), ("0.51", 500)]));
+        assert!(validator.validate(&signal).await.unwrap());
+
+        // The same size walks a shallow book to an average of 0.531, 620 bps through the touch
+        validator.observe_input(&book_input(
+            signal.market_id,
+            &[("0.55", 50), ("0.50", 20), ("0.52", 30)],
+        ));
+        assert!(!validator.validate(&signal).await.unwrap());
+        let result = validator.evaluate(&signal).await.unwrap();
+        assert!(result.reason.contains("slippage 620.0 bps"), "{}", result.reason);
+
+        // And a book holding less than the position can't fill it at all
+        validator.observe_input(&book_input(signal.market_id, &[("0.50", 60)]));
+        assert!(!validator.validate(&signal).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_staleness_validator() {
+        let validator = StalenessValidator::default();
+        let aged = |orderbook: i64, research: i64| {
+            let mut signal = create_test_signal(0.06, 0.8, 0.5);
+            signal.metadata.orderbook_age_ms = Some(orderbook);
+            signal.metadata.price_age_ms = Some(5_000);
+            signal.metadata.research_age_ms = Some(research);
+            signal
+        };
+
+        assert!(validator.validate(&aged(2_000, 60_000)).await.unwrap());
+
+        // A 30 minute old book fails on its own limit, with a low score
+        let stale_book = aged(30 * 60_000, 60_000);
+        assert!(!validator.validate(&stale_book).await.unwrap());
+        let result = validator.evaluate(&stale_book).await.unwrap();
+        assert!(result.score < 0.02);
+        assert!(result.reason.starts_with("orderbook 18000"));
+
+        assert!(!validator.validate(&aged(2_000, 2 * 60 * 60_000)).await.unwrap());
+
+        // Time since creation counts towards the age
+        let mut delayed = aged(25_000, 60_000);
+        assert!(validator.validate(&delayed).await.unwrap());
+        delayed.created_at = Utc::now() - chrono::Duration::seconds(10);
+        assert!(!validator.validate(&delayed).await.unwrap());
+
+        // Nothing recorded, nothing to judge
+        assert!(validator.validate(&create_test_signal(0.06, 0.8, 0.5)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_expected_value_per_hour_of_exposure() {
+        let validator = ExpectedValueValidator::new(ExpectedValueValidatorConfig {
+            fee_bps: Decimal::from(20), // $0.20 on the $100 position
+            min_ev_per_hour: Decimal::ONE,
+            ..ExpectedValueValidatorConfig::default()
+        });
+        let signal = |expected_value: f64, expires_in_mins: Option<i64>| {
+            let mut signal = create_test_signal(0.06, 0.8, 0.5);
+            signal.expected_value = Decimal::from_f64(expected_value).unwrap();
+            signal.expires_at = expires_in_mins.map(|mins| signal.created_at + chrono::Duration::minutes(mins));
+            signal
+        };
+
+        // $9.80 after fees over 10 minutes
+        let quick = signal(10.0, Some(10));
+        assert!(validator.validate(&quick).await.unwrap());
+        assert!(validator.evaluate(&quick).await.unwrap().reason.ends_with("58.80/h vs 1.00/h"));
+
+        // $5.80 after fees held for a day: enough EV, too little per hour
+        let marginal = signal(6.0, Some(24 * 60));
+        assert!(!validator.validate(&marginal).await.unwrap());
+        let result = validator.evaluate(&marginal).await.unwrap();
+        assert!((result.score - 5.8 / 24.0).abs() < 1e-9);
+
+        // No expiry: the default day, so the same verdict
+        assert!(!validator.validate(&signal(6.0, None)).await.unwrap());
+        assert!(validator.validate(&signal(30.0, None)).await.unwrap());
+
+        // The fee alone takes $5.10 below the $5 minimum
+        assert!(!validator.validate(&signal(5.1, Some(10))).await.unwrap());
+        assert_eq!(validator.score(&signal(5.1, Some(10))), Some(4.9));
+    }
+
+    #[tokio::test]
+    async fn test_composite_lists_rejections() {
+        // Enough edge, too little confidence and liquidity
+        let signal = create_test_signal(0.06, 0.6, 0.2);
+        let composite = |policy: ScoringPolicy| {
+            CompositeValidator::new()
+                .add_validator(Box::new(ConfidenceValidator::default()))
+                .add_validator(Box::new(EdgeThresholdValidator::default()))
+                .add_validator(Box::new(LiquidityValidator::default()))
+                .with_policy(policy)
+        };
+
+        let all = composite(ScoringPolicy::AllMustPass);
+        let result = all.evaluate(&signal).await.unwrap();
+        assert!(!result.passed);
+        assert_eq!(result.reason, "rejected by confidence, liquidity");
+        let rejected: Vec<&str> = result.rejections().iter().map(|(name, _)| *name).collect();
+        assert_eq!(rejected, ["confidence", "liquidity"]);
+        assert!(result.rejections()[0].1.starts_with("confidence 0.60"));
+
+        // Stops at the first rejection
+        let result = composite(ScoringPolicy::AllMustPass)
+            .with_short_circuit(true)
+            .evaluate(&signal)
+            .await
+            .unwrap();
+        assert!(!result.passed);
+        assert_eq!(result.breakdown.len(), 1);
+        assert_eq!(result.rejections().len(), 1);
+
+        let any = composite(ScoringPolicy::AnyPass);
+        assert!(any.validate(&signal).await.unwrap());
+        let result = any.evaluate(&signal).await.unwrap();
+        assert!(result.passed);
+        assert_eq!(result.reason, "passed by edge_threshold");
+        assert_eq!(result.rejections().len(), 2);
+
+        // Stops at the first pass
+        let result = composite(ScoringPolicy::AnyPass)
+            .with_short_circuit(true)
+            .evaluate(&signal)
+            .await
+            .unwrap();
+        assert_eq!(result.breakdown.len(), 2);
+
+        let nothing = create_test_signal(0.01, 0.6, 0.2);
+        assert!(!any.validate(&nothing).await.unwrap());
+        assert_eq!(any.evaluate(&nothing).await.unwrap().reason, "no validator passed");
+
+        // Weighted scoring sees every validator even when short-circuiting
+        let weighted = composite(ScoringPolicy::WeightedThreshold {
+            weights: HashMap::new(),
+            min_score: 1.0,
+        })
+        .with_short_circuit(true);
+        let result = weighted.evaluate(&signal).await.unwrap();
+        assert_eq!(result.breakdown.len(), 3);
+        assert_eq!(result.rejections().len(), 2);
+        assert!(!result.passed);
+    }
+
+    #[tokio::test]
+    async fn test_weighted_policy_lets_edge_outweigh_liquidity() {
+        // Ten times the required edge, but below the liquidity minimum
+        let signal = create_test_signal(0.5, 0.8, 0.2);
+        let composite = || {
+            CompositeValidator::new()
+                .add_validator(Box::new(EdgeThresholdValidator::default()))
+                .add_validator(Box::new(LiquidityValidator::default()))
+        };
+
+        let all_must_pass = composite();
+        assert!(!all_must_pass.validate(&signal).await.unwrap());
+        let result = all_must_pass.evaluate(&signal).await.unwrap();
+        assert_eq!(result.reason, "rejected by liquidity");
+
+        let weighted = composite().with_policy(ScoringPolicy::WeightedThreshold {
+            weights: HashMap::from([("edge_threshold".to_string(), 0.7), ("liquidity".to_string(), 0.3)]),
+            min_score: 1.0,
+        });
+        assert!(weighted.validate(&signal).await.unwrap());
+        let result = weighted.evaluate(&signal).await.unwrap();
+        assert!(result.passed);
+        // Edge is capped at 3.0; liquidity 0.2 of a required 0.3
+        assert!((result.score - (0.7 * 3.0 + 0.3 * 0.2 / 0.3)).abs() < 1e-9);
+        assert_eq!(result.breakdown.len(), 2);
+        assert!(!result.breakdown[1].passed);
+
+        // A thin edge can't carry weak liquidity
+        let thin = create_test_signal(0.04, 0.8, 0.2);
+        assert!(!weighted.validate(&thin).await.unwrap());
+    }
 }
@@ -0,0 +1,216 @@
+// Volatility Estimator
+// EWMA realized volatility per market, scored 0-1 for the generators
+
+use chrono::{DateTime, Utc};
+use common::MarketId;
+use rust_decimal::prelude::ToPrimitive;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+
+use crate::signals::SignalInput;
+
+/// How a market's sigma is turned into a 0-1 score
+#[derive(Debug, Clone, PartialEq)]
+pub enum VolatilityCalibration {
+    /// sigma / `full_scale`, capped at 1
+    FixedScale { full_scale: f64 },
+    /// Share of the market's last `window` sigma estimates below the current
+    /// one, so a steady market scores 0 however noisy it is
+    RollingPercentile { window: usize },
+}
+
+/// Volatility estimator configuration
+#[derive(Debug, Clone)]
+pub struct VolatilityEstimatorConfig {
+    /// Price changes after which an observation's weight has halved
+    pub half_life: f64,
+    pub calibration: VolatilityCalibration,
+    /// Price changes seen before a market gets a score
+    pub min_observations: usize,
+}
+
+impl Default for VolatilityEstimatorConfig {
+    fn default() -> Self {
+        Self {
+            half_life: 10.0,
+            calibration: VolatilityCalibration::FixedScale { full_scale: 0.02 }, // 2 cents per change
+            min_observations: 5,
+        }
+    }
+}
+
+/// A market's current volatility
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct VolatilityScore {
+    /// Calibrated score, 0.0 to 1.0
+    pub score: f64,
+    /// EWMA standard deviation of price changes, in price units
+    pub raw_sigma: f64,
+    pub observations: usize,
+}
+
+#[derive(Debug, Default)]
+struct MarketVolatility {
+    /// Last price and time seen per outcome
+    last: HashMap<String, (f64, DateTime<Utc>)>,
+    variance: f64,
+    observations: usize,
+    /// Recent sigma estimates, oldest first, for percentile calibration
+    sigmas: VecDeque<f64>,
+}
+
+/// Realized volatility per market from its price stream.
+///
+/// Each price change of an outcome, in price units, updates an exponentially
+/// weighted mean of squared changes whose weights halve every `half_life`
+/// changes; its square root is the market's sigma. Prices at or before the
+/// last one seen for an outcome are ignored, so the same history can be fed
+/// every cycle.
+#[derive(Debug)]
+pub struct VolatilityEstimator {
+    config: VolatilityEstimatorConfig,
+    markets: HashMap<MarketId, MarketVolatility>,
+}
+
+impl VolatilityEstimator {
+    pub fn new(config: VolatilityEstimatorConfig) -> Self {
+        Self {
+            config,
+            markets: HashMap::new(),
+        }
+    }
+
+    /// Record a price of one of the market's outcomes
+    pub fn observe(&mut self, market_id: MarketId, outcome_id: &str, price: f64, at: DateTime<Utc>) {
+        let alpha = 1.0 - 0.5f64.powf(1.0 / self.config.half_life.max(f64::EPSILON));
+        let window = match self.config.calibration {
+            VolatilityCalibration::RollingPercentile { window } => window,
+            VolatilityCalibration::FixedScale { .. } => 0,
+        };
+
+        let market = self.markets.entry(market_id).or_default();
+        let previous = market.last.get(outcome_id).copied();
+        if previous.is_some_and(|(_, last_at)| at <= last_at) {
+            return;
+        }
+        market.last.insert(outcome_id.to_string(), (price, at));
+        let Some((last_price, _)) = previous else {
+            return;
+        };
+
+        let change = price - last_price;
+        market.variance = if market.observations == 0 {
+            change * change
+        } else {
+            (1.0 - alpha) * market.variance + alpha * change * change
+        };
+        market.observations += 1;
+        if window > 0 {
+            market.sigmas.push_back(market.variance.sqrt());
+            while market.sigmas.len() > window {
+                market.sigmas.pop_front();
+            }
+        }
+    }
+
+    /// Record every snapshot in the input's price history and return the
+    /// market's score
+    pub fn observe_input(&mut self, input: &SignalInput) -> Option<VolatilityScore> {
+        let mut history: Vec<_> = input.price_history.iter().collect();
+        history.sort_by_key(|s| s.timestamp);
+        for snapshot in history {
+            let price = snapshot.price.to_f64().unwrap_or(0.0);
+            self.observe(input.market.id, &snapshot.outcome_id, price, snapshot.timestamp);
+        }
+        self.score(input.market.id)
+    }
+
+    /// The market's current score, once it has `min_observations` changes
+    pub fn score(&self, market_id: MarketId) -> Option<VolatilityScore> {
+        let market = self.markets.get(&market_id)?;
+        if market.observations < self.config.min_observations.max(1) {
+            return None;
+        }
+
+        let raw_sigma = market.variance.sqrt();
+        let score = match self.config.calibration {
+            VolatilityCalibration::FixedScale { full_scale } => raw_sigma / full_scale.max(f64::EPSILON),
+            VolatilityCalibration::RollingPercentile { .. } => {
+                let below = market.sigmas.iter().filter(|&&sigma| sigma < raw_sigma).count();
+                below as f64 / market.sigmas.len().max(1) as f64
+            }
+        };
+        Some(VolatilityScore {
+            score: score.clamp(0.0, 1.0),
+            raw_sigma,
+            observations: market.observations,
+        })
+    }
+
+    /// Every scored market, for monitoring
+    pub fn scores(&self) -> HashMap<MarketId, VolatilityScore> {
+        self.markets.keys().filter_map(|&id| Some((id, self.score(id)?))).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Calm series ticking 0.001 either way, then one swinging 0.03
+    fn calm_then_jumpy(estimator: &mut VolatilityEstimator, market_id: MarketId) -> (f64, f64) {
+        let start = DateTime::UNIX_EPOCH;
+        let at = |i: i64| start + chrono::Duration::minutes(i);
+        for i in 0..30 {
+            let price = if i % 2 == 0 { 0.500 } else { 0.501 };
+            estimator.observe(market_id, "yes", price, at(i));
+        }
+        let calm = estimator.score(market_id).unwrap().score;
+        for i in 30..40 {
+            let price = if i % 2 == 0 { 0.50 } else { 0.53 };
+            estimator.observe(market_id, "yes", price, at(i));
+        }
+        (calm, estimator.score(market_id).unwrap().score)
+    }
+
+    #[test]
+    fn test_score_rises_when_prices_jump() {
+        let market_id = MarketId::random();
+        let mut fixed = VolatilityEstimator::new(VolatilityEstimatorConfig::default());
+        let (calm, jumpy) = calm_then_jumpy(&mut fixed, market_id);
+        assert!((calm - 0.05).abs() < 1e-6, "calm {}", calm);
+        assert!(jumpy > 0.9, "jumpy {}", jumpy);
+        assert!(fixed.score(market_id).unwrap().raw_sigma > 0.02);
+
+        let mut percentile = VolatilityEstimator::new(VolatilityEstimatorConfig {
+            calibration: VolatilityCalibration::RollingPercentile { window: 50 },
+            ..VolatilityEstimatorConfig::default()
+        });
+        let (calm, jumpy) = calm_then_jumpy(&mut percentile, market_id);
+        assert_eq!(calm, 0.0);
+        assert!(jumpy > 0.7, "jumpy {}", jumpy);
+        assert_eq!(percentile.scores().len(), 1);
+    }
+
+    #[test]
+    fn test_replayed_history_is_counted_once() {
+        let market_id = MarketId::random();
+        let mut estimator = VolatilityEstimator::new(VolatilityEstimatorConfig {
+            min_observations: 1,
+            ..VolatilityEstimatorConfig::default()
+        });
+        let at = DateTime::UNIX_EPOCH;
+
+        estimator.observe(market_id, "yes", 0.50, at);
+        assert!(estimator.score(market_id).is_none());
+        estimator.observe(market_id, "yes", 0.51, at + chrono::Duration::minutes(1));
+        let once = estimator.score(market_id).unwrap();
+
+        // The same prices again, and a NO price seen for the first time
+        estimator.observe(market_id, "yes", 0.50, at);
+        estimator.observe(market_id, "yes", 0.51, at + chrono::Duration::minutes(1));
+        estimator.observe(market_id, "no", 0.49, at + chrono::Duration::minutes(1));
+        assert_eq!(estimator.score(market_id), Some(once));
+        assert!((once.raw_sigma - 0.01).abs() < 1e-9);
+    }
+}